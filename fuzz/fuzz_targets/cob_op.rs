@@ -0,0 +1,27 @@
+//! Decode collaborative object operations, as stored in change entries.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nonempty::NonEmpty;
+
+use radicle::cob::issue;
+use radicle::cob::op::Ops;
+use radicle::cob::patch;
+use radicle::crypto::PublicKey;
+use radicle::git;
+use radicle_cob::history::{Entry, EntryWithClock};
+
+fuzz_target!(|data: &[u8]| {
+    let oid = git::Oid::from(git::raw::Oid::zero());
+    let entry = EntryWithClock::root(Entry::new(
+        oid,
+        PublicKey::from([0; 32]),
+        oid,
+        [] as [git::Oid; 0],
+        NonEmpty::new(data.to_vec()),
+        0,
+    ));
+
+    let _ = Ops::<issue::Action>::try_from(&entry);
+    let _ = Ops::<patch::Action>::try_from(&entry);
+});
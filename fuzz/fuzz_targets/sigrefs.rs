@@ -0,0 +1,16 @@
+//! Parse signed refs, as fetched from peers under `refs/rad/sigrefs`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use radicle::storage::refs::Refs;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(refs) = Refs::from_canonical(data) {
+        // What we sign must parse back to the same refs.
+        let canonical = refs.canonical();
+        let parsed = Refs::from_canonical(&canonical).expect("canonical refs are valid");
+
+        assert_eq!(refs, parsed);
+    }
+});
@@ -0,0 +1,14 @@
+//! Decode gossip messages, as received from peers.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use radicle_node::service::message::Message;
+use radicle_node::wire;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(Message::Compressed(compressed)) = wire::deserialize::<Message>(data) {
+        // Compressed messages are decompressed and decoded as soon as they are received.
+        let _ = compressed.decompress();
+    }
+});
@@ -2,10 +2,16 @@
 pub mod rad_assign;
 #[path = "commands/auth.rs"]
 pub mod rad_auth;
+#[path = "commands/block.rs"]
+pub mod rad_block;
 #[path = "commands/checkout.rs"]
 pub mod rad_checkout;
+#[path = "commands/clean.rs"]
+pub mod rad_clean;
 #[path = "commands/clone.rs"]
 pub mod rad_clone;
+#[path = "commands/cob.rs"]
+pub mod rad_cob;
 #[path = "commands/comment.rs"]
 pub mod rad_comment;
 #[path = "commands/delegate.rs"]
@@ -22,14 +28,20 @@ pub mod rad_id;
 pub mod rad_init;
 #[path = "commands/inspect.rs"]
 pub mod rad_inspect;
+#[path = "commands/invite.rs"]
+pub mod rad_invite;
 #[path = "commands/issue.rs"]
 pub mod rad_issue;
+#[path = "commands/label.rs"]
+pub mod rad_label;
 #[path = "commands/ls.rs"]
 pub mod rad_ls;
 #[path = "commands/merge.rs"]
 pub mod rad_merge;
 #[path = "commands/node.rs"]
 pub mod rad_node;
+#[path = "commands/notes.rs"]
+pub mod rad_notes;
 #[path = "commands/patch.rs"]
 pub mod rad_patch;
 #[path = "commands/path.rs"]
@@ -40,6 +52,8 @@ pub mod rad_remote;
 pub mod rad_review;
 #[path = "commands/rm.rs"]
 pub mod rad_rm;
+#[path = "commands/search.rs"]
+pub mod rad_search;
 #[path = "commands/self.rs"]
 pub mod rad_self;
 #[path = "commands/sync.rs"]
@@ -1,16 +1,21 @@
 #![allow(clippy::or_fun_call)]
 use std::ffi::OsString;
+use std::path::PathBuf;
 
 use anyhow::anyhow;
 
 use radicle::crypto::ssh;
 use radicle::crypto::ssh::Passphrase;
+use radicle::crypto::PublicKey;
 use radicle::profile::env::RAD_PASSPHRASE;
 use radicle::{profile, Profile};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
 
+#[path = "auth/verify.rs"]
+mod verify;
+
 pub const HELP: Help = Help {
     name: "auth",
     description: "Manage identities and profiles",
@@ -19,21 +24,49 @@ pub const HELP: Help = Help {
 Usage
 
     rad auth [<option>...]
+    rad auth verify <signature> [--signer <did|nid>] [--file <path>] [<option>...]
 
     A passphrase may be given via the environment variable `RAD_PASSPHRASE` or
     via the standard input stream if `--stdin` is used. Using either of these
     methods disables the passphrase prompt.
 
+    The `verify` command checks a detached signature over a file, or over the
+    standard input if no file is given, and prints who made it. The signature
+    is either a multibase-encoded Ed25519 signature, or the path to a file
+    containing one, eg. the `signature` blob of signed refs. SSH signatures
+    created by `ssh-keygen -Y sign` are also accepted, in which case the signer
+    is optional. The exit status is non-zero if the signature is bad.
+
 Options
 
     --stdin                 Read passphrase from stdin (default: false)
     --help                  Print help
+
+Verify options
+
+    --signer <did|nid>      Key the signature is expected to be made with
+    --file <path>           File that was signed (default: standard input)
+    --namespace <name>      Namespace of SSH signatures (default: file)
+    --json                  Print the result as JSON
 "#,
 };
 
+#[derive(Debug)]
+pub enum Operation {
+    Authenticate,
+    Verify {
+        signature: verify::Source,
+        signer: Option<PublicKey>,
+        file: Option<PathBuf>,
+        namespace: String,
+        json: bool,
+    },
+}
+
 #[derive(Debug)]
 pub struct Options {
     pub stdin: bool,
+    pub op: Operation,
 }
 
 impl Args for Options {
@@ -41,6 +74,12 @@ impl Args for Options {
         use lexopt::prelude::*;
 
         let mut stdin = false;
+        let mut verify = false;
+        let mut signature = None;
+        let mut signer = None;
+        let mut file = None;
+        let mut namespace = String::from("file");
+        let mut json = false;
         let mut parser = lexopt::Parser::from_args(args);
 
         while let Some(arg) = parser.next()? {
@@ -48,18 +87,69 @@ impl Args for Options {
                 Long("stdin") => {
                     stdin = true;
                 }
+                Long("signer") if verify => {
+                    let val = parser.value()?;
+                    signer = Some(term::args::pubkey(&val)?);
+                }
+                Long("file") if verify => {
+                    file = Some(PathBuf::from(parser.value()?));
+                }
+                Long("namespace") if verify => {
+                    namespace = term::args::string(&parser.value()?);
+                }
+                Long("json") if verify => {
+                    json = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Value(val) if !verify && val == "verify" => {
+                    verify = true;
+                }
+                Value(val) if verify && signature.is_none() => {
+                    signature = Some(verify::Source::parse(&val.to_string_lossy()));
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
 
-        Ok((Options { stdin }, vec![]))
+        let op = if verify {
+            Operation::Verify {
+                signature: signature.ok_or_else(|| anyhow!("a signature must be specified"))?,
+                signer,
+                file,
+                namespace,
+                json,
+            }
+        } else {
+            Operation::Authenticate
+        };
+
+        Ok((Options { stdin, op }, vec![]))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if let Operation::Verify {
+        signature,
+        signer,
+        file,
+        namespace,
+        json,
+    } = &options.op
+    {
+        // Verifying doesn't need a profile, but we use it to show the signer's alias.
+        let profile = ctx.profile().ok();
+        let verified = verify::verify(
+            signature.clone(),
+            *signer,
+            file.as_deref(),
+            namespace,
+            profile.as_ref(),
+        )?;
+        return verify::show(&verified, *json);
+    }
+
     match ctx.profile() {
         Ok(profile) => authenticate(&profile, options),
         Err(_) => init(options),
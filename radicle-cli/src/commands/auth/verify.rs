@@ -0,0 +1,189 @@
+//! Verification of detached signatures.
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io};
+
+use anyhow::{anyhow, Context as _};
+use serde::Serialize;
+
+use radicle::crypto::{ssh, PublicKey, Signature};
+use radicle::node::tracking::Alias;
+use radicle::prelude::Did;
+use radicle::Profile;
+
+use crate::terminal as term;
+
+/// Start of a signature in the `SSHSIG` format, as created by `ssh-keygen -Y sign`.
+const SSHSIG_HEADER: &str = "-----BEGIN SSH SIGNATURE-----";
+
+/// A detached signature.
+#[derive(Debug, Clone)]
+pub enum Detached {
+    /// Ed25519 signature, eg. of signed refs.
+    Ed25519(Signature),
+    /// SSH signature, which includes the key of the signer.
+    Ssh(String),
+}
+
+/// Where the signature was read from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// The signature was given on the command line.
+    Arg(Detached),
+    /// The signature was read from a file.
+    File(PathBuf),
+}
+
+impl Source {
+    /// Parse a signature argument, which is either a multibase-encoded signature, or the path
+    /// to a file containing a signature.
+    pub fn parse(val: &str) -> Self {
+        match Signature::from_str(val) {
+            Ok(sig) => Self::Arg(Detached::Ed25519(sig)),
+            Err(_) => Self::File(PathBuf::from(val)),
+        }
+    }
+
+    /// Load the signature.
+    fn load(self) -> anyhow::Result<Detached> {
+        match self {
+            Self::Arg(sig) => Ok(sig),
+            Self::File(path) => {
+                let bytes = fs::read(&path)
+                    .with_context(|| format!("failed to read signature from {}", path.display()))?;
+                let sig = decode(&bytes)
+                    .with_context(|| format!("invalid signature in {}", path.display()))?;
+
+                Ok(sig)
+            }
+        }
+    }
+}
+
+/// A successful verification.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verified {
+    /// The signer.
+    pub signer: Did,
+    /// The signer's alias, if we track them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Alias>,
+}
+
+/// Verify a detached signature over the given file, or over the standard input if no file is
+/// given. The signer must be given, unless the signature is an SSH signature, which carries
+/// the key it was made with.
+pub fn verify(
+    signature: Source,
+    signer: Option<PublicKey>,
+    file: Option<&Path>,
+    namespace: &str,
+    profile: Option<&Profile>,
+) -> anyhow::Result<Verified> {
+    let signature = signature.load()?;
+    let msg = match file {
+        Some(path) => fs::read(path)
+            .with_context(|| format!("failed to read signed data from {}", path.display()))?,
+        None => {
+            let mut msg = Vec::new();
+            io::stdin().read_to_end(&mut msg)?;
+            msg
+        }
+    };
+
+    let key = match signature {
+        Detached::Ed25519(sig) => {
+            let key = signer.ok_or_else(|| {
+                anyhow!("a signer must be specified with `--signer` to verify an Ed25519 signature")
+            })?;
+            key.verify(&msg, &sig)
+                .map_err(|_| anyhow!("bad signature by {}", Did::from(key)))?;
+            key
+        }
+        Detached::Ssh(pem) => {
+            let key =
+                ssh::verify(pem, namespace, &msg).map_err(|e| anyhow!("bad signature: {e}"))?;
+            if let Some(signer) = signer {
+                if signer != key {
+                    anyhow::bail!(
+                        "signature was made by {}, not {}",
+                        Did::from(key),
+                        Did::from(signer)
+                    );
+                }
+            }
+            key
+        }
+    };
+    let alias = match profile {
+        Some(profile) => profile
+            .tracking()?
+            .node_policy(&key)?
+            .and_then(|node| node.alias),
+        None => None,
+    };
+
+    Ok(Verified {
+        signer: key.into(),
+        alias,
+    })
+}
+
+/// Show a successful verification, or print it as JSON.
+pub fn show(verified: &Verified, json: bool) -> anyhow::Result<()> {
+    if json {
+        term::print(serde_json::to_string(verified)?);
+        return Ok(());
+    }
+    let signer = match &verified.alias {
+        Some(alias) => format!(
+            "{} {}",
+            term::format::tertiary(&verified.signer),
+            term::format::parens(term::format::secondary(alias))
+        ),
+        None => term::format::tertiary(&verified.signer).to_string(),
+    };
+    term::success!("Good signature by {signer}");
+
+    Ok(())
+}
+
+/// Decode a signature file, which is either a raw Ed25519 signature, a multibase-encoded one,
+/// or an SSH signature.
+fn decode(bytes: &[u8]) -> anyhow::Result<Detached> {
+    if let Ok(sig) = Signature::try_from(bytes) {
+        return Ok(Detached::Ed25519(sig));
+    }
+    let text = std::str::from_utf8(bytes)?.trim();
+
+    if text.starts_with(SSHSIG_HEADER) {
+        Ok(Detached::Ssh(text.to_owned()))
+    } else {
+        Ok(Detached::Ed25519(Signature::from_str(text)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use radicle::crypto::test::signer::MockSigner;
+    use radicle::crypto::Signer as _;
+
+    #[test]
+    fn test_decode() {
+        let signer = MockSigner::default();
+        let sig = signer.sign(b"radicle");
+
+        assert!(matches!(decode(sig.as_ref()).unwrap(), Detached::Ed25519(s) if s == sig));
+        assert!(
+            matches!(decode(format!("{sig}\n").as_bytes()).unwrap(), Detached::Ed25519(s) if s == sig)
+        );
+        assert!(matches!(
+            decode(format!("{SSHSIG_HEADER}\n...\n").as_bytes()).unwrap(),
+            Detached::Ssh(_)
+        ));
+        assert!(decode(b"not a signature").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::node::Handle;
+use radicle::prelude::*;
+use radicle::Node;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "block",
+    description: "Hide content from an author",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad block <did> [<option>...]
+    rad block --remove <did> [<option>...]
+    rad block [<option>...]
+
+    Issues, patches, comments and reviews authored by a blocked DID are
+    hidden from the output of `rad issue` and `rad patch`. Blocked authors
+    are stored in the profile configuration.
+
+    When no DID is given, the list of blocked authors is shown.
+
+Options
+
+    --remove               Unblock the given DID
+    --untrack              Also remove the tracking policy of the author's node, so that
+                           their namespaces are no longer replicated
+    --help                 Print help
+"#,
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    Block { did: Did, untrack: bool },
+    Unblock { did: Did },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut did: Option<Did> = None;
+        let mut remove = false;
+        let mut untrack = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("remove") => remove = true,
+                Long("untrack") => untrack = true,
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if did.is_none() => {
+                    did = Some(term::args::did(&val)?);
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match (did, remove) {
+            (Some(did), false) => Operation::Block { did, untrack },
+            (Some(did), true) => Operation::Unblock { did },
+            (None, false) => Operation::List,
+            (None, true) => return Err(anyhow!("a DID must be specified with `--remove`")),
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let path = profile.home.config();
+    let mut config = profile.config.clone();
+
+    match options.op {
+        Operation::Block { did, untrack } => {
+            if did == profile.did() {
+                anyhow::bail!("you can't block yourself");
+            }
+            if config.is_blocked(&did) {
+                term::info!("{} is already blocked", term::format::tertiary(did));
+            } else {
+                config.blocked.push(did);
+                config.write(&path)?;
+
+                term::success!("Blocked {}", term::format::tertiary(did));
+            }
+            if untrack {
+                let mut node = Node::new(profile.socket());
+
+                if node.untrack_node(did.into())? {
+                    term::success!(
+                        "Tracking policy for {} removed",
+                        term::format::tertiary(did)
+                    );
+                }
+            }
+        }
+        Operation::Unblock { did } => {
+            if config.is_blocked(&did) {
+                config.blocked.retain(|d| *d != did);
+                config.write(&path)?;
+
+                term::success!("Unblocked {}", term::format::tertiary(did));
+            } else {
+                term::info!("{} is not blocked", term::format::tertiary(did));
+            }
+        }
+        Operation::List => {
+            if config.blocked.is_empty() {
+                term::print(term::format::italic("Nothing to show."));
+            }
+            for did in &config.blocked {
+                term::print(term::format::tertiary(did));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -102,6 +102,9 @@ fn execute(options: Options, profile: &Profile) -> anyhow::Result<PathBuf> {
             return Err(err.into());
         }
     };
+    if let Some(workdir) = repo.workdir() {
+        profile.checkouts()?.register(&id, workdir)?;
+    }
     spinner.message(format!(
         "Repository checkout successful under ./{}",
         term::format::highlight(path.file_name().unwrap_or_default().to_string_lossy())
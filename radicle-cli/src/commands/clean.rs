@@ -0,0 +1,466 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+use radicle::cob::patch::Patches;
+use radicle::git::Url;
+use radicle::node::tracking::store::Config as TrackingStore;
+use radicle::node::tracking::{Policy, Scope};
+use radicle::prelude::*;
+use radicle::storage::git::{gc, Repository};
+use radicle::storage::Namespaces;
+use radicle::Profile;
+
+use crate::git;
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "clean",
+    description: "Clean up stale working copy state",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad clean [<option>...]
+
+    Removes state that is no longer needed from the working copy:
+
+    * Remote-tracking branches of patches that no longer exist
+    * Remotes pointing at namespaces that are neither tracked nor delegates
+    * Push refspecs of branches that no longer exist, and a `rad` remote
+      push URL that doesn't point to your own namespace
+
+    This command must be run from within a project working copy.
+
+    With `--storage`, cleans up the local storage instead, for all repositories:
+    namespaces of peers that are out of the repository's tracking scope are
+    pruned, and unreachable objects are garbage collected. Namespaces of the
+    repository delegates and of your own node are always kept, as are the
+    namespaces that working copies of the repository have remotes for.
+
+Options
+
+    --storage              Clean up storage, instead of the working copy
+    --dry-run              Show what would be removed, without changing anything
+    --help                 Print help
+"#,
+};
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub storage: bool,
+    pub dry_run: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut storage = false;
+        let mut dry_run = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("storage") => storage = true,
+                Long("dry-run") => dry_run = true,
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((Options { storage, dry_run }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if options.storage {
+        let profile = ctx.profile()?;
+        return storage(&profile, options.dry_run);
+    }
+    let (working, rid) = radicle::rad::cwd()
+        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let profile = ctx.profile()?;
+    let stored = profile.storage.repository(rid)?;
+    let cleaner = Cleaner {
+        working: &working,
+        dry_run: options.dry_run,
+    };
+
+    let cleaned = cleaner.patch_branches(&stored)?
+        + cleaner.remotes(&stored, &profile)?
+        + cleaner.push_refspecs(rid, profile.id())?;
+
+    if cleaned == 0 {
+        term::info!("Nothing to clean");
+    }
+    Ok(())
+}
+
+/// Prune out-of-scope namespaces and collect garbage in all stored repositories.
+fn storage(profile: &Profile, dry_run: bool) -> anyhow::Result<()> {
+    let tracking = profile.tracking()?;
+    let checkouts = profile.checkouts()?.all()?;
+    let mut reclaimed = 0;
+    let mut pruned = 0;
+
+    for rid in profile.storage.repositories()? {
+        let repo = profile.storage.repository(rid)?;
+        let mut keep = keep(rid, profile, &tracking)?;
+
+        if let (Namespaces::Trusted(keep), Some(checkouts)) = (&mut keep, checkouts.get(&rid)) {
+            keep.extend(referenced(rid, checkouts)?);
+        }
+        let collected = match gc::collect(&repo, &keep, dry_run) {
+            Ok(collected) => collected,
+            Err(gc::Error::Delegates(e)) => {
+                term::warning(&format!("Skipping {rid}: failed to get its delegates: {e}"));
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for remote in &collected.pruned {
+            if dry_run {
+                term::info!(
+                    "Pruned {} from {} (dry run)",
+                    term::format::tertiary(remote),
+                    term::format::tertiary(rid)
+                );
+            } else {
+                term::success!(
+                    "Pruned {} from {}",
+                    term::format::tertiary(remote),
+                    term::format::tertiary(rid)
+                );
+            }
+        }
+        pruned += collected.pruned.len();
+        reclaimed += collected.reclaimed();
+    }
+
+    if dry_run {
+        if pruned == 0 {
+            term::info!("Nothing to prune");
+        }
+    } else {
+        term::success!(
+            "Reclaimed {} of storage space",
+            term::format::highlight(term::format::bytes(reclaimed))
+        );
+    }
+    Ok(())
+}
+
+/// Namespaces to keep for a repository, according to its tracking scope. Repositories
+/// without a tracking policy, or that aren't tracked, are left as they are.
+fn keep(rid: Id, profile: &Profile, tracking: &TrackingStore) -> anyhow::Result<Namespaces> {
+    let Some(repo) = tracking.repo_policy(&rid)? else {
+        return Ok(Namespaces::All);
+    };
+    if repo.policy != Policy::Track {
+        return Ok(Namespaces::All);
+    }
+    let mut keep = match repo.scope {
+        Scope::All => return Ok(Namespaces::All),
+        Scope::Trusted => tracking
+            .node_policies()?
+            .filter_map(|node| (node.policy == Policy::Track).then_some(node.id))
+            .collect::<HashSet<_>>(),
+        Scope::Explicit(remotes) => remotes.into_iter().map(PublicKey::from).collect(),
+    };
+    keep.insert(*profile.id());
+
+    Ok(Namespaces::Trusted(keep))
+}
+
+/// Namespaces of a repository that its working copies have remotes for.
+fn referenced(rid: Id, checkouts: &[PathBuf]) -> anyhow::Result<HashSet<PublicKey>> {
+    let mut referenced = HashSet::new();
+
+    for path in checkouts {
+        let working = git::Repository::open(path)?;
+
+        referenced.extend(
+            git::rad_remotes(&working)?
+                .into_iter()
+                .filter(|r| r.url.repo == rid)
+                .filter_map(|r| r.url.namespace),
+        );
+    }
+    Ok(referenced)
+}
+
+/// Removes stale state from a working copy.
+struct Cleaner<'a> {
+    working: &'a git::Repository,
+    dry_run: bool,
+}
+
+impl<'a> Cleaner<'a> {
+    /// Remove remote-tracking branches of patches that no longer exist,
+    /// eg. `refs/remotes/<nid>/patch/<id>`.
+    fn patch_branches(&self, stored: &Repository) -> anyhow::Result<usize> {
+        let patches = Patches::open(stored)?;
+        let ids = patches
+            .all()?
+            .filter_map(|p| p.ok())
+            .map(|(id, _, _)| term::format::cob(&id))
+            .collect::<HashSet<_>>();
+        let mut cleaned = 0;
+
+        for r in self.working.references_glob("refs/remotes/*/patch/*")? {
+            let mut r = r?;
+            let Some(name) = r.name().map(ToOwned::to_owned) else {
+                continue;
+            };
+            let Some((_, id)) = name.rsplit_once("/patch/") else {
+                continue;
+            };
+            if ids.contains(id) {
+                continue;
+            }
+            if !self.dry_run {
+                r.delete()?;
+            }
+            let name = name.strip_prefix("refs/remotes/").unwrap_or(&name);
+            self.report("Removed remote-tracking branch", name);
+            cleaned += 1;
+        }
+        Ok(cleaned)
+    }
+
+    /// Remove remotes pointing at namespaces that aren't tracked, and aren't delegates.
+    fn remotes(&self, stored: &Repository, profile: &Profile) -> anyhow::Result<usize> {
+        let tracking = profile.tracking()?;
+        let (_, doc) = stored.identity_doc()?;
+        let delegates = doc.verified()?.delegates;
+        let mut stale = Vec::new();
+
+        for remote in git::rad_remotes(self.working)? {
+            let Some(nid) = remote.url.namespace else {
+                continue;
+            };
+            if remote.url.repo != stored.id
+                || nid == *profile.id()
+                || delegates.contains(&Did::from(nid))
+                || tracking.is_node_tracked(&nid)?
+            {
+                continue;
+            }
+            stale.push(remote.name);
+        }
+        for name in &stale {
+            if !self.dry_run {
+                self.working.remote_delete(name)?;
+            }
+            self.report("Removed remote", name);
+        }
+        Ok(stale.len())
+    }
+
+    /// Remove push refspecs of local branches that no longer exist, and point the push URL of
+    /// the `rad` remote to our own namespace, given our node id.
+    fn push_refspecs(&self, rid: Id, nid: &PublicKey) -> anyhow::Result<usize> {
+        let mut config = self.working.config()?;
+        let mut cleaned = 0;
+
+        for remote in git::rad_remotes(self.working)? {
+            let key = format!("remote.{}.push", remote.name);
+            let refspecs = self
+                .working
+                .find_remote(&remote.name)?
+                .push_refspecs()?
+                .iter()
+                .flatten()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>();
+
+            for refspec in refspecs {
+                let src = refspec.trim_start_matches('+');
+                let src = src.split_once(':').map_or(src, |(src, _)| src);
+
+                if src.is_empty() || src.contains('*') || self.working.revparse_single(src).is_ok()
+                {
+                    continue;
+                }
+                if !self.dry_run {
+                    config.remove_multivar(&key, &format!("^{}$", escape(&refspec)))?;
+                }
+                self.report(
+                    &format!("Removed push refspec of {}", remote.name),
+                    &refspec,
+                );
+                cleaned += 1;
+            }
+        }
+
+        let (remote, _) = git::rad_remote(self.working)?;
+        let name = radicle::rad::REMOTE_NAME.as_str();
+        let expected = Url::from(rid).with_namespace(*nid);
+
+        if remote.pushurl() != Some(expected.to_string().as_str()) {
+            if !self.dry_run {
+                self.working
+                    .remote_set_pushurl(name, Some(expected.to_string().as_str()))?;
+            }
+            self.report(&format!("Updated push URL of {name} to"), &expected);
+            cleaned += 1;
+        }
+        Ok(cleaned)
+    }
+
+    fn report(&self, action: &str, item: impl fmt::Display) {
+        if self.dry_run {
+            term::info!("{action} {} (dry run)", term::format::tertiary(item));
+        } else {
+            term::success!("{action} {}", term::format::tertiary(item));
+        }
+    }
+}
+
+/// Escape a string for use in a git config value regex.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        if "\\.^$|()[]{}*+?".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use radicle::crypto::test::signer::MockSigner;
+    use radicle::test::arbitrary;
+    use radicle::test::fixtures::{self, Shape};
+    use radicle::Storage;
+
+    fn setup(tmp: &tempfile::TempDir, shape: &Shape) -> (Storage, MockSigner, Id, git::Repository) {
+        let signer = MockSigner::from_seed([0xaa; 32]);
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (rid, working) =
+            fixtures::generate(tmp.path().join("working"), shape, &storage, &signer);
+
+        (storage, signer, rid, working)
+    }
+
+    #[test]
+    fn test_patch_branches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shape = Shape {
+            patches: 1,
+            ..Shape::default()
+        };
+        let (storage, signer, rid, working) = setup(&tmp, &shape);
+        let stored = storage.repository(rid).unwrap();
+        let (patch, _, _) = Patches::open(&stored)
+            .unwrap()
+            .all()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let head = working.head().unwrap().target().unwrap();
+        let remote = signer.public_key();
+        let live = format!("refs/remotes/{remote}/patch/{}", term::format::cob(&patch));
+        let stale = format!(
+            "refs/remotes/{remote}/patch/{}",
+            term::format::cob(&arbitrary::oid().into())
+        );
+        for name in [&live, &stale] {
+            working.reference(name, head, false, "").unwrap();
+        }
+
+        let cleaner = Cleaner {
+            working: &working,
+            dry_run: true,
+        };
+        assert_eq!(cleaner.patch_branches(&stored).unwrap(), 1);
+        assert!(working.find_reference(&stale).is_ok());
+
+        let cleaner = Cleaner {
+            working: &working,
+            dry_run: false,
+        };
+        assert_eq!(cleaner.patch_branches(&stored).unwrap(), 1);
+        assert!(working.find_reference(&live).is_ok());
+        assert!(working.find_reference(&stale).is_err());
+        assert_eq!(cleaner.patch_branches(&stored).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_push_refspecs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_storage, signer, rid, working) = setup(&tmp, &Shape::default());
+        let nid = *signer.public_key();
+        let name = radicle::rad::REMOTE_NAME.as_str();
+        let refspecs = |working: &git::Repository| {
+            working
+                .find_remote(name)
+                .unwrap()
+                .push_refspecs()
+                .unwrap()
+                .iter()
+                .flatten()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        working
+            .remote_add_push(name, "refs/heads/feature/0:refs/heads/feature/0")
+            .unwrap();
+        working
+            .remote_add_push(name, "+refs/heads/gone:refs/heads/gone")
+            .unwrap();
+        working
+            .remote_add_push(name, "refs/heads/*:refs/heads/*")
+            .unwrap();
+        working
+            .remote_set_pushurl(
+                name,
+                Some(
+                    Url::from(rid)
+                        .with_namespace(arbitrary::gen::<PublicKey>(1))
+                        .to_string()
+                        .as_str(),
+                ),
+            )
+            .unwrap();
+
+        let cleaner = Cleaner {
+            working: &working,
+            dry_run: true,
+        };
+        assert_eq!(cleaner.push_refspecs(rid, &nid).unwrap(), 2);
+        assert!(refspecs(&working).contains(&String::from("+refs/heads/gone:refs/heads/gone")));
+
+        let cleaner = Cleaner {
+            working: &working,
+            dry_run: false,
+        };
+        assert_eq!(cleaner.push_refspecs(rid, &nid).unwrap(), 2);
+
+        let refspecs = refspecs(&working);
+        assert!(refspecs.contains(&String::from("refs/heads/feature/0:refs/heads/feature/0")));
+        assert!(refspecs.contains(&String::from("refs/heads/*:refs/heads/*")));
+        assert!(!refspecs.iter().any(|r| r.contains("gone")));
+        assert_eq!(
+            working.find_remote(name).unwrap().pushurl(),
+            Some(Url::from(rid).with_namespace(nid).to_string().as_str())
+        );
+        assert_eq!(cleaner.push_refspecs(rid, &nid).unwrap(), 0);
+    }
+}
@@ -1,7 +1,10 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeSet;
 use std::ffi::OsString;
-use std::path::Path;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time;
 
 use anyhow::anyhow;
 use thiserror::Error;
@@ -11,7 +14,7 @@ use radicle::identity::doc::{DocError, Id};
 use radicle::identity::{doc, IdentityError};
 use radicle::node;
 use radicle::node::tracking::Scope;
-use radicle::node::{Handle as _, Node};
+use radicle::node::{ConnectAddress, Handle as _, Node};
 use radicle::prelude::*;
 use radicle::rad;
 use radicle::storage;
@@ -31,13 +34,42 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad clone <rid> [<option>...]
+    rad clone <rid> [--scope <scope> | --remote <did>... | --no-follow] [--seed <address>...] [<option>...]
+
+    By default, the repository is tracked with the 'trusted' scope, which
+    means that only the repository delegates and the nodes we track are
+    fetched. To fetch every fork of the repository, use `--scope all`, and
+    to only fetch the delegates and a given set of remotes, use `--remote`
+    once for every remote. Remotes given with `--remote` are also added to
+    the working copy.
+
+    To clone the repository once, without keeping it up to date, use
+    `--no-follow`: the tracking policy is removed after the clone, or if the
+    clone fails, unless the repository was already tracked.
+
+    By default, the repository is fetched from the connected seeds that are
+    known to have it. Private repositories aren't announced, so to clone one,
+    connect to a seed you have been invited to with `--seed <nid>@<addr>`;
+    the repository is then fetched from the given seeds only.
+
+    To only check out the latest commits of each branch, use `--depth`. The
+    working copy is then shallow, but the stored repository is still complete,
+    since its full history is needed to verify it. Run `git fetch --unshallow`
+    in the working copy to get the rest of the history.
 
 Options
 
-    --no-announce   Do not announce our new refs to the network
-    --no-confirm    Don't ask for confirmation during clone
-    --help          Print help
+    --scope <scope>   Node (remote) tracking scope for the repository
+    --remote <did>    Track the given remote, in addition to the delegates
+                      (may be specified multiple times)
+    --no-follow       Don't track the repository after cloning it
+    --seed <address>  Connect to the given seed, and fetch from it (may be specified multiple times)
+    --timeout <secs>  How many seconds to wait for each seed to respond (default: 9)
+                      Fetches that time out are retried twice
+    --depth <n>       Create a shallow working copy, with the last <n> commits of each branch
+    --no-announce     Do not announce our new refs to the network
+    --no-confirm      Don't ask for confirmation during clone
+    --help            Print help
 
 "#,
 };
@@ -47,7 +79,11 @@ pub struct Options {
     id: Id,
     #[allow(dead_code)]
     interactive: Interactive,
-    announce: bool,
+    scope: Scope,
+    follow: bool,
+    seeds: Vec<ConnectAddress>,
+    timeout: time::Duration,
+    depth: Option<NonZeroUsize>,
 }
 
 impl Args for Options {
@@ -57,18 +93,55 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut id: Option<Id> = None;
         let mut interactive = Interactive::Yes;
-        let mut announce = true;
+        let mut scope = Scope::default();
+        let mut follow = true;
+        let mut seeds = Vec::new();
+        let mut timeout = node::DEFAULT_TIMEOUT;
+        let mut depth = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("no-confirm") => {
                     interactive = Interactive::No;
                 }
-                Long("no-announce") => {
-                    announce = false;
+                Long("scope") => {
+                    let val = parser.value()?;
+
+                    scope = term::args::string(&val).parse()?;
+                }
+                Long("remote") => {
+                    let val = parser.value()?;
+                    let did = term::args::did(&val)?;
+
+                    match &mut scope {
+                        Scope::Explicit(remotes) => {
+                            remotes.insert(did);
+                        }
+                        _ => scope = Scope::Explicit(BTreeSet::from([did])),
+                    }
                 }
-                Long("announce") => {
-                    announce = true;
+                Long("no-follow") => {
+                    follow = false;
+                }
+                Long("seed") => {
+                    let val = parser.value()?;
+                    let seed = val
+                        .to_string_lossy()
+                        .parse()
+                        .map_err(|e| anyhow!("invalid seed address {:?}: {e}", val))?;
+                    seeds.push(seed);
+                }
+                Long("timeout") | Short('t') => {
+                    let value = parser.value()?;
+                    let secs = term::args::parse_value("timeout", value)?;
+
+                    timeout = time::Duration::from_secs(secs);
+                }
+                Long("depth") => {
+                    let value = parser.value()?;
+                    let value = term::args::parse_value("depth", value)?;
+
+                    depth = Some(value);
                 }
                 Long("help") => {
                     return Err(Error::Help.into());
@@ -86,11 +159,19 @@ impl Args for Options {
         let id =
             id.ok_or_else(|| anyhow!("to clone, an RID must be provided; see `rad clone --help`"))?;
 
+        if !follow && scope != Scope::default() {
+            anyhow::bail!("`--no-follow` can't be used with `--scope` or `--remote`");
+        }
+
         Ok((
             Options {
                 id,
                 interactive,
-                announce,
+                scope,
+                follow,
+                seeds,
+                timeout,
+                depth,
             },
             vec![],
         ))
@@ -99,25 +180,97 @@ impl Args for Options {
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
-    let signer = term::signer(&profile)?;
     let mut node = radicle::Node::new(profile.socket());
+    let tracked = profile.tracking()?.is_repo_tracked(&options.id)?;
+    let result = clone_working_copy(&options, &profile, &mut node);
+
+    // Nb. The tracking policy is also removed if the clone failed, so that a failed clone
+    // doesn't leave a repository tracked that wasn't meant to be.
+    if !options.follow && !tracked {
+        match node.untrack_repo(options.id) {
+            Ok(true) if result.is_ok() => {
+                term::info!(
+                    "Tracking policy removed for {}; the repository will not be kept up to date",
+                    term::format::tertiary(options.id)
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                term::warning(&format!(
+                    "Failed to remove tracking policy for {}: {e}",
+                    options.id
+                ));
+            }
+        }
+    }
+    let path = result?;
+
+    term::success!(
+        "Repository successfully cloned under {}",
+        term::format::highlight(Path::new(".").join(path).display())
+    );
+
+    Ok(())
+}
+
+/// Clone the repository and setup its working copy, returning the path of the working copy.
+fn clone_working_copy(
+    options: &Options,
+    profile: &Profile,
+    node: &mut Node,
+) -> anyhow::Result<PathBuf> {
+    let signer = term::signer(profile)?;
+    let seeds = if options.seeds.is_empty() {
+        None
+    } else {
+        Some(sync::connect_and_wait(
+            node,
+            &options.seeds,
+            options.timeout,
+        )?)
+    };
     let (working, doc, proj) = clone(
         options.id,
         &signer,
         &profile.storage,
-        &mut node,
-        options.announce,
+        node,
+        options.scope.clone(),
+        seeds,
+        options.timeout,
+        options.depth,
     )?;
-    let delegates = doc
+    let mut remotes = doc
         .delegates
         .iter()
         .map(|d| **d)
         .filter(|id| id != profile.id())
         .collect::<Vec<_>>();
+
+    if let Scope::Explicit(explicit) = &options.scope {
+        let repo = profile.storage.repository(options.id)?;
+
+        for did in explicit {
+            let nid = **did;
+
+            if nid == *profile.id() || remotes.contains(&nid) {
+                continue;
+            }
+            if repo.remote(&nid).is_err() {
+                term::warning(&format!(
+                    "Remote {} was not found; it will be fetched once it is available",
+                    term::format::node(&nid)
+                ));
+                continue;
+            }
+            remotes.push(nid);
+        }
+    }
     let default_branch = proj.default_branch().clone();
     let path = working.workdir().unwrap(); // SAFETY: The working copy is not bare.
 
-    // Setup tracking for project delegates.
+    profile.checkouts()?.register(&options.id, path)?;
+
+    // Setup tracking for project delegates, and explicitly tracked remotes.
     checkout::setup_remotes(
         project::SetupRemote {
             project: options.id,
@@ -126,15 +279,10 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             fetch: true,
             tracking: true,
         },
-        &delegates,
+        &remotes,
     )?;
 
-    term::success!(
-        "Repository successfully cloned under {}",
-        term::format::highlight(Path::new(".").join(path).display())
-    );
-
-    Ok(())
+    Ok(path.to_path_buf())
 }
 
 #[derive(Error, Debug)]
@@ -159,24 +307,39 @@ pub enum CloneError {
     NoSeeds(Id),
 }
 
+/// Clone a repository. If `seeds` are given, the repository is only fetched from these seeds,
+/// otherwise it is fetched from all connected seeds. If a `depth` is given, the working copy
+/// is shallow.
 pub fn clone<G: Signer>(
     id: Id,
     signer: &G,
     storage: &Storage,
     node: &mut Node,
-    announce: bool,
+    scope: Scope,
+    seeds: Option<BTreeSet<NodeId>>,
+    timeout: time::Duration,
+    depth: Option<NonZeroUsize>,
 ) -> Result<(raw::Repository, Doc<Verified>, Project), CloneError> {
     let me = *signer.public_key();
 
     // Track.
-    if node.track_repo(id, Scope::default())? {
+    if node.track_repo(id, scope)? {
         term::success!(
             "Tracking relationship established for {}",
             term::format::tertiary(id)
         );
     }
 
-    let results = sync::fetch_all(id, node)?;
+    let results = match seeds {
+        Some(seeds) => {
+            let mut results = node::FetchResults::default();
+            for seed in &seeds {
+                results.push(*seed, sync::fetch_from(id, seed, node, timeout)?);
+            }
+            results
+        }
+        None => sync::fetch_all(id, node, timeout)?,
+    };
     let Ok(repository) = storage.repository(id) else {
         // If we don't have the project locally, even after attempting to fetch,
         // there's nothing we can do.
@@ -189,22 +352,13 @@ pub fn clone<G: Signer>(
 
     // Create a local fork of the project, under our own id, unless we have one already.
     if repository.remote(signer.public_key()).is_err() {
-        let mut spinner = term::spinner(format!(
+        let spinner = term::spinner(format!(
             "Forking under {}..",
             term::format::tertiary(term::format::node(&me))
         ));
         rad::fork(id, signer, &storage)?;
-
-        if announce {
-            if let Err(e) = node.announce_refs(id) {
-                spinner.message("Announcing fork..");
-                spinner.error(e);
-            } else {
-                spinner.finish();
-            }
-        } else {
-            spinner.finish();
-        }
+        term::announce::changed(id);
+        spinner.finish();
     }
 
     let doc = repository.identity_doc_of(&me)?;
@@ -224,7 +378,10 @@ pub fn clone<G: Signer>(
         "Creating checkout in ./{}..",
         term::format::tertiary(path.display())
     ));
-    let repo = rad::checkout(id, &me, path, &storage)?;
+    let repo = match depth {
+        Some(depth) => rad::shallow_checkout(id, &me, path, &storage, depth)?,
+        None => rad::checkout(id, &me, path, &storage)?,
+    };
 
     spinner.finish();
 
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsString;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle::cob;
+use radicle::cob::{identity, issue, patch, ObjectId, TypeName};
+use radicle::crypto::PublicKey;
+use radicle::git::Oid;
+use radicle::prelude::{Did, Id};
+use radicle::storage::git::Repository;
+use radicle::storage::{ReadStorage, WriteStorage};
+use radicle_cob::object::Storage as _;
+
+use crate::git::Rev;
+use crate::project;
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "cob",
+    description: "Inspect collaborative objects",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad cob [list] [--type <typename>] [<option>...]
+    rad cob heads <object-id> [--type <typename>] [<option>...]
+    rad cob merge <object-id> [--type <typename>] [<option>...]
+
+    Lists the collaborative objects of a repository, eg. issues and patches, as
+    they are stored: every object is shown with the number of operations it is
+    made of, the number of remotes that have a copy of it, its authors, and
+    when it was last modified. This is useful to track down replication
+    problems, eg. when an object is missing changes made by a peer.
+
+    Without `--type`, objects of all known types are listed.
+
+    The `heads` command shows the heads of an object that have diverged, eg.
+    because peers made changes concurrently and haven't fetched each other's
+    changes yet. Every head is shown with the remotes that point to it, and
+    the operations that only it has. Remotes that are behind every head are
+    shown as well. The type of the object is detected when not given.
+
+    The `merge` command records a change that has every head as parent, so
+    that peers fetching from us get all operations. It is only supported for
+    issues and patches, since their operations commute.
+
+Options
+
+    --type, -t <typename>   Only list objects of the given type, eg. `xyz.radicle.issue`
+    --repo <rid>            Operate on the given repository (default: cwd)
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum OperationName {
+    #[default]
+    List,
+    Heads,
+    Merge,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    List { typename: Option<TypeName> },
+    Heads { id: Rev, typename: Option<TypeName> },
+    Merge { id: Rev, typename: Option<TypeName> },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub repo: Option<Id>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut typename: Option<TypeName> = None;
+        let mut id: Option<Rev> = None;
+        let mut repo = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("type") | Short('t') => {
+                    let val = parser.value()?;
+                    let val = term::args::string(&val);
+
+                    typename = Some(
+                        TypeName::from_str(&val)
+                            .map_err(|_| anyhow!("invalid object type `{val}`"))?,
+                    );
+                }
+                Long("repo") => {
+                    repo = Some(term::args::rid(&parser.value()?)?);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "l" | "list" => op = Some(OperationName::List),
+                    "heads" => op = Some(OperationName::Heads),
+                    "merge" => op = Some(OperationName::Merge),
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if op != Some(OperationName::List) && id.is_none() => {
+                    id = Some(Rev::from(term::args::string(&val)));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or_default() {
+            OperationName::List => Operation::List { typename },
+            OperationName::Heads => Operation::Heads {
+                id: id.ok_or_else(|| anyhow!("an object id must be provided"))?,
+                typename,
+            },
+            OperationName::Merge => Operation::Merge {
+                id: id.ok_or_else(|| anyhow!("an object id must be provided"))?,
+                typename,
+            },
+        };
+
+        Ok((Options { op, repo }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let (_, rid) = project::resolve(options.repo)?;
+    let repo = profile.storage.repository_mut(rid)?;
+
+    match options.op {
+        Operation::List { typename } => {
+            let types = match typename {
+                Some(typename) => vec![typename],
+                None => vec![
+                    identity::TYPENAME.clone(),
+                    issue::TYPENAME.clone(),
+                    patch::TYPENAME.clone(),
+                ],
+            };
+            list(&repo, &types)?;
+        }
+        Operation::Heads { id, typename } => {
+            let id = id.resolve(&repo.backend)?;
+            let typename = typename_of(&repo, &id, typename)?;
+
+            heads(&repo, &typename, &id, profile.id())?;
+        }
+        Operation::Merge { id, typename } => {
+            let id = id.resolve(&repo.backend)?;
+            let typename = typename_of(&repo, &id, typename)?;
+            let signer = term::signer(&profile)?;
+            let object = cob::get(&repo, &typename, &id)?
+                .ok_or_else(|| anyhow!("object `{id}` of type `{typename}` not found"))?;
+            let tips = object.history().tips();
+
+            if tips.len() < 2 {
+                term::info!("Object {} has a single head", term::format::cob(&id));
+                return Ok(());
+            }
+            // Nb. every change has all the heads known to us as parents, so recording a change
+            // that has no effect is enough to merge them.
+            if typename == *issue::TYPENAME {
+                let mut issues = issue::Issues::open(&repo)?;
+                let mut issue = issues.get_mut(&id)?;
+                issue.transaction("Merge", &signer, |tx| tx.tag([], []))?;
+            } else if typename == *patch::TYPENAME {
+                let mut patches = patch::Patches::open(&repo)?;
+                let mut patch = patches.get_mut(&id)?;
+                patch.transaction("Merge", &signer, |tx| tx.tag([], []))?;
+            } else {
+                anyhow::bail!("objects of type `{typename}` can't be merged");
+            }
+            term::success!(
+                "Merged {} heads of {}",
+                tips.len(),
+                term::format::tertiary(term::format::cob(&id))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Get the type of an object, checking that it exists. If no type is given, the known types
+/// are tried in turn.
+fn typename_of(
+    repo: &Repository,
+    id: &ObjectId,
+    typename: Option<TypeName>,
+) -> anyhow::Result<TypeName> {
+    let types = match typename {
+        Some(typename) => vec![typename],
+        None => vec![
+            identity::TYPENAME.clone(),
+            issue::TYPENAME.clone(),
+            patch::TYPENAME.clone(),
+        ],
+    };
+    for typename in types {
+        if repo.objects(&typename, id)?.iter().next().is_some() {
+            return Ok(typename);
+        }
+    }
+    anyhow::bail!("object `{id}` not found")
+}
+
+/// Print the heads of an object, with the remotes pointing to them and the operations that are
+/// only reachable from each of them.
+fn heads(
+    repo: &Repository,
+    typename: &TypeName,
+    id: &ObjectId,
+    whoami: &PublicKey,
+) -> anyhow::Result<()> {
+    let object = cob::get(repo, typename, id)?
+        .ok_or_else(|| anyhow!("object `{id}` of type `{typename}` not found"))?;
+    let entries = object
+        .history()
+        .traverse(HashMap::new(), |mut entries, entry| {
+            entries.insert(Oid::from(*entry.id()), (*entry.actor(), entry.timestamp()));
+            ControlFlow::Continue(entries)
+        });
+    let tips = object.history().tips();
+    let mut remotes: BTreeMap<Oid, Vec<PublicKey>> = BTreeMap::new();
+
+    for reference in repo.objects(typename, id)?.iter() {
+        if let Ok((remote, _)) =
+            radicle::git::parse_ref_namespaced::<PublicKey>(reference.name.as_str())
+        {
+            remotes.entry(reference.target.id).or_default().push(remote);
+        }
+    }
+    let remote = |key: &PublicKey| {
+        let did = term::format::did(&Did::from(*key));
+        if key == whoami {
+            format!("{} {}", did, term::format::primary("(you)"))
+        } else {
+            did.to_string()
+        }
+    };
+
+    term::print(format!(
+        "{} {} {}",
+        term::format::tertiary(term::format::cob(id)),
+        term::format::dim(typename),
+        term::format::dim(format!("({} op(s))", entries.len()))
+    ));
+    for tip in &tips {
+        let mut walk = repo.backend.revwalk()?;
+        walk.push(**tip)?;
+        for other in tips.iter().filter(|t| *t != tip) {
+            walk.hide(**other)?;
+        }
+        let mut unique = Vec::new();
+        for oid in walk {
+            let oid = Oid::from(oid?);
+            if let Some((actor, timestamp)) = entries.get(&oid) {
+                let message = repo.backend.find_commit(*oid)?;
+                let message = message.summary().unwrap_or_default().to_owned();
+                unique.push((oid, *actor, *timestamp, message));
+            }
+        }
+        let pointing = remotes
+            .get(tip)
+            .map(|r| r.iter().map(remote).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        term::blank();
+        term::print(format!(
+            "{} {} {}",
+            term::format::yellow("●"),
+            term::format::secondary(term::format::oid(*tip)),
+            pointing.join(", ")
+        ));
+        if tips.len() == 1 {
+            continue;
+        }
+        let mut table = term::Table::default();
+        for (oid, actor, timestamp, message) in unique.into_iter().rev() {
+            table.push([
+                term::format::dim(String::from("│")),
+                term::format::secondary(term::format::oid(oid)),
+                term::format::default(message),
+                term::format::dim(term::format::did(&Did::from(actor)).to_string()),
+                term::format::timestamp(&cob::Timestamp::new(timestamp)).italic(),
+            ]);
+        }
+        table.print();
+    }
+
+    let behind = remotes
+        .iter()
+        .filter(|(head, _)| !tips.contains(head))
+        .flat_map(|(_, r)| r.iter().map(remote))
+        .collect::<Vec<_>>();
+    if !behind.is_empty() {
+        term::blank();
+        term::print(format!(
+            "{} {}",
+            term::format::dim("Behind:"),
+            behind.join(", ")
+        ));
+    }
+    term::blank();
+    if tips.len() > 1 {
+        term::warning(&format!(
+            "{} heads have diverged; run `rad cob merge {id}` to merge them",
+            tips.len()
+        ));
+    } else {
+        term::success!("No diverged heads");
+    }
+    Ok(())
+}
+
+/// Print the objects of the given types, along with their operation count, the number of
+/// remotes they are stored under, their authors and last modification time.
+fn list(repo: &Repository, types: &[TypeName]) -> anyhow::Result<()> {
+    for (i, typename) in types.iter().enumerate() {
+        let remotes = remotes(repo, typename)?;
+        let mut objects = cob::list(repo, typename)?;
+        objects.sort_by_key(|o| std::cmp::Reverse(o.history().timestamp()));
+
+        if i > 0 {
+            term::blank();
+        }
+        term::print(format!(
+            "{} {}",
+            term::format::bold(typename),
+            term::format::dim(format!("({})", objects.len()))
+        ));
+        if objects.is_empty() {
+            continue;
+        }
+        let mut table = term::Table::default();
+
+        for object in objects {
+            let (ops, authors) =
+                object
+                    .history()
+                    .traverse((0, BTreeSet::new()), |(ops, mut authors), entry| {
+                        authors.insert(*entry.actor());
+                        ControlFlow::Continue((ops + 1, authors))
+                    });
+            let count = remotes.get(object.id()).map_or(0, |r| r.len());
+            let authors = authors
+                .into_iter()
+                .map(|a| term::format::did(&Did::from(a)).to_string())
+                .collect::<Vec<_>>();
+
+            table.push([
+                term::format::tertiary(term::format::cob(object.id())),
+                term::format::default(format!("{ops} op(s)")),
+                term::format::default(format!("{count} remote(s)")),
+                term::format::dim(authors.join(", ")),
+                term::format::timestamp(&cob::Timestamp::new(object.history().timestamp()))
+                    .italic(),
+            ]);
+        }
+        table.print();
+    }
+    Ok(())
+}
+
+/// Get the remotes that have a reference to each object of the given type.
+fn remotes(
+    repo: &Repository,
+    typename: &TypeName,
+) -> anyhow::Result<BTreeMap<ObjectId, BTreeSet<PublicKey>>> {
+    let mut remotes = BTreeMap::new();
+
+    for (id, objects) in repo.types(typename)? {
+        let entry: &mut BTreeSet<PublicKey> = remotes.entry(id).or_default();
+
+        for reference in objects.iter() {
+            if let Ok((remote, _)) =
+                radicle::git::parse_ref_namespaced::<PublicKey>(reference.name.as_str())
+            {
+                entry.insert(remote);
+            }
+        }
+    }
+    Ok(remotes)
+}
@@ -2,8 +2,7 @@ use std::ffi::OsString;
 
 use anyhow::anyhow;
 
-use radicle::cob::issue::Issues;
-use radicle::cob::patch::Patches;
+use radicle::api;
 use radicle::cob::store;
 use radicle::cob::thread;
 use radicle::prelude::*;
@@ -98,33 +97,22 @@ fn comment(
         return Ok(());
     }
 
-    let mut issues = Issues::open(repo)?;
     let id = options.id.resolve(&repo.backend)?;
-    match issues.get_mut(&id) {
-        Ok(mut issue) => {
-            let comment_id = options.reply_to.unwrap_or_else(|| {
-                let (comment_id, _) = issue.comments().next().expect("root comment always exists");
-                *comment_id
-            });
-            let comment_id = issue.comment(message, comment_id, &signer)?;
-
+    match api::issue::comment(repo, &id, &message, options.reply_to, &signer) {
+        Ok(comment_id) => {
             term::print(comment_id);
             return Ok(());
         }
-        Err(store::Error::NotFound(_, _)) => {}
+        Err(api::issue::Error::Store(store::Error::NotFound(_, _))) => {}
         Err(e) => return Err(e.into()),
     }
 
-    let mut patches = Patches::open(repo)?;
-    match patches.get_mut(&id) {
-        Ok(mut patch) => {
-            let (revision_id, _) = patch.revisions().last().expect("patch has a revision");
-            let comment_id = patch.comment(*revision_id, message, options.reply_to, &signer)?;
-
+    match api::patch::comment(repo, &id, &message, options.reply_to, &signer) {
+        Ok(comment_id) => {
             term::print(comment_id);
             return Ok(());
         }
-        Err(store::Error::NotFound(_, _)) => {}
+        Err(api::patch::Error::Store(store::Error::NotFound(_, _))) => {}
         Err(e) => return Err(e.into()),
     }
 
@@ -1,32 +1,59 @@
 use std::ffi::OsString;
 use std::path::Path;
 
-use anyhow::Context as _;
+use anyhow::{anyhow, Context as _};
 
 use radicle::prelude::Id;
 use radicle::rad;
+use radicle::storage::{ReadRepository as _, ReadStorage as _};
 
 use crate::terminal as term;
 use crate::terminal::args;
 use crate::terminal::args::{Args, Error, Help};
 
 pub const HELP: Help = Help {
-    name: "ls",
+    name: "fork",
     description: "Create a fork of a project",
     version: env!("CARGO_PKG_VERSION"),
     usage: r#"
 Usage
 
     rad fork [<rid>] [<option>...]
+    rad fork --remote-name <name> [--push] [<option>...]
+    rad fork [<rid>] --remove [--force] [--no-confirm] [<option>...]
+
+    With `--remote-name`, the command must be run from a working copy of the
+    project. In addition to creating the fork, a remote with the given name is
+    added to the working copy, pointing at our own namespace. If our fork
+    already exists, only the remote is added.
+
+    With `--push`, the current branch is then pushed to the new remote.
+
+    With `--remove`, our fork is removed from local storage instead, along
+    with our signed refs, so that it is no longer replicated from our node.
+    Nodes that already fetched the fork keep their copy. Delegates of the
+    project can't remove their fork. Since our issues, patches and comments
+    are part of our fork, a fork that has any is only removed with `--force`,
+    along with them.
 
 Options
 
-    --help          Print help
+    --remote-name <name>  Add a remote to the working copy pointing at the fork
+    --push                Push the current branch to the new remote
+    --remove              Remove our fork of the project from storage
+    --force               Remove the fork even if it has issues, patches or comments
+    --no-confirm          Don't ask for confirmation before removing the fork
+    --help                Print help
 "#,
 };
 
 pub struct Options {
     rid: Option<Id>,
+    remote: Option<String>,
+    push: bool,
+    remove: bool,
+    force: bool,
+    confirm: bool,
 }
 
 impl Args for Options {
@@ -35,20 +62,61 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut rid = None;
+        let mut remote = None;
+        let mut push = false;
+        let mut remove = false;
+        let mut force = false;
+        let mut confirm = true;
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Long("remote-name") => {
+                    let val = parser.value()?;
+
+                    remote = Some(args::string(&val));
+                }
+                Long("push") => {
+                    push = true;
+                }
+                Long("remove") => {
+                    remove = true;
+                }
+                Long("force") => {
+                    force = true;
+                }
+                Long("no-confirm") => {
+                    confirm = false;
+                }
                 Value(val) if rid.is_none() => {
                     rid = Some(args::rid(&val)?);
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
+        if push && remote.is_none() {
+            anyhow::bail!("`--push` can only be used with `--remote-name`");
+        }
+        if remove && remote.is_some() {
+            anyhow::bail!("`--remove` can't be used with `--remote-name`");
+        }
+        if force && !remove {
+            anyhow::bail!("`--force` can only be used with `--remove`");
+        }
 
-        Ok((Options { rid }, vec![]))
+        Ok((
+            Options {
+                rid,
+                remote,
+                push,
+                remove,
+                force,
+                confirm,
+            },
+            vec![],
+        ))
     }
 }
 
@@ -57,18 +125,83 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let signer = profile.signer()?;
     let storage = &profile.storage;
 
-    let rid = match options.rid {
-        Some(rid) => rid,
-        None => {
-            let (_, rid) = radicle::rad::repo(Path::new("."))
-                .context("Current directory is not a radicle project")?;
+    let Some(name) = options.remote else {
+        let rid = match options.rid {
+            Some(rid) => rid,
+            None => {
+                let (_, rid) = radicle::rad::repo(Path::new("."))
+                    .context("Current directory is not a radicle project")?;
 
-            rid
+                rid
+            }
+        };
+        if options.remove {
+            if options.force {
+                term::warning(
+                    "Your issues, patches and comments in this project will be removed along with your fork",
+                );
+            }
+            if !options.confirm || term::confirm(format!("Remove your fork of {rid}?")) {
+                match rad::unfork(rid, profile.id(), options.force, storage) {
+                    Err(rad::UnforkError::HasCobs(..)) => {
+                        return Err(Error::WithHint {
+                            err: anyhow!("your fork of {rid} has issues, patches or comments, which would be removed with it"),
+                            hint: "Use `--force` to remove them along with your fork.",
+                        }
+                        .into());
+                    }
+                    result => result?,
+                }
+                term::success!("Removed fork of project {rid} for {}", profile.id());
+            }
+            return Ok(());
         }
+        rad::fork(rid, &signer, &storage)?;
+        term::success!("Forked project {rid} for {}", profile.id());
+
+        return Ok(());
     };
 
-    rad::fork(rid, &signer, &storage)?;
-    term::success!("Forked project {rid} for {}", profile.id());
+    let (working, rid) =
+        radicle::rad::repo(Path::new(".")).context("Current directory is not a radicle project")?;
+    if let Some(expected) = options.rid.filter(|r| *r != rid) {
+        anyhow::bail!("the working copy is not a checkout of {expected}");
+    }
+    if working.find_remote(&name).is_ok() {
+        anyhow::bail!("remote `{name}` already exists in the working copy");
+    }
+
+    if storage.repository(rid)?.remote(profile.id()).is_ok() {
+        term::info!("Fork of {rid} already exists for {}", profile.id());
+    } else {
+        rad::fork(rid, &signer, &storage)?;
+        term::success!("Forked project {rid} for {}", profile.id());
+    }
+
+    let url = radicle::git::Url::from(rid).with_namespace(*profile.id());
+    radicle::git::configure_remote(&working, &name, &url, &url)?;
+    term::success!(
+        "Remote {} added, pointing at {}",
+        term::format::tertiary(&name),
+        term::format::tertiary(&url)
+    );
+
+    if options.push {
+        let head = working.head()?;
+        let Some(branch) = head.shorthand().filter(|_| head.is_branch()) else {
+            anyhow::bail!("cannot push: the working copy is not on a branch");
+        };
+        let workdir = working
+            .workdir()
+            .ok_or_else(|| anyhow!("cannot push from a bare repository"))?;
+
+        crate::git::git(workdir, ["push", name.as_str(), branch])?;
+        term::success!(
+            "Branch {} pushed to {}",
+            term::format::tertiary(branch),
+            term::format::tertiary(&name)
+        );
+    }
 
     Ok(())
 }
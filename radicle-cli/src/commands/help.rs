@@ -12,25 +12,57 @@ pub const HELP: Help = Help {
     usage: "Usage: rad help [--help]",
 };
 
-const COMMANDS: &[Help] = &[
+/// Options accepted by all commands, listed in the help output.
+const OPTIONS: &str = r#"Options accepted by all commands
+
+    --color <when>      When to use colors: `always`, `auto` or `never`. Colors are
+                        disabled when `NO_COLOR` is set, unless `always` is used.
+                        The color theme is set with `RAD_THEME`: `default`,
+                        `high-contrast` or `monochrome`.
+    --time <format>     How to show timestamps: `relative`, eg. "3 days ago", or
+                        `iso`, optionally with a timezone, eg. `iso:utc` or
+                        `iso:+02:00`. The default is set with `timeFormat` in the
+                        profile configuration.
+    --no-announce       Don't announce changes to the network. Otherwise, the
+                        repositories changed by a command are announced once,
+                        when the command is done.
+
+Long output, eg. of `rad patch show`, is displayed through the pager set with
+`RAD_PAGER` or `PAGER`, when the output is a terminal. Set it to `cat` or an empty
+string to disable paging.
+
+Errors are reported with a code, which is also the exit status of `rad`: `E1` when
+a command fails, `E2` on invalid usage, and `E3` when an object given on the
+command line, eg. a repository or patch, is not found.
+"#;
+
+/// Commands listed in the help output.
+pub const COMMANDS: &[Help] = &[
     rad_assign::HELP,
     rad_auth::HELP,
+    rad_block::HELP,
     rad_checkout::HELP,
+    rad_clean::HELP,
     rad_clone::HELP,
+    rad_cob::HELP,
     rad_edit::HELP,
     rad_fork::HELP,
     rad_help::HELP,
     rad_id::HELP,
     rad_init::HELP,
     rad_inspect::HELP,
+    rad_invite::HELP,
     rad_issue::HELP,
+    rad_label::HELP,
     rad_ls::HELP,
     rad_merge::HELP,
     rad_node::HELP,
+    rad_notes::HELP,
     rad_patch::HELP,
     rad_path::HELP,
     rad_review::HELP,
     rad_rm::HELP,
+    rad_search::HELP,
     rad_self::HELP,
     rad_tag::HELP,
     rad_track::HELP,
@@ -63,7 +95,7 @@ impl Args for Options {
 }
 
 pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    println!("Usage: rad <command> [--help]");
+    println!("Usage: rad [--color <when>] [--time <format>] [--no-announce] <command> [--help]");
 
     if ctx.profile().is_err() {
         println!();
@@ -91,6 +123,7 @@ pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     println!();
     println!("See `rad <command> --help` to learn about a specific command.");
     println!();
+    println!("{OPTIONS}");
 
     Ok(())
 }
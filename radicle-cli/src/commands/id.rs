@@ -1,13 +1,17 @@
+use std::collections::BTreeSet;
 use std::{ffi::OsString, str::FromStr as _};
 
 use anyhow::{anyhow, Context as _};
 
 use radicle::cob::identity::{self, Proposal, Proposals, Revision, RevisionId};
+use radicle::git;
 use radicle::git::Oid;
-use radicle::identity::Identity;
+use radicle::identity::{Identity, Visibility};
+use radicle::node::ConnectAddress;
 use radicle::prelude::{Did, Doc};
-use radicle::storage::ReadStorage as _;
-use radicle_crypto::Verified;
+use radicle::storage::git::Repository;
+use radicle::storage::{ReadRepository as _, ReadStorage as _};
+use radicle_crypto::{Signer, Verified};
 
 use crate::git::Rev;
 use crate::terminal as term;
@@ -29,6 +33,42 @@ Usage
     rad id rebase <id> [--rev <revision-id>] [<option>...]
     rad id show <id> [--rev <revision-id>] [--revisions] [<option>...]
     rad id (accept|reject|close|commit) [--rev <revision-id>] [--no-confirm] [<option>...]
+    rad id threshold [<id>] [--rev <revision-id>]
+                     [--delegates <did>] [--threshold <num>] [<option>...]
+    rad id handoff --to <did>... [--threshold <num>] [--seed <address>...]
+                   [--no-confirm] [<option>...]
+    rad id visibility [--private|--public] [--allow <did>...] [--disallow <did>...]
+                      [--no-confirm] [<option>...]
+    rad id rewrite [<commit>] [--no-confirm] [<option>...]
+
+    The `threshold` command is a dry-run: it checks whether the identity document
+    resulting from the given delegate and threshold change, or from the given
+    proposal, would still reach quorum with the refs currently published by its
+    delegates. Nothing is changed.
+
+    The `handoff` command guides the transfer of a project to a new set of
+    delegates. The new delegates are added first, then the previous delegates
+    are rescinded, and finally the default seeds of the project are set to the
+    given `<nid>@<addr>` addresses. Each step is an identity proposal that must
+    be accepted by the current delegates; when our own acceptance is enough, the
+    proposal is committed right away. Otherwise, run the command again once the
+    proposal is accepted to continue. A step that would leave the project unable
+    to reach quorum, eg. because the new delegates haven't published their
+    signed refs yet, is refused.
+
+    The `visibility` command shows whether the project is public or private, and
+    who is allowed to fetch it when it is private. With `--private` or `--public`,
+    or with `--allow` and `--disallow` to add or remove nodes from the allow-list,
+    the change is proposed as an identity revision, and committed when our own
+    acceptance is enough, like with `handoff`. Nodes only announce and serve a
+    private project to its delegates and to the nodes in its allow-list.
+
+    The `rewrite` command acknowledges a rewrite of the history of the
+    canonical branch, eg. after a delegate force-pushed it, to the given
+    commit, or to our own head of the default branch. Nodes don't adopt a
+    canonical head that doesn't descend from the previous one until such a
+    rewrite is acknowledged. Like with `handoff`, the acknowledgement is
+    proposed as an identity revision.
 
 Options
 
@@ -95,6 +135,25 @@ pub enum Operation {
     Close {
         id: Rev,
     },
+    Threshold {
+        id: Option<Rev>,
+        rev: Option<RevisionId>,
+        delegates: Vec<Did>,
+        threshold: Option<usize>,
+    },
+    Handoff {
+        to: Vec<Did>,
+        threshold: Option<usize>,
+        seeds: Vec<ConnectAddress>,
+    },
+    Visibility {
+        private: Option<bool>,
+        allow: Vec<Did>,
+        disallow: Vec<Did>,
+    },
+    Rewrite {
+        head: Option<Rev>,
+    },
 }
 
 #[derive(Default, PartialEq, Eq)]
@@ -109,6 +168,10 @@ pub enum OperationName {
     List,
     Commit,
     Close,
+    Threshold,
+    Handoff,
+    Visibility,
+    Rewrite,
 }
 
 pub struct Options {
@@ -127,6 +190,11 @@ impl Args for Options {
         let mut title: Option<String> = None;
         let mut description: Option<String> = None;
         let mut delegates: Vec<Did> = Vec::new();
+        let mut to: Vec<Did> = Vec::new();
+        let mut seeds: Vec<ConnectAddress> = Vec::new();
+        let mut private: Option<bool> = None;
+        let mut allow: Vec<Did> = Vec::new();
+        let mut disallow: Vec<Did> = Vec::new();
         let mut threshold: Option<usize> = None;
         let mut interactive = Interactive::Yes;
         let mut show_revisions = false;
@@ -155,6 +223,10 @@ impl Args for Options {
                     "r" | "reject" => op = Some(OperationName::Reject),
                     "commit" => op = Some(OperationName::Commit),
                     "close" => op = Some(OperationName::Close),
+                    "threshold" => op = Some(OperationName::Threshold),
+                    "handoff" => op = Some(OperationName::Handoff),
+                    "visibility" => op = Some(OperationName::Visibility),
+                    "rewrite" => op = Some(OperationName::Rewrite),
 
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
@@ -169,6 +241,32 @@ impl Args for Options {
                     let did = term::args::did(&parser.value()?)?;
                     delegates.push(did);
                 }
+                Long("to") if op == Some(OperationName::Handoff) => {
+                    let did = term::args::did(&parser.value()?)?;
+                    to.push(did);
+                }
+                Long("seed") if op == Some(OperationName::Handoff) => {
+                    let val = parser.value()?;
+                    let seed = val
+                        .to_string_lossy()
+                        .parse()
+                        .map_err(|e| anyhow!("invalid seed address {:?}: {e}", val))?;
+                    seeds.push(seed);
+                }
+                Long("private") if op == Some(OperationName::Visibility) => {
+                    private = Some(true);
+                }
+                Long("public") if op == Some(OperationName::Visibility) => {
+                    private = Some(false);
+                }
+                Long("allow") if op == Some(OperationName::Visibility) => {
+                    let did = term::args::did(&parser.value()?)?;
+                    allow.push(did);
+                }
+                Long("disallow") if op == Some(OperationName::Visibility) => {
+                    let did = term::args::did(&parser.value()?)?;
+                    disallow.push(did);
+                }
                 Long("threshold") => {
                     threshold = Some(parser.value()?.to_string_lossy().parse()?);
                 }
@@ -225,6 +323,28 @@ impl Args for Options {
             OperationName::Close => Operation::Close {
                 id: id.ok_or_else(|| anyhow!("a proposal must be provided"))?,
             },
+            OperationName::Threshold => Operation::Threshold {
+                id,
+                rev,
+                delegates,
+                threshold,
+            },
+            OperationName::Handoff => {
+                if to.is_empty() {
+                    anyhow::bail!("the new delegates must be given with `--to`");
+                }
+                Operation::Handoff {
+                    to,
+                    threshold,
+                    seeds,
+                }
+            }
+            OperationName::Visibility => Operation::Visibility {
+                private,
+                allow,
+                disallow,
+            },
+            OperationName::Rewrite => Operation::Rewrite { head: id },
         };
         Ok((Options { op, interactive }, vec![]))
     }
@@ -448,10 +568,498 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 }
             }
         }
+        Operation::Threshold {
+            id,
+            rev,
+            delegates,
+            threshold,
+        } => {
+            let proposed = match id {
+                Some(id) => {
+                    let id = id.resolve(&repo.backend)?;
+                    let proposal = proposals
+                        .get(&id)?
+                        .context("No proposal with the given ID exists")?;
+                    let revision = match rev {
+                        Some(rev) => proposal
+                            .revision(&rev)
+                            .context(format!("No revision found for {rev}"))?
+                            .get()
+                            .context(format!("Revision {rev} was redacted"))?,
+                        None => {
+                            proposal
+                                .latest()
+                                .context("No latest proposal revision to analyze")?
+                                .1
+                        }
+                    };
+                    revision.proposed.clone()
+                }
+                None => {
+                    let mut proposed = previous.doc.clone();
+                    proposed.threshold = threshold.unwrap_or(proposed.threshold);
+                    proposed.delegates.extend(delegates);
+                    proposed
+                }
+            };
+            if !analyze(&repo, &proposed)? {
+                anyhow::bail!("the proposed identity would not be able to reach quorum");
+            }
+        }
+        Operation::Handoff {
+            to,
+            threshold,
+            seeds,
+        } => {
+            handoff(
+                &repo,
+                &mut proposals,
+                &to,
+                threshold,
+                &seeds,
+                interactive,
+                &signer,
+            )?;
+        }
+        Operation::Visibility {
+            private,
+            allow,
+            disallow,
+        } => {
+            visibility(
+                &repo,
+                &mut proposals,
+                private,
+                &allow,
+                &disallow,
+                interactive,
+                &signer,
+            )?;
+        }
+        Operation::Rewrite { head } => {
+            rewrite(&repo, &mut proposals, head, interactive, &signer)?;
+        }
     }
     Ok(())
 }
 
+/// Run the next steps of the hand off of a project to the `to` delegates, until a step needs
+/// the acceptance of other delegates, or the hand off is complete.
+fn handoff<G: Signer>(
+    repo: &Repository,
+    proposals: &mut Proposals,
+    to: &[Did],
+    threshold: Option<usize>,
+    seeds: &[ConnectAddress],
+    interactive: &Interactive,
+    signer: &G,
+) -> anyhow::Result<()> {
+    if let Some(threshold) = threshold {
+        if threshold == 0 || threshold > to.len() {
+            anyhow::bail!(
+                "a threshold of {threshold} can never be reached with {} delegate(s)",
+                to.len()
+            );
+        }
+    }
+
+    loop {
+        let previous = Identity::load(signer.public_key(), repo)?;
+        let current = &previous.doc;
+        let added = to
+            .iter()
+            .filter(|did| !current.delegates.iter().any(|d| d == *did))
+            .cloned()
+            .collect::<Vec<_>>();
+        let rescinded = current
+            .delegates
+            .iter()
+            .filter(|did| !to.contains(did))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut proposed = current.clone();
+
+        let title = if !added.is_empty() {
+            // The threshold is only changed once the previous delegates are rescinded,
+            // so that the current delegates keep their quorum in the meantime.
+            proposed.delegates.extend(added);
+            term::info!("Adding the new delegates..");
+
+            "Add new delegates"
+        } else if !rescinded.is_empty() {
+            proposed.threshold = threshold.unwrap_or(current.threshold.min(to.len()));
+            for did in &rescinded {
+                proposed.rescind(did)?;
+            }
+            if !seeds.is_empty() {
+                proposed.set_seeds(seeds);
+            }
+            term::info!("Rescinding the previous delegates..");
+
+            "Rescind previous delegates"
+        } else if !seeds.is_empty() && current.seeds()? != seeds {
+            proposed.set_seeds(seeds);
+            term::info!("Updating the default seeds..");
+
+            "Update default seeds"
+        } else {
+            term::success!(
+                "Project is maintained by {} delegate(s), with a threshold of {}",
+                current.delegates.len(),
+                current.threshold
+            );
+            return Ok(());
+        };
+
+        if !analyze(repo, &proposed)? {
+            return Err(term::args::Error::WithHint {
+                err: anyhow!("the proposed identity would not be able to reach quorum"),
+                hint: "The new delegates must publish their signed refs, eg. with `rad fork`, before the previous delegates are rescinded.",
+            }
+            .into());
+        }
+        if !propose(
+            repo,
+            proposals,
+            &previous,
+            "id handoff",
+            &format!("Hand off: {}", title.to_lowercase()),
+            proposed,
+            interactive,
+            signer,
+        )? {
+            return Ok(());
+        }
+    }
+}
+
+/// Show the visibility of the project, or propose to change it. With `private` set, the
+/// project is made private or public, and `allow` and `disallow` edit the allow-list of a
+/// private project. Returns `true` if the resulting visibility is in effect, ie. it is
+/// unchanged or the proposal to change it was committed.
+pub fn visibility<G: Signer>(
+    repo: &Repository,
+    proposals: &mut Proposals,
+    private: Option<bool>,
+    allow: &[Did],
+    disallow: &[Did],
+    interactive: &Interactive,
+    signer: &G,
+) -> anyhow::Result<bool> {
+    let previous = Identity::load(signer.public_key(), repo)?;
+    let current = previous.doc.visibility()?;
+    let mut visibility = match private {
+        Some(true) if current.is_public() => Visibility::Private {
+            allow: BTreeSet::new(),
+        },
+        Some(false) => Visibility::Public,
+        _ => current.clone(),
+    };
+
+    match &mut visibility {
+        Visibility::Private { allow: list } => {
+            list.extend(allow.iter().copied());
+            for did in disallow {
+                list.remove(did);
+            }
+        }
+        Visibility::Public if !allow.is_empty() || !disallow.is_empty() => {
+            return Err(term::args::Error::WithHint {
+                err: anyhow!("the allow-list only applies to private projects"),
+                hint: "Make the project private with `--private`.",
+            }
+            .into());
+        }
+        Visibility::Public => {}
+    }
+    if visibility == current {
+        print_visibility(&current);
+        return Ok(true);
+    }
+
+    let title = match &visibility {
+        Visibility::Public => "Make project public",
+        Visibility::Private { .. } if current.is_public() => "Make project private",
+        Visibility::Private { .. } => "Update the allow-list",
+    };
+    let mut proposed = previous.doc.clone();
+    proposed.set_visibility(&visibility)?;
+
+    if propose(
+        repo,
+        proposals,
+        &previous,
+        "id visibility",
+        title,
+        proposed,
+        interactive,
+        signer,
+    )? {
+        print_visibility(&visibility);
+
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Acknowledge a rewrite of the history of the canonical branch to the given commit, or to our
+/// own head of the default branch, so that nodes adopt it.
+fn rewrite<G: Signer>(
+    repo: &Repository,
+    proposals: &mut Proposals,
+    head: Option<Rev>,
+    interactive: &Interactive,
+    signer: &G,
+) -> anyhow::Result<()> {
+    let previous = Identity::load(signer.public_key(), repo)?;
+    let head: Oid = match head {
+        Some(rev) => rev.resolve(&repo.backend)?,
+        None => {
+            let project = previous.doc.project()?;
+            let branch_ref = git::Qualified::from(git::lit::refs_heads(project.default_branch()));
+
+            repo.reference_oid(signer.public_key(), &branch_ref)?
+        }
+    };
+    let mut proposed = previous.doc.clone();
+
+    if !proposed.acknowledge_rewrite(head)? {
+        term::info!(
+            "Rewrite to {} is already acknowledged",
+            term::format::tertiary(term::format::oid(head))
+        );
+        return Ok(());
+    }
+    if propose(
+        repo,
+        proposals,
+        &previous,
+        "id rewrite",
+        &format!("Acknowledge rewrite to {head:.7}"),
+        proposed,
+        interactive,
+        signer,
+    )? {
+        term::success!(
+            "Rewrite to {} acknowledged",
+            term::format::tertiary(term::format::oid(head))
+        );
+    }
+    Ok(())
+}
+
+fn print_visibility(visibility: &Visibility) {
+    match visibility {
+        Visibility::Public => {
+            term::info!("Project is {}", term::format::positive("public"));
+        }
+        Visibility::Private { allow } if allow.is_empty() => {
+            term::info!(
+                "Project is {}, and can only be fetched by its delegates",
+                term::format::yellow("private")
+            );
+        }
+        Visibility::Private { allow } => {
+            term::info!(
+                "Project is {}, and can be fetched by its delegates, and:",
+                term::format::yellow("private")
+            );
+            for did in allow {
+                term::indented(term::format::did(did));
+            }
+        }
+    }
+}
+
+/// Propose an identity change made by the `rad <command>` command, or pick up the pending
+/// proposal for it, and commit it if it reaches quorum. Returns `true` if the proposal was
+/// committed.
+pub fn propose<G: Signer>(
+    repo: &Repository,
+    proposals: &mut Proposals,
+    previous: &Identity<Oid>,
+    command: &str,
+    title: &str,
+    proposed: Doc<Verified>,
+    interactive: &Interactive,
+    signer: &G,
+) -> anyhow::Result<bool> {
+    let mut pending = None;
+    for result in proposals.all()? {
+        let (id, proposal, _) = result?;
+        if *proposal.state() != identity::State::Open {
+            continue;
+        }
+        if let Some((_, revision)) = proposal.latest() {
+            if revision.current == previous.current && revision.proposed == proposed {
+                pending = Some(id);
+                break;
+            }
+        }
+    }
+
+    let mut proposal = match pending {
+        Some(id) => {
+            term::info!(
+                "Found pending identity proposal '{}'",
+                term::format::highlight(id)
+            );
+            proposals.get_mut(&id)?
+        }
+        None => {
+            if !confirm(interactive, "Are you sure you want to propose this change?") {
+                return Ok(false);
+            }
+            let proposal = proposals.create(
+                title,
+                format!("Proposed with `rad {command}`"),
+                previous.current,
+                proposed,
+                signer,
+            )?;
+            term::success!(
+                "Identity proposal '{}' created",
+                term::format::highlight(proposal.id)
+            );
+            proposal
+        }
+    };
+
+    let (rid, revision) = proposal
+        .latest()
+        .map(|(rid, revision)| (*rid, revision))
+        .context("No latest proposal revision to accept")?;
+    let did = Did::from(signer.public_key());
+
+    if previous.doc.is_delegate(signer.public_key()) && !revision.accepted().contains(&did) {
+        let (_, signature) = revision.proposed.sign(signer)?;
+        proposal.accept(rid, signature, signer)?;
+        term::success!("Accepted proposal ✓");
+    }
+
+    let (_, revision) = proposal
+        .latest()
+        .context("No latest proposal revision to commit")?;
+    if !revision.is_quorum_reached(previous) {
+        term::info!(
+            "The proposal was accepted by {} of the {} delegate(s) needed to commit it",
+            revision.accepted().len(),
+            previous.doc.threshold
+        );
+        term::tip!(
+            "Once it is accepted with `rad id accept {}`, run `rad {command}` again to continue",
+            proposal.id
+        );
+        return Ok(false);
+    }
+    if !confirm(interactive, "Are you sure you want to commit?") {
+        return Ok(false);
+    }
+    let id = Proposal::commit(&proposal, &rid, signer.public_key(), repo, signer)?;
+    proposal.commit(signer)?;
+    term::success!("Committed new identity '{}'", id.current);
+
+    Ok(true)
+}
+
+/// Simulate whether the proposed identity document would reach quorum, given the refs
+/// currently published by its delegates. Returns `false` if it wouldn't.
+fn analyze(repo: &Repository, proposed: &Doc<Verified>) -> anyhow::Result<bool> {
+    let project = proposed.project()?;
+    let branch = project.default_branch();
+    let branch_ref = git::Qualified::from(git::lit::refs_heads(branch));
+    let mut delegates = Vec::new();
+    for did in proposed.delegates.iter() {
+        if !delegates.contains(&did) {
+            delegates.push(did);
+        }
+    }
+
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+    t.push([
+        term::format::default(String::from("Delegate")),
+        term::format::default(String::from("Signed refs")),
+        term::format::default(branch.to_string()),
+    ]);
+    t.divider();
+
+    let mut active = 0;
+    let mut heads: Vec<git::raw::Oid> = Vec::new();
+    for did in &delegates {
+        let signed = repo.remote(did).is_ok();
+        let head = repo.reference_oid(did, &branch_ref).ok();
+
+        if signed {
+            active += 1;
+        }
+        if let Some(head) = head {
+            heads.push(head.into());
+        }
+        t.push([
+            term::format::did(did),
+            if signed {
+                term::format::positive(String::from("✓"))
+            } else {
+                term::format::negative(String::from("✗"))
+            },
+            head.map(|h| term::format::secondary(term::format::oid(h)))
+                .unwrap_or_else(|| term::format::dim(String::from("-"))),
+        ]);
+    }
+    t.print();
+
+    let threshold = proposed.threshold;
+    let mut bricked = false;
+
+    if threshold == 0 || threshold > delegates.len() {
+        term::error(format!(
+            "A threshold of {threshold} can never be reached with {} delegate(s)",
+            delegates.len()
+        ));
+        bricked = true;
+    } else if threshold > active {
+        term::error(format!(
+            "Only {active} of {} delegate(s) have published signed refs, below the threshold of {threshold}",
+            delegates.len()
+        ));
+        bricked = true;
+    }
+
+    if heads.len() < delegates.len() {
+        term::warning(&format!(
+            "The canonical head of '{branch}' can't be computed until all delegates publish it ({} missing)",
+            delegates.len() - heads.len()
+        ));
+    } else {
+        let head: Oid = match heads.as_slice() {
+            [head] => (*head).into(),
+            heads => repo.backend.merge_base_many(heads)?.into(),
+        };
+        match repo.canonical_head() {
+            Ok((_, current)) if current == head => {
+                term::info!(
+                    "The canonical head of '{branch}' would remain {}",
+                    term::format::secondary(term::format::oid(head))
+                );
+            }
+            _ => {
+                term::warning(&format!(
+                    "The canonical head of '{branch}' would change to {}",
+                    term::format::oid(head)
+                ));
+            }
+        }
+    }
+
+    if !bricked {
+        term::success!(
+            "The proposed identity would reach quorum ({threshold} of {} delegates)",
+            delegates.len()
+        );
+    }
+    Ok(!bricked)
+}
+
 fn warn_out_of_date(revision: &Revision, previous: &Identity<Oid>) {
     if revision.current != previous.current {
         term::warning("Revision is out of date");
@@ -1,18 +1,25 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::env;
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, time};
 
 use anyhow::{anyhow, bail, Context as _};
 use serde_json as json;
 
 use radicle::crypto::ssh;
 use radicle::git::RefString;
+use radicle::identity::doc::{Payload, PayloadId};
+use radicle::identity::Id;
 use radicle::node::tracking::Scope;
 use radicle::node::{Handle, NodeId};
 use radicle::profile;
+use radicle::storage::{ReadRepository as _, ReadStorage as _};
 
+use crate::commands::rad_sync as sync;
 use crate::git;
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -27,26 +34,40 @@ Usage
 
     rad init [<path>] [<option>...]
 
+    With `--template`, the project is set up like an existing repository, given
+    by its RID or the path to one of its working copies. The payloads of its
+    identity document, such as label definitions, are copied to the new project,
+    except for the project payload and the visibility. The files under `.radicle/`
+    in its default branch, such as the reviewers file and issue templates, are
+    copied to the working copy, unless they already exist, for you to commit.
+
 Options
 
         --name               Name of the project
         --description        Description of the project
         --default-branch     The default branch of the project
+        --template <rid|path>
+                             Set up the project like the given repository
     -u, --set-upstream       Setup the upstream of the default branch
         --setup-signing      Setup the radicle key as a signing key for this repository
-        --announce           Announce the new project to the network
+        --announce           Announce the new project to the network, and wait for
+                             the preferred seeds of the profile to sync it
         --no-confirm         Don't ask for confirmation during setup
     -v, --verbose            Verbose mode
         --help               Print help
 "#,
 };
 
+/// How long to wait for preferred seeds to sync a new project.
+pub const SYNC_TIMEOUT: time::Duration = time::Duration::from_secs(9);
+
 #[derive(Default)]
 pub struct Options {
     pub path: Option<PathBuf>,
     pub name: Option<String>,
     pub description: Option<String>,
     pub branch: Option<String>,
+    pub template: Option<String>,
     pub interactive: Interactive,
     pub setup_signing: bool,
     pub set_upstream: bool,
@@ -65,6 +86,7 @@ impl Args for Options {
         let mut name = None;
         let mut description = None;
         let mut branch = None;
+        let mut template = None;
         let mut interactive = Interactive::Yes;
         let mut set_upstream = false;
         let mut setup_signing = false;
@@ -106,6 +128,10 @@ impl Args for Options {
 
                     branch = Some(value);
                 }
+                Long("template") if template.is_none() => {
+                    let value = parser.value()?;
+                    template = Some(term::args::string(&value));
+                }
                 Long("set-upstream") | Short('u') => {
                     set_upstream = true;
                 }
@@ -140,10 +166,11 @@ impl Args for Options {
                 name,
                 description,
                 branch,
+                template,
                 interactive,
                 set_upstream,
                 setup_signing,
-                announce,
+                announce: announce && term::announce::is_enabled(),
                 track,
                 verbose,
             },
@@ -181,6 +208,11 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     }
 
     let signer = term::signer(profile)?;
+    let template = options
+        .template
+        .as_deref()
+        .map(|source| Template::load(source, profile))
+        .transpose()?;
     let head: String = repo
         .head()
         .ok()
@@ -208,17 +240,23 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     let mut spinner = term::spinner("Initializing...");
     let mut push_cmd = String::from("git push");
 
-    match radicle::rad::init(
+    match radicle::rad::init_with(
         &repo,
         &name,
         &description,
         branch,
+        template
+            .as_ref()
+            .map(|t| t.payload.clone())
+            .unwrap_or_default(),
         &signer,
         &profile.storage,
     ) {
         Ok((id, doc, _)) => {
             let proj = doc.project()?;
 
+            profile.checkouts()?.register(&id, &path)?;
+
             if options.track && node.is_running() {
                 // It's important to track our own repositories to make sure that our node signals
                 // interest for them. This ensures that messages relating to them are relayed to us.
@@ -231,6 +269,23 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
             ));
             spinner.finish();
 
+            if let Some(template) = &template {
+                let copied = template.copy(&path)?;
+
+                term::success!(
+                    "Project set up from template {} ({} payload(s), {} file(s))",
+                    term::format::tertiary(template.rid),
+                    template.payload.len(),
+                    copied.len()
+                );
+                for file in &copied {
+                    term::indented(term::format::dim(file.display()));
+                }
+                if !copied.is_empty() {
+                    term::info!("Commit the copied files to share them with the project");
+                }
+            }
+
             if options.verbose {
                 term::blob(json::to_string_pretty(&proj)?);
             }
@@ -262,12 +317,26 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
             }
 
             if options.announce {
+                let seeds = &profile.config.preferred_seeds;
+                if !seeds.is_empty() {
+                    if let Err(e) = sync::connect(&mut node, seeds) {
+                        term::warning(&format!("Failed to connect to preferred seeds: {e}"));
+                    }
+                }
+
                 let spinner = term::spinner("Announcing inventory..");
                 if let Err(e) = node.announce_inventory() {
                     spinner.error(e);
                 } else {
                     spinner.finish();
                 }
+
+                if !seeds.is_empty() {
+                    // Wait for our preferred seeds to fetch the new project.
+                    if let Err(e) = sync::announce(id, node.clone(), seeds, SYNC_TIMEOUT) {
+                        term::warning(&format!("Failed to sync with preferred seeds: {e}"));
+                    }
+                }
             }
 
             term::blank();
@@ -297,6 +366,96 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Directory of the project settings files that are copied from a template.
+pub const TEMPLATE_DIR: &str = ".radicle";
+
+/// A repository that a new project is set up like.
+struct Template {
+    /// Repository ID of the template.
+    rid: Id,
+    /// Identity document payloads, other than the project and visibility payloads.
+    payload: BTreeMap<PayloadId, Payload>,
+    /// Files under [`TEMPLATE_DIR`] in the default branch, by path relative to the
+    /// repository root.
+    files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Template {
+    /// Load a template from storage, given its RID or the path to one of its working copies.
+    fn load(source: &str, profile: &profile::Profile) -> anyhow::Result<Self> {
+        let rid = match Id::from_str(source) {
+            Ok(rid) => rid,
+            Err(_) => {
+                let (_, rid) = radicle::rad::repo(source).map_err(|_| {
+                    anyhow!(
+                        "template '{source}' is neither an RID, nor a working copy of a project"
+                    )
+                })?;
+                rid
+            }
+        };
+        let repo = profile
+            .storage
+            .repository(rid)
+            .with_context(|| format!("template {rid} was not found in storage"))?;
+        let (_, doc) = repo.identity_doc()?;
+        let payload = doc
+            .verified()?
+            .payload
+            .into_iter()
+            .filter(|(id, _)| *id != PayloadId::project() && *id != PayloadId::visibility())
+            .collect();
+
+        let (_, head) = repo.head()?;
+        let tree = repo.backend.find_commit(*head)?.tree()?;
+        let mut files = Vec::new();
+
+        if let Ok(entry) = tree.get_path(Path::new(TEMPLATE_DIR)) {
+            let dir = repo.backend.find_tree(entry.id())?;
+            let mut blobs = Vec::new();
+
+            dir.walk(radicle::git::raw::TreeWalkMode::PreOrder, |root, entry| {
+                if entry.kind() == Some(radicle::git::raw::ObjectType::Blob) {
+                    if let Some(name) = entry.name() {
+                        blobs.push((Path::new(TEMPLATE_DIR).join(root).join(name), entry.id()));
+                    }
+                }
+                radicle::git::raw::TreeWalkResult::Ok
+            })?;
+
+            for (path, oid) in blobs {
+                let blob = repo.backend.find_blob(oid)?;
+                files.push((path, blob.content().to_vec()));
+            }
+        }
+
+        Ok(Self {
+            rid,
+            payload,
+            files,
+        })
+    }
+
+    /// Copy the template files to a working copy, skipping those that already exist. Returns
+    /// the paths of the copied files.
+    fn copy(&self, workdir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let mut copied = Vec::new();
+
+        for (path, content) in &self.files {
+            let dest = workdir.join(path);
+            if dest.exists() {
+                continue;
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&dest, content)?;
+            copied.push(path.clone());
+        }
+        Ok(copied)
+    }
+}
+
 /// Setup radicle key as commit signing key in repository.
 pub fn setup_signing(
     node_id: &NodeId,
@@ -1,7 +1,7 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context as _};
@@ -11,7 +11,9 @@ use json_color::{Color, Colorizer};
 use radicle::crypto::{Unverified, Verified};
 use radicle::identity::Untrusted;
 use radicle::identity::{Doc, Id};
-use radicle::storage::{ReadRepository, ReadStorage};
+use radicle::storage::git::{Repository, NAMESPACES_GLOB};
+use radicle::storage::refs::{Refs, SignedRefs, REFS_BLOB_PATH, SIGREFS_BRANCH};
+use radicle::storage::{ReadRepository, ReadStorage, Remote, RemoteId};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -34,7 +36,8 @@ Options
 
     --id        Return the repository identifier (RID)
     --payload   Inspect the repository's identity payload
-    --refs      Inspect the repository's refs on the local device (requires `tree`)
+    --refs      Show the repository's refs on the local device, as a tree
+    --sigrefs   Show the signed refs of each remote, and whether they verify
     --history   Show the history of the repository identity document
     --help      Print help
 "#,
@@ -43,6 +46,7 @@ Options
 #[derive(Default, Debug, Eq, PartialEq)]
 pub enum Target {
     Refs,
+    Sigrefs,
     Payload,
     History,
     #[default]
@@ -71,6 +75,9 @@ impl Args for Options {
                 Long("refs") => {
                     target = Target::Refs;
                 }
+                Long("sigrefs") => {
+                    target = Target::Sigrefs;
+                }
                 Long("payload") => {
                     target = Target::Payload;
                 }
@@ -127,15 +134,22 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match options.target {
         Target::Refs => {
-            let path = storage.path_of(&id).join("refs").join("namespaces");
-
-            Command::new("tree")
-                .current_dir(path)
-                .args(["--noreport", "--prune"])
-                .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit())
-                .spawn()?
-                .wait()?;
+            let mut tree = RefTree::default();
+            let prefix = NAMESPACES_GLOB.as_str().trim_end_matches('*');
+
+            for r in repo.raw().references_glob(NAMESPACES_GLOB.as_str())? {
+                let r = r?;
+                if let Some(name) = r.name().and_then(|n| n.strip_prefix(prefix)) {
+                    tree.insert(name.split('/'));
+                }
+            }
+            println!(".");
+            tree.print("");
+        }
+        Target::Sigrefs => {
+            for remote in repo.remote_ids()? {
+                sigrefs(&remote?, &repo)?;
+            }
         }
         Target::Payload => {
             println!(
@@ -206,6 +220,97 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print the signed refs of a remote, along with their verification status, and how they
+/// compare to the refs in storage.
+fn sigrefs(remote: &RemoteId, repo: &Repository) -> anyhow::Result<()> {
+    let oid = repo.reference_oid(remote, &SIGREFS_BRANCH)?;
+
+    println!(
+        "{} {}",
+        term::format::yellow("remote"),
+        term::format::yellow(remote)
+    );
+    println!("sigrefs {oid}");
+
+    let (signed, status) = match SignedRefs::load_at(oid, *remote, repo) {
+        Ok(signed) => {
+            let status = match repo.validate_remote(&Remote::<Verified>::new(signed.clone())) {
+                Ok(_) => term::format::positive(String::from("verified")),
+                Err(e) => term::format::negative(format!("invalid: {e}")),
+            };
+            (signed.refs, status)
+        }
+        Err(e) => {
+            // Show what was signed anyway, to help figure out what went wrong.
+            let blob = repo.blob_at(oid, Path::new(REFS_BLOB_PATH))?;
+            let refs = Refs::from_canonical(blob.content())?;
+
+            (refs, term::format::negative(format!("invalid: {e}")))
+        }
+    };
+    println!("status  {status}");
+    let mut stored = BTreeMap::from(repo.references_of(remote)?);
+    stored.remove(&SIGREFS_BRANCH.to_ref_string());
+
+    let mut table = term::Table::default();
+    for (name, signed) in signed.iter() {
+        let (oid, status) = match stored.remove(name) {
+            Some(oid) if oid == *signed => (oid, term::format::dim(String::from("ok"))),
+            Some(oid) => (
+                oid,
+                term::format::negative(format!("expected {}", term::format::oid(*signed))),
+            ),
+            None => (*signed, term::format::negative(String::from("missing"))),
+        };
+        table.push([
+            term::format::default(name.to_string()),
+            term::format::secondary(oid.to_string()),
+            status,
+        ]);
+    }
+    // Refs left in storage are the ones that weren't signed.
+    for (name, oid) in stored {
+        table.push([
+            term::format::default(name.to_string()),
+            term::format::secondary(oid.to_string()),
+            term::format::yellow(String::from("unsigned")),
+        ]);
+    }
+    println!();
+    table.print();
+    println!();
+
+    Ok(())
+}
+
+/// Tree of reference names, printed like the `tree` utility does.
+#[derive(Debug, Default)]
+struct RefTree(BTreeMap<String, RefTree>);
+
+impl RefTree {
+    /// Insert a reference, given its name components.
+    fn insert<'a>(&mut self, mut components: impl Iterator<Item = &'a str>) {
+        if let Some(c) = components.next() {
+            self.0.entry(c.to_owned()).or_default().insert(components);
+        }
+    }
+
+    /// Print the tree, with each line starting with the given prefix.
+    fn print(&self, prefix: &str) {
+        let mut entries = self.0.iter().peekable();
+
+        while let Some((name, subtree)) = entries.next() {
+            let (branch, indent) = if entries.peek().is_some() {
+                ("|-- ", "|   ")
+            } else {
+                ("`-- ", "    ")
+            };
+            println!("{prefix}{branch}{name}");
+            subtree.print(&format!("{prefix}{indent}"));
+        }
+    }
+}
+
 // Used for JSON Colorizing
 fn colorizer() -> Colorizer {
     Colorizer::new()
@@ -0,0 +1,197 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::cob::identity::Proposals;
+use radicle::identity::{Identity, Visibility};
+use radicle::node;
+use radicle::node::{ConnectAddress, Handle as _, Node, Signal};
+use radicle::prelude::{Did, Id};
+use radicle::storage::ReadStorage as _;
+
+use crate::commands::rad_id as id;
+use crate::project;
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+use crate::terminal::Interactive;
+
+pub const HELP: Help = Help {
+    name: "invite",
+    description: "Invite a peer to a private repository",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad invite <did> [--repo <rid>] [--seed <address>...] [<option>...]
+
+    Adds the given peer to the allow-list of a private repository, so that
+    their node may fetch it, and prints the command they can run to clone
+    it. This is the same as `rad id visibility --allow <did>`: if the
+    repository has other delegates, the invitation only takes effect once
+    they have accepted the identity proposal.
+
+    If the peer's node is connected to ours, it is notified of the
+    invitation with a direct signal, so that it can fetch the repository
+    right away.
+
+    The clone command points at the seeds given with `--seed`, in the form
+    `<nid>@<addr>`, or at our own node if none are given.
+
+Options
+
+    --repo <rid>        Repository to invite the peer to (default: cwd)
+    --seed <address>    Seed the peer should clone from (may be specified multiple times)
+    --no-notify         Don't notify the peer's node of the invitation
+    --no-confirm        Don't ask for confirmation
+    --help              Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub did: Did,
+    pub repo: Option<Id>,
+    pub seeds: Vec<ConnectAddress>,
+    pub notify: bool,
+    pub interactive: Interactive,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut did: Option<Did> = None;
+        let mut repo = None;
+        let mut seeds = Vec::new();
+        let mut notify = true;
+        let mut interactive = Interactive::Yes;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("repo") => {
+                    repo = Some(term::args::rid(&parser.value()?)?);
+                }
+                Long("seed") => {
+                    let val = parser.value()?;
+                    let seed = val
+                        .to_string_lossy()
+                        .parse()
+                        .map_err(|e| anyhow!("invalid seed address {:?}: {e}", val))?;
+                    seeds.push(seed);
+                }
+                Long("no-notify") => {
+                    notify = false;
+                }
+                Long("no-confirm") => {
+                    interactive = Interactive::No;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if did.is_none() => {
+                    did = Some(term::args::did(&val)?);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let did = did.ok_or_else(|| anyhow!("a DID must be provided; see `rad invite --help`"))?;
+
+        Ok((
+            Options {
+                did,
+                repo,
+                seeds,
+                notify,
+                interactive,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let (_, rid) = project::resolve(options.repo)?;
+    let repo = profile.storage.repository(rid)?;
+    let doc = Identity::load(signer.public_key(), &repo)?.doc;
+    let did = options.did;
+
+    if doc.is_delegate(did.as_key()) {
+        term::info!(
+            "{} is a delegate of this repository, and can already fetch it",
+            term::format::did(&did)
+        );
+    } else {
+        match doc.visibility()? {
+            Visibility::Public => {
+                term::info!(
+                    "Repository is {}, anyone can fetch it",
+                    term::format::positive("public")
+                );
+            }
+            Visibility::Private { allow } if allow.contains(&did) => {
+                term::info!("{} was already invited", term::format::did(&did));
+            }
+            Visibility::Private { .. } => {
+                let mut proposals = Proposals::open(&repo)?;
+
+                if !id::visibility(
+                    &repo,
+                    &mut proposals,
+                    None,
+                    &[did],
+                    &[],
+                    &options.interactive,
+                    &signer,
+                )? {
+                    term::info!("The invitation takes effect once the other delegates accept it");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    if options.notify {
+        let mut node = Node::new(profile.socket());
+        if node.is_running() && node.sessions()?.contains(did.as_key()) {
+            let mut spinner = term::spinner(format!(
+                "Notifying {} of the invitation..",
+                term::format::did(&did)
+            ));
+            match node.signal(*did.as_key(), Signal::Invite { rid }) {
+                Ok(true) => spinner.finish(),
+                Ok(false) => spinner.failed(),
+                Err(e) => spinner.error(e),
+            }
+        }
+    }
+
+    let seeds = if options.seeds.is_empty() {
+        vec![format!("{}@<host>:{}", profile.id(), node::DEFAULT_PORT)]
+    } else {
+        options.seeds.iter().map(|s| s.to_string()).collect()
+    };
+    let seeds = seeds
+        .iter()
+        .map(|s| format!("--seed {s}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    term::blank();
+    term::info!("To join, {} can run:", term::format::did(&did));
+    term::indented(term::format::secondary(format!(
+        "rad clone {} {seeds}",
+        rid.urn()
+    )));
+    if options.seeds.is_empty() {
+        term::blank();
+        term::info!(
+            "Replace {} with the public address of your node.",
+            term::format::dim("<host>")
+        );
+    }
+    Ok(())
+}
@@ -4,21 +4,28 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Context as _};
 
+use radicle::cob::cache::IssueSummary;
 use radicle::cob::common::{Reaction, Tag};
 use radicle::cob::issue;
 use radicle::cob::issue::{CloseReason, Issues, State};
-use radicle::node::Handle;
-use radicle::prelude::Did;
+use radicle::cob::thread;
+use radicle::prelude::{Did, Id};
+use radicle::profile::Config;
 use radicle::storage::WriteStorage;
-use radicle::{cob, Node};
+use radicle::{cob, Profile};
 use radicle_term::table::TableOptions;
 use radicle_term::{Paint, Table, VStack};
 
 use crate::git::Rev;
+use crate::project;
 use crate::terminal as term;
 use crate::terminal::args::{string, Args, Error, Help};
+use crate::terminal::patch::Message;
 use crate::terminal::Element;
 
+/// Period before their due date during which assigned issues are reminded, in seconds.
+pub const REMINDER_PERIOD: u64 = 60 * 60 * 24 * 3;
+
 pub const HELP: Help = Help {
     name: "issue",
     description: "Manage issues",
@@ -27,21 +34,86 @@ pub const HELP: Help = Help {
 Usage
 
     rad issue [<option>...]
+    rad issue assign <issue-id>... [--to <did>...] [--from <did>...] [<option>...]
+    rad issue board [--all] [<option>...]
+    rad issue comment <issue-id> [--message <text>] [--reply-to <comment-id>] [<option>...]
     rad issue delete <issue-id> [<option>...]
-    rad issue list [--assigned <did>] [<option>...]
+    rad issue due <issue-id> [<date> | --clear] [<option>...]
+    rad issue edit <issue-id> [--title <title>] [--description <text>] [<option>...]
+    rad issue list [--assigned <did>] [--open | --closed] [--overdue] [<option>...]
+    rad issue move <issue-id> <column> [<option>...]
     rad issue open [--title <title>] [--description <text>] [--tag <tag>] [<option>...]
     rad issue react <issue-id> [--emoji <char>] [<option>...]
-    rad issue show <issue-id> [<option>...]
-    rad issue state <issue-id> [--closed | --open | --solved] [<option>...]
+    rad issue show [<issue-id>] [<option>...]
+    rad issue state <issue-id>... [--closed | --open | --solved] [<option>...]
+
+    When run from a terminal, `show` prompts for an issue if none is given.
+
+    The `comment` command comments on an issue, or replies to one of its comments
+    with `--reply-to`. The editor is opened when no message is given. Comments are
+    shown below the issue description by `show`, with replies under the comment
+    they reply to.
+
+    The `edit` command opens the editor with the current title and description of
+    the issue, unless a new title or description is given on the command line.
+
+    The `assign` and `state` commands can be given several issues, eg. to triage
+    them in one go. All issues are checked to exist before any of them is changed,
+    and the changes are announced once, at the end.
+    Outside of a working copy, the repository must be given with `--repo`.
+
+    The `board` command shows open issues grouped by column. Columns are defined in
+    the `xyz.radicle.board` payload of the repository identity, and default to
+    `triage`, `in-progress` and `done`. Issues are moved between columns with `move`.
+
+    Due dates are given as `YYYY-MM-DD`, and issues are due by the end of that day,
+    in UTC. When listing issues, open issues assigned to you are reminded when they
+    are due within three days.
+
+    List options can be saved as named queries, either in the `queries` of the
+    profile configuration, or in the `rad.query.<name>` git configuration of the
+    working copy. A saved query is used by passing its name prefixed with `@`,
+    eg. `rad issue list @mine`.
+
+List options
+
+    --assigned [<did>|me]   Only show issues assigned to the given DID (default: me)
+    --open                  Only show open issues
+    --closed                Only show closed issues
+    --overdue               Only show open issues past their due date
+
+Assign options
+
+    --to <did>              Assign the given DID to the issues (may be repeated)
+    --from <did>            Unassign the given DID from the issues (may be repeated)
+
+Board options
+
+    --all             Also show closed issues
+
+Comment options
+
+    --message, -m <text>        Comment message (may be repeated)
+    --reply-to <comment-id>     Reply to the given comment (default: the issue)
 
 Options
 
+    --repo <rid>      Operate on the given repository (default: cwd)
     --no-announce     Don't announce issue to peers
     --quiet, -q       Don't print anything
     --help            Print help
 "#,
 };
 
+/// Help message shown when editing an issue.
+const EDIT_MSG: &str = r#"
+<!--
+Please edit the issue title and description. The first line is
+the title, and the description follows after a blank line.
+Markdown is supported.
+-->
+"#;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Metadata {
     title: String,
@@ -52,9 +124,15 @@ pub struct Metadata {
 #[derive(Default, Debug, PartialEq, Eq)]
 pub enum OperationName {
     Open,
+    Assign,
+    Board,
+    Comment,
     Delete,
+    Due,
+    Edit,
     #[default]
     List,
+    Move,
     React,
     Show,
     State,
@@ -76,28 +154,56 @@ pub enum Operation {
         tags: Vec<Tag>,
     },
     Show {
-        id: Rev,
+        id: Option<Rev>,
     },
     State {
-        id: Rev,
+        ids: Vec<Rev>,
         state: State,
     },
+    Assign {
+        ids: Vec<Rev>,
+        to: Vec<Did>,
+        from: Vec<Did>,
+    },
+    Comment {
+        id: Rev,
+        message: Message,
+        reply_to: Option<thread::CommentId>,
+    },
     Delete {
         id: Rev,
     },
+    Edit {
+        id: Rev,
+        title: Option<String>,
+        description: Option<String>,
+    },
     React {
         id: Rev,
         reaction: Reaction,
     },
     List {
         assigned: Option<Assigned>,
+        state: Option<State>,
+        overdue: bool,
+    },
+    Board {
+        all: bool,
+    },
+    Move {
+        id: Rev,
+        column: issue::Column,
+    },
+    Due {
+        id: Rev,
+        due: Option<cob::Timestamp>,
     },
 }
 
 #[derive(Debug)]
 pub struct Options {
     pub op: Operation,
-    pub announce: bool,
+    pub repo: Option<Id>,
     pub quiet: bool,
 }
 
@@ -105,16 +211,27 @@ impl Args for Options {
     fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
         use lexopt::prelude::*;
 
+        let args = term::query::expand(args)?;
         let mut parser = lexopt::Parser::from_args(args);
         let mut op: Option<OperationName> = None;
         let mut id: Option<Rev> = None;
+        let mut ids: Vec<Rev> = Vec::new();
+        let mut to: Vec<Did> = Vec::new();
+        let mut from: Vec<Did> = Vec::new();
         let mut assigned: Option<Assigned> = None;
         let mut title: Option<String> = None;
         let mut reaction: Option<Reaction> = None;
+        let mut message = Message::default();
+        let mut reply_to: Option<thread::CommentId> = None;
         let mut description: Option<String> = None;
         let mut state: Option<State> = None;
+        let mut column: Option<issue::Column> = None;
+        let mut all = false;
+        let mut overdue = false;
+        let mut due: Option<cob::Timestamp> = None;
+        let mut clear = false;
         let mut tags = Vec::new();
-        let mut announce = true;
+        let mut repo = None;
         let mut quiet = false;
 
         while let Some(arg) = parser.next()? {
@@ -122,7 +239,9 @@ impl Args for Options {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
-                Long("title") if op == Some(OperationName::Open) => {
+                Long("title")
+                    if op == Some(OperationName::Open) || op == Some(OperationName::Edit) =>
+                {
                     title = Some(parser.value()?.to_string_lossy().into());
                 }
                 Long("tag") if op == Some(OperationName::Open) => {
@@ -132,6 +251,14 @@ impl Args for Options {
 
                     tags.push(tag);
                 }
+                Long("open") if op.is_none() || op == Some(OperationName::List) => {
+                    state = Some(State::Open);
+                }
+                Long("closed") if op.is_none() || op == Some(OperationName::List) => {
+                    state = Some(State::Closed {
+                        reason: CloseReason::Other,
+                    });
+                }
                 Long("closed") if op == Some(OperationName::State) => {
                     state = Some(State::Closed {
                         reason: CloseReason::Other,
@@ -145,39 +272,90 @@ impl Args for Options {
                         reason: CloseReason::Solved,
                     });
                 }
+                Long("to") if op == Some(OperationName::Assign) => {
+                    to.push(term::args::did(&parser.value()?)?);
+                }
+                Long("from") if op == Some(OperationName::Assign) => {
+                    from.push(term::args::did(&parser.value()?)?);
+                }
+                Long("all") if op == Some(OperationName::Board) => {
+                    all = true;
+                }
+                Long("overdue") if op.is_none() || op == Some(OperationName::List) => {
+                    overdue = true;
+                }
+                Long("clear") if op == Some(OperationName::Due) => {
+                    clear = true;
+                }
+                Long("message") | Short('m') if op == Some(OperationName::Comment) => {
+                    let txt: String = parser.value()?.to_string_lossy().into();
+                    message.append(&txt);
+                }
+                Long("reply-to") if op == Some(OperationName::Comment) => {
+                    let val = parser.value()?;
+                    let val = string(&val);
+
+                    reply_to = Some(
+                        val.parse()
+                            .map_err(|_| anyhow!("invalid comment id `{val}`"))?,
+                    );
+                }
                 Long("emoji") if op == Some(OperationName::React) => {
                     if let Some(emoji) = parser.value()?.to_str() {
                         reaction =
                             Some(Reaction::from_str(emoji).map_err(|_| anyhow!("invalid emoji"))?);
                     }
                 }
-                Long("description") if op == Some(OperationName::Open) => {
+                Long("description")
+                    if op == Some(OperationName::Open) || op == Some(OperationName::Edit) =>
+                {
                     description = Some(parser.value()?.to_string_lossy().into());
                 }
                 Long("assigned") | Short('a') if assigned.is_none() => {
                     if let Ok(val) = parser.value() {
-                        let peer = term::args::did(&val)?;
-                        assigned = Some(Assigned::Peer(peer));
+                        if val == "me" {
+                            assigned = Some(Assigned::Me);
+                        } else {
+                            let peer = term::args::did(&val)?;
+                            assigned = Some(Assigned::Peer(peer));
+                        }
                     } else {
                         assigned = Some(Assigned::Me);
                     }
                 }
-                Long("no-announce") => {
-                    announce = false;
+                Long("repo") => {
+                    repo = Some(term::args::rid(&parser.value()?)?);
                 }
                 Long("quiet") | Short('q') => {
                     quiet = true;
                 }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "a" | "assign" => op = Some(OperationName::Assign),
+                    "b" | "board" => op = Some(OperationName::Board),
                     "c" | "show" => op = Some(OperationName::Show),
+                    "comment" => op = Some(OperationName::Comment),
                     "d" | "delete" => op = Some(OperationName::Delete),
+                    "due" => op = Some(OperationName::Due),
+                    "e" | "edit" => op = Some(OperationName::Edit),
                     "l" | "list" => op = Some(OperationName::List),
+                    "m" | "move" => op = Some(OperationName::Move),
                     "o" | "open" => op = Some(OperationName::Open),
                     "r" | "react" => op = Some(OperationName::React),
                     "s" | "state" => op = Some(OperationName::State),
 
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
+                Value(val) if op == Some(OperationName::Move) && id.is_some() => {
+                    column = Some(issue::Column::new(string(&val))?);
+                }
+                Value(val) if op == Some(OperationName::Due) && id.is_some() => {
+                    due = Some(term::args::date(&val)?);
+                }
+                Value(val)
+                    if op == Some(OperationName::State) || op == Some(OperationName::Assign) =>
+                {
+                    ids.push(Rev::from(string(&val)));
+                }
                 Value(val) if op.is_some() => {
                     let val = string(&val);
                     id = Some(Rev::from(val));
@@ -194,49 +372,85 @@ impl Args for Options {
                 description,
                 tags,
             },
-            OperationName::Show => Operation::Show {
-                id: id.ok_or_else(|| anyhow!("an issue must be provided"))?,
-            },
-            OperationName::State => Operation::State {
-                id: id.ok_or_else(|| anyhow!("an issue must be provided"))?,
-                state: state.ok_or_else(|| anyhow!("a state operation must be provided"))?,
-            },
+            OperationName::Show => Operation::Show { id },
+            OperationName::State => {
+                if ids.is_empty() {
+                    anyhow::bail!("an issue must be provided");
+                }
+                Operation::State {
+                    ids,
+                    state: state.ok_or_else(|| anyhow!("a state operation must be provided"))?,
+                }
+            }
+            OperationName::Assign => {
+                if ids.is_empty() {
+                    anyhow::bail!("an issue must be provided");
+                }
+                if to.is_empty() && from.is_empty() {
+                    anyhow::bail!("an assignee must be provided with `--to` or `--from`");
+                }
+                Operation::Assign { ids, to, from }
+            }
             OperationName::React => Operation::React {
                 id: id.ok_or_else(|| anyhow!("an issue must be provided"))?,
                 reaction: reaction.ok_or_else(|| anyhow!("a reaction emoji must be provided"))?,
             },
+            OperationName::Comment => Operation::Comment {
+                id: id.ok_or_else(|| anyhow!("an issue to comment on must be provided"))?,
+                message,
+                reply_to,
+            },
             OperationName::Delete => Operation::Delete {
                 id: id.ok_or_else(|| anyhow!("an issue to remove must be provided"))?,
             },
-            OperationName::List => Operation::List { assigned },
+            OperationName::Edit => Operation::Edit {
+                id: id.ok_or_else(|| anyhow!("an issue to edit must be provided"))?,
+                title,
+                description,
+            },
+            OperationName::List => Operation::List {
+                assigned,
+                state,
+                overdue,
+            },
+            OperationName::Board => Operation::Board { all },
+            OperationName::Due => Operation::Due {
+                id: id.ok_or_else(|| anyhow!("an issue must be provided"))?,
+                due: match (due, clear) {
+                    (Some(_), true) => anyhow::bail!("a date can't be given with `--clear`"),
+                    (Some(due), false) => Some(due),
+                    (None, true) => None,
+                    (None, false) => anyhow::bail!("a due date or `--clear` must be provided"),
+                },
+            },
+            OperationName::Move => Operation::Move {
+                id: id.ok_or_else(|| anyhow!("an issue must be provided"))?,
+                column: column.ok_or_else(|| anyhow!("a column must be provided"))?,
+            },
         };
 
-        Ok((
-            Options {
-                op,
-                announce,
-                quiet,
-            },
-            vec![],
-        ))
+        Ok((Options { op, repo, quiet }, vec![]))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
-    let (_, rid) = radicle::rad::cwd()?;
+    let (_, rid) = project::resolve(options.repo)?;
     let repo = profile.storage.repository_mut(rid)?;
-    let announce = options.announce
-        && matches!(
-            &options.op,
-            Operation::Open { .. }
-                | Operation::React { .. }
-                | Operation::State { .. }
-                | Operation::Delete { .. }
-        );
+    let announce = matches!(
+        &options.op,
+        Operation::Open { .. }
+            | Operation::Comment { .. }
+            | Operation::React { .. }
+            | Operation::State { .. }
+            | Operation::Assign { .. }
+            | Operation::Move { .. }
+            | Operation::Due { .. }
+            | Operation::Delete { .. }
+            | Operation::Edit { .. }
+    );
 
-    let mut node = Node::new(profile.socket());
     let mut issues = Issues::open(&repo)?;
 
     match options.op {
@@ -245,27 +459,90 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             description: Some(description),
             tags,
         } => {
+            if !options.quiet {
+                check_duplicates(&profile, &repo, &title)?;
+            }
             let issue = issues.create(title, description, tags.as_slice(), &[], &signer)?;
             if !options.quiet {
-                show_issue(&issue)?;
+                show_issue(&issue, &profile.config)?;
             }
         }
         Operation::Show { id } => {
-            let id = id.resolve(&repo.backend)?;
+            let id = match id {
+                Some(id) => match id.resolve(&repo.backend) {
+                    Ok(id) => id,
+                    Err(err) if err.code() == radicle::git::raw::ErrorCode::NotFound => {
+                        let ids = issues
+                            .all()?
+                            .filter_map(|i| i.ok().map(|(id, _, _)| id.to_string()))
+                            .collect::<Vec<_>>();
+                        let ids = ids.iter().map(String::as_str);
+
+                        return Err(term::args::not_found("issue", id.as_str(), ids).into());
+                    }
+                    Err(err) => return Err(err.into()),
+                },
+                None => {
+                    let mut choices = Vec::new();
+                    for result in issues.all()? {
+                        let (id, issue, _) = result?;
+                        choices.push((id, issue.title().to_owned()));
+                    }
+                    term::io::cob_select("Which issue?", choices, "an issue must be provided")?
+                }
+            };
             let issue = issues
                 .get(&id)?
                 .context("No issue with the given ID exists")?;
-            show_issue(&issue)?;
+            show_issue(&issue, &profile.config)?;
+            term::io::rejections(&issues.rejected(&id)?.unwrap_or_default());
         }
-        Operation::State { id, state } => {
+        Operation::State { ids, state } => {
+            for id in resolve(&ids, &repo, &issues)? {
+                let mut issue = issues.get_mut(&id)?;
+                issue.lifecycle(state, &signer)?;
+            }
+        }
+        Operation::Assign { ids, to, from } => {
+            for id in resolve(&ids, &repo, &issues)? {
+                let mut issue = issues.get_mut(&id)?;
+                issue.transaction("Assign", &signer, |tx| {
+                    tx.assign(
+                        to.iter().copied().map(cob::ActorId::from),
+                        from.iter().copied().map(cob::ActorId::from),
+                    )
+                })?;
+            }
+        }
+        Operation::Comment {
+            id,
+            message,
+            reply_to,
+        } => {
             let id = id.resolve(&repo.backend)?;
             let mut issue = issues.get_mut(&id)?;
-            issue.lifecycle(state, &signer)?;
+            let reply_to = match reply_to {
+                Some(reply_to) if (*issue).thread().comment(&reply_to).is_none() => {
+                    anyhow::bail!("comment `{reply_to}` not found on issue {id}");
+                }
+                Some(reply_to) => reply_to,
+                None => *issue.root().0,
+            };
+            let message = message.get("Enter a comment...")?;
+            if message.is_empty() {
+                anyhow::bail!("a comment message must be provided");
+            }
+            let comment_id = issue.comment(message, reply_to, &signer)?;
+
+            if !options.quiet {
+                term::print(comment_id);
+            }
         }
         Operation::React { id, reaction } => {
             let id = id.resolve(&repo.backend)?;
             if let Ok(mut issue) = issues.get_mut(&id) {
-                let (comment_id, _) = term::io::comment_select(&issue).unwrap();
+                let (comment_id, _) = term::io::comment_select(&issue, &profile.config)
+                    .ok_or_else(|| anyhow!("a comment to react to must be selected"))?;
                 issue.react(*comment_id, reaction, &signer)?;
             }
         }
@@ -310,6 +587,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 let meta: Metadata =
                     serde_yaml::from_str(&meta).context("failed to parse yaml front-matter")?;
 
+                if !options.quiet {
+                    check_duplicates(&profile, &repo, &meta.title)?;
+                }
                 let issue = issues.create(
                     &meta.title,
                     description.trim(),
@@ -322,15 +602,23 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     &signer,
                 )?;
                 if !options.quiet {
-                    show_issue(&issue)?;
+                    show_issue(&issue, &profile.config)?;
                 }
             }
         }
-        Operation::List { assigned } => {
-            if issues.is_empty()? {
+        Operation::List {
+            assigned,
+            state: filter,
+            overdue,
+        } => {
+            let summaries = profile.cobs()?.issues(&repo)?;
+            if summaries.is_empty() {
                 term::print(term::format::italic("Nothing to show."));
                 return Ok(());
             }
+            let now = cob::Timestamp::now();
+
+            remind(&summaries, &profile, now);
 
             let assignee = match assigned {
                 Some(Assigned::Me) => Some(*profile.id()),
@@ -350,10 +638,23 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             ]);
             t.divider();
 
-            for result in issues.all()? {
-                let (id, issue, _) = result?;
-                let assigned: Vec<_> = issue.assigned().collect();
-                let state = issue.state();
+            for (id, issue) in summaries {
+                let assigned = issue.assignees;
+                let state = issue.state;
+
+                if profile.config.is_blocked(&issue.author) {
+                    continue;
+                }
+
+                if let Some(filter) = filter {
+                    if matches!(filter, State::Open) != matches!(state, State::Open) {
+                        continue;
+                    }
+                }
+
+                if overdue && !issue.is_overdue(now) {
+                    continue;
+                }
 
                 if Some(true) == assignee.map(|a| !assigned.contains(&Did::from(a))) {
                     continue;
@@ -365,8 +666,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     .collect::<Vec<_>>()
                     .join(", ");
 
-                let mut tags = issue.tags().map(|t| t.to_string()).collect::<Vec<_>>();
-                tags.sort();
+                let tags = issue.tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
 
                 t.push([
                     match state {
@@ -374,15 +674,15 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                         State::Closed { .. } => term::format::negative("●").into(),
                     },
                     term::format::tertiary(term::format::cob(&id)).to_owned(),
-                    term::format::default(issue.title().to_owned()),
-                    term::format::did(&issue.author().id).dim(),
+                    term::format::default(issue.title),
+                    term::format::did(&issue.author).dim(),
                     term::format::secondary(tags.join(", ")),
                     if assigned.is_empty() {
                         term::format::dim(String::default())
                     } else {
                         term::format::default(assigned.to_string())
                     },
-                    term::format::timestamp(&issue.timestamp()).dim().italic(),
+                    term::format::timestamp(&issue.timestamp).dim().italic(),
                 ]);
             }
             t.print();
@@ -391,22 +691,203 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             let id = id.resolve(&repo.backend)?;
             issues.remove(&id, &signer)?;
         }
+        Operation::Edit {
+            id,
+            title,
+            description,
+        } => {
+            let id = id.resolve(&repo.backend)?;
+            let mut issue = issues.get_mut(&id)?;
+            let (root, comment) = issue.root();
+            let root = *root;
+            let (old_title, old_description) =
+                (issue.title().to_owned(), comment.body().to_owned());
+
+            let (title, description) = if title.is_none() && description.is_none() {
+                let message =
+                    Message::Edit.get(&format!("{old_title}\n\n{old_description}\n{EDIT_MSG}"))?;
+                let message = message.replace(EDIT_MSG.trim(), "");
+                let (title, description) = message.split_once("\n\n").unwrap_or((&message, ""));
+
+                (title.trim().to_owned(), description.trim().to_owned())
+            } else {
+                (
+                    title.unwrap_or_else(|| old_title.clone()),
+                    description.unwrap_or_else(|| old_description.clone()),
+                )
+            };
+            if title.is_empty() {
+                anyhow::bail!("an issue title must be provided");
+            }
+            let edit_title = title != old_title;
+            let edit_description = description != old_description;
+
+            if !edit_title && !edit_description {
+                term::info!("Nothing to do, the issue is unchanged");
+                return Ok(());
+            }
+            issue.transaction("Edit", &signer, |tx| {
+                if edit_title {
+                    tx.edit(title)?;
+                }
+                if edit_description {
+                    tx.edit_comment(root, description)?;
+                }
+                Ok(())
+            })?;
+            if !options.quiet {
+                show_issue(&issue, &profile.config)?;
+            }
+        }
+        Operation::Due { id, due } => {
+            let id = id.resolve(&repo.backend)?;
+            let mut issue = issues.get_mut(&id)?;
+            issue.set_due(due, &signer)?;
+        }
+        Operation::Move { id, column } => {
+            let (_, doc) = repo.identity_doc()?;
+            let board = issue::Board::from_doc(&doc.verified()?)?;
+            if !board.contains(&column) {
+                let columns = board
+                    .columns
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>();
+                anyhow::bail!(
+                    "column `{column}` is not on the board; available columns are: {}",
+                    columns.join(", ")
+                );
+            }
+            let id = id.resolve(&repo.backend)?;
+            let mut issue = issues.get_mut(&id)?;
+            issue.move_to(column, &signer)?;
+        }
+        Operation::Board { all } => {
+            let (_, doc) = repo.identity_doc()?;
+            let board = issue::Board::from_doc(&doc.verified()?)?;
+            let mut all_issues = Vec::new();
+
+            for result in issues.all()? {
+                let (id, issue, _) = result?;
+
+                if profile.config.is_blocked(&issue.author().id) {
+                    continue;
+                }
+                if !all && issue.state() != &State::Open {
+                    continue;
+                }
+                all_issues.push((id, issue));
+            }
+            all_issues.sort_by_key(|(_, i)| i.timestamp());
+
+            show_board(board.group(all_issues));
+        }
     }
 
     if announce {
-        match node.announce_refs(rid) {
-            Ok(()) => {}
-            Err(e) if e.is_connection_err() => {
-                term::warning("Could not announce issue refs: node is not running");
+        term::announce::changed(rid);
+    }
+
+    Ok(())
+}
+
+/// Resolve the given issues, checking that they all exist, so that a batch operation isn't
+/// left half-done because of a typo.
+fn resolve(
+    ids: &[Rev],
+    repo: &radicle::storage::git::Repository,
+    issues: &Issues,
+) -> anyhow::Result<Vec<issue::IssueId>> {
+    let mut resolved = Vec::with_capacity(ids.len());
+
+    for rev in ids {
+        let id = rev
+            .resolve(&repo.backend)
+            .map_err(|_| anyhow!("issue `{rev}` not found"))?;
+        if issues.get(&id)?.is_none() {
+            anyhow::bail!("issue `{rev}` not found");
+        }
+        if !resolved.contains(&id) {
+            resolved.push(id);
+        }
+    }
+    Ok(resolved)
+}
+
+/// Warn about open issues assigned to us that are due soon, or overdue.
+fn remind(issues: &[(issue::IssueId, IssueSummary)], profile: &Profile, now: cob::Timestamp) {
+    let me = Did::from(*profile.id());
+
+    for (id, issue) in issues {
+        if !issue.assignees.contains(&me) || !issue.is_due_within(now, REMINDER_PERIOD) {
+            continue;
+        }
+        let Some(due) = issue.due else {
+            continue;
+        };
+        term::warning(&format!(
+            "Issue {} {} {} {}",
+            term::format::tertiary(term::format::cob(id)),
+            term::format::default(&issue.title),
+            if issue.is_overdue(now) {
+                "was due on"
+            } else {
+                "is due on"
+            },
+            term::format::date(&due),
+        ));
+    }
+}
+
+fn show_board(groups: Vec<(issue::Column, Vec<(issue::IssueId, issue::Issue)>)>) {
+    for (i, (column, issues)) in groups.into_iter().enumerate() {
+        if i > 0 {
+            term::blank();
+        }
+        term::print(format!(
+            "{} {}",
+            term::format::bold(column.name()),
+            term::format::dim(format!("({})", issues.len()))
+        ));
+
+        for (id, issue) in issues {
+            let assigned = issue
+                .assigned()
+                .map(|p| term::format::did(&p).to_string())
+                .collect::<Vec<_>>();
+            let mut line = format!(
+                "{} {} {}",
+                match issue.state() {
+                    State::Open => term::format::positive("●"),
+                    State::Closed { .. } => term::format::negative("●"),
+                },
+                term::format::tertiary(term::format::cob(&id)),
+                issue.title()
+            );
+            if !assigned.is_empty() {
+                line.push_str(&format!(" {}", term::format::dim(assigned.join(", "))));
             }
-            Err(e) => return Err(e.into()),
+            term::print(line);
         }
     }
+}
 
+/// Warn about open issues with a title similar to the one of the issue about to be opened.
+/// Fails if the user chose not to open it.
+fn check_duplicates(
+    profile: &Profile,
+    repo: &radicle::storage::git::Repository,
+    title: &str,
+) -> anyhow::Result<()> {
+    let similar = profile.cobs()?.similar_issues(repo, title)?;
+
+    if !term::duplicates("issue", similar.into_iter().map(|(id, i)| (id, i.title))) {
+        anyhow::bail!("issue not opened");
+    }
     Ok(())
 }
 
-fn show_issue(issue: &issue::Issue) -> anyhow::Result<()> {
+fn show_issue(issue: &issue::Issue, config: &Config) -> anyhow::Result<()> {
     let tags: Vec<String> = issue.tags().cloned().map(|t| t.into()).collect();
     let assignees: Vec<String> = issue.assigned().map(|a| term::format::did(&a).to_string()).collect();
 
@@ -434,6 +915,24 @@ fn show_issue(issue: &issue::Issue) -> anyhow::Result<()> {
         ]);
     }
 
+    if let Some(due) = issue.due() {
+        attrs.push([
+            term::format::tertiary("Due".to_owned()),
+            if issue.is_overdue(cob::Timestamp::now()) {
+                term::format::negative(format!("{} (overdue)", term::format::date(&due)))
+            } else {
+                term::format::default(term::format::date(&due))
+            },
+        ]);
+    }
+
+    if let Some(column) = issue.column() {
+        attrs.push([
+            term::format::tertiary("Column".to_owned()),
+            term::format::yellow(column.to_string()),
+        ]);
+    }
+
     attrs.push([
         term::format::tertiary("Status".to_owned()),
         match issue.state() {
@@ -447,8 +946,24 @@ fn show_issue(issue: &issue::Issue) -> anyhow::Result<()> {
     ]);
 
     let description = issue.description().unwrap_or_default();
+    let (root, _) = issue.root();
+    let comments = term::comment::thread(issue.thread(), Some(root), config);
+    let mut thread: Vec<Box<dyn Element>> = Vec::new();
+    if !comments.is_empty() {
+        thread.push(term::Label::blank().boxed());
+        thread.push(
+            term::Label::from(term::format::tertiary(format!(
+                "Comments ({})",
+                comments.len()
+            )))
+            .boxed(),
+        );
+    }
+    for (depth, id, comment) in comments {
+        thread.extend(term::comment::widget(depth, id, comment));
+    }
     let widget = VStack::default()
-        .border(Some(term::colors::FAINT))
+        .border(Some(term::theme::current().faint))
         .child(attrs)
         .children(if !description.is_empty() {
             vec![
@@ -457,9 +972,10 @@ fn show_issue(issue: &issue::Issue) -> anyhow::Result<()> {
             ]
         } else {
             vec![]
-        });
+        })
+        .children(thread);
 
-    widget.print();
+    term::pager::page_element(&widget)?;
 
     Ok(())
 }
@@ -0,0 +1,464 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle::cob::common::{Color, Tag};
+use radicle::cob::identity::Proposals;
+use radicle::cob::label::Labels;
+use radicle::cob::{issue, patch, ObjectId};
+use radicle::crypto::Signer;
+use radicle::identity::Identity;
+use radicle::storage::git::Repository;
+use radicle::storage::WriteStorage;
+
+use crate::commands::rad_id as id;
+use crate::git::Rev;
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+use crate::terminal::Interactive;
+
+pub const HELP: Help = Help {
+    name: "label",
+    description: "Manage the labels of a project",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad label [list] [<option>...]
+    rad label define <name> [--color <#rrggbb>] [--description <text>] [<option>...]
+    rad label rename <name> <new-name> [<option>...]
+    rad label delete <name> [--no-confirm] [<option>...]
+    rad label add <name> <issue-or-patch-id>... [<option>...]
+    rad label remove <name> <issue-or-patch-id>... [<option>...]
+
+    Labels are the tags used on issues and patches. Defining a label gives it
+    a color and a description, which are stored in the identity document of
+    the project. Changing the definitions is an identity proposal; when our
+    own acceptance is enough, the proposal is committed right away.
+
+    Renaming or deleting a label also re-tags, or untags, every issue and
+    patch that has it. Tags that were never defined can be renamed and
+    deleted as well.
+
+    The `add` and `remove` commands tag, or untag, several issues and patches
+    in one go. They all are checked to exist before any of them is changed.
+    Changes are announced to the network once, when the command is done.
+
+Options
+
+    --color <#rrggbb>       Label color
+    --description <text>    Label description
+    --no-confirm            Don't ask for confirmation
+    --no-announce           Don't announce changes to the network
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum OperationName {
+    Define,
+    Rename,
+    Delete,
+    Add,
+    Remove,
+    #[default]
+    List,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Define {
+        name: Tag,
+        color: Option<Color>,
+        description: Option<String>,
+    },
+    Rename {
+        from: Tag,
+        to: Tag,
+    },
+    Delete {
+        name: Tag,
+        confirm: bool,
+    },
+    Add {
+        name: Tag,
+        ids: Vec<Rev>,
+    },
+    Remove {
+        name: Tag,
+        ids: Vec<Rev>,
+    },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut names: Vec<Tag> = Vec::new();
+        let mut ids: Vec<Rev> = Vec::new();
+        let mut color: Option<Color> = None;
+        let mut description: Option<String> = None;
+        let mut confirm = true;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("color") if op == Some(OperationName::Define) => {
+                    let value = parser.value()?;
+                    let value = term::args::string(&value);
+
+                    color = Some(Color::from_str(&value)?);
+                }
+                Long("description") if op == Some(OperationName::Define) => {
+                    let value = parser.value()?;
+
+                    description = Some(term::args::string(&value));
+                }
+                Long("no-confirm") => {
+                    confirm = false;
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "l" | "list" => op = Some(OperationName::List),
+                    "define" => op = Some(OperationName::Define),
+                    "rename" => op = Some(OperationName::Rename),
+                    "delete" => op = Some(OperationName::Delete),
+                    "add" => op = Some(OperationName::Add),
+                    "remove" => op = Some(OperationName::Remove),
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val)
+                    if !names.is_empty()
+                        && (op == Some(OperationName::Add)
+                            || op == Some(OperationName::Remove)) =>
+                {
+                    ids.push(Rev::from(term::args::string(&val)));
+                }
+                Value(val) if op.is_some() && op != Some(OperationName::List) => {
+                    let val = term::args::string(&val);
+
+                    names.push(Tag::from_str(&val)?);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let mut names = names.into_iter();
+        let mut name = |msg: &str| names.next().ok_or_else(|| anyhow!("{msg}"));
+        let op = op.unwrap_or_default();
+        if (op == OperationName::Add || op == OperationName::Remove) && ids.is_empty() {
+            anyhow::bail!("at least one issue or patch must be specified");
+        }
+        let op = match op {
+            OperationName::List => Operation::List,
+            OperationName::Define => Operation::Define {
+                name: name("a label name must be specified")?,
+                color,
+                description,
+            },
+            OperationName::Rename => Operation::Rename {
+                from: name("the label to rename must be specified")?,
+                to: name("the new label name must be specified")?,
+            },
+            OperationName::Delete => Operation::Delete {
+                name: name("the label to delete must be specified")?,
+                confirm,
+            },
+            OperationName::Add => Operation::Add {
+                name: name("the label to add must be specified")?,
+                ids,
+            },
+            OperationName::Remove => Operation::Remove {
+                name: name("the label to remove must be specified")?,
+                ids,
+            },
+        };
+        if let Some(name) = names.next() {
+            anyhow::bail!("unexpected label '{name}'");
+        }
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let (_, id) = radicle::rad::cwd()
+        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let repo = profile.storage.repository_mut(id)?;
+    let signer = term::signer(&profile)?;
+    let identity = Identity::load(signer.public_key(), &repo)?;
+    let mut labels = Labels::from_doc(&identity.doc)?;
+    let announce = !matches!(options.op, Operation::List);
+
+    match options.op {
+        Operation::List => {
+            list(&repo, &labels)?;
+        }
+        Operation::Define {
+            name,
+            color,
+            description,
+        } => {
+            let mut label = labels.get(&name).cloned().unwrap_or_default();
+            if let Some(color) = color {
+                label.color = Some(color);
+            }
+            if let Some(description) = description {
+                label.description = description;
+            }
+            if !labels.define(name.clone(), label) {
+                term::info!("Label '{name}' is already defined as given");
+                return Ok(());
+            }
+            propose(
+                &repo,
+                &identity,
+                &labels,
+                "label define",
+                format!("Define label '{name}'"),
+                &signer,
+            )?;
+        }
+        Operation::Rename { from, to } => {
+            let defined = labels.get(&from).is_some();
+            if defined {
+                labels.rename(&from, to.clone())?;
+            }
+            let count = relabel(&repo, &from, Some(&to), &signer)?;
+            if !defined && count == 0 {
+                anyhow::bail!("label '{from}' is not defined, nor used by any issue or patch");
+            }
+            term::success!("Renamed label '{from}' to '{to}' on {count} issue(s) and patch(es)");
+
+            if defined {
+                propose(
+                    &repo,
+                    &identity,
+                    &labels,
+                    "label rename",
+                    format!("Rename label '{from}' to '{to}'"),
+                    &signer,
+                )?;
+            }
+        }
+        Operation::Delete { name, confirm } => {
+            let defined = labels.remove(&name).is_ok();
+            if confirm
+                && !term::confirm(format!(
+                    "Are you sure you want to delete label '{name}' from every issue and patch?"
+                ))
+            {
+                anyhow::bail!("deletion aborted");
+            }
+            let count = relabel(&repo, &name, None, &signer)?;
+            if !defined && count == 0 {
+                anyhow::bail!("label '{name}' is not defined, nor used by any issue or patch");
+            }
+            term::success!("Removed label '{name}' from {count} issue(s) and patch(es)");
+
+            if defined {
+                propose(
+                    &repo,
+                    &identity,
+                    &labels,
+                    "label delete",
+                    format!("Delete label '{name}'"),
+                    &signer,
+                )?;
+            }
+        }
+        Operation::Add { name, ids } => {
+            let count = tag(&repo, &ids, [name.clone()], [], &signer)?;
+            term::success!("Added label '{name}' to {count} issue(s) and patch(es)");
+        }
+        Operation::Remove { name, ids } => {
+            let count = tag(&repo, &ids, [], [name.clone()], &signer)?;
+            term::success!("Removed label '{name}' from {count} issue(s) and patch(es)");
+        }
+    }
+
+    if announce {
+        term::announce::changed(id);
+    }
+    Ok(())
+}
+
+/// Add and remove tags on the given issues and patches. All objects are resolved before any
+/// of them is changed. Returns the number of issues and patches that were changed.
+fn tag<G: Signer>(
+    repo: &Repository,
+    ids: &[Rev],
+    add: impl IntoIterator<Item = Tag> + Clone,
+    remove: impl IntoIterator<Item = Tag> + Clone,
+    signer: &G,
+) -> anyhow::Result<usize> {
+    let mut issues = issue::Issues::open(repo)?;
+    let mut patches = patch::Patches::open(repo)?;
+    let mut targets: Vec<(ObjectId, bool)> = Vec::new();
+
+    for rev in ids {
+        let id = rev
+            .resolve::<ObjectId>(&repo.backend)
+            .map_err(|_| anyhow!("issue or patch `{rev}` not found"))?;
+        let is_issue = if issues.get(&id)?.is_some() {
+            true
+        } else if patches.get(&id)?.is_some() {
+            false
+        } else {
+            anyhow::bail!("issue or patch `{rev}` not found");
+        };
+        if !targets.iter().any(|(t, _)| *t == id) {
+            targets.push((id, is_issue));
+        }
+    }
+    for (id, is_issue) in &targets {
+        if *is_issue {
+            issues
+                .get_mut(id)?
+                .tag(add.clone(), remove.clone(), signer)?;
+        } else {
+            patches
+                .get_mut(id)?
+                .tag(add.clone(), remove.clone(), signer)?;
+        }
+    }
+    Ok(targets.len())
+}
+
+/// Print the defined labels, followed by the tags that are used without being defined, along
+/// with the number of issues and patches using them.
+fn list(repo: &Repository, labels: &Labels) -> anyhow::Result<()> {
+    let usage = usage(repo)?;
+    if labels.iter().next().is_none() && usage.is_empty() {
+        term::info!("No labels defined or used");
+        return Ok(());
+    }
+    let mut table = term::Table::default();
+
+    for (name, label) in labels.iter() {
+        table.push([
+            term::format::bold(name.to_string()),
+            term::format::secondary(
+                label
+                    .color
+                    .as_ref()
+                    .map(|c| c.to_string())
+                    .unwrap_or_default(),
+            ),
+            term::format::italic(label.description.clone()),
+            term::format::dim(format!("{} use(s)", usage.get(name).unwrap_or(&0))),
+        ]);
+    }
+    for (name, count) in usage.iter().filter(|(name, _)| labels.get(name).is_none()) {
+        table.push([
+            term::format::default(name.to_string()),
+            term::format::default(String::new()),
+            term::format::dim(String::from("(not defined)")),
+            term::format::dim(format!("{count} use(s)")),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
+
+/// Count the issues and patches using every tag.
+fn usage(repo: &Repository) -> anyhow::Result<BTreeMap<Tag, usize>> {
+    let mut usage = BTreeMap::new();
+
+    for result in issue::Issues::open(repo)?.all()? {
+        let (_, issue, _) = result?;
+        for tag in issue.tags() {
+            *usage.entry(tag.clone()).or_default() += 1;
+        }
+    }
+    for result in patch::Patches::open(repo)?.all()? {
+        let (_, patch, _) = result?;
+        for tag in patch.tags() {
+            *usage.entry(tag.clone()).or_default() += 1;
+        }
+    }
+    Ok(usage)
+}
+
+/// Replace the `from` tag with the `to` tag, or remove it if `to` is `None`, on every issue
+/// and patch that has it. Returns the number of issues and patches changed.
+fn relabel<G: Signer>(
+    repo: &Repository,
+    from: &Tag,
+    to: Option<&Tag>,
+    signer: &G,
+) -> anyhow::Result<usize> {
+    let mut issues = issue::Issues::open(repo)?;
+    let mut patches = patch::Patches::open(repo)?;
+    let mut count = 0;
+
+    let ids = issues
+        .all()?
+        .filter_map(|r| match r {
+            Ok((id, issue, _)) if issue.tags().any(|t| t == from) => Some(Ok(id)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in ids {
+        let mut issue = issues.get_mut(&id)?;
+        issue.tag(to.cloned(), [from.clone()], signer)?;
+        count += 1;
+    }
+
+    let ids = patches
+        .all()?
+        .filter_map(|r| match r {
+            Ok((id, patch, _)) if patch.tags().any(|t| t == from) => Some(Ok(id)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in ids {
+        let mut patch = patches.get_mut(&id)?;
+        patch.tag(to.cloned(), [from.clone()], signer)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Propose an identity document with the given labels, accept it if we're a delegate, and
+/// commit it if that's enough to reach quorum.
+fn propose<G: Signer>(
+    repo: &Repository,
+    previous: &Identity<radicle::git::Oid>,
+    labels: &Labels,
+    command: &str,
+    title: String,
+    signer: &G,
+) -> anyhow::Result<()> {
+    let mut proposed = previous.doc.clone();
+    labels.to_doc(&mut proposed);
+
+    let mut proposals = Proposals::open(repo)?;
+    id::propose(
+        repo,
+        &mut proposals,
+        previous,
+        command,
+        &title,
+        proposed,
+        &Interactive::No,
+        signer,
+    )?;
+
+    Ok(())
+}
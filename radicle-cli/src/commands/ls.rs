@@ -1,7 +1,10 @@
 use std::ffi::OsString;
+use std::time;
 
+use radicle::node::{Handle as _, Node};
 use radicle::storage::{ReadRepository, ReadStorage};
 
+use crate::commands::rad_search;
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
 
@@ -16,15 +19,29 @@ Usage
 
     rad ls [<option>...]
 
+    With `--network`, the repositories of the connected seeds are listed
+    instead of the local ones, along with their canonical head. Seeds return
+    at most 32 repositories each: a warning is shown when that limit is reached.
+
+    With `--in-use`, the working copies of local repositories are listed,
+    eg. those created with `rad init`, `rad clone` or `rad checkout`.
+    Repositories that back a working copy are kept by `rad rm --all`.
+
 Options
 
-    --versbose, -v  Verbose output
-    --help          Print help
+    --network, -n         List the repositories of connected seeds
+    --in-use              List the working copies of local repositories
+    --timeout, -t <secs>  How long to wait for seeds to answer (default: 6)
+    --versbose, -v        Verbose output
+    --help                Print help
 "#,
 };
 
 pub struct Options {
     verbose: bool,
+    network: bool,
+    in_use: bool,
+    timeout: time::Duration,
 }
 
 impl Args for Options {
@@ -33,26 +50,80 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut verbose = false;
+        let mut network = false;
+        let mut in_use = false;
+        let mut timeout = time::Duration::from_secs(6);
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
                 Long("verbose") | Short('v') => verbose = true,
+                Long("network") | Short('n') => network = true,
+                Long("in-use") => in_use = true,
+                Long("timeout") | Short('t') => {
+                    let value = parser.value()?;
+                    let secs = term::args::parse_value("timeout", value)?;
+
+                    timeout = time::Duration::from_secs(secs);
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
 
-        Ok((Options { verbose }, vec![]))
+        Ok((
+            Options {
+                verbose,
+                network,
+                in_use,
+                timeout,
+            },
+            vec![],
+        ))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
+
+    if options.network {
+        let mut node = Node::new(profile.socket());
+
+        if !node.is_running() {
+            anyhow::bail!("to list the repositories of the network, your node must be running");
+        }
+        // The empty query matches every repository.
+        let results = rad_search::search(&mut node, String::new(), options.timeout)?;
+        rad_search::print(results, options.verbose);
+
+        return Ok(());
+    }
     let storage = &profile.storage;
     let mut table = term::Table::default();
 
+    if options.in_use {
+        for (id, checkouts) in profile.checkouts()?.all()? {
+            let name = storage
+                .repository(id)
+                .ok()
+                .and_then(|repo| repo.project().ok())
+                .map(|proj| proj.name().to_owned())
+                .unwrap_or_default();
+
+            for path in checkouts {
+                table.push([
+                    term::format::bold(name.clone()),
+                    term::format::tertiary(id.urn()),
+                    term::format::italic(path.display().to_string()),
+                ]);
+            }
+        }
+        table.print();
+
+        return Ok(());
+    }
+
     for id in storage.repositories()? {
         let repo = match storage.repository(id) {
             Ok(repo) => repo,
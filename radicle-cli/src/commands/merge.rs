@@ -24,6 +24,11 @@ Usage
 
     To specify a patch revision to merge, use the fully qualified revision id.
 
+    If the patch is stacked on other patches, these must be merged first.
+
+    If the repository's default branch is protected, the merged branch can only
+    be pushed if the patch revision was approved by enough delegates.
+
 Options
 
     -f, --force               Force merging an older patch revision
@@ -159,6 +164,17 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     if !options.force && revision_id != *last_revision_id {
         anyhow::bail!("refusing to merge old patch revision");
     }
+    if let Some(base) = radicle::api::patch::unmerged_base(&patches, &patch_id)? {
+        return Err(Error::WithHint {
+            err: anyhow!(
+                "patch {} is stacked on patch {}, which is not merged",
+                term::format::cob(&patch_id),
+                term::format::cob(&base)
+            ),
+            hint: "Patches in a stack must be merged from the bottom up.",
+        }
+        .into());
+    }
 
     let mut patch = patches
         .get_mut(&patch_id)
@@ -294,7 +310,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     // Update patch COB
     //
     // TODO: Don't allow merging the same revision twice?
-    patch.merge(revision_id, head_oid.into(), &signer)?;
+    //
+    // Nb. We record the new branch head, which contains the revision. On protected branches,
+    // this is the commit that is allowed to be pushed.
+    let merged_oid = repo
+        .head()?
+        .target()
+        .ok_or_else(|| anyhow!("cannot merge into detatched head; aborting"))?;
+    patch.merge(revision_id, merged_oid.into(), &signer)?;
 
     term::success!(
         "Patch state updated, use {} to publish",
@@ -1,16 +1,35 @@
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time;
 
 use anyhow::anyhow;
 
-use radicle::node::{Address, Node, NodeId, ROUTING_DB_FILE, TRACKING_DB_FILE};
+use radicle::identity::Id;
+use radicle::node::tracking::Alias;
+use radicle::node::{
+    Address, ConnectOptions, Handle as _, Node, NodeId, Timestamp, ADDRESS_DB_FILE, AUDIT_DB_FILE,
+    DEFAULT_TIMEOUT, ROUTING_DB_FILE, TRACKING_DB_FILE,
+};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
 
+#[path = "node/attest.rs"]
+mod attest;
+#[path = "node/config.rs"]
+mod config;
 #[path = "node/control.rs"]
 mod control;
+#[path = "node/gossip.rs"]
+mod gossip;
+#[path = "node/log.rs"]
+mod log;
+#[path = "node/peers.rs"]
+mod peers;
 #[path = "node/routing.rs"]
 mod routing;
+#[path = "node/seeds.rs"]
+mod seeds;
 #[path = "node/tracking.rs"]
 mod tracking;
 
@@ -22,31 +41,175 @@ pub const HELP: Help = Help {
 Usage
 
     rad node status [<option>...]
-    rad node start [<option>...]
+    rad node start [--foreground] [<option>...] [-- <node-option>...]
     rad node stop [<option>...]
-    rad node connect <nid> <addr> [<option>...]
+    rad node restart [<option>...] [-- <node-option>...]
+    rad node connect <nid> <addr> [--persistent] [--timeout <secs>] [<option>...]
+    rad node peers [--scores] [<option>...]
+    rad node ban <nid> [<option>...]
+    rad node unban <nid> [<option>...]
     rad node routing [<option>...]
+    rad node seeds <rid> [--json] [--timeout <secs>] [<option>...]
     rad node tracking [--repos|--nodes] [<option>...]
+    rad node attest --addr <addr>... [--alias <name>] [--expires <duration>] [<option>...]
+    rad node attest --verify <file> [<option>...]
+    rad node log connections [--since <duration>] [<option>...]
+    rad node gossip [--trace | --no-trace] [<option>...]
+    rad node config [get <key> | set <key> <value> | unset <key> | reload] [<option>...]
+
+    The `status` command also shows whether the node is connected to the
+    preferred seeds and persistent peers configured in the profile.
+
+    The `start` command runs `radicle-node` with the current profile, in the
+    background, logging to `node/node.log` under the Radicle home. Options
+    after `--` are passed to `radicle-node`. The passphrase is read from
+    `RAD_PASSPHRASE`, or prompted for.
+
+    The `routing` command shows, for each repository, the seeds it can be
+    fetched from, best first. Seeds are scored by their round-trip time and
+    fetch throughput, as measured by the node.
+
+    With `--persistent`, the peer is added to the persistent peers of the
+    profile. The node maintains a connection to persistent peers, reconnecting
+    when the connection drops, and connects to them on startup.
+
+    By default, `connect` returns once the node was asked to connect. With
+    `--timeout`, it waits for the connection to be established, asking the
+    node to connect again if it isn't established in time, up to two times.
+
+    The `seeds` command probes the seeds of a repository known to the node:
+    the node connects to the seeds it isn't connected to, and each seed is
+    shown as reachable or not, along with its addresses and the time of its
+    last announcement of the repository. Seeds that aren't reachable within
+    the timeout are shown as unreachable, and seeds whose inventory no longer
+    includes the repository once connected are shown as missing it. The node
+    disconnects from the seeds it connected to for the probe. With `--json`,
+    the seeds are output as a JSON array.
+
+    The `peers` command shows the peers the node is connected to. With
+    `--scores`, it shows the reputation of peers instead: the node records
+    failed connection attempts, protocol violations, and stale and invalid
+    announcements of each peer, which add up to a penalty. Peers with a higher
+    penalty are connected to last. Peers with a high penalty, or whose fetches
+    often fail, are deprioritized: they are fetched from and relayed to last.
+    Peers whose misbehavior reaches the maximum penalty are banned for a week;
+    connection failures alone never get a peer banned. Peers that don't offend
+    for a day are forgiven.
+
+    The `ban` command bans a peer until it is unbanned: the node disconnects
+    from it, and neither connects to it nor accepts its connections. The `unban`
+    command lifts the ban, and resets the reputation of the peer.
+
+    The `attest` command prints a statement binding our Node ID to the given
+    external addresses and alias, signed with our key, as JSON. Seed operators
+    can publish it, eg. in a DNS record or on their website, for others to
+    verify with `--verify`. The statement expires after 30 days by default.
+
+    The `log connections` command shows the inbound and outbound connections
+    of the node, once closed, with the peer address, the outcome and the bytes
+    exchanged. With `--since`, only connections attempted within the given
+    duration are shown. The node keeps connections in its log for 30 days.
+
+    The `gossip` command shows the last announcements received by the node,
+    who signed and relayed them, and which peers they were relayed to, or why
+    they weren't. Tracing is off by default: enable it with `--trace`, and
+    disable it with `--no-trace`, which also drops the recorded traces.
+
+    The `config` command shows or changes the profile configuration, in
+    `config.json` under the Radicle home. Keys are paths into the JSON
+    document, separated by dots, eg. `node.limits.maxFetches`. Values are
+    parsed as JSON, or else taken as strings. Once the configuration is
+    changed, a running node reloads it: the persistent peers, and the node
+    settings under `node`, ie. `externalAddresses`, `trackingPolicy`,
+    `trackingScope` and `limits`, are applied without a restart. Use
+    `reload` after editing the file by hand.
 
 Options
 
     --help          Print help
+    --foreground    Run the node in the foreground, instead of in the background
+    --persistent    Always maintain a connection to the peer
+    --timeout <s>   Wait up to the given number of seconds for the connection(s)
+    --json          Output JSON
+    --scores        Show the reputation of peers
     --repos         Show the tracked repositories table
     --nodes         Show the tracked nodes table
+    --addr <addr>   Address to attest to (may be specified multiple times)
+    --alias <name>  Alias to attest to
+    --expires <d>   Expire the attestation after the given duration (eg. '90d')
+    --verify <file> Verify the attestation in the given file
+    --since <d>     Only show connections attempted within the given duration
+    --trace         Enable gossip tracing on the running node
+    --no-trace      Disable gossip tracing on the running node
 "#,
 };
 
+/// How long attestations are valid for, by default.
+pub const ATTESTATION_EXPIRY: time::Duration = time::Duration::from_secs(60 * 60 * 24 * 30);
+
 pub struct Options {
     op: Operation,
 }
 
 pub enum Operation {
-    Connect { nid: NodeId, addr: Address },
+    Attest {
+        addresses: Vec<Address>,
+        alias: Option<Alias>,
+        expires: time::Duration,
+    },
+    Ban {
+        nid: NodeId,
+    },
+    Config {
+        op: ConfigOperation,
+    },
+    Connect {
+        nid: NodeId,
+        addr: Address,
+        persistent: bool,
+        timeout: Option<time::Duration>,
+    },
+    Gossip {
+        trace: Option<bool>,
+    },
+    LogConnections {
+        since: Option<time::Duration>,
+    },
+    Peers {
+        scores: bool,
+    },
+    Restart {
+        args: Vec<OsString>,
+    },
     Routing,
-    Start,
+    Seeds {
+        rid: Id,
+        json: bool,
+        timeout: time::Duration,
+    },
+    Start {
+        foreground: bool,
+        args: Vec<OsString>,
+    },
     Status,
     Stop,
-    Tracking { mode: TrackingMode },
+    Tracking {
+        mode: TrackingMode,
+    },
+    Unban {
+        nid: NodeId,
+    },
+    Verify {
+        path: PathBuf,
+    },
+}
+
+pub enum ConfigOperation {
+    Show,
+    Get { key: String },
+    Set { key: String, value: String },
+    Unset { key: String },
+    Reload,
 }
 
 #[derive(Default)]
@@ -58,13 +221,22 @@ pub enum TrackingMode {
 
 #[derive(Default)]
 pub enum OperationName {
+    Attest,
+    Ban,
+    Config,
     Connect,
+    Gossip,
+    Log,
+    Peers,
+    Restart,
     Routing,
+    Seeds,
     Start,
     #[default]
     Status,
     Stop,
     Tracking,
+    Unban,
 }
 
 impl Args for Options {
@@ -76,6 +248,20 @@ impl Args for Options {
         let mut tracking_mode = TrackingMode::default();
         let mut nid: Option<NodeId> = None;
         let mut addr: Option<Address> = None;
+        let mut persistent = false;
+        let mut timeout = None;
+        let mut foreground = false;
+        let mut args = Vec::new();
+        let mut addresses = Vec::new();
+        let mut alias: Option<Alias> = None;
+        let mut expires = ATTESTATION_EXPIRY;
+        let mut verify: Option<PathBuf> = None;
+        let mut since: Option<time::Duration> = None;
+        let mut trace: Option<bool> = None;
+        let mut scores = false;
+        let mut rid: Option<Id> = None;
+        let mut json = false;
+        let mut config_args: Vec<String> = Vec::new();
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -83,12 +269,21 @@ impl Args for Options {
                     return Err(Error::Help.into());
                 }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "attest" => op = Some(OperationName::Attest),
+                    "ban" => op = Some(OperationName::Ban),
+                    "config" => op = Some(OperationName::Config),
                     "connect" => op = Some(OperationName::Connect),
+                    "gossip" => op = Some(OperationName::Gossip),
+                    "log" => op = Some(OperationName::Log),
+                    "peers" => op = Some(OperationName::Peers),
+                    "restart" => op = Some(OperationName::Restart),
                     "routing" => op = Some(OperationName::Routing),
+                    "seeds" => op = Some(OperationName::Seeds),
                     "start" => op = Some(OperationName::Start),
                     "status" => op = Some(OperationName::Status),
                     "stop" => op = Some(OperationName::Stop),
                     "tracking" => op = Some(OperationName::Tracking),
+                    "unban" => op = Some(OperationName::Unban),
 
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
@@ -105,28 +300,123 @@ impl Args for Options {
                         },
                     }
                 }
+                Value(val) if matches!(op, Some(OperationName::Ban | OperationName::Unban)) => {
+                    nid = Some(term::args::nid(&val)?);
+                }
+                Value(val) if matches!(op, Some(OperationName::Seeds)) => {
+                    rid = Some(term::args::rid(&val)?);
+                }
+                Long("json") if matches!(op, Some(OperationName::Seeds)) => {
+                    json = true;
+                }
+                Long("scores") if matches!(op, Some(OperationName::Peers)) => {
+                    scores = true;
+                }
+                Value(val) if matches!(op, Some(OperationName::Config)) => {
+                    config_args.push(term::args::string(&val));
+                }
+                Value(val) if matches!(op, Some(OperationName::Log)) => {
+                    match val.to_string_lossy().as_ref() {
+                        "connections" => {}
+                        unknown => anyhow::bail!("unknown log '{}'", unknown),
+                    }
+                }
+                Long("since") if matches!(op, Some(OperationName::Log)) => {
+                    let val = parser.value()?;
+                    since = Some(term::args::duration(&val)?);
+                }
+                Long("trace") if matches!(op, Some(OperationName::Gossip)) => {
+                    trace = Some(true);
+                }
+                Long("no-trace") if matches!(op, Some(OperationName::Gossip)) => {
+                    trace = Some(false);
+                }
+                Long("persistent") if matches!(op, Some(OperationName::Connect)) => {
+                    persistent = true;
+                }
+                Long("timeout")
+                    if matches!(op, Some(OperationName::Connect | OperationName::Seeds)) =>
+                {
+                    let value = parser.value()?;
+                    let secs = term::args::parse_value("timeout", value)?;
+
+                    timeout = Some(time::Duration::from_secs(secs));
+                }
+                Long("foreground") if matches!(op, Some(OperationName::Start)) => {
+                    foreground = true;
+                }
+                Value(val) if matches!(op, Some(OperationName::Start | OperationName::Restart)) => {
+                    args.push(val);
+                }
                 Long("repos") if matches!(op, Some(OperationName::Tracking)) => {
                     tracking_mode = TrackingMode::Repos
                 }
                 Long("nodes") if matches!(op, Some(OperationName::Tracking)) => {
                     tracking_mode = TrackingMode::Nodes
                 }
+                Long("addr") if matches!(op, Some(OperationName::Attest)) => {
+                    let val = parser.value()?;
+                    addresses.push(term::args::addr(&val)?);
+                }
+                Long("alias") if matches!(op, Some(OperationName::Attest)) => {
+                    let val = parser.value()?;
+                    alias = Some(term::args::string(&val));
+                }
+                Long("expires") if matches!(op, Some(OperationName::Attest)) => {
+                    let val = parser.value()?;
+                    expires = term::args::duration(&val)?;
+                }
+                Long("verify") if matches!(op, Some(OperationName::Attest)) => {
+                    let val = parser.value()?;
+                    verify = Some(PathBuf::from(val));
+                }
                 _ => return Err(anyhow!(arg.unexpected())),
             }
         }
 
         let op = match op.unwrap_or_default() {
+            OperationName::Attest => match verify {
+                Some(path) => Operation::Verify { path },
+                None if addresses.is_empty() => {
+                    anyhow::bail!("at least one address must be provided with `--addr`")
+                }
+                None => Operation::Attest {
+                    addresses,
+                    alias,
+                    expires,
+                },
+            },
+            OperationName::Config => Operation::Config {
+                op: config_operation(config_args)?,
+            },
             OperationName::Connect => Operation::Connect {
                 nid: nid.ok_or_else(|| anyhow!("an NID must be provided"))?,
                 addr: addr.ok_or_else(|| anyhow!("an address must be provided"))?,
+                persistent,
+                timeout,
+            },
+            OperationName::Ban => Operation::Ban {
+                nid: nid.ok_or_else(|| anyhow!("an NID must be provided"))?,
             },
+            OperationName::Gossip => Operation::Gossip { trace },
+            OperationName::Log => Operation::LogConnections { since },
+            OperationName::Peers => Operation::Peers { scores },
+            OperationName::Restart => Operation::Restart { args },
             OperationName::Routing => Operation::Routing,
-            OperationName::Start => Operation::Start,
+            OperationName::Seeds => Operation::Seeds {
+                rid: rid.ok_or_else(|| anyhow!("a repository must be provided"))?,
+                json,
+                timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            },
+            OperationName::Start => Operation::Start { foreground, args },
             OperationName::Status => Operation::Status,
             OperationName::Stop => Operation::Stop,
             OperationName::Tracking => Operation::Tracking {
                 mode: tracking_mode,
             },
+            OperationName::Unban => Operation::Unban {
+                nid: nid.ok_or_else(|| anyhow!("an NID must be provided"))?,
+            },
         };
         Ok((Options { op }, vec![]))
     }
@@ -136,23 +426,102 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
 
     match options.op {
-        Operation::Connect { nid, addr } => {
+        Operation::Attest {
+            addresses,
+            alias,
+            expires,
+        } => {
+            attest::attest(&profile, addresses, alias, expires)?;
+        }
+        Operation::Ban { nid } => {
             let mut node = Node::new(profile.socket());
-            control::connect(&mut node, nid, addr)?
+            peers::ban(&mut node, &profile, nid)?;
+        }
+        Operation::Config { op } => match op {
+            ConfigOperation::Show => config::show(&profile)?,
+            ConfigOperation::Get { key } => config::get(&profile, &key)?,
+            ConfigOperation::Set { key, value } => config::set(&profile, &key, &value)?,
+            ConfigOperation::Unset { key } => config::unset(&profile, &key)?,
+            ConfigOperation::Reload => config::reload(&profile)?,
+        },
+        Operation::Connect {
+            nid,
+            addr,
+            persistent,
+            timeout,
+        } => {
+            let mut node = Node::new(profile.socket());
+            if persistent {
+                control::persist(&profile, nid, addr.clone())?;
+            }
+            let opts = ConnectOptions {
+                persistent,
+                timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            };
+            control::connect(&mut node, nid, addr, opts, timeout.is_some())?
+        }
+        Operation::Gossip { trace } => {
+            let mut node = Node::new(profile.socket());
+            match trace {
+                Some(enable) => gossip::trace(&mut node, enable)?,
+                None => gossip::show(&node)?,
+            }
+        }
+        Operation::LogConnections { since } => {
+            let audit = radicle::node::audit::Log::reader(profile.home.node().join(AUDIT_DB_FILE))?;
+            let since = match since {
+                Some(since) => time::SystemTime::now()
+                    .duration_since(time::UNIX_EPOCH)?
+                    .saturating_sub(since)
+                    .as_millis() as Timestamp,
+                None => 0,
+            };
+            log::connections(&audit, since)?;
+        }
+        Operation::Peers { scores } => {
+            let node = Node::new(profile.socket());
+            if scores {
+                peers::scores(&node, &profile)?;
+            } else {
+                peers::connected(&node)?;
+            }
         }
         Operation::Routing => {
             let store =
                 radicle::node::routing::Table::reader(profile.home.node().join(ROUTING_DB_FILE))?;
-            routing::run(&store)?;
+            let metrics =
+                radicle::node::metrics::Table::reader(profile.home.node().join(ADDRESS_DB_FILE))
+                    .ok();
+            routing::run(&store, metrics.as_ref())?;
+        }
+        Operation::Seeds { rid, json, timeout } => {
+            let mut node = Node::new(profile.socket());
+            if !node.is_running() {
+                anyhow::bail!(
+                    "to probe seeds, the node must be running; start it with `rad node start`"
+                );
+            }
+            let routing =
+                radicle::node::routing::Table::reader(profile.home.node().join(ROUTING_DB_FILE))?;
+            let probes = seeds::probe(rid, profile.id(), &mut node, &routing, timeout)?;
+
+            seeds::show(&probes, json)?;
+        }
+        Operation::Restart { args } => {
+            let node = Node::new(profile.socket());
+            control::restart(node, &profile, args)?;
+        }
+        Operation::Start { foreground, args } => {
+            let node = Node::new(profile.socket());
+            control::start(&node, &profile, foreground, args)?;
         }
-        Operation::Start => control::start()?,
         Operation::Status => {
             let node = Node::new(profile.socket());
-            control::status(&node);
+            control::status(&node, &profile)?;
         }
         Operation::Stop => {
             let node = Node::new(profile.socket());
-            control::stop(node)?;
+            control::stop(node, &profile)?;
         }
         Operation::Tracking { mode } => {
             let store = radicle::node::tracking::store::Config::reader(
@@ -160,7 +529,45 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             )?;
             tracking::run(&store, mode)?
         }
+        Operation::Unban { nid } => {
+            let mut node = Node::new(profile.socket());
+            peers::unban(&mut node, &profile, nid)?;
+        }
+        Operation::Verify { path } => {
+            attest::verify(&path)?;
+        }
     }
 
     Ok(())
 }
+
+/// Parse the arguments of the `config` operation.
+fn config_operation(args: Vec<String>) -> anyhow::Result<ConfigOperation> {
+    let key = |key: String| {
+        if key.split('.').any(str::is_empty) {
+            anyhow::bail!("invalid configuration key '{key}'");
+        }
+        Ok(key)
+    };
+    let mut args = args.into_iter();
+
+    let op = match (args.next().as_deref(), args.next(), args.next()) {
+        (None, None, None) => ConfigOperation::Show,
+        (Some("get"), Some(k), None) => ConfigOperation::Get { key: key(k)? },
+        (Some("set"), Some(k), Some(value)) => ConfigOperation::Set {
+            key: key(k)?,
+            value,
+        },
+        (Some("unset"), Some(k), None) => ConfigOperation::Unset { key: key(k)? },
+        (Some("reload"), None, None) => ConfigOperation::Reload,
+        (Some(op @ ("get" | "set" | "unset" | "reload")), _, _) => {
+            anyhow::bail!("invalid arguments for `config {op}`; see `rad node --help`")
+        }
+        (Some(unknown), _, _) => anyhow::bail!("unknown config operation '{}'", unknown),
+        _ => anyhow::bail!("invalid arguments for `config`; see `rad node --help`"),
+    };
+    if args.next().is_some() {
+        anyhow::bail!("too many arguments for `config`; see `rad node --help`");
+    }
+    Ok(op)
+}
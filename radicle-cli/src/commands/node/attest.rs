@@ -0,0 +1,59 @@
+use std::path::Path;
+use std::{fs, time};
+
+use anyhow::Context as _;
+
+use radicle::cob;
+use radicle::node::attestation::Attestation;
+use radicle::node::tracking::Alias;
+use radicle::node::{Address, Timestamp};
+use radicle::prelude::Did;
+use radicle::Profile;
+
+use crate::terminal as term;
+
+/// Sign an attestation of our node's addresses and alias, valid for the given duration,
+/// and print it as JSON.
+pub fn attest(
+    profile: &Profile,
+    addresses: Vec<Address>,
+    alias: Option<Alias>,
+    expires: time::Duration,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let expiry = (now()? + expires).as_millis() as Timestamp;
+    let attestation = Attestation::new(addresses, alias, expiry, &signer)?;
+
+    term::print(serde_json::to_string_pretty(&attestation)?);
+
+    Ok(())
+}
+
+/// Verify the attestation in the given file.
+pub fn verify(path: &Path) -> anyhow::Result<()> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("failed to read attestation from {}", path.display()))?;
+    let attestation: Attestation = serde_json::from_str(&json)
+        .with_context(|| format!("invalid attestation in {}", path.display()))?;
+    let statement = &attestation.statement;
+
+    attestation.verify(now()?.as_millis() as Timestamp)?;
+
+    term::success!(
+        "Attestation by {} is valid until {}",
+        term::format::tertiary(Did::from(statement.nid)),
+        term::format::date(&cob::Timestamp::new(statement.expiry / 1000)),
+    );
+    if let Some(alias) = &statement.alias {
+        term::info!("Alias: {}", term::format::highlight(alias));
+    }
+    for addr in &statement.addresses {
+        term::info!("Address: {}", term::format::secondary(addr));
+    }
+    Ok(())
+}
+
+/// Time elapsed since the epoch.
+fn now() -> anyhow::Result<time::Duration> {
+    Ok(time::SystemTime::now().duration_since(time::UNIX_EPOCH)?)
+}
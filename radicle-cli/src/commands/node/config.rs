@@ -0,0 +1,109 @@
+use std::path::Path;
+use std::{fs, io};
+
+use anyhow::anyhow;
+use serde_json as json;
+
+use radicle::node::Handle as _;
+use radicle::profile::{self, Profile};
+use radicle::Node;
+
+use crate::terminal as term;
+
+/// Show the configuration.
+pub fn show(profile: &Profile) -> anyhow::Result<()> {
+    let config = load(&profile.home.config())?;
+    term::print(json::to_string_pretty(&config)?);
+
+    Ok(())
+}
+
+/// Show the value of a configuration key.
+pub fn get(profile: &Profile, key: &str) -> anyhow::Result<()> {
+    let config = load(&profile.home.config())?;
+    let value = key
+        .split('.')
+        .try_fold(&config, |v, k| v.get(k))
+        .ok_or_else(|| anyhow!("configuration key '{key}' is not set"))?;
+
+    match value {
+        json::Value::String(s) => term::print(s),
+        other => term::print(json::to_string_pretty(other)?),
+    }
+    Ok(())
+}
+
+/// Set a configuration key. The value is parsed as JSON, or else used as a string.
+pub fn set(profile: &Profile, key: &str, value: &str) -> anyhow::Result<()> {
+    let path = profile.home.config();
+    let mut config = load(&path)?;
+    let value = json::from_str(value).unwrap_or_else(|_| json::Value::String(value.to_owned()));
+    let entry = key.split('.').try_fold(&mut config, |v, k| match v {
+        json::Value::Object(map) => Ok(map
+            .entry(k)
+            .or_insert_with(|| json::Value::Object(json::Map::new()))),
+        _ => Err(anyhow!("configuration key '{key}' is not within an object")),
+    })?;
+    *entry = value;
+
+    write(&path, &config)?;
+    reload(profile)
+}
+
+/// Unset a configuration key.
+pub fn unset(profile: &Profile, key: &str) -> anyhow::Result<()> {
+    let path = profile.home.config();
+    let mut config = load(&path)?;
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (
+            parent.split('.').try_fold(&mut config, |v, k| v.get_mut(k)),
+            last,
+        ),
+        None => (Some(&mut config), key),
+    };
+    if parent
+        .and_then(|p| p.as_object_mut())
+        .and_then(|p| p.remove(last))
+        .is_none()
+    {
+        anyhow::bail!("configuration key '{key}' is not set");
+    }
+
+    write(&path, &config)?;
+    reload(profile)
+}
+
+/// Have the running node reload its configuration, if any.
+pub fn reload(profile: &Profile) -> anyhow::Result<()> {
+    let mut node = Node::new(profile.socket());
+
+    if !node.is_running() {
+        term::info!("Node is not running, the configuration will be applied when it starts");
+        return Ok(());
+    }
+    if node.reload_config()? {
+        term::success!("Node configuration reloaded");
+    } else {
+        term::info!("Node configuration is unchanged");
+    }
+    Ok(())
+}
+
+/// Load the configuration file as JSON, so that keys unknown to us are preserved.
+fn load(path: &Path) -> anyhow::Result<json::Value> {
+    match fs::read_to_string(path) {
+        Ok(s) => json::from_str(&s)
+            .map_err(|e| anyhow!("invalid configuration file '{}': {e}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(json::Value::Object(json::Map::new())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write the configuration file, after checking that it is valid.
+fn write(path: &Path, config: &json::Value) -> anyhow::Result<()> {
+    json::from_value::<profile::Config>(config.clone())
+        .map_err(|e| anyhow!("invalid configuration: {e}"))?;
+    fs::write(path, json::to_string_pretty(config)? + "\n")?;
+
+    Ok(())
+}
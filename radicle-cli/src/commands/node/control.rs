@@ -1,44 +1,302 @@
-use radicle::node::{Address, Handle as _, NodeId};
-use radicle::Node;
+use std::ffi::OsString;
+use std::os::unix::process::CommandExt as _;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::{env, fs, io, thread, time};
+
+use anyhow::{anyhow, Context as _};
+
+use radicle::node;
+use radicle::node::{Address, ConnectAddress, ConnectOptions, Handle as _, NodeId};
+use radicle::profile::env::{RAD_HOME, RAD_PASSPHRASE};
+use radicle::{Node, Profile};
 
 use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Name of the node program.
+pub const NODE_PROGRAM: &str = "radicle-node";
+/// How long to wait for the node to start or stop.
+pub const TIMEOUT: time::Duration = time::Duration::from_secs(9);
+/// How many times the node is asked to connect again, when a connection isn't established
+/// in time.
+pub const CONNECT_RETRIES: usize = 2;
+
+/// Start the node with the given profile, passing it the given arguments.
+pub fn start(
+    node: &Node,
+    profile: &Profile,
+    foreground: bool,
+    args: Vec<OsString>,
+) -> anyhow::Result<()> {
+    if node.is_running() {
+        match pid(profile) {
+            Some(pid) => anyhow::bail!("the node is already running (pid {pid})"),
+            None => anyhow::bail!("the node is already running"),
+        }
+    }
+    let mut cmd = Command::new(program());
+    cmd.env(RAD_HOME, profile.home()).args(args);
+
+    // The node isn't responding, so the control socket, if any, was left behind by
+    // a node that didn't shut down cleanly.
+    if profile.socket().exists() {
+        cmd.arg("--force");
+    }
+
+    if foreground {
+        let status = cmd.status().map_err(spawn_error)?;
+        if !status.success() {
+            anyhow::bail!("the node exited with {status}");
+        }
+        return Ok(());
+    }
+    let passphrase = term::io::passphrase(RAD_PASSPHRASE)?;
+    let log_path = profile.home.node().join(node::LOG_FILE);
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("failed to open log file {}", log_path.display()))?;
+    let spinner = term::spinner("Starting the node...");
+    let mut child = cmd
+        .env(RAD_PASSPHRASE, passphrase.as_str())
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        // Don't forward terminal signals, eg. `SIGINT`, to the node.
+        .process_group(0)
+        .spawn()
+        .map_err(spawn_error)?;
+    let started = time::Instant::now();
+
+    while !node.is_running() {
+        if let Some(status) = child.try_wait()? {
+            spinner.failed();
+            anyhow::bail!(
+                "the node exited with {status}; see {} for details",
+                log_path.display()
+            );
+        }
+        if started.elapsed() >= TIMEOUT {
+            spinner.failed();
+            anyhow::bail!(
+                "the node did not start within {} seconds; see {} for details",
+                TIMEOUT.as_secs(),
+                log_path.display()
+            );
+        }
+        thread::sleep(time::Duration::from_millis(100));
+    }
+    spinner.finish();
+
+    term::success!(
+        "Node started (pid {}), logging to {}",
+        child.id(),
+        term::format::tertiary(log_path.display())
+    );
 
-pub fn start() -> anyhow::Result<()> {
-    todo!()
+    Ok(())
 }
 
-pub fn stop(node: Node) -> anyhow::Result<()> {
+/// Stop the node, and wait for it to shut down.
+pub fn stop(node: Node, profile: &Profile) -> anyhow::Result<()> {
+    if !node.is_running() {
+        anyhow::bail!("the node is not running");
+    }
     let spinner = term::spinner("Stopping the node...");
     if let Err(err) = node.shutdown() {
-        spinner.error(format!("Error occurred while shutting down node: {err}"));
-    } else {
-        spinner.finish();
+        spinner.failed();
+        anyhow::bail!("error occurred while shutting down the node: {err}");
+    }
+    let node = Node::new(profile.socket());
+    let started = time::Instant::now();
+
+    while node.is_running() {
+        if started.elapsed() >= TIMEOUT {
+            spinner.failed();
+            anyhow::bail!("the node did not stop within {} seconds", TIMEOUT.as_secs());
+        }
+        thread::sleep(time::Duration::from_millis(100));
     }
+    spinner.finish();
+
     Ok(())
 }
 
-pub fn connect(node: &mut Node, nid: NodeId, addr: Address) -> anyhow::Result<()> {
-    let spinner = term::spinner(format!(
+/// Stop the node if it's running, and start it again in the background.
+pub fn restart(node: Node, profile: &Profile, args: Vec<OsString>) -> anyhow::Result<()> {
+    if node.is_running() {
+        stop(node, profile)?;
+    }
+    start(&Node::new(profile.socket()), profile, false, args)
+}
+
+/// Get the process id of the node, if it is known.
+pub fn pid(profile: &Profile) -> Option<u32> {
+    fs::read_to_string(profile.home.node().join(node::PID_FILE))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Get the path to the node program. We look for it next to the running program first,
+/// so that the node matches the version of the CLI, and otherwise in the `PATH`.
+fn program() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(NODE_PROGRAM)))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(NODE_PROGRAM))
+}
+
+fn spawn_error(err: io::Error) -> anyhow::Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        anyhow!("`{NODE_PROGRAM}` was not found; make sure it is installed and in your PATH")
+    } else {
+        anyhow!("failed to run `{NODE_PROGRAM}`: {err}")
+    }
+}
+
+/// Ask the node to connect to a peer. If `wait` is set, wait for the connection to be
+/// established, asking the node to connect again up to [`CONNECT_RETRIES`] times if it isn't
+/// established within the timeout of the connect options.
+pub fn connect(
+    node: &mut Node,
+    nid: NodeId,
+    addr: Address,
+    opts: ConnectOptions,
+    wait: bool,
+) -> anyhow::Result<()> {
+    let mut spinner = term::spinner(format!(
         "Connecting to {}@{addr}...",
         term::format::node(&nid)
     ));
-    if let Err(err) = node.connect(nid, addr.clone()) {
-        spinner.error(format!(
-            "Failed to connect to {}@{}: {}",
-            term::format::node(&nid),
-            term::format::secondary(addr),
-            err,
-        ))
-    } else {
-        spinner.finish()
+    if wait && node.sessions()?.contains(&nid) {
+        spinner.finish();
+        return Ok(());
     }
+
+    for attempt in 0..=CONNECT_RETRIES {
+        let events = if wait {
+            Some(node.subscribe(opts.timeout)?)
+        } else {
+            None
+        };
+        if let Err(err) = node.connect(nid, addr.clone(), opts) {
+            spinner.error(format!(
+                "Failed to connect to {}@{}: {}",
+                term::format::node(&nid),
+                term::format::secondary(addr),
+                err,
+            ));
+            return Ok(());
+        }
+        let Some(events) = events else {
+            spinner.finish();
+            return Ok(());
+        };
+        for e in events {
+            match e {
+                Ok(node::Event::PeerConnected { nid: remote }) if remote == nid => {
+                    spinner.finish();
+                    return Ok(());
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if attempt < CONNECT_RETRIES {
+            spinner.message(format!(
+                "Connecting to {}@{addr}... (retrying)",
+                term::format::node(&nid)
+            ));
+        }
+    }
+    spinner.failed();
+
+    anyhow::bail!(
+        "timed out connecting to {}@{addr} after {} attempt(s)",
+        term::format::node(&nid),
+        CONNECT_RETRIES + 1
+    )
+}
+
+/// Add a peer to the persistent peers of the profile, so that the node connects to it
+/// on startup.
+pub fn persist(profile: &Profile, nid: NodeId, addr: Address) -> anyhow::Result<()> {
+    let mut config = profile.config.clone();
+    let peer = ConnectAddress { id: nid, addr };
+
+    config.persistent_peers.retain(|p| p.id != nid);
+    config.persistent_peers.push(peer.clone());
+    config.write(&profile.home.config())?;
+
+    term::success!(
+        "Added {}@{} to persistent peers",
+        term::format::node(&nid),
+        peer.addr
+    );
     Ok(())
 }
 
-pub fn status(node: &Node) {
+pub fn status(node: &Node, profile: &Profile) -> anyhow::Result<()> {
+    let config = &profile.config;
+
     if node.is_running() {
-        term::success!("The node is {}", term::format::positive("running"));
+        match pid(profile) {
+            Some(pid) => term::success!(
+                "The node is {} (pid {pid})",
+                term::format::positive("running")
+            ),
+            None => term::success!("The node is {}", term::format::positive("running")),
+        }
     } else {
         term::info!("The node is {}", term::format::negative("stopped"));
+        return Ok(());
+    }
+    let mut peers = config.preferred_seeds.iter().collect::<Vec<_>>();
+    for peer in &config.persistent_peers {
+        if !peers.iter().any(|p| p.id == peer.id) {
+            peers.push(peer);
+        }
     }
+    if peers.is_empty() {
+        return Ok(());
+    }
+    let sessions = node.sessions()?;
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+
+    t.push([
+        term::format::default(String::from("Peer")),
+        term::format::default(String::from("Address")),
+        term::format::default(String::from("Type")),
+        term::format::default(String::from("Status")),
+    ]);
+    t.divider();
+
+    for peer in peers {
+        let status = if sessions.contains(&peer.id) {
+            term::format::positive(String::from("connected"))
+        } else {
+            term::format::negative(String::from("disconnected"))
+        };
+        let kind = if config.is_persistent(&peer.id) {
+            term::format::yellow(String::from("persistent"))
+        } else {
+            term::format::default(String::from("seed"))
+        };
+        t.push([
+            term::format::tertiary(term::format::node(&peer.id)),
+            term::format::default(peer.addr.to_string()),
+            kind,
+            status,
+        ]);
+    }
+    term::blank();
+    t.print();
+
+    Ok(())
 }
@@ -0,0 +1,87 @@
+use radicle::cob;
+use radicle::node::trace::Verdict;
+use radicle::node::Handle as _;
+use radicle::Node;
+
+use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Enable or disable gossip tracing on the node.
+pub fn trace(node: &mut Node, enable: bool) -> anyhow::Result<()> {
+    let toggled = node.trace_gossip(enable)?;
+
+    match (enable, toggled) {
+        (true, true) => term::success!("Gossip tracing enabled"),
+        (true, false) => term::info!("Gossip tracing is already enabled"),
+        (false, true) => term::success!("Gossip tracing disabled"),
+        (false, false) => term::info!("Gossip tracing is already disabled"),
+    }
+    Ok(())
+}
+
+/// Show the announcements traced by the node, oldest first.
+pub fn show(node: &Node) -> anyhow::Result<()> {
+    let Some(traces) = node.gossip_traces()? else {
+        term::info!(
+            "Gossip tracing is disabled, enable it with {}",
+            term::format::secondary("`rad node gossip --trace`")
+        );
+        return Ok(());
+    };
+    if traces.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+    t.push([
+        term::format::default(String::from("Received")),
+        term::format::default(String::from("Type")),
+        term::format::default(String::from("Origin")),
+        term::format::default(String::from("Relayer")),
+        term::format::default(String::from("RID")),
+        term::format::default(String::from("Verdict")),
+    ]);
+    t.divider();
+
+    for trace in traces {
+        let relayer = if trace.relayer == trace.origin {
+            term::format::dim(String::from("(origin)"))
+        } else {
+            term::format::default(term::format::node(&trace.relayer))
+        };
+        let verdict = match trace.verdict {
+            Verdict::Relayed { to } if to.is_empty() => {
+                term::format::positive(String::from("accepted"))
+            }
+            Verdict::Relayed { to } => term::format::positive(format!(
+                "relayed to {}",
+                to.iter()
+                    .map(term::format::node)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Verdict::Dropped { reason } => term::format::yellow(format!("dropped: {reason}")),
+            Verdict::Rejected { reason } => term::format::negative(format!("rejected: {reason}")),
+        };
+
+        t.push([
+            term::format::dim(
+                term::format::timestamp(&cob::Timestamp::new(trace.time / 1000)).item,
+            ),
+            term::format::default(trace.kind.to_string()),
+            term::format::tertiary(term::format::node(&trace.origin)),
+            relayer,
+            term::format::default(
+                trace
+                    .rid
+                    .map(|rid| rid.urn())
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+            verdict,
+        ]);
+    }
+    t.print();
+
+    Ok(())
+}
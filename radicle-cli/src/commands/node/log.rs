@@ -0,0 +1,52 @@
+use radicle::cob;
+use radicle::node::audit;
+use radicle::node::Timestamp;
+
+use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Show the connections attempted since the given time, oldest first.
+pub fn connections(log: &audit::Log, since: Timestamp) -> anyhow::Result<()> {
+    let entries = log.since(since)?;
+    if entries.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+    t.push([
+        term::format::default(String::from("Time")),
+        term::format::default(String::from("NID")),
+        term::format::default(String::from("Address")),
+        term::format::default(String::from("Direction")),
+        term::format::default(String::from("Result")),
+        term::format::default(String::from("Received")),
+        term::format::default(String::from("Sent")),
+    ]);
+    t.divider();
+
+    for e in entries {
+        let result = if e.established {
+            term::format::positive(format!("closed: {}", e.reason))
+        } else {
+            term::format::negative(format!("failed: {}", e.reason))
+        };
+
+        t.push([
+            term::format::dim(term::format::timestamp(&cob::Timestamp::new(e.time / 1000)).item),
+            term::format::default(
+                e.nid
+                    .map(|nid| term::format::node(&nid))
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+            term::format::default(e.addr.to_string()),
+            term::format::default(e.direction.to_string()),
+            result,
+            term::format::dim(term::format::bytes(e.received)),
+            term::format::dim(term::format::bytes(e.sent)),
+        ]);
+    }
+    t.print();
+
+    Ok(())
+}
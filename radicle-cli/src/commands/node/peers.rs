@@ -0,0 +1,117 @@
+use std::time;
+
+use radicle::node::{metrics, reputation};
+use radicle::node::{Handle as _, NodeId, Timestamp, ADDRESS_DB_FILE};
+use radicle::{Node, Profile};
+
+use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Show the peers the node is connected to.
+pub fn connected(node: &Node) -> anyhow::Result<()> {
+    let sessions = node.sessions()?;
+    if sessions.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+    for nid in sessions {
+        term::print(term::format::tertiary(nid));
+    }
+    Ok(())
+}
+
+/// Show the reputation of the peers the node is connected to, or recorded offenses of,
+/// worst first.
+pub fn scores(node: &Node, profile: &Profile) -> anyhow::Result<()> {
+    let table = reputation::Table::reader(profile.home.node().join(ADDRESS_DB_FILE))?;
+    let metrics = metrics::Table::reader(profile.home.node().join(ADDRESS_DB_FILE))?;
+    let sessions = if node.is_running() {
+        node.sessions()?
+    } else {
+        Vec::new()
+    };
+    let mut entries = table.all()?;
+    for nid in &sessions {
+        if !entries.iter().any(|(n, _)| n == nid) {
+            entries.push((*nid, reputation::Reputation::default()));
+        }
+    }
+    if entries.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+    t.push([
+        term::format::default(String::from("NID")),
+        term::format::default(String::from("Status")),
+        term::format::default(String::from("Failures")),
+        term::format::default(String::from("Violations")),
+        term::format::default(String::from("Stale")),
+        term::format::default(String::from("Invalid")),
+        term::format::default(String::from("Fetches")),
+        term::format::default(String::from("Penalty")),
+    ]);
+    t.divider();
+
+    for (nid, r) in entries {
+        let m = metrics.get(&nid)?;
+        let status = if r.is_banned() {
+            term::format::negative(String::from("banned"))
+        } else if r.is_deprioritized(&m) {
+            term::format::yellow(String::from("deprioritized"))
+        } else if sessions.contains(&nid) {
+            term::format::positive(String::from("connected"))
+        } else {
+            term::format::dim(String::from("-"))
+        };
+        t.push([
+            term::format::tertiary(term::format::node(&nid)),
+            status,
+            term::format::default(r.failures.to_string()),
+            term::format::default(r.violations.to_string()),
+            term::format::default(r.stale.to_string()),
+            term::format::default(r.invalid.to_string()),
+            // Successful fetches, out of all fetches.
+            term::format::default(format!("{}/{}", m.fetches, m.fetches + m.failures)),
+            term::format::default(format!("{}/{}", r.misbehavior(), reputation::BAN_THRESHOLD)),
+        ]);
+    }
+    t.print();
+
+    Ok(())
+}
+
+/// Ban a peer. Goes through the node if it's running, so that it disconnects from the peer.
+pub fn ban(node: &mut Node, profile: &Profile, nid: NodeId) -> anyhow::Result<()> {
+    let banned = if node.is_running() {
+        node.ban(nid)?
+    } else {
+        let now = time::SystemTime::now().duration_since(time::UNIX_EPOCH)?;
+        reputation::Table::open(profile.home.node().join(ADDRESS_DB_FILE))?
+            .ban(&nid, now.as_millis() as Timestamp)?
+    };
+
+    if banned {
+        term::success!("Banned peer {}", term::format::tertiary(nid));
+    } else {
+        term::info!("Peer {} is already banned", term::format::tertiary(nid));
+    }
+    Ok(())
+}
+
+/// Unban a peer, and reset its reputation.
+pub fn unban(node: &mut Node, profile: &Profile, nid: NodeId) -> anyhow::Result<()> {
+    let unbanned = if node.is_running() {
+        node.unban(nid)?
+    } else {
+        reputation::Table::open(profile.home.node().join(ADDRESS_DB_FILE))?.unban(&nid)?
+    };
+
+    if unbanned {
+        term::success!("Unbanned peer {}", term::format::tertiary(nid));
+    } else {
+        term::info!("Peer {} is not banned", term::format::tertiary(nid));
+    }
+    Ok(())
+}
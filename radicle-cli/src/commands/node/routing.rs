@@ -1,20 +1,48 @@
 use radicle::node;
+use radicle::node::metrics;
 
 use crate::terminal as term;
 use crate::terminal::Element;
 
-pub fn run<S: node::routing::Store>(routing: &S) -> anyhow::Result<()> {
+pub fn run<S: node::routing::Store>(
+    routing: &S,
+    metrics: Option<&metrics::Table>,
+) -> anyhow::Result<()> {
     let mut t = term::Table::new(term::table::TableOptions::bordered());
     t.push([
         term::format::default(String::from("RID")),
         term::format::default(String::from("NID")),
+        term::format::default(String::from("RTT")),
+        term::format::default(String::from("Throughput")),
+        term::format::default(String::from("Score")),
     ]);
     t.divider();
 
-    for (id, node) in routing.entries()? {
+    let mut entries = routing
+        .entries()?
+        .map(|(id, node)| {
+            let m = metrics.and_then(|m| m.get(&node).ok()).unwrap_or_default();
+            (id, node, m)
+        })
+        .collect::<Vec<_>>();
+    // Within a repository, list the preferred seeds first.
+    entries.sort_by(|(a, _, x), (b, _, y)| a.cmp(b).then(y.score().total_cmp(&x.score())));
+
+    for (id, node, m) in entries {
         t.push([
             term::format::highlight(id.to_string()),
             term::format::default(term::format::node(&node)),
+            term::format::dim(
+                m.rtt
+                    .map(|rtt| format!("{rtt}ms"))
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+            term::format::dim(
+                m.throughput
+                    .map(|t| format!("{:.1} KiB/s", t as f64 / 1024.))
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+            term::format::default(format!("{:.1}", m.score())),
         ]);
     }
     t.print();
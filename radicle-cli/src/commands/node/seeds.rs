@@ -0,0 +1,181 @@
+use std::collections::BTreeSet;
+use std::{io, time};
+
+use serde::Serialize;
+use serde_json as json;
+
+use radicle::cob;
+use radicle::identity::Id;
+use radicle::node::routing::Store as _;
+use radicle::node::{routing, Address, ConnectOptions, Event, Handle as _, NodeId, Timestamp};
+use radicle::Node;
+
+use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Reachability of a seed, as probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reachability {
+    /// The node was already connected to the seed.
+    Connected,
+    /// The node connected to the seed when probed.
+    Reachable,
+    /// The node couldn't connect to the seed in time.
+    Unreachable,
+    /// The seed is reachable, but its inventory no longer includes the repository.
+    Missing,
+    /// The node doesn't know any address of the seed.
+    Unknown,
+}
+
+/// A seed of a repository, as probed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probe {
+    /// Seed Node ID.
+    pub nid: NodeId,
+    /// Known addresses of the seed.
+    pub addresses: Vec<Address>,
+    /// Reachability of the seed.
+    pub reachability: Reachability,
+    /// Time of the last announcement of the repository's refs or of an inventory including
+    /// it by the seed, in milliseconds since the epoch.
+    pub last_announced: Option<Timestamp>,
+}
+
+/// Probe the seeds of a repository known to the routing table, asking the node to connect to
+/// the ones it isn't connected to, and waiting up to `timeout` for the connections. Once
+/// connected, seeds are checked against the routing table again, since the inventory they
+/// announce on connection may no longer include the repository. The node then disconnects
+/// from the seeds it connected to.
+pub fn probe<S: routing::Store>(
+    rid: Id,
+    local: &NodeId,
+    node: &mut Node,
+    routing: &S,
+    timeout: time::Duration,
+) -> anyhow::Result<Vec<Probe>> {
+    let connected = node.sessions()?.into_iter().collect::<BTreeSet<_>>();
+    let mut probes = Vec::new();
+
+    for seed in routing.get(&rid)? {
+        if seed == *local {
+            continue;
+        }
+        let addresses = node.addresses(seed)?;
+        let reachability = if connected.contains(&seed) {
+            Reachability::Connected
+        } else if addresses.is_empty() {
+            Reachability::Unknown
+        } else {
+            Reachability::Unreachable
+        };
+        probes.push(Probe {
+            nid: seed,
+            addresses,
+            reachability,
+            last_announced: routing.entry(&rid, &seed)?,
+        });
+    }
+
+    let dialed = probes
+        .iter()
+        .filter(|p| p.reachability == Reachability::Unreachable)
+        .map(|p| p.nid)
+        .collect::<BTreeSet<_>>();
+    let mut waiting = dialed.clone();
+
+    if !waiting.is_empty() {
+        let mut events = node.subscribe(timeout)?;
+
+        for p in probes.iter().filter(|p| waiting.contains(&p.nid)) {
+            node.connect(p.nid, p.addresses[0].clone(), ConnectOptions::default())?;
+        }
+        while !waiting.is_empty() {
+            match events.next() {
+                Some(Ok(Event::PeerConnected { nid })) => {
+                    if waiting.remove(&nid) {
+                        if let Some(p) = probes.iter_mut().find(|p| p.nid == nid) {
+                            p.reachability = Reachability::Reachable;
+                        }
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            }
+        }
+    }
+
+    // Seeds that dropped the repository from their inventory are no longer in the
+    // routing table.
+    let seeds = node.seeds(rid)?;
+    for p in probes.iter_mut().filter(|p| {
+        matches!(
+            p.reachability,
+            Reachability::Connected | Reachability::Reachable
+        )
+    }) {
+        if !seeds.is_connected(&p.nid) && !seeds.is_disconnected(&p.nid) {
+            p.reachability = Reachability::Missing;
+        }
+    }
+    for nid in &dialed {
+        node.disconnect(*nid)?;
+    }
+    // List the most recently active seeds first.
+    probes.sort_by(|a, b| b.last_announced.cmp(&a.last_announced));
+
+    Ok(probes)
+}
+
+/// Show the probed seeds of a repository, as a table, or as JSON.
+pub fn show(probes: &[Probe], json: bool) -> anyhow::Result<()> {
+    if json {
+        term::print(json::to_string_pretty(probes)?);
+        return Ok(());
+    }
+    if probes.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+
+    let mut t = term::Table::new(term::table::TableOptions::bordered());
+    t.push([
+        term::format::default(String::from("NID")),
+        term::format::default(String::from("Address")),
+        term::format::default(String::from("Status")),
+        term::format::default(String::from("Last announced")),
+    ]);
+    t.divider();
+
+    for p in probes {
+        let status = match p.reachability {
+            Reachability::Connected => term::format::positive(String::from("connected")),
+            Reachability::Reachable => term::format::positive(String::from("reachable")),
+            Reachability::Unreachable => term::format::negative(String::from("unreachable")),
+            Reachability::Missing => term::format::negative(String::from("missing repository")),
+            Reachability::Unknown => term::format::yellow(String::from("no known address")),
+        };
+        t.push([
+            term::format::tertiary(term::format::node(&p.nid)),
+            term::format::default(
+                p.addresses
+                    .first()
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+            status,
+            term::format::dim(
+                p.last_announced
+                    .map(|t| term::format::timestamp(&cob::Timestamp::new(t / 1000)).item)
+                    .unwrap_or_else(|| String::from("-")),
+            ),
+        ]);
+    }
+    t.print();
+
+    Ok(())
+}
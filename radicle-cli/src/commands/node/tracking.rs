@@ -20,18 +20,38 @@ fn print_repos(store: &tracking::store::Config) -> anyhow::Result<()> {
         term::format::default(String::from("RID")),
         term::format::default(String::from("Scope")),
         term::format::default(String::from("Policy")),
+        term::format::default(String::from("Limits")),
     ]);
     t.divider();
 
-    for tracking::Repo { id, scope, policy } in store.repo_policies()? {
+    for tracking::Repo {
+        id,
+        scope,
+        policy,
+        limits,
+    } in store.repo_policies()?
+    {
         let id = id.to_string();
         let scope = scope.to_string();
         let policy = policy.to_string();
+        let limits = [
+            limits.max_pack_size.map(|n| format!("pack <= {n}")),
+            limits.max_object_size.map(|n| format!("object <= {n}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let limits = if limits.is_empty() {
+            String::from("-")
+        } else {
+            limits.join(", ")
+        };
 
         t.push([
             term::format::highlight(id),
             term::format::secondary(scope),
             term::format::secondary(policy),
+            term::format::dim(limits),
         ])
     }
     t.print();
@@ -48,7 +68,10 @@ fn print_nodes(store: &tracking::store::Config) -> anyhow::Result<()> {
     ]);
     t.divider();
 
-    for tracking::Node { id, alias, policy } in store.node_policies()? {
+    for tracking::Node {
+        id, alias, policy, ..
+    } in store.node_policies()?
+    {
         t.push([
             term::format::highlight(Did::from(id).to_string()),
             match alias {
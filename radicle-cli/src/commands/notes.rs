@@ -0,0 +1,209 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::crypto::PublicKey;
+use radicle::git;
+use radicle::git::Oid;
+use radicle::prelude::Id;
+use radicle::storage::git::notes::{self, Note};
+use radicle::storage::WriteStorage;
+
+use crate::git::Rev;
+use crate::project;
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+use crate::terminal::patch::Message;
+
+pub const HELP: Help = Help {
+    name: "notes",
+    description: "Annotate commits with personal notes",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad notes [list] [<option>...]
+    rad notes add <rev> [--message <string>] [<option>...]
+    rad notes show <rev> [<option>...]
+    rad notes remove <rev> [<option>...]
+
+    Notes are annotations on commits, eg. review bookmarks or reminders, that
+    don't change the commits themselves. They are stored in your own namespace,
+    signed, and replicated along with the rest of your fork, so that peers can
+    see them.
+
+    The `list` command shows your notes. The `add` command adds a note on a
+    commit; if you already have a note on it, the new message is appended. The
+    `show` command shows the notes of all peers on a commit, and `remove`
+    removes your note on a commit.
+
+    Commits are looked up in the working copy when there is one, and must have
+    been pushed to storage.
+
+Options
+
+    --message, -m <string>  Note message (default: prompt)
+    --repo <rid>            Operate on the given repository (default: cwd)
+    --help                  Print help
+"#,
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum OperationName {
+    #[default]
+    List,
+    Add,
+    Show,
+    Remove,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    List,
+    Add { rev: Rev, message: Message },
+    Show { rev: Rev },
+    Remove { rev: Rev },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub repo: Option<Id>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut rev: Option<Rev> = None;
+        let mut message = Message::default();
+        let mut repo = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("message") | Short('m') => {
+                    let txt: String = term::args::string(&parser.value()?);
+                    message.append(&txt);
+                }
+                Long("repo") => {
+                    repo = Some(term::args::rid(&parser.value()?)?);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "l" | "list" => op = Some(OperationName::List),
+                    "a" | "add" => op = Some(OperationName::Add),
+                    "s" | "show" => op = Some(OperationName::Show),
+                    "r" | "remove" => op = Some(OperationName::Remove),
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if op != Some(OperationName::List) && rev.is_none() => {
+                    rev = Some(Rev::from(term::args::string(&val)));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let rev = || rev.ok_or_else(|| anyhow!("a commit must be provided"));
+        let op = match op.unwrap_or_default() {
+            OperationName::List => Operation::List,
+            OperationName::Add => Operation::Add {
+                rev: rev()?,
+                message,
+            },
+            OperationName::Show => Operation::Show { rev: rev()? },
+            OperationName::Remove => Operation::Remove { rev: rev()? },
+        };
+
+        Ok((Options { op, repo }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let (workdir, rid) = project::resolve(options.repo)?;
+    let repo = profile.storage.repository_mut(rid)?;
+    let resolve = |rev: &Rev| -> anyhow::Result<Oid> {
+        let oid: git::raw::Oid = rev.resolve(workdir.as_ref().unwrap_or(&repo.backend))?;
+        if repo.backend.find_commit(oid).is_err() {
+            anyhow::bail!(
+                "commit {} was not found in storage, push it first",
+                term::format::oid(oid)
+            );
+        }
+        Ok(oid.into())
+    };
+
+    match options.op {
+        Operation::List => {
+            let notes = notes::by(&repo, profile.id())?;
+            if notes.is_empty() {
+                term::print(term::format::italic("Nothing to show."));
+            }
+            for note in notes {
+                print(&note, profile.id());
+            }
+        }
+        Operation::Add { rev, message } => {
+            let object = resolve(&rev)?;
+            let message = message.get("Enter a note...")?;
+            if message.trim().is_empty() {
+                anyhow::bail!("a note message must be provided");
+            }
+            let signer = term::signer(&profile)?;
+            notes::add(&repo, object, &message, &signer)?;
+
+            term::success!(
+                "Added note on {}",
+                term::format::tertiary(term::format::oid(object))
+            );
+        }
+        Operation::Show { rev } => {
+            let notes = notes::of(&repo, resolve(&rev)?)?;
+            if notes.is_empty() {
+                term::print(term::format::italic("Nothing to show."));
+            }
+            for note in notes {
+                print(&note, profile.id());
+            }
+        }
+        Operation::Remove { rev } => {
+            let object = resolve(&rev)?;
+            let signer = term::signer(&profile)?;
+
+            if notes::remove(&repo, object, &signer)? {
+                term::success!(
+                    "Removed note on {}",
+                    term::format::tertiary(term::format::oid(object))
+                );
+            } else {
+                term::info!(
+                    "You don't have a note on {}",
+                    term::format::tertiary(term::format::oid(object))
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print a note, with its author.
+fn print(note: &Note, me: &PublicKey) {
+    let author = if &note.author == me {
+        term::format::dim(String::from("(you)"))
+    } else {
+        term::format::dim(term::format::node(&note.author))
+    };
+    term::print(format!(
+        "{} {}",
+        term::format::tertiary(term::format::oid(note.object)),
+        author
+    ));
+    for line in note.message.lines() {
+        term::indented(line);
+    }
+    term::blank();
+}
@@ -2,32 +2,47 @@
 mod archive;
 #[path = "patch/checkout.rs"]
 mod checkout;
+#[path = "patch/comment.rs"]
+mod comment;
 #[path = "patch/common.rs"]
 mod common;
 #[path = "patch/create.rs"]
 mod create;
 #[path = "patch/delete.rs"]
 mod delete;
+#[path = "patch/edit.rs"]
+mod edit;
 #[path = "patch/list.rs"]
 mod list;
 #[path = "patch/ready.rs"]
 mod ready;
+#[path = "patch/review.rs"]
+mod review;
+#[path = "patch/scope.rs"]
+mod scope;
 #[path = "patch/show.rs"]
 mod show;
+#[path = "patch/split.rs"]
+mod split;
 #[path = "patch/update.rs"]
 mod update;
 
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 use anyhow::anyhow;
 
+use radicle::cob::common::Tag;
 use radicle::cob::patch;
 use radicle::cob::patch::PatchId;
+use radicle::cob::thread;
 use radicle::storage::git::transport;
 use radicle::{prelude::*, Node};
 
 use crate::commands::rad_sync as sync;
 use crate::git::Rev;
+use crate::project;
 use crate::terminal as term;
 use crate::terminal::args::{string, Args, Error, Help};
 use crate::terminal::patch::Message;
@@ -40,22 +55,88 @@ pub const HELP: Help = Help {
 Usage
 
     rad patch [<option>...]
-    rad patch list [--all|--merged|--open|--archived|--draft] [<option>...]
-    rad patch show <patch-id> [<option>...]
-    rad patch open [--draft] [<option>...]
+    rad patch list [--all|--merged|--open|--archived|--draft] [--label <name>...]
+                   [--path <path>...] [--all-repos] [<option>...]
+    rad patch show [<patch-id>] [<option>...]
+    rad patch open [--draft] [--stack-on <patch-id>] [--scope <path>...] [<option>...]
     rad patch archive <patch-id> [<option>...]
-    rad patch update <patch-id> [<option>...]
-    rad patch checkout <patch-id> [<option>...]
+    rad patch update <patch-id> [--rebase] [<option>...]
+    rad patch checkout [<patch-id>] [<option>...]
     rad patch delete <patch-id> [<option>...]
+    rad patch edit <patch-id> [<option>...]
     rad patch ready <patch-id> [--undo] [<option>...]
+    rad patch split <patch-id> [--at <commit>...] [<option>...]
+    rad patch review <patch-id> [--accept|--reject] [--revision <id>]
+                     [--inline <path>:<start>[-<end>]:<comment>...] [<option>...]
+    rad patch comment <patch-id> [--revision <id>] [--reply-to <comment-id>] [<option>...]
+    rad patch scope <patch-id> [<path>...] [<option>...]
+
+    When run from a terminal, `show` and `checkout` prompt for a patch if none is given.
+
+    List options can be saved as named queries, either in the `queries` of the profile
+    configuration, or in the `rad.query.<name>` git configuration of the working copy.
+    A saved query is used by passing its name prefixed with `@`, eg. `rad patch list @all`.
+
+    Outside of a working copy, the repository must be given with `--repo`. Opening,
+    updating and checking out patches always requires a working copy.
+
+    When a patch is opened, review is requested from the reviewers of the changed paths,
+    as listed in the `.radicle/reviewers` file of the default branch.
+
+    A patch can be stacked on another patch with `--stack-on`, for changes that build on
+    top of each other. The patch then only shows the changes made on top of the other
+    patch, and can only be merged after it. Stacks are shown as trees by `rad patch list`.
+
+    When the branch a patch is based on has advanced, the patch can be rebased onto it and
+    updated in one step with `update --rebase`. This rebases the current branch of the
+    working copy onto the default branch, or onto the patch it is stacked on.
+
+    A large patch can be split into a stack of patches with `split`. Each part of the
+    stack ends at one of the patch commits: these are either given with `--at`, or
+    selected interactively. The patches are named after the last commit of each part,
+    and the original patch is archived.
+
+    A patch revision is reviewed with `review`, optionally accepting or rejecting it. The
+    review message is a summary of the review, and inline comments are left on line ranges
+    of the files of the revision, eg. `--inline src/main.rs:10-12:"Use a constant here"`.
+    Lines start at one, and the end of a range is included.
+
+    The `comment` command comments on the discussion of a patch revision, or replies to
+    one of its comments with `--reply-to`. The latest revision is commented on, unless
+    a revision is given, or the comment replied to is on another revision. The editor is
+    opened when no message is given. Revision discussions are shown by `show`, with
+    replies under the comment they reply to.
+
+    The title and description of a patch are changed with `edit`. The message is given
+    like when opening a patch, and the editor is opened with the current title and
+    description when no message is given. When only a title is given, the description
+    is kept.
+
+    Patches are labeled with `rad label add <name> <patch-id>`, and can be listed by
+    label with `--label`. When the option is repeated, only the patches that have all
+    the given labels are listed.
+
+    In large repositories, a patch can declare the paths it affects, with `--scope` when it
+    is opened, or later with `scope`. Paths are relative to the repository root, and a
+    directory covers all the paths under it. Review is then also requested from the
+    reviewers of these paths. Running `scope` without paths clears the scope of a patch.
+    Patches are listed by path with `--path`: a patch is listed when its scope overlaps with
+    one of the given paths, or, for patches without a scope, when it changes a file under
+    one of them.
 
 Show options
 
-    -p, --patch                Show the actual patch diff
+    -p, --patch, --diff        Show the patch diff, rendered from storage
+        --check-merge          Test-merge the patch into its merge target, and report conflicts
+        --range-diff <old> <new>
+                               Compare the commits of two revisions, like `git range-diff`
 
 Open/Update options
 
         --draft                Open patch in draft mode
+        --stack-on <patch-id>  Stack the patch on another patch
+        --scope <path>         Declare a path affected by the patch (may be repeated)
+        --rebase               Rebase the branch onto the patch base before updating
     -q, --quiet                Supress most output, only print the revision id
         --[no-]announce        Announce patch to network (default: false)
         --[no-]push            Push patch head to storage (default: true)
@@ -69,13 +150,43 @@ List options
         --merged               Show only merged patches
         --open                 Show only open patches (default)
         --draft                Show only draft patches
+        --label <name>         Show only patches with the given label (may be repeated)
+        --path <path>          Show only patches affecting the given path (may be repeated)
+        --all-repos            List patches of all repositories in storage
 
 Ready options
 
         --undo                 Convert a patch back to a draft
 
+Split options
+
+        --at <commit>          End a part of the stack at the given commit (may be repeated)
+    -q, --quiet                Only print the ids of the new patches
+
+Review options
+
+        --accept               Accept the revision
+        --reject               Reject the revision
+        --revision <id>        Review the given revision (default: latest)
+        --inline <comment>     Comment on lines of a file, as `<path>:<start>[-<end>]:<comment>`
+                               (may be repeated)
+    -m, --message [<string>]   Provide a review summary (default: prompt)
+        --no-message           Leave the review summary blank
+
+Edit options
+
+    -m, --message [<string>]   Provide the patch title and description (default: prompt)
+
+Comment options
+
+        --revision <id>        Comment on the given revision (default: latest)
+        --reply-to <comment>   Reply to the given comment
+    -m, --message [<string>]   Provide the comment message (default: prompt)
+    -q, --quiet                Only print the id of the new comment
+
 Other options
 
+        --repo <rid>           Operate on the given repository (default: cwd)
         --help                 Print help
 "#,
 };
@@ -89,6 +200,11 @@ pub enum OperationName {
     Delete,
     Checkout,
     Ready,
+    Split,
+    Review,
+    Comment,
+    Scope,
+    Edit,
     #[default]
     List,
 }
@@ -99,15 +215,20 @@ pub enum Operation {
         message: Message,
         draft: bool,
         quiet: bool,
+        stack_on: Option<Rev>,
+        scope: Vec<PathBuf>,
     },
     Show {
-        patch_id: Rev,
+        patch_id: Option<Rev>,
         diff: bool,
+        check_merge: bool,
+        range_diff: Option<(Rev, Rev)>,
     },
     Update {
         patch_id: Option<Rev>,
         message: Message,
         quiet: bool,
+        rebase: bool,
     },
     Archive {
         patch_id: Rev,
@@ -116,20 +237,51 @@ pub enum Operation {
         patch_id: Rev,
         undo: bool,
     },
+    Split {
+        patch_id: Rev,
+        at: Vec<Rev>,
+        quiet: bool,
+    },
+    Review {
+        patch_id: Rev,
+        revision: Option<Rev>,
+        verdict: Option<patch::Verdict>,
+        message: Message,
+        inline: Vec<review::Inline>,
+    },
+    Comment {
+        patch_id: Rev,
+        revision: Option<Rev>,
+        reply_to: Option<thread::CommentId>,
+        message: Message,
+        quiet: bool,
+    },
+    Scope {
+        patch_id: Rev,
+        paths: Vec<PathBuf>,
+    },
+    Edit {
+        patch_id: Rev,
+        message: Message,
+    },
     Delete {
         patch_id: Rev,
     },
     Checkout {
-        patch_id: Rev,
+        patch_id: Option<Rev>,
     },
     List {
         filter: Option<patch::State>,
+        labels: Vec<Tag>,
+        paths: Vec<PathBuf>,
+        all_repos: bool,
     },
 }
 
 #[derive(Debug)]
 pub struct Options {
     pub op: Operation,
+    pub repo: Option<Id>,
     pub fetch: bool,
     pub announce: bool,
     pub push: bool,
@@ -140,6 +292,7 @@ impl Args for Options {
     fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
         use lexopt::prelude::*;
 
+        let args = term::query::expand(args)?;
         let mut parser = lexopt::Parser::from_args(args);
         let mut op: Option<OperationName> = None;
         let mut verbose = false;
@@ -150,9 +303,23 @@ impl Args for Options {
         let mut push = true;
         let mut filter = Some(patch::State::Open);
         let mut diff = false;
+        let mut check_merge = false;
+        let mut range_diff = None;
         let mut draft = false;
         let mut undo = false;
         let mut quiet = false;
+        let mut repo = None;
+        let mut all_repos = false;
+        let mut labels = Vec::new();
+        let mut paths = Vec::new();
+        let mut scope = Vec::new();
+        let mut stack_on = None;
+        let mut at = Vec::new();
+        let mut rebase = false;
+        let mut revision = None;
+        let mut verdict = None;
+        let mut inline = Vec::new();
+        let mut reply_to = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -176,9 +343,6 @@ impl Args for Options {
                 Long("announce") => {
                     announce = true;
                 }
-                Long("no-announce") => {
-                    announce = false;
-                }
                 Long("push") => {
                     push = true;
                 }
@@ -190,22 +354,79 @@ impl Args for Options {
                 Long("draft") if op == Some(OperationName::Open) => {
                     draft = true;
                 }
+                Long("rebase") if op == Some(OperationName::Update) => {
+                    rebase = true;
+                }
+                Long("stack-on") if op == Some(OperationName::Open) => {
+                    let val = parser.value()?;
+                    stack_on = Some(Rev::from(string(&val)));
+                }
+                Long("scope") if op == Some(OperationName::Open) => {
+                    let val = parser.value()?;
+                    scope.push(PathBuf::from(val));
+                }
                 Long("quiet") | Short('q')
-                    if op == Some(OperationName::Open) || op == Some(OperationName::Update) =>
+                    if op == Some(OperationName::Open)
+                        || op == Some(OperationName::Update)
+                        || op == Some(OperationName::Split)
+                        || op == Some(OperationName::Comment) =>
                 {
                     quiet = true;
                 }
 
                 // Show options.
-                Long("patch") | Short('p') if op == Some(OperationName::Show) => {
+                Long("patch") | Long("diff") | Short('p') if op == Some(OperationName::Show) => {
                     diff = true;
                 }
+                Long("check-merge") if op == Some(OperationName::Show) => {
+                    check_merge = true;
+                }
+                Long("range-diff") if op == Some(OperationName::Show) => {
+                    let old = Rev::from(string(&parser.value()?));
+                    let new = Rev::from(string(&parser.value()?));
+                    range_diff = Some((old, new));
+                }
 
                 // Ready options.
                 Long("undo") if op == Some(OperationName::Ready) => {
                     undo = true;
                 }
 
+                // Split options.
+                Long("at") if op == Some(OperationName::Split) => {
+                    let val = parser.value()?;
+                    at.push(Rev::from(string(&val)));
+                }
+
+                // Review options.
+                Long("accept") if op == Some(OperationName::Review) && verdict.is_none() => {
+                    verdict = Some(patch::Verdict::Accept);
+                }
+                Long("reject") if op == Some(OperationName::Review) && verdict.is_none() => {
+                    verdict = Some(patch::Verdict::Reject);
+                }
+                Long("revision")
+                    if op == Some(OperationName::Review) || op == Some(OperationName::Comment) =>
+                {
+                    let val = parser.value()?;
+                    revision = Some(Rev::from(string(&val)));
+                }
+                Long("inline") if op == Some(OperationName::Review) => {
+                    let val = parser.value()?;
+                    inline.push(string(&val).parse()?);
+                }
+
+                // Comment options.
+                Long("reply-to") if op == Some(OperationName::Comment) => {
+                    let val = parser.value()?;
+                    let val = string(&val);
+
+                    reply_to = Some(
+                        val.parse()
+                            .map_err(|_| anyhow!("invalid comment id `{val}`"))?,
+                    );
+                }
+
                 // List options.
                 Long("all") => {
                     filter = None;
@@ -222,8 +443,24 @@ impl Args for Options {
                 Long("open") => {
                     filter = Some(patch::State::Open);
                 }
+                Long("label") => {
+                    let val = parser.value()?;
+                    let val = string(&val);
+
+                    labels.push(Tag::from_str(&val)?);
+                }
+                Long("path") => {
+                    let val = parser.value()?;
+                    paths.push(PathBuf::from(val));
+                }
+                Long("all-repos") => {
+                    all_repos = true;
+                }
 
                 // Common.
+                Long("repo") => {
+                    repo = Some(term::args::rid(&parser.value()?)?);
+                }
                 Long("verbose") | Short('v') => {
                     verbose = true;
                 }
@@ -240,6 +477,11 @@ impl Args for Options {
                     "c" | "checkout" => op = Some(OperationName::Checkout),
                     "a" | "archive" => op = Some(OperationName::Archive),
                     "y" | "ready" => op = Some(OperationName::Ready),
+                    "split" => op = Some(OperationName::Split),
+                    "review" => op = Some(OperationName::Review),
+                    "comment" => op = Some(OperationName::Comment),
+                    "scope" => op = Some(OperationName::Scope),
+                    "e" | "edit" => op = Some(OperationName::Edit),
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
                 Value(val)
@@ -250,6 +492,11 @@ impl Args for Options {
                             Some(OperationName::Delete),
                             Some(OperationName::Archive),
                             Some(OperationName::Ready),
+                            Some(OperationName::Split),
+                            Some(OperationName::Review),
+                            Some(OperationName::Comment),
+                            Some(OperationName::Scope),
+                            Some(OperationName::Edit),
                             Some(OperationName::Checkout),
                         ]
                         .contains(&op) =>
@@ -257,6 +504,9 @@ impl Args for Options {
                     let val = string(&val);
                     patch_id = Some(Rev::from(val));
                 }
+                Value(val) if op == Some(OperationName::Scope) => {
+                    paths.push(PathBuf::from(val));
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
@@ -266,11 +516,20 @@ impl Args for Options {
                 message,
                 draft,
                 quiet,
+                stack_on,
+                scope,
+            },
+            OperationName::List => Operation::List {
+                filter,
+                labels,
+                paths,
+                all_repos,
             },
-            OperationName::List => Operation::List { filter },
             OperationName::Show => Operation::Show {
-                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                patch_id,
                 diff,
+                check_merge,
+                range_diff,
             },
             OperationName::Delete => Operation::Delete {
                 patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
@@ -279,26 +538,53 @@ impl Args for Options {
                 patch_id,
                 message,
                 quiet,
+                rebase,
             },
             OperationName::Archive => Operation::Archive {
                 patch_id: patch_id.ok_or_else(|| anyhow!("a patch id must be provided"))?,
             },
-            OperationName::Checkout => Operation::Checkout {
-                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
-            },
+            OperationName::Checkout => Operation::Checkout { patch_id },
             OperationName::Ready => Operation::Ready {
                 patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
                 undo,
             },
+            OperationName::Split => Operation::Split {
+                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                at,
+                quiet,
+            },
+            OperationName::Review => Operation::Review {
+                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                revision,
+                verdict,
+                message,
+                inline,
+            },
+            OperationName::Comment => Operation::Comment {
+                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                revision,
+                reply_to,
+                message,
+                quiet,
+            },
+            OperationName::Scope => Operation::Scope {
+                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                paths,
+            },
+            OperationName::Edit => Operation::Edit {
+                patch_id: patch_id.ok_or_else(|| anyhow!("a patch must be provided"))?,
+                message,
+            },
         };
 
         Ok((
             Options {
                 op,
+                repo,
                 fetch,
                 push,
                 verbose,
-                announce,
+                announce: announce && term::announce::is_enabled(),
             },
             vec![],
         ))
@@ -306,16 +592,33 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let (workdir, id) = radicle::rad::cwd()
-        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
-
     let profile = ctx.profile()?;
+
+    if let Operation::List {
+        filter,
+        ref labels,
+        ref paths,
+        all_repos: true,
+    } = options.op
+    {
+        return list::run_all(&profile, filter, labels, paths);
+    }
+    let (workdir, id) = project::resolve(options.repo)?;
+    let working_copy = || {
+        workdir
+            .as_ref()
+            .ok_or_else(|| anyhow!("this operation must be run in the context of a working copy"))
+    };
     let repository = profile.storage.repository(id)?;
 
     transport::local::register(profile.storage.clone());
 
     if options.fetch {
-        sync::fetch_all(repository.id(), &mut Node::new(profile.socket()))?;
+        sync::fetch_all(
+            repository.id(),
+            &mut Node::new(profile.socket()),
+            radicle::node::DEFAULT_TIMEOUT,
+        )?;
     }
 
     match options.op {
@@ -323,28 +626,63 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             ref message,
             draft,
             quiet,
+            ref stack_on,
+            ref scope,
         } => {
+            let stack_on = stack_on
+                .as_ref()
+                .map(|id| id.resolve::<PatchId>(&repository.backend))
+                .transpose()?;
             create::run(
                 &repository,
                 &profile,
-                &workdir,
+                working_copy()?,
                 message.clone(),
                 draft,
                 quiet,
+                stack_on,
+                scope.clone(),
                 options,
             )?;
         }
-        Operation::List { filter } => {
-            list::run(&repository, &profile, filter)?;
+        Operation::List {
+            filter,
+            ref labels,
+            ref paths,
+            ..
+        } => {
+            list::run(&repository, &profile, filter, labels, paths)?;
         }
-        Operation::Show { patch_id, diff } => {
-            let patch_id = patch_id.resolve(&repository.backend)?;
-            show::run(&profile, &repository, &workdir, &patch_id, diff)?;
+        Operation::Show {
+            patch_id,
+            diff,
+            check_merge,
+            range_diff,
+        } => {
+            let patch_id = common::patch_or_select(patch_id, &repository, None)?;
+            let range_diff = range_diff
+                .map(|(old, new)| -> anyhow::Result<_> {
+                    Ok((
+                        old.resolve::<patch::RevisionId>(&repository.backend)?,
+                        new.resolve::<patch::RevisionId>(&repository.backend)?,
+                    ))
+                })
+                .transpose()?;
+            show::run(
+                &profile,
+                &repository,
+                workdir.as_ref(),
+                &patch_id,
+                diff,
+                check_merge,
+                range_diff,
+            )?;
         }
         Operation::Update {
             ref patch_id,
             ref message,
             quiet,
+            rebase,
         } => {
             let patch_id = patch_id
                 .as_ref()
@@ -353,10 +691,11 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             update::run(
                 &repository,
                 &profile,
-                &workdir,
+                working_copy()?,
                 patch_id,
                 message.clone(),
                 quiet,
+                rebase,
                 &options,
             )?;
         }
@@ -368,13 +707,87 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
             ready::run(&repository, &profile, &patch_id, undo)?;
         }
+        Operation::Split {
+            ref patch_id,
+            ref at,
+            quiet,
+        } => {
+            let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
+            let at = at
+                .iter()
+                .map(|rev| rev.resolve(&repository.backend))
+                .collect::<Result<Vec<_>, _>>()?;
+            split::run(&repository, &profile, &patch_id, &at, quiet)?;
+        }
+        Operation::Review {
+            ref patch_id,
+            ref revision,
+            verdict,
+            ref message,
+            ref inline,
+        } => {
+            let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
+            let revision = revision
+                .as_ref()
+                .map(|rev| rev.resolve(&repository.backend))
+                .transpose()?;
+            review::run(
+                &repository,
+                &profile,
+                &patch_id,
+                revision,
+                verdict,
+                message.clone(),
+                inline.clone(),
+            )?;
+        }
+        Operation::Comment {
+            ref patch_id,
+            ref revision,
+            reply_to,
+            ref message,
+            quiet,
+        } => {
+            let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
+            let revision = revision
+                .as_ref()
+                .map(|rev| rev.resolve(&repository.backend))
+                .transpose()?;
+            comment::run(
+                &repository,
+                &profile,
+                &patch_id,
+                revision,
+                reply_to,
+                message.clone(),
+                quiet,
+            )?;
+        }
+        Operation::Scope {
+            ref patch_id,
+            ref paths,
+        } => {
+            let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
+            scope::run(&repository, &profile, &patch_id, paths)?;
+        }
+        Operation::Edit {
+            ref patch_id,
+            ref message,
+        } => {
+            let patch_id = patch_id.resolve::<PatchId>(&repository.backend)?;
+            edit::run(&repository, &profile, &patch_id, message.clone())?;
+        }
         Operation::Delete { patch_id } => {
             let patch_id = patch_id.resolve(&repository.backend)?;
             delete::run(&repository, &profile, &patch_id)?;
         }
         Operation::Checkout { patch_id } => {
-            let patch_id = patch_id.resolve(&repository.backend)?;
-            checkout::run(&repository, &workdir, &patch_id)?;
+            let patch_id = common::patch_or_select(
+                patch_id,
+                &repository,
+                Some(&[patch::State::Open, patch::State::Draft]),
+            )?;
+            checkout::run(&repository, working_copy()?, &patch_id)?;
         }
     }
     Ok(())
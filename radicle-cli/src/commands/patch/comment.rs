@@ -0,0 +1,77 @@
+use super::*;
+
+use radicle::cob::patch::RevisionId;
+use radicle::cob::thread::CommentId;
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+/// Comment help message.
+pub const COMMENT_HELP_MSG: &str = r#"
+<!--
+Please enter a comment on the patch revision. Leaving the comment
+blank aborts it.
+
+Markdown supported.
+-->
+"#;
+
+/// Comment on a patch revision, or reply to a comment of its discussion. When replying,
+/// the revision defaults to the one the comment was made on, and otherwise to the latest
+/// revision.
+pub fn run(
+    repository: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    revision_id: Option<RevisionId>,
+    reply_to: Option<CommentId>,
+    message: Message,
+    quiet: bool,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let mut patches = patch::Patches::open(repository)?;
+    let Ok(mut patch) = patches.get_mut(patch_id) else {
+        anyhow::bail!("Patch `{patch_id}` not found");
+    };
+    let revision_id = match (revision_id, reply_to) {
+        (Some(id), _) => {
+            let revision = patch
+                .revision(&id)
+                .ok_or_else(|| anyhow!("revision `{id}` of patch `{patch_id}` not found"))?;
+
+            if let Some(reply_to) = reply_to {
+                if revision.discussion().comment(&reply_to).is_none() {
+                    anyhow::bail!("comment `{reply_to}` not found on revision `{id}`");
+                }
+            }
+            id
+        }
+        (None, Some(reply_to)) => patch
+            .revisions()
+            .find(|(_, r)| r.discussion().comment(&reply_to).is_some())
+            .map(|(id, _)| *id)
+            .ok_or_else(|| anyhow!("comment `{reply_to}` not found on patch `{patch_id}`"))?,
+        (None, None) => patch
+            .latest()
+            .map(|(id, _)| *id)
+            .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?,
+    };
+
+    let message = message.get(COMMENT_HELP_MSG)?;
+    let message = message.replace(COMMENT_HELP_MSG.trim(), "");
+    let message = message.trim();
+    if message.is_empty() {
+        anyhow::bail!("a comment message must be provided");
+    }
+    let comment_id = patch.comment(revision_id, message, reply_to, &signer)?;
+
+    if quiet {
+        term::print(comment_id);
+    } else {
+        term::success!(
+            "Comment {} added to patch {}",
+            term::format::tertiary(comment_id),
+            term::format::tertiary(term::format::cob(patch_id))
+        );
+    }
+    Ok(())
+}
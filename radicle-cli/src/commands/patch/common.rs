@@ -1,16 +1,57 @@
 use anyhow::{anyhow, Context};
 
-use radicle::cob::patch::{Clock, MergeTarget, Patch, PatchId, Patches};
+use radicle::cob::patch::{Clock, Patch, PatchId, Patches, State};
 use radicle::git;
 use radicle::git::raw::Oid;
 use radicle::prelude::*;
 use radicle::storage::git::Repository;
 
+use crate::git::Rev;
 use crate::terminal as term;
 use crate::terminal::args::Error;
 
 use super::Options;
 
+/// Resolve the given patch id. If no id was given, let the user select a patch
+/// interactively, optionally restricting the choice to patches in one of the given states.
+pub fn patch_or_select(
+    patch_id: Option<Rev>,
+    storage: &Repository,
+    states: Option<&[State]>,
+) -> anyhow::Result<PatchId> {
+    let patches = Patches::open(storage)?;
+
+    if let Some(patch_id) = patch_id {
+        return match patch_id.resolve(&storage.backend) {
+            Ok(id) => Ok(id),
+            Err(err) if err.code() == git::raw::ErrorCode::NotFound => {
+                let ids = patches
+                    .all()?
+                    .filter_map(|p| p.ok().map(|(id, _, _)| id.to_string()))
+                    .collect::<Vec<_>>();
+
+                Err(term::args::not_found(
+                    "patch",
+                    patch_id.as_str(),
+                    ids.iter().map(String::as_str),
+                )
+                .into())
+            }
+            Err(err) => Err(err.into()),
+        };
+    }
+    let mut choices = Vec::new();
+
+    for result in patches.all()? {
+        let (id, patch, _) = result?;
+
+        if states.map_or(true, |s| s.contains(&patch.state())) {
+            choices.push((id, patch.title().to_owned()));
+        }
+    }
+    term::io::cob_select("Which patch?", choices, "a patch must be provided")
+}
+
 /// Give the name of the branch or an appropriate error.
 #[inline]
 pub fn branch_name<'a>(branch: &'a git::raw::Branch) -> anyhow::Result<&'a str> {
@@ -52,20 +93,29 @@ pub fn get_merge_target(
     Ok((get_branch(qualified_ref), (*target_oid).into()))
 }
 
-/// Return the [`Oid`] of the merge target.
-pub fn patch_merge_target_oid(target: MergeTarget, repository: &Repository) -> anyhow::Result<Oid> {
-    match target {
-        MergeTarget::Delegates => {
-            if let Ok((_, target)) = repository.head() {
-                Ok(*target)
-            } else {
-                anyhow::bail!(
-                    "failed to determine default branch head for project {}",
-                    repository.id,
-                );
-            }
-        }
-    }
+/// Hint shown when the proposed commits don't satisfy the repository policy.
+pub const POLICY_HINT: &str = "This repository requires commits to be signed-off. To sign-off all the commits of your branch, run `git rebase --signoff <base>`.";
+
+/// Check that the commits proposed with `head` satisfy the repository policy, eg. that they
+/// are signed-off, when required.
+pub fn check_policy(
+    storage: &Repository,
+    workdir: &git::raw::Repository,
+    target: git::Oid,
+    head: git::Oid,
+) -> anyhow::Result<()> {
+    let (_, doc) = storage.identity_doc()?;
+    let policy = doc.verified()?.policy()?;
+    let base = workdir.merge_base(*target, *head)?;
+
+    policy
+        .check(workdir, Some(base.into()), head)
+        .map_err(|err| Error::WithHint {
+            err: err.into(),
+            hint: POLICY_HINT,
+        })?;
+
+    Ok(())
 }
 
 /// Get the diff stats between two commits.
@@ -1,11 +1,11 @@
+use std::path::PathBuf;
+
 use anyhow::anyhow;
 
-use radicle::cob::patch;
+use radicle::cob::patch::PatchId;
 use radicle::git;
-use radicle::node::Handle;
 use radicle::prelude::*;
 use radicle::storage::git::Repository;
-use radicle::Node;
 
 use crate::terminal as term;
 
@@ -86,9 +86,10 @@ pub fn run(
     message: term::patch::Message,
     draft: bool,
     quiet: bool,
+    stack_on: Option<PatchId>,
+    scope: Vec<PathBuf>,
     options: Options,
 ) -> anyhow::Result<()> {
-    let mut patches = patch::Patches::open(storage)?;
     let head_branch = try_branch(workdir.head()?)?;
     let head_branch_name = push_to_storage(workdir, storage, &head_branch, &options)?;
 
@@ -116,54 +117,73 @@ pub fn run(
     // TODO: List matching working copy refs for all targets.
 
     let (title, description) = handle_patch_message(message, workdir, &head_branch)?;
+
+    if !quiet {
+        let similar = profile.cobs()?.similar_patches(storage, &title)?;
+
+        if !term::duplicates("patch", similar.into_iter().map(|(id, p)| (id, p.title))) {
+            anyhow::bail!("patch not opened");
+        }
+    }
     let head_oid = branch_oid(&head_branch)?;
-    let base_oid = workdir.merge_base(*target_oid, *head_oid)?;
     let signer = term::signer(profile)?;
-    let patch = if draft {
-        patches.draft(
-            title,
-            &description,
-            patch::MergeTarget::default(),
-            base_oid,
-            head_oid,
-            &[],
-            &signer,
-        )
-    } else {
-        patches.create(
-            title,
-            &description,
-            patch::MergeTarget::default(),
-            base_oid,
-            head_oid,
-            &[],
-            &signer,
-        )
-    }?;
+    let (patch_id, reviewers) = radicle::api::patch::open(
+        storage,
+        title,
+        description,
+        head_oid,
+        &[],
+        &scope,
+        draft,
+        stack_on,
+        &signer,
+    )
+    .map_err(|err| match err {
+        radicle::api::patch::Error::Policy(err) => term::args::Error::WithHint {
+            err: err.into(),
+            hint: POLICY_HINT,
+        }
+        .into(),
+        err => anyhow::Error::from(err),
+    })?;
 
     if !quiet {
-        term::success!("Patch {} created", term::format::highlight(patch.id));
+        term::success!("Patch {} created", term::format::highlight(patch_id));
+
+        if let Some(base) = stack_on {
+            term::info!(
+                "Stacked on patch {}",
+                term::format::tertiary(term::format::cob(&base))
+            );
+        }
+        if !scope.is_empty() {
+            term::info!(
+                "Scoped to {}",
+                term::format::secondary(
+                    scope
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            );
+        }
+
+        for did in &reviewers {
+            term::info!("Requested review from {}", term::format::tertiary(did));
+        }
         term::blank();
     }
 
     if options.announce {
-        let mut node = Node::new(profile.socket());
-        match node.announce_refs(storage.id()) {
-            Ok(()) => {}
-            Err(e) if e.is_connection_err() => {
-                term::warning("Could not announce patch refs: node is not running");
-            }
-            Err(e) => {
-                return Err(e.into());
-            }
-        }
+        term::announce::changed(storage.id());
     } else if !quiet {
         term::info!("To publish your patch to the network, run:");
         term::indented(term::format::secondary("git push rad"));
     }
 
     if quiet {
-        term::print(patch.id);
+        term::print(patch_id);
     }
     Ok(())
 }
@@ -0,0 +1,67 @@
+use super::*;
+
+use radicle::cob::patch;
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+/// Edit help message.
+pub const EDIT_HELP_MSG: &str = r#"
+<!--
+Please edit the patch message. An empty message aborts
+the edit.
+
+The first line is the patch title. The patch description
+follows, and must be separated with a blank line, just
+like a commit message. Markdown is supported in the title
+and description.
+-->
+"#;
+
+/// Change the title and description of a patch. The editor is opened with the current ones
+/// when no message is given. A message without a description only changes the title.
+pub fn run(
+    repository: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    message: Message,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let mut patches = patch::Patches::open(repository)?;
+    let Ok(mut patch) = patches.get_mut(patch_id) else {
+        anyhow::bail!("Patch `{patch_id}` not found");
+    };
+
+    // A single `-m` only gives a title, and shouldn't clear the description.
+    let title_only = matches!(&message, Message::Text(m) if !m.trim().contains("\n\n"));
+    let message = message.get(&format!(
+        "{}\n\n{}\n{EDIT_HELP_MSG}",
+        patch.title(),
+        patch.description()
+    ))?;
+    let message = message.replace(EDIT_HELP_MSG.trim(), "");
+    let (title, description) = match message.split_once("\n\n") {
+        Some((title, description)) => (title, description),
+        None if title_only => (message.as_str(), patch.description()),
+        None => (message.as_str(), ""),
+    };
+    let (title, description) = (title.trim(), description.trim());
+
+    if title.is_empty() {
+        anyhow::bail!("a patch title must be provided");
+    }
+    if title == patch.title() && description == patch.description() {
+        term::info!(
+            "Patch {} is unchanged",
+            term::format::tertiary(term::format::cob(patch_id))
+        );
+        return Ok(());
+    }
+    let target = patch.target();
+    patch.edit(title.to_owned(), description.to_owned(), target, &signer)?;
+
+    term::success!(
+        "Patch {} edited",
+        term::format::tertiary(term::format::cob(patch_id))
+    );
+    Ok(())
+}
@@ -1,10 +1,17 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
 use anyhow::anyhow;
 
+use radicle::cob::cache::PatchSummary;
+use radicle::cob::common::Tag;
 use radicle::cob::patch;
-use radicle::cob::patch::{Patch, PatchId, Patches, Verdict};
+use radicle::cob::patch::{Patch, PatchId, Verdict};
+use radicle::git;
 use radicle::prelude::*;
 use radicle::profile::Profile;
 use radicle::storage::git::Repository;
+use radicle::storage::ReadStorage as _;
 
 use crate::terminal as term;
 use term::table::{Table, TableOptions};
@@ -12,44 +19,214 @@ use term::Element as _;
 
 use super::common;
 
-/// List patches.
+/// List patches. If `labels` are given, only the patches with all of them are listed, and
+/// if `paths` are given, only the patches affecting one of them.
 pub fn run(
     repository: &Repository,
     profile: &Profile,
     filter: Option<patch::State>,
+    labels: &[Tag],
+    paths: &[PathBuf],
+) -> anyhow::Result<()> {
+    let mut cache = profile.cobs()?;
+    let all = cache.patches(repository)?;
+    let patches = patches(repository, &all, profile, filter, labels, paths)?;
+
+    if patches.is_empty() {
+        term::print(term::format::italic("Nothing to show."));
+        return Ok(());
+    }
+    print(repository, profile, patches, &all);
+
+    Ok(())
+}
+
+/// List patches of all repositories in storage.
+pub fn run_all(
+    profile: &Profile,
+    filter: Option<patch::State>,
+    labels: &[Tag],
+    paths: &[PathBuf],
 ) -> anyhow::Result<()> {
+    let mut empty = true;
+    let mut cache = profile.cobs()?;
+
+    for rid in profile.storage.repositories()? {
+        // Nb. A repository that fails to load shouldn't keep us from listing the patches of
+        // the other repositories.
+        let repository = match profile.storage.repository(rid) {
+            Ok(repository) => repository,
+            Err(err) => {
+                term::warning(&format!("failed to load repository '{rid}': {err}"));
+                continue;
+            }
+        };
+        let all = match cache.patches(&repository) {
+            Ok(all) => all,
+            Err(err) => {
+                term::warning(&format!("failed to load patches of '{rid}': {err}"));
+                continue;
+            }
+        };
+        let patches = match patches(&repository, &all, profile, filter, labels, paths) {
+            Ok(patches) => patches,
+            Err(err) => {
+                term::warning(&format!("failed to filter patches of '{rid}': {err}"));
+                continue;
+            }
+        };
+
+        if patches.is_empty() {
+            continue;
+        }
+        let name = repository
+            .project()
+            .map(|p| p.name().to_owned())
+            .unwrap_or_default();
+
+        if !empty {
+            term::blank();
+        }
+        term::info!(
+            "{} {}",
+            term::format::bold(name),
+            term::format::tertiary(rid.urn())
+        );
+        print(&repository, profile, patches, &all);
+
+        empty = false;
+    }
+    if empty {
+        term::print(term::format::italic("Nothing to show."));
+    }
+    Ok(())
+}
+
+/// Get the patches matching the filter, labels and paths, the user's own patches first.
+fn patches<'a>(
+    repository: &Repository,
+    all: &'a [(PatchId, PatchSummary)],
+    profile: &Profile,
+    filter: Option<patch::State>,
+    labels: &[Tag],
+    paths: &[PathBuf],
+) -> anyhow::Result<Vec<(PatchId, &'a PatchSummary)>> {
     let me = *profile.id();
-    let patches = Patches::open(repository)?;
-    let all = patches.all()?;
 
     // Patches the user authored.
     let mut own = Vec::new();
     // Patches other users authored.
     let mut other = Vec::new();
 
-    for patch in all {
-        let (id, patch, _) = patch?;
-
+    for (id, patch) in all {
         if let Some(filter) = filter {
-            if patch.state() != filter {
+            if patch.state != filter {
                 continue;
             }
         }
-        if patch.author().id().as_key() == &me {
-            own.push((id, patch));
+        if !labels.iter().all(|l| patch.tags.contains(l)) {
+            continue;
+        }
+        if !paths.is_empty() && !affects(repository, patch, paths)? {
+            continue;
+        }
+        if profile.config.is_blocked(&patch.author) {
+            continue;
+        }
+        if patch.author.as_key() == &me {
+            own.push((*id, patch));
         } else {
-            other.push((id, patch));
+            other.push((*id, patch));
         }
     }
+    own.extend(other);
 
-    if own.is_empty() && other.is_empty() {
-        term::print(term::format::italic("Nothing to show."));
-        return Ok(());
+    Ok(own)
+}
+
+/// Check whether a patch affects one of the given paths: its declared scope overlaps with
+/// one of them, or if it has no scope, its latest revision changes a path under one of them.
+fn affects(
+    repository: &Repository,
+    patch: &PatchSummary,
+    paths: &[PathBuf],
+) -> anyhow::Result<bool> {
+    if !patch.scope.is_empty() {
+        return Ok(paths.iter().any(|p| patch::overlaps(&patch.scope, p)));
+    }
+    let raw = repository.raw();
+    let base = raw.find_commit(*patch.base)?.tree()?;
+    let head = raw.find_commit(*patch.head)?.tree()?;
+    let diff = raw.diff_tree_to_tree(Some(&base), Some(&head), None)?;
+    let changed = diff
+        .deltas()
+        .flat_map(|d| [d.old_file().path(), d.new_file().path()])
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+
+    Ok(paths.iter().any(|p| patch::overlaps(&changed, p)))
+}
+
+/// Order patches so that patches stacked on another patch follow it, and get the depth
+/// of each patch in its stack. Patches stacked on a patch that isn't listed are shown at
+/// the top level.
+fn stacked(patches: Vec<(PatchId, &PatchSummary)>) -> Vec<(usize, PatchId, &PatchSummary)> {
+    let ids = patches.iter().map(|(id, _)| *id).collect::<BTreeSet<_>>();
+    let mut children = BTreeMap::<PatchId, Vec<usize>>::new();
+    let mut roots = Vec::new();
+
+    for (ix, (_, patch)) in patches.iter().enumerate() {
+        match patch.stacked_on {
+            Some(base) if ids.contains(&base) => children.entry(base).or_default().push(ix),
+            _ => roots.push(ix),
+        }
+    }
+
+    let mut order = Vec::with_capacity(patches.len());
+    let mut visited = vec![false; patches.len()];
+    let mut stack = roots
+        .into_iter()
+        .rev()
+        .map(|ix| (0, ix))
+        .collect::<Vec<_>>();
+
+    while let Some((depth, ix)) = stack.pop() {
+        if visited[ix] {
+            continue;
+        }
+        visited[ix] = true;
+        order.push((depth, ix));
+
+        if let Some(children) = children.get(&patches[ix].0) {
+            stack.extend(children.iter().rev().map(|c| (depth + 1, *c)));
+        }
     }
+    // Patches that are part of a cycle are never reached from a root.
+    order.extend(
+        (0..patches.len())
+            .filter(|ix| !visited[*ix])
+            .map(|ix| (0, ix)),
+    );
+
+    let mut patches = patches.into_iter().map(Some).collect::<Vec<_>>();
+    order
+        .into_iter()
+        .filter_map(|(depth, ix)| patches[ix].take().map(|(id, p)| (depth, id, p)))
+        .collect()
+}
 
-    let mut table = Table::<9, term::Line>::new(TableOptions {
+/// Print a table of patches.
+fn print(
+    repository: &Repository,
+    profile: &Profile,
+    patches: Vec<(PatchId, &PatchSummary)>,
+    all: &[(PatchId, PatchSummary)],
+) {
+    let me = *profile.id();
+    let mut table = Table::<10, term::Line>::new(TableOptions {
         spacing: 2,
-        border: Some(term::colors::FAINT),
+        border: Some(term::theme::current().faint),
         ..TableOptions::default()
     });
 
@@ -60,6 +237,7 @@ pub fn run(
         term::format::bold(String::from("Author")).into(),
         term::format::bold(String::new()).into(),
         term::format::bold(String::from("Head")).into(),
+        term::format::bold(String::from("Commits")).into(),
         term::format::bold(String::from("+")).into(),
         term::format::bold(String::from("-")).into(),
         term::format::bold(String::from("Opened")).into(),
@@ -67,16 +245,10 @@ pub fn run(
     table.divider();
 
     let mut errors = Vec::new();
-    for (id, patch) in &mut own {
-        match row(&me, id, patch, repository) {
+    for (depth, id, patch) in &stacked(patches) {
+        match row(&me, id, patch, *depth, repository, all) {
             Ok(r) => table.push(r),
-            Err(e) => errors.push((patch.title(), id, e.to_string())),
-        }
-    }
-    for (id, patch) in &mut other {
-        match row(&me, id, patch, repository) {
-            Ok(r) => table.push(r),
-            Err(e) => errors.push((patch.title(), id, e.to_string())),
+            Err(e) => errors.push((&patch.title, id, e.to_string())),
         }
     }
     table.print();
@@ -89,23 +261,24 @@ pub fn run(
             ));
         }
     }
-
-    Ok(())
 }
 
-/// Patch row.
+/// Patch row. Patches stacked on other patches are indented according to their `depth`.
 pub fn row(
     whoami: &PublicKey,
     id: &PatchId,
-    patch: &Patch,
+    patch: &PatchSummary,
+    depth: usize,
     repository: &Repository,
-) -> anyhow::Result<[term::Line; 9]> {
-    let state = patch.state();
-    let (_, revision) = patch
-        .latest()
-        .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?;
-    let stats = common::diff_stats(repository.raw(), revision.base(), &revision.head())?;
-    let author = patch.author().id;
+    all: &[(PatchId, PatchSummary)],
+) -> anyhow::Result<[term::Line; 10]> {
+    let state = patch.state;
+    let stats = common::diff_stats(repository.raw(), &patch.base, &patch.head)?;
+    let upstream = upstream(repository, patch, all)?;
+    let (ahead, behind) = repository
+        .raw()
+        .graph_ahead_behind(*patch.head, *upstream)?;
+    let author = patch.author;
 
     Ok([
         match state {
@@ -115,29 +288,65 @@ pub fn row(
             patch::State::Merged { .. } => term::format::primary("✔").into(),
         },
         term::format::tertiary(term::format::cob(id)).into(),
-        term::format::default(patch.title().to_owned()).into(),
+        if depth > 0 {
+            term::Line::spaced([
+                term::format::dim(format!("{}└─", "   ".repeat(depth - 1))).into(),
+                term::format::default(patch.title.clone()).into(),
+            ])
+        } else {
+            term::format::default(patch.title.clone()).into()
+        },
         term::format::did(&author).dim().into(),
         if author.as_key() == whoami {
             term::format::primary("(you)".to_owned()).into()
         } else {
             term::format::default(String::new()).into()
         },
-        term::format::secondary(term::format::oid(revision.head())).into(),
+        term::format::secondary(term::format::oid(patch.head)).into(),
+        term::Line::spaced([
+            term::format::dim(format!("↑{ahead}")).into(),
+            if behind > 0 {
+                term::format::yellow(format!("↓{behind}")).into()
+            } else {
+                term::format::dim(format!("↓{behind}")).into()
+            },
+        ]),
         term::format::positive(format!("+{}", stats.insertions())).into(),
         term::format::negative(format!("-{}", stats.deletions())).into(),
-        term::format::timestamp(&patch.timestamp())
+        term::format::timestamp(&patch.timestamp)
             .dim()
             .italic()
             .into(),
     ])
 }
 
+/// Get the commit a patch is expected to be based on, like [`radicle::api::patch::upstream`]
+/// does, using the summaries of the other patches.
+fn upstream(
+    repository: &Repository,
+    patch: &PatchSummary,
+    all: &[(PatchId, PatchSummary)],
+) -> anyhow::Result<git::Oid> {
+    if let Some(id) = patch.stacked_on {
+        let (_, base) = all
+            .iter()
+            .find(|(other, _)| *other == id)
+            .ok_or_else(|| anyhow!("patch {id} was not found"))?;
+
+        if base.state != patch::State::Merged {
+            return Ok(base.head);
+        }
+    }
+    Ok(radicle::api::patch::target(repository, patch.target)?)
+}
+
 pub fn timeline(
-    whoami: &PublicKey,
+    profile: &Profile,
     patch_id: &PatchId,
     patch: &Patch,
     repository: &Repository,
 ) -> anyhow::Result<Vec<term::Line>> {
+    let whoami = profile.id();
     let you = patch.author().id().as_key() == whoami;
     let mut open = term::Line::spaced([
         term::format::positive("●").into(),
@@ -172,6 +381,9 @@ pub fn timeline(
         }
 
         for merge in revision.merges() {
+            if profile.config.is_blocked(&Did::from(merge.node)) {
+                continue;
+            }
             let peer = repository.remote(&merge.node)?;
             let mut badges = Vec::new();
 
@@ -194,6 +406,9 @@ pub fn timeline(
             ));
         }
         for (reviewer, review) in revision.reviews() {
+            if profile.config.is_blocked(&Did::from(*reviewer)) {
+                continue;
+            }
             let verdict = review.verdict();
             let verdict_symbol = match verdict {
                 Some(Verdict::Accept) => term::format::positive("✓"),
@@ -0,0 +1,200 @@
+use super::*;
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use radicle::cob;
+use radicle::cob::patch::{CodeComment, CodeLocation, RevisionId, Verdict};
+use radicle::git;
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+/// Review help message.
+pub const REVIEW_HELP_MSG: &str = r#"
+<!--
+You may enter a review summary here. If you leave this blank,
+no summary will be attached to your review.
+
+Markdown supported.
+-->
+"#;
+
+/// An inline comment given on the command line, as `<path>:<start>[-<end>]:<comment>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inline {
+    /// Path of the file commented on.
+    pub path: PathBuf,
+    /// Lines commented on, starting at one. The end is exclusive.
+    pub lines: Range<usize>,
+    /// Comment.
+    pub comment: String,
+}
+
+impl FromStr for Inline {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Nb. paths may contain colons, so the line range is the first `:<range>:` found.
+        for (ix, _) in s.match_indices(':') {
+            let (path, rest) = (&s[..ix], &s[ix + 1..]);
+            let Some((range, comment)) = rest.split_once(':') else {
+                break;
+            };
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) else {
+                continue;
+            };
+            if path.is_empty() {
+                break;
+            }
+            if start == 0 || end < start {
+                anyhow::bail!("invalid line range `{range}` in inline comment");
+            }
+            if comment.trim().is_empty() {
+                anyhow::bail!("inline comment on `{path}:{range}` is empty");
+            }
+            return Ok(Self {
+                path: PathBuf::from(path),
+                lines: start..end + 1,
+                comment: comment.trim().to_owned(),
+            });
+        }
+        Err(anyhow!(
+            "invalid inline comment `{s}`, expected `<path>:<start>[-<end>]:<comment>`"
+        ))
+    }
+}
+
+/// Review a patch revision, with an optional verdict, summary and inline comments. Inline
+/// comments are made on the files of the revision head.
+pub fn run(
+    repository: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    revision_id: Option<RevisionId>,
+    verdict: Option<Verdict>,
+    message: Message,
+    inline: Vec<Inline>,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let mut patches = patch::Patches::open(repository)?;
+    let Ok(mut patch) = patches.get_mut(patch_id) else {
+        anyhow::bail!("Patch `{patch_id}` not found");
+    };
+    let (revision_id, revision) = match revision_id {
+        Some(id) => {
+            let revision = patch
+                .revision(&id)
+                .ok_or_else(|| anyhow!("revision `{id}` of patch `{patch_id}` not found"))?;
+            (id, revision)
+        }
+        None => patch
+            .latest()
+            .map(|(id, revision)| (*id, revision))
+            .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?,
+    };
+    let head = revision.head();
+    let tree = repository.backend.find_commit(*head)?.tree()?;
+    let timestamp = cob::Timestamp::now();
+
+    let inline = inline
+        .into_iter()
+        .map(|c| code_comment(repository, &tree, head, c, timestamp))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let message = message.get(REVIEW_HELP_MSG)?;
+    let message = message.replace(REVIEW_HELP_MSG.trim(), "");
+    let message = message.trim();
+    let summary = (!message.is_empty()).then(|| message.to_owned());
+    let comments = inline.len();
+
+    patch.review(revision_id, verdict, summary, inline, &signer)?;
+
+    let patch_id = term::format::tertiary(term::format::cob(patch_id));
+    match verdict {
+        Some(Verdict::Accept) => {
+            term::success!("Patch {} {}", patch_id, term::format::highlight("accepted"));
+        }
+        Some(Verdict::Reject) => {
+            term::success!("Patch {} {}", patch_id, term::format::negative("rejected"));
+        }
+        None => {
+            term::success!("Patch {} reviewed", patch_id);
+        }
+    }
+    if comments > 0 {
+        term::info!("{comments} inline comment(s) added");
+    }
+    Ok(())
+}
+
+/// Check that an inline comment is on an existing file and line range of the given tree, and
+/// turn it into a code comment.
+fn code_comment(
+    repository: &Repository,
+    tree: &git::raw::Tree,
+    commit: git::Oid,
+    inline: Inline,
+    timestamp: cob::Timestamp,
+) -> anyhow::Result<CodeComment> {
+    let Inline {
+        path,
+        lines,
+        comment,
+    } = inline;
+    let entry = tree
+        .get_path(&path)
+        .map_err(|_| anyhow!("file `{}` not found in revision", path.display()))?;
+    let blob = repository
+        .backend
+        .find_blob(entry.id())
+        .map_err(|_| anyhow!("`{}` is not a file in the revision", path.display()))?;
+    let count = String::from_utf8_lossy(blob.content()).lines().count();
+
+    if lines.end - 1 > count {
+        anyhow::bail!(
+            "line {} is out of range for `{}`, which has {count} line(s)",
+            lines.end - 1,
+            path.display()
+        );
+    }
+    let location = CodeLocation {
+        blob: entry.id().into(),
+        path,
+        commit,
+        lines,
+    };
+    Ok(CodeComment::new(location, comment, timestamp))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inline_from_str() {
+        assert_eq!(
+            Inline::from_str("src/main.rs:10-12:Use a constant: it's clearer").unwrap(),
+            Inline {
+                path: PathBuf::from("src/main.rs"),
+                lines: 10..13,
+                comment: String::from("Use a constant: it's clearer"),
+            }
+        );
+        assert_eq!(
+            Inline::from_str("dir:name/file.txt:3:Typo").unwrap(),
+            Inline {
+                path: PathBuf::from("dir:name/file.txt"),
+                lines: 3..4,
+                comment: String::from("Typo"),
+            }
+        );
+        assert!(Inline::from_str("README").is_err());
+        assert!(Inline::from_str("README:1").is_err());
+        assert!(Inline::from_str("README:0:Nope").is_err());
+        assert!(Inline::from_str("README:4-2:Nope").is_err());
+        assert!(Inline::from_str("README:2: ").is_err());
+        assert!(Inline::from_str(":2:Nope").is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+use std::path::PathBuf;
+
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+/// Declare the paths affected by a patch, and request review from the reviewers of these
+/// paths. An empty list of paths clears the scope.
+pub fn run(
+    repository: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    paths: &[PathBuf],
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let reviewers = radicle::api::patch::scope(repository, patch_id, paths, &signer)?;
+
+    if paths.is_empty() {
+        term::success!(
+            "Scope of patch {} cleared",
+            term::format::tertiary(term::format::cob(patch_id))
+        );
+    } else {
+        term::success!(
+            "Scope of patch {} set to {}",
+            term::format::tertiary(term::format::cob(patch_id)),
+            term::format::secondary(
+                paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        );
+    }
+    for did in &reviewers {
+        term::info!("Requested review from {}", term::format::tertiary(did));
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
-use std::process;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
 use radicle::cob::patch;
 use radicle::git;
 use radicle::storage::git::Repository;
@@ -13,29 +15,456 @@ use crate::terminal as term;
 use super::common::*;
 use super::*;
 
-fn show_patch_diff(patch: &patch::Patch, storage: &Repository) -> anyhow::Result<()> {
-    let target_head = patch_merge_target_oid(patch.target(), storage)?;
-    let base_oid = storage.raw().merge_base(target_head, **patch.head())?;
-    let diff = format!("{}..{}", base_oid, patch.head());
+/// The role of a key that is known to the repository, and trusted to sign commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signer {
+    Delegate,
+    Contributor,
+}
 
-    process::Command::new("git")
-        .current_dir(storage.path())
-        .args(["log", "--patch", &diff])
-        .stdout(process::Stdio::inherit())
-        .stderr(process::Stdio::inherit())
-        .spawn()?
-        .wait()?;
+impl std::fmt::Display for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delegate => write!(f, "delegate"),
+            Self::Contributor => write!(f, "contributor"),
+        }
+    }
+}
 
-    Ok(())
+/// Get the keys that commit signatures are verified against: the repository delegates,
+/// and the authors of the patch revisions.
+fn signers(
+    patch: &patch::Patch,
+    storage: &Repository,
+) -> anyhow::Result<BTreeMap<PublicKey, Signer>> {
+    let (_, doc) = storage.identity_doc()?;
+    let mut signers = BTreeMap::new();
+
+    for (_, revision) in patch.revisions() {
+        signers.insert(*revision.author().id().as_key(), Signer::Contributor);
+    }
+    for delegate in doc.delegates.iter() {
+        signers.insert(*delegate.as_key(), Signer::Delegate);
+    }
+    Ok(signers)
+}
+
+/// Describe the signature of a commit, verified against the given signers.
+fn signature(
+    repo: &git::raw::Repository,
+    oid: git::Oid,
+    signers: &BTreeMap<PublicKey, Signer>,
+) -> term::Line {
+    match git::commit_signer(repo, oid) {
+        Ok(None) => term::Line::new(term::format::dim("unsigned")),
+        Ok(Some(key)) => {
+            let did = term::format::did(&Did::from(key));
+
+            match signers.get(&key) {
+                Some(signer) => term::Line::new(term::format::positive("verified"))
+                    .item(format!(" ({signer} {did})")),
+                None => term::Line::new(term::format::negative("unverified"))
+                    .item(format!(" (unknown key {did})")),
+            }
+        }
+        Err(e) => term::Line::new(term::format::negative("unverified")).item(format!(" ({e})")),
+    }
+}
+
+/// Render the commits of a patch with their diffs, like `git log --patch` would, from
+/// the objects in storage. Each commit's signature is verified against the keys known
+/// to the repository.
+///
+/// Since no working copy or `git` binary is needed, this also works on seeds.
+fn patch_diff(patch: &patch::Patch, storage: &Repository) -> anyhow::Result<String> {
+    let repo = storage.raw();
+    let signers = signers(patch, storage)?;
+    let upstream = radicle::api::patch::upstream(storage, patch)?;
+    let base = repo.merge_base(*upstream, **patch.head())?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git::raw::Sort::TIME)?;
+    revwalk.push(**patch.head())?;
+    revwalk.hide(base)?;
+
+    let mut output = String::new();
+    for (i, oid) in revwalk.enumerate() {
+        let commit = repo.find_commit(oid?)?;
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&commit_diff(repo, &commit, &signers)?);
+    }
+    Ok(output)
+}
+
+/// Render a single commit, followed by a summary of its changes and its diff.
+fn commit_diff(
+    repo: &git::raw::Repository,
+    commit: &git::raw::Commit,
+    signers: &BTreeMap<PublicKey, Signer>,
+) -> anyhow::Result<String> {
+    let tree = commit.tree()?;
+    let parent = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
+    diff.find_similar(None)?;
+
+    let author = commit.author();
+    let mut output = String::new();
+
+    writeln!(
+        output,
+        "{}",
+        term::format::yellow(format!("commit {}", commit.id()))
+    )?;
+    writeln!(
+        output,
+        "Author: {} <{}>",
+        author.name().unwrap_or_default(),
+        author.email().unwrap_or_default()
+    )?;
+    writeln!(output, "Date:   {}", commit_time(&commit.time()))?;
+    writeln!(
+        output,
+        "Signature: {}",
+        signature(repo, commit.id().into(), signers)
+    )?;
+    writeln!(output)?;
+
+    for line in commit.message().unwrap_or_default().trim_end().lines() {
+        if line.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, "    {line}")?;
+        }
+    }
+    writeln!(output)?;
+
+    for line in diff_summary(&diff)? {
+        writeln!(output, " {line}")?;
+    }
+    writeln!(output)?;
+
+    diff.print(git::raw::DiffFormat::Patch, |_, _, line| {
+        let origin = line.origin();
+        let content = String::from_utf8_lossy(line.content());
+        let content = content.trim_end_matches('\n');
+
+        for text in content.split('\n') {
+            let text = match origin {
+                '+' | '-' | ' ' => format!("{origin}{text}"),
+                _ => text.to_owned(),
+            };
+            let text = match origin {
+                '+' | '>' => term::format::positive(text),
+                '-' | '<' => term::format::negative(text),
+                'F' => term::format::bold(text),
+                'H' => term::format::tertiary(text),
+                'B' => term::format::yellow(text),
+                _ => term::format::default(text),
+            };
+            output.push_str(&text.to_string());
+            output.push('\n');
+        }
+        true
+    })?;
+
+    Ok(output)
+}
+
+/// Summarize the changes in a diff, including the file changes that don't show up
+/// as line changes, such as binary files and file mode changes.
+fn diff_summary(diff: &git::raw::Diff) -> anyhow::Result<Vec<term::Line>> {
+    let stats = diff.stats()?;
+    let mut lines = vec![diff_stats(&stats)];
+
+    for i in 0..diff.deltas().len() {
+        let Some(patch) = git::raw::Patch::from_diff(diff, i)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let old = delta.old_file();
+        let new = delta.new_file();
+        let path = new
+            .path()
+            .or(old.path())
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let old_mode = u32::from(old.mode());
+        let new_mode = u32::from(new.mode());
+
+        let summary = match delta.status() {
+            git::raw::Delta::Added => Some(format!("create mode {new_mode:06o} {path}")),
+            git::raw::Delta::Deleted => Some(format!("delete mode {old_mode:06o} {path}")),
+            git::raw::Delta::Renamed => {
+                let from = old
+                    .path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                Some(format!("rename {from} => {path}"))
+            }
+            _ if old_mode != new_mode => Some(format!(
+                "mode change {old_mode:06o} => {new_mode:06o} {path}"
+            )),
+            _ => None,
+        };
+        if let Some(summary) = summary {
+            lines.push(term::Line::new(term::format::dim(summary)));
+        }
+        if delta.flags().is_binary() {
+            lines.push(term::Line::new(term::format::yellow(format!(
+                "binary {path}"
+            ))));
+        }
+    }
+    Ok(lines)
+}
+
+/// Format diff stats the way `git` does, eg. "1 file changed, 2 insertions(+)".
+fn diff_stats(stats: &git::raw::DiffStats) -> term::Line {
+    let plural = |n: usize, one: &str, many: &str| {
+        if n == 1 {
+            format!("{n} {one}")
+        } else {
+            format!("{n} {many}")
+        }
+    };
+    let (insertions, deletions) = (stats.insertions(), stats.deletions());
+    let mut line = term::Line::new(plural(
+        stats.files_changed(),
+        "file changed",
+        "files changed",
+    ));
+
+    if insertions > 0 || deletions == 0 {
+        line = line.item(", ").item(term::format::positive(plural(
+            insertions,
+            "insertion(+)",
+            "insertions(+)",
+        )));
+    }
+    if deletions > 0 || insertions == 0 {
+        line = line.item(", ").item(term::format::negative(plural(
+            deletions,
+            "deletion(-)",
+            "deletions(-)",
+        )));
+    }
+    line
+}
+
+/// A commit of a revision, as compared by [`range_diff`].
+struct RangeCommit {
+    oid: git::Oid,
+    summary: String,
+    /// The commit message and diff, uncolored.
+    patch: String,
+}
+
+/// Get the commits of a revision, from its base to its head, oldest first.
+fn range_commits(
+    repo: &git::raw::Repository,
+    revision: &patch::Revision,
+) -> anyhow::Result<Vec<RangeCommit>> {
+    let head = *revision.head();
+    let base = repo.merge_base(**revision.base(), head)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git::raw::Sort::TOPOLOGICAL | git::raw::Sort::REVERSE)?;
+    revwalk.push(head)?;
+    revwalk.hide(base)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let parent = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let mut diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
+        diff.find_similar(None)?;
+
+        let mut patch = commit.message().unwrap_or_default().trim_end().to_owned();
+        patch.push_str("\n\n");
+        diff.print(git::raw::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+
+        commits.push(RangeCommit {
+            oid: commit.id().into(),
+            summary: commit.summary().unwrap_or_default().to_owned(),
+            patch,
+        });
+    }
+    Ok(commits)
+}
+
+/// Compare the commits of two revisions, the way `git range-diff` does.
+///
+/// Commits of the new revision are paired with commits of the old revision that have the
+/// same changes, or failing that, the same summary. Paired commits are shown with `=` if
+/// they are identical, and with `!` followed by a diff of their changes otherwise. Commits
+/// only found in the old revision are shown with `<`, and those only found in the new
+/// revision with `>`.
+fn range_diff(
+    storage: &Repository,
+    old: &patch::Revision,
+    new: &patch::Revision,
+) -> anyhow::Result<String> {
+    let repo = storage.raw();
+    let old = range_commits(repo, old)?;
+    let new = range_commits(repo, new)?;
+
+    compare_ranges(&old, &new)
+}
+
+/// Compare two ranges of commits. See [`range_diff`].
+fn compare_ranges(old: &[RangeCommit], new: &[RangeCommit]) -> anyhow::Result<String> {
+    use similar::{ChangeTag, TextDiff};
+
+    let mut pairs: Vec<Option<usize>> = vec![None; new.len()];
+    let mut paired = vec![false; old.len()];
+    for (j, n) in new.iter().enumerate() {
+        if let Some(i) = (0..old.len()).find(|i| !paired[*i] && old[*i].patch == n.patch) {
+            pairs[j] = Some(i);
+            paired[i] = true;
+        }
+    }
+    for (j, n) in new.iter().enumerate() {
+        if pairs[j].is_some() {
+            continue;
+        }
+        if let Some(i) = (0..old.len()).find(|i| !paired[*i] && old[*i].summary == n.summary) {
+            pairs[j] = Some(i);
+            paired[i] = true;
+        }
+    }
+
+    let side = |ix: Option<usize>, commits: &[RangeCommit]| match ix {
+        Some(ix) => format!("{}:  {}", ix + 1, term::format::oid(commits[ix].oid)),
+        None => String::from("-:  -------"),
+    };
+    let mut output = String::new();
+    let mut removed = (0..old.len()).filter(|i| !paired[*i]).peekable();
+
+    for (j, n) in new.iter().enumerate() {
+        // Show the removed commits that came before the old commit of this pair.
+        while let Some(i) = removed.next_if(|i| pairs[j].map_or(false, |p| *i < p)) {
+            writeln!(
+                output,
+                "{}",
+                term::format::negative(format!(
+                    "{} < {} {}",
+                    side(Some(i), old),
+                    side(None, new),
+                    old[i].summary
+                ))
+            )?;
+        }
+        let Some(i) = pairs[j] else {
+            writeln!(
+                output,
+                "{}",
+                term::format::positive(format!(
+                    "{} > {} {}",
+                    side(None, old),
+                    side(Some(j), new),
+                    n.summary
+                ))
+            )?;
+            continue;
+        };
+        if old[i].patch == n.patch {
+            writeln!(
+                output,
+                "{}",
+                term::format::dim(format!(
+                    "{} = {} {}",
+                    side(Some(i), old),
+                    side(Some(j), new),
+                    n.summary
+                ))
+            )?;
+            continue;
+        }
+        writeln!(
+            output,
+            "{}",
+            term::format::yellow(format!(
+                "{} ! {} {}",
+                side(Some(i), old),
+                side(Some(j), new),
+                n.summary
+            ))
+        )?;
+
+        let diff = TextDiff::from_lines(&old[i].patch, &n.patch);
+        for (k, group) in diff.grouped_ops(3).iter().enumerate() {
+            if k > 0 {
+                writeln!(output, "    {}", term::format::dim("@@"))?;
+            }
+            for op in group {
+                for change in diff.iter_changes(op) {
+                    let line = change.value().trim_end_matches('\n');
+                    match change.tag() {
+                        ChangeTag::Delete => {
+                            writeln!(output, "    {}", term::format::negative(format!("-{line}")))?
+                        }
+                        ChangeTag::Insert => {
+                            writeln!(output, "    {}", term::format::positive(format!("+{line}")))?
+                        }
+                        ChangeTag::Equal => writeln!(output, "     {line}")?,
+                    }
+                }
+            }
+        }
+        writeln!(output)?;
+    }
+    for i in removed {
+        writeln!(
+            output,
+            "{}",
+            term::format::negative(format!(
+                "{} < {} {}",
+                side(Some(i), old),
+                side(None, new),
+                old[i].summary
+            ))
+        )?;
+    }
+    Ok(output)
+}
+
+/// Format a commit time the way `git log` does, eg. "Thu Dec 15 17:28:04 2022 +0000".
+fn commit_time(time: &git::raw::Time) -> String {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60);
+    let utc = NaiveDateTime::from_timestamp_opt(time.seconds(), 0);
+
+    match (utc, offset) {
+        (Some(utc), Some(offset)) => DateTime::<Utc>::from_utc(utc, Utc)
+            .with_timezone(&offset)
+            .format("%a %b %-d %H:%M:%S %Y %z")
+            .to_string(),
+        _ => time.seconds().to_string(),
+    }
 }
 
 pub fn run(
     profile: &Profile,
     stored: &Repository,
-    // TODO: Should be optional.
-    workdir: &git::raw::Repository,
+    workdir: Option<&git::raw::Repository>,
     patch_id: &PatchId,
     diff: bool,
+    check_merge: bool,
+    range: Option<(patch::RevisionId, patch::RevisionId)>,
 ) -> anyhow::Result<()> {
     let patches = patch::Patches::open(stored)?;
     let Some(patch) = patches.get(patch_id)? else {
@@ -45,9 +474,19 @@ pub fn run(
         .latest()
         .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?;
     let state = patch.state();
-    let branches = common::branches(&revision.head(), workdir)?;
-    let target_head = common::patch_merge_target_oid(patch.target(), stored)?;
-    let ahead_behind = common::ahead_behind(stored.raw(), revision.head().into(), target_head)?;
+    let branches = match workdir {
+        Some(workdir) => common::branches(&revision.head(), workdir)?,
+        None => Vec::new(),
+    };
+    let upstream = radicle::api::patch::upstream(stored, &patch)?;
+    let mut ahead_behind = common::ahead_behind(stored.raw(), revision.head().into(), *upstream)?;
+    let (_, behind) = radicle::api::patch::ahead_behind(stored, &patch)?;
+
+    if behind > 0 {
+        ahead_behind.push(term::format::dim(
+            " (rebase with `rad patch update --rebase`)",
+        ));
+    }
 
     let mut attrs = Table::<2, term::Line>::new(TableOptions {
         spacing: 2,
@@ -65,6 +504,40 @@ pub fn run(
         term::format::tertiary("Author".to_owned()).into(),
         term::format::default(patch.author().id().to_string()).into(),
     ]);
+    let reviewers = patch
+        .reviewers()
+        .map(|did| term::format::did(&did).to_string())
+        .collect::<Vec<_>>();
+    if !reviewers.is_empty() {
+        attrs.push([
+            term::format::tertiary("Reviewers".to_owned()).into(),
+            term::format::default(reviewers.join(", ")).into(),
+        ]);
+    }
+    let labels = patch.tags().map(|t| t.to_string()).collect::<Vec<_>>();
+    if !labels.is_empty() {
+        attrs.push([
+            term::format::tertiary("Labels".to_owned()).into(),
+            term::format::secondary(labels.join(", ")).into(),
+        ]);
+    }
+    let scope = patch
+        .scope()
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>();
+    if !scope.is_empty() {
+        attrs.push([
+            term::format::tertiary("Scope".to_owned()).into(),
+            term::format::default(scope.join(", ")).into(),
+        ]);
+    }
+    if let Some(base) = patch.stacked_on() {
+        attrs.push([
+            term::format::tertiary("Stacked on".to_owned()).into(),
+            term::format::default(base.to_string()).into(),
+        ]);
+    }
     attrs.push([
         term::format::tertiary("Head".to_owned()).into(),
         term::format::secondary(revision.head().to_string()).into(),
@@ -92,7 +565,7 @@ pub fn run(
 
     let description = patch.description().trim();
     let mut widget = VStack::default()
-        .border(Some(term::colors::FAINT))
+        .border(Some(term::theme::current().faint))
         .child(attrs)
         .children(if !description.is_empty() {
             vec![
@@ -104,15 +577,164 @@ pub fn run(
         })
         .divider();
 
-    for line in list::timeline(profile.id(), patch_id, &patch, stored)? {
+    for line in list::timeline(profile, patch_id, &patch, stored)? {
         widget.push(line);
     }
-    widget.print();
+    for (revision_id, revision) in patch.revisions() {
+        let comments = term::comment::thread(revision.discussion(), None, &profile.config);
+        if comments.is_empty() {
+            continue;
+        }
+        widget = widget.divider();
+        widget.push(term::Label::from(term::format::tertiary(format!(
+            "Comments on revision {revision_id} ({})",
+            comments.len()
+        ))));
+
+        for (depth, id, comment) in comments {
+            for line in term::comment::widget(depth, id, comment) {
+                widget.push(line);
+            }
+        }
+    }
+    let mut output = widget.display();
 
+    if check_merge {
+        output.push('\n');
+        output.push_str(&merge_check(&patch, stored)?);
+        output.push('\n');
+    }
     if diff {
-        term::blank();
-        show_patch_diff(&patch, stored)?;
-        term::blank();
+        output.push('\n');
+        output.push_str(&patch_diff(&patch, stored)?);
+        output.push('\n');
+    }
+    if let Some((old, new)) = range {
+        let revision = |id: patch::RevisionId| {
+            patch
+                .revision(&id)
+                .ok_or_else(|| anyhow!("revision `{id}` of patch `{patch_id}` not found"))
+        };
+        output.push('\n');
+        output.push_str(&range_diff(stored, revision(old)?, revision(new)?)?);
     }
+    term::pager::page(&output)?;
+    term::io::rejections(&patches.rejected(patch_id)?.unwrap_or_default());
+
     Ok(())
 }
+
+/// Test-merge the patch into its merge target and describe the outcome.
+fn merge_check(patch: &patch::Patch, stored: &Repository) -> anyhow::Result<String> {
+    let target = radicle::api::patch::target(stored, patch.target())?;
+    let conflicts = radicle::api::patch::check_merge(stored, patch)?;
+
+    if conflicts.is_empty() {
+        return Ok(format!(
+            "{} Patch merges cleanly into {}\n",
+            term::format::positive("✓"),
+            term::format::secondary(term::format::oid(target))
+        ));
+    }
+    let mut output = format!(
+        "{} Patch conflicts with {} in {} file(s)\n",
+        term::format::negative("✗"),
+        term::format::secondary(term::format::oid(target)),
+        conflicts.len()
+    );
+    for conflict in conflicts {
+        let hunks = conflict
+            .hunks
+            .iter()
+            .map(|h| {
+                if h.end <= h.start + 1 {
+                    format!("{}", h.start)
+                } else {
+                    format!("{}-{}", h.start, h.end - 1)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        output.push_str(&format!("  {}", conflict.path.display()));
+        if !hunks.is_empty() {
+            output.push_str(&format!(
+                " {}",
+                term::format::dim(format!("(lines {})", hunks.join(", ")))
+            ));
+        }
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn commit(n: u8, summary: &str, diff: &str) -> RangeCommit {
+        RangeCommit {
+            oid: git::Oid::from_str(&n.to_string().repeat(40)).unwrap(),
+            summary: summary.to_owned(),
+            patch: format!("{summary}\n\n{diff}"),
+        }
+    }
+
+    #[test]
+    fn test_compare_ranges() {
+        term::theme::set_color(term::theme::ColorMode::Never);
+
+        let old = [
+            commit(1, "Add foo", "+foo\n"),
+            commit(2, "Drop qux", "-qux\n"),
+            commit(3, "Fix bar", "-bar\n+baz\n"),
+        ];
+        let new = [
+            commit(4, "Add foo", "+foo\n"),
+            commit(5, "Fix bar", "-bar\n+bat\n"),
+            commit(6, "Add quux", "+quux\n"),
+        ];
+        let output = compare_ranges(&old, &new).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "1:  1111111 = 1:  4444444 Add foo",
+                "2:  2222222 < -:  ------- Drop qux",
+                "3:  3333333 ! 2:  5555555 Fix bar",
+                "     Fix bar",
+                "     ",
+                "     -bar",
+                "    -+baz",
+                "    ++bat",
+                "",
+                "-:  ------- > 3:  6666666 Add quux",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_ranges_reordered() {
+        term::theme::set_color(term::theme::ColorMode::Never);
+
+        // Commits are paired by their changes first, even if they were reordered.
+        let old = [
+            commit(1, "Add foo", "+foo\n"),
+            commit(2, "Add bar", "+bar\n"),
+        ];
+        let new = [
+            commit(3, "Add bar", "+bar\n"),
+            commit(4, "Add foo", "+foo\n"),
+        ];
+        let output = compare_ranges(&old, &new).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "2:  2222222 = 1:  3333333 Add bar",
+                "1:  1111111 = 2:  4444444 Add foo",
+            ]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+use radicle::api::patch::Part;
+use radicle::cob::patch;
+use radicle::git;
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+use super::common::*;
+
+/// Split a patch into a stack of patches, ending the parts at the given commits. If no
+/// commits are given, the user is asked which commits should end a part.
+pub fn run(
+    repository: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    at: &[git::Oid],
+    quiet: bool,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let patches = patch::Patches::open(repository)?;
+    let Some(patch) = patches.get(patch_id)? else {
+        anyhow::bail!("Patch `{patch_id}` not found");
+    };
+    let (_, revision) = patch
+        .latest()
+        .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?;
+    let head = revision.head();
+    let raw = repository.raw();
+    let mut commits = patch_commits(raw, revision.base(), &head)?;
+    // Oldest commits first.
+    commits.reverse();
+
+    if commits.len() < 2 {
+        anyhow::bail!("patch `{patch_id}` has a single commit and can't be split");
+    }
+    for oid in at {
+        if !commits.iter().any(|c| c.id() == **oid) {
+            anyhow::bail!("commit {oid} is not part of patch `{patch_id}`");
+        }
+    }
+
+    let mut heads = if at.is_empty() {
+        if !term::is_terminal() {
+            anyhow::bail!("the commits to split the patch at must be given with `--at`");
+        }
+        select(&commits)
+    } else {
+        commits
+            .iter()
+            .map(|c| git::Oid::from(c.id()))
+            .filter(|oid| at.contains(oid))
+            .collect()
+    };
+    if heads.last() != Some(&head) {
+        heads.push(head);
+    }
+    if heads.len() < 2 {
+        anyhow::bail!("at least two parts are needed to split a patch");
+    }
+
+    let parts = heads
+        .into_iter()
+        .map(|head| {
+            let commit = raw.find_commit(*head)?;
+            let message = commit.message().unwrap_or_default();
+            let (title, description) = message.split_once("\n\n").unwrap_or((message, ""));
+
+            Ok(Part {
+                title: title.trim().to_owned(),
+                description: description.trim().to_owned(),
+                head,
+            })
+        })
+        .collect::<Result<Vec<_>, git::raw::Error>>()?;
+    let ids = radicle::api::patch::split(repository, patch_id, &parts, &signer)?;
+
+    if quiet {
+        for id in ids {
+            term::print(id);
+        }
+        return Ok(());
+    }
+    for (id, part) in ids.iter().zip(&parts) {
+        term::success!(
+            "Patch {} created {}",
+            term::format::highlight(term::format::cob(id)),
+            term::format::parens(term::format::secondary(term::format::oid(part.head)))
+        );
+    }
+    term::success!(
+        "Patch {} archived",
+        term::format::highlight(term::format::cob(patch_id))
+    );
+
+    Ok(())
+}
+
+/// Ask the user after which commits a part should end. The last commit always ends a part.
+fn select(commits: &[git::raw::Commit]) -> Vec<git::Oid> {
+    let mut heads = Vec::new();
+
+    for commit in commits.iter().take(commits.len() - 1) {
+        let end = term::ask(
+            format!(
+                "End part {} after {} {}?",
+                heads.len() + 1,
+                term::format::secondary(term::format::oid(commit.id())),
+                commit.summary().unwrap_or_default()
+            ),
+            false,
+        );
+        if end {
+            heads.push(commit.id().into());
+        }
+    }
+    heads
+}
@@ -6,6 +6,7 @@ use radicle::storage::git::Repository;
 use super::common::*;
 use super::Options;
 use crate::terminal as term;
+use crate::terminal::args::Error;
 
 const REVISION_MSG: &str = r#"
 <!--
@@ -59,6 +60,42 @@ fn show_update_commit_info(
     Ok(())
 }
 
+/// Rebase the current branch of the working copy onto the given commit, fetching the
+/// commit from storage if necessary.
+fn rebase(
+    storage: &Repository,
+    workdir: &git::raw::Repository,
+    onto: git::Oid,
+) -> anyhow::Result<()> {
+    let path = workdir
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("cannot rebase in a bare repository"))?;
+
+    if workdir.find_commit(*onto).is_err() {
+        crate::git::git(
+            workdir.path(),
+            [
+                "fetch",
+                &git::Url::from(storage.id).to_string(),
+                &onto.to_string(),
+            ],
+        )?;
+    }
+    term::info!(
+        "Rebasing onto {}..",
+        term::format::secondary(term::format::oid(onto))
+    );
+
+    if let Err(e) = crate::git::git(path, ["rebase", &onto.to_string()]) {
+        return Err(Error::WithHint {
+            err: e.into(),
+            hint: "Resolve the conflicts and run `git rebase --continue`, then update the patch with `rad patch update`.",
+        }
+        .into());
+    }
+    Ok(())
+}
+
 /// Run patch update.
 pub fn run(
     storage: &Repository,
@@ -67,20 +104,36 @@ pub fn run(
     patch_id: Option<patch::PatchId>,
     message: term::patch::Message,
     quiet: bool,
+    rebase: bool,
     options: &Options,
 ) -> anyhow::Result<()> {
     // `HEAD`; This is what we are proposing as a patch.
-    let head_branch = try_branch(workdir.head()?)?;
-
-    push_to_storage(workdir, storage, &head_branch, options)?;
-
-    let (_, target_oid) = get_merge_target(storage, &head_branch)?;
+    let mut head_branch = try_branch(workdir.head()?)?;
     let mut patches = patch::Patches::open(storage)?;
 
     let patch_id = match patch_id {
         Some(patch_id) => patch_id,
-        None => select_patch(&patches, workdir, &head_branch, target_oid, &profile.did())?,
+        None => {
+            let (_, target_oid) = storage.canonical_head()?;
+            select_patch(&patches, workdir, &head_branch, target_oid, &profile.did())?
+        }
     };
+    if rebase {
+        let Some(patch) = patches.get(&patch_id)? else {
+            anyhow::bail!("Patch `{patch_id}` not found");
+        };
+        let upstream = radicle::api::patch::upstream(storage, &patch)?;
+
+        self::rebase(storage, workdir, upstream)?;
+        // Rebasing moves the branch, so we look it up again.
+        head_branch = try_branch(workdir.head()?)?;
+    }
+
+    push_to_storage(workdir, storage, &head_branch, options)?;
+
+    let (_, target_oid) = get_merge_target(storage, &head_branch)?;
+    check_policy(storage, workdir, target_oid, branch_oid(&head_branch)?)?;
+
     let Ok(mut patch) = patches.get_mut(&patch_id) else {
         anyhow::bail!("Patch `{patch_id}` not found");
     };
@@ -99,7 +152,8 @@ pub fn run(
     }
 
     let head_oid = branch_oid(&head_branch)?;
-    let base_oid = workdir.merge_base(*target_oid, *head_oid)?;
+    let upstream = radicle::api::patch::upstream(storage, &patch)?;
+    let base_oid = workdir.merge_base(*upstream, *head_oid)?;
     let message = message.get(REVISION_MSG)?;
     let message = message.replace(REVISION_MSG.trim(), "");
     let message = message.trim();
@@ -116,7 +170,7 @@ pub fn run(
     }
 
     if options.announce {
-        // TODO
+        term::announce::changed(storage.id());
     }
 
     Ok(())
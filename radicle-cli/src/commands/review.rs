@@ -1,32 +1,59 @@
+#[path = "review/draft.rs"]
+mod draft;
+
 use std::ffi::OsString;
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 
-use radicle::cob::patch::{Patches, RevisionIx, Verdict};
+use radicle::cob;
+use radicle::cob::patch::{
+    CodeComment, CodeLocation, PatchId, Patches, Revision, RevisionIx, Verdict,
+};
+use radicle::git;
 use radicle::prelude::*;
 use radicle::rad;
+use radicle::storage::git::Repository;
 
 use crate::git::Rev;
 use crate::terminal as term;
 use crate::terminal::args::{string, Args, Error, Help};
 use crate::terminal::patch::Message;
 
+use draft::{Draft, Drafts};
+
 pub const HELP: Help = Help {
     name: "review",
-    description: "Approve or reject a patch",
+    description: "Review a patch, hunk by hunk",
     version: env!("CARGO_PKG_VERSION"),
     usage: r#"
 Usage
 
-    rad review [<patch-id>] [--accept|--reject] [-m [<string>]] [<option>...]
+    rad review <patch-id> [--revision <number>] [<option>...]
+    rad review <patch-id> [--accept|--reject] [-m [<string>]] [<option>...]
+    rad review <patch-id> --discard
 
     To specify a patch to review, use the fully qualified patch id
     or an unambiguous prefix of it.
 
+    Without a verdict or message, the changes of the patch revision are walked
+    hunk by hunk, and comments can be left on each hunk. The review is saved as
+    a draft as it goes, under the profile home, so that quitting and running the
+    command again resumes it. Once every hunk is reviewed, the patch is accepted
+    or rejected, and the verdict is published along with the comments, all at once.
+
+    Given a verdict or message, the review is published right away, along with the
+    comments of the draft, if any. Use `--discard` to throw a draft away.
+
 Options
 
     -r, --revision <number>   Revision number to review, defaults to the latest
+        --accept              Accept the patch
+        --reject              Reject the patch
+        --discard             Discard the draft review of the patch
         --[no-]sync           Sync review to seed (default: sync)
     -m, --message [<string>]  Provide a comment with the review (default: prompt)
         --help                Print help
@@ -43,6 +70,16 @@ Markdown supported.
 -->
 "#;
 
+/// Hunk comment help message.
+pub const COMMENT_HELP_MSG: &str = r#"
+<!--
+Enter a comment on the hunk. If you leave this blank,
+no comment will be added.
+
+Markdown supported.
+-->
+"#;
+
 #[derive(Debug)]
 pub struct Options {
     pub id: Rev,
@@ -51,6 +88,8 @@ pub struct Options {
     pub sync: bool,
     pub verbose: bool,
     pub verdict: Option<Verdict>,
+    pub discard: bool,
+    pub interactive: bool,
 }
 
 impl Args for Options {
@@ -64,6 +103,7 @@ impl Args for Options {
         let mut sync = true;
         let mut verbose = false;
         let mut verdict = None;
+        let mut discard = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -102,12 +142,17 @@ impl Args for Options {
                 Long("reject") if verdict.is_none() => {
                     verdict = Some(Verdict::Reject);
                 }
+                Long("discard") => {
+                    discard = true;
+                }
                 Value(val) => {
                     id = Some(Rev::from(string(&val)));
                 }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
+        // Without a verdict or message, we walk the hunks of the patch.
+        let interactive = verdict.is_none() && message == Message::Edit && !discard;
 
         Ok((
             Options {
@@ -117,6 +162,8 @@ impl Args for Options {
                 revision,
                 verbose,
                 verdict,
+                discard,
+                interactive,
             },
             vec![],
         ))
@@ -139,11 +186,70 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .get_mut(&patch_id)
         .context(format!("couldn't find patch {patch_id} locally"))?;
     let patch_id_pretty = term::format::tertiary(term::format::cob(&patch_id));
-    let revision_ix = options.revision.unwrap_or_else(|| patch.version());
-    let (revision_id, _) = patch
-        .revisions()
-        .nth(revision_ix)
-        .ok_or_else(|| anyhow!("revision R{} does not exist", revision_ix))?;
+    let drafts = Drafts::new(&profile.home);
+
+    if options.discard {
+        if drafts.discard(&id, &patch_id)? {
+            term::success!("Draft review of patch {} discarded", patch_id_pretty);
+        } else {
+            term::info!("There is no draft review of patch {}", patch_id_pretty);
+        }
+        return Ok(());
+    }
+
+    let draft = drafts.load(&id, &patch_id)?;
+    let revision_id = match (options.revision, &draft) {
+        (Some(ix), _) => {
+            let (revision_id, _) = patch
+                .revisions()
+                .nth(ix)
+                .ok_or_else(|| anyhow!("revision R{} does not exist", ix))?;
+            *revision_id
+        }
+        // Resume reviewing the revision of the draft.
+        (None, Some(draft)) => draft.revision,
+        (None, None) => {
+            let (revision_id, _) = patch
+                .latest()
+                .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?;
+            *revision_id
+        }
+    };
+    let revision = patch
+        .revision(&revision_id)
+        .ok_or_else(|| anyhow!("revision {revision_id} of patch {patch_id} not found"))?
+        .clone();
+    let mut draft = match draft {
+        Some(draft) if draft.revision != revision_id => {
+            return Err(Error::WithHint {
+                err: anyhow!("there is a draft review of another revision of this patch"),
+                hint: "To start over, discard the draft with `rad review <patch-id> --discard`.",
+            }
+            .into());
+        }
+        Some(draft) => draft,
+        None => Draft::new(revision_id),
+    };
+
+    let verdict = if options.interactive {
+        if !term::is_terminal() {
+            anyhow::bail!("reviewing hunks requires a terminal; use `--accept` or `--reject`");
+        }
+        match walk(&repository, &revision, &mut draft, &drafts, &patch_id)? {
+            Some(verdict) => verdict,
+            None => {
+                term::success!("Draft review of patch {} saved", patch_id_pretty);
+                term::tip!(
+                    "Run `rad review {}` to resume it",
+                    term::format::cob(&patch_id)
+                );
+                return Ok(());
+            }
+        }
+    } else {
+        options.verdict
+    };
+
     let message = options.message.get(REVIEW_HELP_MSG)?;
     let message = message.replace(REVIEW_HELP_MSG.trim(), "");
     let message = if message.is_empty() {
@@ -151,10 +257,27 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     } else {
         Some(message)
     };
+    let timestamp = cob::Timestamp::now();
+    let inline = draft
+        .comments
+        .into_iter()
+        .map(|c| {
+            let location = CodeLocation {
+                blob: c.blob,
+                path: c.path,
+                commit: revision.head(),
+                lines: c.lines,
+            };
+            CodeComment::new(location, c.body, timestamp)
+        })
+        .collect::<Vec<_>>();
+    let comments = inline.len();
 
-    patch.review(*revision_id, options.verdict, message, vec![], &signer)?;
+    // The verdict, comment and hunk comments are all published as a single operation.
+    patch.review(revision_id, verdict, message, inline, &signer)?;
+    drafts.discard(&id, &patch_id)?;
 
-    match options.verdict {
+    match verdict {
         Some(Verdict::Accept) => {
             term::success!(
                 "Patch {} {}",
@@ -173,6 +296,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             term::success!("Patch {} reviewed", patch_id_pretty);
         }
     }
+    if comments > 0 {
+        term::info!("{comments} inline comment(s) added");
+    }
 
     if options.sync {
         term::warning("the `--sync` option is not yet supported");
@@ -180,3 +306,378 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// A hunk of the changes of a revision.
+struct Hunk {
+    /// Path of the file changed.
+    path: PathBuf,
+    /// File changed, as of the revision head.
+    blob: git::Oid,
+    /// Hunk header, eg. `@@ -1,2 +1,3 @@`.
+    header: String,
+    /// Lines of the hunk, prefixed with their origin.
+    lines: Vec<(char, String)>,
+    /// Lines of the file covered by the hunk, as of the revision head.
+    new: Range<usize>,
+}
+
+impl Hunk {
+    /// Lines to comment on. Hunks that only remove lines are commented on the line above.
+    fn commented(&self) -> Range<usize> {
+        let start = self.new.start.max(1);
+        start..start + self.new.len().max(1)
+    }
+}
+
+/// Action taken on a hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HunkAction {
+    Next,
+    Comment,
+    Previous,
+    Quit,
+}
+
+impl fmt::Display for HunkAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Next => write!(f, "Next hunk"),
+            Self::Comment => write!(f, "Comment on hunk"),
+            Self::Previous => write!(f, "Previous hunk"),
+            Self::Quit => write!(f, "Save draft and quit"),
+        }
+    }
+}
+
+/// Outcome of a review, once every hunk was reviewed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Accept,
+    Reject,
+    Comment,
+    Quit,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => write!(f, "Accept patch"),
+            Self::Reject => write!(f, "Reject patch"),
+            Self::Comment => write!(f, "Publish comments only"),
+            Self::Quit => write!(f, "Save draft and quit"),
+        }
+    }
+}
+
+/// Walk the hunks of a revision, starting where the draft left off, and saving it after
+/// every step. Returns the verdict once every hunk is reviewed, or `None` if the user quit.
+fn walk(
+    repository: &Repository,
+    revision: &Revision,
+    draft: &mut Draft,
+    drafts: &Drafts,
+    patch_id: &PatchId,
+) -> anyhow::Result<Option<Option<Verdict>>> {
+    let rid = repository.id;
+    let hunks = hunks(repository, revision)?;
+    let actions = [
+        HunkAction::Next,
+        HunkAction::Comment,
+        HunkAction::Previous,
+        HunkAction::Quit,
+    ];
+
+    if draft.reviewed > 0 && draft.reviewed < hunks.len() {
+        term::info!(
+            "Resuming review at hunk {} of {}",
+            draft.reviewed + 1,
+            hunks.len()
+        );
+    }
+    while let Some(hunk) = hunks.get(draft.reviewed) {
+        term::blank();
+        term::print(term::format::dim(format!(
+            "Hunk {} of {}",
+            draft.reviewed + 1,
+            hunks.len()
+        )));
+        term::print(term::format::bold(hunk.path.display()));
+        term::print(term::format::tertiary(&hunk.header));
+
+        for (origin, text) in &hunk.lines {
+            let line = format!("{origin}{text}");
+            let line = match origin {
+                '+' => term::format::positive(line),
+                '-' => term::format::negative(line),
+                _ => term::format::default(line),
+            };
+            term::print(line);
+        }
+        for c in draft
+            .comments
+            .iter()
+            .filter(|c| c.path == hunk.path && c.lines == hunk.commented())
+        {
+            term::print(term::format::yellow(format!("> {}", c.body)));
+        }
+
+        let action =
+            term::select("What next?", &actions, &HunkAction::Next)?.unwrap_or(HunkAction::Quit);
+        let proceed = step(draft, hunk, action, || {
+            let body = Message::Edit.get(COMMENT_HELP_MSG)?;
+            Ok(body.replace(COMMENT_HELP_MSG.trim(), ""))
+        })?;
+        drafts.save(&rid, patch_id, draft)?;
+
+        if !proceed {
+            return Ok(None);
+        }
+    }
+
+    term::blank();
+    term::info!(
+        "All hunks reviewed, with {} comment(s)",
+        draft.comments.len()
+    );
+    let outcomes = [
+        Outcome::Accept,
+        Outcome::Reject,
+        Outcome::Comment,
+        Outcome::Quit,
+    ];
+    match term::select("Publish review?", &outcomes, &Outcome::Accept)? {
+        Some(Outcome::Accept) => Ok(Some(Some(Verdict::Accept))),
+        Some(Outcome::Reject) => Ok(Some(Some(Verdict::Reject))),
+        Some(Outcome::Comment) => Ok(Some(None)),
+        Some(Outcome::Quit) | None => Ok(None),
+    }
+}
+
+/// Apply an action to the hunk under review, getting the comment from `comment` if
+/// commenting. Returns `false` if the review should stop there.
+fn step(
+    draft: &mut Draft,
+    hunk: &Hunk,
+    action: HunkAction,
+    comment: impl FnOnce() -> anyhow::Result<String>,
+) -> anyhow::Result<bool> {
+    match action {
+        HunkAction::Next => {
+            draft.reviewed += 1;
+        }
+        HunkAction::Comment => {
+            let body = comment()?;
+            let body = body.trim();
+
+            if !body.is_empty() {
+                draft.comments.push(draft::Comment {
+                    path: hunk.path.clone(),
+                    blob: hunk.blob,
+                    lines: hunk.commented(),
+                    body: body.to_owned(),
+                });
+            }
+        }
+        HunkAction::Previous => {
+            draft.reviewed = draft.reviewed.saturating_sub(1);
+        }
+        HunkAction::Quit => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Get the hunks of the changes of a revision, from its base to its head.
+fn hunks(repository: &Repository, revision: &Revision) -> anyhow::Result<Vec<Hunk>> {
+    let repo = repository.raw();
+    let base = repo.find_commit(**revision.base())?.tree()?;
+    let head = repo.find_commit(*revision.head())?.tree()?;
+
+    diff_hunks(repo, &base, &head)
+}
+
+/// Get the hunks of the changes between two trees.
+fn diff_hunks(
+    repo: &git::raw::Repository,
+    base: &git::raw::Tree,
+    head: &git::raw::Tree,
+) -> anyhow::Result<Vec<Hunk>> {
+    let mut diff = repo.diff_tree_to_tree(Some(base), Some(head), None)?;
+    diff.find_similar(None)?;
+
+    let mut hunks = Vec::new();
+    for i in 0..diff.deltas().len() {
+        // Nb. binary files have no hunks.
+        let Some(patch) = git::raw::Patch::from_diff(&diff, i)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let new = delta.new_file();
+        let path = new
+            .path()
+            .or(delta.old_file().path())
+            .map(PathBuf::from)
+            .unwrap_or_default();
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, count) = patch.hunk(h)?;
+            let start = hunk.new_start() as usize;
+            let lines = (0..count)
+                .map(|l| {
+                    let line = patch.line_in_hunk(h, l)?;
+                    let text = String::from_utf8_lossy(line.content());
+
+                    Ok((line.origin(), text.trim_end_matches('\n').to_owned()))
+                })
+                .collect::<Result<Vec<_>, git::raw::Error>>()?;
+
+            hunks.push(Hunk {
+                path: path.clone(),
+                blob: new.id().into(),
+                header: String::from_utf8_lossy(hunk.header()).trim_end().to_owned(),
+                lines,
+                new: start..start + hunk.new_lines() as usize,
+            });
+        }
+    }
+    Ok(hunks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use radicle::test::arbitrary;
+
+    /// Write a tree with the given files.
+    fn tree<'a>(repo: &'a git::raw::Repository, files: &[(&str, String)]) -> git::raw::Tree<'a> {
+        let mut builder = repo.treebuilder(None).unwrap();
+        for (path, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            builder.insert(path, blob, 0o100_644).unwrap();
+        }
+        let oid = builder.write().unwrap();
+
+        repo.find_tree(oid).unwrap()
+    }
+
+    /// Lines of a file, numbered from `1` to `n`. Nb. lines starting with a letter would show
+    /// up in hunk headers, as function context.
+    fn lines(n: usize) -> Vec<String> {
+        (1..=n).map(|i| i.to_string()).collect()
+    }
+
+    /// Hunks of changes touching several files: one file is changed in two places, one is
+    /// added, and one is deleted.
+    fn fixture(repo: &git::raw::Repository) -> Vec<Hunk> {
+        let mut changed = lines(20);
+        changed[1] = String::from("II");
+        changed[17] = String::from("XVIII");
+
+        let base = tree(
+            repo,
+            &[
+                ("a.txt", lines(20).join("\n") + "\n"),
+                ("c.txt", String::from("deleted\nfile\n")),
+            ],
+        );
+        let head = tree(
+            repo,
+            &[
+                ("a.txt", changed.join("\n") + "\n"),
+                ("b.txt", String::from("added\n")),
+            ],
+        );
+        diff_hunks(repo, &base, &head).unwrap()
+    }
+
+    #[test]
+    fn test_hunks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git::raw::Repository::init(tmp.path()).unwrap();
+        let hunks = fixture(&repo);
+
+        assert_eq!(
+            hunks
+                .iter()
+                .map(|h| (h.path.to_str().unwrap(), h.header.as_str(), h.new.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a.txt", "@@ -1,5 +1,5 @@", 1..6),
+                ("a.txt", "@@ -15,6 +15,6 @@", 15..21),
+                ("b.txt", "@@ -0,0 +1 @@", 1..2),
+                ("c.txt", "@@ -1,2 +0,0 @@", 0..0),
+            ]
+        );
+        // Both hunks of a file refer to the file as of the head.
+        assert_eq!(hunks[0].blob, hunks[1].blob);
+        assert_ne!(hunks[0].blob, hunks[2].blob);
+        assert_eq!(
+            hunks[0]
+                .lines
+                .iter()
+                .filter(|(origin, _)| *origin != ' ')
+                .collect::<Vec<_>>(),
+            vec![&('-', String::from("2")), &('+', String::from("II"))]
+        );
+        assert_eq!(
+            hunks[3].lines,
+            vec![('-', String::from("deleted")), ('-', String::from("file"))]
+        );
+        assert_eq!(hunks[1].commented(), 15..21);
+        // Hunks that only remove lines are commented on the line above.
+        assert_eq!(hunks[3].commented(), 1..2);
+    }
+
+    #[test]
+    fn test_step() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git::raw::Repository::init(tmp.path()).unwrap();
+        let hunks = fixture(&repo);
+        let mut draft = Draft::new(arbitrary::oid().into());
+        let comment = |body: &str| {
+            let body = body.to_owned();
+            move || Ok(body)
+        };
+
+        assert!(step(&mut draft, &hunks[0], HunkAction::Next, comment("")).unwrap());
+        assert!(step(
+            &mut draft,
+            &hunks[1],
+            HunkAction::Comment,
+            comment("Why roman numerals?\n")
+        )
+        .unwrap());
+        assert_eq!(draft.reviewed, 1);
+
+        // Going back to the previous hunk, in the same file.
+        assert!(step(&mut draft, &hunks[1], HunkAction::Previous, comment("")).unwrap());
+        assert_eq!(draft.reviewed, 0);
+
+        // Empty comments are ignored.
+        assert!(step(&mut draft, &hunks[0], HunkAction::Comment, comment(" \n")).unwrap());
+        assert!(step(&mut draft, &hunks[0], HunkAction::Next, comment("")).unwrap());
+        assert!(step(&mut draft, &hunks[1], HunkAction::Next, comment("")).unwrap());
+        assert!(step(
+            &mut draft,
+            &hunks[2],
+            HunkAction::Comment,
+            comment("New file?")
+        )
+        .unwrap());
+
+        // Quitting keeps our place.
+        assert!(!step(&mut draft, &hunks[2], HunkAction::Quit, comment("")).unwrap());
+        assert_eq!(draft.reviewed, 2);
+        assert_eq!(
+            draft
+                .comments
+                .iter()
+                .map(|c| (c.path.to_str().unwrap(), c.lines.clone(), c.body.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a.txt", 15..21, "Why roman numerals?"),
+                ("b.txt", 1..2, "New file?"),
+            ]
+        );
+        assert_eq!(draft.comments[0].blob, hunks[1].blob);
+    }
+}
@@ -0,0 +1,135 @@
+//! Review drafts, saved under the profile home so that a review can be resumed.
+use std::ops::Range;
+use std::path::PathBuf;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use radicle::cob::patch::{PatchId, RevisionId};
+use radicle::git;
+use radicle::identity::Id;
+use radicle::profile::Home;
+
+/// Directory of review drafts, relative to the profile home.
+const DRAFTS_DIR: &str = "drafts/reviews";
+
+/// A review in progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    /// Revision under review.
+    pub revision: RevisionId,
+    /// Number of hunks reviewed so far.
+    pub reviewed: usize,
+    /// Comments made on hunks.
+    pub comments: Vec<Comment>,
+}
+
+impl Draft {
+    /// Create an empty draft for the given revision.
+    pub fn new(revision: RevisionId) -> Self {
+        Self {
+            revision,
+            reviewed: 0,
+            comments: Vec::new(),
+        }
+    }
+}
+
+/// A comment on a hunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    /// Path of the file commented on.
+    pub path: PathBuf,
+    /// File commented on, as of the revision head.
+    pub blob: git::Oid,
+    /// Lines commented on, starting at one. The end is exclusive.
+    pub lines: Range<usize>,
+    /// Comment.
+    pub body: String,
+}
+
+/// Review drafts of a profile.
+#[derive(Debug, Clone)]
+pub struct Drafts {
+    path: PathBuf,
+}
+
+impl Drafts {
+    /// Get the review drafts stored under the given profile home.
+    pub fn new(home: &Home) -> Self {
+        Self {
+            path: home.path().join(DRAFTS_DIR),
+        }
+    }
+
+    /// Load the draft review of a patch, if any.
+    pub fn load(&self, rid: &Id, patch: &PatchId) -> anyhow::Result<Option<Draft>> {
+        match fs::read(self.file(rid, patch)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save the draft review of a patch, replacing any existing one.
+    pub fn save(&self, rid: &Id, patch: &PatchId, draft: &Draft) -> anyhow::Result<()> {
+        let file = self.file(rid, patch);
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(file, serde_json::to_vec_pretty(draft)?)?;
+
+        Ok(())
+    }
+
+    /// Discard the draft review of a patch. Returns `false` if there was none.
+    pub fn discard(&self, rid: &Id, patch: &PatchId) -> io::Result<bool> {
+        match fs::remove_file(self.file(rid, patch)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn file(&self, rid: &Id, patch: &PatchId) -> PathBuf {
+        self.path
+            .join(rid.canonical())
+            .join(patch.to_string())
+            .with_extension("json")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use radicle::test::arbitrary;
+
+    #[test]
+    fn test_save_load_discard() {
+        let tmp = tempfile::tempdir().unwrap();
+        let drafts = Drafts {
+            path: tmp.path().to_path_buf(),
+        };
+        let rid = arbitrary::gen::<Id>(1);
+        let patch = arbitrary::oid().into();
+        let mut draft = Draft::new(arbitrary::oid().into());
+
+        assert_eq!(drafts.load(&rid, &patch).unwrap(), None);
+
+        draft.reviewed = 2;
+        draft.comments.push(Comment {
+            path: PathBuf::from("src/main.rs"),
+            blob: arbitrary::oid(),
+            lines: 3..5,
+            body: String::from("Use a constant"),
+        });
+        drafts.save(&rid, &patch, &draft).unwrap();
+        assert_eq!(drafts.load(&rid, &patch).unwrap(), Some(draft));
+
+        assert!(drafts.discard(&rid, &patch).unwrap());
+        assert!(!drafts.discard(&rid, &patch).unwrap());
+        assert_eq!(drafts.load(&rid, &patch).unwrap(), None);
+    }
+}
@@ -4,6 +4,7 @@ use std::fs;
 use anyhow::anyhow;
 
 use radicle::identity::Id;
+use radicle::{Node, Profile};
 
 use crate::commands::rad_untrack;
 use crate::terminal as term;
@@ -17,18 +18,29 @@ pub const HELP: Help = Help {
 Usage
 
     rad rm <rid> [<option>...]
+    rad rm --all [<option>...]
 
     Removes a repository from storage. The repository is also untracked, if possible.
 
+    Repositories that back a working copy, eg. one created with `rad init`,
+    `rad clone` or `rad checkout`, are not removed unless `--force` is given.
+    Use `rad ls --in-use` to list these working copies.
+
+    With `--all`, all repositories that don't back a working copy are removed.
+
 Options
 
+    --all               Remove all repositories that don't back a working copy
+    --force             Remove the repository even if it backs a working copy
     --no-confirm        Do not ask for confirmation before removal (default: false)
     --help              Print help
 "#,
 };
 
 pub struct Options {
-    rid: Id,
+    /// Repository to remove, or `None` to remove all repositories that aren't in use.
+    rid: Option<Id>,
+    force: bool,
     confirm: bool,
 }
 
@@ -38,10 +50,18 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut id: Option<Id> = None;
+        let mut all = false;
+        let mut force = false;
         let mut confirm = true;
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Long("all") => {
+                    all = true;
+                }
+                Long("force") => {
+                    force = true;
+                }
                 Long("no-confirm") => {
                     confirm = false;
                 }
@@ -55,9 +75,16 @@ impl Args for Options {
             }
         }
 
+        match (&id, all) {
+            (None, false) => anyhow::bail!("an RID must be provided; see `rad rm --help`"),
+            (Some(_), true) => anyhow::bail!("an RID can't be provided with `--all`"),
+            _ => {}
+        }
+
         Ok((
             Options {
-                rid: id.ok_or_else(|| anyhow!("an RID must be provided; see `rad rm --help`"))?,
+                rid: id,
+                force,
                 confirm,
             },
             vec![],
@@ -68,22 +95,79 @@ impl Args for Options {
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let storage = &profile.storage;
-    let rid = options.rid;
-    let path = radicle::storage::git::paths::repository(storage, &rid);
+    let in_use = profile.checkouts()?.all()?;
     let mut node = radicle::Node::new(profile.socket());
 
+    let Some(rid) = options.rid else {
+        let rids = storage
+            .repositories()?
+            .into_iter()
+            .filter(|rid| !in_use.contains_key(rid))
+            .collect::<Vec<_>>();
+
+        for (rid, checkouts) in &in_use {
+            term::info!(
+                "Keeping {}, which backs {} working copy(s)",
+                term::format::tertiary(rid),
+                checkouts.len()
+            );
+        }
+        if rids.is_empty() {
+            term::info!("Nothing to remove");
+            return Ok(());
+        }
+        if options.confirm && !term::confirm(format!("Remove {} repository(s)?", rids.len())) {
+            return Ok(());
+        }
+        for rid in rids {
+            remove(rid, &profile, &mut node)?;
+        }
+        return Ok(());
+    };
+    let path = radicle::storage::git::paths::repository(storage, &rid);
+
     if !path.exists() {
-        anyhow::bail!("repository {rid} was not found");
+        let repos = storage.repositories()?;
+        let repos = repos.iter().map(|id| id.urn()).collect::<Vec<_>>();
+
+        return Err(term::args::not_found(
+            "repository",
+            &rid.urn(),
+            repos.iter().map(String::as_str),
+        )
+        .into());
+    }
+    if let Some(checkouts) = in_use.get(&rid) {
+        if !options.force {
+            return Err(Error::WithHint {
+                err: anyhow!(
+                    "repository {rid} backs {} working copy(s), eg. {}",
+                    checkouts.len(),
+                    checkouts[0].display()
+                ),
+                hint: "To remove it anyway, use `--force`.",
+            }
+            .into());
+        }
     }
 
     if !options.confirm || term::confirm(format!("Remove {rid}?")) {
-        if let Err(e) = rad_untrack::untrack_repo(rid, &mut node) {
-            term::warning(&format!("Failed to untrack repository: {e}"));
-            term::warning("Make sure to untrack this repository when your node is running");
-        }
-        fs::remove_dir_all(path)?;
-        term::success!("Successfully removed {rid} from storage");
+        remove(rid, &profile, &mut node)?;
+    }
+
+    Ok(())
+}
+
+/// Untrack a repository, and remove it from storage.
+fn remove(rid: Id, profile: &Profile, node: &mut Node) -> anyhow::Result<()> {
+    let path = radicle::storage::git::paths::repository(&profile.storage, &rid);
+
+    if let Err(e) = rad_untrack::untrack_repo(rid, node) {
+        term::warning(&format!("Failed to untrack repository: {e}"));
+        term::warning("Make sure to untrack this repository when your node is running");
     }
+    fs::remove_dir_all(path)?;
+    term::success!("Successfully removed {rid} from storage");
 
     Ok(())
 }
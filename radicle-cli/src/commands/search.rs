@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::{io, time};
+
+use anyhow::anyhow;
+
+use radicle::node;
+use radicle::node::{Event, Handle as _, Node, NodeId, SearchResult};
+use radicle::prelude::Id;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "search",
+    description: "Search for repositories on the network",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad search <query> [<option>...]
+
+    Asks the connected seeds for the public repositories whose name or
+    description contains the given query, ignoring case. Only seeds that
+    support search are queried.
+
+Options
+
+    --timeout, -t <secs>  How long to wait for seeds to answer (default: 6)
+    --verbose, -v         Show the delegates of every repository
+    --help                Print help
+"#,
+};
+
+pub struct Options {
+    pub query: String,
+    pub timeout: time::Duration,
+    pub verbose: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut query: Option<String> = None;
+        let mut timeout = time::Duration::from_secs(6);
+        let mut verbose = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("timeout") | Short('t') => {
+                    let value = parser.value()?;
+                    let secs = term::args::parse_value("timeout", value)?;
+
+                    timeout = time::Duration::from_secs(secs);
+                }
+                Long("verbose") | Short('v') => {
+                    verbose = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if query.is_none() => {
+                    let val = term::args::string(&val);
+
+                    if val.trim().is_empty() {
+                        anyhow::bail!("search query must not be empty");
+                    }
+                    query = Some(val);
+                }
+                arg => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((
+            Options {
+                query: query.ok_or_else(|| anyhow!("a search query must be specified"))?,
+                timeout,
+                verbose,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let mut node = Node::new(profile.socket());
+
+    if !node.is_running() {
+        anyhow::bail!("to search the network, your node must be running");
+    }
+    let results = search(&mut node, options.query, options.timeout)?;
+
+    print(results, options.verbose);
+
+    Ok(())
+}
+
+/// Search the repositories of connected seeds, waiting for them to answer until the timeout
+/// is reached. Results are returned along with the seeds that have the repository.
+pub fn search(
+    node: &mut Node,
+    query: String,
+    timeout: time::Duration,
+) -> anyhow::Result<BTreeMap<Id, (SearchResult, Vec<NodeId>)>> {
+    let events = node.subscribe(timeout)?;
+    let seeds = node.search(query.clone())?;
+    let mut results: BTreeMap<Id, (SearchResult, Vec<NodeId>)> = BTreeMap::new();
+
+    if seeds == 0 {
+        term::info!("Not connected to any seeds that support search");
+        return Ok(results);
+    }
+
+    let mut spinner = term::spinner(format!("Searching {seeds} seed(s).."));
+    let mut answered = 0;
+    let mut truncated = 0;
+
+    for e in events {
+        match e {
+            Ok(Event::SearchResults {
+                remote,
+                query: query_,
+                results: found,
+            }) if query == query_ => {
+                // Seeds return a limited number of results, so there may be more.
+                if found.len() >= node::SEARCH_RESULTS_LIMIT {
+                    truncated += 1;
+                }
+                for result in found {
+                    results
+                        .entry(result.rid)
+                        .or_insert_with(|| (result, Vec::new()))
+                        .1
+                        .push(remote);
+                }
+                answered += 1;
+                spinner.message(format!("Searching {seeds} seed(s).. ({answered} answered)"));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e.into()),
+        }
+        if answered == seeds {
+            break;
+        }
+    }
+    spinner.message(format!(
+        "Found {} repository(s) on {answered} of {seeds} seed(s)",
+        results.len()
+    ));
+    spinner.finish();
+
+    if truncated > 0 {
+        term::warning(&format!(
+            "{truncated} seed(s) returned the maximum of {} results, some repositories may be missing",
+            node::SEARCH_RESULTS_LIMIT
+        ));
+    }
+    Ok(results)
+}
+
+/// Print search results as a table.
+pub fn print(results: BTreeMap<Id, (SearchResult, Vec<NodeId>)>, verbose: bool) {
+    if results.is_empty() {
+        return;
+    }
+    let mut table = term::Table::default();
+
+    for (rid, (result, seeds)) in results {
+        table.push([
+            term::format::bold(result.name),
+            term::format::tertiary(rid.urn()),
+            term::format::secondary(term::format::oid(result.head)),
+            term::format::italic(result.description),
+            term::format::dim(format!("{} seed(s)", seeds.len())),
+        ]);
+        if verbose {
+            for delegate in &result.delegates {
+                table.push([
+                    term::format::default(String::new()),
+                    term::format::did(delegate),
+                    term::format::default(String::new()),
+                    term::format::default(String::new()),
+                    term::format::default(String::new()),
+                ]);
+            }
+        }
+    }
+    table.print();
+}
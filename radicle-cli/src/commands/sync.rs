@@ -1,13 +1,18 @@
 use std::collections::BTreeSet;
 use std::ffi::OsString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{io, time};
 
 use anyhow::{anyhow, Context as _};
 
+use radicle::git::Oid;
 use radicle::node;
-use radicle::node::{Event, FetchResult, FetchResults, Handle as _, Node};
+use radicle::node::{
+    ConnectAddress, ConnectOptions, Event, FetchResult, FetchResults, Handle as _, Node, Signal,
+};
 use radicle::prelude::{Id, NodeId, Profile};
+use radicle::storage::git::bundle;
+use radicle::storage::{ReadRepository as _, ReadStorage as _};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -19,37 +24,79 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad sync [<rid>] [<option>...]
-    rad sync [<rid>] [--fetch] [--seed <nid>] [<option>...]
-
-    By default, the current repository is synced.
-
-    When `--fetch` is specified, this command will fetch from
-    all connected seeds. To instead specify a seed, use the
-    `--seed <nid>` option in combination with `--fetch`.
+    rad sync [<rid>] [--announce-only | --fetch-only] [--seed <nid>...] [<option>...]
+    rad sync [<rid>] --bundle <file>
+    rad sync [<rid>] --unbundle <file>
+
+    By default, the current repository is synced, by announcing its refs
+    and waiting for seeds to fetch them. Connected seeds are also asked to
+    fetch the repository from us right away, and let us know once they
+    have, or if their tracking policy doesn't allow it.
+
+    When `--fetch-only` is specified, this command will instead fetch from
+    all connected seeds, and nothing is announced.
+
+    When preferred seeds are configured in the profile's `config.json`,
+    or default seeds are set in the repository's identity document, eg.
+    with `rad id handoff --seed`, the repository is announced to those
+    seeds, and the command waits for them specifically, instead of all
+    connected seeds.
+
+    To restrict either half of the sync to specific seeds, use the
+    `--seed <nid>` option, which may be given more than once. This takes
+    precedence over the preferred seeds. Seeds given as `<nid>@<addr>` are
+    connected to first, if we aren't already. The outcome is then reported
+    for each seed, and the command fails unless all of them are in sync, so
+    that replication to a known seed can be ensured, eg. before going offline.
+
+    For nodes without a network path between them, `--bundle` writes the
+    repository to a file, which can then be imported on the other side
+    with `--unbundle`. As when fetching, only the remotes in the tracking
+    scope of the repository are imported, and all their signed refs and
+    identities are verified before anything is written to storage.
+
+    When fetching rewrites the history of the canonical branch, eg. because
+    a delegate force-pushed it, a warning is shown. Nodes don't adopt such a
+    rewrite until the delegates acknowledge it with `rad id rewrite`, unless
+    they are started with `--allow-rewrites`.
 
 Options
 
-    --fetch, -f         Fetch from seeds instead of having seeds fetch from us
-    --seed <nid>        Seed to fetch from (use with `--fetch`)
-    --timeout <secs>    How many seconds to wait while syncing
-    --verbose, -v       Verbose output
-    --help              Print help
+    --announce-only, -a   Only announce our refs and wait for seeds to fetch them (default)
+    --fetch-only, -f      Only fetch from seeds, without announcing our refs
+    --seed <nid>[@<addr>] Seed to sync with (may be specified multiple times)
+    --bundle <file>       Write the repository to a bundle file, for offline sync
+    --unbundle <file>     Import the repository from a bundle file
+    --timeout, -t <secs>  How many seconds to wait for seeds while syncing (default: 9)
+                          Fetches that time out are retried twice
+    --verbose, -v         Verbose output
+    --help                Print help
 
 "#,
 };
 
+/// Number of times a fetch is retried when the node doesn't respond in time.
+pub const FETCH_RETRIES: usize = 2;
+
 #[derive(Default, Debug)]
 pub enum SyncMode {
+    /// Fetch from seeds.
     Fetch,
+    /// Announce our refs and wait for seeds to fetch from us.
     #[default]
     Announce,
+    /// Write the repository to a bundle file.
+    Bundle(PathBuf),
+    /// Import the repository from a bundle file.
+    Unbundle(PathBuf),
 }
 
 #[derive(Default, Debug)]
 pub struct Options {
     pub rid: Option<Id>,
-    pub seed: Option<NodeId>,
+    pub seeds: Vec<NodeId>,
+    /// Addresses of the seeds to connect to before syncing.
+    pub addresses: Vec<ConnectAddress>,
     pub verbose: bool,
     pub timeout: time::Duration,
     pub mode: SyncMode,
@@ -61,9 +108,10 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut verbose = false;
-        let mut timeout = time::Duration::from_secs(9);
+        let mut timeout = node::DEFAULT_TIMEOUT;
         let mut rid = None;
-        let mut seed = None;
+        let mut seeds = Vec::new();
+        let mut addresses = Vec::new();
         let mut mode = SyncMode::default();
 
         while let Some(arg) = parser.next()? {
@@ -71,14 +119,36 @@ impl Args for Options {
                 Long("verbose") | Short('v') => {
                     verbose = true;
                 }
-                Long("seed") if matches!(mode, SyncMode::Fetch) => {
+                Long("seed") => {
                     let val = parser.value()?;
-                    let val = term::args::nid(&val)?;
-                    seed = Some(val);
+                    let nid = if val.to_string_lossy().contains('@') {
+                        let addr: ConnectAddress = val
+                            .to_string_lossy()
+                            .parse()
+                            .map_err(|e| anyhow!("invalid seed address {:?}: {e}", val))?;
+                        let nid = addr.id;
+
+                        addresses.push(addr);
+                        nid
+                    } else {
+                        term::args::nid(&val)?
+                    };
+                    if !seeds.contains(&nid) {
+                        seeds.push(nid);
+                    }
                 }
-                Long("fetch") | Short('f') => {
+                Long("fetch-only") | Long("fetch") | Short('f') => {
                     mode = SyncMode::Fetch;
                 }
+                Long("announce-only") | Long("announce") | Short('a') => {
+                    mode = SyncMode::Announce;
+                }
+                Long("bundle") => {
+                    mode = SyncMode::Bundle(parser.value()?.into());
+                }
+                Long("unbundle") => {
+                    mode = SyncMode::Unbundle(parser.value()?.into());
+                }
                 Long("timeout") | Short('t') => {
                     let value = parser.value()?;
                     let secs = term::args::parse_value("timeout", value)?;
@@ -102,7 +172,8 @@ impl Args for Options {
                 rid,
                 verbose,
                 timeout,
-                seed,
+                seeds,
+                addresses,
                 mode,
             },
             vec![],
@@ -124,37 +195,218 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     let mut node = radicle::Node::new(profile.socket());
 
+    if !options.addresses.is_empty() {
+        connect_and_wait(&mut node, &options.addresses, options.timeout)?;
+    }
+
     match options.mode {
-        SyncMode::Announce => announce(rid, node, options.timeout),
-        SyncMode::Fetch => fetch(rid, profile, &mut node, options.seed),
+        SyncMode::Announce if options.seeds.is_empty() => {
+            let mut seeds = profile.config.preferred_seeds.clone();
+            let (_, doc) = profile.storage.repository(rid)?.identity_doc()?;
+
+            for seed in doc.verified()?.seeds()? {
+                if !seeds.contains(&seed) {
+                    seeds.push(seed);
+                }
+            }
+            announce(rid, node, &seeds, options.timeout)
+        }
+        SyncMode::Announce => {
+            let sessions = node.sessions()?;
+            let mut seeds = BTreeSet::new();
+
+            for seed in &options.seeds {
+                if sessions.contains(seed) {
+                    seeds.insert(*seed);
+                } else {
+                    term::notice!("Not connected to seed {seed}, skipping..");
+                }
+            }
+            let report = if seeds.is_empty() {
+                SyncReport::default()
+            } else {
+                sync_with(rid, node, seeds, options.timeout)?
+            };
+            report_seeds(&options.seeds, &report)
+        }
+        SyncMode::Fetch => fetch(rid, profile, &mut node, &options.seeds, options.timeout),
+        SyncMode::Bundle(path) => {
+            bundle::create(&profile.storage, rid, &path)?;
+            term::success!(
+                "Repository {} written to {}",
+                term::format::tertiary(rid),
+                term::format::tertiary(path.display())
+            );
+            Ok(())
+        }
+        SyncMode::Unbundle(path) => unbundle(rid, &profile, &mut node, &path),
     }
 }
 
-fn announce(rid: Id, mut node: Node, timeout: time::Duration) -> anyhow::Result<()> {
-    let events = node.subscribe(timeout)?;
-    let seeds = node.seeds(rid)?;
-    let mut seeds = seeds.connected().collect::<BTreeSet<_>>();
+/// Import a repository from a bundle file, and announce the new refs if the node is running.
+pub fn unbundle(rid: Id, profile: &Profile, node: &mut Node, path: &Path) -> anyhow::Result<()> {
+    let import = bundle::import(&profile.storage, rid, path, &profile.tracking()?)?;
 
+    for (remote, err) in &import.rejected {
+        term::warning(&format!(
+            "Rejected remote {}: {err}",
+            term::format::node(remote)
+        ));
+    }
+    if !import.untracked.is_empty() {
+        term::info!(
+            "Skipped {} remote(s) outside of the tracking scope of {}",
+            import.untracked.len(),
+            term::format::tertiary(rid)
+        );
+    }
+    if import.imported.is_empty() {
+        term::info!(
+            "Repository {} is already up to date",
+            term::format::tertiary(rid)
+        );
+        return Ok(());
+    }
+    term::success!(
+        "Imported {} remote(s) of {} from {}",
+        import.imported.len(),
+        term::format::tertiary(rid),
+        term::format::tertiary(path.display())
+    );
+    if !profile.tracking()?.is_repo_tracked(&rid)? {
+        term::notice!("Repository {rid} is not tracked; run `rad track {rid}` to track it");
+    }
+    if node.is_running() {
+        node.announce_refs(rid)?;
+    }
+    Ok(())
+}
+
+/// Announce our refs and wait for seeds to sync with us. If preferred seeds are given,
+/// only those seeds are waited for, otherwise all connected seeds of the repository are.
+pub fn announce(
+    rid: Id,
+    mut node: Node,
+    preferred: &[ConnectAddress],
+    timeout: time::Duration,
+) -> anyhow::Result<()> {
+    let seeds = if preferred.is_empty() {
+        node.seeds(rid)?
+            .connected()
+            .copied()
+            .collect::<BTreeSet<_>>()
+    } else {
+        connect(&mut node, preferred)?;
+        preferred.iter().map(|seed| seed.id).collect()
+    };
+    announce_to(rid, node, seeds, timeout)
+}
+
+/// Outcome of syncing with a set of seeds.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Seeds that fetched our refs.
+    pub synced: Vec<NodeId>,
+    /// Seeds that declined to fetch our refs.
+    pub declined: Vec<NodeId>,
+    /// Seeds that didn't let us know in time.
+    pub timed_out: Vec<NodeId>,
+}
+
+/// Announce our refs and wait for the given seeds to sync with us.
+pub fn announce_to(
+    rid: Id,
+    node: Node,
+    seeds: BTreeSet<NodeId>,
+    timeout: time::Duration,
+) -> anyhow::Result<()> {
     if seeds.is_empty() {
         term::info!("Not connected to any seeds");
         return Ok(());
     }
+    let report = sync_with(rid, node, seeds, timeout)?;
+
+    for seed in &report.declined {
+        term::notice!("Seed {seed} declined to sync..");
+    }
+    for seed in &report.timed_out {
+        term::notice!("Seed {seed} timed out..");
+    }
+
+    if report.synced.is_empty() {
+        if report.declined.is_empty() {
+            anyhow::bail!("all seeds timed out");
+        }
+        anyhow::bail!("no seeds synced with us");
+    }
+    Ok(())
+}
+
+/// Report the outcome of syncing with each of the given seeds. Fails unless all of them
+/// synced with us.
+fn report_seeds(seeds: &[NodeId], report: &SyncReport) -> anyhow::Result<()> {
+    let mut failed = 0;
+
+    for seed in seeds {
+        if report.synced.contains(seed) {
+            term::success!(
+                "Synced with seed {}",
+                term::format::tertiary(term::format::node(seed))
+            );
+            continue;
+        }
+        if report.declined.contains(seed) {
+            term::notice!("Seed {seed} declined to sync..");
+        } else if report.timed_out.contains(seed) {
+            term::notice!("Seed {seed} timed out..");
+        }
+        failed += 1;
+    }
+    if failed > 0 {
+        anyhow::bail!("failed to sync with {failed} of {} seed(s)", seeds.len());
+    }
+    Ok(())
+}
+
+/// Announce our refs, ask the given seeds to fetch them, and wait for them to sync with us.
+pub fn sync_with(
+    rid: Id,
+    mut node: Node,
+    mut seeds: BTreeSet<NodeId>,
+    timeout: time::Duration,
+) -> anyhow::Result<SyncReport> {
+    let events = node.subscribe(timeout)?;
+
     node.announce_refs(rid)?;
 
+    // Ask the seeds to fetch from us now, rather than waiting for them to act on our
+    // announcement. Seeds that don't support sync requests still get the announcement.
+    for seed in &seeds {
+        node.signal(*seed, Signal::SyncRequest { rid })?;
+    }
+
     let mut spinner = term::spinner(format!("Syncing with {} node(s)..", seeds.len()));
-    let mut synced = Vec::new();
-    let mut timeout: Vec<NodeId> = Vec::new();
+    let mut report = SyncReport::default();
 
     for e in events {
         match e {
             Ok(Event::RefsSynced { remote, rid: rid_ }) if rid == rid_ => {
-                seeds.remove(&remote);
-                synced.push(remote);
-                spinner.message(format!("Synced with {remote}.."));
+                if seeds.remove(&remote) {
+                    report.synced.push(remote);
+                    spinner.message(format!("Synced with {remote}.."));
+                }
+            }
+            Ok(Event::Signal {
+                remote,
+                signal: Signal::SyncDeclined { rid: rid_ },
+            }) if rid == rid_ => {
+                if seeds.remove(&remote) {
+                    report.declined.push(remote);
+                }
             }
             Ok(_) => {}
             Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
-                timeout.extend(seeds.into_iter());
+                report.timed_out.extend(seeds.into_iter());
                 break;
             }
             Err(e) => return Err(e.into()),
@@ -164,38 +416,92 @@ fn announce(rid: Id, mut node: Node, timeout: time::Duration) -> anyhow::Result<
         }
     }
 
-    if synced.is_empty() {
+    if report.synced.is_empty() {
         spinner.failed();
     } else {
-        spinner.message(format!("Synced with {} node(s)", synced.len()));
+        spinner.message(format!("Synced with {} node(s)", report.synced.len()));
         spinner.finish();
     }
+    Ok(report)
+}
 
-    for seed in timeout {
-        term::notice!("Seed {seed} timed out..");
-    }
+/// Connect to the given seeds, if we aren't already connected.
+pub fn connect(node: &mut Node, seeds: &[ConnectAddress]) -> anyhow::Result<()> {
+    let sessions = node.sessions()?;
 
-    if synced.is_empty() {
-        anyhow::bail!("all seeds timed out");
+    for seed in seeds.iter().filter(|s| !sessions.contains(&s.id)) {
+        if let Err(e) = node.connect(seed.id, seed.addr.clone(), ConnectOptions::default()) {
+            term::notice!("Failed to connect to seed {}: {e}", seed.id);
+        }
     }
     Ok(())
 }
 
+/// Connect to the given seeds, and wait for the connections to be established, up to the
+/// given timeout. Returns the seeds we are connected to.
+pub fn connect_and_wait(
+    node: &mut Node,
+    seeds: &[ConnectAddress],
+    timeout: time::Duration,
+) -> anyhow::Result<BTreeSet<NodeId>> {
+    let mut events = node.subscribe(timeout)?;
+    let mut connected = node.sessions()?.into_iter().collect::<BTreeSet<_>>();
+    let mut pending = BTreeSet::new();
+
+    for seed in seeds.iter().filter(|s| !connected.contains(&s.id)) {
+        if let Err(e) = node.connect(seed.id, seed.addr.clone(), ConnectOptions::default()) {
+            term::notice!("Failed to connect to seed {}: {e}", seed.id);
+        } else {
+            pending.insert(seed.id);
+        }
+    }
+    while !pending.is_empty() {
+        match events.next() {
+            Some(Ok(Event::PeerConnected { nid })) => {
+                if pending.remove(&nid) {
+                    connected.insert(nid);
+                }
+            }
+            Some(Ok(_)) => {}
+            Some(Err(e)) if e.kind() == io::ErrorKind::WouldBlock => {
+                for seed in pending {
+                    term::notice!("Seed {seed} timed out..");
+                }
+                break;
+            }
+            Some(Err(e)) => return Err(e.into()),
+            None => break,
+        }
+    }
+    Ok(seeds
+        .iter()
+        .map(|s| s.id)
+        .filter(|id| connected.contains(id))
+        .collect())
+}
+
+/// Fetch the repository from the given seeds, or from all connected seeds if none are given.
 pub fn fetch(
     rid: Id,
     profile: Profile,
     node: &mut Node,
-    seed: Option<NodeId>,
+    seeds: &[NodeId],
+    timeout: time::Duration,
 ) -> anyhow::Result<()> {
     if !profile.tracking()?.is_repo_tracked(&rid)? {
         anyhow::bail!("repository {rid} is not tracked");
     }
 
-    let results = if let Some(seed) = seed {
-        let result = fetch_from(rid, &seed, node)?;
-        FetchResults::from(vec![(seed, result)])
+    let head = head(rid, &profile);
+    let results = if seeds.is_empty() {
+        fetch_all(rid, node, timeout)?
     } else {
-        fetch_all(rid, node)?
+        let mut results = FetchResults::default();
+        for seed in seeds {
+            let result = fetch_from(rid, seed, node, timeout)?;
+            results.push(*seed, result);
+        }
+        results
     };
     let success = results.success().count();
     let failed = results.failed().count();
@@ -204,11 +510,54 @@ pub fn fetch(
         term::error(format!("Failed to fetch repository from {failed} seed(s)"));
     } else {
         term::success!("Fetched repository from {success} seed(s)");
+        warn_rewrite(rid, &profile, head)?;
+    }
+    if !seeds.is_empty() && failed > 0 {
+        anyhow::bail!("failed to fetch from {failed} of {} seed(s)", seeds.len());
+    }
+    Ok(())
+}
+
+/// Get the head of the canonical branch of a repository, if we have it.
+fn head(rid: Id, profile: &Profile) -> Option<Oid> {
+    let repo = profile.storage.repository(rid).ok()?;
+    let (_, head) = repo.head().ok()?;
+
+    Some(head)
+}
+
+/// Warn about a rewrite of the history of the canonical branch since it was at `before`, or
+/// about a rewrite that is held back until the delegates acknowledge it.
+fn warn_rewrite(rid: Id, profile: &Profile, before: Option<Oid>) -> anyhow::Result<()> {
+    let repo = profile.storage.repository(rid)?;
+
+    if let Some(rewrite) = repo.rewrite(false)?.filter(|r| !r.adopted) {
+        term::warning(&format!(
+            "The delegates rewrote the history of the canonical branch ({rewrite})"
+        ));
+        term::warning(
+            "The rewrite is not adopted until the delegates acknowledge it with `rad id rewrite`",
+        );
+        return Ok(());
+    }
+    let Some(old) = before else {
+        return Ok(());
+    };
+    let (_, new) = repo.head()?;
+
+    if old != new && !repo.backend.graph_descendant_of(*new, *old)? {
+        term::warning(&format!(
+            "The history of the canonical branch was rewritten ({old:.7}...{new:.7})"
+        ));
     }
     Ok(())
 }
 
-pub fn fetch_all(rid: Id, node: &mut Node) -> Result<FetchResults, node::Error> {
+pub fn fetch_all(
+    rid: Id,
+    node: &mut Node,
+    timeout: time::Duration,
+) -> Result<FetchResults, node::Error> {
     // Get seeds. This consults the local routing table only.
     let seeds = node.seeds(rid)?;
     let mut results = FetchResults::default();
@@ -216,20 +565,49 @@ pub fn fetch_all(rid: Id, node: &mut Node) -> Result<FetchResults, node::Error>
     if seeds.has_connections() {
         // Fetch from all seeds.
         for seed in seeds.connected() {
-            let result = fetch_from(rid, seed, node)?;
+            let result = fetch_from(rid, seed, node, timeout)?;
             results.push(*seed, result);
         }
     }
     Ok(results)
 }
 
-pub fn fetch_from(rid: Id, seed: &NodeId, node: &mut Node) -> Result<FetchResult, node::Error> {
-    let spinner = term::spinner(format!(
+/// Fetch the repository from the given seed. If the node doesn't respond in time, the fetch
+/// is retried up to [`FETCH_RETRIES`] times before it is considered failed.
+pub fn fetch_from(
+    rid: Id,
+    seed: &NodeId,
+    node: &mut Node,
+    timeout: time::Duration,
+) -> Result<FetchResult, node::Error> {
+    let mut spinner = term::spinner(format!(
         "Fetching {} from {}..",
         term::format::tertiary(rid),
         term::format::tertiary(term::format::node(seed))
     ));
-    let result = node.fetch(rid, *seed)?;
+    let mut retries = FETCH_RETRIES;
+    let result = loop {
+        match node.fetch(rid, *seed, timeout) {
+            Ok(result) => break result,
+            Err(e) if e.is_timeout() && retries > 0 => {
+                retries -= 1;
+                spinner.message(format!(
+                    "Fetching {} from {}.. (retrying)",
+                    term::format::tertiary(rid),
+                    term::format::tertiary(term::format::node(seed))
+                ));
+            }
+            Err(e) if e.is_timeout() => {
+                break FetchResult::Failed {
+                    reason: format!("timed out after {} second(s)", timeout.as_secs()),
+                };
+            }
+            Err(e) => {
+                spinner.failed();
+                return Err(e);
+            }
+        }
+    };
 
     match &result {
         FetchResult::Success { .. } => {
@@ -1,9 +1,11 @@
+use std::collections::BTreeSet;
 use std::ffi::OsString;
+use std::time;
 
 use anyhow::anyhow;
 
-use radicle::node::tracking::{Alias, Scope};
-use radicle::node::{Handle, NodeId};
+use radicle::node::tracking::{self, Alias, FetchLimits, Scope};
+use radicle::node::{Handle, NodeId, Timestamp, DEFAULT_TIMEOUT, TRACKING_DB_FILE};
 use radicle::{prelude::*, Node};
 
 use crate::commands::rad_sync as sync;
@@ -17,8 +19,9 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad track <nid> [--alias <name>] [<option>...]
-    rad track <rid> [--[no-]fetch] [--scope <scope>] [<option>...]
+    rad track <nid> [--alias <name>] [--expires <duration>] [<option>...]
+    rad track <rid> [--[no-]fetch] [--scope <scope> | --remote <did>...] [<option>...]
+              [--max-pack-size <bytes>] [--max-object-size <bytes>]
 
     The `track` command takes either an NID or an RID. Based on the argument, it will
     either update the tracking policy of a node (NID), or a repository (RID).
@@ -28,11 +31,38 @@ Usage
     On the other hand, with `trusted`, only the repository delegates will be tracked,
     plus any remote that is explicitly tracked via `rad track <nid>`.
 
+    To only track the repository delegates and a given set of remotes, use `--remote`
+    once for every remote to track. This is useful for seeds that want to host
+    a large repository without every fork of it.
+
+    The scope of a repository that is already tracked can be changed by tracking it
+    again with a different `--scope`. The node then removes the remotes that are no
+    longer in scope from storage, and fetches the ones that came into scope.
+
+    When tracking a node, an expiry can be given with `--expires`, eg. `30d`. Once it
+    expires, the node drops the tracking policy. This is useful to temporarily follow
+    a node, eg. to review its fork of a repository. Tracking the node again without
+    `--expires` makes the policy permanent.
+
+    When tracking a repository, the data accepted when fetching it can be limited with
+    `--max-pack-size` and `--max-object-size`. Fetches transferring more than the maximum
+    pack size are aborted, and namespaces containing an object larger than the maximum
+    object size are refused. This protects seeds from very large pushes. Tracking the
+    repository again without these options leaves its limits unchanged.
+
 Options
 
     --alias <name>         Associate an alias to a tracked node
+    --expires <duration>   Drop the node's tracking policy after the given duration,
+                           in seconds, minutes, hours, days or weeks (eg. '30d')
     --[no-]fetch           Fetch refs after tracking
     --scope <scope>        Node (remote) tracking scope for a repository
+    --remote <did>         Track the given remote of a repository, in addition to its
+                           delegates (may be specified multiple times)
+    --max-pack-size <bytes>
+                           Maximum number of bytes received when fetching the repository
+    --max-object-size <bytes>
+                           Maximum size of a single object fetched for the repository
     --verbose, -v          Verbose output
     --help                 Print help
 "#,
@@ -40,8 +70,16 @@ Options
 
 #[derive(Debug)]
 pub enum Operation {
-    TrackNode { nid: NodeId, alias: Option<Alias> },
-    TrackRepo { rid: Id, scope: Scope },
+    TrackNode {
+        nid: NodeId,
+        alias: Option<Alias>,
+        expires: Option<time::Duration>,
+    },
+    TrackRepo {
+        rid: Id,
+        scope: Scope,
+        limits: FetchLimits,
+    },
 }
 
 #[derive(Debug)]
@@ -67,14 +105,20 @@ impl Args for Options {
                         op = Some(Operation::TrackRepo {
                             rid,
                             scope: Scope::default(),
+                            limits: FetchLimits::default(),
                         });
                     } else if let Ok(did) = term::args::did(val) {
                         op = Some(Operation::TrackNode {
                             nid: did.into(),
                             alias: None,
+                            expires: None,
                         });
                     } else if let Ok(nid) = term::args::nid(val) {
-                        op = Some(Operation::TrackNode { nid, alias: None });
+                        op = Some(Operation::TrackNode {
+                            nid,
+                            alias: None,
+                            expires: None,
+                        });
                     }
                 }
                 (Long("alias"), Some(Operation::TrackNode { alias, .. })) => {
@@ -86,6 +130,11 @@ impl Args for Options {
 
                     *alias = Some(name.to_owned());
                 }
+                (Long("expires"), Some(Operation::TrackNode { expires, .. })) => {
+                    let val = parser.value()?;
+
+                    *expires = Some(term::args::duration(&val)?);
+                }
                 (Long("scope"), Some(Operation::TrackRepo { scope, .. })) => {
                     let val = parser.value()?;
 
@@ -95,6 +144,29 @@ impl Args for Options {
                         .ok_or_else(|| anyhow!("scope specified is not UTF-8"))?
                         .parse()?;
                 }
+                (Long("remote"), Some(Operation::TrackRepo { scope, .. })) => {
+                    let val = parser.value()?;
+                    let did = term::args::did(&val)?;
+
+                    match scope {
+                        Scope::Explicit(remotes) => {
+                            remotes.insert(did);
+                        }
+                        _ => *scope = Scope::Explicit(BTreeSet::from([did])),
+                    }
+                }
+                (Long("max-pack-size"), Some(Operation::TrackRepo { limits, .. })) => {
+                    let val = parser.value()?;
+                    let size = term::args::parse_value("max-pack-size", val)?;
+
+                    limits.max_pack_size = Some(size);
+                }
+                (Long("max-object-size"), Some(Operation::TrackRepo { limits, .. })) => {
+                    let val = parser.value()?;
+                    let size = term::args::parse_value("max-object-size", val)?;
+
+                    limits.max_object_size = Some(size);
+                }
                 (Long("fetch"), Some(Operation::TrackRepo { .. })) => fetch = true,
                 (Long("no-fetch"), Some(Operation::TrackRepo { .. })) => fetch = false,
                 (Long("verbose") | Short('v'), _) => verbose = true,
@@ -123,14 +195,22 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let mut node = radicle::Node::new(profile.socket());
 
     match options.op {
-        Operation::TrackNode { nid, alias } => {
-            track_node(nid, alias, &mut node)?;
+        Operation::TrackNode {
+            nid,
+            alias,
+            expires,
+        } => {
+            track_node(nid, alias, expires, &mut node)?;
         }
-        Operation::TrackRepo { rid, scope } => {
+        Operation::TrackRepo { rid, scope, limits } => {
             track_repo(rid, scope, &mut node)?;
 
+            if !limits.is_empty() {
+                set_limits(rid, limits, &profile)?;
+            }
+
             if options.fetch {
-                sync::fetch(rid, profile, &mut node, None)?;
+                sync::fetch(rid, profile, &mut node, &[], DEFAULT_TIMEOUT)?;
             }
         }
     }
@@ -138,19 +218,58 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 }
 
 pub fn track_repo(rid: Id, scope: Scope, node: &mut Node) -> anyhow::Result<()> {
-    let tracked = node.track_repo(rid, scope)?;
+    let tracked = node.track_repo(rid, scope.clone())?;
     let outcome = if tracked { "updated" } else { "exists" };
 
-    term::success!(
-        "Tracking policy {outcome} for {} with scope '{scope}'",
-        term::format::tertiary(rid),
-    );
+    if let Scope::Explicit(remotes) = &scope {
+        term::success!(
+            "Tracking policy {outcome} for {} with scope 'explicit'",
+            term::format::tertiary(rid),
+        );
+        for did in remotes {
+            term::info!("  {}", term::format::tertiary(did));
+        }
+    } else {
+        term::success!(
+            "Tracking policy {outcome} for {} with scope '{scope}'",
+            term::format::tertiary(rid),
+        );
+    }
+
+    Ok(())
+}
+
+/// Set the limits on the data accepted when fetching a repository. The node reads them
+/// from the tracking database before every fetch.
+pub fn set_limits(rid: Id, limits: FetchLimits, profile: &Profile) -> anyhow::Result<()> {
+    let mut store = tracking::store::Config::open(profile.home.node().join(TRACKING_DB_FILE))?;
+
+    if store.set_repo_limits(&rid, limits)? {
+        term::success!("Fetch limits updated for {}", term::format::tertiary(rid));
+    }
+    if let Some(max) = limits.max_pack_size {
+        term::info!("  Maximum pack size: {} bytes", term::format::dim(max));
+    }
+    if let Some(max) = limits.max_object_size {
+        term::info!("  Maximum object size: {} bytes", term::format::dim(max));
+    }
 
     Ok(())
 }
 
-pub fn track_node(nid: NodeId, alias: Option<Alias>, node: &mut Node) -> anyhow::Result<()> {
-    let tracked = node.track_node(nid, alias.clone())?;
+pub fn track_node(
+    nid: NodeId,
+    alias: Option<Alias>,
+    expires: Option<time::Duration>,
+    node: &mut Node,
+) -> anyhow::Result<()> {
+    let expiry = expires
+        .map(|d| -> anyhow::Result<Timestamp> {
+            let now = time::SystemTime::now().duration_since(time::UNIX_EPOCH)?;
+            Ok((now + d).as_millis() as Timestamp)
+        })
+        .transpose()?;
+    let tracked = node.track_node(nid, alias.clone(), expiry)?;
     let outcome = if tracked { "updated" } else { "exists" };
 
     if let Some(alias) = alias {
@@ -164,6 +283,25 @@ pub fn track_node(nid: NodeId, alias: Option<Alias>, node: &mut Node) -> anyhow:
             term::format::tertiary(nid),
         );
     }
+    if let Some(expires) = expires {
+        term::info!(
+            "The policy expires in {}",
+            term::format::dim(humanize(expires))
+        );
+    }
 
     Ok(())
 }
+
+/// Format a duration with the largest unit that divides it.
+fn humanize(d: time::Duration) -> String {
+    let secs = d.as_secs();
+
+    for (unit, name) in [(60 * 60 * 24, "day"), (60 * 60, "hour"), (60, "minute")] {
+        if secs >= unit && secs % unit == 0 {
+            let n = secs / unit;
+            return format!("{n} {name}{}", if n == 1 { "" } else { "s" });
+        }
+    }
+    format!("{secs} second{}", if secs == 1 { "" } else { "s" })
+}
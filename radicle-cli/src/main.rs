@@ -2,8 +2,6 @@ use std::ffi::OsString;
 use std::io;
 use std::{io::ErrorKind, iter, process};
 
-use anyhow::anyhow;
-
 use radicle_cli::commands::*;
 use radicle_cli::terminal as term;
 
@@ -20,14 +18,23 @@ enum Command {
 }
 
 fn main() {
-    match parse_args().map_err(Some).and_then(run) {
+    match term::theme::init(term::theme::ColorMode::Auto)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| parse_args())
+        .map_err(Some)
+        .and_then(run)
+    {
         Ok(_) => process::exit(0),
-        Err(err) => {
-            if let Some(err) = err {
-                term::error(format!("Error: rad: {err}"));
+        Err(Some(err)) => {
+            let code = term::args::code(&err);
+
+            term::error(format!("Error ({code}): rad: {err}"));
+            if let Some(hint) = term::args::hint(&err) {
+                term::info!("{} {}", term::ERROR_HINT_PREFIX, term::format::yellow(hint));
             }
-            process::exit(1);
+            process::exit(code.exit_status());
         }
+        Err(None) => process::exit(1),
     }
 }
 
@@ -45,6 +52,15 @@ fn parse_args() -> anyhow::Result<Command> {
             Long("version") => {
                 command = Some(Command::Version);
             }
+            Long("color") if command.is_none() => {
+                term::global("--color", &parser.value()?.to_string_lossy())?;
+            }
+            Long("time") if command.is_none() => {
+                term::global("--time", &parser.value()?.to_string_lossy())?;
+            }
+            Long("no-announce") if command.is_none() => {
+                term::global_flag("--no-announce")?;
+            }
             Value(val) if command.is_none() => {
                 if val == *"." {
                     command = Some(Command::Other(vec![OsString::from("inspect")]));
@@ -123,6 +139,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "block" => {
+            term::run_command_args::<rad_block::Options, _>(
+                rad_block::HELP,
+                "Block",
+                rad_block::run,
+                args.to_vec(),
+            );
+        }
         "checkout" => {
             term::run_command_args::<rad_checkout::Options, _>(
                 rad_checkout::HELP,
@@ -131,6 +155,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "clean" => {
+            term::run_command_args::<rad_clean::Options, _>(
+                rad_clean::HELP,
+                "Clean",
+                rad_clean::run,
+                args.to_vec(),
+            );
+        }
         "clone" => {
             term::run_command_args::<rad_clone::Options, _>(
                 rad_clone::HELP,
@@ -139,6 +171,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "cob" => {
+            term::run_command_args::<rad_cob::Options, _>(
+                rad_cob::HELP,
+                "Cob",
+                rad_cob::run,
+                args.to_vec(),
+            );
+        }
         "comment" => {
             term::run_command_args::<rad_comment::Options, _>(
                 rad_comment::HELP,
@@ -203,6 +243,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "invite" => {
+            term::run_command_args::<rad_invite::Options, _>(
+                rad_invite::HELP,
+                "Invite",
+                rad_invite::run,
+                args.to_vec(),
+            );
+        }
         "issue" => {
             term::run_command_args::<rad_issue::Options, _>(
                 rad_issue::HELP,
@@ -211,6 +259,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "label" => {
+            term::run_command_args::<rad_label::Options, _>(
+                rad_label::HELP,
+                "Label",
+                rad_label::run,
+                args.to_vec(),
+            );
+        }
         "ls" => {
             term::run_command_args::<rad_ls::Options, _>(
                 rad_ls::HELP,
@@ -235,6 +291,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "notes" => {
+            term::run_command_args::<rad_notes::Options, _>(
+                rad_notes::HELP,
+                "Notes",
+                rad_notes::run,
+                args.to_vec(),
+            );
+        }
         "patch" => {
             term::run_command_args::<rad_patch::Options, _>(
                 rad_patch::HELP,
@@ -267,6 +331,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "search" => {
+            term::run_command_args::<rad_search::Options, _>(
+                rad_search::HELP,
+                "Search",
+                rad_search::run,
+                args.to_vec(),
+            );
+        }
         "self" => {
             term::run_command_args::<rad_self::Options, _>(
                 rad_self::HELP,
@@ -335,8 +407,8 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
             rad_remote::run,
             args.to_vec(),
         ),
-        _ => {
-            let exe = format!("{NAME}-{exe}");
+        name => {
+            let exe = format!("{NAME}-{name}");
             let status = process::Command::new(exe.clone()).args(args).status();
 
             match status {
@@ -347,7 +419,17 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 }
                 Err(err) => {
                     if let ErrorKind::NotFound = err.kind() {
-                        return Err(Some(anyhow!("command `{}` not found", exe)));
+                        let commands = rad_help::COMMANDS.iter().map(|help| help.name);
+
+                        return Err(Some(
+                            term::args::Error::Unknown {
+                                kind: "command",
+                                value: name.to_owned(),
+                                suggestion: term::args::suggest(name, commands)
+                                    .map(ToOwned::to_owned),
+                            }
+                            .into(),
+                        ));
                     } else {
                         return Err(Some(err.into()));
                     }
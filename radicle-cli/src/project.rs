@@ -1,9 +1,33 @@
+use anyhow::anyhow;
+
 use radicle::git::raw::Remote;
 use radicle::git::RefString;
 use radicle::prelude::*;
 
 use crate::git;
 
+/// Resolve the repository to operate on: the given RID if any, otherwise the repository
+/// of the current working copy. Also returns the working copy, if we're in one.
+pub fn resolve(rid: Option<Id>) -> anyhow::Result<(Option<git::Repository>, Id)> {
+    match rid {
+        Some(rid) => {
+            let workdir = radicle::rad::cwd()
+                .ok()
+                .filter(|(_, id)| *id == rid)
+                .map(|(workdir, _)| workdir);
+
+            Ok((workdir, rid))
+        }
+        None => {
+            let (workdir, rid) = radicle::rad::cwd().map_err(|_| {
+                anyhow!("this command must be run in the context of a project, or with `--repo`")
+            })?;
+
+            Ok((Some(workdir), rid))
+        }
+    }
+}
+
 /// Setup a project remote and tracking branch.
 pub struct SetupRemote<'a> {
     /// The project id.
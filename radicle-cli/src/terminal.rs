@@ -1,9 +1,12 @@
+pub mod announce;
 pub mod args;
 pub use args::{Args, Error, Help};
+pub mod comment;
 pub mod format;
 pub mod io;
 pub use io::{proposal, signer};
 pub mod patch;
+pub mod query;
 pub use radicle_term::*;
 
 use std::ffi::OsString;
@@ -66,11 +69,18 @@ where
 {
     use io as term;
 
+    let args = match globals(args) {
+        Ok(args) => args,
+        Err(err) => {
+            term::error(format!("rad {}: {err}", help.name));
+            process::exit(1);
+        }
+    };
     let options = match A::from_args(args) {
         Ok((opts, unparsed)) => {
             if let Err(err) = args::finish(unparsed) {
                 term::error(err);
-                process::exit(1);
+                process::exit(args::ErrorCode::Usage.exit_status());
             }
             opts
         }
@@ -82,34 +92,104 @@ where
                 }
                 Some(Error::Usage) => {
                     term::usage(help.name, help.usage);
-                    process::exit(1);
+                    process::exit(args::ErrorCode::Usage.exit_status());
                 }
                 _ => {}
             };
+            let err = args::unexpected(err, help.usage);
+            let code = args::code(&err);
+
             eprintln!(
                 "{} {} rad {}: {err}",
                 Paint::red(ERROR_PREFIX),
-                Paint::red("Error:"),
+                Paint::red(format!("Error ({code}):")),
                 help.name,
             );
 
-            if let Some(Error::WithHint { hint, .. }) = err.downcast_ref::<Error>() {
-                eprintln!("{}", Paint::yellow(hint));
+            if let Some(hint) = args::hint(&err) {
+                eprintln!("{} {}", ERROR_HINT_PREFIX, Paint::yellow(hint));
             }
 
-            process::exit(1);
+            process::exit(code.exit_status());
         }
     };
 
-    match cmd.run(options, self::profile) {
+    match cmd.run(options, self::profile).and_then(|()| {
+        if announce::is_pending() {
+            announce::flush(&self::profile()?)?;
+        }
+        Ok(())
+    }) {
         Ok(()) => process::exit(0),
         Err(err) => {
             terminal::fail(&format!("{action} failed"), &err);
-            process::exit(1);
+            process::exit(args::code(&err).exit_status());
         }
     }
 }
 
+/// Options accepted by all commands.
+pub const GLOBAL_OPTIONS: [&str; 2] = ["--color", "--time"];
+
+/// Flags accepted by all commands.
+pub const GLOBAL_FLAGS: [&str; 1] = ["--no-announce"];
+
+/// Handle the [`GLOBAL_OPTIONS`] and [`GLOBAL_FLAGS`], and return the remaining arguments.
+/// Arguments following `--` are left untouched.
+fn globals(args: Vec<OsString>) -> anyhow::Result<Vec<OsString>> {
+    let mut args = args.into_iter();
+    let mut rest = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let opt = arg.to_string_lossy().into_owned();
+
+        if opt == "--" {
+            rest.push(arg);
+            rest.extend(args);
+            break;
+        }
+        if GLOBAL_FLAGS.contains(&opt.as_str()) {
+            global_flag(&opt)?;
+            continue;
+        }
+        let (name, value) = match opt.split_once('=') {
+            Some((name, value)) if GLOBAL_OPTIONS.contains(&name) => (name, value.to_owned()),
+            _ if GLOBAL_OPTIONS.contains(&opt.as_str()) => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("missing value for option '{opt}'"))?;
+
+                (opt.as_str(), value.to_string_lossy().into_owned())
+            }
+            _ => {
+                rest.push(arg);
+                continue;
+            }
+        };
+        global(name, &value)?;
+    }
+    Ok(rest)
+}
+
+/// Apply one of the [`GLOBAL_OPTIONS`].
+pub fn global(name: &str, value: &str) -> anyhow::Result<()> {
+    match name {
+        "--color" => theme::set_color(value.parse().map_err(|e: String| anyhow::anyhow!(e))?),
+        "--time" => format::set_time_format(value.parse()?),
+        _ => anyhow::bail!("unknown option '{name}'"),
+    }
+    Ok(())
+}
+
+/// Apply one of the [`GLOBAL_FLAGS`].
+pub fn global_flag(name: &str) -> anyhow::Result<()> {
+    match name {
+        "--no-announce" => announce::disable(),
+        _ => anyhow::bail!("unknown flag '{name}'"),
+    }
+    Ok(())
+}
+
 /// Get the default profile. Fails if there is no profile.
 pub fn profile() -> Result<Profile, anyhow::Error> {
     let error = args::Error::WithHint {
@@ -118,7 +198,13 @@ pub fn profile() -> Result<Profile, anyhow::Error> {
     };
 
     match Profile::load() {
-        Ok(profile) => Ok(profile),
+        Ok(profile) => {
+            // The `--time` option takes precedence over the configuration.
+            if format::time_format().is_none() {
+                format::set_time_format(profile.config.time_format);
+            }
+            Ok(profile)
+        }
         Err(_) => Err(error.into()),
     }
 }
@@ -127,14 +213,16 @@ pub fn fail(header: &str, error: &anyhow::Error) {
     let err = error.to_string();
     let err = err.trim_end();
     let separator = if err.contains('\n') { ":\n" } else { ": " };
+    let code = args::code(error);
 
     println!(
-        "{ERROR_PREFIX} {}{}{error:#}",
+        "{ERROR_PREFIX} {} {}{}{error:#}",
         Paint::red(header).bold(),
+        Paint::red(format!("({code})")),
         Paint::red(separator),
     );
 
-    if let Some(Error::WithHint { hint, .. }) = error.downcast_ref::<Error>() {
+    if let Some(hint) = args::hint(error) {
         println!("{} {}", ERROR_HINT_PREFIX, Paint::yellow(hint));
         blank();
     }
@@ -0,0 +1,71 @@
+//! Announcing changes to the network.
+//!
+//! Commands don't announce the repositories they change right away. Instead, they record
+//! them with [`changed`], and the refs of each changed repository are announced once, when
+//! the command is done, however many changes were made to it, eg. by a batch operation.
+//!
+//! Announcements are disabled for any command with the global `--no-announce` flag.
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use radicle::identity::Id;
+use radicle::node::Handle as _;
+use radicle::profile::Profile;
+use radicle::Node;
+
+use crate::terminal as term;
+
+/// Whether announcements are disabled.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Repositories changed by the current command, that are yet to be announced.
+static CHANGED: Mutex<BTreeSet<Id>> = Mutex::new(BTreeSet::new());
+
+/// Disable announcements for the current command.
+pub fn disable() {
+    DISABLED.store(true, Ordering::SeqCst);
+}
+
+/// Check whether announcements are enabled.
+pub fn is_enabled() -> bool {
+    !DISABLED.load(Ordering::SeqCst)
+}
+
+/// Record that a repository was changed, and should be announced when the command is done.
+/// Does nothing if announcements are disabled.
+pub fn changed(rid: Id) {
+    if is_enabled() {
+        CHANGED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(rid);
+    }
+}
+
+/// Announce the refs of the changed repositories, once each. Warns instead of failing
+/// if the node isn't running.
+pub fn flush(profile: &Profile) -> anyhow::Result<()> {
+    let changed = std::mem::take(&mut *CHANGED.lock().unwrap_or_else(PoisonError::into_inner));
+    let mut node = Node::new(profile.socket());
+
+    for rid in changed {
+        match node.announce_refs(rid) {
+            Ok(()) => {}
+            Err(e) if e.is_connection_err() => {
+                term::warning("Could not announce changes: node is not running");
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Check whether there are changes to announce.
+pub fn is_pending() -> bool {
+    !CHANGED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .is_empty()
+}
@@ -1,5 +1,6 @@
 use std::ffi::OsString;
 use std::str::FromStr;
+use std::time;
 
 use anyhow::anyhow;
 
@@ -22,6 +23,166 @@ pub enum Error {
         err: anyhow::Error,
         hint: &'static str,
     },
+    /// An unknown command, option or value was given.
+    #[error("unknown {kind} '{value}'")]
+    Unknown {
+        kind: &'static str,
+        value: String,
+        suggestion: Option<String>,
+    },
+    /// The given object, eg. a repository or patch, was not found.
+    #[error("{kind} '{value}' was not found")]
+    NotFound {
+        kind: &'static str,
+        value: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl Error {
+    /// The error code of this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Help | Self::Usage | Self::Unknown { .. } => ErrorCode::Usage,
+            Self::NotFound { .. } => ErrorCode::NotFound,
+            Self::WithHint { err, .. } => self::code(err),
+        }
+    }
+
+    /// A hint to display along with the error, if any.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::WithHint { hint, .. } => Some(hint.to_string()),
+            Self::Unknown { suggestion, .. } | Self::NotFound { suggestion, .. } => {
+                suggestion.as_ref().map(|s| format!("Did you mean '{s}'?"))
+            }
+            Self::Help | Self::Usage => None,
+        }
+    }
+}
+
+/// Error code, included in error output and used as the exit status of `rad`,
+/// so that scripts can tell errors apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The command failed.
+    Failure = 1,
+    /// The command was invoked incorrectly, eg. with an unknown option.
+    Usage = 2,
+    /// An object given on the command line was not found.
+    NotFound = 3,
+}
+
+impl ErrorCode {
+    /// Exit status to use for this error.
+    pub fn exit_status(&self) -> i32 {
+        *self as i32
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{}", self.exit_status())
+    }
+}
+
+/// Get the error code of an error.
+pub fn code(err: &anyhow::Error) -> ErrorCode {
+    if let Some(err) = err.downcast_ref::<Error>() {
+        err.code()
+    } else if err.is::<lexopt::Error>() {
+        ErrorCode::Usage
+    } else {
+        ErrorCode::Failure
+    }
+}
+
+/// Get the hint to display along with an error, if any.
+pub fn hint(err: &anyhow::Error) -> Option<String> {
+    err.downcast_ref::<Error>().and_then(Error::hint)
+}
+
+/// Convert an argument parsing error into an [`Error::Unknown`] if it was caused by
+/// an unknown option, suggesting one of the options mentioned in the usage text.
+pub fn unexpected(err: anyhow::Error, usage: &str) -> anyhow::Error {
+    match err.downcast_ref::<lexopt::Error>() {
+        Some(lexopt::Error::UnexpectedOption(opt)) => {
+            let suggestion = suggest(opt, options(usage)).map(ToOwned::to_owned);
+
+            Error::Unknown {
+                kind: "option",
+                value: opt.clone(),
+                suggestion,
+            }
+            .into()
+        }
+        _ => err,
+    }
+}
+
+/// Return an [`Error::NotFound`] error, suggesting the closest candidate, if any.
+pub fn not_found<'a>(
+    kind: &'static str,
+    value: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Error {
+    Error::NotFound {
+        kind,
+        value: value.to_owned(),
+        suggestion: suggest(value, candidates).map(ToOwned::to_owned),
+    }
+}
+
+/// Get the long options mentioned in a usage text, eg. `--help`.
+pub fn options(usage: &str) -> Vec<&str> {
+    let mut options = usage
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .filter(|w| w.len() > 2 && w.starts_with("--") && !w.starts_with("---"))
+        .collect::<Vec<_>>();
+
+    options.sort_unstable();
+    options.dedup();
+    options
+}
+
+/// Suggest the candidate closest to the given input, if any is close enough.
+///
+/// Candidates are also matched by prefix, so that abbreviated identifiers such as
+/// short patch IDs get suggestions too.
+pub fn suggest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = (input.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|c| *c != input)
+        .map(|c| {
+            let prefix = c
+                .char_indices()
+                .nth(input.chars().count())
+                .map_or(c, |(i, _)| &c[..i]);
+            (distance(input, c).min(distance(input, prefix)), c)
+        })
+        .filter(|(d, _)| *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// Edit distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
 }
 
 pub struct Help {
@@ -130,3 +291,93 @@ pub fn cob(val: &OsString) -> anyhow::Result<cob::ObjectId> {
     let val = val.to_string_lossy();
     cob::ObjectId::from_str(&val).map_err(|_| anyhow!("invalid Object ID '{}'", val))
 }
+
+/// Parse a duration such as `90s`, `15m`, `12h`, `30d` or `2w`.
+pub fn duration(val: &OsString) -> anyhow::Result<time::Duration> {
+    let val = val.to_string_lossy();
+    let end = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    let (count, unit) = val.split_at(end);
+    let count: u64 = count
+        .parse()
+        .map_err(|_| anyhow!("invalid duration '{}'", val))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(anyhow!("invalid duration '{}', expected eg. '30d'", val)),
+    };
+    Ok(time::Duration::from_secs(count * secs))
+}
+
+/// Parse a date such as `2023-06-30`. The date is converted to the timestamp of the
+/// last second of that day, in UTC.
+pub fn date(val: &OsString) -> anyhow::Result<cob::Timestamp> {
+    let val = val.to_string_lossy();
+    let date = chrono::NaiveDate::parse_from_str(&val, "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid date '{}', expected eg. '2023-06-30'", val))?;
+    let time = date
+        .and_hms_opt(23, 59, 59)
+        .ok_or_else(|| anyhow!("invalid date '{}'", val))?;
+    let secs = u64::try_from(time.timestamp())
+        .map_err(|_| anyhow!("invalid date '{}', dates must be after 1970", val))?;
+
+    Ok(cob::Timestamp::from(secs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        let commands = ["issue", "patch", "sync", "track", "untrack"];
+
+        assert_eq!(suggest("isue", commands), Some("issue"));
+        assert_eq!(suggest("pach", commands), Some("patch"));
+        assert_eq!(suggest("untrak", commands), Some("untrack"));
+        assert_eq!(suggest("xyz", commands), None);
+        assert_eq!(suggest("patch", commands), None);
+    }
+
+    #[test]
+    fn test_suggest_prefix() {
+        let ids = [
+            "a3f4b1c9d2e84f0aa1b2c3d4e5f60718293a4b5c",
+            "f0e1d2c3b4a5968778695a4b3c2d1e0f9a8b7c6d",
+        ];
+
+        assert_eq!(suggest("a3f5b1c", ids), Some(ids[0]));
+        assert_eq!(suggest("f0e1d2c", ids), Some(ids[1]));
+        assert_eq!(suggest("0123456", ids), None);
+    }
+
+    #[test]
+    fn test_options() {
+        let usage = r#"
+Usage
+
+    rad sync [<rid>] [--fetch] [--seed <nid>] [<option>...]
+
+Options
+
+    --fetch, -f         Fetch from seeds
+    --help              Print help
+"#;
+        assert_eq!(options(usage), vec!["--fetch", "--help", "--seed"]);
+    }
+
+    #[test]
+    fn test_duration() {
+        let secs = |s: &str| duration(&OsString::from(s)).map(|d| d.as_secs()).ok();
+
+        assert_eq!(secs("90s"), Some(90));
+        assert_eq!(secs("15m"), Some(900));
+        assert_eq!(secs("30d"), Some(2_592_000));
+        assert_eq!(secs("2w"), Some(1_209_600));
+        assert_eq!(secs("30"), None);
+        assert_eq!(secs("d"), None);
+        assert_eq!(secs("3y"), None);
+    }
+}
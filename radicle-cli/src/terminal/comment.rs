@@ -0,0 +1,76 @@
+use radicle::cob::thread::{Comment, CommentId, Thread};
+use radicle::prelude::Did;
+use radicle::profile::Config;
+
+use crate::terminal as term;
+use crate::terminal::Element;
+
+/// Get the comments of a thread in thread order: every comment is followed by its replies.
+/// Comments are returned with their depth in the thread, starting at zero for the replies to
+/// `parent`, or, if there is no parent, for the comments that aren't replies.
+///
+/// Comments from blocked authors are left out, along with the replies to them.
+pub fn thread<'a>(
+    thread: &'a Thread,
+    parent: Option<&'a CommentId>,
+    config: &Config,
+) -> Vec<(usize, &'a CommentId, &'a Comment)> {
+    let top: Vec<_> = match parent {
+        Some(parent) => thread.replies(parent).collect(),
+        None => thread
+            .comments()
+            .filter(|(_, c)| {
+                c.reply_to()
+                    .map_or(true, |id| thread.comment(&id).is_none())
+            })
+            .collect(),
+    };
+    let mut comments = Vec::new();
+    let mut stack = top
+        .into_iter()
+        .rev()
+        .map(|(id, comment)| (0, id, comment))
+        .collect::<Vec<_>>();
+
+    while let Some((depth, id, comment)) = stack.pop() {
+        if config.is_blocked(&Did::from(comment.author())) {
+            continue;
+        }
+        comments.push((depth, id, comment));
+
+        let mut replies = thread
+            .replies(id)
+            .map(|(id, comment)| (depth + 1, id, comment))
+            .collect::<Vec<_>>();
+        replies.reverse();
+        stack.extend(replies);
+    }
+    comments
+}
+
+/// Render a comment returned by [`thread`]: a header with its author, time and id, followed by
+/// its body, indented by its depth.
+pub fn widget(depth: usize, id: &CommentId, comment: &Comment) -> Vec<Box<dyn Element>> {
+    let mut header: Vec<term::Label> = Vec::new();
+    if depth > 0 {
+        header.push(term::format::dim(format!("{}└─", "   ".repeat(depth - 1))).into());
+    }
+    header.extend([
+        term::format::did(&Did::from(comment.author())).into(),
+        term::format::timestamp(&comment.timestamp())
+            .dim()
+            .italic()
+            .into(),
+        term::format::dim(id.to_string()).into(),
+    ]);
+    let mut lines: Vec<Box<dyn Element>> = vec![
+        term::Label::blank().boxed(),
+        Box::new(term::Line::spaced(header)),
+    ];
+
+    let indent = "   ".repeat(depth);
+    for line in comment.body().lines() {
+        lines.push(term::Label::new(&format!("{indent}{line}")).boxed());
+    }
+    lines
+}
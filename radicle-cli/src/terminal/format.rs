@@ -1,12 +1,15 @@
+use std::sync::{PoisonError, RwLock};
 use std::{fmt, time};
 
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, SecondsFormat, Utc};
+
 pub use radicle_term::format::*;
 pub use radicle_term::{style, Paint};
 
 use radicle::cob::{ObjectId, Timestamp};
 use radicle::node::NodeId;
 use radicle::prelude::Did;
-use radicle::profile::Profile;
+use radicle::profile::{Profile, TimeFormat, Timezone};
 
 use crate::terminal as term;
 
@@ -24,6 +27,17 @@ pub fn oid(oid: impl Into<radicle::git::Oid>) -> String {
     format!("{:.7}", oid.into())
 }
 
+/// Format a number of bytes in a human-friendly way.
+pub fn bytes(n: u64) -> String {
+    if n < 1024 {
+        format!("{n} B")
+    } else if n < 1024 * 1024 {
+        format!("{:.1} KiB", n as f64 / 1024.)
+    } else {
+        format!("{:.1} MiB", n as f64 / (1024. * 1024.))
+    }
+}
+
 /// Wrap parenthesis around styled input, eg. `"input"` -> `"(input)"`.
 pub fn parens<D: fmt::Display>(input: Paint<D>) -> Paint<String> {
     Paint::new(format!("({})", input.item)).with_style(input.style)
@@ -40,13 +54,62 @@ pub fn did(did: &Did) -> Paint<String> {
     Paint::new(format!("{}…{}", &nid[..7], &nid[nid.len() - 7..]))
 }
 
-/// Format a timestamp.
+/// The time format set with [`set_time_format`], if any.
+static TIME_FORMAT: RwLock<Option<TimeFormat>> = RwLock::new(None);
+
+/// Set how timestamps are formatted.
+pub fn set_time_format(format: TimeFormat) {
+    *TIME_FORMAT.write().unwrap_or_else(PoisonError::into_inner) = Some(format);
+}
+
+/// Get how timestamps are formatted, if it was set.
+pub fn time_format() -> Option<TimeFormat> {
+    *TIME_FORMAT.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Format a timestamp, according to the configured [`TimeFormat`].
 pub fn timestamp(time: &Timestamp) -> Paint<String> {
-    let fmt = timeago::Formatter::new();
-    let now = Timestamp::now();
-    let duration = time::Duration::from_secs(now.as_secs() - time.as_secs());
+    match time_format().unwrap_or_default() {
+        TimeFormat::Relative => {
+            let fmt = timeago::Formatter::new();
+            let now = Timestamp::now();
+            let duration = time::Duration::from_secs(now.as_secs().saturating_sub(time.as_secs()));
 
-    Paint::new(fmt.convert(duration))
+            Paint::new(fmt.convert(duration))
+        }
+        TimeFormat::Iso(tz) => Paint::new(iso8601(time, tz)),
+    }
+}
+
+/// Format a timestamp in ISO-8601 format, in the given timezone.
+pub fn iso8601(time: &Timestamp, tz: Timezone) -> String {
+    let secs = i64::try_from(time.as_secs()).unwrap_or(i64::MAX);
+    let Some(utc) = NaiveDateTime::from_timestamp_opt(secs, 0) else {
+        return time.as_secs().to_string();
+    };
+    let utc = DateTime::<Utc>::from_utc(utc, Utc);
+
+    match tz {
+        Timezone::Utc => utc.to_rfc3339_opts(SecondsFormat::Secs, true),
+        Timezone::Local => utc
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        Timezone::Offset(minutes) => match FixedOffset::east_opt(minutes * 60) {
+            Some(offset) => utc
+                .with_timezone(&offset)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            None => utc.to_rfc3339_opts(SecondsFormat::Secs, true),
+        },
+    }
+}
+
+/// Format the date of a timestamp as `YYYY-MM-DD`, in UTC.
+pub fn date(time: &Timestamp) -> String {
+    let secs = i64::try_from(time.as_secs()).unwrap_or(i64::MAX);
+    let Some(utc) = NaiveDateTime::from_timestamp_opt(secs, 0) else {
+        return time.as_secs().to_string();
+    };
+    utc.format("%Y-%m-%d").to_string()
 }
 
 /// Identity formatter that takes a profile and displays it as
@@ -100,3 +163,23 @@ impl<'a> fmt::Display for Identity<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        let time = Timestamp::new(1_672_574_400);
+
+        assert_eq!(iso8601(&time, Timezone::Utc), "2023-01-01T12:00:00Z");
+        assert_eq!(
+            iso8601(&time, Timezone::Offset(120)),
+            "2023-01-01T14:00:00+02:00"
+        );
+        assert_eq!(
+            iso8601(&time, Timezone::Offset(-330)),
+            "2023-01-01T06:30:00-05:30"
+        );
+    }
+}
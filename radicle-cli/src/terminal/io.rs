@@ -1,9 +1,14 @@
+use std::fmt;
+
 use radicle::cob::issue::Issue;
+use radicle::cob::store::Rejection;
 use radicle::cob::thread::{Comment, CommentId};
+use radicle::cob::ObjectId;
 use radicle::crypto::ssh::keystore::MemorySigner;
 use radicle::crypto::Signer;
+use radicle::prelude::Did;
 use radicle::profile::env::RAD_PASSPHRASE;
-use radicle::profile::Profile;
+use radicle::profile::{Config, Profile};
 
 pub use radicle_term::io::*;
 pub use radicle_term::spinner;
@@ -22,8 +27,15 @@ pub fn signer(profile: &Profile) -> anyhow::Result<Box<dyn Signer>> {
     Ok(signer.boxed())
 }
 
-pub fn comment_select(issue: &Issue) -> Option<(&CommentId, &Comment)> {
-    let comments = issue.comments().collect::<Vec<_>>();
+/// Select a comment of an issue. Comments from blocked authors are not shown.
+pub fn comment_select<'a>(
+    issue: &'a Issue,
+    config: &Config,
+) -> Option<(&'a CommentId, &'a Comment)> {
+    let comments = issue
+        .comments()
+        .filter(|(_, c)| !config.is_blocked(&c.author().into()))
+        .collect::<Vec<_>>();
     let selection = Select::new(
         "Which comment do you want to react to?",
         (0..comments.len()).collect(),
@@ -36,6 +48,82 @@ pub fn comment_select(issue: &Issue) -> Option<(&CommentId, &Comment)> {
     comments.get(selection).copied()
 }
 
+/// A collaborative object that can be selected interactively.
+struct CobChoice {
+    id: ObjectId,
+    title: String,
+}
+
+impl fmt::Display for CobChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            crate::terminal::format::cob(&self.id),
+            self.title
+        )
+    }
+}
+
+/// Select a collaborative object, eg. an issue or patch, from a fuzzy-searchable list of
+/// object ids and titles. Fails with the given `missing` error when not running in an
+/// interactive terminal, or when nothing is selected.
+pub fn cob_select(
+    prompt: &str,
+    cobs: impl IntoIterator<Item = (ObjectId, String)>,
+    missing: &str,
+) -> anyhow::Result<ObjectId> {
+    if !is_terminal() {
+        anyhow::bail!("{missing}");
+    }
+    let choices = cobs
+        .into_iter()
+        .map(|(id, title)| CobChoice { id, title })
+        .collect::<Vec<_>>();
+
+    if choices.is_empty() {
+        anyhow::bail!("{missing}");
+    }
+    match select_fuzzy(prompt, choices)? {
+        Some(choice) => Ok(choice.id),
+        None => anyhow::bail!("{missing}"),
+    }
+}
+
+/// Warn about the open collaborative objects, eg. issues, with a title similar to the one of
+/// a `kind` of object that is about to be created. When running in a terminal, ask whether
+/// to create it anyway. Returns `false` if the user chose not to.
+pub fn duplicates(kind: &str, similar: impl IntoIterator<Item = (ObjectId, String)>) -> bool {
+    let similar = similar.into_iter().collect::<Vec<_>>();
+    if similar.is_empty() {
+        return true;
+    }
+    warning(&format!(
+        "Found {} open {kind}(s) with a similar title:",
+        similar.len()
+    ));
+    for choice in similar
+        .into_iter()
+        .map(|(id, title)| CobChoice { id, title })
+    {
+        indented(choice);
+    }
+    !is_terminal() || confirm(format!("Open the {kind} anyway?"))
+}
+
+/// Warn about the changes that were left out of a collaborative object, eg. because they
+/// failed verification.
+pub fn rejections(rejected: &[Rejection]) {
+    for r in rejected {
+        warning(&format!(
+            "Change {} by {} was rejected: {}",
+            crate::terminal::format::oid(*r.entry),
+            crate::terminal::format::did(&Did::from(r.author)),
+            r.reason
+        ));
+    }
+}
+
 pub mod proposal {
     use std::fmt::Write as _;
 
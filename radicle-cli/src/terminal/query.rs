@@ -0,0 +1,129 @@
+//! Saved queries for list commands.
+//!
+//! A saved query is a named list of options, eg. `mine = --assigned me --open`, that can be
+//! passed to a list command as `@mine`. Queries are looked up in the `rad.query.<name>` git
+//! configuration of the current working copy first, so that they can be shared by a project,
+//! and then in the `queries` of the profile configuration.
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::git;
+use radicle::profile::Profile;
+
+/// Prefix of saved query arguments.
+pub const PREFIX: char = '@';
+/// Options whose values are free text, eg. a title or a message, and are never expanded,
+/// even if they start with the [`PREFIX`].
+pub const TEXT_OPTIONS: &[&str] = &["-m", "--message", "--title", "--description"];
+
+/// Expand the saved queries in the given arguments. The values of [`TEXT_OPTIONS`], and
+/// arguments following `--` are left untouched.
+pub fn expand(args: Vec<OsString>) -> anyhow::Result<Vec<OsString>> {
+    if !args.iter().any(is_query) {
+        return Ok(args);
+    }
+    let repo = git::raw::Repository::open_from_env().ok();
+    let config = Profile::load().ok().map(|p| p.config);
+
+    expand_with(args, |name| {
+        let key = format!("rad.query.{name}");
+
+        if let Some(query) = repo
+            .as_ref()
+            .and_then(|r| r.config().ok())
+            .and_then(|c| c.get_string(&key).ok())
+        {
+            return Some(query);
+        }
+        config
+            .as_ref()
+            .and_then(|c| c.query(name))
+            .map(ToOwned::to_owned)
+    })
+}
+
+/// Expand the saved queries in the given arguments, using `lookup` to find queries by name.
+pub fn expand_with(
+    args: Vec<OsString>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<Vec<OsString>> {
+    let mut args = args.into_iter();
+    let mut expanded = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            expanded.push(arg);
+            expanded.extend(args);
+            break;
+        }
+        if TEXT_OPTIONS.iter().any(|o| arg == *o) {
+            expanded.push(arg);
+            expanded.extend(args.next());
+            continue;
+        }
+        if !is_query(&arg) {
+            expanded.push(arg);
+            continue;
+        }
+        let arg = arg.to_string_lossy();
+        let name = &arg[PREFIX.len_utf8()..];
+        let query = lookup(name).ok_or_else(|| {
+            anyhow!("saved query `{name}` was not found; queries are set in the `queries` of your profile configuration, or with `git config rad.query.{name} <options>`")
+        })?;
+
+        expanded.extend(query.split_whitespace().map(OsString::from));
+    }
+    Ok(expanded)
+}
+
+fn is_query(arg: &OsString) -> bool {
+    arg.to_str()
+        .map_or(false, |a| a.len() > 1 && a.starts_with(PREFIX))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_expand() {
+        let lookup = |name: &str| (name == "mine").then(|| String::from("--assigned me  --open"));
+
+        assert_eq!(
+            expand_with(args(&["list", "@mine", "-q"]), lookup).unwrap(),
+            args(&["list", "--assigned", "me", "--open", "-q"])
+        );
+        assert_eq!(
+            expand_with(args(&["list", "--", "@mine"]), lookup).unwrap(),
+            args(&["list", "--", "@mine"])
+        );
+        assert_eq!(
+            expand_with(args(&["list", "@"]), lookup).unwrap(),
+            args(&["list", "@"])
+        );
+        assert!(expand_with(args(&["list", "@theirs"]), lookup).is_err());
+    }
+
+    #[test]
+    fn test_expand_text_options() {
+        let lookup = |name: &str| (name == "mine").then(|| String::from("--assigned me"));
+
+        assert_eq!(
+            expand_with(args(&["open", "--title", "@mine", "-m", "@theirs"]), lookup).unwrap(),
+            args(&["open", "--title", "@mine", "-m", "@theirs"])
+        );
+        assert_eq!(
+            expand_with(args(&["edit", "--description", "@mine"]), lookup).unwrap(),
+            args(&["edit", "--description", "@mine"])
+        );
+        assert_eq!(
+            expand_with(args(&["list", "-m", "x", "@mine"]), lookup).unwrap(),
+            args(&["list", "-m", "x", "--assigned", "me"])
+        );
+    }
+}
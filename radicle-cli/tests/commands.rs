@@ -1,7 +1,7 @@
 use std::env;
 use std::path::Path;
 use std::str::FromStr;
-use std::{thread, time};
+use std::time;
 
 use radicle::git;
 use radicle::node::Handle as _;
@@ -77,6 +77,36 @@ fn rad_issue() {
     test("examples/rad-issue.md", &working, Some(home), []).unwrap();
 }
 
+#[test]
+fn rad_issue_edit() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup a test repository.
+    fixtures::repository(&working);
+
+    test("examples/rad-init.md", &working, Some(home), []).unwrap();
+    test("examples/rad-issue.md", &working, Some(home), []).unwrap();
+    test("examples/rad-issue-edit.md", &working, Some(home), []).unwrap();
+}
+
+#[test]
+fn rad_cob() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup a test repository.
+    fixtures::repository(&working);
+
+    test("examples/rad-init.md", &working, Some(home), []).unwrap();
+    test("examples/rad-issue.md", &working, Some(home), []).unwrap();
+    test("examples/rad-cob.md", &working, Some(home), []).unwrap();
+}
+
 #[test]
 fn rad_tag() {
     let mut environment = Environment::new();
@@ -92,6 +122,62 @@ fn rad_tag() {
     test("examples/rad-tag.md", &working, Some(home), []).unwrap();
 }
 
+#[test]
+fn rad_label() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup a test repository.
+    fixtures::repository(&working);
+
+    test("examples/rad-init.md", &working, Some(home), []).unwrap();
+    test("examples/rad-issue.md", &working, Some(home), []).unwrap();
+    test("examples/rad-tag.md", &working, Some(home), []).unwrap();
+    test("examples/rad-label.md", &working, Some(home), []).unwrap();
+}
+
+#[test]
+fn rad_block() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+
+    test(
+        "examples/rad-block.md",
+        environment.tmp(),
+        Some(&profile.home),
+        [],
+    )
+    .unwrap();
+}
+
+#[test]
+fn rad_clean() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let working = tempfile::tempdir().unwrap();
+
+    // Setup a test repository.
+    fixtures::repository(working.path());
+
+    test(
+        "examples/rad-init.md",
+        working.path(),
+        Some(&profile.home),
+        [],
+    )
+    .unwrap();
+
+    test(
+        "examples/rad-clean.md",
+        working.path(),
+        Some(&profile.home),
+        [],
+    )
+    .unwrap();
+}
+
 #[test]
 fn rad_init() {
     let mut environment = Environment::new();
@@ -225,6 +311,40 @@ fn rad_id_rebase() {
     test("examples/rad-id-rebase.md", working.path(), Some(home), []).unwrap();
 }
 
+#[test]
+fn rad_id_threshold() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let working = tempfile::tempdir().unwrap();
+    let home = &profile.home;
+
+    // Setup a test repository.
+    fixtures::repository(working.path());
+
+    test("examples/rad-init.md", working.path(), Some(home), []).unwrap();
+    test(
+        "examples/rad-id-threshold.md",
+        working.path(),
+        Some(home),
+        [],
+    )
+    .unwrap();
+}
+
+#[test]
+fn rad_id_handoff() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let working = tempfile::tempdir().unwrap();
+    let home = &profile.home;
+
+    // Setup a test repository.
+    fixtures::repository(working.path());
+
+    test("examples/rad-init.md", working.path(), Some(home), []).unwrap();
+    test("examples/rad-id-handoff.md", working.path(), Some(home), []).unwrap();
+}
+
 #[test]
 fn rad_node() {
     logger::init(log::Level::Debug);
@@ -291,6 +411,27 @@ fn rad_patch_draft() {
     .unwrap();
 }
 
+#[test]
+fn rad_patch_edit() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let working = tempfile::tempdir().unwrap();
+    let home = &profile.home;
+
+    // Setup a test repository.
+    fixtures::repository(working.path());
+
+    test("examples/rad-init.md", working.path(), Some(home), []).unwrap();
+    test(
+        "examples/rad-patch-draft.md",
+        working.path(),
+        Some(home),
+        [],
+    )
+    .unwrap();
+    test("examples/rad-patch-edit.md", working.path(), Some(home), []).unwrap();
+}
+
 #[test]
 fn rad_rm() {
     let mut environment = Environment::new();
@@ -343,6 +484,130 @@ fn rad_clone() {
     test("examples/rad-clone.md", working, Some(&bob.home), []).unwrap();
 }
 
+#[test]
+fn rad_init_template() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup the repositories of the template and of the new project.
+    fixtures::repository(working.join("template"));
+    fixtures::repository(working.join("project"));
+
+    test("examples/rad-init-template.md", &working, Some(home), []).unwrap();
+}
+
+#[test]
+fn rad_invite() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup a test repository.
+    fixtures::repository(&working);
+
+    test("examples/rad-init.md", &working, Some(home), []).unwrap();
+    test("examples/rad-invite.md", &working, Some(home), []).unwrap();
+}
+
+#[test]
+fn rad_clone_seed() {
+    logger::init(log::Level::Debug);
+
+    let mut environment = Environment::new();
+    let mut alice = environment.node("alice");
+    let bob = environment.node("bob");
+    let working = environment.tmp().join("working");
+
+    let (repo, _) = fixtures::repository(working.join("alice"));
+    let rid = alice.project_from("heartwood", "Radicle Heartwood Protocol & Stack", &repo);
+
+    let alice = alice.spawn(Config::default());
+    let bob = bob.spawn(Config::default());
+    let bob_did = format!("did:key:{}", bob.id);
+    let seed = format!("{}@{}", alice.id, alice.addr);
+
+    // Alice makes the repository private, and invites Bob to it.
+    alice
+        .rad(
+            "id",
+            &["visibility", "--private", "--no-confirm"],
+            working.join("alice"),
+        )
+        .unwrap();
+    alice
+        .rad(
+            "invite",
+            &[bob_did.as_str(), "--no-notify", "--no-confirm"],
+            working.join("alice"),
+        )
+        .unwrap();
+
+    // Bob isn't connected to Alice, and private repositories aren't announced, so the
+    // repository can only be cloned by connecting to Alice with `--seed`.
+    bob.rad(
+        "clone",
+        &[rid.to_string().as_str(), "--seed", seed.as_str()],
+        working.join("bob"),
+    )
+    .unwrap();
+
+    assert!(working
+        .join("bob")
+        .join("heartwood")
+        .join("README")
+        .exists());
+    assert!(bob.storage.repository(rid).is_ok());
+}
+
+#[test]
+fn rad_node_seeds() {
+    logger::init(log::Level::Debug);
+
+    let mut environment = Environment::new();
+    let mut alice = environment.node("alice");
+    let bob = environment.node("bob");
+    let working = environment.tmp().join("working");
+
+    let acme = alice.project("heartwood", "Radicle Heartwood Protocol & Stack");
+
+    let mut alice = alice.spawn(Config::default());
+    let mut bob = bob.spawn(Config::default());
+    alice.handle.track_repo(acme, Scope::Trusted).unwrap();
+
+    bob.connect(&alice).converge([&alice]);
+
+    test("examples/rad-node-seeds.md", working, Some(&bob.home), []).unwrap();
+}
+
+#[test]
+fn rad_clone_no_follow() {
+    logger::init(log::Level::Debug);
+
+    let mut environment = Environment::new();
+    let mut alice = environment.node("alice");
+    let bob = environment.node("bob");
+    let working = environment.tmp().join("working");
+
+    let acme = alice.project("heartwood", "Radicle Heartwood Protocol & Stack");
+
+    let mut alice = alice.spawn(Config::default());
+    let mut bob = bob.spawn(Config::default());
+    alice.handle.track_repo(acme, Scope::Trusted).unwrap();
+
+    bob.connect(&alice).converge([&alice]);
+
+    test(
+        "examples/rad-clone-no-follow.md",
+        working,
+        Some(&bob.home),
+        [],
+    )
+    .unwrap();
+}
+
 #[test]
 fn rad_self() {
     let mut environment = Environment::new();
@@ -387,11 +652,6 @@ fn rad_init_sync_and_clone() {
 
     fixtures::repository(working.join("alice"));
 
-    // Necessary for now, if we don't want the new inventry announcement to be considered stale
-    // for Bob.
-    // TODO: Find a way to advance internal clocks instead.
-    thread::sleep(time::Duration::from_millis(3));
-
     // Alice initializes a repo after her node has started, and after bob has connected to it.
     test(
         "examples/rad-init-sync.md",
@@ -488,6 +748,20 @@ fn rad_fork() {
     .unwrap();
 }
 
+#[test]
+fn rad_fork_remote() {
+    let mut environment = Environment::new();
+    let profile = environment.profile("alice");
+    let home = &profile.home;
+    let working = environment.tmp().join("working");
+
+    // Setup a test repository.
+    fixtures::repository(&working);
+
+    test("examples/rad-init.md", &working, Some(home), []).unwrap();
+    test("examples/rad-fork-remote.md", &working, Some(home), []).unwrap();
+}
+
 #[test]
 // User tries to clone; no seeds are available, but user has the repo locally.
 fn test_clone_without_seeds() {
@@ -524,7 +798,7 @@ fn test_cob_replication() {
     let mut bob = bob.spawn(Config::default());
     let events = alice.handle.events();
 
-    alice.handle.track_node(bob.id, None).unwrap();
+    alice.handle.track_node(bob.id, None, None).unwrap();
     alice.connect(&bob);
 
     bob.routes_to(&[(rid, alice.id)]);
@@ -552,10 +826,6 @@ fn test_cob_replication() {
         .unwrap();
     log::debug!(target: "test", "Issue {} created", issue.id());
 
-    // Make sure that Bob's issue refs announcement has a different timestamp than his fork's
-    // announcement, otherwise Alice will consider it stale.
-    thread::sleep(time::Duration::from_millis(3));
-
     bob.handle.announce_refs(rid).unwrap();
 
     // Wait for Alice to fetch the issue refs.
@@ -615,6 +885,35 @@ fn rad_sync() {
     .unwrap();
 }
 
+#[test]
+fn rad_sync_bundle() {
+    let mut environment = Environment::new();
+    let mut alice = environment.node("alice");
+    let bob = environment.node("bob");
+    let working = environment.tmp().join("working");
+
+    alice.project("heartwood", "Radicle Heartwood Protocol & Stack");
+
+    // Nb. Alice and Bob are never connected.
+    let alice = alice.spawn(Config::default());
+    let bob = bob.spawn(Config::default());
+
+    test(
+        "examples/rad-sync-bundle.md",
+        &working,
+        Some(&alice.home),
+        [],
+    )
+    .unwrap();
+    test(
+        "examples/rad-sync-unbundle.md",
+        &working,
+        Some(&bob.home),
+        [],
+    )
+    .unwrap();
+}
+
 #[test]
 //
 //     alice -- seed -- bob
@@ -638,9 +937,6 @@ fn test_replication_via_seed() {
     alice.connect(&seed);
     bob.connect(&seed);
 
-    // Enough time for the next inventory from Seed to not be considered stale by Bob.
-    thread::sleep(time::Duration::from_millis(3));
-
     alice.routes_to(&[]);
     seed.routes_to(&[]);
     bob.routes_to(&[]);
@@ -724,7 +1020,7 @@ fn rad_remote() {
     let mut alice = alice.spawn(Config::default());
     alice
         .handle
-        .track_node(bob.id, Some("bob".to_owned()))
+        .track_node(bob.id, Some("bob".to_owned()), None)
         .unwrap();
 
     test(
@@ -18,6 +18,9 @@ use crate::{
 };
 
 const MANIFEST_BLOB_NAME: &str = "manifest";
+/// Name of the blob holding the change timestamp. Since it is part of the change tree, the
+/// timestamp is signed along with the rest of the change.
+const TIMESTAMP_BLOB_NAME: &str = "timestamp";
 
 pub mod error {
     use std::str::Utf8Error;
@@ -61,6 +64,8 @@ pub mod error {
             #[source]
             err: serde_json::Error,
         },
+        #[error("the 'timestamp' found at '{0}' was invalid")]
+        InvalidTimestamp(Oid),
         #[error("a 'change' file was expected be found in '{0}'")]
         NoChange(Oid),
         #[error("the 'change' found at '{0}' was not a blob")]
@@ -108,7 +113,9 @@ impl change::Storage for git2::Repository {
             history_type,
         };
 
-        let revision = write_manifest(self, &manifest, &contents)?;
+        let time = change_time(self)?;
+        let timestamp = time.seconds() as Timestamp;
+        let revision = write_manifest(self, &manifest, &contents, timestamp)?;
         let tree = self.find_tree(revision)?;
         let signature = {
             let sig = signer.sign(revision.as_bytes());
@@ -116,7 +123,7 @@ impl change::Storage for git2::Repository {
             ExtendedSignature::new(*key, sig)
         };
 
-        let (id, timestamp) = write_commit(
+        let id = write_commit(
             self,
             resource,
             parents.clone(),
@@ -124,6 +131,7 @@ impl change::Storage for git2::Repository {
             message,
             signature.clone(),
             tree,
+            time,
         )?;
         Ok(Change {
             id,
@@ -139,7 +147,6 @@ impl change::Storage for git2::Repository {
 
     fn load(&self, id: Self::ObjectId) -> Result<Change, Self::LoadError> {
         let commit = Commit::read(self, id.into())?;
-        let timestamp = git2::Time::from(commit.committer().time).seconds() as u64;
         let resource = parse_resource_trailer(commit.trailers())?;
         let parents = commit
             .parents()
@@ -159,6 +166,11 @@ impl change::Storage for git2::Repository {
         let tree = self.find_tree(commit.tree())?;
         let manifest = load_manifest(self, &tree)?;
         let contents = load_contents(self, &tree)?;
+        // Changes made before timestamps were signed only have the commit time.
+        let timestamp = match load_timestamp(self, &tree)? {
+            Some(timestamp) => timestamp,
+            None => git2::Time::from(commit.committer().time).seconds() as Timestamp,
+        };
 
         Ok(Change {
             id,
@@ -207,6 +219,23 @@ fn load_manifest(
     })
 }
 
+fn load_timestamp(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+) -> Result<Option<Timestamp>, error::Load> {
+    let Some(entry) = tree.get_name(TIMESTAMP_BLOB_NAME) else {
+        return Ok(None);
+    };
+    let object = entry.to_object(repo)?;
+    let timestamp = object
+        .as_blob()
+        .and_then(|blob| std::str::from_utf8(blob.content()).ok())
+        .and_then(|s| s.parse::<Timestamp>().ok())
+        .ok_or_else(|| error::Load::InvalidTimestamp(tree.id().into()))?;
+
+    Ok(Some(timestamp))
+}
+
 fn load_contents(
     repo: &git2::Repository,
     tree: &git2::Tree,
@@ -233,6 +262,20 @@ fn load_contents(
     NonEmpty::collect(ops.into_values()).ok_or_else(|| error::Load::NoChange(tree.id().into()))
 }
 
+/// The time of a new change, which is the current time, unless it is set for testing.
+fn change_time(repo: &git2::Repository) -> Result<git2::Time, git2::Error> {
+    let time = repo.signature()?.when();
+
+    #[cfg(debug_assertions)]
+    let time = if let Ok(s) = std::env::var(crate::git::RAD_COMMIT_TIME) {
+        git2::Time::new(s.trim().parse::<i64>().unwrap(), 0)
+    } else {
+        time
+    };
+    Ok(time)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn write_commit<O>(
     repo: &git2::Repository,
     resource: O,
@@ -241,7 +284,8 @@ fn write_commit<O>(
     message: String,
     signature: ExtendedSignature,
     tree: git2::Tree,
-) -> Result<(Oid, Timestamp), error::Create>
+    time: git2::Time,
+) -> Result<Oid, error::Create>
 where
     O: AsRef<git2::Oid>,
 {
@@ -260,7 +304,6 @@ where
 
     let trailers: Vec<OwnedTrailer> = vec![trailers::ResourceCommitTrailer::from(resource).into()];
     let author = repo.signature()?;
-    let timestamp = author.when().seconds();
 
     let mut headers = commit::Headers::new();
     headers.push(
@@ -270,18 +313,10 @@ where
             .map_err(signatures::error::Signatures::from)?
             .as_str(),
     );
-    let author = commit::Author::try_from(&author)?;
-
-    #[cfg(debug_assertions)]
-    let (author, timestamp) = if let Ok(s) = std::env::var(crate::git::RAD_COMMIT_TIME) {
-        let timestamp = s.trim().parse::<i64>().unwrap();
-        let author = commit::Author {
-            time: git_commit::author::Time::new(timestamp, 0),
-            ..author
-        };
-        (author, timestamp)
-    } else {
-        (author, timestamp)
+    // The commit time is the change time, whose timestamp is also in the signed tree.
+    let author = commit::Author {
+        time: git_commit::author::Time::new(time.seconds(), time.offset_minutes()),
+        ..commit::Author::try_from(&author)?
     };
 
     let oid = Commit::new(
@@ -295,13 +330,14 @@ where
     )
     .write(repo)?;
 
-    Ok((Oid::from(oid), timestamp as u64))
+    Ok(Oid::from(oid))
 }
 
 fn write_manifest(
     repo: &git2::Repository,
     manifest: &store::Manifest,
     contents: &NonEmpty<Vec<u8>>,
+    timestamp: Timestamp,
 ) -> Result<git2::Oid, git2::Error> {
     let mut tb = repo.treebuilder(None)?;
     // SAFETY: we're serializing to an in memory buffer so the only source of
@@ -313,6 +349,12 @@ fn write_manifest(
         manifest_oid,
         git2::FileMode::Blob.into(),
     )?;
+    let timestamp_oid = repo.blob(timestamp.to_string().as_bytes())?;
+    tb.insert(
+        TIMESTAMP_BLOB_NAME,
+        timestamp_oid,
+        git2::FileMode::Blob.into(),
+    )?;
 
     for (ix, op) in contents.iter().enumerate() {
         let oid = repo.blob(op.as_ref())?;
@@ -6,12 +6,17 @@ use git_ext::Oid;
 use radicle_dag::Dag;
 
 use crate::history::entry::{EntryId, EntryWithClock};
+use crate::history::{Rejection, RejectionReason};
 use crate::{change::Change, history, pruning_fold};
 
+/// Evaluate the change graph into a history. Changes that fail verification are rejected,
+/// along with their descendants, and recorded in the history.
+///
 /// # Panics
 ///
 /// If the change corresponding to the root OID is not in `items`
 pub fn evaluate(root: Oid, graph: &Dag<Oid, Change>, rng: fastrand::Rng) -> history::History {
+    let mut rejected = Vec::new();
     let entries = pruning_fold::pruning_fold(
         HashMap::<EntryId, EntryWithClock>::new(),
         graph.sorted(rng).into_iter().map(|oid| {
@@ -24,43 +29,66 @@ pub fn evaluate(root: Oid, graph: &Dag<Oid, Change>, rng: fastrand::Rng) -> hist
                 child_commits,
             }
         }),
-        |mut entries, c| match evaluate_change(c.change, &c.child_commits) {
-            Err(RejectionReason::InvalidSignatures) => {
-                log::warn!(
-                    "rejecting change '{}' because its signatures were invalid",
-                    c.change.id(),
-                );
-                ControlFlow::Break(entries)
-            }
-            Ok(entry) => {
-                // Get parent commits and calculate this node's clock based on theirs.
-                let clock = graph[&c.oid]
-                    .dependencies
-                    .iter()
-                    .map(|e| entries[&EntryId::from(*e)].clock())
-                    .max()
-                    .unwrap_or_default() // When there are no operations, the clock is zero.
-                    + 1;
-                log::trace!("change '{}' accepted", c.change.id());
-
-                entries.insert(*entry.id(), EntryWithClock { entry, clock });
-
-                ControlFlow::Continue(entries)
-            }
+        |mut entries, c| {
+            // Nb. The parents of a change are always evaluated before the change itself,
+            // and the change is pruned if any of them was rejected.
+            let parents = graph[&c.oid]
+                .dependencies
+                .iter()
+                .map(|e| &entries[&EntryId::from(*e)]);
+
+            let entry = match evaluate_change(c.change, &c.child_commits, parents.clone()) {
+                Ok(entry) => entry,
+                Err(reason) => {
+                    log::warn!("rejecting change '{}': {reason}", c.change.id());
+
+                    rejected.push(Rejection {
+                        id: EntryId::from(*c.change.id()),
+                        actor: c.change.signature.key,
+                        reason,
+                    });
+                    return ControlFlow::Break(entries);
+                }
+            };
+            // Get parent commits and calculate this node's clock based on theirs.
+            let clock = parents
+                .map(|e| e.clock())
+                .max()
+                .unwrap_or_default() // When there are no operations, the clock is zero.
+                + 1;
+            log::trace!("change '{}' accepted", c.change.id());
+
+            entries.insert(*entry.id(), EntryWithClock { entry, clock });
+
+            ControlFlow::Continue(entries)
         },
     );
     // SAFETY: The caller must guarantee that `root` is in `items`
-    history::History::new(root, entries).unwrap()
+    history::History::new(root, entries)
+        .unwrap()
+        .with_rejected(rejected)
 }
 
-fn evaluate_change(
+fn evaluate_change<'a>(
     change: &Change,
     child_commits: &[Oid],
+    parents: impl Iterator<Item = &'a EntryWithClock>,
 ) -> Result<history::Entry, RejectionReason> {
     // Check the change signatures are valid
     if !change.valid_signatures() {
-        return Err(RejectionReason::InvalidSignatures);
+        return Err(RejectionReason::InvalidSignature);
     };
+    // Check that time doesn't go backwards: a change can't be older than the changes
+    // it builds upon. Since timestamps are signed, only the change author can get this wrong.
+    if let Some(parent) = parents.max_by_key(|p| p.timestamp()) {
+        if change.timestamp < parent.timestamp() {
+            return Err(RejectionReason::TimestampRegression {
+                parent: *parent.id(),
+                parent_timestamp: parent.timestamp(),
+                timestamp: change.timestamp,
+            });
+        }
+    }
 
     Ok(history::Entry::new(
         *change.id(),
@@ -90,7 +118,75 @@ impl<'a> pruning_fold::GraphNode for ChangeWithChildren<'a> {
     }
 }
 
-#[derive(Debug)]
-enum RejectionReason {
-    InvalidSignatures,
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use crypto::test::signer::MockSigner;
+    use nonempty::nonempty;
+
+    use super::*;
+    use crate::change::{Storage as _, Template};
+    use crate::test;
+
+    #[test]
+    fn test_timestamp_regression() {
+        let storage = test::Storage::new();
+        let repo = storage.as_raw();
+        let signer = MockSigner::default();
+        let sig = git2::Signature::now("terry", "terry@discworld").unwrap();
+        let tree = repo.treebuilder(None).unwrap().write().unwrap();
+        let tree = repo.find_tree(tree).unwrap();
+        let resource = Oid::from(
+            repo.commit(None, &sig, &sig, "resource", &tree, &[])
+                .unwrap(),
+        );
+        let template = |tips, contents: &[u8]| Template {
+            typename: "xyz.rad.issue".parse().unwrap(),
+            history_type: "test".to_owned(),
+            tips,
+            message: "change".to_owned(),
+            contents: nonempty![contents.to_vec()],
+        };
+        let root = repo
+            .store(resource, vec![], &signer, template(vec![], b"root"))
+            .unwrap();
+        let mut child = repo
+            .store(resource, vec![], &signer, template(vec![root.id], b"child"))
+            .unwrap();
+        let graph = |child: &Change| {
+            let mut graph = Dag::new();
+            graph.node(root.id, root.clone());
+            graph.node(child.id, child.clone());
+            graph.dependency(child.id, root.id);
+            graph
+        };
+
+        // Timestamps are stored in the change tree, which is what gets signed.
+        let tree = repo.find_tree(root.revision.into()).unwrap();
+        assert!(tree.get_name("timestamp").is_some());
+        assert_eq!(repo.load(root.id).unwrap().timestamp, root.timestamp);
+
+        child.timestamp = root.timestamp;
+        let history = evaluate(root.id, &graph(&child), fastrand::Rng::new());
+        assert_eq!(history.tips(), BTreeSet::from([child.id]));
+        assert!(history.rejected().is_empty());
+
+        // Rewind the child's clock.
+        child.timestamp = root.timestamp - 1;
+        let history = evaluate(root.id, &graph(&child), fastrand::Rng::new());
+        assert_eq!(history.tips(), BTreeSet::from([root.id]));
+        assert_eq!(
+            history.rejected(),
+            &[Rejection {
+                id: EntryId::from(child.id),
+                actor: *signer.public_key(),
+                reason: RejectionReason::TimestampRegression {
+                    parent: EntryId::from(root.id),
+                    parent_timestamp: root.timestamp,
+                    timestamp: root.timestamp - 1,
+                }
+            }]
+        );
+    }
 }
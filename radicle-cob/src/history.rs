@@ -18,6 +18,33 @@ pub use entry::{Clock, Contents, Entry, EntryId, EntryWithClock, Timestamp};
 #[derive(Clone, Debug)]
 pub struct History {
     graph: Dag<EntryId, EntryWithClock>,
+    /// Changes that were rejected when the history was evaluated. The descendants of
+    /// rejected changes are also left out of the history.
+    rejected: Vec<Rejection>,
+}
+
+/// A change that was left out of a history, because it failed verification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rejection {
+    /// The rejected change.
+    pub id: EntryId,
+    /// The author the change claims.
+    pub actor: PublicKey,
+    /// Why the change was rejected.
+    pub reason: RejectionReason,
+}
+
+/// The reason a change was rejected.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RejectionReason {
+    #[error("the change signature is invalid")]
+    InvalidSignature,
+    #[error("the change timestamp {timestamp} is earlier than the timestamp {parent_timestamp} of its parent {parent}")]
+    TimestampRegression {
+        parent: EntryId,
+        parent_timestamp: Timestamp,
+        timestamp: Timestamp,
+    },
 }
 
 impl PartialEq for History {
@@ -92,6 +119,16 @@ impl History {
             .unwrap_or_default()
     }
 
+    /// Get the changes that were rejected when the history was evaluated.
+    pub fn rejected(&self) -> &[Rejection] {
+        &self.rejected
+    }
+
+    pub(crate) fn with_rejected(mut self, rejected: Vec<Rejection>) -> Self {
+        self.rejected = rejected;
+        self
+    }
+
     /// A topological (parents before children) traversal of the dependency
     /// graph of this history. This is analagous to
     /// [`std::iter::Iterator::fold`] in that it folds every change into an
@@ -152,6 +189,7 @@ impl History {
 
     pub fn merge(&mut self, other: Self) {
         self.graph.merge(other.graph);
+        self.rejected.extend(other.rejected);
     }
 }
 
@@ -162,11 +200,17 @@ fn create_dag<'a>(root: &'a EntryId, entries: &'a HashMap<EntryId, EntryWithCloc
 
     while let Some(entry) = to_process.pop() {
         for child_id in entry.children() {
-            let child = entries[child_id].clone();
+            // Nb. Children that were rejected are not in the entries.
+            let Some(child) = entries.get(child_id).cloned() else {
+                continue;
+            };
             graph.node(*child_id, child.clone());
             graph.dependency(*child_id, entry.id);
             to_process.push(child.clone());
         }
     }
-    History { graph }
+    History {
+        graph,
+        rejected: Vec::new(),
+    }
 }
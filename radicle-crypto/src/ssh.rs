@@ -15,6 +15,9 @@ use crate::PublicKey;
 
 pub use keystore::{Keystore, Passphrase};
 
+/// Namespace of the SSH signatures git creates when signing commits.
+pub const GIT_NAMESPACE: &str = "git";
+
 #[derive(Debug, Error)]
 pub enum ExtendedSignatureError {
     #[error(transparent)]
@@ -23,6 +26,8 @@ pub enum ExtendedSignatureError {
     Crypto(#[from] crypto::Error),
     #[error("unsupported signature algorithm")]
     UnsupportedAlgorithm,
+    #[error("unexpected signature namespace '{0}'")]
+    Namespace(String),
 }
 
 /// Signature with public key, used for SSH signing.
@@ -74,6 +79,33 @@ impl ExtendedSignature {
     }
 }
 
+/// Verify an SSH signature over a message, in the `SSHSIG` format created by
+/// `ssh-keygen -Y sign`, eg. when git signs commits. Returns the key of the signer.
+pub fn verify(
+    pem: impl AsRef<[u8]>,
+    namespace: &str,
+    msg: &[u8],
+) -> Result<PublicKey, ExtendedSignatureError> {
+    let sig = ssh_key::SshSig::from_pem(pem)?;
+    if sig.namespace() != namespace {
+        return Err(ExtendedSignatureError::Namespace(
+            sig.namespace().to_owned(),
+        ));
+    }
+    let key = crypto::PublicKey::from(
+        sig.public_key()
+            .ed25519()
+            .ok_or(ExtendedSignatureError::UnsupportedAlgorithm)?
+            .0,
+    );
+    let signature = crypto::Signature::try_from(sig.signature().as_bytes())?;
+    let data = ssh_key::SshSig::signed_data(namespace, sig.hash_alg(), msg)?;
+
+    key.verify(data, &signature)?;
+
+    Ok(key)
+}
+
 pub mod fmt {
     use crate::PublicKey;
 
@@ -249,6 +281,8 @@ mod test {
     use radicle_ssh::agent::client::{AgentClient, ClientStream, Error};
     use radicle_ssh::encoding::*;
 
+    use super::GIT_NAMESPACE;
+
     #[derive(Clone, Default)]
     struct DummyStream {
         incoming: Arc<Mutex<Vec<u8>>>,
@@ -308,6 +342,31 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_verify() {
+        let keypair = crypto::KeyPair::from_seed(crypto::Seed::new([1; 32]));
+        let msg = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+        let data =
+            ssh_key::SshSig::signed_data(GIT_NAMESPACE, ssh_key::HashAlg::Sha512, msg).unwrap();
+        let sig = keypair.sk.sign(&data, None);
+        let pem = ssh_key::SshSig::new(
+            ssh_key::public::KeyData::from(ssh_key::public::Ed25519PublicKey(*keypair.pk)),
+            GIT_NAMESPACE,
+            ssh_key::HashAlg::Sha512,
+            ssh_key::Signature::new(ssh_key::Algorithm::Ed25519, *sig).unwrap(),
+        )
+        .unwrap()
+        .to_pem(ssh_key::LineEnding::default())
+        .unwrap();
+
+        assert_eq!(
+            super::verify(&pem, GIT_NAMESPACE, msg).unwrap(),
+            PublicKey::from(*keypair.pk)
+        );
+        assert!(super::verify(&pem, GIT_NAMESPACE, b"tree 0000000").is_err());
+        assert!(super::verify(&pem, "file", msg).is_err());
+    }
+
     #[test]
     fn test_agent_encoding_sign() {
         use std::str::FromStr;
@@ -0,0 +1,64 @@
+/*
+ * C bindings to the Radicle profile, storage and COB APIs.
+ *
+ * Every function returns a `rad_error_t` code, and writes its result through
+ * an out-pointer. When a function fails, a description of the error can be
+ * obtained with `rad_last_error`.
+ *
+ * Strings returned through out-pointers are owned by the caller, and must be
+ * released with `rad_string_free`. Structured data is returned as JSON.
+ */
+#ifndef RADICLE_H
+#define RADICLE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Error codes. These values are stable. */
+typedef enum {
+    RAD_OK = 0,
+    RAD_ERROR_NULL = 1,
+    RAD_ERROR_UTF8 = 2,
+    RAD_ERROR_INVALID = 3,
+    RAD_ERROR_NOT_FOUND = 4,
+    RAD_ERROR_PROFILE = 5,
+    RAD_ERROR_STORAGE = 6,
+    RAD_ERROR_IDENTITY = 7,
+    RAD_ERROR_COB = 8,
+    RAD_ERROR_INTERNAL = 9,
+} rad_error_t;
+
+/* Opaque profile handle. */
+typedef struct RadProfile rad_profile_t;
+
+/* Errors. */
+const char *rad_last_error(void);
+
+/* Strings. */
+void rad_string_free(char *s);
+
+/* Profiles. */
+rad_error_t rad_profile_load(rad_profile_t **out);
+rad_error_t rad_profile_open(const char *home, rad_profile_t **out);
+rad_error_t rad_profile_init(const char *home, const char *passphrase, rad_profile_t **out);
+void rad_profile_free(rad_profile_t *profile);
+rad_error_t rad_profile_did(const rad_profile_t *profile, char **out);
+rad_error_t rad_profile_home(const rad_profile_t *profile, char **out);
+
+/* Storage. */
+rad_error_t rad_storage_repositories(const rad_profile_t *profile, char **out);
+rad_error_t rad_repository_project(const rad_profile_t *profile, const char *rid, char **out);
+rad_error_t rad_repository_doc(const rad_profile_t *profile, const char *rid, char **out);
+
+/* Collaborative objects. */
+rad_error_t rad_issues_list(const rad_profile_t *profile, const char *rid, char **out);
+rad_error_t rad_issue_get(const rad_profile_t *profile, const char *rid, const char *id, char **out);
+rad_error_t rad_patches_list(const rad_profile_t *profile, const char *rid, char **out);
+rad_error_t rad_patch_get(const rad_profile_t *profile, const char *rid, const char *id, char **out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RADICLE_H */
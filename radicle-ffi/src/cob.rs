@@ -0,0 +1,123 @@
+//! Collaborative object reads.
+use std::ffi::c_char;
+use std::str::FromStr;
+
+use radicle::cob::issue::Issues;
+use radicle::cob::json;
+use radicle::cob::patch::Patches;
+use radicle::cob::ObjectId;
+
+use crate::error::{call, Error, RadError};
+use crate::profile::RadProfile;
+use crate::storage;
+
+/// Parse a collaborative object id.
+fn object_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::from_str(id).map_err(|e| Error::Invalid("object id", e.to_string()))
+}
+
+/// List the issues of a repository, as a JSON array of issues. Issues that fail to load
+/// are skipped.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` a valid C string, and `out` a valid pointer.
+/// On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_issues_list(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = storage::repository(profile, crate::string(rid, "rid")?)?;
+        let issues = Issues::open(&repo)?
+            .all()?
+            .filter_map(|r| r.ok())
+            .map(|(id, issue, _)| json::issue(&id, &issue))
+            .collect::<Vec<_>>();
+
+        crate::write_json(out, &issues)
+    })
+}
+
+/// Get an issue of a repository, as a JSON object.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` and `id` valid C strings, and `out` a valid
+/// pointer. On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_issue_get(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    id: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = storage::repository(profile, crate::string(rid, "rid")?)?;
+        let id = object_id(crate::string(id, "id")?)?;
+        let issue = Issues::open(&repo)?
+            .get(&id)?
+            .ok_or_else(|| Error::NotFound(format!("issue {id}")))?;
+
+        crate::write_json(out, &json::issue(&id, &issue))
+    })
+}
+
+/// List the patches of a repository, as a JSON array of patches. Patches that fail to
+/// load are skipped.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` a valid C string, and `out` a valid pointer.
+/// On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_patches_list(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = storage::repository(profile, crate::string(rid, "rid")?)?;
+        let patches = Patches::open(&repo)?
+            .all()?
+            .filter_map(|r| r.ok())
+            .map(|(id, patch, _)| json::patch(&id, &patch))
+            .collect::<Vec<_>>();
+
+        crate::write_json(out, &patches)
+    })
+}
+
+/// Get a patch of a repository, as a JSON object.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` and `id` valid C strings, and `out` a valid
+/// pointer. On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_patch_get(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    id: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = storage::repository(profile, crate::string(rid, "rid")?)?;
+        let id = object_id(crate::string(id, "id")?)?;
+        let patch = Patches::open(&repo)?
+            .get(&id)?
+            .ok_or_else(|| Error::NotFound(format!("patch {id}")))?;
+
+        crate::write_json(out, &json::patch(&id, &patch))
+    })
+}
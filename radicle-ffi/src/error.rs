@@ -0,0 +1,134 @@
+//! Error codes and reporting.
+use std::cell::RefCell;
+use std::ffi::{c_char, CString, NulError};
+use std::{io, panic, ptr};
+
+use thiserror::Error;
+
+use radicle::cob::store;
+use radicle::identity::doc::DocError;
+use radicle::identity::IdentityError;
+use radicle::{profile, storage};
+
+thread_local! {
+    /// Description of the last error that occurred on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Error code returned by the library functions.
+///
+/// These values are part of the library's ABI: existing codes are never renumbered.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadError {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    Null = 1,
+    /// A string argument was not valid UTF-8.
+    Utf8 = 2,
+    /// An argument could not be parsed, eg. a malformed repository id.
+    Invalid = 3,
+    /// The requested repository or object was not found.
+    NotFound = 4,
+    /// The profile could not be loaded or initialized.
+    Profile = 5,
+    /// Storage could not be read.
+    Storage = 6,
+    /// A repository identity could not be loaded or verified.
+    Identity = 7,
+    /// A collaborative object could not be loaded.
+    Cob = 8,
+    /// An unexpected internal error.
+    Internal = 9,
+}
+
+/// An error that occurred during a call.
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    #[error("argument `{0}` is null")]
+    Null(&'static str),
+    #[error("argument `{0}` is not valid UTF-8")]
+    Utf8(&'static str),
+    #[error("invalid {0}: {1}")]
+    Invalid(&'static str, String),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("profile: {0}")]
+    Profile(#[from] profile::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("identity: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("identity document: {0}")]
+    Doc(#[from] DocError),
+    #[error("cob: {0}")]
+    Cob(#[from] store::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("string contains a nul byte: {0}")]
+    Nul(#[from] NulError),
+    #[error("panicked: {0}")]
+    Panic(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Profile(profile::Error::from(err))
+    }
+}
+
+impl Error {
+    /// The error code to return to the caller.
+    pub(crate) fn code(&self) -> RadError {
+        match self {
+            Self::Null(_) => RadError::Null,
+            Self::Utf8(_) => RadError::Utf8,
+            Self::Invalid(..) => RadError::Invalid,
+            Self::NotFound(_) => RadError::NotFound,
+            Self::Profile(_) => RadError::Profile,
+            Self::Storage(_) => RadError::Storage,
+            Self::Identity(_) | Self::Doc(_) => RadError::Identity,
+            Self::Cob(store::Error::NotFound(..)) => RadError::NotFound,
+            Self::Cob(_) => RadError::Cob,
+            Self::Json(_) | Self::Nul(_) | Self::Panic(_) => RadError::Internal,
+        }
+    }
+}
+
+/// Run the body of a library function, catching panics so that they don't unwind
+/// across the FFI boundary, and recording the error, if any, for [`rad_last_error`].
+pub(crate) fn call(f: impl FnOnce() -> Result<(), Error>) -> RadError {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or_else(|e| {
+        let msg = if let Some(msg) = e.downcast_ref::<&str>() {
+            msg.to_string()
+        } else if let Some(msg) = e.downcast_ref::<String>() {
+            msg.clone()
+        } else {
+            String::from("unknown cause")
+        };
+        Err(Error::Panic(msg))
+    });
+
+    match result {
+        Ok(()) => RadError::Ok,
+        Err(err) => {
+            let code = err.code();
+            let msg = CString::new(err.to_string().replace('\0', "")).unwrap_or_default();
+
+            LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+
+            code
+        }
+    }
+}
+
+/// Get a description of the last error that occurred on the calling thread, or null if
+/// no error occurred yet.
+///
+/// The string is owned by the library, and is only valid until the next call that fails
+/// on the same thread. It must not be freed.
+#[no_mangle]
+pub extern "C" fn rad_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |msg| msg.as_ptr()))
+}
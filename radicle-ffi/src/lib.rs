@@ -0,0 +1,171 @@
+//! C bindings to the Radicle profile, storage and COB APIs, for use by non-Rust clients,
+//! eg. mobile and desktop applications.
+//!
+//! Conventions:
+//!
+//! * Every function returns a [`RadError`] code, and writes its result through an
+//!   out-pointer. When a function fails, a description of the error can be obtained with
+//!   [`error::rad_last_error`].
+//! * Profiles are passed around as opaque [`RadProfile`] handles, which must be released
+//!   with [`profile::rad_profile_free`].
+//! * Strings returned by the library are owned by the caller, and must be released with
+//!   [`rad_string_free`]. Structured data, such as identity documents, issues and patches,
+//!   is returned as JSON.
+//!
+//! The C declarations are in `include/radicle.h`.
+#![cfg_attr(not(test), warn(clippy::unwrap_used))]
+pub mod cob;
+pub mod error;
+pub mod profile;
+pub mod storage;
+
+use std::ffi::{c_char, CStr, CString};
+
+use serde::Serialize;
+
+use radicle::Profile;
+
+pub use error::RadError;
+pub use profile::RadProfile;
+
+use error::Error;
+
+/// Release a string returned by the library. Does nothing if the string is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string obtained from this library, that wasn't already released.
+#[no_mangle]
+pub unsafe extern "C" fn rad_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Read a string argument.
+unsafe fn string<'a>(ptr: *const c_char, name: &'static str) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Err(Error::Null(name));
+    }
+    CStr::from_ptr(ptr).to_str().map_err(|_| Error::Utf8(name))
+}
+
+/// Access an out-pointer argument.
+unsafe fn out<'a, T>(ptr: *mut T, name: &'static str) -> Result<&'a mut T, Error> {
+    ptr.as_mut().ok_or(Error::Null(name))
+}
+
+/// Access a profile handle argument.
+unsafe fn profile<'a>(ptr: *const RadProfile) -> Result<&'a Profile, Error> {
+    ptr.as_ref()
+        .map(|p| &p.profile)
+        .ok_or(Error::Null("profile"))
+}
+
+/// Write a string result, to be released with [`rad_string_free`].
+unsafe fn write_str(ptr: *mut *mut c_char, s: String) -> Result<(), Error> {
+    let out = out(ptr, "out")?;
+    *out = CString::new(s)?.into_raw();
+
+    Ok(())
+}
+
+/// Write a JSON result, to be released with [`rad_string_free`].
+unsafe fn write_json(ptr: *mut *mut c_char, value: &impl Serialize) -> Result<(), Error> {
+    write_str(ptr, serde_json::to_string(value)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use radicle::api;
+    use radicle::crypto::ssh::keystore::MemorySigner;
+    use radicle::prelude::*;
+    use radicle::test::fixtures;
+
+    use super::cob::*;
+    use super::error::*;
+    use super::profile::*;
+    use super::storage::*;
+    use super::*;
+
+    /// Take ownership of a string returned by the library.
+    unsafe fn take(s: *mut c_char) -> String {
+        let string = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        rad_string_free(s);
+        string
+    }
+
+    fn cstr(s: impl ToString) -> CString {
+        CString::new(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_profile_storage_cob() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = cstr(tmp.path().join("home").display());
+        let passphrase = cstr("radicle");
+        let mut handle = ptr::null_mut();
+        let mut out = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                rad_profile_init(home.as_ptr(), passphrase.as_ptr(), &mut handle),
+                RadError::Ok
+            );
+            rad_profile_free(handle);
+            assert_eq!(rad_profile_open(home.as_ptr(), &mut handle), RadError::Ok);
+        }
+        let profile = unsafe { &(*handle).profile };
+        let signer = MemorySigner::load(&profile.keystore, "radicle".to_owned().into()).unwrap();
+        let (rid, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &profile.storage, &signer).unwrap();
+        let repo = profile.storage.repository(rid).unwrap();
+        let issue = api::issue::open(&repo, "Title", "Description", &[], &[], &signer).unwrap();
+        let rid = cstr(rid);
+
+        unsafe {
+            assert_eq!(rad_profile_did(handle, &mut out), RadError::Ok);
+            assert_eq!(take(out), profile.did().to_string());
+
+            assert_eq!(rad_storage_repositories(handle, &mut out), RadError::Ok);
+            assert_eq!(take(out), format!("[\"{}\"]", rid.to_str().unwrap()));
+
+            assert_eq!(
+                rad_repository_project(handle, rid.as_ptr(), &mut out),
+                RadError::Ok
+            );
+            assert!(take(out).contains("\"defaultBranch\":\"master\""));
+
+            assert_eq!(
+                rad_issues_list(handle, rid.as_ptr(), &mut out),
+                RadError::Ok
+            );
+            assert!(take(out).contains("\"title\":\"Title\""));
+
+            let id = cstr(issue);
+            assert_eq!(
+                rad_issue_get(handle, rid.as_ptr(), id.as_ptr(), &mut out),
+                RadError::Ok
+            );
+            assert!(take(out).contains(&format!("\"id\":\"{issue}\"")));
+
+            assert_eq!(
+                rad_patch_get(handle, rid.as_ptr(), id.as_ptr(), &mut out),
+                RadError::NotFound
+            );
+            assert_eq!(
+                rad_issues_list(handle, cstr("rad:invalid").as_ptr(), &mut out),
+                RadError::Invalid
+            );
+            assert!(CStr::from_ptr(rad_last_error())
+                .to_str()
+                .unwrap()
+                .starts_with("invalid repository id"));
+
+            assert_eq!(rad_profile_did(ptr::null(), &mut out), RadError::Null);
+            rad_profile_free(handle);
+        }
+    }
+}
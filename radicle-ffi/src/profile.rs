@@ -0,0 +1,134 @@
+//! Profile management.
+use std::ffi::c_char;
+
+use radicle::profile::{Home, Profile};
+
+use crate::error::{call, RadError};
+
+/// Opaque handle to a Radicle profile.
+///
+/// Obtained from [`rad_profile_load`], [`rad_profile_open`] or [`rad_profile_init`], and
+/// released with [`rad_profile_free`]. A handle may be shared between threads, as long as
+/// it isn't freed while in use.
+pub struct RadProfile {
+    pub(crate) profile: Profile,
+}
+
+impl RadProfile {
+    fn into_raw(profile: Profile) -> *mut Self {
+        Box::into_raw(Box::new(Self { profile }))
+    }
+}
+
+/// Load the profile at the Radicle home, ie. `RAD_HOME` or `~/.radicle`.
+///
+/// # Safety
+///
+/// `out` must be a valid pointer. On success, it points to a new profile handle.
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_load(out: *mut *mut RadProfile) -> RadError {
+    call(|| {
+        let out = crate::out(out, "out")?;
+        let profile = Profile::load()?;
+
+        *out = RadProfile::into_raw(profile);
+
+        Ok(())
+    })
+}
+
+/// Open an existing profile at the given home directory.
+///
+/// # Safety
+///
+/// `home` must be a valid C string, and `out` a valid pointer. On success, `out` points
+/// to a new profile handle.
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_open(
+    home: *const c_char,
+    out: *mut *mut RadProfile,
+) -> RadError {
+    call(|| {
+        let home = crate::string(home, "home")?;
+        let out = crate::out(out, "out")?;
+        let profile = Profile::open(Home::new(home)?)?;
+
+        *out = RadProfile::into_raw(profile);
+
+        Ok(())
+    })
+}
+
+/// Create a new profile at the given home directory, with a new key pair encrypted with
+/// the given passphrase.
+///
+/// # Safety
+///
+/// `home` and `passphrase` must be valid C strings, and `out` a valid pointer. On success,
+/// `out` points to a new profile handle.
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_init(
+    home: *const c_char,
+    passphrase: *const c_char,
+    out: *mut *mut RadProfile,
+) -> RadError {
+    call(|| {
+        let home = crate::string(home, "home")?;
+        let passphrase = crate::string(passphrase, "passphrase")?;
+        let out = crate::out(out, "out")?;
+        let profile = Profile::init(Home::new(home)?, passphrase.to_owned())?;
+
+        *out = RadProfile::into_raw(profile);
+
+        Ok(())
+    })
+}
+
+/// Release a profile handle. Does nothing if the handle is null.
+///
+/// # Safety
+///
+/// `profile` must be null or a handle obtained from this library, that wasn't already
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_free(profile: *mut RadProfile) {
+    if !profile.is_null() {
+        drop(Box::from_raw(profile));
+    }
+}
+
+/// Get the DID of the profile, eg. `did:key:z6Mk...`.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, and `out` a valid pointer. On success, `out` points
+/// to a string that must be released with [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_did(
+    profile: *const RadProfile,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+
+        crate::write_str(out, profile.did().to_string())
+    })
+}
+
+/// Get the home directory of the profile.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, and `out` a valid pointer. On success, `out` points
+/// to a string that must be released with [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_profile_home(
+    profile: *const RadProfile,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+
+        crate::write_str(out, profile.home().to_string_lossy().into_owned())
+    })
+}
@@ -0,0 +1,84 @@
+//! Storage reads.
+use std::ffi::c_char;
+use std::str::FromStr;
+
+use radicle::identity::Id;
+use radicle::prelude::*;
+use radicle::storage::git::Repository;
+
+use crate::error::{call, Error, RadError};
+use crate::profile::RadProfile;
+
+/// Open a repository in the profile's storage.
+pub(crate) fn repository(profile: &Profile, rid: &str) -> Result<Repository, Error> {
+    let rid = Id::from_str(rid).map_err(|e| Error::Invalid("repository id", e.to_string()))?;
+
+    if !profile.storage.contains(&rid)? {
+        return Err(Error::NotFound(format!("repository {rid}")));
+    }
+    profile.storage.repository(rid).map_err(Error::from)
+}
+
+/// List the repositories in storage, as a JSON array of repository ids.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, and `out` a valid pointer. On success, `out` points
+/// to a string that must be released with [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_storage_repositories(
+    profile: *const RadProfile,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repos = profile.storage.repositories()?;
+
+        crate::write_json(out, &repos)
+    })
+}
+
+/// Get the project payload of a repository, as a JSON object.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` a valid C string, and `out` a valid pointer.
+/// On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_repository_project(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = repository(profile, crate::string(rid, "rid")?)?;
+        let project = repo.project()?;
+
+        crate::write_json(out, &project)
+    })
+}
+
+/// Get the verified identity document of a repository, as a JSON object.
+///
+/// # Safety
+///
+/// `profile` must be a valid handle, `rid` a valid C string, and `out` a valid pointer.
+/// On success, `out` points to a string that must be released with
+/// [`crate::rad_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rad_repository_doc(
+    profile: *const RadProfile,
+    rid: *const c_char,
+    out: *mut *mut c_char,
+) -> RadError {
+    call(|| {
+        let profile = crate::profile(profile)?;
+        let repo = repository(profile, crate::string(rid, "rid")?)?;
+        let (_, doc) = repo.identity_doc()?;
+        let doc = doc.verified()?;
+
+        crate::write_json(out, &doc)
+    })
+}
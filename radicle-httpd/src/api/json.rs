@@ -3,14 +3,11 @@
 use std::path::Path;
 use std::str;
 
-use serde::Serialize;
 use serde_json::{json, Value};
 
-use radicle::cob::issue::{Issue, IssueId};
+use radicle::cob;
 use radicle::cob::patch::{Patch, PatchId};
-use radicle::cob::thread;
-use radicle::cob::thread::{CommentId, Thread};
-use radicle::cob::{ActorId, Author, Reaction, Timestamp};
+use radicle::cob::ActorId;
 use radicle::git::RefString;
 use radicle::storage::{git, refs, ReadRepository};
 use radicle_surf::blob::Blob;
@@ -91,48 +88,17 @@ pub(crate) fn tree(tree: &Tree, path: &str, stats: &Stats) -> Value {
     })
 }
 
-/// Returns JSON for an `issue`.
-pub(crate) fn issue(id: IssueId, issue: Issue) -> Value {
-    json!({
-        "id": id.to_string(),
-        "author": issue.author(),
-        "title": issue.title(),
-        "state": issue.state(),
-        "assignees": issue.assigned().collect::<Vec<_>>(),
-        "discussion": issue
-          .comments()
-          .map(|(id, comment)| Comment::new(id, comment, issue.thread()))
-          .collect::<Vec<_>>(),
-        "tags": issue.tags().collect::<Vec<_>>(),
-    })
-}
-
-/// Returns JSON for a `patch`.
+/// Returns JSON for a `patch`, along with the refs pointing to the head of each revision.
 pub(crate) fn patch(id: PatchId, patch: Patch, repo: &git::Repository) -> Value {
-    json!({
-        "id": id.to_string(),
-        "author": patch.author(),
-        "title": patch.title(),
-        "description": patch.description(),
-        "state": patch.state(),
-        "target": patch.target(),
-        "tags": patch.tags().collect::<Vec<_>>(),
-        "revisions": patch.revisions().map(|(id, rev)| {
-            json!({
-                "id": id,
-                "description": rev.description(),
-                "base": rev.base(),
-                "oid": rev.head(),
-                "refs": get_refs(repo, patch.author().id(), &rev.head()).unwrap_or(vec![]),
-                "merges": rev.merges().collect::<Vec<_>>(),
-                "discussions": rev.discussion().comments()
-                  .map(|(id, comment)| Comment::new(id, comment, rev.discussion()))
-                  .collect::<Vec<_>>(),
-                "timestamp": rev.timestamp(),
-                "reviews": rev.reviews().collect::<Vec<_>>(),
-            })
-        }).collect::<Vec<_>>(),
-    })
+    let mut value = cob::json::patch(&id, &patch);
+
+    if let Some(revisions) = value["revisions"].as_array_mut() {
+        for (json, (_, rev)) in revisions.iter_mut().zip(patch.revisions()) {
+            json["refs"] =
+                json!(get_refs(repo, patch.author().id(), &rev.head()).unwrap_or_default());
+        }
+    }
+    value
 }
 
 /// Returns the name part of a path string.
@@ -163,27 +129,3 @@ fn get_refs(
 
     Ok(refs)
 }
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Comment<'a> {
-    id: CommentId,
-    author: Author,
-    body: &'a str,
-    reactions: Vec<(&'a ActorId, &'a Reaction)>,
-    timestamp: Timestamp,
-    reply_to: Option<CommentId>,
-}
-
-impl<'a> Comment<'a> {
-    fn new(id: &'a CommentId, comment: &'a thread::Comment, thread: &'a Thread) -> Self {
-        Self {
-            id: *id,
-            author: Author::new(comment.author()),
-            body: comment.body(),
-            reactions: thread.reactions(id).collect::<Vec<_>>(),
-            timestamp: comment.timestamp(),
-            reply_to: comment.reply_to(),
-        }
-    }
-}
@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tower_http::set_header::SetResponseHeaderLayer;
 
-use radicle::cob::{issue, patch, thread, ActorId, Tag};
+use radicle::cob::{self, issue, patch, thread, ActorId, Tag};
 use radicle::identity::Id;
 use radicle::node::NodeId;
 use radicle::storage::git::paths;
@@ -442,7 +442,7 @@ async fn issues_handler(
     issues.sort_by(|(_, a, _), (_, b, _)| b.timestamp().cmp(&a.timestamp()));
     let issues = issues
         .into_iter()
-        .map(|(id, issue, _)| api::json::issue(id, issue))
+        .map(|(id, issue, _)| cob::json::issue(&id, &issue))
         .skip(page * per_page)
         .take(per_page)
         .collect::<Vec<_>>();
@@ -522,6 +522,12 @@ async fn issue_update_handler(
         issue::Action::Tag { add, remove } => {
             issue.tag(add, remove, &signer)?;
         }
+        issue::Action::Move { column } => {
+            issue.move_to(column, &signer)?;
+        }
+        issue::Action::Due { due } => {
+            issue.set_due(due, &signer)?;
+        }
         issue::Action::Edit { title } => {
             issue.edit(title, &signer)?;
         }
@@ -560,7 +566,7 @@ async fn issue_handler(
         .get(&issue_id.into())?
         .ok_or(Error::NotFound)?;
 
-    Ok::<_, Error>(Json(api::json::issue(issue_id.into(), issue)))
+    Ok::<_, Error>(Json(cob::json::issue(&issue_id.into(), &issue)))
 }
 
 #[derive(Deserialize, Serialize)]
@@ -649,12 +655,19 @@ async fn patch_update_handler(
         patch::Action::Tag { add, remove } => {
             patch.tag(add, remove, &signer)?;
         }
+        patch::Action::RequestReview { add, remove } => {
+            patch.request_review(add, remove, &signer)?;
+        }
+        patch::Action::Scope { paths } => {
+            patch.set_scope(paths, &signer)?;
+        }
         patch::Action::Revision {
             description,
             base,
             oid,
+            stacked_on,
         } => {
-            patch.update(description, base, oid, &signer)?;
+            patch.update_stacked(description, base, oid, stacked_on, &signer)?;
         }
         patch::Action::Redact { .. } => {
             todo!()
@@ -60,6 +60,7 @@ pub fn profile(home: &Path, seed: [u8; 32]) -> radicle::Profile {
         storage,
         keystore,
         public_key: keypair.pk.into(),
+        config: Default::default(),
     }
 }
 
@@ -0,0 +1,69 @@
+//! Benchmarks for decoding gossip messages received from the network.
+//!
+//! Run with `cargo bench -p radicle-node`.
+use std::net;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use radicle::crypto::test::signer::MockSigner;
+use radicle::crypto::Signer;
+use radicle::node::{Address, Features};
+use radicle::storage::{ReadRepository, ReadStorage};
+use radicle::test::{arbitrary, fixtures};
+use radicle_node::prelude::Id;
+use radicle_node::service::message::{
+    AnnouncementMessage, InventoryAnnouncement, Message, NodeAnnouncement, RefsAnnouncement,
+};
+use radicle_node::service::INVENTORY_LIMIT;
+use radicle_node::wire;
+
+fn decode(c: &mut Criterion) {
+    let tmp = tempfile::tempdir().unwrap();
+    let signer = MockSigner::default();
+    let storage = fixtures::storage(tmp.path(), &signer).unwrap();
+    let rid = storage.inventory().unwrap()[0];
+    let repo = storage.repository(rid).unwrap();
+
+    let inventory = Message::inventory(
+        InventoryAnnouncement {
+            inventory: arbitrary::vec::<Id>(INVENTORY_LIMIT).try_into().unwrap(),
+            timestamp: 0,
+        },
+        &signer,
+    );
+    let addresses = vec![Address::from(net::SocketAddr::from(([127, 0, 0, 1], 8776)))];
+    let node = Message::node(
+        NodeAnnouncement {
+            features: Features::SEED,
+            timestamp: 0,
+            alias: [0; 32],
+            addresses: addresses.try_into().unwrap(),
+            nonce: 0,
+        }
+        .solve(),
+        &signer,
+    );
+    let remote = repo.remote(signer.public_key()).unwrap();
+    let refs: Message = AnnouncementMessage::from(RefsAnnouncement {
+        rid,
+        refs: vec![remote.refs.unverified()].try_into().unwrap(),
+        timestamp: 0,
+    })
+    .signed(&signer)
+    .into();
+
+    for (name, msg) in [
+        ("decode inventory announcement", inventory),
+        ("decode node announcement", node),
+        ("decode refs announcement", refs),
+    ] {
+        let bytes = wire::serialize(&msg);
+
+        c.bench_function(name, |b| {
+            b.iter(|| wire::deserialize::<Message>(&bytes).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -1,5 +1,6 @@
 mod store;
 mod types;
 
+pub use radicle::node::reputation;
 pub use store::*;
 pub use types::*;
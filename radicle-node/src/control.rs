@@ -13,8 +13,11 @@ use serde_json as json;
 
 use crate::identity::Id;
 use crate::node::NodeId;
-use crate::node::{Command, CommandName, CommandResult};
+use crate::node::{
+    Address, Command, CommandName, CommandResult, ConnectOptions, Signal, DEFAULT_TIMEOUT,
+};
 use crate::runtime;
+use crate::service;
 
 /// Maximum timeout for waiting for node events.
 const MAX_TIMEOUT: time::Duration = time::Duration::MAX;
@@ -28,7 +31,7 @@ pub enum Error {
 }
 
 /// Listen for commands on the control socket, and process them.
-pub fn listen<H: Handle<Error = runtime::HandleError> + 'static>(
+pub fn listen<H: Handle<Error = runtime::HandleError, Sessions = service::Sessions> + 'static>(
     listener: UnixListener,
     handle: H,
 ) -> Result<(), Error> {
@@ -77,7 +80,7 @@ enum CommandError {
     Shutdown,
 }
 
-fn command<H: Handle<Error = runtime::HandleError> + 'static>(
+fn command<H: Handle<Error = runtime::HandleError, Sessions = service::Sessions> + 'static>(
     stream: &UnixStream,
     mut handle: H,
 ) -> Result<(), CommandError> {
@@ -93,16 +96,16 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
 
     match cmd.name {
         CommandName::Connect => {
-            let (nid, addr) = parse::args(cmd)?;
-            if let Err(e) = handle.connect(nid, addr) {
+            let (nid, addr, opts) = parse::connect(cmd)?;
+            if let Err(e) = handle.connect(nid, addr, opts) {
                 return Err(CommandError::Runtime(e));
             } else {
                 CommandResult::Okay { updated: true }.to_writer(writer)?;
             }
         }
         CommandName::Fetch => {
-            let (rid, nid): (Id, NodeId) = parse::args(cmd)?;
-            fetch(rid, nid, writer, &mut handle)?;
+            let (rid, nid, timeout) = parse::fetch(cmd)?;
+            fetch(rid, nid, timeout, writer, &mut handle)?;
         }
         CommandName::Seeds => {
             let rid: Id = parse::arg(cmd)?;
@@ -110,6 +113,12 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
 
             json::to_writer(writer, &seeds)?;
         }
+        CommandName::Addresses => {
+            let nid: NodeId = parse::arg(cmd)?;
+            let addresses = handle.addresses(nid)?;
+
+            json::to_writer(writer, &addresses)?;
+        }
         CommandName::TrackRepo => {
             let (rid, scope) = parse::args(cmd)?;
 
@@ -135,16 +144,23 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
             }
         }
         CommandName::TrackNode => {
-            let (node, alias) = match cmd.args.as_slice() {
-                [node] => (node.as_str(), None),
-                [node, alias] => (node.as_str(), Some(alias.to_owned())),
+            let (node, alias, expiry) = match cmd.args.as_slice() {
+                [node] => (node.as_str(), None, None),
+                [node, alias] => (node.as_str(), Some(alias.to_owned()), None),
+                [node, alias, expiry] => {
+                    let alias = (!alias.is_empty()).then(|| alias.to_owned());
+                    let expiry = expiry.parse().map_err(|e| {
+                        CommandError::InvalidCommandArg(expiry.to_owned(), Box::new(e))
+                    })?;
+                    (node.as_str(), alias, Some(expiry))
+                }
                 _ => return Err(CommandError::InvalidCommandArgs(cmd.args)),
             };
             let nid = node
                 .parse()
                 .map_err(|e| CommandError::InvalidCommandArg(node.to_owned(), Box::new(e)))?;
 
-            match handle.track_node(nid, alias) {
+            match handle.track_node(nid, alias, expiry) {
                 Ok(updated) => {
                     CommandResult::Okay { updated }.to_writer(writer)?;
                 }
@@ -187,6 +203,85 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
                 return Err(CommandError::Runtime(e));
             }
         },
+        CommandName::Search => {
+            let query: String = parse::arg(cmd)?;
+            let peers = handle.search(query)?;
+
+            json::to_writer(writer, &peers)?;
+        }
+        CommandName::Signal => {
+            let (nid, signal) = parse::signal(cmd)?;
+
+            match handle.signal(nid, signal) {
+                Ok(updated) => {
+                    CommandResult::Okay { updated }.to_writer(writer)?;
+                }
+                Err(e) => {
+                    return Err(CommandError::Runtime(e));
+                }
+            }
+        }
+        CommandName::TraceGossip => {
+            let enable: bool = parse::arg(cmd)?;
+
+            match handle.trace_gossip(enable) {
+                Ok(updated) => {
+                    CommandResult::Okay { updated }.to_writer(writer)?;
+                }
+                Err(e) => {
+                    return Err(CommandError::Runtime(e));
+                }
+            }
+        }
+        CommandName::ReloadConfig => match handle.reload_config() {
+            Ok(updated) => {
+                CommandResult::Okay { updated }.to_writer(writer)?;
+            }
+            Err(e) => {
+                return Err(CommandError::Runtime(e));
+            }
+        },
+        CommandName::Ban => {
+            let nid: NodeId = parse::arg(cmd)?;
+
+            match handle.ban(nid) {
+                Ok(updated) => {
+                    CommandResult::Okay { updated }.to_writer(writer)?;
+                }
+                Err(e) => {
+                    return Err(CommandError::Runtime(e));
+                }
+            }
+        }
+        CommandName::Disconnect => {
+            let nid: NodeId = parse::arg(cmd)?;
+
+            match handle.disconnect(nid) {
+                Ok(updated) => {
+                    CommandResult::Okay { updated }.to_writer(writer)?;
+                }
+                Err(e) => {
+                    return Err(CommandError::Runtime(e));
+                }
+            }
+        }
+        CommandName::Unban => {
+            let nid: NodeId = parse::arg(cmd)?;
+
+            match handle.unban(nid) {
+                Ok(updated) => {
+                    CommandResult::Okay { updated }.to_writer(writer)?;
+                }
+                Err(e) => {
+                    return Err(CommandError::Runtime(e));
+                }
+            }
+        }
+        CommandName::GossipTraces => {
+            let traces = handle.gossip_traces()?;
+
+            json::to_writer(writer, &traces)?;
+        }
         CommandName::Subscribe => {
             let mut stream = stream.try_clone()?;
 
@@ -208,6 +303,15 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
         CommandName::Status => {
             CommandResult::ok().to_writer(writer).ok();
         }
+        CommandName::Sessions => {
+            let sessions = handle.sessions()?;
+            let connected = sessions
+                .connected()
+                .map(|(nid, _)| *nid)
+                .collect::<Vec<_>>();
+
+            json::to_writer(writer, &connected)?;
+        }
         CommandName::Shutdown => {
             return Err(CommandError::Shutdown);
         }
@@ -218,10 +322,11 @@ fn command<H: Handle<Error = runtime::HandleError> + 'static>(
 fn fetch<W: Write, H: Handle<Error = runtime::HandleError>>(
     id: Id,
     node: NodeId,
+    timeout: time::Duration,
     mut writer: W,
     handle: &mut H,
 ) -> Result<(), CommandError> {
-    match handle.fetch(id, node) {
+    match handle.fetch(id, node, timeout) {
         Ok(result) => {
             json::to_writer(&mut writer, &result)?;
         }
@@ -269,6 +374,58 @@ mod parse {
 
         Ok((arg1, arg2))
     }
+
+    /// Parse the arguments of a `connect` command: a node id, an address, and optionally
+    /// whether the connection is persistent.
+    pub(super) fn connect(cmd: Command) -> Result<(NodeId, Address, ConnectOptions), CommandError> {
+        let mut opts = ConnectOptions::default();
+        let cmd = match cmd.args.as_slice() {
+            [nid, addr, persistent] => {
+                opts.persistent = persistent.parse().map_err(|e| {
+                    CommandError::InvalidCommandArg(persistent.clone(), Box::new(e))
+                })?;
+                Command::new(cmd.name, [nid, addr])
+            }
+            _ => cmd,
+        };
+        let (nid, addr) = args(cmd)?;
+
+        Ok((nid, addr, opts))
+    }
+
+    pub(super) fn fetch(cmd: Command) -> Result<(Id, NodeId, time::Duration), CommandError> {
+        let mut timeout = DEFAULT_TIMEOUT;
+        let cmd = match cmd.args.as_slice() {
+            [rid, nid, secs] => {
+                timeout = secs
+                    .parse()
+                    .map(time::Duration::from_secs)
+                    .map_err(|e| CommandError::InvalidCommandArg(secs.clone(), Box::new(e)))?;
+                Command::new(cmd.name, [rid, nid])
+            }
+            _ => cmd,
+        };
+        let (rid, nid) = args(cmd)?;
+
+        Ok((rid, nid, timeout))
+    }
+
+    /// Parse the arguments of a `signal` command: a node id, and the signal, as JSON.
+    pub(super) fn signal(cmd: Command) -> Result<(NodeId, Signal), CommandError> {
+        let [nid, signal]: [String; 2] = cmd
+            .args
+            .clone()
+            .try_into()
+            .map_err(|_| CommandError::InvalidCommandArgs(cmd.args))?;
+
+        let nid = nid
+            .parse()
+            .map_err(|e| CommandError::InvalidCommandArg(nid, Box::new(e)))?;
+        let signal = json::from_str(&signal)
+            .map_err(|e| CommandError::InvalidCommandArg(signal, Box::new(e)))?;
+
+        Ok((nid, signal))
+    }
 }
 
 #[cfg(test)]
@@ -346,10 +503,10 @@ mod tests {
         assert!(!handle.untrack_repo(proj).unwrap());
 
         assert!(handle
-            .track_node(peer, Some(String::from("alice")))
+            .track_node(peer, Some(String::from("alice")), None)
             .unwrap());
         assert!(!handle
-            .track_node(peer, Some(String::from("alice")))
+            .track_node(peer, Some(String::from("alice")), None)
             .unwrap());
         assert!(handle.untrack_node(peer).unwrap());
         assert!(!handle.untrack_node(peer).unwrap());
@@ -3,6 +3,8 @@ pub mod bounded;
 pub mod control;
 pub mod deserializer;
 pub mod logger;
+pub mod portmap;
+pub mod prometheus;
 pub mod runtime;
 pub mod service;
 pub mod signals;
@@ -10,6 +12,7 @@ pub mod signals;
 pub mod test;
 #[cfg(test)]
 pub mod tests;
+pub mod webhooks;
 pub mod wire;
 pub mod worker;
 
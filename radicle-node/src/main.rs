@@ -3,13 +3,14 @@ use std::{fs, net, process};
 use anyhow::{anyhow, Context as _};
 use crossbeam_channel as chan;
 use cyphernet::addr::PeerAddr;
-use localtime::LocalDuration;
 
+use radicle::node;
 use radicle::prelude::Signer;
 use radicle::profile;
 use radicle_node::crypto::ssh::keystore::{Keystore, MemorySigner};
 use radicle_node::prelude::{Address, NodeId};
 use radicle_node::service::tracking::{Policy, Scope};
+use radicle_node::worker::ContentPolicy;
 use radicle_node::Runtime;
 use radicle_node::{logger, service, signals};
 use radicle_term as term;
@@ -26,21 +27,36 @@ Options
     --git-daemon         <address>      Address to bind git-daemon to (default 0.0.0.0:9418)
     --tracking-policy    (track|block)  Default tracking policy
     --tracking-scope     (trusted|all)  Default scope for tracking policies
+    --prune-expired                     Remove a node's namespaces when its tracking policy expires
+    --allow-repo         <pattern>      Only fetch repositories matching the pattern, eg. 'rad:z3gq*'
+    --deny-repo          <pattern>      Refuse repositories matching the pattern
+    --block-node         <nid>          Refuse the namespaces of the given node
+    --max-object-size    <bytes>        Refuse namespaces containing objects larger than this size
+    --allow-rewrites                    Adopt canonical branch rewrites not acknowledged by the delegates
     --force                             Force start even if an existing control socket is found
+    --leaf                              Don't relay announcements from other nodes
+    --relay-fanout       <count>        Maximum number of peers to relay each announcement to
+    --observer                          Only take part in gossip; don't fetch or serve repositories
+    --port-mapping                      Map the listen port on the gateway with NAT-PMP or UPnP
     --help                              Print help
     --listen             <address>      Address to listen on
+    --metrics            <address>      Serve Prometheus metrics over HTTP at this address
 "#;
 
 #[derive(Debug)]
 struct Options {
     connect: Vec<(NodeId, Address)>,
-    external_addresses: Vec<Address>,
     daemon: Option<net::SocketAddr>,
-    limits: service::config::Limits,
     listen: Vec<net::SocketAddr>,
+    metrics: Option<net::SocketAddr>,
     force: bool,
-    tracking_policy: Policy,
-    tracking_scope: Scope,
+    relay: bool,
+    relay_fanout: Option<usize>,
+    observer: bool,
+    prune_expired: bool,
+    content_policy: ContentPolicy,
+    /// Node settings that take precedence over the profile's.
+    node: profile::NodeConfig,
 }
 
 impl Options {
@@ -49,13 +65,16 @@ impl Options {
 
         let mut parser = lexopt::Parser::from_env();
         let mut connect = Vec::new();
-        let mut external_addresses = Vec::new();
-        let mut limits = service::config::Limits::default();
+        let mut node = profile::NodeConfig::default();
         let mut listen = Vec::new();
         let mut daemon = None;
-        let mut tracking_policy = Policy::default();
-        let mut tracking_scope = Scope::default();
+        let mut metrics = None;
+        let mut prune_expired = false;
         let mut force = false;
+        let mut relay = true;
+        let mut relay_fanout = None;
+        let mut observer = false;
+        let mut content_policy = ContentPolicy::default();
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -65,43 +84,86 @@ impl Options {
                 }
                 Long("external-address") => {
                     let addr = parser.value()?.parse()?;
-                    external_addresses.push(addr);
+                    node.external_addresses.push(addr);
                 }
                 Long("force") => {
                     force = true;
                 }
+                Long("leaf") => {
+                    relay = false;
+                }
+                Long("relay-fanout") => {
+                    relay_fanout = Some(parser.value()?.parse()?);
+                }
+                Long("observer") => {
+                    observer = true;
+                }
                 Long("git-daemon") => {
                     let addr = parser.value()?.parse()?;
                     daemon = Some(addr);
                 }
                 Long("tracking-policy") => {
-                    let policy = parser
+                    let policy: Policy = parser
                         .value()?
                         .parse()
                         .map_err(|s| anyhow!("unknown tracking policy {:?}", s))?;
-                    tracking_policy = policy;
+                    node.tracking_policy = Some(policy);
                 }
                 Long("tracking-scope") => {
-                    let scope = parser
+                    let scope: Scope = parser
                         .value()?
                         .parse()
                         .map_err(|s| anyhow!("unknown tracking scope {:?}", s))?;
-                    tracking_scope = scope;
+                    node.tracking_scope = Some(scope);
+                }
+                Long("prune-expired") => {
+                    prune_expired = true;
+                }
+                Long("allow-repo") => {
+                    let pattern = parser.value()?.parse()?;
+                    content_policy.allow.push(pattern);
+                }
+                Long("deny-repo") => {
+                    let pattern = parser.value()?.parse()?;
+                    content_policy.deny.push(pattern);
+                }
+                Long("block-node") => {
+                    let nid = parser.value()?.parse()?;
+                    content_policy.blocked.insert(nid);
+                }
+                Long("max-object-size") => {
+                    let size = parser.value()?.parse()?;
+                    content_policy.max_object_size = Some(size);
+                }
+                Long("allow-rewrites") => {
+                    content_policy.allow_rewrites = true;
                 }
                 Long("limit-routing-max-age") => {
-                    let secs: u64 = parser.value()?.parse()?;
-                    limits.routing_max_age = LocalDuration::from_secs(secs);
+                    node.limits.routing_max_age = Some(parser.value()?.parse()?);
                 }
                 Long("limit-routing-max-size") => {
-                    limits.routing_max_size = parser.value()?.parse()?;
+                    node.limits.routing_max_size = Some(parser.value()?.parse()?);
                 }
                 Long("limit-fetch-concurrency") => {
-                    limits.fetch_concurrency = parser.value()?.parse()?;
+                    node.limits.fetch_concurrency = Some(parser.value()?.parse()?);
+                }
+                Long("limit-max-fetches") => {
+                    node.limits.max_fetches = Some(parser.value()?.parse()?);
+                }
+                Long("limit-max-time-delta") => {
+                    node.limits.max_time_delta = Some(parser.value()?.parse()?);
+                }
+                Long("port-mapping") => {
+                    node.port_mapping = Some(true);
                 }
                 Long("listen") => {
                     let addr = parser.value()?.parse()?;
                     listen.push(addr);
                 }
+                Long("metrics") => {
+                    let addr = parser.value()?.parse()?;
+                    metrics = Some(addr);
+                }
                 Long("help") => {
                     println!("{HELP_MSG}");
                     process::exit(0);
@@ -110,7 +172,7 @@ impl Options {
             }
         }
 
-        if external_addresses.len() > service::ADDRESS_LIMIT {
+        if node.external_addresses.len() > service::ADDRESS_LIMIT {
             anyhow::bail!(
                 "external address limit ({}) exceeded",
                 service::ADDRESS_LIMIT,
@@ -120,12 +182,15 @@ impl Options {
         Ok(Self {
             connect,
             daemon,
-            external_addresses,
             force,
-            limits,
+            relay,
+            relay_fanout,
+            observer,
             listen,
-            tracking_policy,
-            tracking_scope,
+            metrics,
+            prune_expired,
+            content_policy,
+            node,
         })
     }
 }
@@ -147,12 +212,16 @@ fn execute() -> anyhow::Result<()> {
 
     log::info!(target: "node", "Node ID is {}", signer.public_key());
 
+    // Settings that can also be set in the profile are given as overrides, which are applied
+    // on top of the profile's settings when the node starts, and when it reloads them.
     let config = service::Config {
-        connect: options.connect.into_iter().collect(),
-        external_addresses: options.external_addresses,
-        limits: options.limits,
-        policy: options.tracking_policy,
-        scope: options.tracking_scope,
+        connect: options.connect,
+        relay: options.relay,
+        relay_fanout: options.relay_fanout,
+        observer: options.observer,
+        prune_expired: options.prune_expired,
+        content_policy: options.content_policy,
+        overrides: options.node,
         ..service::Config::default()
     };
     let proxy = net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), 9050);
@@ -167,7 +236,24 @@ fn execute() -> anyhow::Result<()> {
         log::debug!(target: "node", "Removing existing control socket..");
         fs::remove_file(home.socket()).ok();
     }
-    Runtime::init(home, config, options.listen, proxy, daemon, signals, signer)?.run()?;
+    let pid = home.node().join(node::PID_FILE);
+    let runtime = Runtime::init(
+        home,
+        config,
+        options.listen,
+        proxy,
+        daemon,
+        options.metrics,
+        signals,
+        signer,
+    )?;
+
+    // Record our process id, so that the node can be found by other tools, eg. `rad node`.
+    fs::write(&pid, process::id().to_string())?;
+    let result = runtime.run();
+    fs::remove_file(&pid).ok();
+
+    result?;
 
     Ok(())
 }
@@ -0,0 +1,629 @@
+//! Port mapping.
+//!
+//! Most nodes are behind a NAT, and can't accept inbound connections unless the gateway
+//! forwards their listen port to them. When port mapping is enabled, the node asks the
+//! gateway to do so, with NAT-PMP, or UPnP IGD if NAT-PMP isn't supported. The external
+//! address obtained is advertised in our node announcements, and the mapping is renewed
+//! before it expires. Addresses that aren't publicly routable, eg. when the gateway is itself
+//! behind a carrier-grade NAT, aren't advertised. The mapping is deleted when the node shuts
+//! down.
+//!
+//! Like the webhooks client, the UPnP client is deliberately minimal: it only speaks plain
+//! HTTP, one request per connection, and picks what it needs from the gateway's responses
+//! without parsing them as XML.
+use std::io::{Read as _, Write as _};
+use std::{fmt, fs, io, net, time};
+
+use crossbeam_channel as chan;
+use thiserror::Error;
+
+use crate::node::Address;
+use crate::runtime::Handle;
+
+/// Lifetime of the mappings asked for.
+pub const LIFETIME: time::Duration = time::Duration::from_secs(60 * 60);
+/// How long to wait before trying again, when the port couldn't be mapped.
+pub const RETRY_INTERVAL: time::Duration = time::Duration::from_secs(60 * 10);
+/// How long to wait for the gateway to answer.
+pub const TIMEOUT: time::Duration = time::Duration::from_secs(3);
+
+/// Port gateways listen on for NAT-PMP requests.
+const NAT_PMP_PORT: u16 = 5351;
+/// Address UPnP gateways are discovered on.
+const SSDP_ADDR: ([u8; 4], u16) = ([239, 255, 255, 250], 1900);
+/// UPnP services that can map ports, in order of preference.
+const UPNP_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// An error occuring while mapping a port.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An I/O error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// No default gateway was found.
+    #[error("no default gateway found")]
+    NoGateway,
+    /// No UPnP gateway answered our discovery request.
+    #[error("no UPnP gateway found")]
+    NoUpnpGateway,
+    /// The gateway refused a NAT-PMP request.
+    #[error("NAT-PMP request failed with result code {0}")]
+    NatPmp(u16),
+    /// The gateway refused a UPnP request.
+    #[error("UPnP request failed with status {0}")]
+    Upnp(u16),
+    /// The gateway answered with something we don't understand.
+    #[error("invalid response from gateway: {0}")]
+    InvalidResponse(&'static str),
+    /// The external address isn't publicly routable.
+    #[error("external address {0} is not publicly routable")]
+    NotGlobal(net::Ipv4Addr),
+}
+
+/// Protocol a port was mapped with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    NatPmp,
+    Upnp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NatPmp => write!(f, "NAT-PMP"),
+            Self::Upnp => write!(f, "UPnP"),
+        }
+    }
+}
+
+/// A port mapping on the gateway.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// Protocol the port was mapped with.
+    pub protocol: Protocol,
+    /// External address the port is reachable at.
+    pub external: net::SocketAddrV4,
+    /// How long the mapping lasts.
+    pub lifetime: time::Duration,
+}
+
+/// Map the given listen port on the gateway and advertise the external address, renewing
+/// the mapping halfway through its lifetime, until the node shuts down, or the `shutdown`
+/// channel is closed. The mapping is then deleted.
+pub fn run(port: u16, mut handle: Handle, shutdown: chan::Receiver<()>) {
+    let mut mapped = None;
+
+    loop {
+        let wait = match map(port) {
+            Ok(mapping) => {
+                log::info!(
+                    target: "portmap",
+                    "Mapped port {port} to {} with {}", mapping.external, mapping.protocol
+                );
+                mapped = Some(mapping.protocol);
+
+                let addr = Address::from(net::SocketAddr::from(mapping.external));
+                if let Err(e) = handle.map_address(addr) {
+                    log::debug!(target: "portmap", "Stopping port mapping: {e}");
+                    break;
+                }
+                // Some gateways grant mappings that don't expire.
+                if mapping.lifetime.is_zero() {
+                    LIFETIME / 2
+                } else {
+                    mapping.lifetime / 2
+                }
+            }
+            Err(e) => {
+                log::warn!(target: "portmap", "Failed to map port {port}: {e}");
+                RETRY_INTERVAL
+            }
+        };
+        if let Err(chan::RecvTimeoutError::Disconnected) = shutdown.recv_timeout(wait) {
+            break;
+        }
+    }
+
+    if let Some(protocol) = mapped {
+        match unmap(port, protocol) {
+            Ok(()) => log::info!(target: "portmap", "Deleted mapping of port {port}"),
+            Err(e) => {
+                log::warn!(target: "portmap", "Failed to delete mapping of port {port}: {e}")
+            }
+        }
+    }
+}
+
+/// Map the given port on the gateway, with NAT-PMP, or UPnP if that fails. Fails if the
+/// external address obtained isn't publicly routable.
+pub fn map(port: u16) -> Result<Mapping, Error> {
+    let nat_pmp = gateway().and_then(|gateway| nat_pmp(gateway, port, LIFETIME));
+    let mapping = match nat_pmp {
+        Ok(mapping) => mapping,
+        Err(e) => {
+            log::debug!(target: "portmap", "Failed to map port {port} with NAT-PMP: {e}");
+            upnp(port, LIFETIME)?
+        }
+    };
+    let ip = *mapping.external.ip();
+
+    if !is_global(ip) {
+        // Peers can't reach us at this address, so the mapping is of no use.
+        unmap(port, mapping.protocol).ok();
+
+        return Err(Error::NotGlobal(ip));
+    }
+    Ok(mapping)
+}
+
+/// Delete the mapping of the given port, made with the given protocol.
+pub fn unmap(port: u16, protocol: Protocol) -> Result<(), Error> {
+    match protocol {
+        Protocol::NatPmp => nat_pmp(gateway()?, port, time::Duration::ZERO).map(|_| ()),
+        Protocol::Upnp => {
+            let args = format!(
+                "<NewRemoteHost></NewRemoteHost>\
+                 <NewExternalPort>{port}</NewExternalPort>\
+                 <NewProtocol>TCP</NewProtocol>"
+            );
+            UpnpGateway::discover()?
+                .call("DeletePortMapping", &args)
+                .map(|_| ())
+        }
+    }
+}
+
+/// Map a TCP port with NAT-PMP, as specified in RFC 6886. A zero lifetime deletes the
+/// mapping of the port.
+pub fn nat_pmp(
+    gateway: net::Ipv4Addr,
+    port: u16,
+    lifetime: time::Duration,
+) -> Result<Mapping, Error> {
+    nat_pmp_with((gateway, NAT_PMP_PORT).into(), port, lifetime)
+}
+
+/// Map a TCP port with NAT-PMP, using the gateway at the given address.
+fn nat_pmp_with(
+    gateway: net::SocketAddr,
+    port: u16,
+    lifetime: time::Duration,
+) -> Result<Mapping, Error> {
+    let socket = net::UdpSocket::bind((net::Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    socket.connect(gateway)?;
+
+    let mut buf = [0; 16];
+
+    // Ask for the external address.
+    socket.send(&[0, 0])?;
+    if socket.recv(&mut buf)? < 12 || buf[1] != 128 {
+        return Err(Error::InvalidResponse("expected an external address"));
+    }
+    match u16::from_be_bytes([buf[2], buf[3]]) {
+        0 => {}
+        code => return Err(Error::NatPmp(code)),
+    }
+    let ip = net::Ipv4Addr::new(buf[8], buf[9], buf[10], buf[11]);
+
+    // Ask for the mapping, suggesting the same external port. When deleting a mapping, the
+    // external port must be zero.
+    let external = if lifetime.is_zero() { 0 } else { port };
+    let mut request = [0; 12];
+    request[1] = 2;
+    request[4..6].copy_from_slice(&port.to_be_bytes());
+    request[6..8].copy_from_slice(&external.to_be_bytes());
+    request[8..12].copy_from_slice(&(lifetime.as_secs() as u32).to_be_bytes());
+
+    socket.send(&request)?;
+    if socket.recv(&mut buf)? < 16 || buf[1] != 130 {
+        return Err(Error::InvalidResponse("expected a port mapping"));
+    }
+    match u16::from_be_bytes([buf[2], buf[3]]) {
+        0 => {}
+        code => return Err(Error::NatPmp(code)),
+    }
+    let external = u16::from_be_bytes([buf[10], buf[11]]);
+    let lifetime = u32::from_be_bytes([buf[12], buf[13], buf[14], buf[15]]);
+
+    Ok(Mapping {
+        protocol: Protocol::NatPmp,
+        external: net::SocketAddrV4::new(ip, external),
+        lifetime: time::Duration::from_secs(lifetime as u64),
+    })
+}
+
+/// Map a TCP port with UPnP IGD.
+pub fn upnp(port: u16, lifetime: time::Duration) -> Result<Mapping, Error> {
+    let gateway = UpnpGateway::discover()?;
+    let local = match local_ip(gateway.addr)? {
+        net::IpAddr::V4(ip) => ip,
+        net::IpAddr::V6(_) => return Err(Error::InvalidResponse("gateway is not on IPv4")),
+    };
+
+    let args = format!(
+        "<NewRemoteHost></NewRemoteHost>\
+         <NewExternalPort>{port}</NewExternalPort>\
+         <NewProtocol>TCP</NewProtocol>\
+         <NewInternalPort>{port}</NewInternalPort>\
+         <NewInternalClient>{local}</NewInternalClient>\
+         <NewEnabled>1</NewEnabled>\
+         <NewPortMappingDescription>radicle-node</NewPortMappingDescription>\
+         <NewLeaseDuration>{}</NewLeaseDuration>",
+        lifetime.as_secs()
+    );
+    gateway.call("AddPortMapping", &args)?;
+
+    let response = gateway.call("GetExternalIPAddress", "")?;
+    let ip = element(&response, "NewExternalIPAddress")
+        .and_then(|ip| ip.parse::<net::Ipv4Addr>().ok())
+        .ok_or(Error::InvalidResponse("expected an external address"))?;
+
+    Ok(Mapping {
+        protocol: Protocol::Upnp,
+        external: net::SocketAddrV4::new(ip, port),
+        lifetime,
+    })
+}
+
+/// The port mapping service of a UPnP gateway.
+struct UpnpGateway {
+    /// Address of the gateway.
+    addr: net::SocketAddr,
+    /// Host of the gateway, as given in its location.
+    host: String,
+    /// Service type.
+    service: &'static str,
+    /// Path of the service's control URL.
+    control: String,
+}
+
+impl UpnpGateway {
+    /// Discover a gateway and find its port mapping service.
+    fn discover() -> Result<Self, Error> {
+        let location = discover()?;
+        let (host, path) = location
+            .strip_prefix("http://")
+            .and_then(|url| url.find('/').map(|ix| url.split_at(ix)))
+            .ok_or(Error::InvalidResponse("unsupported gateway location"))?;
+        let addr = socket_addr(host)?;
+        let description = http(addr, host, "GET", path, &[], "")?;
+        let (service, control) =
+            control_url(&description).ok_or(Error::InvalidResponse("no port mapping service"))?;
+
+        Ok(Self {
+            addr,
+            host: host.to_owned(),
+            service,
+            control: control.to_owned(),
+        })
+    }
+
+    /// Call an action of the port mapping service. Returns the response body.
+    fn call(&self, action: &str, args: &str) -> Result<String, Error> {
+        soap(
+            self.addr,
+            &self.host,
+            &self.control,
+            self.service,
+            action,
+            args,
+        )
+    }
+}
+
+/// Check whether an address is publicly routable. Gateways that are themselves behind a NAT
+/// report external addresses that peers can't reach.
+fn is_global(ip: net::Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    // Shared address space of carrier-grade NATs, ie. 100.64.0.0/10. See RFC 6598.
+    let shared = a == 100 && b & 0b1100_0000 == 64;
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || shared)
+}
+
+/// Find the default gateway in the routing table. Only supported on Linux.
+fn gateway() -> Result<net::Ipv4Addr, Error> {
+    let routes = fs::read_to_string("/proc/net/route")?;
+
+    default_route(&routes).ok_or(Error::NoGateway)
+}
+
+/// Find the gateway of the default route, in a routing table as listed in `/proc/net/route`.
+fn default_route(routes: &str) -> Option<net::Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+        let (destination, gateway) = (fields.next()?, fields.next()?);
+
+        if destination != "00000000" {
+            return None;
+        }
+        // Nb. addresses are listed in network byte order, as a native-endian number.
+        let gateway = u32::from_str_radix(gateway, 16).ok()?;
+        Some(net::Ipv4Addr::from(gateway.to_ne_bytes()))
+    })
+}
+
+/// Discover a UPnP gateway with SSDP. Returns the location of its description.
+fn discover() -> Result<String, Error> {
+    let socket = net::UdpSocket::bind((net::Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+
+    let request = "M-SEARCH * HTTP/1.1\r\n\
+         HOST: 239.255.255.250:1900\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         MX: 2\r\n\
+         ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+    socket.send_to(request.as_bytes(), SSDP_ADDR)?;
+
+    let mut buf = [0; 2048];
+    loop {
+        let (n, _) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Err(Error::NoUpnpGateway);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let response = String::from_utf8_lossy(&buf[..n]);
+        let location = response.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_owned())
+        });
+        if let Some(location) = location {
+            return Ok(location);
+        }
+    }
+}
+
+/// Find a port mapping service in a gateway description. Returns the service type and
+/// the path of its control URL.
+fn control_url(description: &str) -> Option<(&'static str, &str)> {
+    UPNP_SERVICES.iter().find_map(|service| {
+        let ix = description.find(&format!("<serviceType>{service}</serviceType>"))?;
+        let url = element(&description[ix..], "controlURL")?;
+        // Control URLs may be absolute.
+        let path = match url.strip_prefix("http://") {
+            Some(url) => &url[url.find('/')?..],
+            None => url,
+        };
+        Some((*service, path))
+    })
+}
+
+/// Get the text of the first element with the given name.
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{name}>"))? + start;
+
+    Some(xml[start..end].trim())
+}
+
+/// Call a UPnP action with SOAP. Returns the response body.
+fn soap(
+    addr: net::SocketAddr,
+    host: &str,
+    control: &str,
+    service: &str,
+    action: &str,
+    args: &str,
+) -> Result<String, Error> {
+    let body = format!(
+        "<?xml version=\"1.0\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body><u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body>\
+         </s:Envelope>"
+    );
+    let soap_action = format!("\"{service}#{action}\"");
+
+    http(
+        addr,
+        host,
+        "POST",
+        control,
+        &[
+            ("Content-Type", "text/xml; charset=\"utf-8\""),
+            ("SOAPAction", &soap_action),
+        ],
+        &body,
+    )
+}
+
+/// Make an HTTP request to the gateway. Returns the response body.
+///
+/// Nb. HTTP/1.0 is used, so that the response isn't chunked.
+fn http(
+    addr: net::SocketAddr,
+    host: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Result<String, Error> {
+    let mut stream = net::TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut request = format!("{method} {path} HTTP/1.0\r\nHost: {host}\r\n");
+    for (name, value) in headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or(Error::InvalidResponse("invalid HTTP status line"))?;
+    if status != 200 {
+        return Err(Error::Upnp(status));
+    }
+    let (_, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or(Error::InvalidResponse("invalid HTTP response"))?;
+
+    Ok(body.to_owned())
+}
+
+/// Resolve a gateway host, with an optional port.
+fn socket_addr(host: &str) -> Result<net::SocketAddr, Error> {
+    let addrs = if host.contains(':') {
+        net::ToSocketAddrs::to_socket_addrs(host)?.next()
+    } else {
+        net::ToSocketAddrs::to_socket_addrs(&(host, 80))?.next()
+    };
+    addrs.ok_or(Error::InvalidResponse("gateway host could not be resolved"))
+}
+
+/// Get our local IP address on the route to the given address.
+fn local_ip(addr: net::SocketAddr) -> io::Result<net::IpAddr> {
+    let socket = net::UdpSocket::bind((net::Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect(addr)?;
+
+    Ok(socket.local_addr()?.ip())
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    /// Run a fake NAT-PMP gateway answering one mapping request, with the given external
+    /// address. Returns the gateway's address, and the mapping request received.
+    fn fake_gateway(external: net::Ipv4Addr) -> (net::SocketAddr, thread::JoinHandle<[u8; 12]>) {
+        let socket = net::UdpSocket::bind((net::Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let mut buf = [0; 12];
+
+            // External address request.
+            let (n, client) = socket.recv_from(&mut buf).unwrap();
+            assert_eq!(&buf[..n], &[0, 0]);
+
+            let mut response = [0; 12];
+            response[1] = 128;
+            response[8..12].copy_from_slice(&external.octets());
+            socket.send_to(&response, client).unwrap();
+
+            // Mapping request. The gateway grants the requested port and lifetime.
+            let (n, client) = socket.recv_from(&mut buf).unwrap();
+            assert_eq!(n, 12);
+            assert_eq!(buf[1], 2);
+
+            let mut response = [0; 16];
+            response[1] = 130;
+            response[8..10].copy_from_slice(&buf[4..6]);
+            response[10..12].copy_from_slice(&buf[6..8]);
+            response[12..16].copy_from_slice(&buf[8..12]);
+            socket.send_to(&response, client).unwrap();
+
+            buf
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_nat_pmp() {
+        let external = net::Ipv4Addr::new(203, 0, 113, 7);
+        let (addr, handle) = fake_gateway(external);
+        let mapping = nat_pmp_with(addr, 8776, LIFETIME).unwrap();
+
+        assert_eq!(
+            mapping,
+            Mapping {
+                protocol: Protocol::NatPmp,
+                external: net::SocketAddrV4::new(external, 8776),
+                lifetime: LIFETIME,
+            }
+        );
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_nat_pmp_delete() {
+        let (addr, handle) = fake_gateway(net::Ipv4Addr::new(203, 0, 113, 7));
+        nat_pmp_with(addr, 8776, time::Duration::ZERO).unwrap();
+
+        // Deleting a mapping is asking for one with a zero lifetime and external port.
+        let request = handle.join().unwrap();
+        assert_eq!(&request[4..6], &8776u16.to_be_bytes());
+        assert_eq!(&request[6..12], &[0; 6]);
+    }
+
+    #[test]
+    fn test_is_global() {
+        assert!(is_global(net::Ipv4Addr::new(203, 0, 113, 7)));
+        assert!(is_global(net::Ipv4Addr::new(100, 128, 0, 1)));
+        assert!(!is_global(net::Ipv4Addr::new(192, 168, 1, 2)));
+        assert!(!is_global(net::Ipv4Addr::new(10, 0, 0, 1)));
+        assert!(!is_global(net::Ipv4Addr::new(127, 0, 0, 1)));
+        assert!(!is_global(net::Ipv4Addr::new(100, 64, 0, 1)));
+        assert!(!is_global(net::Ipv4Addr::new(100, 127, 255, 255)));
+        assert!(!is_global(net::Ipv4Addr::UNSPECIFIED));
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_default_route() {
+        let routes = "\
+Iface	Destination	Gateway 	Flags	RefCnt	Use	Metric	Mask		MTU	Window	IRTT
+eth0	0001A8C0	00000000	0001	0	0	100	00FFFFFF	0	0	0
+eth0	00000000	0101A8C0	0003	0	0	100	00000000	0	0	0
+";
+        let local = routes.lines().take(2).collect::<Vec<_>>().join("\n");
+
+        assert_eq!(
+            default_route(routes),
+            Some(net::Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(default_route(&local), None);
+    }
+
+    #[test]
+    fn test_control_url() {
+        let description = "\
+<root><device><serviceList>
+<service>
+<serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+<controlURL>/ctl/L3F</controlURL>
+</service>
+<service>
+<serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+<controlURL>http://192.168.1.1:5000/ctl/IPConn</controlURL>
+</service>
+</serviceList></device></root>";
+
+        assert_eq!(
+            control_url(description),
+            Some((UPNP_SERVICES[0], "/ctl/IPConn"))
+        );
+        assert_eq!(control_url("<root></root>"), None);
+        assert_eq!(
+            element(
+                "<NewExternalIPAddress> 203.0.113.7 </NewExternalIPAddress>",
+                "NewExternalIPAddress"
+            ),
+            Some("203.0.113.7")
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Prometheus metrics endpoint.
+//!
+//! When enabled, the node serves metrics about its activity over HTTP, at `/metrics`, in the
+//! Prometheus text format. The server is deliberately minimal: it handles one request per
+//! connection, and only answers `GET /metrics`.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read as _, Write as _};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{io, net, thread, time};
+
+use crate::runtime::Handle;
+use crate::service::counters::{Counters, FETCH_BUCKETS};
+
+/// How long to wait for a client to send its request.
+pub const READ_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+/// How long to wait for a client to receive the response.
+pub const WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+/// Maximum length of the request line, and of every header, in bytes.
+pub const MAX_LINE_LEN: usize = 8 * 1024;
+/// Maximum number of request headers.
+pub const MAX_HEADERS: usize = 64;
+/// Maximum number of requests served at the same time. Connections over this limit are
+/// closed right away, so that slow clients can't use up the node's threads.
+pub const MAX_CONNECTIONS: usize = 8;
+
+/// Metrics of the node, at a point in time.
+#[derive(Debug, Default, Clone)]
+pub struct Snapshot {
+    /// Number of sessions, by session state.
+    pub sessions: BTreeMap<String, usize>,
+    /// Number of entries in the routing table.
+    pub routing: usize,
+    /// Number of repositories in the local inventory.
+    pub inventory: usize,
+    /// Activity counters.
+    pub counters: Counters,
+}
+
+/// Listen for metrics requests on the given listener.
+pub fn listen(listener: net::TcpListener, handle: Handle) -> Result<(), io::Error> {
+    log::debug!(target: "metrics", "Serving metrics on {}..", listener.local_addr()?);
+
+    // Number of requests being served. Only this thread increments it.
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    log::debug!(target: "metrics", "Too many metrics requests, closing connection..");
+                    continue;
+                }
+                active.fetch_add(1, Ordering::SeqCst);
+
+                let spawned = thread::Builder::new().name(String::from("metrics")).spawn({
+                    let handle = handle.clone();
+                    let active = active.clone();
+
+                    move || {
+                        if let Err(e) = serve(stream, &handle) {
+                            log::debug!(target: "metrics", "Failed to serve metrics request: {e}");
+                        }
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    }
+                });
+                if let Err(e) = spawned {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    log::error!(target: "metrics", "Failed to spawn metrics thread: {e}");
+                }
+            }
+            Err(e) => log::error!(target: "metrics", "Failed to accept metrics request: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Answer a single request.
+fn serve(mut stream: net::TcpStream, handle: &Handle) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let deadline = time::Instant::now() + READ_TIMEOUT;
+    let request = read_request(&mut BufReader::new(&stream), deadline)?;
+
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => match handle.snapshot() {
+            Ok(snapshot) => ("200 OK", render(&snapshot).unwrap_or_default()),
+            Err(e) => ("500 Internal Server Error", format!("{e}\n")),
+        },
+        _ => ("404 Not Found", String::from("Not Found\n")),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Read a request, and return its request line. The headers are skipped, since we don't
+/// need them, but they count towards the limits: the request must be received before the
+/// deadline, and its lines and number of headers are capped.
+fn read_request<R: BufRead>(reader: &mut R, deadline: time::Instant) -> io::Result<String> {
+    let request = read_line(reader, deadline)?;
+
+    for _ in 0..=MAX_HEADERS {
+        let header = read_line(reader, deadline)?;
+
+        if header.trim().is_empty() {
+            return Ok(request);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "too many request headers",
+    ))
+}
+
+/// Read a line of at most [`MAX_LINE_LEN`] bytes, unless the deadline has passed.
+fn read_line<R: BufRead>(reader: &mut R, deadline: time::Instant) -> io::Result<String> {
+    if time::Instant::now() >= deadline {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "request not received in time",
+        ));
+    }
+    let mut line = String::new();
+    let len = reader
+        .by_ref()
+        .take(MAX_LINE_LEN as u64)
+        .read_line(&mut line)?;
+
+    if len == MAX_LINE_LEN && !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "request line too long",
+        ));
+    }
+    Ok(line)
+}
+
+/// Render a snapshot in the Prometheus text format.
+pub fn render(snapshot: &Snapshot) -> Result<String, fmt::Error> {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "# HELP radicle_gossip_messages_received_total Messages received from peers."
+    )?;
+    writeln!(out, "# TYPE radicle_gossip_messages_received_total counter")?;
+    for (kind, count) in &snapshot.counters.messages {
+        writeln!(
+            out,
+            "radicle_gossip_messages_received_total{{type=\"{kind}\"}} {count}"
+        )?;
+    }
+
+    writeln!(out, "# HELP radicle_sessions Peer sessions, by state.")?;
+    writeln!(out, "# TYPE radicle_sessions gauge")?;
+    for (state, count) in &snapshot.sessions {
+        writeln!(out, "radicle_sessions{{state=\"{state}\"}} {count}")?;
+    }
+
+    let fetches = &snapshot.counters.fetches;
+    writeln!(
+        out,
+        "# HELP radicle_fetch_duration_seconds Duration of successful fetches."
+    )?;
+    writeln!(out, "# TYPE radicle_fetch_duration_seconds histogram")?;
+    for (bound, count) in FETCH_BUCKETS.iter().zip(fetches.buckets.iter()) {
+        writeln!(
+            out,
+            "radicle_fetch_duration_seconds_bucket{{le=\"{bound}\"}} {count}"
+        )?;
+    }
+    writeln!(
+        out,
+        "radicle_fetch_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        fetches.count
+    )?;
+    writeln!(out, "radicle_fetch_duration_seconds_sum {}", fetches.sum)?;
+    writeln!(
+        out,
+        "radicle_fetch_duration_seconds_count {}",
+        fetches.count
+    )?;
+
+    writeln!(
+        out,
+        "# HELP radicle_routing_entries Entries in the routing table."
+    )?;
+    writeln!(out, "# TYPE radicle_routing_entries gauge")?;
+    writeln!(out, "radicle_routing_entries {}", snapshot.routing)?;
+
+    writeln!(
+        out,
+        "# HELP radicle_inventory_repositories Repositories in the local inventory."
+    )?;
+    writeln!(out, "# TYPE radicle_inventory_repositories gauge")?;
+    writeln!(out, "radicle_inventory_repositories {}", snapshot.inventory)?;
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut snapshot = Snapshot {
+            routing: 42,
+            inventory: 3,
+            ..Snapshot::default()
+        };
+        snapshot.sessions.insert(String::from("connected"), 2);
+        snapshot.counters.messages.insert("ping", 5);
+        snapshot.counters.fetches.observe(0.5);
+
+        let out = render(&snapshot).unwrap();
+
+        assert!(out.contains("radicle_gossip_messages_received_total{type=\"ping\"} 5\n"));
+        assert!(out.contains("radicle_sessions{state=\"connected\"} 2\n"));
+        assert!(out.contains("radicle_fetch_duration_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(out.contains("radicle_fetch_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(out.contains("radicle_fetch_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+        assert!(out.contains("radicle_fetch_duration_seconds_count 1\n"));
+        assert!(out.contains("radicle_routing_entries 42\n"));
+        assert!(out.contains("radicle_inventory_repositories 3\n"));
+    }
+
+    #[test]
+    fn test_read_request() {
+        let deadline = time::Instant::now() + READ_TIMEOUT;
+        let request = b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+        assert_eq!(
+            read_request(&mut &request[..], deadline).unwrap(),
+            "GET /metrics HTTP/1.1\r\n"
+        );
+        // The connection may be closed without an empty line after the headers.
+        assert!(read_request(&mut &b"GET /metrics HTTP/1.1\r\n"[..], deadline).is_ok());
+    }
+
+    #[test]
+    fn test_read_request_limits() {
+        let deadline = time::Instant::now() + READ_TIMEOUT;
+
+        let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_LEN));
+        let err = read_request(&mut long.as_bytes(), deadline).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let headers = format!(
+            "GET /metrics HTTP/1.1\r\n{}\r\n",
+            "X-Header: value\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let err = read_request(&mut headers.as_bytes(), deadline).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let headers = format!(
+            "GET /metrics HTTP/1.1\r\n{}\r\n",
+            "X-Header: value\r\n".repeat(MAX_HEADERS)
+        );
+        assert!(read_request(&mut headers.as_bytes(), deadline).is_ok());
+
+        let err = read_request(
+            &mut &b"GET /metrics HTTP/1.1\r\n\r\n"[..],
+            time::Instant::now(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -15,15 +15,21 @@ use thiserror::Error;
 
 use radicle::git;
 use radicle::node::Handle as _;
-use radicle::node::{ADDRESS_DB_FILE, ROUTING_DB_FILE, TRACKING_DB_FILE};
+use radicle::node::{
+    ADDRESS_DB_FILE, AUDIT_DB_FILE, INVENTORY_DB_FILE, ROUTING_DB_FILE, TRACKING_DB_FILE,
+};
+use radicle::profile;
 use radicle::profile::Home;
 use radicle::Storage;
 
 use crate::address;
 use crate::control;
 use crate::crypto::Signer;
-use crate::node::{routing, NodeId};
+use crate::node::{audit, inventory, metrics, routing, NodeId};
+use crate::portmap;
+use crate::prometheus;
 use crate::service::{tracking, Event};
+use crate::webhooks;
 use crate::wire;
 use crate::wire::Wire;
 use crate::worker;
@@ -32,6 +38,10 @@ use crate::{service, LocalTime};
 pub use handle::Error as HandleError;
 pub use handle::Handle;
 
+/// Maximum number of connection audit log entries waiting to be recorded.
+/// Entries are dropped if the log falls further behind.
+pub const AUDIT_QUEUE_SIZE: usize = 1024;
+
 /// A client error.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -44,6 +54,18 @@ pub enum Error {
     /// A tracking database error.
     #[error("tracking database error: {0}")]
     Tracking(#[from] tracking::Error),
+    /// An inventory cache error.
+    #[error("inventory cache error: {0}")]
+    Inventory(#[from] inventory::Error),
+    /// A peer metrics error.
+    #[error("peer metrics error: {0}")]
+    Metrics(#[from] metrics::Error),
+    /// A peer reputation error.
+    #[error("peer reputation error: {0}")]
+    Reputation(#[from] address::reputation::Error),
+    /// A connection audit log error.
+    #[error("connection audit log error: {0}")]
+    Audit(#[from] audit::Error),
     /// An I/O error.
     #[error("i/o error: {0}")]
     Io(#[from] io::Error),
@@ -60,6 +82,9 @@ pub enum Error {
     /// A git version error.
     #[error("git version error: {0}")]
     GitVersion(#[from] git::VersionError),
+    /// A profile configuration error.
+    #[error("profile configuration error: {0}")]
+    Profile(#[from] profile::Error),
 }
 
 /// Publishes events to subscribers.
@@ -104,21 +129,28 @@ pub struct Runtime {
     pub storage: Storage,
     pub reactor: Reactor<wire::Control>,
     pub daemon: net::SocketAddr,
+    pub exporter: Option<net::TcpListener>,
     pub pool: worker::Pool,
     pub local_addrs: Vec<net::SocketAddr>,
     pub signals: chan::Receiver<()>,
+    /// Connection audit log, and the entries to record in it.
+    pub audit: (audit::Log, chan::Receiver<audit::Entry>),
+    /// Whether to map our listen port on the gateway.
+    pub port_mapping: bool,
 }
 
 impl Runtime {
     /// Initialize the runtime.
     ///
     /// This function spawns threads.
+    #[allow(clippy::too_many_arguments)]
     pub fn init<G: Signer + Ecdh + 'static>(
         home: Home,
         config: service::Config,
         listen: Vec<net::SocketAddr>,
         proxy: net::SocketAddr,
         daemon: net::SocketAddr,
+        exporter: Option<net::SocketAddr>,
         signals: chan::Receiver<()>,
         signer: G,
     ) -> Result<Runtime, Error>
@@ -127,6 +159,12 @@ impl Runtime {
     {
         let id = *signer.public_key();
         let node_dir = home.node();
+        // The settings of the profile are applied on top of the given configuration, which is
+        // kept, so that settings can later be reloaded.
+        let defaults = config.clone();
+        let mut config = config;
+        config.apply(&profile::Config::load(&home.config())?);
+
         let network = config.network;
         let rng = fastrand::Rng::new();
         let clock = LocalTime::now();
@@ -134,20 +172,32 @@ impl Runtime {
         let address_db = node_dir.join(ADDRESS_DB_FILE);
         let routing_db = node_dir.join(ROUTING_DB_FILE);
         let tracking_db = node_dir.join(TRACKING_DB_FILE);
+        let inventory_db = node_dir.join(INVENTORY_DB_FILE);
+        let audit_db = node_dir.join(AUDIT_DB_FILE);
 
         log::info!(target: "node", "Opening address book {}..", address_db.display());
-        let addresses = address::Book::open(address_db)?;
+        let addresses = address::Book::open(&address_db)?;
+        let metrics = metrics::Table::open(&address_db)?;
+        let reputation = address::reputation::Table::open(address_db)?;
 
         log::info!(target: "node", "Opening routing table {}..", routing_db.display());
         let routing = routing::Table::open(routing_db)?;
 
         log::info!(target: "node", "Opening tracking policy table {}..", tracking_db.display());
         let tracking = tracking::Store::open(tracking_db)?;
-        let tracking = tracking::Config::new(config.policy, config.scope, tracking);
+        let tracking = tracking::Config::new(config.policy, config.scope.clone(), tracking);
+
+        log::info!(target: "node", "Opening inventory cache {}..", inventory_db.display());
+        let inventory = inventory::Cache::open(inventory_db)?;
+
+        log::info!(target: "node", "Opening connection audit log {}..", audit_db.display());
+        let audit = audit::Log::open(audit_db)?;
 
         log::info!(target: "node", "Default tracking policy set to '{}'", &config.policy);
         log::info!(target: "node", "Initializing service ({:?})..", network);
         let emitter: Emitter<Event> = Default::default();
+        let policy = config.content_policy.clone();
+        let port_mapping = config.port_mapping;
         let service = service::Service::new(
             config,
             clock,
@@ -155,13 +205,18 @@ impl Runtime {
             storage.clone(),
             addresses,
             tracking,
+            inventory,
+            metrics,
+            reputation,
             signer.clone(),
             rng,
             emitter.clone(),
-        );
+        )
+        .with_defaults(defaults);
 
         let (worker_send, worker_recv) = chan::unbounded::<worker::Task>();
-        let mut wire = Wire::new(service, worker_send, signer, proxy, clock);
+        let (audit_send, audit_recv) = chan::bounded::<audit::Entry>(AUDIT_QUEUE_SIZE);
+        let mut wire = Wire::new(service, worker_send, signer, proxy, audit_send, clock);
         let mut local_addrs = Vec::new();
 
         for addr in listen {
@@ -195,8 +250,18 @@ impl Runtime {
                 storage: storage.clone(),
                 daemon,
                 atomic,
+                policy,
             },
         );
+        let exporter = match exporter {
+            Some(addr) => {
+                let listener = net::TcpListener::bind(addr)?;
+                log::info!(target: "node", "Serving metrics on {}..", listener.local_addr()?);
+
+                Some(listener)
+            }
+            None => None,
+        };
         let control = match UnixListener::bind(home.socket()) {
             Ok(sock) => sock,
             Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
@@ -214,10 +279,13 @@ impl Runtime {
             storage,
             reactor,
             daemon,
+            exporter,
             handle,
             pool,
             signals,
             local_addrs,
+            audit: (audit, audit_recv),
+            port_mapping,
         })
     }
 
@@ -231,6 +299,48 @@ impl Runtime {
             let handle = self.handle.clone();
             move || control::listen(self.control, handle)
         })?;
+        if let Some(listener) = self.exporter {
+            thread::Builder::new().name(self.id.to_human()).spawn({
+                let handle = self.handle.clone();
+                move || prometheus::listen(listener, handle)
+            })?;
+        }
+        thread::Builder::new().name(self.id.to_human()).spawn({
+            let home = home.clone();
+            let storage = self.storage.clone();
+            let events = self.handle.events();
+            move || webhooks::run(home, storage, events)
+        })?;
+        // Connections are recorded on their own thread, so that the reactor doesn't wait on
+        // the database. The thread exits once the reactor drops its end of the channel.
+        thread::Builder::new().name(self.id.to_human()).spawn({
+            let (mut audit, entries) = self.audit;
+            move || {
+                for entry in entries {
+                    if let Err(e) = audit.record(entry) {
+                        log::error!(target: "node", "Error recording connection to audit log: {e}");
+                    }
+                }
+            }
+        })?;
+        // Closed once the reactor exits, so that the port mapping is deleted.
+        let (portmap_stop, portmap_stopped) = chan::bounded::<()>(1);
+        let mut mapper = None;
+        if self.port_mapping {
+            // Only IPv4 ports are mapped, since IPv6 addresses usually don't need it.
+            match self.local_addrs.iter().find(|a| a.is_ipv4()) {
+                Some(addr) => {
+                    mapper = Some(thread::Builder::new().name(self.id.to_human()).spawn({
+                        let port = addr.port();
+                        let handle = self.handle.clone();
+                        move || portmap::run(port, handle, portmap_stopped)
+                    })?);
+                }
+                None => {
+                    log::warn!(target: "node", "Port mapping is enabled, but not listening on IPv4");
+                }
+            }
+        }
         let _signals = thread::Builder::new()
             .name(self.id.to_human())
             .spawn(move || {
@@ -259,6 +369,11 @@ impl Runtime {
         self.pool.run().unwrap();
         self.reactor.join().unwrap();
 
+        drop(portmap_stop);
+        if let Some(mapper) = mapper {
+            mapper.join().ok();
+        }
+
         daemon::kill(&daemon).ok(); // Ignore error if daemon has already exited, for whatever reason.
         daemon.wait()?;
 
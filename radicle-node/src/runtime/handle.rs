@@ -1,15 +1,19 @@
+use std::collections::BTreeMap;
 use std::os::unix::net::UnixStream;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{fmt, io, time};
 
 use crossbeam_channel as chan;
+use radicle::node::trace::Trace;
 use radicle::node::Seeds;
 use thiserror::Error;
 
 use crate::identity::Id;
-use crate::node::{Command, FetchResult};
+use crate::node::{Address, Command, ConnectOptions, FetchResult, Signal, Timestamp};
+use crate::profile;
 use crate::profile::Home;
+use crate::prometheus::Snapshot;
 use crate::runtime::Emitter;
 use crate::service;
 use crate::service::tracking;
@@ -35,6 +39,9 @@ pub enum Error {
     /// An I/O error occured.
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    /// The profile configuration could not be loaded.
+    #[error(transparent)]
+    Profile(#[from] profile::Error),
 }
 
 impl From<chan::RecvError> for Error {
@@ -114,6 +121,40 @@ impl Handle {
         self.controller.cmd(wire::Control::Flush { remote, stream })
     }
 
+    /// Advertise the external address obtained by mapping our port on the gateway.
+    pub fn map_address(&mut self, addr: Address) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::MapAddress(addr, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    /// Get a snapshot of the node's metrics.
+    pub fn snapshot(&self) -> Result<Snapshot, Error> {
+        let (sender, receiver) = chan::unbounded();
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            let mut sessions = BTreeMap::new();
+            for session in state.sessions().values() {
+                *sessions.entry(session.state.to_string()).or_default() += 1;
+            }
+            sender
+                .send(Snapshot {
+                    sessions,
+                    routing: state.routing().len()?,
+                    inventory: state.inventory().len()?,
+                    counters: state.counters().clone(),
+                })
+                .ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let snapshot = receiver.recv()?;
+
+        Ok(snapshot)
+    }
+
     fn command(&self, cmd: service::Command) -> Result<(), io::Error> {
         self.controller.cmd(wire::Control::User(cmd))
     }
@@ -127,8 +168,13 @@ impl radicle::node::Handle for Handle {
         true
     }
 
-    fn connect(&mut self, node: NodeId, addr: radicle::node::Address) -> Result<(), Error> {
-        self.command(service::Command::Connect(node, addr))?;
+    fn connect(
+        &mut self,
+        node: NodeId,
+        addr: radicle::node::Address,
+        opts: ConnectOptions,
+    ) -> Result<(), Error> {
+        self.command(service::Command::Connect(node, addr, opts))?;
 
         Ok(())
     }
@@ -139,15 +185,25 @@ impl radicle::node::Handle for Handle {
         receiver.recv().map_err(Error::from)
     }
 
-    fn fetch(&mut self, id: Id, from: NodeId) -> Result<FetchResult, Error> {
+    fn fetch(
+        &mut self,
+        id: Id,
+        from: NodeId,
+        timeout: time::Duration,
+    ) -> Result<FetchResult, Error> {
         let (sender, receiver) = chan::bounded(1);
         self.command(service::Command::Fetch(id, from, sender))?;
-        receiver.recv().map_err(Error::from)
+        receiver.recv_timeout(timeout).map_err(Error::from)
     }
 
-    fn track_node(&mut self, id: NodeId, alias: Option<String>) -> Result<bool, Error> {
+    fn track_node(
+        &mut self,
+        id: NodeId,
+        alias: Option<String>,
+        expiry: Option<Timestamp>,
+    ) -> Result<bool, Error> {
         let (sender, receiver) = chan::bounded(1);
-        self.command(service::Command::TrackNode(id, alias, sender))?;
+        self.command(service::Command::TrackNode(id, alias, expiry, sender))?;
         receiver.recv().map_err(Error::from)
     }
 
@@ -185,6 +241,72 @@ impl radicle::node::Handle for Handle {
         receiver.recv().map_err(Error::from)
     }
 
+    fn search(&mut self, query: String) -> Result<usize, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Search(query, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn signal(&mut self, to: NodeId, signal: Signal) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Signal(to, signal, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn trace_gossip(&mut self, enable: bool) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::TraceGossip(enable, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn reload_config(&mut self) -> Result<bool, Error> {
+        let config = profile::Config::load(&self.home.config())?;
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Reconfigure(Box::new(config), sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn addresses(&self, id: NodeId) -> Result<Vec<Address>, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Addresses(id, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn ban(&mut self, id: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Ban(id, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn disconnect(&mut self, id: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Disconnect(id, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn unban(&mut self, id: NodeId) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Unban(id, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
+    fn gossip_traces(&self) -> Result<Option<Vec<Trace>>, Error> {
+        let (sender, receiver) = chan::unbounded();
+        let query: Arc<QueryState> = Arc::new(move |state| {
+            sender
+                .send(state.traces().map(|t| t.iter().cloned().collect()))
+                .ok();
+            Ok(())
+        });
+        let (err_sender, err_receiver) = chan::bounded(1);
+        self.command(service::Command::QueryState(query, err_sender))?;
+        err_receiver.recv()??;
+
+        let traces = receiver.recv()?;
+
+        Ok(traces)
+    }
+
     fn subscribe(
         &self,
         _timeout: time::Duration,
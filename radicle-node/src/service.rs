@@ -2,14 +2,16 @@
 #![allow(clippy::collapsible_match)]
 #![allow(clippy::collapsible_if)]
 pub mod config;
+pub mod counters;
 pub mod filter;
 pub mod message;
 pub mod reactor;
+pub mod scheduler;
 pub mod session;
 pub mod tracking;
 
 use std::collections::hash_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use std::{fmt, net, str};
@@ -20,24 +22,33 @@ use localtime::{LocalDuration, LocalTime};
 use log::*;
 
 use crate::address;
+use crate::address::reputation;
 use crate::address::AddressBook;
 use crate::crypto;
 use crate::crypto::{Signer, Verified};
 use crate::identity::IdentityError;
 use crate::identity::{Doc, Id};
 use crate::node;
+use crate::node::inventory;
+use crate::node::metrics;
 use crate::node::routing;
 use crate::node::routing::InsertResult;
-use crate::node::{Address, Features, FetchResult, Seed, Seeds};
+use crate::node::trace;
+use crate::node::{
+    Address, ConnectOptions, Features, FetchResult, SearchResult, Seed, Seeds, Signal,
+};
 use crate::prelude::*;
+use crate::profile;
 use crate::runtime::Emitter;
-use crate::service::message::{Announcement, AnnouncementMessage, Ping};
+use crate::service::message::{Announcement, AnnouncementMessage, Hello, Ping};
 use crate::service::message::{NodeAnnouncement, RefsAnnouncement};
+use crate::service::message::{Search, SearchResults};
 use crate::service::tracking::Scope;
 use crate::storage;
-use crate::storage::{Namespaces, ReadStorage};
-use crate::storage::{ReadRepository, RefUpdate};
-use crate::worker::FetchError;
+use crate::storage::refs::SIGREFS_BRANCH;
+use crate::storage::{Namespaces, ReadStorage, WriteStorage};
+use crate::storage::{ReadRepository, RefUpdate, Rewrite, WriteRepository};
+use crate::worker::{FetchError, FetchStats, Refusal};
 use crate::Link;
 
 pub use crate::node::events::{Event, Events};
@@ -46,9 +57,11 @@ pub use crate::service::config::{Config, Network};
 pub use crate::service::message::{Message, ZeroBytes};
 pub use crate::service::session::Session;
 
+use self::counters::Counters;
 use self::gossip::Gossip;
 use self::message::InventoryAnnouncement;
 use self::reactor::Reactor;
+use self::scheduler::{Priority, Schedule, Scheduler};
 use self::tracking::NamespacesError;
 
 /// Target number of peers to maintain connections to.
@@ -65,16 +78,30 @@ pub const PRUNE_INTERVAL: LocalDuration = LocalDuration::from_mins(30);
 pub const STALE_CONNECTION_TIMEOUT: LocalDuration = LocalDuration::from_mins(2);
 /// How much time should pass after a peer was last active for a *ping* to be sent.
 pub const KEEP_ALIVE_DELTA: LocalDuration = LocalDuration::from_mins(1);
-/// Maximum time difference between the local time, and an announcement timestamp.
+/// Default maximum time difference between the local time, and an announcement timestamp.
 pub const MAX_TIME_DELTA: LocalDuration = LocalDuration::from_mins(60);
 /// Maximum attempts to connect to a peer before we give up.
 pub const MAX_CONNECTION_ATTEMPTS: usize = 3;
 /// How far back from the present time should we request gossip messages when connecting to a peer.
 pub const SUBSCRIBE_BACKLOG_DELTA: LocalDuration = LocalDuration::from_mins(60);
+/// How long after a peer disconnected can its session be resumed.
+pub const RESUME_WINDOW: LocalDuration = LocalDuration::from_mins(10);
+/// How far back from the time a peer disconnected should we send it gossip messages when
+/// resuming its session. Announcements can reach us some time after they were made.
+pub const RESUME_BACKLOG_DELTA: LocalDuration = LocalDuration::from_mins(5);
 /// Minimum amount of time to wait before reconnecting to a peer.
 pub const MIN_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_secs(3);
 /// Maximum amount of time to wait before reconnecting to a peer.
 pub const MAX_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_mins(60);
+/// Time to wait on a connection attempt before also dialing the next known address of a peer.
+pub const CONNECTION_ATTEMPT_DELAY: LocalDuration = LocalDuration::from_millis(250);
+/// Maximum number of cached inventory entries to validate against storage per "idle" task.
+pub const INVENTORY_VALIDATION_BATCH: usize = 256;
+/// Minimum time between two searches of our inventory by the same peer. Searches that come
+/// sooner are ignored.
+pub const MIN_SEARCH_INTERVAL: LocalDuration = LocalDuration::from_secs(1);
+/// How long to wait for the results of a search before giving up on them.
+pub const SEARCH_TIMEOUT: LocalDuration = LocalDuration::from_mins(1);
 
 /// Maximum external address limit imposed by message size limits.
 pub use message::ADDRESS_LIMIT;
@@ -109,6 +136,10 @@ pub enum Error {
     Routing(#[from] routing::Error),
     #[error(transparent)]
     Tracking(#[from] tracking::Error),
+    #[error("inventory cache: {0}")]
+    Inventory(#[from] inventory::Error),
+    #[error(transparent)]
+    Identity(#[from] IdentityError),
     #[error("namespaces error: {0}")]
     Namespaces(#[from] NamespacesError),
 }
@@ -116,6 +147,15 @@ pub enum Error {
 /// Function used to query internal service state.
 pub type QueryState = dyn Fn(&dyn ServiceState) -> Result<(), CommandError> + Send + Sync;
 
+/// Whether a received announcement should be relayed to our peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relay {
+    /// Relay the announcement.
+    Yes,
+    /// Don't relay the announcement, for the given reason.
+    No(&'static str),
+}
+
 /// Commands sent to the service by the operator.
 pub enum Command {
     /// Announce repository references for given repository to peers.
@@ -125,19 +165,42 @@ pub enum Command {
     /// Announce local inventory to peers.
     SyncInventory(chan::Sender<bool>),
     /// Connect to node with the given address.
-    Connect(NodeId, Address),
+    Connect(NodeId, Address, ConnectOptions),
+    /// Disconnect from the given node.
+    Disconnect(NodeId, chan::Sender<bool>),
     /// Lookup seeds for the given repository in the routing table.
     Seeds(Id, chan::Sender<Seeds>),
+    /// Get the known addresses of the given node.
+    Addresses(NodeId, chan::Sender<Vec<Address>>),
     /// Fetch the given repository from the network.
     Fetch(Id, NodeId, chan::Sender<FetchResult>),
     /// Track the given repository.
     TrackRepo(Id, Scope, chan::Sender<bool>),
     /// Untrack the given repository.
     UntrackRepo(Id, chan::Sender<bool>),
-    /// Track the given node.
-    TrackNode(NodeId, Option<String>, chan::Sender<bool>),
+    /// Track the given node, optionally until the given time.
+    TrackNode(
+        NodeId,
+        Option<String>,
+        Option<Timestamp>,
+        chan::Sender<bool>,
+    ),
     /// Untrack the given node.
     UntrackNode(NodeId, chan::Sender<bool>),
+    /// Search the repositories of connected peers.
+    Search(String, chan::Sender<usize>),
+    /// Send a signal to a connected peer.
+    Signal(NodeId, Signal, chan::Sender<bool>),
+    /// Enable or disable gossip tracing.
+    TraceGossip(bool, chan::Sender<bool>),
+    /// Ban the given node.
+    Ban(NodeId, chan::Sender<bool>),
+    /// Unban the given node.
+    Unban(NodeId, chan::Sender<bool>),
+    /// Apply the given profile configuration.
+    Reconfigure(Box<profile::Config>, chan::Sender<bool>),
+    /// Advertise the given external address, obtained by mapping our port on the gateway.
+    MapAddress(Address, chan::Sender<bool>),
     /// Query the internal service state.
     QueryState(Arc<QueryState>, chan::Sender<Result<(), CommandError>>),
 }
@@ -148,13 +211,22 @@ impl fmt::Debug for Command {
             Self::AnnounceRefs(id) => write!(f, "AnnounceRefs({id})"),
             Self::AnnounceInventory => write!(f, "AnnounceInventory"),
             Self::SyncInventory(_) => write!(f, "SyncInventory(..)"),
-            Self::Connect(id, addr) => write!(f, "Connect({id}, {addr})"),
+            Self::Connect(id, addr, opts) => write!(f, "Connect({id}, {addr}, {opts:?})"),
+            Self::Disconnect(id, _) => write!(f, "Disconnect({id})"),
             Self::Seeds(id, _) => write!(f, "Seeds({id})"),
+            Self::Addresses(id, _) => write!(f, "Addresses({id})"),
             Self::Fetch(id, node, _) => write!(f, "Fetch({id}, {node})"),
             Self::TrackRepo(id, scope, _) => write!(f, "TrackRepo({id}, {scope})"),
             Self::UntrackRepo(id, _) => write!(f, "UntrackRepo({id})"),
-            Self::TrackNode(id, _, _) => write!(f, "TrackNode({id})"),
+            Self::TrackNode(id, _, _, _) => write!(f, "TrackNode({id})"),
             Self::UntrackNode(id, _) => write!(f, "UntrackNode({id})"),
+            Self::Search(query, _) => write!(f, "Search({query:?})"),
+            Self::Signal(nid, signal, _) => write!(f, "Signal({nid}, {signal:?})"),
+            Self::TraceGossip(enable, _) => write!(f, "TraceGossip({enable})"),
+            Self::Ban(id, _) => write!(f, "Ban({id})"),
+            Self::Unban(id, _) => write!(f, "Unban({id})"),
+            Self::Reconfigure(_, _) => write!(f, "Reconfigure(..)"),
+            Self::MapAddress(addr, _) => write!(f, "MapAddress({addr})"),
             Self::QueryState { .. } => write!(f, "QueryState(..)"),
         }
     }
@@ -169,12 +241,16 @@ pub enum CommandError {
     Routing(#[from] routing::Error),
     #[error(transparent)]
     Tracking(#[from] tracking::Error),
+    #[error(transparent)]
+    Inventory(#[from] inventory::Error),
 }
 
 #[derive(Debug)]
 pub struct Service<R, A, S, G> {
     /// Service configuration.
     config: Config,
+    /// Configuration the settings of the profile are applied on, when reloaded.
+    defaults: Config,
     /// Our cryptographic signer and key.
     signer: G,
     /// Project storage.
@@ -185,10 +261,29 @@ pub struct Service<R, A, S, G> {
     addresses: A,
     /// Tracking policy configuration.
     tracking: tracking::Config,
+    /// Persistent cache of our local inventory.
+    inventory: inventory::Cache,
+    /// Peer metrics, used to prefer faster seeds.
+    metrics: metrics::Table,
+    /// Peer reputation, used to avoid misbehaving peers.
+    reputation: reputation::Table,
+    /// Traces of the last announcements received, if gossip tracing is enabled.
+    traces: Option<VecDeque<trace::Trace>>,
+    /// Activity counters, exported as metrics.
+    counters: Counters,
+    /// Cached inventory entries that haven't yet been validated against storage.
+    unvalidated: Vec<Id>,
+    /// Repositories known to be private, used to notice visibility changes.
+    private: HashSet<Id>,
     /// State relating to gossip.
     gossip: Gossip,
     /// Peer sessions, currently or recently connected.
     sessions: Sessions,
+    /// Sessions of disconnected peers that can be resumed.
+    resumable: HashMap<NodeId, session::Resumable>,
+    /// Whether peers are deprioritized, as last computed. Cleared on every idle task, and
+    /// for a peer when it is penalized, or a fetch from it completes.
+    deprioritized_cache: HashMap<NodeId, bool>,
     /// Clock. Tells the time.
     clock: LocalTime,
     /// Interface to the I/O reactor.
@@ -197,6 +292,13 @@ pub struct Service<R, A, S, G> {
     rng: Rng,
     /// Fetch requests initiated by user, which are waiting for results.
     fetch_reqs: HashMap<(Id, NodeId), chan::Sender<FetchResult>>,
+    /// Schedules fetches across sessions.
+    scheduler: Scheduler,
+    /// Search queries initiated by user, which are waiting for results, by peer, along with
+    /// the time they were sent.
+    search_reqs: HashMap<(NodeId, String), LocalTime>,
+    /// Fetches requested by peers with a sync request, which are waiting for a reply.
+    sync_reqs: HashSet<(Id, NodeId)>,
     /// Current tracked repository bloom filter.
     filter: Filter,
     /// Last time the service was idle.
@@ -209,6 +311,10 @@ pub struct Service<R, A, S, G> {
     last_announce: LocalTime,
     /// Time when the service was initialized.
     start_time: LocalTime,
+    /// Timestamp of our last announcement.
+    last_timestamp: Timestamp,
+    /// External address obtained by mapping our port on the gateway, if any.
+    mapped: Option<Address>,
     /// Publishes events to subscribers.
     emitter: Emitter<Event>,
 }
@@ -232,7 +338,7 @@ impl<R, A, S, G> Service<R, A, S, G>
 where
     R: routing::Store,
     A: address::Store,
-    S: ReadStorage + 'static,
+    S: WriteStorage + 'static,
     G: Signer,
 {
     pub fn new(
@@ -242,17 +348,29 @@ where
         storage: S,
         addresses: A,
         tracking: tracking::Config,
+        inventory: inventory::Cache,
+        metrics: metrics::Table,
+        reputation: reputation::Table,
         signer: G,
         rng: Rng,
         emitter: Emitter<Event>,
     ) -> Self {
         let sessions = Sessions::new(rng.clone());
+        let scheduler = Scheduler::new(config.limits.max_fetches, config.limits.fetch_concurrency);
 
         Self {
+            defaults: config.clone(),
             config,
             storage,
             addresses,
             tracking,
+            inventory,
+            metrics,
+            reputation,
+            traces: None,
+            counters: Counters::default(),
+            unvalidated: Vec::new(),
+            private: HashSet::new(),
             signer,
             rng,
             clock,
@@ -260,17 +378,31 @@ where
             gossip: Gossip::default(),
             reactor: Reactor::default(),
             sessions,
+            resumable: HashMap::new(),
+            deprioritized_cache: HashMap::new(),
             fetch_reqs: HashMap::new(),
+            scheduler,
+            search_reqs: HashMap::new(),
+            sync_reqs: HashSet::new(),
             filter: Filter::empty(),
             last_idle: LocalTime::default(),
             last_sync: LocalTime::default(),
             last_prune: LocalTime::default(),
             last_announce: LocalTime::default(),
             start_time: LocalTime::default(),
+            last_timestamp: Timestamp::default(),
+            mapped: None,
             emitter,
         }
     }
 
+    /// Set the configuration the settings of the profile are applied on, when they are
+    /// reloaded. By default, this is the configuration the service was created with.
+    pub fn with_defaults(mut self, defaults: Config) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
     /// Return the next i/o action to execute.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<reactor::Io> {
@@ -328,6 +460,16 @@ where
         &mut self.addresses
     }
 
+    /// Get the mutable peer metrics.
+    pub fn metrics_mut(&mut self) -> &mut metrics::Table {
+        &mut self.metrics
+    }
+
+    /// Get the peer reputation.
+    pub fn reputation(&self) -> &reputation::Table {
+        &self.reputation
+    }
+
     /// Get the storage instance.
     pub fn storage(&self) -> &S {
         &self.storage
@@ -338,6 +480,11 @@ where
         &mut self.storage
     }
 
+    /// Get the service configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Get the tracking policy.
     pub fn tracking(&self) -> &tracking::Config {
         &self.tracking
@@ -378,12 +525,31 @@ where
         for (id, addr) in addrs {
             self.connect(id, addr);
         }
+        // Load our inventory from the cache if possible, so that we don't have to scan
+        // storage before serving peers. Cached entries are validated lazily, from the
+        // "idle" task.
+        let inventory = if self.inventory.is_empty()? {
+            self.refresh_inventory()?
+        } else {
+            let inventory = self.inventory.inventory()?;
+            debug!(
+                target: "service",
+                "Loaded {} repositories from inventory cache", inventory.len()
+            );
+            self.unvalidated = inventory.clone();
+            inventory
+        };
         // Ensure that our inventory is recorded in our routing table, and we are tracking
         // all of it. It can happen that inventory is not properly tracked if for eg. the
         // user creates a new repository while the node is stopped.
-        for rid in self.storage.inventory()? {
+        for rid in inventory {
             self.routing.insert(rid, self.node_id(), time.as_millis())?;
 
+            // Remember which repositories are private, so that we notice when they're not.
+            if !self.is_public(rid) {
+                self.private.insert(rid);
+            }
+
             if !self.is_tracking(&rid)? {
                 if self
                     .track_repo(&rid, tracking::Scope::Trusted)
@@ -416,12 +582,26 @@ where
 
         trace!(target: "service", "Wake +{}", now - self.start_time);
 
+        self.dial_stalled(now);
+
         if now - self.last_idle >= IDLE_INTERVAL {
             trace!(target: "service", "Running 'idle' task...");
 
             self.keep_alive(&now);
             self.disconnect_unresponsive_peers(&now);
             self.maintain_connections();
+
+            if let Err(e) = self.validate_inventory(INVENTORY_VALIDATION_BATCH) {
+                error!(target: "service", "Error validating inventory cache: {e}");
+            }
+            if let Err(e) = self.untrack_expired_nodes() {
+                error!(target: "service", "Error untracking expired nodes: {e}");
+            }
+            self.resumable
+                .retain(|_, r| !r.is_expired(now, RESUME_WINDOW));
+            self.deprioritized_cache.clear();
+            self.search_reqs
+                .retain(|_, sent| now - *sent < SEARCH_TIMEOUT);
             self.reactor.wakeup(IDLE_INTERVAL);
             self.last_idle = now;
         }
@@ -435,11 +615,7 @@ where
             self.last_sync = now;
         }
         if now - self.last_announce >= ANNOUNCE_INTERVAL {
-            if let Err(err) = self
-                .storage
-                .inventory()
-                .and_then(|i| self.announce_inventory(i))
-            {
+            if let Err(err) = self.announce_inventory() {
                 error!(target: "service", "Error announcing inventory: {}", err);
             }
             self.reactor.wakeup(ANNOUNCE_INTERVAL);
@@ -451,6 +627,9 @@ where
             if let Err(err) = self.prune_routing_entries(&now) {
                 error!("Error pruning routing entries: {}", err);
             }
+            if let Err(err) = self.reputation.expire(now.as_millis()) {
+                error!(target: "service", "Error expiring peer reputation: {err}");
+            }
             self.reactor.wakeup(PRUNE_INTERVAL);
             self.last_prune = now;
         }
@@ -463,7 +642,15 @@ where
         info!(target: "service", "Received command {:?}", cmd);
 
         match cmd {
-            Command::Connect(nid, addr) => {
+            Command::Connect(nid, addr, opts) => {
+                if opts.persistent {
+                    if !self.config.is_persistent(&nid) {
+                        self.config.connect.push((nid, addr.clone()));
+                    }
+                    if let Some(sess) = self.sessions.get_mut(&nid) {
+                        sess.persistent = true;
+                    }
+                }
                 self.connect(nid, addr);
             }
             Command::Seeds(rid, resp) => match self.seeds(&rid) {
@@ -474,18 +661,43 @@ where
                     error!(target: "service", "Error reading routing table for {rid}: {e}");
                 }
             },
+            Command::Addresses(nid, resp) => match self.addresses.get(&nid) {
+                Ok(node) => {
+                    let addrs = node
+                        .map(|n| n.addrs.into_iter().map(|ka| ka.addr).collect())
+                        .unwrap_or_default();
+                    resp.send(addrs).ok();
+                }
+                Err(e) => {
+                    error!(target: "service", "Error reading address book for {nid}: {e}");
+                }
+            },
             Command::Fetch(rid, seed, resp) => {
                 // TODO: Establish connections to unconnected seeds, and retry.
                 self.fetch_reqs.insert((rid, seed), resp);
-                self.fetch(rid, &seed);
+                self.fetch(rid, &seed, Priority::Interactive);
             }
             Command::TrackRepo(rid, scope, resp) => {
+                let previous = self
+                    .tracking
+                    .repo_policy(&rid)
+                    .expect("Service::command: error reading repository policy");
+                let rescoped =
+                    previous.policy == tracking::Policy::Track && previous.scope != scope;
                 // Update our tracking policy.
                 let tracked = self
                     .track_repo(&rid, scope)
                     .expect("Service::command: error tracking repository");
                 resp.send(tracked).ok();
 
+                // If the scope of a repository we were already tracking changed, make sure
+                // our copy of it reflects the new scope.
+                if rescoped {
+                    if let Err(e) = self.reconcile_scope(rid) {
+                        error!(target: "service", "Error reconciling {rid} with its tracking scope: {e}");
+                    }
+                }
+
                 // Let all our peers know that we're interested in this repo from now on.
                 self.reactor.broadcast(
                     Message::subscribe(self.filter(), self.time(), Timestamp::MAX),
@@ -498,12 +710,16 @@ where
                     .expect("Service::command: error untracking repository");
                 resp.send(untracked).ok();
             }
-            Command::TrackNode(id, alias, resp) => {
+            Command::TrackNode(id, alias, expiry, resp) => {
                 let tracked = self
                     .tracking
                     .track_node(&id, alias.as_deref())
                     .expect("Service::command: error tracking node");
-                resp.send(tracked).ok();
+                let expiry = self
+                    .tracking
+                    .set_node_expiry(&id, expiry)
+                    .expect("Service::command: error setting node policy expiry");
+                resp.send(tracked || expiry).ok();
             }
             Command::UntrackNode(id, resp) => {
                 let untracked = self
@@ -513,15 +729,19 @@ where
                 resp.send(untracked).ok();
             }
             Command::AnnounceRefs(id) => {
+                if let Err(err) = self.reconcile_visibility(id) {
+                    error!("Error reconciling visibility of {id}: {err}");
+                }
                 if let Err(err) = self.announce_refs(id, [self.node_id()]) {
                     error!("Error announcing refs: {}", err);
                 }
             }
             Command::AnnounceInventory => {
+                // Make sure we don't announce a stale inventory, eg. if a repository was
+                // just created.
                 if let Err(err) = self
-                    .storage
-                    .inventory()
-                    .and_then(|i| self.announce_inventory(i))
+                    .sync_inventory()
+                    .and_then(|_| self.announce_inventory())
                 {
                     error!("Error announcing inventory: {}", err);
                 }
@@ -533,14 +753,301 @@ where
                 resp.send(synced.added.len() + synced.removed.len() > 0)
                     .ok();
             }
+            Command::Search(query, resp) => {
+                resp.send(self.search(query)).ok();
+            }
+            Command::Signal(nid, signal, resp) => {
+                resp.send(self.signal(nid, signal)).ok();
+            }
+            Command::TraceGossip(enable, resp) => {
+                let toggled = enable != self.traces.is_some();
+                if toggled {
+                    self.traces = enable.then(VecDeque::new);
+                }
+                resp.send(toggled).ok();
+            }
+            Command::Ban(nid, resp) => {
+                let banned = self
+                    .reputation
+                    .ban(&nid, self.time())
+                    .expect("Service::command: error banning node");
+
+                if self.sessions.contains_key(&nid) {
+                    self.reactor
+                        .disconnect(nid, DisconnectReason::Session(session::Error::Banned));
+                }
+                resp.send(banned).ok();
+            }
+            Command::Disconnect(nid, resp) => {
+                let connected = self.sessions.is_connected(&nid);
+                if connected {
+                    self.reactor.disconnect(nid, DisconnectReason::Command);
+                }
+                resp.send(connected).ok();
+            }
+            Command::Unban(nid, resp) => {
+                let unbanned = self
+                    .reputation
+                    .unban(&nid)
+                    .expect("Service::command: error unbanning node");
+                resp.send(unbanned).ok();
+            }
+            Command::Reconfigure(config, resp) => {
+                resp.send(self.reconfigure(&config)).ok();
+            }
+            Command::MapAddress(addr, resp) => {
+                resp.send(self.map_address(addr)).ok();
+            }
             Command::QueryState(query, sender) => {
                 sender.send(query(self)).ok();
             }
         }
     }
 
-    pub fn fetch(&mut self, rid: Id, from: &NodeId) {
-        let Some(session) = self.sessions.get_mut(from) else {
+    /// Apply the settings of the profile configuration to the running service, eg. when the
+    /// configuration is reloaded. Settings that were unset return to their defaults. Returns
+    /// whether any setting changed.
+    ///
+    /// Peers removed from the persistent peers of the profile are kept until the node
+    /// restarts.
+    fn reconfigure(&mut self, profile: &profile::Config) -> bool {
+        let mut config = self.defaults.clone();
+        config.apply(profile);
+
+        if let Some(addr) = &self.mapped {
+            config.add_external_address(addr.clone());
+        }
+
+        for (nid, addr) in &self.config.connect {
+            if !config.is_persistent(nid) {
+                config.connect.push((*nid, addr.clone()));
+            }
+        }
+
+        let connect = config
+            .connect
+            .iter()
+            .filter(|(nid, _)| !self.config.is_persistent(nid))
+            .cloned()
+            .collect::<Vec<_>>();
+        let tracking = config.policy != self.config.policy || config.scope != self.config.scope;
+        let limits = config.limits != self.config.limits;
+        let addresses = config.external_addresses != self.config.external_addresses;
+
+        self.config = config;
+
+        if tracking {
+            info!(
+                target: "service",
+                "Default tracking policy set to '{}' with scope '{}'",
+                self.config.policy, self.config.scope
+            );
+            self.tracking
+                .set_defaults(self.config.policy, self.config.scope.clone());
+        }
+        if limits {
+            info!(target: "service", "Service limits set to {:?}", self.config.limits);
+
+            self.scheduler.set_limits(
+                self.config.limits.max_fetches,
+                self.config.limits.fetch_concurrency,
+            );
+            self.dequeue_fetches();
+        }
+        if addresses {
+            info!(target: "service", "External addresses set to {:?}", self.config.external_addresses);
+
+            self.announce_node();
+        }
+        for (nid, addr) in &connect {
+            if let Some(sess) = self.sessions.get_mut(nid) {
+                sess.persistent = true;
+            } else {
+                self.connect(*nid, addr.clone());
+            }
+        }
+        tracking || limits || addresses || !connect.is_empty()
+    }
+
+    /// Advertise the external address obtained by mapping our port on the gateway, in place
+    /// of the previously mapped one. Returns whether our external addresses changed.
+    fn map_address(&mut self, addr: Address) -> bool {
+        if self.mapped.as_ref() == Some(&addr) {
+            return false;
+        }
+        if let Some(old) = self.mapped.take() {
+            self.config.external_addresses.retain(|a| a != &old);
+        }
+        if !self.config.add_external_address(addr.clone()) {
+            warn!(target: "service", "Not advertising mapped address {addr}: address limit reached, or address already set");
+            return false;
+        }
+        info!(target: "service", "Advertising mapped address {addr}..");
+
+        self.mapped = Some(addr);
+        self.announce_node();
+
+        true
+    }
+
+    /// Announce our node to connected peers, eg. after our external addresses changed.
+    fn announce_node(&mut self) {
+        if let Some(ann) = gossip::node(self.time(), &self.config) {
+            self.reactor.broadcast(
+                Message::node(ann, &self.signer),
+                self.sessions.connected().map(|(_, s)| s),
+            );
+        }
+    }
+
+    /// Send a search query to the connected peers that support it. Returns the number of
+    /// peers the query was sent to.
+    pub fn search(&mut self, query: String) -> usize {
+        // Nb. Strings are length-prefixed with a single byte on the wire.
+        if query.len() > u8::MAX as usize {
+            error!(target: "service", "Search query {query:?} is too long");
+            return 0;
+        }
+        let peers = self
+            .sessions
+            .connected()
+            .filter(|(_, s)| s.capabilities().has(node::Capabilities::SEARCH))
+            .map(|(nid, _)| *nid)
+            .collect::<Vec<_>>();
+
+        for nid in &peers {
+            self.search_reqs.insert((*nid, query.clone()), self.clock);
+        }
+        self.reactor.broadcast(
+            Message::Search(Search { query }),
+            self.sessions
+                .connected()
+                .filter(|(nid, _)| peers.contains(nid))
+                .map(|(_, s)| s),
+        );
+        peers.len()
+    }
+
+    /// Send a signal to a connected peer. Returns `false` if the peer isn't connected, or
+    /// doesn't accept signals.
+    pub fn signal(&mut self, nid: NodeId, signal: Signal) -> bool {
+        let Some(peer) = self.sessions.get_mut(&nid) else {
+            return false;
+        };
+        if !peer.is_connected() || !peer.capabilities().has(node::Capabilities::SIGNAL) {
+            debug!(target: "service", "Unable to send signal to {nid}: peer doesn't accept signals");
+            return false;
+        }
+        self.reactor.write(peer, Message::Signal(signal));
+
+        true
+    }
+
+    /// Handle a signal received from a connected peer.
+    fn signaled(&mut self, remote: NodeId, signal: Signal) {
+        match &signal {
+            Signal::FetchHint { rid } => match self.tracking.is_repo_tracked(rid) {
+                Ok(true) => self.fetch(*rid, &remote, Priority::Background),
+                Ok(false) => {
+                    debug!(target: "service", "Ignoring fetch hint for untracked repository {rid} from {remote}");
+                }
+                Err(e) => {
+                    error!(target: "service", "Error checking tracking policy of {rid}: {e}");
+                }
+            },
+            Signal::SyncRequest { rid } => {
+                let rid = *rid;
+
+                match self.tracking.is_repo_tracked(&rid) {
+                    Ok(true) if !self.config.observer => {
+                        debug!(target: "service", "Syncing {rid} with {remote}, as requested..");
+
+                        self.sync_reqs.insert((rid, remote));
+                        self.fetch(rid, &remote, Priority::Background);
+                    }
+                    Ok(_) => {
+                        debug!(target: "service", "Declining request to sync {rid} with {remote}");
+
+                        self.signal(remote, Signal::SyncDeclined { rid });
+                    }
+                    Err(e) => {
+                        error!(target: "service", "Error checking tracking policy of {rid}: {e}");
+
+                        self.signal(remote, Signal::SyncDeclined { rid });
+                    }
+                }
+            }
+            Signal::Synced { rid, sigrefs } => {
+                // Let subscribers know that the peer is in sync with our refs, as when it
+                // announces them.
+                if self.sigrefs_of(rid, &self.node_id()) == Some(*sigrefs) {
+                    self.emitter.emit(Event::RefsSynced { remote, rid: *rid });
+                }
+            }
+            Signal::SyncDeclined { rid } => {
+                info!(target: "service", "Peer {remote} declined to sync {rid}");
+            }
+            Signal::Invite { rid } => {
+                info!(target: "service", "Invited to {rid} by {remote}");
+            }
+            Signal::Ticket { .. } | Signal::Resume { .. } | Signal::ResumeDeclined => {
+                // Session signals are handled as they are received.
+            }
+            Signal::Unknown { kind, .. } => {
+                debug!(target: "service", "Received signal of unknown kind {kind} from {remote}");
+            }
+        }
+        self.emitter.emit(Event::Signal { remote, signal });
+    }
+
+    /// Get the commit of a remote's signed refs, in our copy of a repository.
+    fn sigrefs_of(&self, rid: &Id, remote: &NodeId) -> Option<storage::Oid> {
+        let repo = self.storage.repository(*rid).ok()?;
+        repo.reference_oid(remote, &SIGREFS_BRANCH).ok()
+    }
+
+    /// Get the repositories of our inventory whose project name or description match a
+    /// search query.
+    fn search_results(&self, query: &str) -> Result<Vec<SearchResult>, Error> {
+        let mut results = Vec::new();
+
+        for rid in self.announced_inventory()? {
+            let Ok(repo) = self.storage.repository(rid) else {
+                continue;
+            };
+            let Ok((_, doc)) = repo.identity_doc() else {
+                continue;
+            };
+            let Ok(doc) = doc.verified() else {
+                continue;
+            };
+            let Ok(project) = doc.project() else {
+                continue;
+            };
+            if !SearchResult::matches(project.name(), project.description(), query) {
+                continue;
+            }
+            let Ok((_, head)) = repo.head() else {
+                continue;
+            };
+            results.push(SearchResult {
+                rid,
+                name: project.name().to_owned(),
+                description: project.description().to_owned(),
+                head,
+                delegates: doc.delegates.into(),
+            });
+            if results.len() == message::SEARCH_RESULTS_LIMIT {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Fetch a repository from a peer. The fetch is started right away, unless the fetch
+    /// limits are reached, in which case it is queued until another fetch completes.
+    pub fn fetch(&mut self, rid: Id, from: &NodeId, priority: Priority) {
+        let Some(session) = self.sessions.get(from) else {
             error!(target: "service", "Session {from} does not exist; cannot initiate fetch");
             return;
         };
@@ -548,38 +1055,134 @@ where
 
         let seed = session.id;
 
-        match session.fetch(rid) {
-            session::FetchResult::Queued => {
-                debug!(target: "service", "Fetch queued for {rid} with {seed}..");
+        if self.config.observer {
+            debug!(target: "service", "Ignoring fetch of {rid} from {seed}: node is an observer");
+
+            self.fetch_failed(rid, seed, String::from("node is an observer"));
+            return;
+        }
+
+        match self.scheduler.schedule(rid, seed, priority) {
+            Schedule::Ready => {
+                self.start_fetch(rid, seed);
+            }
+            Schedule::Queued => {
+                debug!(
+                    target: "service",
+                    "Fetch queued for {rid} with {seed} ({} ongoing, {} queued)..",
+                    self.scheduler.ongoing(),
+                    self.scheduler.queued()
+                );
             }
+            Schedule::AlreadyFetching => {
+                debug!(target: "service", "Ignoring redundant attempt to fetch {rid} from {seed}");
+            }
+        }
+    }
+
+    /// Start a scheduled fetch. If it can't be started, its slot is freed.
+    fn start_fetch(&mut self, rid: Id, seed: NodeId) {
+        let Some(session) = self.sessions.get_mut(&seed) else {
+            error!(target: "service", "Session {seed} does not exist; cannot initiate fetch");
+
+            self.scheduler.fetched(&rid, &seed);
+            return;
+        };
+
+        match session.fetch(rid) {
             session::FetchResult::Ready => {
                 debug!(target: "service", "Fetch initiated for {rid} with {seed}..");
 
                 match self.tracking.namespaces_for(&self.storage, &rid) {
                     Ok(namespaces) => {
-                        self.reactor.fetch(session, rid, namespaces);
+                        let limits = match self.tracking.repo_policy(&rid) {
+                            Ok(repo) => repo.limits,
+                            Err(e) => {
+                                error!(target: "service", "Error getting fetch limits for {rid}: {e}");
+                                tracking::FetchLimits::default()
+                            }
+                        };
+                        self.reactor.fetch(session, rid, namespaces, limits);
                     }
                     Err(err) => {
                         error!(target: "service", "Error getting namespaces for {rid}: {err}");
 
-                        if let Some(resp) = self.fetch_reqs.remove(&(rid, seed)) {
-                            resp.send(FetchResult::Failed {
-                                reason: err.to_string(),
-                            })
-                            .ok();
-                        }
+                        session.fetched(rid);
+                        self.scheduler.fetched(&rid, &seed);
+                        self.fetch_failed(rid, seed, err.to_string());
                     }
                 };
             }
             session::FetchResult::AlreadyFetching => {
-                debug!(target: "service", "Ignoring redundant attempt to fetch {rid} from {from}");
+                debug!(target: "service", "Ignoring redundant attempt to fetch {rid} from {seed}");
             }
             session::FetchResult::NotConnected => {
                 error!(target: "service", "Unable to fetch {rid} from peer {seed}: peer is not connected");
+
+                self.scheduler.fetched(&rid, &seed);
+                self.fetch_failed(rid, seed, String::from("peer is not connected"));
             }
         }
     }
 
+    /// Start queued fetches, for as long as the fetch limits allow.
+    fn dequeue_fetches(&mut self) {
+        while let Some((rid, seed)) = self.scheduler.dequeue() {
+            debug!(target: "service", "Dequeued fetch {rid} from session {seed}..");
+
+            self.start_fetch(rid, seed);
+        }
+    }
+
+    /// Return a failure to the user or peer who requested a fetch, if any.
+    fn fetch_failed(&mut self, rid: Id, seed: NodeId, reason: String) {
+        if let Some(resp) = self.fetch_reqs.remove(&(rid, seed)) {
+            resp.send(FetchResult::Failed { reason }).ok();
+        }
+        if self.sync_reqs.remove(&(rid, seed)) {
+            self.signal(seed, Signal::SyncDeclined { rid });
+        }
+    }
+
+    /// Report content refused by the content policy during a fetch.
+    pub fn refused(&mut self, rid: Id, remote: NodeId, refused: Vec<Refusal>) {
+        for Refusal { namespace, reason } in refused {
+            self.emitter.emit(Event::FetchRefused {
+                remote,
+                rid,
+                namespace,
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    /// Report a rewrite of the canonical branch history caused by a fetch.
+    pub fn rewritten(&mut self, rid: Id, remote: NodeId, rewrite: Rewrite) {
+        if rewrite.adopted {
+            warn!(target: "service", "Canonical branch of {rid} rewritten after fetching from {remote} ({rewrite})");
+        } else {
+            warn!(target: "service", "Canonical branch of {rid} rewritten after fetching from {remote} ({rewrite}), but not adopted");
+        }
+        self.emitter.emit(Event::CanonicalRewrite {
+            remote,
+            rid,
+            old: rewrite.old,
+            new: rewrite.new,
+            adopted: rewrite.adopted,
+        });
+    }
+
+    /// Record the transfer statistics of a successful fetch.
+    pub fn transferred(&mut self, remote: NodeId, stats: FetchStats) {
+        let elapsed = LocalDuration::from_millis(stats.elapsed.as_millis());
+        self.counters.fetched(stats.elapsed);
+        self.deprioritized_cache.remove(&remote);
+
+        if let Err(e) = self.metrics.record_fetch(&remote, stats.received, elapsed) {
+            error!(target: "service", "Error recording fetch metrics for {remote}: {e}");
+        }
+    }
+
     pub fn fetched(
         &mut self,
         rid: Id,
@@ -593,6 +1196,11 @@ where
                 for update in &updated {
                     debug!(target: "service", "Ref updated: {update} for {rid}");
                 }
+                if !updated.is_empty() {
+                    if let Err(e) = self.reconcile_visibility(rid) {
+                        error!(target: "service", "Error reconciling visibility of {rid}: {e}");
+                    }
+                }
                 self.emitter.emit(Event::RefsFetched {
                     remote,
                     rid,
@@ -608,6 +1216,15 @@ where
                 let reason = err.to_string();
                 error!(target: "service", "Fetch failed for {rid} from {remote}: {reason}");
 
+                // Fetches refused by our own policy aren't the remote's fault.
+                if !matches!(err, FetchError::Refused(_)) {
+                    self.deprioritized_cache.remove(&remote);
+
+                    if let Err(e) = self.metrics.record_failure(&remote) {
+                        error!(target: "service", "Error recording fetch failure for {remote}: {e}");
+                    }
+                }
+
                 // For now, we only disconnect the remote in case of timeout. In the future,
                 // there may be other reasons to disconnect.
                 if err.is_timeout() {
@@ -618,6 +1235,8 @@ where
             }
         };
 
+        let success = matches!(result, FetchResult::Success { .. });
+
         if let Some(results) = self.fetch_reqs.remove(&(rid, remote)) {
             debug!(target: "service", "Found existing fetch request, sending result..");
 
@@ -651,15 +1270,32 @@ where
         //
         // Announce the newly fetched repository to the
         // network, if necessary.
-        self.sync_and_announce();
+        match self.sync_fetched(rid) {
+            Ok(true) => {
+                if let Err(e) = self.announce_inventory() {
+                    error!(target: "service", "Failed to announce inventory: {e}");
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(target: "service", "Failed to add {rid} to inventory: {e}");
+            }
+        }
 
-        if let Some(s) = self.sessions.get_mut(&remote) {
-            if let Some(dequeued) = s.fetched(rid) {
-                debug!(target: "service", "Dequeued fetch {dequeued} from session {remote}..");
+        // Reply to the peer who requested this fetch with a sync request, if any.
+        if self.sync_reqs.remove(&(rid, remote)) {
+            let reply = match self.sigrefs_of(&rid, &remote) {
+                Some(sigrefs) if success => Signal::Synced { rid, sigrefs },
+                _ => Signal::SyncDeclined { rid },
+            };
+            self.signal(remote, reply);
+        }
 
-                self.fetch(dequeued, &remote);
-            }
+        if let Some(s) = self.sessions.get_mut(&remote) {
+            s.fetched(rid);
         }
+        self.scheduler.fetched(&rid, &remote);
+        self.dequeue_fetches();
     }
 
     pub fn accepted(&mut self, _addr: net::SocketAddr) {
@@ -670,7 +1306,10 @@ where
         debug!(target: "service", "Attempted connection to {nid} ({addr})");
 
         if let Some(sess) = self.sessions.get_mut(&nid) {
-            sess.to_attempted();
+            // Nb. When a peer has multiple addresses, we may attempt more than one of them.
+            if sess.is_initial() {
+                sess.to_attempted();
+            }
         } else {
             #[cfg(debug_assertions)]
             panic!("Service::attempted: unknown session {nid}@{addr}");
@@ -679,15 +1318,49 @@ where
 
     pub fn connected(&mut self, remote: NodeId, link: Link) {
         info!(target: "service", "Connected to {} ({:?})", remote, link);
+
+        if self.is_banned(&remote) {
+            info!(target: "service", "Disconnecting from banned peer {remote}..");
+
+            if link.is_inbound() {
+                self.sessions.entry(remote).or_insert_with(|| {
+                    Session::inbound(remote, false, self.rng.clone(), self.clock)
+                });
+            }
+            self.reactor
+                .disconnect(remote, DisconnectReason::Session(session::Error::Banned));
+
+            return;
+        }
         self.emitter.emit(Event::PeerConnected { nid: remote });
 
-        let msgs = self.initial(link);
+        let hello = self.supports_hello(&remote);
+        let resumable = self
+            .resumable
+            .remove(&remote)
+            .filter(|r| !r.is_expired(self.clock, RESUME_WINDOW));
+        // If the peer issued us a ticket in our previous session, we ask it to resume that
+        // session, and hold back our handshake until it replies.
+        let resume = resumable
+            .as_ref()
+            .and_then(|r| r.received)
+            .filter(|_| hello);
+        let msgs = match resume {
+            Some(ticket) => self.resumption(ticket),
+            None => self.initial(link, hello),
+        };
 
         if link.is_outbound() {
             if let Some(peer) = self.sessions.get_mut(&remote) {
                 peer.to_connected(self.clock);
+                peer.hello_sent = hello;
+                peer.resumable = resumable;
+                peer.resuming = resume.is_some();
                 self.reactor.write_all(peer, msgs);
             }
+            if let Err(e) = self.reputation.record_success(&remote) {
+                error!(target: "service", "Error recording connection to {remote}: {e}");
+            }
         } else {
             match self.sessions.entry(remote) {
                 Entry::Occupied(e) => {
@@ -702,8 +1375,10 @@ where
                         self.config.is_persistent(&remote),
                         self.rng.clone(),
                         self.clock,
-                        self.config.limits.clone(),
                     ));
+                    peer.hello_sent = hello;
+                    peer.resumable = resumable;
+                    peer.resuming = resume.is_some();
                     self.reactor.write_all(peer, msgs);
                 }
             }
@@ -724,9 +1399,41 @@ where
         };
         let link = session.link;
 
-        // If the peer disconnected while we were fetching, return a failure to any
-        // potential fetcher.
-        for rid in session.fetching() {
+        // If we're still trying to connect, dial the peer's next address right away.
+        if let Some(addr) = session.next_dial(since, CONNECTION_ATTEMPT_DELAY) {
+            debug!(target: "service", "Dialing {remote} on next address {addr}..");
+
+            if session.has_dials() {
+                self.reactor.wakeup(CONNECTION_ATTEMPT_DELAY);
+            }
+            self.reactor.connect(remote, addr);
+
+            return;
+        }
+
+        // If we couldn't connect to the peer at all, it's a strike against it, unless we're
+        // meant to stay connected to it.
+        if link.is_outbound()
+            && !session.persistent
+            && !session.is_connected()
+            && (reason.is_dial_err() || reason.is_connection_err())
+        {
+            if let Err(e) = self.reputation.record(
+                &remote,
+                reputation::Offense::ConnectionFailure,
+                since.as_millis(),
+            ) {
+                error!(target: "service", "Error recording connection failure of {remote}: {e}");
+            }
+        }
+        if let Some(resumable) = session.to_resumable(since) {
+            self.resumable.insert(remote, resumable);
+        }
+
+        // If the peer disconnected while we were fetching, or had fetches queued, return a
+        // failure to any potential fetcher.
+        let dropped = self.scheduler.disconnected(&remote);
+        for rid in session.fetching().into_iter().chain(dropped) {
             if let Some(resp) = self.fetch_reqs.remove(&(rid, remote)) {
                 resp.send(FetchResult::Failed {
                     reason: format!("disconnected: {reason}"),
@@ -734,6 +1441,7 @@ where
                 .ok();
             }
         }
+        self.sync_reqs.retain(|(_, nid)| *nid != remote);
 
         // Attempt to re-connect to persistent peers.
         if self.config.peer(&remote).is_some() {
@@ -755,12 +1463,22 @@ where
                 self.maintain_connections();
             }
         }
+        // Queued fetches from other peers may have been waiting on the ones we dropped.
+        self.dequeue_fetches();
     }
 
     pub fn received_message(&mut self, remote: NodeId, message: Message) {
+        let offense = if let Message::Announcement(_) = message {
+            reputation::Offense::InvalidAnnouncement
+        } else {
+            reputation::Offense::Violation
+        };
         match self.handle_message(&remote, message) {
             Ok(_) => {}
             Err(err) => {
+                if err.is_violation() {
+                    self.penalize(&remote, offense);
+                }
                 // If there's an error, stop processing messages from this peer.
                 // However, we still relay messages returned up to this point.
                 self.reactor
@@ -774,13 +1492,13 @@ where
 
     /// Handle an announcement message.
     ///
-    /// Returns `true` if this announcement should be stored and relayed to connected peers,
-    /// and `false` if it should not.
+    /// Returns whether this announcement should be relayed to connected peers, and if not,
+    /// why.
     pub fn handle_announcement(
         &mut self,
         relayer: &NodeId,
         announcement: &Announcement,
-    ) -> Result<bool, session::Error> {
+    ) -> Result<Relay, session::Error> {
         if !announcement.verify() {
             return Err(session::Error::Misbehavior);
         }
@@ -792,11 +1510,28 @@ where
 
         // Ignore our own announcements, in case the relayer sent one by mistake.
         if *announcer == self.node_id() {
-            return Ok(false);
+            return Ok(Relay::No("announced by us"));
         }
         let now = self.clock;
         let timestamp = message.timestamp();
-        let relay = self.config.relay;
+        let relay = if self.config.relay {
+            Relay::Yes
+        } else {
+            Relay::No("relaying is disabled")
+        };
+
+        // Announcements relayed by others can be outdated for innocent reasons, but peers
+        // have no reason to send us an announcement of their own older than the last one.
+        if relayer == announcer
+            && self
+                .gossip
+                .nodes
+                .get(announcer)
+                .map_or(false, |n| n.is_outdated(announcement))
+            && self.penalize(relayer, reputation::Offense::StaleAnnouncement)
+        {
+            return Err(session::Error::Banned);
+        }
         let peer = self
             .gossip
             .nodes
@@ -804,7 +1539,9 @@ where
             .or_insert_with(Node::default);
 
         // Don't allow messages from too far in the future.
-        if timestamp.saturating_sub(now.as_millis()) > MAX_TIME_DELTA.as_millis() as u64 {
+        if timestamp.saturating_sub(now.as_millis())
+            > self.config.limits.max_time_delta.as_millis() as u64
+        {
             return Err(session::Error::InvalidTimestamp(timestamp));
         }
 
@@ -814,19 +1551,19 @@ where
                 // out last seen time.
                 if !peer.inventory_announced(announcement.clone()) {
                     trace!(target: "service", "Ignoring stale inventory announcement from {announcer} (t={})", self.time());
-                    return Ok(false);
+                    return Ok(Relay::No("stale"));
                 }
 
                 match self.sync_routing(&message.inventory, *announcer, message.timestamp) {
                     Ok(synced) => {
                         if synced.is_empty() {
                             trace!(target: "service", "No routes updated by inventory announcement from {announcer}");
-                            return Ok(false);
+                            return Ok(Relay::No("no routes updated"));
                         }
                     }
                     Err(e) => {
                         error!(target: "service", "Error processing inventory from {}: {}", announcer, e);
-                        return Ok(false);
+                        return Ok(Relay::No("routing table error"));
                     }
                 }
 
@@ -854,7 +1591,7 @@ where
                                 Ok(false) => {
                                     debug!(target: "service", "Missing tracked inventory {id}; initiating fetch..");
 
-                                    self.fetch(*id, announcer);
+                                    self.fetch(*id, announcer, Priority::Background);
                                 }
                                 Err(e) => {
                                     error!(target: "service", "Error checking local inventory: {e}");
@@ -893,7 +1630,7 @@ where
                 // our last seen time.
                 if !peer.refs_announced(message.rid, announcement.clone()) {
                     trace!(target: "service", "Ignoring stale refs announcement from {announcer} (time={timestamp})");
-                    return Ok(false);
+                    return Ok(Relay::No("stale"));
                 }
 
                 // Check if the announcer is in sync with our own refs, and if so emit an event.
@@ -923,7 +1660,7 @@ where
                     // which is required by the protocol to only announce refs it has.
                     if self.sessions.is_connected(announcer) {
                         match self.should_fetch_refs_announcement(message, &repo_entry.scope) {
-                            Ok(true) => self.fetch(message.rid, announcer),
+                            Ok(true) => self.fetch(message.rid, announcer, Priority::Background),
                             Ok(false) => {}
                             Err(e) => {
                                 error!(target: "service", "Failed to check refs announcement: {e}");
@@ -944,6 +1681,7 @@ where
                         "Ignoring refs announcement from {announcer}: repository {} isn't tracked",
                         message.rid
                     );
+                    return Ok(Relay::No("repository is not tracked"));
                 }
             }
             AnnouncementMessage::Node(
@@ -958,22 +1696,41 @@ where
                 // our last seen time.
                 if !peer.node_announced(announcement.clone()) {
                     trace!(target: "service", "Ignoring stale node announcement from {announcer}");
-                    return Ok(false);
+                    return Ok(Relay::No("stale"));
                 }
 
                 if !ann.validate() {
                     warn!(target: "service", "Dropping node announcement from {announcer}: invalid proof-of-work");
-                    return Ok(false);
+                    return Ok(Relay::No("invalid proof-of-work"));
                 }
 
                 let alias = match str::from_utf8(alias) {
                     Ok(s) => s,
                     Err(e) => {
                         warn!(target: "service", "Dropping node announcement from {announcer}: invalid alias: {e}");
-                        return Ok(false);
+                        return Ok(Relay::No("invalid alias"));
                     }
                 };
 
+                // Now that we know the announcer understands `Hello`, send ours if we're
+                // connected to it and haven't done so already.
+                if features.has(Features::HELLO) {
+                    if let Some(sess) = self.sessions.get_mut(announcer) {
+                        if sess.is_connected() && !sess.hello_sent {
+                            sess.hello_sent = true;
+                            self.reactor.write(sess, Message::Hello(Hello::default()));
+                        }
+                    }
+                }
+
+                // Observers don't serve repositories, so make sure we never route fetches
+                // to them, even if they previously announced an inventory.
+                if features.has(Features::OBSERVER) {
+                    if let Err(e) = self.sync_routing(&[], *announcer, timestamp) {
+                        error!(target: "service", "Error removing routes of observer {announcer}: {e}");
+                    }
+                }
+
                 // If this node isn't a seed, we're not interested in adding it
                 // to our address book, but other nodes may be, so we relay the message anyway.
                 if !features.has(Features::SEED) {
@@ -1002,11 +1759,37 @@ where
                     Err(err) => {
                         // An error here is due to a fault in our address store.
                         error!(target: "service", "Error processing node announcement from {announcer}: {err}");
+                        return Ok(Relay::No("address book error"));
                     }
                 }
             }
         }
-        Ok(false)
+        Ok(Relay::No("no new information"))
+    }
+
+    /// Record what we did with a received announcement, if gossip tracing is enabled.
+    fn record_trace(&mut self, relayer: NodeId, ann: &Announcement, verdict: trace::Verdict) {
+        let time = self.time();
+        let Some(traces) = &mut self.traces else {
+            return;
+        };
+        let (kind, rid) = match &ann.message {
+            AnnouncementMessage::Node(_) => (trace::Kind::Node, None),
+            AnnouncementMessage::Inventory(_) => (trace::Kind::Inventory, None),
+            AnnouncementMessage::Refs(refs) => (trace::Kind::Refs, Some(refs.rid)),
+        };
+        if traces.len() >= trace::MAX_TRACES {
+            traces.pop_front();
+        }
+        traces.push_back(trace::Trace {
+            time,
+            kind,
+            origin: ann.node,
+            relayer,
+            rid,
+            timestamp: ann.message.timestamp(),
+            verdict,
+        });
     }
 
     /// A convenient method to check if we should fetch from a `RefsAnnouncement`
@@ -1025,7 +1808,7 @@ where
         // Second, check the scope.
         match scope {
             tracking::Scope::All => Ok(true),
-            tracking::Scope::Trusted => {
+            tracking::Scope::Trusted | tracking::Scope::Explicit(_) => {
                 match self.tracking.namespaces_for(&self.storage, &message.rid) {
                     Ok(Namespaces::All) => Ok(true),
                     Ok(Namespaces::Trusted(mut trusted)) => {
@@ -1058,30 +1841,104 @@ where
             return Ok(());
         };
         peer.last_active = self.clock;
+        self.counters.received(&message);
         message.log(log::Level::Debug, remote, Link::Inbound);
 
         trace!(target: "service", "Received message {:?} from {}", &message, peer.id);
 
         match (&mut peer.state, message) {
+            // Negotiate the session protocol.
+            (session::State::Connected { .. }, Message::Hello(hello)) => {
+                if peer.protocol.is_some() {
+                    return Err(session::Error::Misbehavior);
+                }
+                let protocol = Hello::default()
+                    .negotiate(&hello)
+                    .map_err(session::Error::UnsupportedVersion)?;
+
+                debug!(
+                    target: "service",
+                    "Negotiated protocol version {} with {} ({})",
+                    protocol.version, peer.id, protocol.capabilities
+                );
+                peer.protocol = Some(protocol);
+
+                // The peer understands `Hello`, so reply with ours if we haven't sent it yet.
+                if !peer.hello_sent {
+                    peer.hello_sent = true;
+                    self.reactor.write(peer, Message::Hello(Hello::default()));
+                }
+                if peer.capabilities().has(node::Capabilities::RESUME) {
+                    // Issue a ticket the peer can use to resume this session, if it reconnects.
+                    let ticket = self.rng.u64(..);
+
+                    peer.ticket = Some(ticket);
+                    self.reactor
+                        .write(peer, Message::Signal(Signal::Ticket { ticket }));
+                } else if peer.resuming {
+                    // The peer can't resume our previous session, so we start a new one.
+                    self.handshake(remote);
+                }
+            }
+            // Decompress and process a compressed message.
+            (session::State::Connected { .. }, Message::Compressed(compressed)) => {
+                if !peer.capabilities().has(node::Capabilities::ZSTD) {
+                    return Err(session::Error::Misbehavior);
+                }
+                let msg = compressed
+                    .decompress()
+                    .map_err(|_| session::Error::Misbehavior)?;
+
+                // Compressed messages can't be nested.
+                if matches!(msg, Message::Compressed(_)) {
+                    return Err(session::Error::Misbehavior);
+                }
+                return self.handle_message(remote, msg);
+            }
             // Process a peer announcement.
             (session::State::Connected { .. }, Message::Announcement(ann)) => {
                 let relayer = peer.id;
                 let announcer = ann.node;
 
-                // Returning true here means that the message should be relayed.
-                if self.handle_announcement(&relayer, &ann)? {
-                    // Choose peers we should relay this message to.
-                    // 1. Don't relay to the peer who sent us this message.
-                    // 2. Don't relay to the peer who signed this announcement.
-                    let relay_to = self
-                        .sessions
-                        .connected()
-                        .filter(|(id, _)| *id != remote && *id != &announcer)
-                        .map(|(_, p)| p);
+                match self.handle_announcement(&relayer, &ann) {
+                    Ok(Relay::Yes) => {
+                        // Peers in bad standing are relayed to last, when not relaying to all.
+                        let connected = self
+                            .sessions
+                            .connected()
+                            .map(|(id, _)| *id)
+                            .collect::<Vec<_>>();
+                        let deprioritized = self.deprioritized(&connected);
+                        // Choose peers we should relay this message to.
+                        // 1. Don't relay to the peer who sent us this message.
+                        // 2. Don't relay to the peer who signed this announcement.
+                        let relay_to = self
+                            .sessions
+                            .connected()
+                            .filter(|(id, _)| *id != remote && *id != &announcer)
+                            .map(|(_, p)| p);
+
+                        let relayed = self.reactor.relay(
+                            ann.clone(),
+                            relay_to,
+                            self.config.relay_fanout,
+                            &deprioritized,
+                            &self.rng,
+                        );
+                        self.record_trace(relayer, &ann, trace::Verdict::Relayed { to: relayed });
 
-                    self.reactor.relay(ann, relay_to);
+                        return Ok(());
+                    }
+                    Ok(Relay::No(reason)) => {
+                        let reason = reason.to_owned();
+                        self.record_trace(relayer, &ann, trace::Verdict::Dropped { reason });
+                    }
+                    Err(err) => {
+                        let reason = err.to_string();
+                        self.record_trace(relayer, &ann, trace::Verdict::Rejected { reason });
 
-                    return Ok(());
+                        return Err(err);
+                    }
                 }
             }
             (session::State::Connected { .. }, Message::Subscribe(subscribe)) => {
@@ -1109,10 +1966,84 @@ where
                 );
             }
             (session::State::Connected { ping, .. }, Message::Pong { zeroes }) => {
-                if let session::PingState::AwaitingResponse(ponglen) = *ping {
-                    if (ponglen as usize) == zeroes.len() {
+                if let session::PingState::AwaitingResponse { len, since } = *ping {
+                    if (len as usize) == zeroes.len() {
                         *ping = session::PingState::Ok;
+
+                        if let Err(e) = self.metrics.record_rtt(remote, self.clock - since) {
+                            error!(target: "service", "Error recording round-trip time for {remote}: {e}");
+                        }
+                    }
+                }
+            }
+            (session::State::Connected { .. }, Message::Search(Search { query })) => {
+                if !peer.capabilities().has(node::Capabilities::SEARCH) {
+                    return Err(session::Error::Misbehavior);
+                }
+                if peer
+                    .last_search
+                    .map_or(false, |last| self.clock - last < MIN_SEARCH_INTERVAL)
+                {
+                    debug!(target: "service", "Ignoring search from {remote}: too many searches");
+                    return Ok(());
+                }
+                peer.last_search = Some(self.clock);
+
+                let results = match self.search_results(&query) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        error!(target: "service", "Error searching inventory for {query:?}: {e}");
+                        return Ok(());
+                    }
+                };
+                // Sessions are borrowed by the search, so we look the peer up again.
+                let Some(peer) = self.sessions.get_mut(remote) else {
+                    return Ok(());
+                };
+                self.reactor.write(
+                    peer,
+                    Message::SearchResults(SearchResults {
+                        query,
+                        results: BoundedVec::truncate(results),
+                    })
+                    .compressed(),
+                );
+            }
+            (
+                session::State::Connected { .. },
+                Message::SearchResults(SearchResults { query, results }),
+            ) => {
+                // Ignore results we didn't ask for.
+                if self.search_reqs.remove(&(*remote, query.clone())).is_some() {
+                    self.emitter.emit(Event::SearchResults {
+                        remote: *remote,
+                        query,
+                        results: results.unbound(),
+                    });
+                }
+            }
+            (session::State::Connected { .. }, Message::Signal(signal)) => {
+                if !peer.capabilities().has(node::Capabilities::SIGNAL) {
+                    return Err(session::Error::Misbehavior);
+                }
+                match signal {
+                    Signal::Ticket { .. } | Signal::Resume { .. } | Signal::ResumeDeclined
+                        if !peer.capabilities().has(node::Capabilities::RESUME) =>
+                    {
+                        return Err(session::Error::Misbehavior);
+                    }
+                    Signal::Ticket { ticket } => {
+                        peer.peer_ticket = Some(ticket);
                     }
+                    Signal::Resume { ticket } => {
+                        self.resume(remote, ticket);
+                    }
+                    Signal::ResumeDeclined => {
+                        if peer.resuming {
+                            self.handshake(remote);
+                        }
+                    }
+                    signal => self.signaled(*remote, signal),
                 }
             }
             (session::State::Attempted { .. } | session::State::Initial, msg) => {
@@ -1125,30 +2056,203 @@ where
         Ok(())
     }
 
-    /// Set of initial messages to send to a peer.
-    fn initial(&self, _link: Link) -> Vec<Message> {
+    /// Check whether a peer advertised support for [`Message::Hello`].
+    fn supports_hello(&self, nid: &NodeId) -> bool {
+        match self.addresses.get(nid) {
+            Ok(node) => node.map_or(false, |n| n.features.has(Features::HELLO)),
+            Err(e) => {
+                error!(target: "service", "Error looking up features of {nid}: {e}");
+                false
+            }
+        }
+    }
+
+    /// Set of initial messages to send to a peer. Our [`Message::Hello`] is only included
+    /// if the peer is known to understand it.
+    fn initial(&mut self, _link: Link, hello: bool) -> Vec<Message> {
         let filter = self.filter();
 
         // TODO: Only subscribe to outbound connections, otherwise we will consume too
         // much bandwidth.
 
+        let inventory = self.handshake_inventory();
+
         gossip::handshake(
-            self.clock.as_millis(),
-            &self.storage,
+            self.timestamp(),
+            inventory,
             &self.signer,
             filter,
+            hello,
             &self.config,
         )
     }
 
+    /// Messages to send to a peer instead of our handshake, to resume our previous session
+    /// with the given ticket. Since our inventory may have changed while we were disconnected,
+    /// we announce it again.
+    fn resumption(&mut self, ticket: u64) -> Vec<Message> {
+        let inventory = self.handshake_inventory();
+
+        vec![
+            Message::Hello(Hello::default()),
+            Message::Signal(Signal::Resume { ticket }),
+            Message::inventory(gossip::inventory(self.timestamp(), inventory), &self.signer),
+        ]
+    }
+
+    /// Send our handshake to a peer whose session couldn't be resumed.
+    fn handshake(&mut self, remote: &NodeId) {
+        let Some(link) = self.sessions.get(remote).map(|s| s.link) else {
+            return;
+        };
+        debug!(target: "service", "Unable to resume session with {remote}, starting a new one..");
+
+        let msgs = self.initial(link, false);
+
+        if let Some(peer) = self.sessions.get_mut(remote) {
+            peer.resuming = false;
+            self.reactor.write_all(peer, msgs);
+        }
+    }
+
+    /// Resume the previous session of a peer that presented the given ticket: restore its
+    /// subscription, and send it the gossip it missed while disconnected.
+    fn resume(&mut self, remote: &NodeId, ticket: u64) {
+        let Some(peer) = self.sessions.get_mut(remote) else {
+            return;
+        };
+        // Nb. Tickets can only be used once.
+        let Some(resumable) = peer.resumable.take().filter(|r| r.issued == Some(ticket)) else {
+            debug!(target: "service", "Declining to resume session of {remote}: unknown ticket");
+
+            self.reactor
+                .write(peer, Message::Signal(Signal::ResumeDeclined));
+            return;
+        };
+        debug!(target: "service", "Resuming session of {remote}..");
+
+        if let Some(subscribe) = resumable.subscribe {
+            let since = resumable
+                .since
+                .as_millis()
+                .saturating_sub(RESUME_BACKLOG_DELTA.as_millis() as u64)
+                .max(subscribe.since);
+
+            for ann in self
+                .gossip
+                .filtered(&subscribe.filter, since, subscribe.until)
+                .filter(|ann| &ann.node != remote)
+            {
+                self.reactor.write(peer, ann.into());
+            }
+            peer.subscribe = Some(subscribe);
+        }
+    }
+
+    /// Inventory to announce to peers when connecting.
+    fn handshake_inventory(&self) -> Vec<Id> {
+        match self.announced_inventory() {
+            Ok(i) => i,
+            Err(e) => {
+                error!("Error getting local inventory for handshake: {}", e);
+                // Other than crashing the node completely, there's nothing we can do
+                // here besides returning an empty inventory and logging an error.
+                vec![]
+            }
+        }
+    }
+
     /// Update our routing table with our local node's inventory.
     fn sync_inventory(&mut self) -> Result<SyncedRouting, Error> {
-        let inventory = self.storage.inventory()?;
+        let inventory = self.refresh_inventory()?;
         let result = self.sync_routing(&inventory, self.node_id(), self.time())?;
 
         Ok(result)
     }
 
+    /// Compute our inventory from storage, and update the inventory cache with it.
+    fn refresh_inventory(&mut self) -> Result<Vec<Id>, Error> {
+        let inventory = self.storage.inventory()?;
+        let entries = inventory
+            .iter()
+            .map(|rid| Ok((*rid, self.head_of(*rid)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.inventory.set(entries, self.time())?;
+        // Since we've just looked at all of storage, there is nothing left to validate.
+        self.unvalidated.clear();
+
+        Ok(inventory)
+    }
+
+    /// Add a repository we just fetched to our inventory cache and routing table, without
+    /// looking at the rest of storage. Returns `true` if the repository is new to our inventory.
+    fn sync_fetched(&mut self, rid: Id) -> Result<bool, Error> {
+        if !self.storage.contains(&rid)? {
+            return Ok(false);
+        }
+        let head = self.head_of(rid)?;
+        let time = self.time();
+
+        self.inventory.insert(rid, head, time)?;
+
+        match self.routing.insert(rid, self.node_id(), time)? {
+            InsertResult::SeedAdded => {
+                info!(target: "service", "Routing table updated for {rid} with local node");
+                self.emitter.emit(Event::SeedDiscovered {
+                    rid,
+                    nid: self.node_id(),
+                });
+                Ok(true)
+            }
+            InsertResult::TimeUpdated | InsertResult::NotUpdated => Ok(false),
+        }
+    }
+
+    /// Get the head of a repository in storage, for our inventory cache. Repositories whose
+    /// head can't be computed, eg. because the delegates diverged, are still part of our
+    /// inventory, and have no head.
+    fn head_of(&self, rid: Id) -> Result<Option<storage::Oid>, Error> {
+        match self.storage.repository(rid)?.head() {
+            Ok((_, head)) => Ok(Some(head)),
+            Err(e) => {
+                warn!(target: "service", "Error getting head of {rid}: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Validate up to `batch` cached inventory entries against storage, updating or removing
+    /// them as needed. Once all entries are validated, the inventory is re-computed from
+    /// storage, to pick up repositories that were added while the node was stopped.
+    fn validate_inventory(&mut self, batch: usize) -> Result<(), Error> {
+        if self.unvalidated.is_empty() {
+            return Ok(());
+        }
+        let time = self.time();
+        let n = batch.min(self.unvalidated.len());
+        let batch = self.unvalidated.drain(..n).collect::<Vec<_>>();
+
+        for rid in batch {
+            if !self.storage.contains(&rid)? {
+                warn!(target: "service", "Cached repository {rid} is no longer in storage");
+                self.inventory.remove(&rid)?;
+                continue;
+            }
+            let head = self.head_of(rid)?;
+
+            if self.inventory.insert(rid, head, time)? {
+                debug!(target: "service", "Cached head of {rid} updated to {head:?}");
+            }
+        }
+
+        if self.unvalidated.is_empty() {
+            debug!(target: "service", "Inventory cache validated");
+            self.sync_and_announce();
+        }
+        Ok(())
+    }
+
     /// Process a peer inventory announcement by updating our routing table.
     /// This function expects the peer's full inventory, and prunes entries that are not in the
     /// given inventory.
@@ -1203,8 +2307,20 @@ where
         remotes: impl IntoIterator<Item = NodeId>,
     ) -> Result<(), storage::Error> {
         let repo = self.storage.repository(rid)?;
-        let peers = self.sessions.connected().map(|(_, p)| p);
-        let timestamp = self.time();
+        let timestamp = self.timestamp();
+        // Private repositories are only announced to the nodes allowed to fetch them.
+        let visible = |nid: &NodeId| match repo.is_visible_to(nid) {
+            Ok(visible) => visible,
+            Err(e) => {
+                warn!(target: "service", "Couldn't get the visibility of {rid}: {e}");
+                false
+            }
+        };
+        let peers = self
+            .sessions
+            .connected()
+            .map(|(_, p)| p)
+            .filter(|p| visible(&p.id));
         let mut refs = BoundedVec::<_, REF_REMOTE_LIMIT>::new();
 
         for remote_id in remotes.into_iter() {
@@ -1238,11 +2354,7 @@ where
             Ok(synced) => {
                 // Only announce if our inventory changed.
                 if synced.added.len() + synced.removed.len() > 0 {
-                    if let Err(e) = self
-                        .storage
-                        .inventory()
-                        .and_then(|i| self.announce_inventory(i))
-                    {
+                    if let Err(e) = self.announce_inventory() {
                         error!(target: "service", "Failed to announce inventory: {e}");
                     }
                 }
@@ -1253,10 +2365,75 @@ where
         }
     }
 
+    /// Check whether a peer is banned.
+    fn is_banned(&self, nid: &NodeId) -> bool {
+        self.reputation.is_banned(nid).unwrap_or_else(|e| {
+            error!(target: "service", "Error looking up reputation of {nid}: {e}");
+            false
+        })
+    }
+
+    /// Get the given peers whose reputation and metrics are bad enough to prefer others
+    /// over them. Since this is called for every relayed announcement, the reputation and
+    /// metrics of a peer are only looked up if it isn't in the cache.
+    fn deprioritized<'a>(&mut self, nids: impl IntoIterator<Item = &'a NodeId>) -> HashSet<NodeId> {
+        let mut deprioritized = HashSet::new();
+
+        for nid in nids {
+            let verdict = if let Some(verdict) = self.deprioritized_cache.get(nid) {
+                *verdict
+            } else {
+                let reputation = self.reputation.get(nid);
+                let metrics = self.metrics.get(nid);
+
+                match (reputation, metrics) {
+                    (Ok(r), Ok(m)) => {
+                        let verdict = r.is_deprioritized(&m);
+                        self.deprioritized_cache.insert(*nid, verdict);
+                        verdict
+                    }
+                    (Err(e), _) => {
+                        error!(target: "service", "Error looking up reputation of {nid}: {e}");
+                        false
+                    }
+                    (_, Err(e)) => {
+                        error!(target: "service", "Error looking up metrics of {nid}: {e}");
+                        false
+                    }
+                }
+            };
+            if verdict {
+                deprioritized.insert(*nid);
+            }
+        }
+        deprioritized
+    }
+
+    /// Record an offense of a peer. Returns `true` if the peer is banned.
+    fn penalize(&mut self, nid: &NodeId, offense: reputation::Offense) -> bool {
+        self.deprioritized_cache.remove(nid);
+
+        match self.reputation.record(nid, offense, self.time()) {
+            Ok(reputation) => {
+                if reputation.banned == Some(self.time()) {
+                    info!(
+                        target: "service",
+                        "Banned peer {nid} with a penalty of {}", reputation.penalty()
+                    );
+                }
+                reputation.is_banned()
+            }
+            Err(e) => {
+                error!(target: "service", "Error recording {offense:?} of {nid}: {e}");
+                false
+            }
+        }
+    }
+
     fn reconnect(&mut self, nid: NodeId, addr: Address) -> bool {
         if let Some(sess) = self.sessions.get_mut(&nid) {
             sess.to_initial();
-            self.reactor.connect(nid, addr);
+            self.dial(nid, addr);
 
             return true;
         }
@@ -1268,22 +2445,67 @@ where
             warn!(target: "service", "Attempted connection to peer {nid} which already has a session");
             return false;
         }
+        if self.is_banned(&nid) {
+            warn!(target: "service", "Attempted connection to banned peer {nid}");
+            return false;
+        }
         let persistent = self.config.is_persistent(&nid);
 
-        self.sessions.insert(
-            nid,
-            Session::outbound(
-                nid,
-                persistent,
-                self.rng.clone(),
-                self.config.limits.clone(),
-            ),
-        );
-        self.reactor.connect(nid, addr);
+        self.sessions
+            .insert(nid, Session::outbound(nid, persistent, self.rng.clone()));
+        self.dial(nid, addr);
 
         true
     }
 
+    /// Dial a peer. If the peer has other known addresses, these are dialed in turn,
+    /// [`CONNECTION_ATTEMPT_DELAY`] apart, until a connection is established. This way,
+    /// dead addresses don't stall the connection.
+    fn dial(&mut self, nid: NodeId, addr: Address) {
+        let others = match self.addresses.get(&nid) {
+            Ok(Some(node)) => node
+                .addrs
+                .into_iter()
+                .map(|ka| ka.addr)
+                .filter(|a| *a != addr)
+                .collect::<Vec<_>>(),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                error!(target: "service", "Error looking up addresses of {nid}: {e}");
+                Vec::new()
+            }
+        };
+
+        if let Some(sess) = self.sessions.get_mut(&nid) {
+            if !others.is_empty() {
+                sess.dials(others, self.clock + CONNECTION_ATTEMPT_DELAY);
+                self.reactor.wakeup(CONNECTION_ATTEMPT_DELAY);
+            }
+        }
+        self.reactor.connect(nid, addr);
+    }
+
+    /// Dial the next address of peers whose connection attempts have stalled.
+    fn dial_stalled(&mut self, now: LocalTime) {
+        let mut dials = Vec::new();
+
+        for (nid, sess) in self.sessions.iter_mut() {
+            if sess.is_dial_due(now) {
+                if let Some(addr) = sess.next_dial(now, CONNECTION_ATTEMPT_DELAY) {
+                    dials.push((*nid, addr, sess.has_dials()));
+                }
+            }
+        }
+        for (nid, addr, more) in dials {
+            debug!(target: "service", "Connection attempt to {nid} stalled; dialing {addr}..");
+
+            if more {
+                self.reactor.wakeup(CONNECTION_ATTEMPT_DELAY);
+            }
+            self.reactor.connect(nid, addr);
+        }
+    }
+
     fn seeds(&self, rid: &Id) -> Result<Seeds, Error> {
         #[derive(Default)]
         pub struct Stats {
@@ -1336,13 +2558,80 @@ where
         self.clock.as_millis()
     }
 
+    /// Get a timestamp for a new announcement.
+    ///
+    /// Peers discard announcements that aren't newer than the last one they've seen from us,
+    /// so timestamps are kept strictly increasing, even when several announcements are made
+    /// within the same millisecond.
+    fn timestamp(&mut self) -> Timestamp {
+        self.last_timestamp = self.time().max(self.last_timestamp + 1);
+        self.last_timestamp
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     // Periodic tasks
     ////////////////////////////////////////////////////////////////////////////
 
+    /// Get the inventory we announce to peers. Observers don't serve repositories, so they
+    /// always announce an empty inventory. Private repositories are never announced.
+    fn announced_inventory(&self) -> Result<Vec<Id>, Error> {
+        if self.config.observer {
+            return Ok(vec![]);
+        }
+        let inventory = self.inventory.inventory()?;
+
+        Ok(inventory
+            .into_iter()
+            .filter(|rid| self.is_public(*rid))
+            .collect())
+    }
+
+    /// Whether a repository is public. Repositories whose visibility can't be determined are
+    /// treated as private.
+    fn is_public(&self, rid: Id) -> bool {
+        let visibility = self
+            .storage
+            .repository(rid)
+            .map_err(IdentityError::from)
+            .and_then(|repo| repo.visibility());
+
+        match visibility {
+            Ok(visibility) => visibility.is_public(),
+            Err(e) => {
+                warn!(target: "service", "Couldn't get the visibility of {rid}: {e}");
+                false
+            }
+        }
+    }
+
+    /// Bring our inventory announcement in line with the visibility of a repository, after
+    /// its identity may have changed, eg. because its allow-list was edited. Who we serve the
+    /// repository to is decided when it is fetched, so this only needs to make sure that
+    /// repositories that became private are no longer announced, and the other way round.
+    fn reconcile_visibility(&mut self, rid: Id) -> Result<(), Error> {
+        let visibility = match self.storage.repository(rid)?.visibility() {
+            Ok(visibility) => visibility,
+            Err(e) => {
+                warn!(target: "service", "Couldn't get the visibility of {rid}: {e}");
+                return Ok(());
+            }
+        };
+        let changed = if visibility.is_public() {
+            self.private.remove(&rid)
+        } else {
+            self.private.insert(rid)
+        };
+        if changed {
+            debug!(target: "service", "Visibility of {rid} changed, announcing inventory..");
+            self.announce_inventory()?;
+        }
+        Ok(())
+    }
+
     /// Announce our inventory to all connected peers.
-    fn announce_inventory(&mut self, inventory: Vec<Id>) -> Result<(), storage::Error> {
-        let time = self.time();
+    fn announce_inventory(&mut self) -> Result<(), Error> {
+        let inventory = self.announced_inventory()?;
+        let time = self.timestamp();
         let inv = Message::inventory(gossip::inventory(time, inventory), &self.signer);
         for (_, sess) in self.sessions.connected() {
             self.reactor.write(sess, inv.clone());
@@ -1350,6 +2639,94 @@ where
         Ok(())
     }
 
+    /// Bring a repository in line with its tracking scope, after the scope changed. Remotes
+    /// that are no longer in scope are removed from storage, and the repository is fetched
+    /// from the connected seeds, best first, to get the remotes that came into scope.
+    fn reconcile_scope(&mut self, rid: Id) -> Result<(), Error> {
+        if !self.storage.inventory()?.contains(&rid) {
+            return Ok(());
+        }
+        if let Namespaces::Trusted(trusted) = self.tracking.namespaces_for(&self.storage, &rid)? {
+            let repo = self.storage.repository_mut(rid)?;
+            let local = self.node_id();
+
+            let remotes = repo.remotes().map_err(storage::Error::from)?;
+
+            for remote in remotes.keys() {
+                if *remote == local || trusted.contains(remote) {
+                    continue;
+                }
+                repo.remove_remote(remote)?;
+                debug!(target: "service", "Removed remote {remote} from {rid}: out of scope");
+            }
+        }
+
+        let seeds = self.seeds(&rid)?;
+        self.fetch_ranked(rid, seeds.connected());
+
+        Ok(())
+    }
+
+    /// Fetch a repository from the given seeds, best first, according to our metrics. Seeds
+    /// in bad standing come last. Since a repository is only fetched from one seed at a time,
+    /// the next seeds are only fetched from once the previous fetch is done, so that we fall
+    /// back to them if it fails.
+    fn fetch_ranked<'a>(&mut self, rid: Id, seeds: impl IntoIterator<Item = &'a NodeId>) {
+        match self.metrics.rank(seeds) {
+            Ok(mut ranked) => {
+                let deprioritized = self.deprioritized(ranked.iter().map(|(seed, _)| seed));
+                ranked.sort_by_key(|(seed, _)| deprioritized.contains(seed));
+
+                for (seed, _) in ranked {
+                    self.fetch(rid, &seed, Priority::Background);
+                }
+            }
+            Err(e) => {
+                error!(target: "service", "Couldn't rank seeds for {rid}: {e}");
+            }
+        }
+    }
+
+    /// Drop the tracking policies of nodes that have expired, and if configured to, remove
+    /// their namespaces from storage.
+    fn untrack_expired_nodes(&mut self) -> Result<(), Error> {
+        let expired = self.tracking.expired_nodes(self.time())?;
+
+        for nid in expired {
+            self.tracking.untrack_node(&nid)?;
+            info!(target: "service", "Tracking policy for {nid} expired");
+
+            if !self.config.prune_expired {
+                continue;
+            }
+            for rid in self.storage.inventory()? {
+                // Like garbage collection, only prune the node from repositories where we no
+                // longer fetch its namespace, and never prune delegates.
+                match self.tracking.namespaces_for(&self.storage, &rid) {
+                    Ok(Namespaces::Trusted(trusted)) if !trusted.contains(&nid) => {}
+                    Ok(_) | Err(NamespacesError::NoTrusted { .. }) => continue,
+                    Err(e) => {
+                        error!(target: "service", "Error getting namespaces of {rid}: {e}");
+                        continue;
+                    }
+                }
+                let repo = self.storage.repository_mut(rid)?;
+                match repo.delegates() {
+                    Ok(delegates) if delegates.iter().any(|d| **d == nid) => continue,
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!(target: "service", "Error getting delegates of {rid}: {e}");
+                        continue;
+                    }
+                }
+                if let Err(e) = repo.remove_remote(&nid) {
+                    error!(target: "service", "Error pruning {nid} from {rid}: {e}");
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn prune_routing_entries(&mut self, now: &LocalTime) -> Result<(), routing::Error> {
         let count = self.routing.len()?;
         if count <= self.config.limits.routing_max_size {
@@ -1386,7 +2763,7 @@ where
             .filter(|(_, session)| *now - session.last_active >= KEEP_ALIVE_DELTA)
             .map(|(_, session)| session);
         for session in inactive_sessions {
-            session.ping(&mut self.reactor).ok();
+            session.ping(*now, &mut self.reactor).ok();
         }
     }
 
@@ -1403,10 +2780,25 @@ where
             return Vec::new();
         }
 
-        self.addresses
+        let reputations = match self.reputation.all() {
+            Ok(all) => all.into_iter().collect::<HashMap<_, _>>(),
+            Err(e) => {
+                error!(target: "service", "Error looking up peer reputation: {e}");
+                HashMap::new()
+            }
+        };
+        let mut candidates = self
+            .addresses
             .entries()
             .unwrap()
             .filter(|(node_id, _)| !sessions.contains_key(node_id))
+            .filter(|(node_id, _)| !reputations.get(node_id).map_or(false, |r| r.is_banned()))
+            .collect::<Vec<_>>();
+
+        // Dial the peers with the best reputation first.
+        candidates.sort_by_key(|(node_id, _)| reputations.get(node_id).map_or(0, |r| r.penalty()));
+        candidates
+            .into_iter()
             .take(wanted)
             .map(|(n, s)| (n, s.addr))
             .collect()
@@ -1414,6 +2806,9 @@ where
 
     /// Fetch all repositories that are tracked but missing from our inventory.
     fn fetch_missing_inventory(&mut self) -> Result<(), Error> {
+        if self.config.observer {
+            return Ok(());
+        }
         let inventory = self.storage().inventory()?;
         let missing = self
             .tracking
@@ -1425,9 +2820,7 @@ where
             match self.seeds(&rid) {
                 Ok(seeds) => {
                     if seeds.has_connections() {
-                        for seed in seeds.connected() {
-                            self.fetch(rid, seed);
-                        }
+                        self.fetch_ranked(rid, seeds.connected());
                     } else {
                         // TODO: We should make sure that this fetch is retried later, either
                         // when we connect to a seed, or when we discover a new seed.
@@ -1480,6 +2873,9 @@ where
         }
 
         for (nid, addr, attempts) in reconnect {
+            if self.is_banned(&nid) {
+                continue;
+            }
             if self.reconnect(nid, addr) {
                 debug!(target: "service", "Reconnecting to {nid} (attempts={attempts})...");
             }
@@ -1501,6 +2897,12 @@ pub trait ServiceState {
     fn config(&self) -> &Config;
     /// Get reference to routing table.
     fn routing(&self) -> &dyn routing::Store;
+    /// Get the gossip traces, oldest first, if gossip tracing is enabled.
+    fn traces(&self) -> Option<&VecDeque<trace::Trace>>;
+    /// Get the activity counters.
+    fn counters(&self) -> &Counters;
+    /// Get the local inventory cache.
+    fn inventory(&self) -> &inventory::Cache;
 }
 
 impl<R, A, S, G> ServiceState for Service<R, A, S, G>
@@ -1532,6 +2934,18 @@ where
     fn routing(&self) -> &dyn routing::Store {
         &self.routing
     }
+
+    fn traces(&self) -> Option<&VecDeque<trace::Trace>> {
+        self.traces.as_ref()
+    }
+
+    fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    fn inventory(&self) -> &inventory::Cache {
+        &self.inventory
+    }
 }
 
 /// Disconnect reason.
@@ -1547,6 +2961,8 @@ pub enum DisconnectReason {
     Fetch(FetchError),
     /// Session error.
     Session(session::Error),
+    /// We were asked to disconnect, eg. by the operator.
+    Command,
 }
 
 impl DisconnectReason {
@@ -1566,6 +2982,7 @@ impl DisconnectReason {
             Self::Connection(_) => true,
             Self::Fetch(_) => true,
             Self::Session(err) => err.is_transient(),
+            Self::Command => true,
         }
     }
 }
@@ -1577,6 +2994,7 @@ impl fmt::Display for DisconnectReason {
             Self::Connection(err) => write!(f, "{err}"),
             Self::Session(err) => write!(f, "{err}"),
             Self::Fetch(err) => write!(f, "fetch: {err}"),
+            Self::Command => write!(f, "disconnected by command"),
         }
     }
 }
@@ -1612,6 +3030,16 @@ pub struct Node {
 }
 
 impl Node {
+    /// Check whether an announcement is older than the last one of the same kind.
+    pub fn is_outdated(&self, ann: &Announcement) -> bool {
+        let last = match &ann.message {
+            AnnouncementMessage::Inventory(_) => self.last_inventory.as_ref(),
+            AnnouncementMessage::Node(_) => self.last_node.as_ref(),
+            AnnouncementMessage::Refs(refs) => self.last_refs.get(&refs.rid),
+        };
+        last.map_or(false, |last| ann.timestamp() < last.timestamp())
+    }
+
     /// Process a refs announcement for the given node.
     /// Returns `true` if the timestamp was updated.
     pub fn refs_announced(&mut self, id: Id, ann: Announcement) -> bool {
@@ -1756,31 +3184,29 @@ mod gossip {
         }
     }
 
-    pub fn handshake<G: Signer, S: ReadStorage>(
+    pub fn handshake<G: Signer>(
         now: Timestamp,
-        storage: &S,
+        inventory: Vec<Id>,
         signer: &G,
         filter: Filter,
+        hello: bool,
         config: &Config,
     ) -> Vec<Message> {
-        let inventory = match storage.inventory() {
-            Ok(i) => i,
-            Err(e) => {
-                error!("Error getting local inventory for handshake: {}", e);
-                // Other than crashing the node completely, there's nothing we can do
-                // here besides returning an empty inventory and logging an error.
-                vec![]
-            }
-        };
-
-        let mut msgs = vec![
-            Message::inventory(gossip::inventory(now, inventory), signer),
-            Message::subscribe(
-                filter,
-                now - SUBSCRIBE_BACKLOG_DELTA.as_millis() as u64,
-                Timestamp::MAX,
-            ),
-        ];
+        let mut msgs = Vec::new();
+        // Nodes that predate `Hello` disconnect on unknown message types, so we only send
+        // it to peers that are known to support it.
+        if hello {
+            msgs.push(Message::Hello(Hello::default()));
+        }
+        msgs.push(Message::inventory(
+            gossip::inventory(now, inventory),
+            signer,
+        ));
+        msgs.push(Message::subscribe(
+            filter,
+            now - SUBSCRIBE_BACKLOG_DELTA.as_millis() as u64,
+            Timestamp::MAX,
+        ));
         if let Some(m) = gossip::node(now, config) {
             msgs.push(Message::node(m, signer));
         };
@@ -1789,7 +3215,12 @@ mod gossip {
     }
 
     pub fn node(timestamp: Timestamp, config: &Config) -> Option<NodeAnnouncement> {
-        let features = node::Features::SEED;
+        let features = if config.observer {
+            node::Features::OBSERVER
+        } else {
+            node::Features::SEED
+        }
+        .with(node::Features::HELLO);
         let alias = config.alias();
         let addresses: BoundedVec<_, ADDRESS_LIMIT> = config
             .external_addresses
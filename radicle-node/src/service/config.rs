@@ -1,9 +1,13 @@
 use localtime::LocalDuration;
 
+use log::warn;
+
 use radicle::node::Address;
+use radicle::profile;
 
 use crate::service::tracking::{Policy, Scope};
-use crate::service::NodeId;
+use crate::service::{NodeId, ADDRESS_LIMIT, MAX_TIME_DELTA};
+use crate::worker::ContentPolicy;
 
 /// Peer-to-peer network.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -14,7 +18,7 @@ pub enum Network {
 }
 
 /// Configuration parameters defining attributes of minima and maxima.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Limits {
     /// Number of routing table entries before we start pruning.
     pub routing_max_size: usize,
@@ -22,6 +26,13 @@ pub struct Limits {
     pub routing_max_age: LocalDuration,
     /// Maximum number of concurrent fetches per per connection.
     pub fetch_concurrency: usize,
+    /// Maximum number of concurrent fetches, across all connections. Fetches beyond
+    /// this limit are queued.
+    pub max_fetches: usize,
+    /// How far in the future an announcement timestamp may be, relative to our local time.
+    /// This is the tolerated clock skew between nodes; peers sending announcements
+    /// beyond it are disconnected.
+    pub max_time_delta: LocalDuration,
 }
 
 impl Default for Limits {
@@ -30,6 +41,8 @@ impl Default for Limits {
             routing_max_size: 1000,
             routing_max_age: LocalDuration::from_mins(7 * 24 * 60),
             fetch_concurrency: 1,
+            max_fetches: 8,
+            max_time_delta: MAX_TIME_DELTA,
         }
     }
 }
@@ -44,14 +57,34 @@ pub struct Config {
     pub external_addresses: Vec<Address>,
     /// Peer-to-peer network.
     pub network: Network,
-    /// Whether or not our node should relay inventories.
+    /// Whether or not our node should relay announcements from other nodes. A node that
+    /// doesn't relay is a *leaf* node: it still sends its own announcements, but doesn't
+    /// act as a gossip router for the rest of the network.
     pub relay: bool,
+    /// Maximum number of peers an announcement is relayed to. When more peers are eligible,
+    /// a random subset of them is chosen. If `None`, announcements are relayed to all
+    /// eligible peers.
+    pub relay_fanout: Option<usize>,
+    /// Whether or not our node is an *observer*. Observers maintain routing and address
+    /// gossip, but neither fetch nor serve repositories. This is useful for network monitors
+    /// and crawlers.
+    pub observer: bool,
     /// Configured service limits.
     pub limits: Limits,
     /// Default tracking policy.
     pub policy: Policy,
     /// Default tracking scope.
     pub scope: Scope,
+    /// Whether to remove a node's namespaces from storage once its tracking policy expires.
+    pub prune_expired: bool,
+    /// Policy applied to fetched content, before it is stored.
+    pub content_policy: ContentPolicy,
+    /// Whether to map our listen port on the gateway with NAT-PMP or UPnP, and advertise
+    /// the external address obtained. Only applies on startup.
+    pub port_mapping: bool,
+    /// Node settings given on the command line. These take precedence over the settings
+    /// of the profile, and are applied on top of them, see [`Config::apply`].
+    pub overrides: profile::NodeConfig,
 }
 
 impl Default for Config {
@@ -61,9 +94,15 @@ impl Default for Config {
             external_addresses: vec![],
             network: Network::default(),
             relay: true,
+            relay_fanout: None,
+            observer: false,
             limits: Limits::default(),
             policy: Policy::default(),
             scope: Scope::default(),
+            prune_expired: false,
+            content_policy: ContentPolicy::default(),
+            port_mapping: false,
+            overrides: profile::NodeConfig::default(),
         }
     }
 }
@@ -84,6 +123,75 @@ impl Config {
         self.connect.iter().any(|(i, _)| i == id)
     }
 
+    /// Apply the settings of the profile configuration. Persistent peers of the profile are
+    /// added to the peers to connect to, and the node settings that are set take precedence
+    /// over ours, except for the ones overridden on the command line.
+    pub fn apply(&mut self, profile: &profile::Config) {
+        for peer in &profile.persistent_peers {
+            if !self.is_persistent(&peer.id) {
+                self.connect.push((peer.id, peer.addr.clone()));
+            }
+        }
+        let overrides = self.overrides.clone();
+
+        self.apply_node(&profile.node);
+        self.apply_node(&overrides);
+    }
+
+    /// Apply the node settings that are set.
+    fn apply_node(&mut self, node: &profile::NodeConfig) {
+        if !node.external_addresses.is_empty() {
+            if node.external_addresses.len() > ADDRESS_LIMIT {
+                warn!(
+                    target: "service",
+                    "External address limit ({ADDRESS_LIMIT}) exceeded, ignoring extra addresses"
+                );
+            }
+            self.external_addresses = node
+                .external_addresses
+                .iter()
+                .take(ADDRESS_LIMIT)
+                .cloned()
+                .collect();
+        }
+        if let Some(policy) = node.tracking_policy {
+            self.policy = policy;
+        }
+        if let Some(scope) = &node.tracking_scope {
+            self.scope = scope.clone();
+        }
+        if let Some(size) = node.limits.routing_max_size {
+            self.limits.routing_max_size = size;
+        }
+        if let Some(secs) = node.limits.routing_max_age {
+            self.limits.routing_max_age = LocalDuration::from_secs(secs);
+        }
+        if let Some(n) = node.limits.fetch_concurrency {
+            self.limits.fetch_concurrency = n;
+        }
+        if let Some(n) = node.limits.max_fetches {
+            self.limits.max_fetches = n;
+        }
+        if let Some(secs) = node.limits.max_time_delta {
+            self.limits.max_time_delta = LocalDuration::from_secs(secs);
+        }
+        if let Some(enabled) = node.port_mapping {
+            self.port_mapping = enabled;
+        }
+    }
+
+    /// Add an external address, unless it's already there, or the address limit is reached.
+    /// Returns whether the address was added.
+    pub fn add_external_address(&mut self, addr: Address) -> bool {
+        if self.external_addresses.len() >= ADDRESS_LIMIT || self.external_addresses.contains(&addr)
+        {
+            return false;
+        }
+        self.external_addresses.push(addr);
+
+        true
+    }
+
     pub fn alias(&self) -> [u8; 32] {
         let mut alias = [0u8; 32];
 
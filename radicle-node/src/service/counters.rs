@@ -0,0 +1,90 @@
+//! In-memory service counters, exported as metrics.
+use std::collections::BTreeMap;
+use std::time;
+
+use super::message::{Announcement, AnnouncementMessage, Message};
+
+/// Upper bounds of the fetch duration histogram buckets, in seconds.
+pub const FETCH_BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 1., 2.5, 5., 10., 30.];
+
+/// Counters of the service's activity since it was started.
+#[derive(Debug, Default, Clone)]
+pub struct Counters {
+    /// Messages received from peers, by message type.
+    pub messages: BTreeMap<&'static str, u64>,
+    /// Durations of successful fetches.
+    pub fetches: Histogram,
+}
+
+impl Counters {
+    /// Count a message received from a peer.
+    pub fn received(&mut self, msg: &Message) {
+        *self.messages.entry(kind(msg)).or_default() += 1;
+    }
+
+    /// Record the duration of a successful fetch.
+    pub fn fetched(&mut self, elapsed: time::Duration) {
+        self.fetches.observe(elapsed.as_secs_f64());
+    }
+}
+
+/// A histogram with the [`FETCH_BUCKETS`] buckets.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Histogram {
+    /// Number of observations less than or equal to each bucket's upper bound.
+    pub buckets: [u64; FETCH_BUCKETS.len()],
+    /// Total number of observations.
+    pub count: u64,
+    /// Sum of all observations.
+    pub sum: f64,
+}
+
+impl Histogram {
+    /// Record an observation.
+    pub fn observe(&mut self, value: f64) {
+        for (bound, bucket) in FETCH_BUCKETS.iter().zip(self.buckets.iter_mut()) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Type of a message, as used in metric labels.
+fn kind(msg: &Message) -> &'static str {
+    match msg {
+        Message::Hello(_) => "hello",
+        Message::Subscribe(_) => "subscribe",
+        Message::Announcement(Announcement { message, .. }) => match message {
+            AnnouncementMessage::Node(_) => "node-announcement",
+            AnnouncementMessage::Inventory(_) => "inventory-announcement",
+            AnnouncementMessage::Refs(_) => "refs-announcement",
+        },
+        Message::Compressed(_) => "compressed",
+        Message::Ping(_) => "ping",
+        Message::Pong { .. } => "pong",
+        Message::Search(_) => "search",
+        Message::SearchResults(_) => "search-results",
+        Message::Signal(_) => "signal",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_observe() {
+        let mut h = Histogram::default();
+
+        h.observe(0.25);
+        h.observe(3.);
+        h.observe(60.);
+
+        assert_eq!(h.buckets, [0, 1, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(h.count, 3);
+        assert_eq!(h.sum, 63.25);
+    }
+}
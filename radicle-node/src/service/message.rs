@@ -4,7 +4,7 @@ use crate::crypto;
 use crate::crypto::Unverified;
 use crate::identity::Id;
 use crate::node;
-use crate::node::Address;
+use crate::node::{Address, Signal};
 use crate::prelude::BoundedVec;
 use crate::service::filter::Filter;
 use crate::service::{Link, NodeId, Timestamp};
@@ -19,6 +19,21 @@ pub const ADDRESS_LIMIT: usize = 16;
 pub const REF_REMOTE_LIMIT: usize = 512;
 /// Maximum number of inventory which can be announced to other nodes.
 pub const INVENTORY_LIMIT: usize = 2973;
+/// Maximum number of repositories returned in a [`SearchResults`] message.
+pub const SEARCH_RESULTS_LIMIT: usize = node::SEARCH_RESULTS_LIMIT;
+/// Maximum number of delegates included in a search result.
+pub const SEARCH_DELEGATES_LIMIT: usize = 8;
+/// Gossip protocol version spoken by this node.
+pub const PROTOCOL_VERSION: u8 = 1;
+/// Oldest gossip protocol version this node can talk to.
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+/// Protocol capabilities supported by this node.
+pub const CAPABILITIES: node::Capabilities = node::Capabilities::ZSTD
+    .with(node::Capabilities::SEARCH)
+    .with(node::Capabilities::SIGNAL)
+    .with(node::Capabilities::RESUME);
+/// Minimum encoded size of a message for it to be considered for compression.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 // TODO: We should check the length and charset when deserializing.
@@ -50,6 +65,93 @@ impl Subscribe {
     }
 }
 
+/// Ask a peer for the repositories in its inventory whose name or description contain the
+/// query. Only sent on sessions where both peers support [`node::Capabilities::SEARCH`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    /// Search query. Matched case-insensitively.
+    pub query: String,
+}
+
+/// Response to a [`Search`] message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResults {
+    /// The query these are the results of.
+    pub query: String,
+    /// Matching repositories.
+    pub results: BoundedVec<node::SearchResult, SEARCH_RESULTS_LIMIT>,
+}
+
+/// First message sent on a new session, advertizing the protocol version and capabilities
+/// of the sender.
+///
+/// Once both peers have received each other's `Hello`, the session uses the lowest of the
+/// two protocol versions, and the capabilities supported by both peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    /// Protocol version.
+    pub version: u8,
+    /// Protocol capabilities.
+    pub capabilities: node::Capabilities,
+}
+
+impl Hello {
+    /// Negotiate the session protocol with the `Hello` received from a peer.
+    ///
+    /// Returns the peer's version as an error if we can't talk to it.
+    pub fn negotiate(&self, other: &Hello) -> Result<Hello, u8> {
+        if other.version < MIN_PROTOCOL_VERSION {
+            return Err(other.version);
+        }
+        Ok(Hello {
+            version: self.version.min(other.version),
+            capabilities: self.capabilities & other.capabilities,
+        })
+    }
+}
+
+impl Default for Hello {
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES,
+        }
+    }
+}
+
+/// A message compressed with zstd. Only sent on sessions where both peers support
+/// [`node::Capabilities::ZSTD`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Compressed(Vec<u8>);
+
+impl Compressed {
+    /// Compression level. Messages are small, so higher levels don't gain much.
+    const LEVEL: i32 = 3;
+
+    /// Compress a message.
+    pub fn compress(msg: &Message) -> Result<Self, io::Error> {
+        zstd::bulk::compress(&wire::serialize(msg), Self::LEVEL).map(Self)
+    }
+
+    /// Decompress the message. The decompressed message can't exceed the maximum message size.
+    pub fn decompress(&self) -> Result<Message, wire::Error> {
+        let bytes = zstd::bulk::decompress(&self.0, Message::MAX_SIZE as usize)?;
+
+        wire::deserialize(&bytes)
+    }
+
+    /// Compressed bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Compressed {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
 /// Node announcing itself to the network.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NodeAnnouncement {
@@ -343,6 +445,10 @@ impl Announcement {
 /// These are the messages peers send to each other.
 #[derive(Clone, PartialEq, Eq)]
 pub enum Message {
+    /// Advertize our protocol version and capabilities. This is the first message
+    /// sent on a session.
+    Hello(Hello),
+
     /// Subscribe to gossip messages matching the filter and time range.
     Subscribe(Subscribe),
 
@@ -350,6 +456,9 @@ pub enum Message {
     /// using [`Message::Subscribe`].
     Announcement(Announcement),
 
+    /// A compressed message. See [`Compressed`].
+    Compressed(Compressed),
+
     /// Ask a connected peer for a Pong.
     ///
     /// Used to check if the remote peer is responsive, or a side-effect free way to keep a
@@ -361,6 +470,15 @@ pub enum Message {
         /// The pong payload.
         zeroes: ZeroBytes,
     },
+
+    /// Search the repositories of a connected peer.
+    Search(Search),
+
+    /// Response to `Search` message.
+    SearchResults(SearchResults),
+
+    /// Signal sent directly to a connected peer. Never relayed.
+    Signal(Signal),
 }
 
 impl PartialOrd for Message {
@@ -400,6 +518,28 @@ impl Message {
         AnnouncementMessage::from(message).signed(signer).into()
     }
 
+    /// Compress the message, if it's a large announcement and compressing it saves space.
+    /// Otherwise, return it as-is.
+    pub fn compressed(self) -> Self {
+        if !matches!(
+            &self,
+            Self::Announcement(Announcement {
+                message: AnnouncementMessage::Inventory(_) | AnnouncementMessage::Refs(_),
+                ..
+            }) | Self::SearchResults(_)
+        ) {
+            return self;
+        }
+        let size = wire::serialize(&self).len();
+        if size < COMPRESSION_THRESHOLD {
+            return self;
+        }
+        match Compressed::compress(&self) {
+            Ok(c) if c.as_bytes().len() < size => Self::Compressed(c),
+            _ => self,
+        }
+    }
+
     pub fn subscribe(filter: Filter, since: Timestamp, until: Timestamp) -> Self {
         Self::Subscribe(Subscribe {
             filter,
@@ -434,11 +574,27 @@ impl Message {
                     )
                 }
             },
+            Self::Hello(Hello {
+                version,
+                capabilities,
+            }) => format!("{verb} hello (version {version}, {capabilities}) {prep} {remote}"),
+            Self::Compressed(c) => format!(
+                "{verb} compressed message of {} byte(s) {prep} {remote}",
+                c.as_bytes().len()
+            ),
             Self::Ping { .. } => format!("{verb} ping {prep} {remote}"),
             Self::Pong { .. } => format!("{verb} pong {prep} {remote}"),
             Self::Subscribe(Subscribe { .. }) => {
                 format!("{verb} subscription filter {prep} {remote}")
             }
+            Self::Search(Search { query }) => {
+                format!("{verb} search query {query:?} {prep} {remote}")
+            }
+            Self::SearchResults(SearchResults { query, results }) => format!(
+                "{verb} {} search result(s) for {query:?} {prep} {remote}",
+                results.len()
+            ),
+            Self::Signal(signal) => format!("{verb} signal {signal:?} {prep} {remote}"),
         };
         log::log!(target: "service", level, "{msg}");
     }
@@ -483,14 +639,24 @@ impl From<Announcement> for Message {
 impl fmt::Debug for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Hello(Hello {
+                version,
+                capabilities,
+            }) => write!(f, "Hello({version}, {capabilities})"),
             Self::Subscribe(Subscribe { since, until, .. }) => {
                 write!(f, "Subscribe({since}..{until})")
             }
             Self::Announcement(Announcement { node, message, .. }) => {
                 write!(f, "Announcement({node}, {message:?})")
             }
+            Self::Compressed(c) => write!(f, "Compressed({} bytes)", c.as_bytes().len()),
             Self::Ping(Ping { ponglen, zeroes }) => write!(f, "Ping({ponglen}, {zeroes:?})"),
             Self::Pong { zeroes } => write!(f, "Pong({zeroes:?})"),
+            Self::Search(Search { query }) => write!(f, "Search({query:?})"),
+            Self::SearchResults(SearchResults { query, results }) => {
+                write!(f, "SearchResults({query:?}, {} result(s))", results.len())
+            }
+            Self::Signal(signal) => write!(f, "Signal({signal:?})"),
         }
     }
 }
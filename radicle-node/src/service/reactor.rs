@@ -1,9 +1,12 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
+use fastrand::Rng;
 use log::*;
 
+use crate::node::Capabilities;
 use crate::prelude::*;
 use crate::service::session::Session;
+use crate::service::tracking::FetchLimits;
 use crate::service::Link;
 use crate::storage::Namespaces;
 
@@ -26,6 +29,8 @@ pub enum Io {
         remote: NodeId,
         /// Namespaces being fetched.
         namespaces: Namespaces,
+        /// Limits on the data accepted.
+        limits: FetchLimits,
     },
     /// Ask for a wakeup in a specified amount of time.
     Wakeup(LocalDuration),
@@ -53,7 +58,8 @@ impl Reactor {
         msg.log(log::Level::Debug, &remote.id, Link::Outbound);
         trace!(target: "service", "Write {:?} to {}", &msg, remote);
 
-        self.io.push_back(Io::Write(remote.id, vec![msg]));
+        self.io
+            .push_back(Io::Write(remote.id, vec![Self::compress(remote, msg)]));
     }
 
     pub fn write_all(&mut self, remote: &Session, msgs: impl IntoIterator<Item = Message>) {
@@ -70,18 +76,39 @@ impl Reactor {
             );
             msg.log(log::Level::Debug, &remote.id, Link::Outbound);
         }
+        let msgs = msgs
+            .into_iter()
+            .map(|msg| Self::compress(remote, msg))
+            .collect();
+
         self.io.push_back(Io::Write(remote.id, msgs));
     }
 
+    /// Compress a message for the given peer, if the session allows it.
+    fn compress(remote: &Session, msg: Message) -> Message {
+        if remote.capabilities().has(Capabilities::ZSTD) {
+            msg.compressed()
+        } else {
+            msg
+        }
+    }
+
     pub fn wakeup(&mut self, after: LocalDuration) {
         self.io.push_back(Io::Wakeup(after));
     }
 
-    pub fn fetch(&mut self, remote: &mut Session, rid: Id, namespaces: Namespaces) {
+    pub fn fetch(
+        &mut self,
+        remote: &mut Session,
+        rid: Id,
+        namespaces: Namespaces,
+        limits: FetchLimits,
+    ) {
         self.io.push_back(Io::Fetch {
             rid,
             namespaces,
             remote: remote.id,
+            limits,
         });
     }
 
@@ -97,23 +124,45 @@ impl Reactor {
         }
     }
 
-    /// Relay a message to interested peers.
-    pub fn relay<'a>(&mut self, ann: Announcement, peers: impl IntoIterator<Item = &'a Session>) {
-        if let AnnouncementMessage::Refs(msg) = &ann.message {
+    /// Relay a message to interested peers. If a fanout is given, the message is relayed
+    /// to at most that many of them, chosen at random. Returns the peers the message was
+    /// relayed to.
+    pub fn relay<'a>(
+        &mut self,
+        ann: Announcement,
+        peers: impl IntoIterator<Item = &'a Session>,
+        fanout: Option<usize>,
+        deprioritized: &HashSet<NodeId>,
+        rng: &Rng,
+    ) -> Vec<NodeId> {
+        let mut peers = if let AnnouncementMessage::Refs(msg) = &ann.message {
             let id = msg.rid;
-            let peers = peers.into_iter().filter(|p| {
-                if let Some(subscribe) = &p.subscribe {
-                    subscribe.filter.contains(&id)
-                } else {
-                    // If the peer did not send us a `subscribe` message, we don'the
-                    // relay any messages to them.
-                    false
-                }
-            });
-            self.broadcast(ann, peers);
+            peers
+                .into_iter()
+                .filter(|p| {
+                    if let Some(subscribe) = &p.subscribe {
+                        subscribe.filter.contains(&id)
+                    } else {
+                        // If the peer did not send us a `subscribe` message, we don'the
+                        // relay any messages to them.
+                        false
+                    }
+                })
+                .collect::<Vec<_>>()
         } else {
-            self.broadcast(ann, peers);
+            peers.into_iter().collect::<Vec<_>>()
+        };
+
+        if let Some(fanout) = fanout {
+            rng.shuffle(&mut peers);
+            // Relay to peers in good standing first. The sort is stable, so they stay shuffled.
+            peers.sort_by_key(|p| deprioritized.contains(&p.id));
+            peers.truncate(fanout);
         }
+        let relayed = peers.iter().map(|p| p.id).collect();
+        self.broadcast(ann, peers);
+
+        relayed
     }
 
     #[cfg(any(test, feature = "test"))]
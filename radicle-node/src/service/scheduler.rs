@@ -0,0 +1,231 @@
+//! Fetch scheduling.
+//!
+//! Fetches are scheduled across all sessions: at most [`Limits::max_fetches`] fetches run at
+//! once, and at most [`Limits::fetch_concurrency`] per peer. A repository is only fetched from
+//! one peer at a time, and fetches requested by the user, eg. with `rad sync`, are started
+//! before fetches triggered by gossip.
+//!
+//! [`Limits::max_fetches`]: super::config::Limits::max_fetches
+//! [`Limits::fetch_concurrency`]: super::config::Limits::fetch_concurrency
+use std::collections::{HashMap, VecDeque};
+
+use crate::identity::Id;
+use crate::prelude::NodeId;
+
+/// Priority of a fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Fetch triggered by gossip, or by the node's own maintenance.
+    Background,
+    /// Fetch requested by the user.
+    Interactive,
+}
+
+/// Return value of [`Scheduler::schedule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// The fetch can be started.
+    Ready,
+    /// The fetch was queued, and will be returned by [`Scheduler::dequeue`] once it can start.
+    Queued,
+    /// We are already fetching the given repository from this peer, or it is already queued.
+    AlreadyFetching,
+}
+
+/// Schedules fetches.
+#[derive(Debug)]
+pub struct Scheduler {
+    /// Maximum number of concurrent fetches.
+    max_fetches: usize,
+    /// Maximum number of concurrent fetches per peer.
+    max_per_peer: usize,
+    /// Ongoing fetches, by repository.
+    ongoing: HashMap<Id, NodeId>,
+    /// Queued user-requested fetches.
+    interactive: VecDeque<(Id, NodeId)>,
+    /// Queued background fetches.
+    background: VecDeque<(Id, NodeId)>,
+}
+
+impl Scheduler {
+    /// Create a new scheduler with the given limits.
+    pub fn new(max_fetches: usize, max_per_peer: usize) -> Self {
+        Self {
+            max_fetches: max_fetches.max(1),
+            max_per_peer: max_per_peer.max(1),
+            ongoing: HashMap::new(),
+            interactive: VecDeque::new(),
+            background: VecDeque::new(),
+        }
+    }
+
+    /// Change the limits. Ongoing fetches beyond the new limits are left to complete.
+    pub fn set_limits(&mut self, max_fetches: usize, max_per_peer: usize) {
+        self.max_fetches = max_fetches.max(1);
+        self.max_per_peer = max_per_peer.max(1);
+    }
+
+    /// Schedule a fetch of `rid` from `from`. If the fetch can't start right away, it is
+    /// queued. Queuing a fetch that is already queued with a lower priority raises its
+    /// priority.
+    pub fn schedule(&mut self, rid: Id, from: NodeId, priority: Priority) -> Schedule {
+        if self.ongoing.get(&rid) == Some(&from) {
+            return Schedule::AlreadyFetching;
+        }
+        let entry = (rid, from);
+
+        if self.interactive.contains(&entry) {
+            return Schedule::AlreadyFetching;
+        }
+        if self.background.contains(&entry) {
+            if priority == Priority::Interactive {
+                self.background.retain(|e| e != &entry);
+                self.interactive.push_back(entry);
+            }
+            return Schedule::AlreadyFetching;
+        }
+        if self.is_ready(&rid, &from) {
+            self.ongoing.insert(rid, from);
+
+            return Schedule::Ready;
+        }
+        match priority {
+            Priority::Interactive => self.interactive.push_back(entry),
+            Priority::Background => self.background.push_back(entry),
+        }
+        Schedule::Queued
+    }
+
+    /// Mark the fetch of `rid` from `from` as done, freeing its slot.
+    pub fn fetched(&mut self, rid: &Id, from: &NodeId) {
+        if self.ongoing.get(rid) == Some(from) {
+            self.ongoing.remove(rid);
+        }
+    }
+
+    /// Get the next queued fetch that can start, if any. The fetch is marked as ongoing.
+    pub fn dequeue(&mut self) -> Option<(Id, NodeId)> {
+        for queue in [Priority::Interactive, Priority::Background] {
+            let ix = self
+                .queue(queue)
+                .iter()
+                .position(|(rid, from)| self.is_ready(rid, from));
+
+            if let Some((rid, from)) = ix.and_then(|ix| self.queue_mut(queue).remove(ix)) {
+                self.ongoing.insert(rid, from);
+
+                return Some((rid, from));
+            }
+        }
+        None
+    }
+
+    /// Drop the queued fetches from a peer, eg. because it disconnected. Returns the
+    /// repositories whose fetches were dropped from the queue.
+    ///
+    /// Ongoing fetches from the peer keep their slot until [`Scheduler::fetched`] is called,
+    /// once the worker running the fetch is done with it.
+    pub fn disconnected(&mut self, nid: &NodeId) -> Vec<Id> {
+        let mut dropped = Vec::new();
+        for queue in [&mut self.interactive, &mut self.background] {
+            queue.retain(|(rid, from)| {
+                if from == nid {
+                    dropped.push(*rid);
+                    return false;
+                }
+                true
+            });
+        }
+        dropped
+    }
+
+    /// Number of ongoing fetches.
+    pub fn ongoing(&self) -> usize {
+        self.ongoing.len()
+    }
+
+    /// Number of queued fetches.
+    pub fn queued(&self) -> usize {
+        self.interactive.len() + self.background.len()
+    }
+
+    /// Whether a fetch of `rid` from `from` can start now.
+    fn is_ready(&self, rid: &Id, from: &NodeId) -> bool {
+        self.ongoing.len() < self.max_fetches
+            && !self.ongoing.contains_key(rid)
+            && self.ongoing.values().filter(|n| *n == from).count() < self.max_per_peer
+    }
+
+    fn queue(&self, priority: Priority) -> &VecDeque<(Id, NodeId)> {
+        match priority {
+            Priority::Interactive => &self.interactive,
+            Priority::Background => &self.background,
+        }
+    }
+
+    fn queue_mut(&mut self, priority: Priority) -> &mut VecDeque<(Id, NodeId)> {
+        match priority {
+            Priority::Interactive => &mut self.interactive,
+            Priority::Background => &mut self.background,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_schedule() {
+        let mut scheduler = Scheduler::new(2, 1);
+        let [rid1, rid2, rid3] = arbitrary::gen::<[Id; 3]>(1);
+        let [bob, eve] = arbitrary::gen::<[NodeId; 2]>(1);
+
+        assert_eq!(
+            scheduler.schedule(rid1, bob, Priority::Background),
+            Schedule::Ready
+        );
+        assert_eq!(
+            scheduler.schedule(rid1, bob, Priority::Background),
+            Schedule::AlreadyFetching
+        );
+        // A repository is only fetched from one peer at a time.
+        assert_eq!(
+            scheduler.schedule(rid1, eve, Priority::Background),
+            Schedule::Queued
+        );
+        // Only one fetch per peer.
+        assert_eq!(
+            scheduler.schedule(rid2, bob, Priority::Background),
+            Schedule::Queued
+        );
+        assert_eq!(
+            scheduler.schedule(rid3, bob, Priority::Interactive),
+            Schedule::Queued
+        );
+        assert_eq!(scheduler.dequeue(), None);
+
+        // User-requested fetches go first.
+        scheduler.fetched(&rid1, &bob);
+        assert_eq!(scheduler.dequeue(), Some((rid3, bob)));
+        assert_eq!(scheduler.dequeue(), Some((rid1, eve)));
+        assert_eq!(scheduler.dequeue(), None);
+        assert_eq!(scheduler.ongoing(), 2);
+        assert_eq!(scheduler.queued(), 1);
+
+        // Queued fetches from a peer that disconnected are dropped, but ongoing fetches keep
+        // their slot until they're done.
+        assert_eq!(scheduler.disconnected(&bob), vec![rid2]);
+        assert_eq!(scheduler.ongoing(), 2);
+        assert_eq!(scheduler.queued(), 0);
+        assert_eq!(
+            scheduler.schedule(rid2, eve, Priority::Background),
+            Schedule::Queued
+        );
+
+        scheduler.fetched(&rid3, &bob);
+        assert_eq!(scheduler.ongoing(), 1);
+        assert_eq!(scheduler.dequeue(), Some((rid2, eve)));
+    }
+}
@@ -1,10 +1,10 @@
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
 
-use crate::service::config::Limits;
+use crate::node::{Address, Capabilities};
 use crate::service::message;
-use crate::service::message::Message;
-use crate::service::{Id, LocalTime, NodeId, Reactor, Rng};
+use crate::service::message::{Hello, Message};
+use crate::service::{Id, LocalDuration, LocalTime, NodeId, Reactor, Rng};
 use crate::Link;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
@@ -13,7 +13,12 @@ pub enum PingState {
     /// The peer has not been sent a ping.
     None,
     /// A ping has been sent and is waiting on the peer's response.
-    AwaitingResponse(u16),
+    AwaitingResponse {
+        /// Length of the expected pong.
+        len: u16,
+        /// Time at which the ping was sent.
+        since: LocalTime,
+    },
     /// The peer was successfully pinged.
     Ok,
 }
@@ -65,8 +70,6 @@ impl fmt::Display for State {
 /// Return value of [`Session::fetch`].
 #[derive(Debug)]
 pub enum FetchResult {
-    /// Maximum concurrent fetches reached.
-    Queued,
     /// We are already fetching the given repo from this peer.
     AlreadyFetching,
     /// Ok, ready to fetch.
@@ -91,6 +94,12 @@ pub enum Error {
     /// The remote peer timed out.
     #[error("peer timed out")]
     Timeout,
+    /// The remote peer speaks a protocol version we don't support.
+    #[error("unsupported protocol version {0}")]
+    UnsupportedVersion(u8),
+    /// The remote peer is banned.
+    #[error("peer is banned")]
+    Banned,
 }
 
 impl Error {
@@ -101,8 +110,38 @@ impl Error {
             Self::ProtocolMismatch => true,
             Self::Misbehavior => false,
             Self::Timeout => true,
+            Self::UnsupportedVersion(_) => false,
+            Self::Banned => false,
         }
     }
+
+    /// Check whether this error is a protocol violation, which hurts the peer's reputation.
+    /// Invalid timestamps are usually caused by clock skew rather than malice, so they don't
+    /// count as violations.
+    pub fn is_violation(&self) -> bool {
+        matches!(self, Self::Misbehavior)
+    }
+}
+
+/// State of a disconnected session, kept for a while so that the session can be resumed
+/// when the peer reconnects. See [`Capabilities::RESUME`].
+#[derive(Debug, Clone)]
+pub struct Resumable {
+    /// Ticket we issued to the peer, which it presents to resume the session.
+    pub issued: Option<u64>,
+    /// Ticket the peer issued to us, which we present to resume the session.
+    pub received: Option<u64>,
+    /// The peer's subscription.
+    pub subscribe: Option<message::Subscribe>,
+    /// Time at which the peer disconnected.
+    pub since: LocalTime,
+}
+
+impl Resumable {
+    /// Whether the session can no longer be resumed.
+    pub fn is_expired(&self, now: LocalTime, window: LocalDuration) -> bool {
+        now - self.since >= window
+    }
 }
 
 /// A peer session. Each connected peer will have one session.
@@ -119,10 +158,29 @@ pub struct Session {
     pub state: State,
     /// Peer subscription.
     pub subscribe: Option<message::Subscribe>,
+    /// Protocol version and capabilities negotiated with the peer, once its
+    /// [`Message::Hello`] was received.
+    pub protocol: Option<Hello>,
+    /// Whether we sent our own [`Message::Hello`] to the peer. We only do so once we know
+    /// the peer understands it, ie. it sent us a hello or advertised the `HELLO` feature.
+    pub hello_sent: bool,
+    /// Ticket we issued to the peer in this session.
+    pub ticket: Option<u64>,
+    /// Ticket the peer issued to us in this session.
+    pub peer_ticket: Option<u64>,
+    /// State of the peer's previous session, if it can be resumed.
+    pub resumable: Option<Resumable>,
+    /// Whether we asked the peer to resume our previous session, and are holding back our
+    /// handshake until it accepts or declines.
+    pub resuming: bool,
     /// Last time a message was received from the peer.
     pub last_active: LocalTime,
-    /// Fetch queue.
-    pub queue: VecDeque<Id>,
+    /// Last time the peer searched our inventory, used to limit how often it can.
+    pub last_search: Option<LocalTime>,
+    /// Other known addresses of the peer, dialed in turn while we're trying to connect.
+    dials: VecDeque<Address>,
+    /// When to dial the next address in the queue.
+    dial_at: LocalTime,
 
     /// Connection attempts. For persistent peers, Tracks
     /// how many times we've attempted to connect. We reset this to zero
@@ -130,8 +188,6 @@ pub struct Session {
     attempts: usize,
     /// Source of entropy.
     rng: Rng,
-    /// Protocol limits.
-    limits: Limits,
 }
 
 impl fmt::Display for Session {
@@ -154,28 +210,29 @@ impl fmt::Display for Session {
 }
 
 impl Session {
-    pub fn outbound(id: NodeId, persistent: bool, rng: Rng, limits: Limits) -> Self {
+    pub fn outbound(id: NodeId, persistent: bool, rng: Rng) -> Self {
         Self {
             id,
             state: State::Initial,
             link: Link::Outbound,
             subscribe: None,
+            protocol: None,
+            hello_sent: false,
+            ticket: None,
+            peer_ticket: None,
+            resumable: None,
+            resuming: false,
             persistent,
             last_active: LocalTime::default(),
-            queue: VecDeque::default(),
+            last_search: None,
+            dials: VecDeque::default(),
+            dial_at: LocalTime::default(),
             attempts: 1,
             rng,
-            limits,
         }
     }
 
-    pub fn inbound(
-        id: NodeId,
-        persistent: bool,
-        rng: Rng,
-        time: LocalTime,
-        limits: Limits,
-    ) -> Self {
+    pub fn inbound(id: NodeId, persistent: bool, rng: Rng, time: LocalTime) -> Self {
         Self {
             id,
             state: State::Connected {
@@ -185,12 +242,19 @@ impl Session {
             },
             link: Link::Inbound,
             subscribe: None,
+            protocol: None,
+            hello_sent: false,
+            ticket: None,
+            peer_ticket: None,
+            resumable: None,
+            resuming: false,
             persistent,
             last_active: LocalTime::default(),
-            queue: VecDeque::default(),
+            last_search: None,
+            dials: VecDeque::default(),
+            dial_at: LocalTime::default(),
             attempts: 0,
             rng,
-            limits,
         }
     }
 
@@ -210,38 +274,67 @@ impl Session {
         matches!(self.state, State::Initial)
     }
 
+    /// Whether we're still trying to establish an outbound connection.
+    pub fn is_dialing(&self) -> bool {
+        matches!(self.state, State::Initial | State::Attempted)
+    }
+
+    /// Whether there are other addresses left to dial.
+    pub fn has_dials(&self) -> bool {
+        !self.dials.is_empty()
+    }
+
+    /// Whether the next address should be dialed.
+    pub fn is_dial_due(&self, now: LocalTime) -> bool {
+        self.is_dialing() && self.has_dials() && now >= self.dial_at
+    }
+
+    /// Set the other addresses to dial, starting at the given time.
+    pub fn dials(&mut self, addrs: impl IntoIterator<Item = Address>, at: LocalTime) {
+        self.dials = addrs.into_iter().collect();
+        self.dial_at = at;
+    }
+
+    /// Return the next address to dial, and schedule the one after it for `now + delay`.
+    pub fn next_dial(&mut self, now: LocalTime, delay: LocalDuration) -> Option<Address> {
+        if !self.is_dialing() {
+            return None;
+        }
+        let addr = self.dials.pop_front()?;
+        self.dial_at = now + delay;
+
+        Some(addr)
+    }
+
     pub fn attempts(&self) -> usize {
         self.attempts
     }
 
+    /// Capabilities that can be used on this session. Until the peer's [`Message::Hello`]
+    /// is received, no capabilities can be used.
+    pub fn capabilities(&self) -> Capabilities {
+        self.protocol
+            .map(|p| p.capabilities)
+            .unwrap_or(Capabilities::NONE)
+    }
+
     pub fn fetch(&mut self, rid: Id) -> FetchResult {
         if let State::Connected { fetching, .. } = &mut self.state {
-            if fetching.contains(&rid) || self.queue.contains(&rid) {
+            if !fetching.insert(rid) {
                 return FetchResult::AlreadyFetching;
             }
-            if fetching.len() >= self.limits.fetch_concurrency {
-                self.queue.push_back(rid);
-                return FetchResult::Queued;
-            }
-            fetching.insert(rid);
-
             FetchResult::Ready
         } else {
             FetchResult::NotConnected
         }
     }
 
-    pub fn fetched(&mut self, rid: Id) -> Option<Id> {
+    pub fn fetched(&mut self, rid: Id) {
         if let State::Connected { fetching, .. } = &mut self.state {
             if !fetching.remove(&rid) {
                 log::error!(target: "service", "Fetched unknown repository {rid}");
             }
-            // Dequeue the next fetch, if any.
-            if let Some(rid) = self.queue.pop_front() {
-                return Some(rid);
-            }
         }
-        None
     }
 
     pub fn to_attempted(&mut self) {
@@ -259,6 +352,7 @@ impl Session {
             "Can only transition to 'connected' state from 'connecting' state"
         );
         self.attempts = 0;
+        self.dials.clear();
         self.state = State::Connected {
             since,
             ping: PingState::default(),
@@ -270,6 +364,26 @@ impl Session {
     /// that was requested.
     pub fn to_disconnected(&mut self, since: LocalTime, retry_at: LocalTime) {
         self.state = State::Disconnected { since, retry_at };
+        self.protocol = None;
+        self.hello_sent = false;
+        self.ticket = None;
+        self.peer_ticket = None;
+        self.resumable = None;
+        self.resuming = false;
+        self.dials.clear();
+    }
+
+    /// State to keep once the session is disconnected, if it can be resumed.
+    pub fn to_resumable(&self, since: LocalTime) -> Option<Resumable> {
+        if !self.is_connected() || (self.ticket.is_none() && self.peer_ticket.is_none()) {
+            return None;
+        }
+        Some(Resumable {
+            issued: self.ticket,
+            received: self.peer_ticket,
+            subscribe: self.subscribe.clone(),
+            since,
+        })
     }
 
     /// Return to initial state from disconnected state. This state transition
@@ -290,10 +404,13 @@ impl Session {
         }
     }
 
-    pub fn ping(&mut self, reactor: &mut Reactor) -> Result<(), Error> {
+    pub fn ping(&mut self, now: LocalTime, reactor: &mut Reactor) -> Result<(), Error> {
         if let State::Connected { ping, .. } = &mut self.state {
             let msg = message::Ping::new(&mut self.rng);
-            *ping = PingState::AwaitingResponse(msg.ponglen);
+            *ping = PingState::AwaitingResponse {
+                len: msg.ponglen,
+                since: now,
+            };
 
             reactor.write(self, Message::Ping(msg));
         }
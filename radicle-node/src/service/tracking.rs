@@ -13,7 +13,7 @@ use crate::service::NodeId;
 
 pub use crate::node::tracking::store::Config as Store;
 pub use crate::node::tracking::store::Error;
-pub use crate::node::tracking::{Alias, Node, Policy, Repo, Scope};
+pub use crate::node::tracking::{Alias, FetchLimits, Node, Policy, Repo, Scope};
 
 #[derive(Debug, Error)]
 pub enum NamespacesError {
@@ -62,6 +62,12 @@ impl Config {
         }
     }
 
+    /// Set the default policy and scope.
+    pub fn set_defaults(&mut self, policy: Policy, scope: Scope) {
+        self.policy = policy;
+        self.scope = scope;
+    }
+
     /// Check if a repository is tracked.
     pub fn is_repo_tracked(&self, id: &Id) -> Result<bool, Error> {
         self.repo_policy(id)
@@ -81,6 +87,7 @@ impl Config {
             id: *id,
             alias: None,
             policy: self.policy,
+            expiry: None,
         }))
     }
 
@@ -89,8 +96,9 @@ impl Config {
     pub fn repo_policy(&self, id: &Id) -> Result<Repo, Error> {
         Ok(self.store.repo_policy(id)?.unwrap_or(Repo {
             id: *id,
-            scope: self.scope,
+            scope: self.scope.clone(),
             policy: self.policy,
+            limits: FetchLimits::default(),
         }))
     }
 
@@ -108,33 +116,37 @@ impl Config {
                 error!(target: "service", "Attempted to fetch untracked repo {rid}");
                 Err(NamespacesError::BlockedPolicy { rid: *rid })
             }
-            Policy::Track => match entry.scope {
-                Scope::All => Ok(Namespaces::All),
-                Scope::Trusted => {
-                    let nodes = self
-                        .node_policies()
-                        .map_err(|err| FailedNodes { rid: *rid, err })?;
-                    let mut trusted: HashSet<_> = nodes
-                        .filter_map(|node| (node.policy == Policy::Track).then_some(node.id))
-                        .collect();
-
-                    if let Ok(repo) = storage.repository(*rid) {
-                        let delegates = repo
-                            .delegates()
-                            .map_err(|err| FailedDelegates { rid: *rid, err })?
-                            .map(PublicKey::from);
-                        trusted.extend(delegates);
-                    };
-                    if trusted.is_empty() {
-                        // Nb. returning All here because the
-                        // fetching logic will correctly determine
-                        // trusted and delegate remotes.
-                        Ok(Namespaces::All)
-                    } else {
-                        Ok(Namespaces::Trusted(trusted))
+            Policy::Track => {
+                let mut trusted: HashSet<_> = match entry.scope {
+                    Scope::All => return Ok(Namespaces::All),
+                    Scope::Trusted => {
+                        let nodes = self
+                            .node_policies()
+                            .map_err(|err| FailedNodes { rid: *rid, err })?;
+
+                        nodes
+                            .filter_map(|node| (node.policy == Policy::Track).then_some(node.id))
+                            .collect()
                     }
+                    Scope::Explicit(remotes) => remotes.into_iter().map(PublicKey::from).collect(),
+                };
+
+                if let Ok(repo) = storage.repository(*rid) {
+                    let delegates = repo
+                        .delegates()
+                        .map_err(|err| FailedDelegates { rid: *rid, err })?
+                        .map(PublicKey::from);
+                    trusted.extend(delegates);
+                };
+                if trusted.is_empty() {
+                    // Nb. returning All here because the
+                    // fetching logic will correctly determine
+                    // trusted and delegate remotes.
+                    Ok(Namespaces::All)
+                } else {
+                    Ok(Namespaces::Trusted(trusted))
                 }
-            },
+            }
         }
     }
 }
@@ -2,11 +2,13 @@ use bloomy::BloomFilter;
 use qcheck::Arbitrary;
 
 use crate::crypto;
+use crate::identity::project::Project;
+use crate::node::Signal;
 use crate::prelude::{BoundedVec, Id, NodeId, Timestamp};
 use crate::service::filter::{Filter, FILTER_SIZE_L, FILTER_SIZE_M, FILTER_SIZE_S};
 use crate::service::message::{
-    Announcement, InventoryAnnouncement, Message, NodeAnnouncement, Ping, RefsAnnouncement,
-    Subscribe, ZeroBytes,
+    Announcement, Compressed, Hello, InventoryAnnouncement, Message, NodeAnnouncement, Ping,
+    RefsAnnouncement, Search, SearchResults, Subscribe, ZeroBytes,
 };
 use crate::wire::MessageType;
 
@@ -36,6 +38,11 @@ impl Arbitrary for Message {
                 MessageType::Subscribe,
                 MessageType::Ping,
                 MessageType::Pong,
+                MessageType::Hello,
+                MessageType::Compressed,
+                MessageType::Search,
+                MessageType::SearchResults,
+                MessageType::Signal,
             ])
             .unwrap();
 
@@ -93,6 +100,24 @@ impl Arbitrary for Message {
             MessageType::Pong => Self::Pong {
                 zeroes: ZeroBytes::new(u16::arbitrary(g).min(Ping::MAX_PONG_ZEROES)),
             },
+            MessageType::Compressed => {
+                let mut rng = fastrand::Rng::with_seed(u64::arbitrary(g));
+                let msg = Self::Ping(Ping::new(&mut rng));
+
+                Self::Compressed(Compressed::compress(&msg).unwrap())
+            }
+            MessageType::Hello => Self::Hello(Hello {
+                version: u8::arbitrary(g),
+                capabilities: u64::arbitrary(g).into(),
+            }),
+            MessageType::Search => Self::Search(Search {
+                query: Project::arbitrary(g).name().to_owned(),
+            }),
+            MessageType::SearchResults => Self::SearchResults(SearchResults {
+                query: Project::arbitrary(g).name().to_owned(),
+                results: BoundedVec::arbitrary(g),
+            }),
+            MessageType::Signal => Self::Signal(Signal::arbitrary(g)),
         }
     }
 }
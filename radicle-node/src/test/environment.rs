@@ -26,7 +26,7 @@ use radicle::storage::{ReadStorage as _, WriteRepository};
 use radicle::test::fixtures;
 use radicle::Storage;
 
-use crate::node::NodeId;
+use crate::node::{ConnectOptions, NodeId};
 use crate::service::Event;
 use crate::storage::git::transport;
 use crate::{runtime, runtime::Handle, service, Runtime};
@@ -104,6 +104,7 @@ impl Environment {
             storage,
             keystore,
             public_key: keypair.pk.into(),
+            config: Default::default(),
         }
     }
 }
@@ -147,7 +148,9 @@ impl<G: Signer + cyphernet::Ecdh> NodeHandle<G> {
         let local_events = self.handle.events();
         let remote_events = remote.handle.events();
 
-        self.handle.connect(remote.id, remote.addr.into()).unwrap();
+        self.handle
+            .connect(remote.id, remote.addr.into(), ConnectOptions::default())
+            .unwrap();
 
         local_events
             .iter()
@@ -303,6 +306,7 @@ impl<G: cyphernet::Ecdh<Pk = NodeId> + Signer + Clone> Node<G> {
             listen,
             proxy,
             daemon,
+            None,
             signals,
             self.signer.clone(),
         )
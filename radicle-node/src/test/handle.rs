@@ -3,7 +3,8 @@ use std::sync::{Arc, Mutex};
 use std::{io, time};
 
 use crate::identity::Id;
-use crate::node::{Event, FetchResult, Seeds};
+use crate::node::trace::Trace;
+use crate::node::{Address, ConnectOptions, Event, FetchResult, Seeds, Signal, Timestamp};
 use crate::runtime::HandleError;
 use crate::service::NodeId;
 use crate::service::{self, tracking};
@@ -23,7 +24,12 @@ impl radicle::node::Handle for Handle {
         true
     }
 
-    fn connect(&mut self, _node: NodeId, _addr: radicle::node::Address) -> Result<(), Self::Error> {
+    fn connect(
+        &mut self,
+        _node: NodeId,
+        _addr: radicle::node::Address,
+        _opts: ConnectOptions,
+    ) -> Result<(), Self::Error> {
         unimplemented!();
     }
 
@@ -31,7 +37,12 @@ impl radicle::node::Handle for Handle {
         unimplemented!();
     }
 
-    fn fetch(&mut self, _id: Id, _from: NodeId) -> Result<FetchResult, Self::Error> {
+    fn fetch(
+        &mut self,
+        _id: Id,
+        _from: NodeId,
+        _timeout: time::Duration,
+    ) -> Result<FetchResult, Self::Error> {
         Ok(FetchResult::Success {
             updated: vec![],
             namespaces: HashSet::new(),
@@ -46,7 +57,12 @@ impl radicle::node::Handle for Handle {
         Ok(self.tracking_repos.lock().unwrap().remove(&id))
     }
 
-    fn track_node(&mut self, id: NodeId, _alias: Option<String>) -> Result<bool, Self::Error> {
+    fn track_node(
+        &mut self,
+        id: NodeId,
+        _alias: Option<String>,
+        _expiry: Option<Timestamp>,
+    ) -> Result<bool, Self::Error> {
         Ok(self.tracking_nodes.lock().unwrap().insert(id))
     }
 
@@ -75,6 +91,42 @@ impl radicle::node::Handle for Handle {
         unimplemented!()
     }
 
+    fn search(&mut self, _query: String) -> Result<usize, Self::Error> {
+        unimplemented!()
+    }
+
+    fn signal(&mut self, _to: NodeId, _signal: Signal) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn trace_gossip(&mut self, _enable: bool) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn reload_config(&mut self) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn addresses(&self, _id: NodeId) -> Result<Vec<Address>, Self::Error> {
+        unimplemented!()
+    }
+
+    fn ban(&mut self, _id: NodeId) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn disconnect(&mut self, _id: NodeId) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn unban(&mut self, _id: NodeId) -> Result<bool, Self::Error> {
+        unimplemented!()
+    }
+
+    fn gossip_traces(&self) -> Result<Option<Vec<Trace>>, Self::Error> {
+        unimplemented!()
+    }
+
     fn sessions(&self) -> Result<Self::Sessions, Self::Error> {
         unimplemented!();
     }
@@ -14,7 +14,10 @@ use crate::crypto::test::signer::MockSigner;
 use crate::crypto::Signer;
 use crate::identity::Id;
 use crate::node;
+use crate::node::inventory;
+use crate::node::metrics;
 use crate::node::routing;
+use crate::node::ConnectOptions;
 use crate::prelude::*;
 use crate::runtime::Emitter;
 use crate::service;
@@ -152,7 +155,10 @@ where
     ) -> Self {
         let routing = routing::Table::memory().unwrap();
         let tracking = tracking::Store::memory().unwrap();
-        let tracking = tracking::Config::new(config.policy, config.scope, tracking);
+        let tracking = tracking::Config::new(config.policy, config.scope.clone(), tracking);
+        let inventory = inventory::Cache::memory().unwrap();
+        let metrics = metrics::Table::memory().unwrap();
+        let reputation = address::reputation::Table::memory().unwrap();
         let tempdir = tempfile::tempdir().unwrap();
         let id = *config.signer.public_key();
         let ip = ip.into();
@@ -169,6 +175,9 @@ where
             storage,
             config.addrs,
             tracking,
+            inventory,
+            metrics,
+            reputation,
             config.signer,
             config.rng.clone(),
             emitter,
@@ -257,12 +266,20 @@ where
     }
 
     pub fn node_announcement(&self) -> Message {
+        self.node_announcement_with(if self.service.config().observer {
+            node::Features::OBSERVER
+        } else {
+            node::Features::SEED
+        })
+    }
+
+    pub fn node_announcement_with(&self, features: node::Features) -> Message {
         let mut alias = [0u8; 32];
         alias[..self.name.len()].copy_from_slice(self.name.as_bytes());
 
         Message::node(
             NodeAnnouncement {
-                features: node::Features::SEED,
+                features,
                 timestamp: self.timestamp(),
                 alias,
                 addresses: Some(net::SocketAddr::from((self.ip, node::DEFAULT_PORT)).into()).into(),
@@ -325,8 +342,11 @@ where
         let remote_addr = simulator::Peer::<T, H>::addr(peer);
 
         self.initialize();
-        self.service
-            .command(Command::Connect(remote_id, remote_addr.clone()));
+        self.service.command(Command::Connect(
+            remote_id,
+            remote_addr.clone(),
+            ConnectOptions::default(),
+        ));
 
         self.outbox()
             .find(|o| matches!(o, Io::Connect { .. }))
@@ -385,6 +405,7 @@ where
                 rid,
                 remote,
                 namespaces,
+                ..
             } = io
             {
                 Some((rid, remote, namespaces))
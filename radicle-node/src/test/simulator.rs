@@ -436,6 +436,23 @@ impl<S: WriteStorage + 'static, G: Signer> Simulation<S, G> {
         !self.is_done()
     }
 
+    /// Drop an established connection between two nodes, as if `node` had closed it.
+    /// Does nothing if the nodes aren't connected.
+    pub fn disconnect(&mut self, node: &NodeId, remote: &NodeId) {
+        if !self.connections.contains(&(*node, *remote)) {
+            return;
+        }
+        self.schedule(
+            node,
+            Io::Disconnect(
+                *remote,
+                DisconnectReason::Connection(Arc::new(io::Error::from(
+                    io::ErrorKind::ConnectionReset,
+                ))),
+            ),
+        );
+    }
+
     /// Process a service output event from a node.
     pub fn schedule(&mut self, node: &NodeId, out: Io) {
         let node = *node;
@@ -612,6 +629,7 @@ impl<S: WriteStorage + 'static, G: Signer> Simulation<S, G> {
                 rid,
                 remote,
                 namespaces,
+                ..
             } => {
                 log::info!(
                     target: "sim",
@@ -1,8 +1,9 @@
 mod e2e;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::default::*;
 use std::io;
+use std::net;
 use std::sync::Arc;
 use std::time;
 
@@ -10,12 +11,17 @@ use crossbeam_channel as chan;
 use netservices::LinkDirection as Link;
 use radicle::storage::ReadRepository;
 
+use crate::address;
+use crate::address::reputation;
+use crate::address::Store as _;
 use crate::collections::{HashMap, HashSet};
 use crate::crypto::test::signer::MockSigner;
 use crate::identity::Id;
 use crate::node;
+use crate::node::ConnectOptions;
 use crate::prelude::*;
 use crate::prelude::{LocalDuration, Timestamp};
+use crate::profile;
 use crate::service::config::*;
 use crate::service::filter::Filter;
 use crate::service::message::*;
@@ -37,6 +43,7 @@ use crate::test::simulator::{Peer as _, Simulation};
 use crate::test::storage::MockStorage;
 use crate::wire::Decode;
 use crate::wire::Encode;
+use crate::worker;
 use crate::LocalTime;
 use crate::{git, identity, rad, runtime, service, test};
 
@@ -116,9 +123,21 @@ fn test_redundant_connect() {
     let mut alice = Peer::new("alice", [8, 8, 8, 8]);
     let bob = Peer::new("bob", [9, 9, 9, 9]);
 
-    alice.command(Command::Connect(bob.id(), bob.address()));
-    alice.command(Command::Connect(bob.id(), bob.address()));
-    alice.command(Command::Connect(bob.id(), bob.address()));
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
 
     // Only one connection attempt is made.
     assert_matches!(
@@ -140,7 +159,11 @@ fn test_connection_kept_alive() {
     )
     .initialize([&mut alice, &mut bob]);
 
-    alice.command(service::Command::Connect(bob.id(), bob.address()));
+    alice.command(service::Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
     sim.run_while([&mut alice, &mut bob], |s| !s.is_settled());
     assert_eq!(1, alice.sessions().connected().count(), "bob connects");
 
@@ -407,230 +430,1043 @@ fn test_inventory_relay_bad_timestamp() {
         Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::InvalidTimestamp(t))))
         if addr == bob.id() && t == timestamp
     );
+    // Bob's clock is probably skewed, which doesn't hurt his reputation.
+    assert_eq!(
+        alice.reputation().get(&bob.id()).unwrap(),
+        reputation::Reputation::default()
+    );
 }
 
 #[test]
-fn test_announcement_rebroadcast() {
-    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+fn test_inventory_relay_max_time_delta() {
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        MockStorage::empty(),
+        peer::Config {
+            config: Config {
+                limits: Limits {
+                    max_time_delta: LocalDuration::from_mins(3 * 60),
+                    ..Limits::default()
+                },
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
     let bob = Peer::new("bob", [8, 8, 8, 8]);
     let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let two_hours = 3600 * 1000 * 2;
+    let timestamp = alice.timestamp() + two_hours;
 
     alice.connect_to(&bob);
-
-    let received = test::gossip::messages(6, alice.local_time(), MAX_TIME_DELTA);
-    for msg in received.iter().cloned() {
-        alice.receive(bob.id(), msg);
-    }
-
     alice.connect_from(&eve);
     alice.receive(
-        eve.id(),
-        Message::Subscribe(Subscribe {
-            filter: Filter::default(),
-            since: Timestamp::MIN,
-            until: Timestamp::MAX,
-        }),
+        bob.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: BoundedVec::new(),
+                timestamp,
+            },
+            bob.signer(),
+        ),
+    );
+    assert_matches!(
+        alice.messages(eve.id()).next(),
+        Some(Message::Announcement(Announcement { node, .. })) if node == bob.id(),
+        "Bob's inventory is within the configured time delta, and is relayed"
     );
-
-    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
-    let received = received.into_iter().collect::<BTreeSet<_>>();
-
-    assert_eq!(relayed, received);
 }
 
 #[test]
-fn test_announcement_rebroadcast_duplicates() {
-    let mut carol = Peer::new("carol", [4, 4, 4, 4]);
+fn test_announcement_timestamps_increase() {
     let mut alice = Peer::new("alice", [7, 7, 7, 7]);
     let bob = Peer::new("bob", [8, 8, 8, 8]);
-    let eve = Peer::new("eve", [9, 9, 9, 9]);
-    let rids = arbitrary::set::<Id>(3..=3);
 
     alice.connect_to(&bob);
+    alice.command(Command::AnnounceInventory);
+    alice.command(Command::AnnounceInventory);
+
+    // All announcements are made without any time passing, yet each one is newer than the last,
+    // so that Bob doesn't discard them as stale.
+    let timestamps = alice
+        .messages(bob.id())
+        .filter_map(|m| match m {
+            Message::Announcement(Announcement {
+                message: AnnouncementMessage::Inventory(InventoryAnnouncement { timestamp, .. }),
+                ..
+            }) => Some(timestamp),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
 
-    // These are not expected to be relayed.
-    let stale = {
-        let mut anns = BTreeSet::new();
-
-        for _ in 0..5 {
-            carol.elapse(LocalDuration::from_mins(1));
-
-            anns.insert(carol.inventory_announcement());
-            anns.insert(carol.node_announcement());
-        }
-        anns
-    };
-
-    // These are expected to be relayed.
-    let expected = {
-        let mut anns = BTreeSet::new();
-
-        carol.elapse(LocalDuration::from_mins(1));
-        anns.insert(carol.inventory_announcement());
-        anns.insert(carol.node_announcement());
-
-        for rid in rids {
-            alice.track_repo(&rid, tracking::Scope::All).unwrap();
-            anns.insert(carol.refs_announcement(rid));
-            anns.insert(bob.refs_announcement(rid));
-        }
-        anns
-    };
+    assert_eq!(timestamps.len(), 3);
+    assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+}
 
-    let mut all = stale.iter().chain(expected.iter()).collect::<Vec<_>>();
-    fastrand::shuffle(&mut all);
+#[test]
+fn test_hello_negotiation() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
 
-    // Alice receives all messages out of order.
-    for ann in all {
-        alice.receive(bob.id, ann.clone());
-    }
+    alice.connect_to(&bob);
+    assert_eq!(alice.sessions().get(&bob.id()).unwrap().protocol, None);
 
-    // Alice relays just the expected ones back to Eve.
-    alice.connect_from(&eve);
+    // Bob speaks a newer protocol, with capabilities we don't know about.
     alice.receive(
-        eve.id(),
-        Message::Subscribe(Subscribe {
-            filter: Filter::default(),
-            since: Timestamp::MIN,
-            until: Timestamp::MAX,
+        bob.id(),
+        Message::Hello(Hello {
+            version: PROTOCOL_VERSION + 1,
+            capabilities: node::Capabilities::from(u64::MAX),
         }),
     );
+    let sess = alice.sessions().get(&bob.id()).unwrap();
+    assert_eq!(sess.protocol, Some(Hello::default()));
+    assert_eq!(sess.capabilities(), CAPABILITIES);
 
-    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
+    // Alice didn't know Bob understands hellos, so she replies with hers.
+    assert_matches!(
+        alice.messages(bob.id()).next(),
+        Some(Message::Hello(h)) if h == Hello::default()
+    );
 
-    assert_eq!(relayed.len(), 8);
-    assert_eq!(relayed, expected);
+    // The protocol can only be negotiated once per session.
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    assert_matches!(
+        alice.outbox().next(),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::Misbehavior)))
+        if addr == bob.id()
+    );
 }
 
 #[test]
-fn test_announcement_rebroadcast_timestamp_filtered() {
+fn test_hello_not_sent_to_old_peers() {
     let mut alice = Peer::new("alice", [7, 7, 7, 7]);
     let bob = Peer::new("bob", [8, 8, 8, 8]);
     let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let is_hello = |m: &Message| matches!(m, Message::Hello(_));
 
-    alice.connect_to(&bob);
+    // Bob predates hellos, so we don't send him ours.
+    alice.initialize();
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
+    alice.attempted(bob.id(), &bob.address());
+    alice.connected(bob.id(), Link::Outbound);
+    assert!(!alice.messages(bob.id()).any(|m| is_hello(&m)));
 
-    let delta = LocalDuration::from_mins(10);
-    let first = test::gossip::messages(3, alice.local_time() - delta, LocalDuration::from_secs(0));
-    let second = test::gossip::messages(3, alice.local_time(), LocalDuration::from_secs(0));
-    let third = test::gossip::messages(3, alice.local_time() + delta, LocalDuration::from_secs(0));
+    // Without a hello, his messages are processed as before.
+    alice.receive(bob.id(), Message::Subscribe(Subscribe::all()));
+    let sess = alice.sessions().get(&bob.id()).unwrap();
+    assert!(sess.subscribe.is_some());
+    assert_eq!(sess.protocol, None);
+    assert_eq!(sess.capabilities(), node::Capabilities::NONE);
+    assert!(!alice.outbox().any(|o| matches!(o, Io::Disconnect(..))));
 
-    // Alice receives three batches of messages.
-    for msg in first
-        .iter()
-        .chain(second.iter())
-        .chain(third.iter())
-        .cloned()
-    {
-        alice.receive(bob.id(), msg);
-    }
+    // Once he advertises support for it, we send ours.
+    alice.receive(
+        bob.id(),
+        bob.node_announcement_with(node::Features::SEED.with(node::Features::HELLO)),
+    );
+    assert_matches!(
+        alice.messages(bob.id()).find(is_hello),
+        Some(Message::Hello(h)) if h == Hello::default()
+    );
 
-    // Eve subscribes to messages within the period of the second batch only.
-    alice.connect_from(&eve);
+    // Eve is known to support hellos, so we send ours as soon as we connect.
     alice.receive(
+        bob.id(),
+        eve.node_announcement_with(node::Features::SEED.with(node::Features::HELLO)),
+    );
+    alice.command(Command::Connect(
         eve.id(),
-        Message::Subscribe(Subscribe {
-            filter: Filter::default(),
-            since: alice.local_time().as_millis(),
-            until: (alice.local_time() + delta).as_millis(),
-        }),
+        eve.address(),
+        ConnectOptions::default(),
+    ));
+    alice.attempted(eve.id(), &eve.address());
+    alice.connected(eve.id(), Link::Outbound);
+    assert_matches!(
+        alice.messages(eve.id()).next(),
+        Some(Message::Hello(h)) if h == Hello::default()
     );
-
-    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
-    let second = second.into_iter().collect::<BTreeSet<_>>();
-
-    assert_eq!(relayed.len(), second.len());
-    assert_eq!(relayed, second);
 }
 
 #[test]
-fn test_announcement_relay() {
+fn test_hello_unsupported_version() {
     let mut alice = Peer::new("alice", [7, 7, 7, 7]);
-    let mut bob = Peer::new("bob", [8, 8, 8, 8]);
-    let mut eve = Peer::new("eve", [9, 9, 9, 9]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
 
     alice.connect_to(&bob);
-    alice.connect_to(&eve);
-    alice.receive(bob.id(), bob.inventory_announcement());
-
+    alice.receive(
+        bob.id(),
+        Message::Hello(Hello {
+            version: MIN_PROTOCOL_VERSION - 1,
+            capabilities: node::Capabilities::NONE,
+        }),
+    );
     assert_matches!(
-        alice.messages(eve.id()).next(),
-        Some(Message::Announcement(_))
+        alice.outbox().next(),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::UnsupportedVersion(v))))
+        if addr == bob.id() && v == MIN_PROTOCOL_VERSION - 1
     );
+}
 
-    alice.receive(bob.id(), bob.inventory_announcement());
-    assert!(
-        alice.messages(eve.id()).next().is_none(),
-        "Another inventory with the same timestamp is ignored"
-    );
+#[test]
+fn test_session_resumption() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let reason =
+        DisconnectReason::Connection(Arc::new(io::Error::from(io::ErrorKind::ConnectionReset)));
+    let ticket_of = |msgs: &[Message]| {
+        msgs.iter().find_map(|m| match m {
+            Message::Signal(node::Signal::Ticket { ticket }) => Some(*ticket),
+            _ => None,
+        })
+    };
 
-    bob.elapse(LocalDuration::from_mins(1));
-    alice.receive(bob.id(), bob.inventory_announcement());
-    assert_matches!(
-        alice.messages(eve.id()).next(),
-        Some(Message::Announcement(_)),
-        "Another inventory with a fresher timestamp is relayed"
+    // Alice and Bob negotiate resumption, and issue each other a ticket.
+    alice.connect_from(&bob);
+    alice.receive(
+        bob.id(),
+        bob.node_announcement_with(node::Features::SEED.with(node::Features::HELLO)),
+    );
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.receive(bob.id(), Message::Subscribe(Subscribe::all()));
+    alice.receive(
+        bob.id(),
+        Message::Signal(node::Signal::Ticket { ticket: 42 }),
     );
 
-    alice.receive(bob.id(), bob.node_announcement());
+    let msgs = alice.messages(bob.id()).collect::<Vec<_>>();
+    let ticket = ticket_of(&msgs).expect("alice issues bob a ticket");
+
+    // While Bob is away, Alice hears from Eve.
+    alice.disconnected(bob.id(), &reason);
+    alice.connect_to(&eve);
+    alice.receive(eve.id(), eve.node_announcement());
+
+    // When Bob reconnects, Alice asks him to resume their session, instead of starting a
+    // new one.
+    alice.connected(bob.id(), Link::Inbound);
     assert_matches!(
-        alice.messages(eve.id()).next(),
-        Some(Message::Announcement(_)),
-        "A node announcement with the same timestamp as the inventory is relayed"
+        alice.messages(bob.id()).collect::<Vec<_>>().as_slice(),
+        [
+            Message::Hello(_),
+            Message::Signal(node::Signal::Resume { ticket: 42 }),
+            Message::Announcement(Announcement {
+                message: AnnouncementMessage::Inventory(_),
+                ..
+            }),
+        ]
     );
 
-    alice.receive(bob.id(), bob.node_announcement());
-    assert!(alice.messages(eve.id()).next().is_none(), "Only once");
+    // Bob asks the same, so Alice restores his subscription, and sends him what he missed.
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.receive(bob.id(), Message::Signal(node::Signal::Resume { ticket }));
+    assert!(alice.sessions().get(&bob.id()).unwrap().subscribe.is_some());
 
-    alice.receive(eve.id(), eve.node_announcement());
+    let msgs = alice.messages(bob.id()).collect::<Vec<_>>();
+    let next = ticket_of(&msgs).expect("alice issues bob a new ticket");
+    assert!(msgs.iter().any(|m| matches!(
+        m,
+        Message::Announcement(Announcement {
+            node,
+            message: AnnouncementMessage::Node(_),
+            ..
+        }) if *node == eve.id()
+    )));
+    assert!(!msgs.iter().any(|m| matches!(
+        m,
+        Message::Subscribe(_) | Message::Signal(node::Signal::ResumeDeclined)
+    )));
+
+    // Tickets can only be used once.
+    alice.receive(bob.id(), Message::Signal(node::Signal::Resume { ticket }));
     assert_matches!(
         alice.messages(bob.id()).next(),
-        Some(Message::Announcement(_)),
-        "A node announcement from Eve is relayed to Bob"
-    );
-    assert!(
-        alice.messages(eve.id()).next().is_none(),
-        "But not back to Eve"
+        Some(Message::Signal(node::Signal::ResumeDeclined))
     );
 
-    eve.elapse(LocalDuration::from_mins(1));
-    alice.receive(bob.id(), eve.node_announcement());
-    assert!(
-        alice.messages(bob.id()).next().is_none(),
-        "Bob already know about this message, since he sent it"
-    );
-    assert!(
-        alice.messages(eve.id()).next().is_none(),
-        "Eve already know about this message, since she signed it"
+    // If Bob declines to resume, Alice starts a new session.
+    alice.receive(bob.id(), Message::Signal(node::Signal::ResumeDeclined));
+    assert!(alice
+        .messages(bob.id())
+        .any(|m| matches!(m, Message::Subscribe(_))));
+
+    // Sessions can only be resumed for a while.
+    alice.disconnected(bob.id(), &reason);
+    alice.clock_mut().elapse(RESUME_WINDOW);
+    alice.connected(bob.id(), Link::Inbound);
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.receive(
+        bob.id(),
+        Message::Signal(node::Signal::Resume { ticket: next }),
     );
+    assert!(alice
+        .messages(bob.id())
+        .any(|m| matches!(m, Message::Signal(node::Signal::ResumeDeclined))));
 }
 
 #[test]
-fn test_refs_announcement_relay() {
-    let tmp = tempfile::tempdir().unwrap();
-    let mut alice = Peer::config(
-        "alice",
-        [7, 7, 7, 7],
-        Storage::open(tmp.path().join("alice")).unwrap(),
-        peer::Config::default(),
-    );
-    let eve = Peer::config(
-        "eve",
-        [8, 8, 8, 8],
-        Storage::open(tmp.path().join("eve")).unwrap(),
-        peer::Config::default(),
-    );
+fn test_compressed_message() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let subscribe = Compressed::compress(&Message::Subscribe(Subscribe::all())).unwrap();
 
-    let bob = {
-        let mut rng = fastrand::Rng::new();
-        let signer = MockSigner::new(&mut rng);
-        let storage = fixtures::storage(tmp.path().join("bob"), &signer).unwrap();
+    // Bob negotiated compression, so his compressed messages are processed.
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.receive(bob.id(), Message::Compressed(subscribe.clone()));
+    assert!(alice.sessions().get(&bob.id()).unwrap().subscribe.is_some());
 
-        Peer::config(
-            "bob",
-            [9, 9, 9, 9],
-            storage,
-            peer::Config {
+    // Eve didn't, so she isn't allowed to send compressed messages.
+    alice.connect_to(&eve);
+    alice.receive(eve.id(), Message::Compressed(subscribe));
+    assert_matches!(
+        alice.outbox().find(|o| matches!(o, Io::Disconnect(..))),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::Misbehavior)))
+        if addr == eve.id()
+    );
+}
+
+#[test]
+fn test_persistent_peer_connection_failure() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let refused =
+        DisconnectReason::Dial(Arc::new(io::Error::from(io::ErrorKind::ConnectionRefused)));
+
+    alice.initialize();
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
+    alice.command(Command::Connect(
+        eve.id(),
+        eve.address(),
+        ConnectOptions {
+            persistent: true,
+            ..ConnectOptions::default()
+        },
+    ));
+    alice.attempted(bob.id(), &bob.address());
+    alice.attempted(eve.id(), &eve.address());
+    alice.disconnected(bob.id(), &refused);
+    alice.disconnected(eve.id(), &refused);
+
+    // Failing to connect to a peer counts against it, unless it's a persistent peer.
+    assert_eq!(alice.reputation().get(&bob.id()).unwrap().failures, 1);
+    assert_eq!(alice.reputation().get(&eve.id()).unwrap().failures, 0);
+}
+
+#[test]
+fn test_misbehaving_peer_banned() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let subscribe = Compressed::compress(&Message::Subscribe(Subscribe::all())).unwrap();
+
+    for _ in 0..reputation::BAN_THRESHOLD / reputation::Offense::Violation.penalty() {
+        assert!(!alice.reputation().is_banned(&eve.id()).unwrap());
+
+        alice.connect_to(&eve);
+        alice.receive(eve.id(), Message::Compressed(subscribe.clone()));
+        alice.disconnected(
+            eve.id(),
+            &DisconnectReason::Session(session::Error::Misbehavior),
+        );
+    }
+    assert!(alice.reputation().is_banned(&eve.id()).unwrap());
+
+    // Eve is banned, so we don't connect to her, nor accept her connections.
+    alice.command(Command::Connect(
+        eve.id(),
+        eve.address(),
+        ConnectOptions::default(),
+    ));
+    assert!(!alice.outbox().any(|o| matches!(o, Io::Connect(..))));
+
+    alice.connected(eve.id(), Link::Inbound);
+    assert_matches!(
+        alice.outbox().find(|o| matches!(o, Io::Disconnect(..))),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::Banned)))
+        if addr == eve.id()
+    );
+}
+
+#[test]
+fn test_ban_command() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.connect_to(&bob);
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Ban(bob.id(), sender));
+    assert!(receiver.recv().unwrap());
+    assert_matches!(
+        alice.outbox().find(|o| matches!(o, Io::Disconnect(..))),
+        Some(Io::Disconnect(addr, DisconnectReason::Session(session::Error::Banned)))
+        if addr == bob.id()
+    );
+    alice.disconnected(bob.id(), &DisconnectReason::Session(session::Error::Banned));
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Unban(bob.id(), sender));
+    assert!(receiver.recv().unwrap());
+    assert_eq!(
+        alice.reputation().get(&bob.id()).unwrap(),
+        reputation::Reputation::default()
+    );
+    alice.connect_to(&bob);
+}
+
+#[test]
+fn test_fetch_outcomes_recorded() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let rid = arbitrary::gen::<Id>(1);
+
+    alice.connect_to(&bob);
+    alice.transferred(
+        bob.id(),
+        worker::FetchStats {
+            received: 1024,
+            elapsed: time::Duration::from_secs(1),
+        },
+    );
+    alice.fetched(rid, bob.id(), Ok((vec![], Default::default())));
+    alice.fetched(
+        rid,
+        bob.id(),
+        Err(worker::FetchError::Io(io::ErrorKind::TimedOut.into())),
+    );
+    // Fetches refused by our own policy aren't the remote's fault.
+    alice.fetched(
+        rid,
+        bob.id(),
+        Err(worker::FetchError::Refused(worker::policy::Reason::Denied)),
+    );
+
+    let metrics = alice.metrics_mut().get(&bob.id()).unwrap();
+    assert_eq!(metrics.fetches, 1);
+    assert_eq!(metrics.failures, 1);
+    // Fetch outcomes don't change the reputation itself.
+    assert_eq!(
+        alice.reputation().get(&bob.id()).unwrap(),
+        reputation::Reputation::default()
+    );
+}
+
+#[test]
+fn test_search() {
+    let temp = tempfile::tempdir().unwrap();
+    let storage = Storage::open(temp.path()).unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let mut bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let acme = alice.project("acme", "Acme Corporation");
+    let heartwood = alice.project("heartwood", "Radicle Heartwood Protocol & Stack");
+    let (_, head) = alice.storage().repository(acme).unwrap().head().unwrap();
+
+    // Alice answers searches with the matching repositories of her inventory.
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.receive(
+        bob.id(),
+        Message::Search(Search {
+            query: String::from("CORP"),
+        }),
+    );
+    let results = alice
+        .messages(bob.id())
+        .find_map(|m| match m {
+            Message::SearchResults(results) => Some(results),
+            _ => None,
+        })
+        .expect("alice answers the search");
+
+    assert_eq!(results.query, "CORP");
+    assert_eq!(
+        results.results.as_slice(),
+        &[node::SearchResult {
+            rid: acme,
+            name: String::from("acme"),
+            description: String::from("Acme Corporation"),
+            head,
+            delegates: vec![Did::from(alice.id())],
+        }]
+    );
+
+    // Searches that come too soon after the previous one are ignored.
+    alice.receive(
+        bob.id(),
+        Message::Search(Search {
+            query: String::from("acme"),
+        }),
+    );
+    assert!(!alice
+        .messages(bob.id())
+        .any(|m| matches!(m, Message::SearchResults(_))));
+    alice.elapse(MIN_SEARCH_INTERVAL);
+
+    // The empty query matches every repository, which is how inventories are listed.
+    alice.receive(
+        bob.id(),
+        Message::Search(Search {
+            query: String::new(),
+        }),
+    );
+    let results = alice
+        .messages(bob.id())
+        .find_map(|m| match m {
+            Message::SearchResults(results) => Some(results),
+            _ => None,
+        })
+        .expect("alice answers the search");
+    let mut rids = results.results.iter().map(|r| r.rid).collect::<Vec<_>>();
+    let mut expected = vec![acme, heartwood];
+    rids.sort();
+    expected.sort();
+
+    assert_eq!(rids, expected);
+
+    // Bob only asks peers that support searching, and only reports the results it asked for.
+    let events = bob.events();
+    let (sender, receiver) = chan::bounded(1);
+    bob.connect_to(&alice);
+    bob.receive(alice.id(), Message::Hello(Hello::default()));
+    bob.connect_to(&eve);
+    bob.command(Command::Search(String::from("CORP"), sender));
+    assert_eq!(receiver.recv().unwrap(), 1);
+
+    bob.receive(
+        eve.id(),
+        Message::SearchResults(SearchResults {
+            query: String::from("CORP"),
+            results: BoundedVec::new(),
+        }),
+    );
+    bob.receive(alice.id(), Message::SearchResults(results));
+    events
+        .wait(
+            |e| {
+                if let Event::SearchResults {
+                    remote,
+                    query,
+                    results,
+                } = e
+                {
+                    assert_eq!(remote, &alice.id());
+                    assert_eq!(query, "CORP");
+                    assert_eq!(results[0].rid, acme);
+
+                    true
+                } else {
+                    false
+                }
+            },
+            time::Duration::from_secs(3),
+        )
+        .unwrap();
+
+    // Results that come after we gave up on a search aren't reported.
+    let (sender, receiver) = chan::bounded(1);
+    bob.command(Command::Search(String::from("acme"), sender));
+    assert_eq!(receiver.recv().unwrap(), 1);
+
+    bob.elapse(SEARCH_TIMEOUT);
+    bob.receive(
+        alice.id(),
+        Message::SearchResults(SearchResults {
+            query: String::from("acme"),
+            results: BoundedVec::new(),
+        }),
+    );
+    assert!(!events
+        .try_iter()
+        .any(|e| matches!(e, Event::SearchResults { .. })));
+}
+
+#[test]
+fn test_reconfigure() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let rid = arbitrary::gen::<Id>(1);
+    let mut config = profile::Config::default();
+
+    assert!(!alice.tracking().is_repo_tracked(&rid).unwrap());
+
+    config.persistent_peers.push(node::ConnectAddress {
+        id: bob.id(),
+        addr: bob.address(),
+    });
+    config.node.tracking_policy = Some(tracking::Policy::Track);
+    config.node.limits.max_fetches = Some(1);
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Reconfigure(Box::new(config.clone()), sender));
+    assert!(receiver.recv().unwrap());
+    assert!(alice.tracking().is_repo_tracked(&rid).unwrap());
+    assert!(alice.config().is_persistent(&bob.id()));
+    assert_eq!(alice.config().limits.max_fetches, 1);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        Some(Io::Connect(a, _)) if a == bob.id()
+    );
+
+    // Applying the same configuration again doesn't change anything.
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Reconfigure(Box::new(config.clone()), sender));
+    assert!(!receiver.recv().unwrap());
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        None
+    );
+
+    // Unset settings return to their defaults.
+    config.node = profile::NodeConfig::default();
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Reconfigure(Box::new(config), sender));
+    assert!(receiver.recv().unwrap());
+    assert!(!alice.tracking().is_repo_tracked(&rid).unwrap());
+    assert!(alice.config().is_persistent(&bob.id()));
+    assert_eq!(alice.config().limits, Limits::default());
+}
+
+#[test]
+fn test_reconfigure_overrides() {
+    let mut overrides = profile::NodeConfig::default();
+    overrides.limits.max_fetches = Some(2);
+
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        MockStorage::empty(),
+        peer::Config {
+            config: Config {
+                overrides,
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let mut config = profile::Config::default();
+    config.node.limits.max_fetches = Some(1);
+    config.node.limits.fetch_concurrency = Some(3);
+
+    // Settings given on the command line take precedence over the profile's.
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Reconfigure(Box::new(config), sender));
+    assert!(receiver.recv().unwrap());
+    assert_eq!(alice.config().limits.max_fetches, 2);
+    assert_eq!(alice.config().limits.fetch_concurrency, 3);
+}
+
+#[test]
+fn test_signal() {
+    let temp = tempfile::tempdir().unwrap();
+    let storage = Storage::open(temp.path()).unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let acme = alice.project("acme", "");
+    let rid = arbitrary::gen::<Id>(1);
+    let events = alice.events();
+
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), Message::Hello(Hello::default()));
+    alice.connect_to(&eve);
+
+    // Peers that can resume sessions are issued a ticket.
+    assert_matches!(
+        alice
+            .messages(bob.id())
+            .find(|m| matches!(m, Message::Signal(_))),
+        Some(Message::Signal(node::Signal::Ticket { .. }))
+    );
+
+    // Signals are only sent to peers that accept them.
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Signal(
+        bob.id(),
+        node::Signal::Invite { rid },
+        sender,
+    ));
+    assert!(receiver.recv().unwrap());
+    assert_matches!(
+        alice.messages(bob.id()).find(|m| matches!(m, Message::Signal(_))),
+        Some(Message::Signal(node::Signal::Invite { rid: r })) if r == rid
+    );
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::Signal(
+        eve.id(),
+        node::Signal::Invite { rid },
+        sender,
+    ));
+    assert!(!receiver.recv().unwrap());
+    assert_matches!(
+        alice
+            .messages(eve.id())
+            .find(|m| matches!(m, Message::Signal(_))),
+        None
+    );
+
+    // Fetch hints are followed for tracked repositories.
+    alice.receive(bob.id(), Message::Signal(node::Signal::FetchHint { rid }));
+    assert_matches!(alice.fetches().next(), None);
+
+    alice.track_repo(&rid, tracking::Scope::All).unwrap();
+    alice.receive(bob.id(), Message::Signal(node::Signal::FetchHint { rid }));
+    assert_matches!(alice.fetches().next(), Some((r, nid, _)) if r == rid && nid == bob.id());
+
+    // Sync requests are declined for repositories we don't track.
+    let untracked = arbitrary::gen::<Id>(1);
+    alice.receive(
+        bob.id(),
+        Message::Signal(node::Signal::SyncRequest { rid: untracked }),
+    );
+    assert_matches!(
+        alice.messages(bob.id()).find(|m| matches!(m, Message::Signal(_))),
+        Some(Message::Signal(node::Signal::SyncDeclined { rid: r })) if r == untracked
+    );
+
+    // Otherwise, we fetch from the peer, and reply once the fetch is done.
+    alice.fetched(rid, bob.id(), Ok((vec![], Default::default())));
+    alice.receive(bob.id(), Message::Signal(node::Signal::SyncRequest { rid }));
+    assert_matches!(alice.fetches().next(), Some((r, nid, _)) if r == rid && nid == bob.id());
+
+    alice.fetched(rid, bob.id(), Ok((vec![], Default::default())));
+    assert_matches!(
+        alice.messages(bob.id()).find(|m| matches!(m, Message::Signal(_))),
+        // We don't have the peer's signed refs.
+        Some(Message::Signal(node::Signal::SyncDeclined { rid: r })) if r == rid
+    );
+
+    // Peers telling us they have our signed refs are in sync with us.
+    let sigrefs = alice
+        .storage()
+        .repository(acme)
+        .unwrap()
+        .reference_oid(&alice.id(), &radicle::storage::refs::SIGREFS_BRANCH)
+        .unwrap();
+    alice.receive(
+        bob.id(),
+        Message::Signal(node::Signal::Synced { rid: acme, sigrefs }),
+    );
+    events
+        .wait(
+            |e| matches!(e, Event::RefsSynced { remote, rid } if remote == &bob.id() && rid == &acme),
+            time::Duration::from_secs(3),
+        )
+        .unwrap();
+
+    // Received signals are passed on to subscribers.
+    events
+        .wait(
+            |e| {
+                matches!(
+                    e,
+                    Event::Signal { remote, signal: node::Signal::Synced { .. } }
+                    if remote == &bob.id()
+                )
+            },
+            time::Duration::from_secs(3),
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_announcement_rebroadcast() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    alice.connect_to(&bob);
+
+    let received = test::gossip::messages(6, alice.local_time(), MAX_TIME_DELTA);
+    for msg in received.iter().cloned() {
+        alice.receive(bob.id(), msg);
+    }
+
+    alice.connect_from(&eve);
+    alice.receive(
+        eve.id(),
+        Message::Subscribe(Subscribe {
+            filter: Filter::default(),
+            since: Timestamp::MIN,
+            until: Timestamp::MAX,
+        }),
+    );
+
+    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
+    let received = received.into_iter().collect::<BTreeSet<_>>();
+
+    assert_eq!(relayed, received);
+}
+
+#[test]
+fn test_announcement_rebroadcast_duplicates() {
+    let mut carol = Peer::new("carol", [4, 4, 4, 4]);
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let rids = arbitrary::set::<Id>(3..=3);
+
+    alice.connect_to(&bob);
+
+    // These are not expected to be relayed.
+    let stale = {
+        let mut anns = BTreeSet::new();
+
+        for _ in 0..5 {
+            carol.elapse(LocalDuration::from_mins(1));
+
+            anns.insert(carol.inventory_announcement());
+            anns.insert(carol.node_announcement());
+        }
+        anns
+    };
+
+    // These are expected to be relayed.
+    let expected = {
+        let mut anns = BTreeSet::new();
+
+        carol.elapse(LocalDuration::from_mins(1));
+        anns.insert(carol.inventory_announcement());
+        anns.insert(carol.node_announcement());
+
+        for rid in rids {
+            alice.track_repo(&rid, tracking::Scope::All).unwrap();
+            anns.insert(carol.refs_announcement(rid));
+            anns.insert(bob.refs_announcement(rid));
+        }
+        anns
+    };
+
+    let mut all = stale.iter().chain(expected.iter()).collect::<Vec<_>>();
+    fastrand::shuffle(&mut all);
+
+    // Alice receives all messages out of order.
+    for ann in all {
+        alice.receive(bob.id, ann.clone());
+    }
+
+    // Alice relays just the expected ones back to Eve.
+    alice.connect_from(&eve);
+    alice.receive(
+        eve.id(),
+        Message::Subscribe(Subscribe {
+            filter: Filter::default(),
+            since: Timestamp::MIN,
+            until: Timestamp::MAX,
+        }),
+    );
+
+    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
+
+    assert_eq!(relayed.len(), 8);
+    assert_eq!(relayed, expected);
+}
+
+#[test]
+fn test_announcement_rebroadcast_timestamp_filtered() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    alice.connect_to(&bob);
+
+    let delta = LocalDuration::from_mins(10);
+    let first = test::gossip::messages(3, alice.local_time() - delta, LocalDuration::from_secs(0));
+    let second = test::gossip::messages(3, alice.local_time(), LocalDuration::from_secs(0));
+    let third = test::gossip::messages(3, alice.local_time() + delta, LocalDuration::from_secs(0));
+
+    // Alice receives three batches of messages.
+    for msg in first
+        .iter()
+        .chain(second.iter())
+        .chain(third.iter())
+        .cloned()
+    {
+        alice.receive(bob.id(), msg);
+    }
+
+    // Eve subscribes to messages within the period of the second batch only.
+    alice.connect_from(&eve);
+    alice.receive(
+        eve.id(),
+        Message::Subscribe(Subscribe {
+            filter: Filter::default(),
+            since: alice.local_time().as_millis(),
+            until: (alice.local_time() + delta).as_millis(),
+        }),
+    );
+
+    let relayed = alice.messages(eve.id()).collect::<BTreeSet<_>>();
+    let second = second.into_iter().collect::<BTreeSet<_>>();
+
+    assert_eq!(relayed.len(), second.len());
+    assert_eq!(relayed, second);
+}
+
+#[test]
+fn test_announcement_relay() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let mut bob = Peer::new("bob", [8, 8, 8, 8]);
+    let mut eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+    alice.receive(bob.id(), bob.inventory_announcement());
+
+    assert_matches!(
+        alice.messages(eve.id()).next(),
+        Some(Message::Announcement(_))
+    );
+
+    alice.receive(bob.id(), bob.inventory_announcement());
+    assert!(
+        alice.messages(eve.id()).next().is_none(),
+        "Another inventory with the same timestamp is ignored"
+    );
+
+    bob.elapse(LocalDuration::from_mins(1));
+    alice.receive(bob.id(), bob.inventory_announcement());
+    assert_matches!(
+        alice.messages(eve.id()).next(),
+        Some(Message::Announcement(_)),
+        "Another inventory with a fresher timestamp is relayed"
+    );
+
+    alice.receive(bob.id(), bob.node_announcement());
+    assert_matches!(
+        alice.messages(eve.id()).next(),
+        Some(Message::Announcement(_)),
+        "A node announcement with the same timestamp as the inventory is relayed"
+    );
+
+    alice.receive(bob.id(), bob.node_announcement());
+    assert!(alice.messages(eve.id()).next().is_none(), "Only once");
+
+    alice.receive(eve.id(), eve.node_announcement());
+    assert_matches!(
+        alice.messages(bob.id()).next(),
+        Some(Message::Announcement(_)),
+        "A node announcement from Eve is relayed to Bob"
+    );
+    assert!(
+        alice.messages(eve.id()).next().is_none(),
+        "But not back to Eve"
+    );
+
+    eve.elapse(LocalDuration::from_mins(1));
+    alice.receive(bob.id(), eve.node_announcement());
+    assert!(
+        alice.messages(bob.id()).next().is_none(),
+        "Bob already know about this message, since he sent it"
+    );
+    assert!(
+        alice.messages(eve.id()).next().is_none(),
+        "Eve already know about this message, since she signed it"
+    );
+}
+
+#[test]
+fn test_announcement_relay_leaf() {
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        MockStorage::empty(),
+        peer::Config {
+            config: Config {
+                relay: false,
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+    alice.receive(bob.id(), bob.inventory_announcement());
+
+    assert!(
+        alice.messages(eve.id()).next().is_none(),
+        "Leaf nodes don't relay announcements"
+    );
+}
+
+#[test]
+fn test_announcement_relay_fanout() {
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        MockStorage::empty(),
+        peer::Config {
+            config: Config {
+                relay_fanout: Some(1),
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let carol = Peer::new("carol", [10, 10, 10, 10]);
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+    alice.connect_to(&carol);
+    alice.receive(bob.id(), bob.inventory_announcement());
+
+    let relayed = [eve.id(), carol.id()]
+        .into_iter()
+        .filter(|id| {
+            alice
+                .messages(*id)
+                .any(|m| matches!(m, Message::Announcement(ann) if ann.node == bob.id()))
+        })
+        .count();
+
+    assert_eq!(relayed, 1, "Announcements are relayed to at most one peer");
+}
+
+#[test]
+fn test_refs_announcement_relay() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        Storage::open(tmp.path().join("alice")).unwrap(),
+        peer::Config::default(),
+    );
+    let eve = Peer::config(
+        "eve",
+        [8, 8, 8, 8],
+        Storage::open(tmp.path().join("eve")).unwrap(),
+        peer::Config::default(),
+    );
+
+    let bob = {
+        let mut rng = fastrand::Rng::new();
+        let signer = MockSigner::new(&mut rng);
+        let storage = fixtures::storage(tmp.path().join("bob"), &signer).unwrap();
+
+        Peer::config(
+            "bob",
+            [9, 9, 9, 9],
+            storage,
+            peer::Config {
                 signer,
                 rng,
                 ..peer::Config::default()
@@ -715,6 +1551,88 @@ fn test_refs_announcement_fetch_trusted_no_inventory() {
     assert_matches!(alice.outbox().next(), Some(Io::Fetch { .. }));
 }
 
+#[test]
+fn test_observer_refuses_fetch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        Storage::open(tmp.path().join("alice")).unwrap(),
+        peer::Config {
+            config: Config {
+                observer: true,
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = {
+        let mut rng = fastrand::Rng::new();
+        let signer = MockSigner::new(&mut rng);
+        let storage = fixtures::storage(tmp.path().join("bob"), &signer).unwrap();
+
+        Peer::config(
+            "bob",
+            [9, 9, 9, 9],
+            storage,
+            peer::Config {
+                signer,
+                rng,
+                ..peer::Config::default()
+            },
+        )
+    };
+    let rid = bob.storage().inventory().unwrap()[0];
+
+    alice.track_repo(&rid, tracking::Scope::All).unwrap();
+    alice.connect_to(&bob);
+    alice.receive(bob.id(), bob.refs_announcement(rid));
+
+    assert!(
+        !alice.outbox().any(|io| matches!(io, Io::Fetch { .. })),
+        "Observers never fetch"
+    );
+}
+
+#[test]
+fn test_observer_routes_dropped() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::config(
+        "bob",
+        [8, 8, 8, 8],
+        MockStorage::empty(),
+        peer::Config {
+            config: Config {
+                observer: true,
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let rids = arbitrary::vec::<Id>(3);
+
+    alice.connect_to(&bob);
+    alice.receive(
+        bob.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: rids.clone().try_into().unwrap(),
+                timestamp: bob.timestamp(),
+            },
+            bob.signer(),
+        ),
+    );
+    for rid in &rids {
+        assert!(alice.routing().get(rid).unwrap().contains(&bob.id()));
+    }
+
+    // Once Bob advertises himself as an observer, fetches are no longer routed to him.
+    alice.receive(bob.id(), bob.node_announcement());
+    for rid in &rids {
+        assert!(!alice.routing().get(rid).unwrap().contains(&bob.id()));
+    }
+}
+
 /// Alice and Bob both have the same repo.
 ///
 /// First, Alice will not fetch from Bob's `RefsAnnouncement` as Alice does not
@@ -753,7 +1671,12 @@ fn test_refs_announcement_trusted() {
 
     // Alice starts to track Bob.
     let (sender, receiver) = chan::bounded(1);
-    alice.command(Command::TrackNode(bob.id, Some("bob".to_string()), sender));
+    alice.command(Command::TrackNode(
+        bob.id,
+        Some("bob".to_string()),
+        None,
+        sender,
+    ));
     let policy_change = receiver.recv().map_err(runtime::HandleError::from).unwrap();
     assert!(policy_change);
 
@@ -763,6 +1686,177 @@ fn test_refs_announcement_trusted() {
     assert_matches!(alice.outbox().next(), Some(Io::Fetch { .. }));
 }
 
+#[test]
+fn test_track_node_expiry() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let expiry = alice.local_time() + LocalDuration::from_mins(10);
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TrackNode(
+        bob.id,
+        None,
+        Some(expiry.as_millis()),
+        sender,
+    ));
+    assert!(receiver.recv().unwrap());
+
+    let tracked = |peer: &Peer<MockStorage, MockSigner>| {
+        peer.tracking()
+            .node_policies()
+            .unwrap()
+            .any(|node| node.id == bob.id)
+    };
+    assert!(tracked(&alice));
+
+    alice.elapse(LocalDuration::from_mins(5));
+    assert!(tracked(&alice));
+
+    alice.elapse(LocalDuration::from_mins(5));
+    assert!(!tracked(&alice), "The policy is dropped once it expires");
+}
+
+#[test]
+fn test_track_node_expiry_prune() {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = Storage::open(tmp.path().join("alice")).unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        storage,
+        peer::Config {
+            config: Config {
+                prune_expired: true,
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = MockSigner::default();
+    let acme = alice.project("acme", "");
+    let open = alice.project("open", "");
+    let (theirs, _, _, _) =
+        fixtures::project(tmp.path().join("theirs"), alice.storage(), &bob).unwrap();
+    let expiry = alice.local_time() + LocalDuration::from_mins(1);
+    let has_bob = |alice: &Peer<Storage, MockSigner>, rid: Id| {
+        alice
+            .storage()
+            .repository(rid)
+            .unwrap()
+            .remote_ids()
+            .unwrap()
+            .any(|r| r.unwrap() == *bob.public_key())
+    };
+
+    rad::fork(acme, &bob, alice.storage()).unwrap();
+    rad::fork(open, &bob, alice.storage()).unwrap();
+    alice.track_repo(&acme, tracking::Scope::Trusted).unwrap();
+    alice.track_repo(&open, tracking::Scope::All).unwrap();
+    alice.track_repo(&theirs, tracking::Scope::Trusted).unwrap();
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TrackNode(
+        *bob.public_key(),
+        None,
+        Some(expiry.as_millis()),
+        sender,
+    ));
+    assert!(receiver.recv().unwrap());
+    assert!(has_bob(&alice, acme));
+
+    alice.elapse(LocalDuration::from_mins(1));
+    assert!(!has_bob(&alice, acme), "Bob is pruned if untrusted");
+    assert!(has_bob(&alice, open), "Bob is kept where all are fetched");
+    assert!(has_bob(&alice, theirs), "Delegates are never pruned");
+}
+
+/// Change the visibility of a repository of which the peer is the only delegate.
+fn set_visibility(peer: &Peer<Storage, MockSigner>, rid: Id, visibility: identity::Visibility) {
+    use radicle::storage::WriteRepository as _;
+
+    let repo = peer.storage().repository(rid).unwrap();
+    let (_, doc) = repo.identity_doc().unwrap();
+    let mut doc = doc.verified().unwrap();
+    doc.set_visibility(&visibility).unwrap();
+
+    let (_, sig) = doc.sign(peer.signer()).unwrap();
+    doc.update(
+        &peer.id(),
+        "Change visibility",
+        &[(&peer.id(), sig)],
+        repo.raw(),
+    )
+    .unwrap();
+    repo.set_identity_head().unwrap();
+    repo.sign_refs(peer.signer()).unwrap();
+}
+
+#[test]
+fn test_visibility_reconcile() {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = Storage::open(tmp.path().join("alice")).unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let acme = alice.project("acme", "");
+    let private = identity::Visibility::Private {
+        allow: BTreeSet::new(),
+    };
+    let inventory = |alice: &mut Peer<Storage, MockSigner>| {
+        alice
+            .messages(bob.id())
+            .find_map(|m| match m {
+                Message::Announcement(Announcement {
+                    message: AnnouncementMessage::Inventory(InventoryAnnouncement { inventory, .. }),
+                    ..
+                }) => Some(inventory),
+                _ => None,
+            })
+            .expect("alice announces her inventory")
+    };
+
+    // The project is already private when Alice starts, so she doesn't announce it.
+    set_visibility(&alice, acme, private.clone());
+    alice.connect_to(&bob);
+    assert!(!inventory(&mut alice).contains(&acme));
+
+    // Once it is public, she does.
+    set_visibility(&alice, acme, identity::Visibility::Public);
+    alice.command(Command::AnnounceRefs(acme));
+    assert!(inventory(&mut alice).contains(&acme));
+
+    // And stops when it is private again.
+    set_visibility(&alice, acme, private);
+    alice.command(Command::AnnounceRefs(acme));
+    assert!(!inventory(&mut alice).contains(&acme));
+}
+
+#[test]
+fn test_worker_visibility() {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = Storage::open(tmp.path().join("alice")).unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = MockSigner::default();
+    let eve = MockSigner::default();
+    let acme = alice.project("acme", "");
+
+    assert!(worker::is_visible(alice.storage(), acme, bob.public_key()));
+
+    set_visibility(
+        &alice,
+        acme,
+        identity::Visibility::Private {
+            allow: BTreeSet::from([Did::from(*bob.public_key())]),
+        },
+    );
+    assert!(worker::is_visible(alice.storage(), acme, &alice.id()));
+    assert!(worker::is_visible(alice.storage(), acme, bob.public_key()));
+    assert!(!worker::is_visible(alice.storage(), acme, eve.public_key()));
+
+    // Repositories we don't have are never visible.
+    let unknown = arbitrary::gen::<Id>(1);
+    assert!(!worker::is_visible(alice.storage(), unknown, &alice.id()));
+}
+
 #[test]
 fn test_refs_announcement_no_subscribe() {
     let storage = arbitrary::nonempty_storage(1);
@@ -876,6 +1970,68 @@ fn test_inventory_relay() {
     );
 }
 
+#[test]
+fn test_gossip_trace() {
+    // Topology is eve <-> alice <-> bob
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let inv = BoundedVec::try_from(arbitrary::vec(1)).unwrap();
+    let now = LocalTime::now().as_millis();
+    let announcement = Message::inventory(
+        InventoryAnnouncement {
+            inventory: inv,
+            timestamp: now,
+        },
+        bob.signer(),
+    );
+
+    alice.connect_to(&bob);
+    alice.connect_from(&eve);
+    alice.receive(bob.id(), announcement.clone());
+    assert!(alice.traces().is_none(), "Tracing is disabled by default");
+
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TraceGossip(true, sender));
+    assert!(receiver.recv().unwrap());
+
+    // Relayed announcements are traced, along with the peers they were relayed to.
+    alice.receive(
+        bob.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: BoundedVec::try_from(arbitrary::vec(1)).unwrap(),
+                timestamp: now + 1,
+            },
+            bob.signer(),
+        ),
+    );
+    // Stale announcements are traced too, with the reason they weren't relayed.
+    alice.receive(bob.id(), announcement);
+
+    let traces = alice.traces().unwrap().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(traces.len(), 2);
+    assert_eq!(traces[0].kind, node::trace::Kind::Inventory);
+    assert_eq!(traces[0].origin, bob.id());
+    assert_eq!(traces[0].relayer, bob.id());
+    assert_eq!(
+        traces[0].verdict,
+        node::trace::Verdict::Relayed { to: vec![eve.id()] }
+    );
+    assert_eq!(
+        traces[1].verdict,
+        node::trace::Verdict::Dropped {
+            reason: String::from("stale")
+        }
+    );
+
+    // Disabling tracing drops the traces.
+    let (sender, receiver) = chan::bounded(1);
+    alice.command(Command::TraceGossip(false, sender));
+    assert!(receiver.recv().unwrap());
+    assert!(alice.traces().is_none());
+}
+
 #[test]
 fn test_persistent_peer_reconnect_attempt() {
     let mut bob = Peer::new("bob", [8, 8, 8, 8]);
@@ -900,34 +2056,164 @@ fn test_persistent_peer_reconnect_attempt() {
     )
     .initialize([&mut alice, &mut bob, &mut eve]);
 
-    sim.run_while([&mut alice, &mut bob, &mut eve], |s| !s.is_settled());
+    sim.run_while([&mut alice, &mut bob, &mut eve], |s| !s.is_settled());
+
+    let ips = alice
+        .sessions()
+        .connected()
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    assert!(ips.contains(&bob.id()));
+    assert!(ips.contains(&eve.id()));
+
+    // ... Negotiated ...
+    //
+    // Now let's disconnect a peer.
+
+    // A non-transient disconnect, such as one due to peer misbehavior will still trigger a
+    // a reconnection, since this is a persistent peer.
+    let reason = DisconnectReason::Session(session::Error::Misbehavior);
+
+    for _ in 0..3 {
+        alice.disconnected(bob.id(), &reason);
+        alice.elapse(service::MAX_RECONNECTION_DELTA);
+        alice
+            .outbox()
+            .find(|io| matches!(io, Io::Connect(a, _) if a == &bob.id()))
+            .unwrap();
+
+        alice.attempted(bob.id(), &bob.address());
+    }
+}
+
+#[test]
+fn test_persistent_peer_connect_command() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.initialize();
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions {
+            persistent: true,
+            ..ConnectOptions::default()
+        },
+    ));
+    alice
+        .outbox()
+        .find(|io| matches!(io, Io::Connect(a, _) if a == &bob.id()))
+        .unwrap();
+    alice.attempted(bob.id(), &bob.address());
+
+    assert!(alice.sessions().get(&bob.id()).unwrap().persistent);
+
+    // Bob is now a persistent peer, so we try to reconnect when the connection drops.
+    alice.disconnected(
+        bob.id(),
+        &DisconnectReason::Session(session::Error::Misbehavior),
+    );
+    alice.elapse(service::MAX_RECONNECTION_DELTA);
+    alice
+        .outbox()
+        .find(|io| matches!(io, Io::Connect(a, _) if a == &bob.id()))
+        .unwrap();
+}
+
+#[test]
+fn test_dial_stalled_connection_attempt() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let ipv6 = Address::from(net::SocketAddr::from((net::Ipv6Addr::LOCALHOST, 8776)));
+    let ipv4 = Address::from(net::SocketAddr::from(([8, 8, 8, 9], 8776)));
+
+    let timestamp = alice.timestamp();
+
+    alice.initialize();
+    alice
+        .addresses_mut()
+        .insert(
+            &bob.id(),
+            node::Features::default(),
+            "bob",
+            timestamp,
+            [bob.address(), ipv6.clone(), ipv4.clone()]
+                .into_iter()
+                .map(|a| address::KnownAddress::new(a, address::Source::Peer)),
+        )
+        .unwrap();
+    alice.command(Command::Connect(
+        bob.id(),
+        bob.address(),
+        ConnectOptions::default(),
+    ));
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        Some(Io::Connect(nid, addr)) if nid == bob.id() && addr == bob.address()
+    );
+    alice.attempted(bob.id(), &bob.address());
+
+    // The first attempt stalls, so the next address is dialed, without giving up on the first.
+    alice.elapse(service::CONNECTION_ATTEMPT_DELAY);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        Some(Io::Connect(nid, addr)) if nid == bob.id() && addr == ipv6
+    );
+    alice.attempted(bob.id(), &ipv6);
 
-    let ips = alice
-        .sessions()
-        .connected()
-        .map(|(id, _)| *id)
-        .collect::<Vec<_>>();
-    assert!(ips.contains(&bob.id()));
-    assert!(ips.contains(&eve.id()));
+    // Once connected, the remaining address is not dialed.
+    alice.connected(bob.id(), Link::Outbound);
+    alice.elapse(service::CONNECTION_ATTEMPT_DELAY);
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        None
+    );
+}
 
-    // ... Negotiated ...
-    //
-    // Now let's disconnect a peer.
+#[test]
+fn test_dial_failed_connection_attempt() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let ipv6 = Address::from(net::SocketAddr::from((net::Ipv6Addr::LOCALHOST, 8776)));
 
-    // A non-transient disconnect, such as one due to peer misbehavior will still trigger a
-    // a reconnection, since this is a persistent peer.
-    let reason = DisconnectReason::Session(session::Error::Misbehavior);
+    let timestamp = alice.timestamp();
 
-    for _ in 0..3 {
-        alice.disconnected(bob.id(), &reason);
-        alice.elapse(service::MAX_RECONNECTION_DELTA);
-        alice
-            .outbox()
-            .find(|io| matches!(io, Io::Connect(a, _) if a == &bob.id()))
-            .unwrap();
+    alice.initialize();
+    alice
+        .addresses_mut()
+        .insert(
+            &bob.id(),
+            node::Features::default(),
+            "bob",
+            timestamp,
+            [ipv6.clone(), bob.address()]
+                .into_iter()
+                .map(|a| address::KnownAddress::new(a, address::Source::Peer)),
+        )
+        .unwrap();
+    alice.command(Command::Connect(
+        bob.id(),
+        ipv6.clone(),
+        ConnectOptions::default(),
+    ));
+    alice
+        .outbox()
+        .find(|io| matches!(io, Io::Connect { .. }))
+        .unwrap();
 
-        alice.attempted(bob.id(), &bob.address());
-    }
+    // The attempt fails right away, so the next address is dialed without waiting.
+    alice.disconnected(
+        bob.id(),
+        &DisconnectReason::Dial(Arc::new(io::Error::from(io::ErrorKind::ConnectionRefused))),
+    );
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Connect { .. })),
+        Some(Io::Connect(nid, addr)) if nid == bob.id() && addr == bob.address()
+    );
+    alice.attempted(bob.id(), &bob.address());
+    alice.connected(bob.id(), Link::Outbound);
+
+    assert!(alice.sessions().is_connected(&bob.id()));
 }
 
 #[test]
@@ -1039,6 +2325,73 @@ fn test_track_repo_subscribe() {
     );
 }
 
+#[test]
+fn test_track_repo_scope_change() {
+    let storage = arbitrary::nonempty_storage(1);
+    let rid = *storage.inventory.keys().next().unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.connect_to(&bob);
+    alice.receive(
+        bob.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: vec![rid].try_into().unwrap(),
+                timestamp: LocalTime::now().as_millis(),
+            },
+            bob.signer(),
+        ),
+    );
+    alice.outbox().for_each(drop);
+
+    // Widening the scope of a tracked repository fetches it, to get the new remotes.
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::TrackRepo(rid, tracking::Scope::All, send));
+    assert!(recv.recv().unwrap());
+    assert_matches!(
+        alice.outbox().find(|io| matches!(io, Io::Fetch { .. })),
+        Some(Io::Fetch { rid: rid_, remote, .. }) if rid_ == rid && remote == bob.id
+    );
+
+    // Tracking with the same scope doesn't.
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::TrackRepo(rid, tracking::Scope::All, send));
+    assert!(!recv.recv().unwrap());
+    assert!(!alice.outbox().any(|io| matches!(io, Io::Fetch { .. })));
+}
+
+#[test]
+fn test_track_repo_scope_narrowed() {
+    let storage = arbitrary::nonempty_storage(1);
+    let rid = *storage.inventory.keys().next().unwrap();
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::TrackRepo(rid, tracking::Scope::All, send));
+    assert!(recv.recv().unwrap());
+
+    // Alice has the remotes of Bob and Eve, but only tracks Bob.
+    for peer in [&bob, &eve] {
+        let refs = arbitrary::gen::<Refs>(8).signed(peer.signer()).unwrap();
+        alice.storage_mut().insert_remote(rid, peer.id, refs);
+    }
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::TrackNode(bob.id, None, None, send));
+    assert!(recv.recv().unwrap());
+
+    // Narrowing the scope to trusted nodes removes Eve's remote.
+    let (send, recv) = chan::bounded(1);
+    alice.command(Command::TrackRepo(rid, tracking::Scope::Trusted, send));
+    assert!(recv.recv().unwrap());
+
+    let remotes = alice.storage().repository(rid).unwrap().remotes().unwrap();
+    assert!(remotes.contains_key(&bob.id));
+    assert!(!remotes.contains_key(&eve.id));
+}
+
 #[test]
 fn test_fetch_missing_inventory() {
     let rid = arbitrary::gen::<Id>(1);
@@ -1076,14 +2429,116 @@ fn test_fetch_missing_inventory() {
     assert!(recv.recv().unwrap());
 
     alice.elapse(service::SYNC_INTERVAL);
+    let Some(Io::Fetch { remote, .. }) = alice.outbox().find(|m| matches!(m, Io::Fetch { .. }))
+    else {
+        panic!("a seed must be fetched from");
+    };
+    // A repository is fetched from one seed at a time, so the other seed is fetched from
+    // once the first fetch is done.
+    alice.fetched(
+        rid,
+        remote,
+        Err(worker::FetchError::Io(io::ErrorKind::Other.into())),
+    );
     alice
         .outbox()
         .find(|m| matches!(m, Io::Fetch { .. }))
         .unwrap();
+}
+
+#[test]
+fn test_fetch_missing_inventory_ranked() {
+    let rid = arbitrary::gen::<Id>(1);
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let (send, recv) = chan::bounded::<bool>(1);
+    let now = LocalTime::now();
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+    alice.receive(
+        bob.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: vec![rid].try_into().unwrap(),
+                timestamp: now.as_millis(),
+            },
+            bob.signer(),
+        ),
+    );
+    alice.receive(
+        eve.id(),
+        Message::inventory(
+            InventoryAnnouncement {
+                inventory: vec![rid].try_into().unwrap(),
+                timestamp: now.as_millis(),
+            },
+            eve.signer(),
+        ),
+    );
+    alice.command(Command::TrackRepo(rid, node::tracking::Scope::All, send));
+    alice.outbox().for_each(drop);
+
+    assert!(recv.recv().unwrap());
+
+    // Eve is a faster seed than Bob.
+    alice
+        .metrics_mut()
+        .record_fetch(&bob.id, 64 * 1024, LocalDuration::from_secs(1))
+        .unwrap();
     alice
-        .outbox()
-        .find(|m| matches!(m, Io::Fetch { .. }))
+        .metrics_mut()
+        .record_fetch(&eve.id, 1024 * 1024, LocalDuration::from_secs(1))
         .unwrap();
+
+    alice.elapse(service::SYNC_INTERVAL);
+    assert_matches!(
+        alice.outbox().find(|m| matches!(m, Io::Fetch { .. })),
+        Some(Io::Fetch { remote, .. }) if remote == eve.id
+    );
+    assert!(!alice.outbox().any(|m| matches!(m, Io::Fetch { .. })));
+
+    // Fetching from Eve fails, so we fall back to Bob.
+    alice.fetched(
+        rid,
+        eve.id,
+        Err(worker::FetchError::Io(io::ErrorKind::Other.into())),
+    );
+    assert_matches!(
+        alice.outbox().find(|m| matches!(m, Io::Fetch { .. })),
+        Some(Io::Fetch { remote, .. }) if remote == bob.id
+    );
+}
+
+#[test]
+fn test_pong_records_rtt() {
+    let mut alice = Peer::new("alice", [7, 7, 7, 7]);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+
+    alice.connect_to(&bob);
+    alice.elapse(KEEP_ALIVE_DELTA);
+
+    let ponglen = alice
+        .messages(bob.id())
+        .find_map(|m| match m {
+            Message::Ping(Ping { ponglen, .. }) => Some(ponglen),
+            _ => None,
+        })
+        .expect("Alice pings Bob");
+
+    alice.elapse(LocalDuration::from_millis(120));
+    alice.receive(
+        bob.id(),
+        Message::Pong {
+            zeroes: ZeroBytes::new(ponglen),
+        },
+    );
+    assert_eq!(
+        alice.metrics_mut().get(&bob.id).unwrap().rtt,
+        Some(120),
+        "The round-trip time is recorded"
+    );
 }
 #[test]
 fn test_queued_fetch() {
@@ -1132,6 +2587,99 @@ fn test_queued_fetch() {
     assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid3);
 }
 
+#[test]
+fn test_queued_fetch_max_fetches() {
+    let storage = arbitrary::nonempty_storage(2);
+    let mut repo_keys = storage.inventory.keys();
+    let rid1 = *repo_keys.next().unwrap();
+    let rid2 = *repo_keys.next().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        storage,
+        peer::Config {
+            config: Config {
+                limits: Limits {
+                    max_fetches: 1,
+                    ..Limits::default()
+                },
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+
+    let (send1, _recv1) = chan::bounded::<node::FetchResult>(1);
+    alice.command(Command::Fetch(rid1, bob.id, send1));
+    let (send2, _recv2) = chan::bounded::<node::FetchResult>(1);
+    alice.command(Command::Fetch(rid2, eve.id, send2));
+
+    // Only one fetch is initiated, even though the fetches are from different peers.
+    assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid1);
+    assert_matches!(alice.fetches().next(), None);
+
+    // Once the 1st fetch is done, the 2nd fetch is dequeued.
+    alice.fetched(rid1, bob.id, Ok((vec![], Default::default())));
+    assert_matches!(alice.fetches().next(), Some((rid, nid, _)) if rid == rid2 && nid == eve.id);
+}
+
+#[test]
+fn test_disconnected_fetch_max_fetches() {
+    let storage = arbitrary::nonempty_storage(2);
+    let mut repo_keys = storage.inventory.keys();
+    let rid1 = *repo_keys.next().unwrap();
+    let rid2 = *repo_keys.next().unwrap();
+    let mut alice = Peer::config(
+        "alice",
+        [7, 7, 7, 7],
+        storage,
+        peer::Config {
+            config: Config {
+                limits: Limits {
+                    max_fetches: 1,
+                    ..Limits::default()
+                },
+                ..Config::default()
+            },
+            ..peer::Config::default()
+        },
+    );
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let eve = Peer::new("eve", [9, 9, 9, 9]);
+    let reason =
+        DisconnectReason::Connection(Arc::new(io::Error::from(io::ErrorKind::ConnectionReset)));
+
+    alice.connect_to(&bob);
+    alice.connect_to(&eve);
+
+    let (send1, recv1) = chan::bounded::<node::FetchResult>(1);
+    alice.command(Command::Fetch(rid1, bob.id, send1));
+    let (send2, _recv2) = chan::bounded::<node::FetchResult>(1);
+    alice.command(Command::Fetch(rid2, eve.id, send2));
+
+    assert_matches!(alice.fetches().next(), Some((rid, _, _)) if rid == rid1);
+    assert_matches!(alice.fetches().next(), None);
+
+    // Bob disconnects during the 1st fetch. The fetch fails, but the worker may still be
+    // running it, so the 2nd fetch isn't dequeued yet.
+    alice.disconnected(bob.id(), &reason);
+    assert_matches!(recv1.try_recv(), Ok(node::FetchResult::Failed { .. }));
+    assert_matches!(alice.fetches().next(), None);
+
+    // Once the worker is done with the 1st fetch, the 2nd fetch is dequeued.
+    alice.fetched(
+        rid1,
+        bob.id,
+        Err(worker::FetchError::Io(io::ErrorKind::Other.into())),
+    );
+    assert_matches!(alice.fetches().next(), Some((rid, nid, _)) if rid == rid2 && nid == eve.id);
+}
+
 #[test]
 fn test_refs_synced_event() {
     let temp = tempfile::tempdir().unwrap();
@@ -1200,8 +2748,16 @@ fn test_push_and_pull() {
     local::register(alice.storage().clone());
 
     // Alice and Bob connect to Eve.
-    alice.command(service::Command::Connect(eve.id(), eve.address()));
-    bob.command(service::Command::Connect(eve.id(), eve.address()));
+    alice.command(service::Command::Connect(
+        eve.id(),
+        eve.address(),
+        ConnectOptions::default(),
+    ));
+    bob.command(service::Command::Connect(
+        eve.id(),
+        eve.address(),
+        ConnectOptions::default(),
+    ));
 
     // Alice creates a new project.
     let (proj_id, _, _) = rad::init(
@@ -1318,9 +2874,21 @@ fn prop_inventory_exchange_dense() {
         }
 
         // Fully-connected.
-        bob.command(Command::Connect(alice.id(), alice.address()));
-        bob.command(Command::Connect(eve.id(), eve.address()));
-        eve.command(Command::Connect(alice.id(), alice.address()));
+        bob.command(Command::Connect(
+            alice.id(),
+            alice.address(),
+            ConnectOptions::default(),
+        ));
+        bob.command(Command::Connect(
+            eve.id(),
+            eve.address(),
+            ConnectOptions::default(),
+        ));
+        eve.command(Command::Connect(
+            alice.id(),
+            alice.address(),
+            ConnectOptions::default(),
+        ));
 
         let mut peers: HashMap<_, _> = [
             (alice.node_id(), alice),
@@ -1367,3 +2935,98 @@ fn prop_inventory_exchange_dense() {
         .tests(20)
         .quickcheck(property as fn(MockStorage, MockStorage, MockStorage));
 }
+
+/// Routing tables converge to the global inventory, whatever the history of connections,
+/// disconnections and announcements that preceded the network being healed.
+#[test]
+fn prop_routing_convergence() {
+    type Inventories = (MockStorage, MockStorage, MockStorage, MockStorage);
+
+    fn property(seed: u64, (a, b, c, d): Inventories) {
+        // All randomness is derived from the seed, so that failures can be reproduced.
+        let rng = fastrand::Rng::with_seed(seed);
+        let mut peers = [
+            ("alice", 7, a),
+            ("bob", 8, b),
+            ("eve", 9, c),
+            ("carol", 10, d),
+        ]
+        .into_iter()
+        .map(|(name, ip, storage)| {
+            let mut rng = fastrand::Rng::with_seed(rng.u64(..));
+            let signer = MockSigner::new(&mut rng);
+
+            Peer::config(
+                name,
+                [ip; 4],
+                storage,
+                peer::Config {
+                    signer,
+                    rng,
+                    ..peer::Config::default()
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+        let mut routing = BTreeMap::<Id, BTreeSet<NodeId>>::new();
+        for peer in &peers {
+            for rid in peer.storage().inventory.keys() {
+                routing.entry(*rid).or_default().insert(peer.id());
+            }
+        }
+
+        let options = simulator::Options {
+            latency: 0..3,
+            failure_rate: 0.,
+        };
+        let mut sim =
+            Simulation::new(LocalTime::now(), rng.clone(), options).initialize(peers.iter_mut());
+
+        // Random schedule of connections, disconnections and announcements.
+        for _ in 0..rng.usize(1..24) {
+            let i = rng.usize(..peers.len());
+            let j = (i + rng.usize(1..peers.len())) % peers.len();
+            let (remote, addr) = (peers[j].id(), peers[j].address());
+
+            match rng.u8(..3) {
+                0 => peers[i].command(Command::Connect(remote, addr, ConnectOptions::default())),
+                1 => sim.disconnect(&peers[i].id(), &remote),
+                _ => peers[i].command(Command::AnnounceInventory),
+            }
+            sim.run_while(peers.iter_mut(), |s| !s.is_settled());
+        }
+
+        // Heal the network.
+        for i in 0..peers.len() {
+            for j in i + 1..peers.len() {
+                let (remote, addr) = (peers[j].id(), peers[j].address());
+
+                if !peers[i].sessions().is_connected(&remote) {
+                    peers[i].command(Command::Connect(remote, addr, ConnectOptions::default()));
+                }
+            }
+        }
+        sim.run_while(peers.iter_mut(), |s| !s.is_settled());
+
+        for peer in &peers {
+            for (rid, seeds) in &routing {
+                if seeds.contains(&peer.id()) {
+                    continue;
+                }
+                let lookup = peer.lookup(*rid).unwrap();
+
+                assert_eq!(
+                    &lookup.remote.into_iter().collect::<BTreeSet<_>>(),
+                    seeds,
+                    "{} has a route to every seed of {rid}",
+                    peer.name
+                );
+            }
+        }
+    }
+    qcheck::QuickCheck::new()
+        .gen(qcheck::Gen::new(5))
+        .tests(20)
+        .quickcheck(property as fn(u64, Inventories));
+}
@@ -2,13 +2,15 @@ use std::{collections::HashSet, thread, time};
 
 use radicle::crypto::{test::signer::MockSigner, Signer};
 use radicle::git;
-use radicle::node::{FetchResult, Handle as _};
+use radicle::node;
+use radicle::node::{ConnectOptions, FetchResult, Handle as _, DEFAULT_TIMEOUT};
 use radicle::storage::{ReadRepository, ReadStorage, WriteRepository, WriteStorage};
 use radicle::test::fixtures;
 use radicle::{assert_matches, rad};
 
 use crate::service;
 use crate::service::config::Limits;
+use crate::service::tracking;
 use crate::service::tracking::Scope;
 use crate::storage::git::transport;
 use crate::test::environment::{converge, Environment, Node};
@@ -165,7 +167,7 @@ fn test_replication() {
     let seeds = alice.handle.seeds(acme).unwrap();
     assert!(seeds.is_connected(&bob.id));
 
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     let updated = match result {
@@ -223,7 +225,7 @@ fn test_replication_no_delegates() {
     converge([&alice, &bob]);
 
     alice.handle.track_repo(acme, Scope::All).unwrap();
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
 
     assert_matches!(
         result,
@@ -268,9 +270,12 @@ fn test_replication_invalid() {
     alice.connect(&bob);
     converge([&alice, &bob]);
 
-    alice.handle.track_node(*carol.public_key(), None).unwrap();
+    alice
+        .handle
+        .track_node(*carol.public_key(), None, None)
+        .unwrap();
     alice.handle.track_repo(acme, Scope::Trusted).unwrap();
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
 
     // Fetch is successful despite not fetching Carol's refs, since she isn't a delegate.
     assert!(result.is_success());
@@ -302,7 +307,7 @@ fn test_migrated_clone() {
     let tracked = bob.handle.track_repo(acme, Scope::All).unwrap();
     assert!(tracked);
 
-    let result = bob.handle.fetch(acme, alice.id).unwrap();
+    let result = bob.handle.fetch(acme, alice.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     log::debug!(target: "test", "Fetch complete with {}", alice.id);
@@ -313,7 +318,7 @@ fn test_migrated_clone() {
         std::fs::remove_dir_all(path).unwrap();
     }
     assert!(!alice.storage.contains(&acme).unwrap());
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     let alice_repo = alice.storage.repository(acme).unwrap();
@@ -351,13 +356,13 @@ fn test_dont_fetch_owned_refs() {
 
     assert!(bob.handle.track_repo(acme, Scope::Trusted).unwrap());
 
-    let result = bob.handle.fetch(acme, alice.id).unwrap();
+    let result = bob.handle.fetch(acme, alice.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     log::debug!(target: "test", "Fetch complete with {}", bob.id);
 
     alice.issue(acme, "Don't fetch self", "Use ^");
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success())
 }
 
@@ -396,10 +401,10 @@ fn test_fetch_trusted_remotes() {
     );
     assert!(bob.handle.track_repo(acme, Scope::Trusted).unwrap());
     for nid in &trusted {
-        assert!(bob.handle.track_node(*nid, None).unwrap());
+        assert!(bob.handle.track_node(*nid, None, None).unwrap());
     }
 
-    let result = bob.handle.fetch(acme, alice.id).unwrap();
+    let result = bob.handle.fetch(acme, alice.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     log::debug!(target: "test", "Fetch complete with {}", bob.id);
@@ -431,9 +436,9 @@ fn test_fetch_preserve_owned_refs() {
     converge([&alice, &bob]);
 
     assert!(bob.handle.track_repo(acme, Scope::Trusted).unwrap());
-    assert!(bob.handle.track_node(alice.id, None).unwrap());
+    assert!(bob.handle.track_node(alice.id, None, None).unwrap());
 
-    let result = bob.handle.fetch(acme, alice.id).unwrap();
+    let result = bob.handle.fetch(acme, alice.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     log::debug!(target: "test", "Fetch complete with {}", bob.id);
@@ -448,7 +453,7 @@ fn test_fetch_preserve_owned_refs() {
         .unwrap();
 
     // Fetch shouldn't prune any of our own refs.
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     let (updated, _) = result.success().unwrap();
     assert_eq!(updated, vec![]);
 
@@ -483,7 +488,7 @@ fn test_clone() {
     let seeds = alice.handle.seeds(acme).unwrap();
     assert!(seeds.is_connected(&bob.id));
 
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     rad::fork(acme, &alice.signer, &alice.storage).unwrap();
@@ -538,17 +543,61 @@ fn test_fetch_up_to_date() {
     transport::local::register(alice.storage.clone());
 
     let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert!(result.is_success());
 
     // Fetch again! This time, everything's up to date.
-    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
     assert_eq!(
         result.success(),
         Some((vec![], HashSet::from_iter([bob.id])))
     );
 }
 
+#[test]
+fn test_fetch_pack_too_large() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let mut alice = alice.spawn(service::Config::default());
+    let bob = bob.spawn(service::Config::default());
+    let mut tracking =
+        tracking::Store::open(alice.home.node().join(node::TRACKING_DB_FILE)).unwrap();
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    // Only accept a few bytes of the repository.
+    tracking
+        .set_repo_limits(
+            &acme,
+            tracking::FetchLimits {
+                max_pack_size: Some(64),
+                ..tracking::FetchLimits::default()
+            },
+        )
+        .unwrap();
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
+    assert_matches!(
+        result,
+        FetchResult::Failed { reason } if reason.contains("maximum pack size of 64 bytes")
+    );
+
+    // The aborted fetch's channel was closed, so fetching again over the same connection
+    // works once the limit is lifted.
+    tracking
+        .set_repo_limits(&acme, tracking::FetchLimits::default())
+        .unwrap();
+    let result = alice.handle.fetch(acme, bob.id, DEFAULT_TIMEOUT).unwrap();
+    assert!(result.is_success());
+}
+
 #[test]
 fn test_large_fetch() {
     logger::init(log::Level::Debug);
@@ -695,8 +744,13 @@ fn test_connection_crossing() {
     let mut alice = alice.spawn(service::Config::default());
     let mut bob = bob.spawn(service::Config::default());
 
-    alice.handle.connect(bob.id, bob.addr.into()).unwrap();
-    bob.handle.connect(alice.id, alice.addr.into()).unwrap();
+    alice
+        .handle
+        .connect(bob.id, bob.addr.into(), ConnectOptions::default())
+        .unwrap();
+    bob.handle
+        .connect(alice.id, alice.addr.into(), ConnectOptions::default())
+        .unwrap();
 
     thread::sleep(time::Duration::from_secs(1));
 
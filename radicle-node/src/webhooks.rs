@@ -0,0 +1,425 @@
+//! Webhooks.
+//!
+//! The node posts a JSON payload to each URL configured under `node.webhooks` in the profile
+//! configuration, whenever refs of a tracked repository are fetched. Besides the fetch itself,
+//! payloads are sent for the patches and issues that were opened, and the patch revisions
+//! that were pushed, so that eg. CI can be triggered without polling the node.
+//!
+//! Like the metrics endpoint, the client is deliberately minimal: it only speaks plain
+//! HTTP/1.1, one request per connection, and doesn't follow redirects. In particular, HTTPS
+//! isn't supported: webhooks that are only served over HTTPS can be reached through a local
+//! proxy that terminates TLS. Payloads that can't be delivered are logged and dropped.
+//!
+//! Every webhook is delivered to by its own thread, so that a slow or unreachable webhook
+//! doesn't hold up the others. The configuration is read again for every event, so that
+//! changes to it apply without restarting the node.
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::{io, net, thread, time};
+
+use crossbeam_channel as chan;
+use serde::Serialize;
+
+use radicle::cob::patch::{self, RevisionId};
+use radicle::cob::{issue, ObjectId};
+use radicle::git;
+use radicle::identity::Id;
+use radicle::node::{Event, Events};
+use radicle::prelude::NodeId;
+use radicle::profile::{self, Home};
+use radicle::storage::{ReadStorage, RefUpdate};
+use radicle::Storage;
+
+/// How long to wait for a webhook to connect, accept a payload or answer.
+pub const TIMEOUT: time::Duration = time::Duration::from_secs(6);
+/// Number of events that can be waiting to be delivered to a webhook. When a webhook falls
+/// further behind, the payloads of new events are dropped.
+pub const QUEUE_SIZE: usize = 64;
+
+/// A payload posted to webhooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum Payload {
+    /// Refs of a repository were fetched from a remote.
+    RefsFetched {
+        rid: Id,
+        remote: NodeId,
+        updated: Vec<RefUpdate>,
+    },
+    /// A patch was opened.
+    PatchOpened {
+        rid: Id,
+        patch: ObjectId,
+        author: NodeId,
+    },
+    /// A new revision of a patch was pushed.
+    PatchRevision {
+        rid: Id,
+        patch: ObjectId,
+        revision: RevisionId,
+        author: NodeId,
+    },
+    /// An issue was opened.
+    IssueOpened {
+        rid: Id,
+        issue: ObjectId,
+        author: NodeId,
+    },
+}
+
+/// Post the payloads of node events to the configured webhooks, until the events channel
+/// is closed.
+pub fn run(home: Home, storage: Storage, events: Events) {
+    // Payloads waiting to be delivered, by webhook URL.
+    let mut queues: HashMap<String, chan::Sender<Vec<Payload>>> = HashMap::new();
+
+    for event in events {
+        if !matches!(event, Event::RefsFetched { .. }) {
+            continue;
+        }
+        let urls = match profile::Config::load(&home.config()) {
+            Ok(config) => config.node.webhooks,
+            Err(e) => {
+                log::error!(target: "webhooks", "Failed to load configuration: {e}");
+                continue;
+            }
+        };
+        // Stop delivering to webhooks that were removed from the configuration. Their threads
+        // exit once the payloads already queued are delivered.
+        queues.retain(|url, _| urls.contains(url));
+
+        if urls.is_empty() {
+            continue;
+        }
+        let payloads = payloads(&event, &storage);
+        if payloads.is_empty() {
+            continue;
+        }
+
+        for url in urls {
+            let queue = match queues.entry(url) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => match spawn(e.key()) {
+                    Ok(queue) => e.insert(queue),
+                    Err(err) => {
+                        log::error!(target: "webhooks", "Failed to spawn thread for webhook {}: {err}", e.key());
+                        continue;
+                    }
+                },
+            };
+            match queue.try_send(payloads.clone()) {
+                Ok(()) => {}
+                Err(chan::TrySendError::Full(_)) => {
+                    log::warn!(target: "webhooks", "Webhook delivery queue is full, dropping payloads..");
+                }
+                Err(chan::TrySendError::Disconnected(_)) => {
+                    log::error!(target: "webhooks", "Webhook delivery thread exited unexpectedly");
+                }
+            }
+        }
+        // Delivery threads that exited are spawned again on the next event.
+        queues.retain(|_, queue| !queue.is_disconnected());
+    }
+}
+
+/// Spawn a thread that posts the payloads sent to the returned queue to the given webhook,
+/// in order. The thread exits when the queue is dropped.
+fn spawn(url: &str) -> io::Result<chan::Sender<Vec<Payload>>> {
+    let (queue, payloads) = chan::bounded::<Vec<Payload>>(QUEUE_SIZE);
+    let url = url.to_owned();
+
+    thread::Builder::new()
+        .name(String::from("webhook"))
+        .spawn(move || {
+            for payload in payloads.iter().flatten() {
+                if let Err(e) = post(&url, &payload) {
+                    log::warn!(target: "webhooks", "Failed to post to webhook {url}: {e}");
+                }
+            }
+        })?;
+
+    Ok(queue)
+}
+
+/// Get the payloads to post for an event.
+pub fn payloads(event: &Event, storage: &Storage) -> Vec<Payload> {
+    let Event::RefsFetched {
+        remote,
+        rid,
+        updated,
+    } = event
+    else {
+        return vec![];
+    };
+    let updated = updated
+        .iter()
+        .filter(|u| !matches!(u, RefUpdate::Skipped { .. }))
+        .cloned()
+        .collect::<Vec<_>>();
+    if updated.is_empty() {
+        return vec![];
+    }
+    let mut payloads = Vec::new();
+
+    for update in &updated {
+        let (name, tip, created) = match update {
+            RefUpdate::Created { name, oid } => (name, *oid, true),
+            RefUpdate::Updated { name, new, .. } => (name, *new, false),
+            RefUpdate::Deleted { .. } | RefUpdate::Skipped { .. } => continue,
+        };
+        let Ok((author, name)) = git::parse_ref_namespaced::<NodeId>(name.as_str()) else {
+            continue;
+        };
+        let Some((typename, id)) = name
+            .as_str()
+            .strip_prefix("refs/cobs/")
+            .and_then(|cob| cob.split_once('/'))
+        else {
+            continue;
+        };
+        let Ok(id) = id.parse::<ObjectId>() else {
+            continue;
+        };
+        // The identifier of an object is the commit it was created with, so a cob was opened
+        // by the remote if its ref was created pointing to that commit.
+        let opened = created && *id == tip;
+
+        if typename == issue::TYPENAME.as_str() {
+            if opened {
+                payloads.push(Payload::IssueOpened {
+                    rid: *rid,
+                    issue: id,
+                    author,
+                });
+            }
+        } else if typename == patch::TYPENAME.as_str() {
+            if opened {
+                payloads.push(Payload::PatchOpened {
+                    rid: *rid,
+                    patch: id,
+                    author,
+                });
+            } else if is_revision(storage, *rid, &id, RevisionId::from(tip)) {
+                payloads.push(Payload::PatchRevision {
+                    rid: *rid,
+                    patch: id,
+                    revision: RevisionId::from(tip),
+                    author,
+                });
+            }
+        }
+    }
+    payloads.insert(
+        0,
+        Payload::RefsFetched {
+            rid: *rid,
+            remote: *remote,
+            updated,
+        },
+    );
+    payloads
+}
+
+/// Check whether the given change of a patch created a revision.
+fn is_revision(storage: &Storage, rid: Id, patch: &ObjectId, change: RevisionId) -> bool {
+    let Ok(repo) = storage.repository(rid) else {
+        return false;
+    };
+    let Ok(patches) = patch::Patches::open(&repo) else {
+        return false;
+    };
+    matches!(patches.get(patch), Ok(Some(p)) if p.revision(&change).is_some())
+}
+
+/// Post a payload to a webhook, as JSON.
+pub fn post(url: &str, payload: &Payload) -> Result<(), io::Error> {
+    let (host, path) = parse(url)?;
+    let body = serde_json::to_string(payload)?;
+    let addr = net::ToSocketAddrs::to_socket_addrs(&host)?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))?;
+    let mut stream = net::TcpStream::connect_timeout(&addr, TIMEOUT)?;
+
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\n\
+         Host: {host}\r\n\
+         User-Agent: radicle-node\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+
+    let mut status = String::new();
+    BufReader::new(&stream).read_line(&mut status)?;
+
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(code) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("webhook answered with status {code}"),
+        )),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid response from webhook",
+        )),
+    }
+}
+
+/// Split a webhook URL into its host, with port, and path.
+fn parse(url: &str) -> Result<(String, &str), io::Error> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only `http://` webhook URLs are supported, HTTPS requires a TLS-terminating proxy",
+        )
+    })?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "webhook URL has no host",
+        ));
+    }
+    let host = if host.contains(':') {
+        host.to_owned()
+    } else {
+        format!("{host}:80")
+    };
+    Ok((host, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+    use std::str::FromStr;
+    use std::thread;
+
+    use radicle::test::arbitrary;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("http://ci.example.com/hooks/radicle").unwrap(),
+            (String::from("ci.example.com:80"), "/hooks/radicle")
+        );
+        assert_eq!(
+            parse("http://127.0.0.1:8080").unwrap(),
+            (String::from("127.0.0.1:8080"), "/")
+        );
+        assert!(parse("https://ci.example.com").is_err());
+        assert!(parse("http:///hooks").is_err());
+    }
+
+    #[test]
+    fn test_payloads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = Storage::open(tmp.path()).unwrap();
+        let rid = arbitrary::gen::<Id>(1);
+        let remote = arbitrary::gen::<NodeId>(1);
+        let issue = git::Oid::from_str("d96f425412c9f8ad5d9a9a05c9831d0728e2338d").unwrap();
+        let name = git::refs::storage::cob(&remote, &issue::TYPENAME, &issue.into());
+        let updated = vec![
+            RefUpdate::Created {
+                name: name.to_ref_string(),
+                oid: issue,
+            },
+            RefUpdate::Skipped {
+                name: git::refname!("refs/namespaces/a/refs/heads/master"),
+                oid: issue,
+            },
+        ];
+        let event = Event::RefsFetched {
+            remote,
+            rid,
+            updated: updated.clone(),
+        };
+
+        assert_eq!(
+            payloads(&event, &storage),
+            vec![
+                Payload::RefsFetched {
+                    rid,
+                    remote,
+                    updated: updated[..1].to_vec(),
+                },
+                Payload::IssueOpened {
+                    rid,
+                    issue: issue.into(),
+                    author: remote,
+                },
+            ]
+        );
+        assert!(payloads(&Event::PeerConnected { nid: remote }, &storage).is_empty());
+    }
+
+    #[test]
+    fn test_post() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+
+            stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            stream.read_to_string(&mut request).ok();
+            request
+        });
+        let payload = Payload::IssueOpened {
+            rid: arbitrary::gen::<Id>(1),
+            issue: arbitrary::oid().into(),
+            author: arbitrary::gen::<NodeId>(1),
+        };
+        post(&url, &payload).unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.ends_with(&serde_json::to_string(&payload).unwrap()));
+        assert!(request.contains("\"type\":\"issue-opened\""));
+    }
+
+    #[test]
+    fn test_deliver_independently() {
+        // A webhook that accepts the connection, but never answers.
+        let stalled = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled_url = format!("http://{}/hook", stalled.local_addr().unwrap());
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        });
+        let payload = Payload::IssueOpened {
+            rid: arbitrary::gen::<Id>(1),
+            issue: arbitrary::oid().into(),
+            author: arbitrary::gen::<NodeId>(1),
+        };
+        let started = time::Instant::now();
+
+        spawn(&stalled_url)
+            .unwrap()
+            .send(vec![payload.clone(), payload.clone()])
+            .unwrap();
+        spawn(&url).unwrap().send(vec![payload]).unwrap();
+        server.join().unwrap();
+
+        // The stalled webhook would hold up delivery for at least the timeout.
+        assert!(started.elapsed() < TIMEOUT);
+        drop(stalled);
+    }
+}
@@ -461,6 +461,20 @@ impl Decode for node::Features {
     }
 }
 
+impl Encode for node::Capabilities {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        self.deref().encode(writer)
+    }
+}
+
+impl Decode for node::Capabilities {
+    fn decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, Error> {
+        let capabilities = u64::decode(reader)?;
+
+        Ok(Self::from(capabilities))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,8 @@ use std::{io, mem, net};
 
 use byteorder::{NetworkEndian, ReadBytesExt};
 use cyphernet::addr::{Addr, HostName, NetAddr};
+use radicle::git;
+use radicle::node;
 use radicle::node::Address;
 
 use crate::prelude::*;
@@ -19,6 +21,11 @@ pub enum MessageType {
     Subscribe = 8,
     Ping = 10,
     Pong = 12,
+    Hello = 14,
+    Compressed = 16,
+    Search = 18,
+    SearchResults = 20,
+    Signal = 22,
 }
 
 impl From<MessageType> for u16 {
@@ -38,6 +45,11 @@ impl TryFrom<u16> for MessageType {
             8 => Ok(MessageType::Subscribe),
             10 => Ok(MessageType::Ping),
             12 => Ok(MessageType::Pong),
+            14 => Ok(MessageType::Hello),
+            16 => Ok(MessageType::Compressed),
+            18 => Ok(MessageType::Search),
+            20 => Ok(MessageType::SearchResults),
+            22 => Ok(MessageType::Signal),
             _ => Err(other),
         }
     }
@@ -50,6 +62,8 @@ impl Message {
 
     pub fn type_id(&self) -> u16 {
         match self {
+            Self::Hello { .. } => MessageType::Hello,
+            Self::Compressed { .. } => MessageType::Compressed,
             Self::Subscribe { .. } => MessageType::Subscribe,
             Self::Announcement(Announcement { message, .. }) => match message {
                 AnnouncementMessage::Node(_) => MessageType::NodeAnnouncement,
@@ -58,6 +72,9 @@ impl Message {
             },
             Self::Ping { .. } => MessageType::Ping,
             Self::Pong { .. } => MessageType::Pong,
+            Self::Search { .. } => MessageType::Search,
+            Self::SearchResults { .. } => MessageType::SearchResults,
+            Self::Signal { .. } => MessageType::Signal,
         }
         .into()
     }
@@ -180,11 +197,123 @@ impl wire::Decode for InventoryAnnouncement {
     }
 }
 
+impl wire::Encode for node::SearchResult {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let mut n = 0;
+        let delegates = BoundedVec::<_, SEARCH_DELEGATES_LIMIT>::truncate(
+            self.delegates.iter().map(|did| **did).collect(),
+        );
+
+        n += self.rid.encode(writer)?;
+        n += self.name.encode(writer)?;
+        n += self.description.encode(writer)?;
+        n += self.head.encode(writer)?;
+        n += delegates.encode(writer)?;
+
+        Ok(n)
+    }
+}
+
+impl wire::Decode for node::SearchResult {
+    fn decode<R: std::io::Read + ?Sized>(reader: &mut R) -> Result<Self, wire::Error> {
+        let rid = Id::decode(reader)?;
+        let name = String::decode(reader)?;
+        let description = String::decode(reader)?;
+        let head = git::Oid::decode(reader)?;
+        let delegates = BoundedVec::<PublicKey, SEARCH_DELEGATES_LIMIT>::decode(reader)?;
+
+        Ok(Self {
+            rid,
+            name,
+            description,
+            head,
+            delegates: delegates.unbound().into_iter().map(Did::from).collect(),
+        })
+    }
+}
+
+impl wire::Encode for node::Signal {
+    fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
+        let payload = match self {
+            node::Signal::Invite { rid }
+            | node::Signal::FetchHint { rid }
+            | node::Signal::SyncRequest { rid }
+            | node::Signal::SyncDeclined { rid } => wire::serialize(rid),
+            node::Signal::Synced { rid, sigrefs } => {
+                let mut payload = wire::serialize(rid);
+                payload.extend(wire::serialize(sigrefs));
+                payload
+            }
+            node::Signal::Ticket { ticket } | node::Signal::Resume { ticket } => {
+                wire::serialize(ticket)
+            }
+            node::Signal::ResumeDeclined => vec![],
+            node::Signal::Unknown { payload, .. } => payload.clone(),
+        };
+        let mut n = 0;
+
+        n += self.kind().encode(writer)?;
+        n += payload.as_slice().encode(writer)?;
+
+        Ok(n)
+    }
+}
+
+impl wire::Decode for node::Signal {
+    fn decode<R: std::io::Read + ?Sized>(reader: &mut R) -> Result<Self, wire::Error> {
+        let kind = u16::decode(reader)?;
+        let len = wire::Size::decode(reader)?;
+        let mut payload = vec![0; len as usize];
+        io::Read::read_exact(reader, &mut payload)?;
+
+        // Nb. Trailing payload bytes are ignored, so that fields can be added to known
+        // signals without breaking older nodes.
+        let signal = match kind {
+            node::Signal::INVITE => node::Signal::Invite {
+                rid: wire::deserialize(&payload)?,
+            },
+            node::Signal::FETCH_HINT => node::Signal::FetchHint {
+                rid: wire::deserialize(&payload)?,
+            },
+            node::Signal::SYNC_REQUEST => node::Signal::SyncRequest {
+                rid: wire::deserialize(&payload)?,
+            },
+            node::Signal::SYNCED => {
+                let mut payload = io::Cursor::new(payload.as_slice());
+
+                node::Signal::Synced {
+                    rid: Id::decode(&mut payload)?,
+                    sigrefs: git::Oid::decode(&mut payload)?,
+                }
+            }
+            node::Signal::SYNC_DECLINED => node::Signal::SyncDeclined {
+                rid: wire::deserialize(&payload)?,
+            },
+            node::Signal::TICKET => node::Signal::Ticket {
+                ticket: wire::deserialize(&payload)?,
+            },
+            node::Signal::RESUME => node::Signal::Resume {
+                ticket: wire::deserialize(&payload)?,
+            },
+            node::Signal::RESUME_DECLINED => node::Signal::ResumeDeclined,
+            _ => node::Signal::Unknown { kind, payload },
+        };
+        Ok(signal)
+    }
+}
+
 impl wire::Encode for Message {
     fn encode<W: std::io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, std::io::Error> {
         let mut n = self.type_id().encode(writer)?;
 
         match self {
+            Self::Hello(Hello {
+                version,
+                capabilities,
+            }) => {
+                n += version.encode(writer)?;
+                n += capabilities.encode(writer)?;
+            }
             Self::Subscribe(Subscribe {
                 filter,
                 since,
@@ -203,6 +332,9 @@ impl wire::Encode for Message {
                 n += message.encode(writer)?;
                 n += signature.encode(writer)?;
             }
+            Self::Compressed(c) => {
+                n += c.as_bytes().encode(writer)?;
+            }
             Self::Ping(Ping { ponglen, zeroes }) => {
                 n += ponglen.encode(writer)?;
                 n += zeroes.encode(writer)?;
@@ -210,6 +342,16 @@ impl wire::Encode for Message {
             Self::Pong { zeroes } => {
                 n += zeroes.encode(writer)?;
             }
+            Self::Search(Search { query }) => {
+                n += query.encode(writer)?;
+            }
+            Self::SearchResults(SearchResults { query, results }) => {
+                n += query.encode(writer)?;
+                n += results.encode(writer)?;
+            }
+            Self::Signal(signal) => {
+                n += signal.encode(writer)?;
+            }
         }
 
         if n > wire::Size::MAX as usize {
@@ -227,6 +369,15 @@ impl wire::Decode for Message {
         let type_id = reader.read_u16::<NetworkEndian>()?;
 
         match MessageType::try_from(type_id) {
+            Ok(MessageType::Hello) => {
+                let version = u8::decode(reader)?;
+                let capabilities = node::Capabilities::decode(reader)?;
+
+                Ok(Self::Hello(Hello {
+                    version,
+                    capabilities,
+                }))
+            }
             Ok(MessageType::Subscribe) => {
                 let filter = Filter::decode(reader)?;
                 let since = Timestamp::decode(reader)?;
@@ -274,6 +425,13 @@ impl wire::Decode for Message {
                 }
                 .into())
             }
+            Ok(MessageType::Compressed) => {
+                let len = wire::Size::decode(reader)?;
+                let mut bytes = vec![0; len as usize];
+                io::Read::read_exact(reader, &mut bytes)?;
+
+                Ok(Self::Compressed(Compressed::from(bytes)))
+            }
             Ok(MessageType::Ping) => {
                 let ponglen = u16::decode(reader)?;
                 let zeroes = ZeroBytes::decode(reader)?;
@@ -283,6 +441,19 @@ impl wire::Decode for Message {
                 let zeroes = ZeroBytes::decode(reader)?;
                 Ok(Self::Pong { zeroes })
             }
+            Ok(MessageType::Search) => {
+                let query = String::decode(reader)?;
+                Ok(Self::Search(Search { query }))
+            }
+            Ok(MessageType::SearchResults) => {
+                let query = String::decode(reader)?;
+                let results = BoundedVec::decode(reader)?;
+                Ok(Self::SearchResults(SearchResults { query, results }))
+            }
+            Ok(MessageType::Signal) => {
+                let signal = node::Signal::decode(reader)?;
+                Ok(Self::Signal(signal))
+            }
             Err(other) => Err(wire::Error::UnknownMessageType(other)),
         }
     }
@@ -367,6 +538,7 @@ mod tests {
     use qcheck_macros::quickcheck;
 
     use crate::deserializer::Deserializer;
+    use crate::test::arbitrary;
     use crate::wire::{self, Encode};
 
     #[test]
@@ -407,6 +579,41 @@ mod tests {
             .expect_err("pong should exceed max message size");
     }
 
+    #[test]
+    fn test_compressed_encode_decode() {
+        let rid = arbitrary::gen::<Id>(1);
+        let msg: Message = Announcement {
+            node: arbitrary::gen(1),
+            message: InventoryAnnouncement {
+                inventory: vec![rid; 128].try_into().unwrap(),
+                timestamp: 0,
+            }
+            .into(),
+            signature: Signature::from([0; 64]),
+        }
+        .into();
+
+        let compressed = msg.clone().compressed();
+        let Message::Compressed(c) = &compressed else {
+            panic!("inventory announcement should be compressed");
+        };
+        assert!(c.as_bytes().len() < wire::serialize(&msg).len());
+
+        let Message::Compressed(c) =
+            wire::deserialize::<Message>(&wire::serialize(&compressed)).unwrap()
+        else {
+            panic!("compressed message should decode as compressed");
+        };
+        assert_eq!(c.decompress().unwrap(), msg);
+
+        // Only announcements are compressed.
+        let ping = Message::Ping(Ping {
+            ponglen: 0,
+            zeroes: ZeroBytes::new(8),
+        });
+        assert_eq!(ping.clone().compressed(), ping);
+    }
+
     #[quickcheck]
     fn prop_message_encode_decode(message: Message) {
         assert_eq!(
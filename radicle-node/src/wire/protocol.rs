@@ -22,7 +22,7 @@ use netservices::{NetConnection, NetProtocol, NetReader, NetWriter};
 use reactor::Timestamp;
 
 use radicle::collections::HashMap;
-use radicle::node::{routing, NodeId};
+use radicle::node::{audit, routing, Address, NodeId};
 use radicle::storage::WriteStorage;
 
 use crate::crypto::Signer;
@@ -243,6 +243,57 @@ impl Peer {
     }
 }
 
+/// A connection, tracked until it is closed and logged to the audit log.
+struct Connection {
+    /// When the connection was attempted.
+    since: LocalTime,
+    /// The peer, once known.
+    nid: Option<NodeId>,
+    /// Address of the peer.
+    addr: Address,
+    /// Connection direction.
+    direction: audit::Direction,
+    /// Whether the handshake completed.
+    established: bool,
+    /// Bytes received from the peer.
+    received: u64,
+    /// Bytes sent to the peer.
+    sent: u64,
+}
+
+impl Connection {
+    fn new(
+        since: LocalTime,
+        nid: Option<NodeId>,
+        addr: Address,
+        direction: audit::Direction,
+    ) -> Self {
+        Self {
+            since,
+            nid,
+            addr,
+            direction,
+            established: false,
+            received: 0,
+            sent: 0,
+        }
+    }
+
+    /// Turn the connection into an audit log entry, once closed.
+    fn entry(self, reason: &DisconnectReason) -> audit::Entry {
+        audit::Entry {
+            time: self.since.as_millis(),
+            nid: self.nid,
+            addr: self.addr,
+            direction: self.direction,
+            established: self.established,
+            reason: reason.to_string(),
+            received: self.received,
+            sent: self.sent,
+        }
+    }
+}
+
 struct Peers(HashMap<RawFd, Peer>);
 
 impl Peers {
@@ -287,6 +338,13 @@ impl Peers {
         })
     }
 
+    /// Check whether a connection attempt to the given node is in progress.
+    fn is_dialing(&self, node_id: &NodeId) -> bool {
+        self.0
+            .values()
+            .any(|peer| matches!(peer, Peer::Outbound { id } if id == node_id))
+    }
+
     fn connected(&self) -> impl Iterator<Item = (RawFd, &NodeId)> {
         self.0.iter().filter_map(|(fd, peer)| {
             if let Peer::Connected { nid: id, .. } = peer {
@@ -312,6 +370,10 @@ pub struct Wire<R, S, W, G: Signer + Ecdh> {
     peers: Peers,
     /// SOCKS5 proxy address.
     proxy: net::SocketAddr,
+    /// Connection audit log entries, recorded by a separate thread.
+    audit: chan::Sender<audit::Entry>,
+    /// Connections that haven't been logged to the audit log yet.
+    connections: HashMap<RawFd, Connection>,
 }
 
 impl<R, S, W, G> Wire<R, S, W, G>
@@ -326,6 +388,7 @@ where
         worker: chan::Sender<Task>,
         signer: G,
         proxy: net::SocketAddr,
+        audit: chan::Sender<audit::Entry>,
         clock: LocalTime,
     ) -> Self {
         service
@@ -339,6 +402,8 @@ where
             proxy,
             actions: VecDeque::new(),
             peers: Peers(HashMap::default()),
+            audit,
+            connections: HashMap::default(),
         }
     }
 
@@ -363,6 +428,37 @@ where
         }
     }
 
+    /// Notify the service of a disconnection, unless other connection attempts to the
+    /// same peer are still in progress.
+    fn disconnected(&mut self, id: NodeId, reason: &DisconnectReason) {
+        if self.peers.is_dialing(&id) {
+            log::debug!(
+                target: "wire",
+                "Connection attempt to {id} failed ({reason}); other attempts are in progress"
+            );
+            return;
+        }
+        self.service.disconnected(id, reason);
+    }
+
+    /// Log a closed connection to the audit log.
+    fn closed(&mut self, fd: RawFd, reason: &DisconnectReason) {
+        let Some(conn) = self.connections.remove(&fd) else {
+            return;
+        };
+        self.audit(conn.entry(reason));
+    }
+
+    /// Queue an entry to be recorded in the audit log, without waiting on the log.
+    fn audit(&mut self, entry: audit::Entry) {
+        if let Err(chan::TrySendError::Full(entry)) = self.audit.try_send(entry) {
+            log::warn!(
+                target: "wire",
+                "Audit log queue is full; dropping entry for connection to {}", entry.addr
+            );
+        }
+    }
+
     fn worker_result(&mut self, task: TaskResult) {
         log::debug!(
             target: "wire",
@@ -371,26 +467,41 @@ where
         );
 
         let nid = task.remote;
-        let Some((fd, peer)) = self.peers.lookup_mut(&nid) else {
-            log::warn!(target: "wire", "Peer {nid} not found; ignoring fetch result");
-            return;
-        };
 
-        let Peer::Connected { nid, link, streams, .. } = peer else {
-            log::warn!(target: "wire", "Peer {nid} is not connected; ignoring fetch result");
-            return;
-        };
-
-        // Only call into the service if we initiated this fetch.
+        // Only call into the service if we initiated this fetch. Nb. this is done even if the
+        // peer disconnected in the meantime, since the service only frees the fetch's slot once
+        // it is done.
         match task.result {
-            FetchResult::Initiator { rid, result } => {
-                self.service.fetched(rid, *nid, result);
+            FetchResult::Initiator {
+                rid,
+                result,
+                refused,
+                rewrite,
+                stats,
+            } => {
+                if result.is_ok() {
+                    self.service.transferred(nid, stats);
+                }
+                self.service.refused(rid, nid, refused);
+                if let Some(rewrite) = rewrite {
+                    self.service.rewritten(rid, nid, rewrite);
+                }
+                self.service.fetched(rid, nid, result);
             }
             FetchResult::Responder { .. } => {
                 // We don't do anything with upload results for now.
             }
         }
 
+        let Some((fd, peer)) = self.peers.lookup_mut(&nid) else {
+            log::debug!(target: "wire", "Peer {nid} not found; not closing stream {}", task.stream);
+            return;
+        };
+        let Peer::Connected { link, streams, .. } = peer else {
+            log::debug!(target: "wire", "Peer {nid} is not connected; not closing stream {}", task.stream);
+            return;
+        };
+
         // Nb. It's possible that the stream would already be unregistered if we received an early
         // "close" from the remote. Otherwise, we unregister it here and send the "close" ourselves.
         if streams.unregister(&task.stream).is_some() {
@@ -463,7 +574,19 @@ where
                     "Accepting inbound peer connection from {}..",
                     connection.remote_addr()
                 );
-                self.peers.insert(connection.as_raw_fd(), Peer::inbound());
+                let fd = connection.as_raw_fd();
+                let addr = Address::from(connection.remote_addr());
+
+                self.peers.insert(fd, Peer::inbound());
+                self.connections.insert(
+                    fd,
+                    Connection::new(
+                        self.service.local_time(),
+                        None,
+                        addr,
+                        audit::Direction::Inbound,
+                    ),
+                );
 
                 let session = accept::<G>(connection, self.signer.clone());
                 let transport = match NetTransport::with_session(session, Link::Inbound) {
@@ -502,8 +625,27 @@ where
                     .filter(|(other, d)| **d == id && *other != fd)
                     .map(|(fd, _)| fd)
                     .collect::<Vec<_>>();
+                let dialed = matches!(self.peers.get_mut(&fd), Some(Peer::Outbound { .. }));
 
                 for fd in conflicting {
+                    // If we dialed multiple addresses of the peer, drop the other attempts
+                    // without notifying the service, since it only knows of one session.
+                    if dialed {
+                        if let Some(peer @ Peer::Outbound { .. }) = self.peers.get_mut(&fd) {
+                            log::debug!(
+                                target: "wire", "Closing redundant connection attempt to {id} (fd={fd})"
+                            );
+                            *peer = Peer::Disconnecting {
+                                id: None,
+                                reason: DisconnectReason::Dial(Arc::new(io::Error::from(
+                                    io::ErrorKind::AlreadyExists,
+                                ))),
+                            };
+                            self.actions.push_back(Action::UnregisterTransport(fd));
+
+                            continue;
+                        }
+                    }
                     log::warn!(
                         target: "wire", "Closing conflicting session with {id} (fd={fd})"
                     );
@@ -521,16 +663,24 @@ where
                 };
                 let link = peer.connected(id);
 
+                if let Some(conn) = self.connections.get_mut(&fd) {
+                    conn.nid = Some(id);
+                    conn.established = true;
+                }
                 self.service.connected(id, link);
             }
             SessionEvent::Data(data) => {
                 if let Some(Peer::Connected {
                     nid,
+                    link,
                     inbox,
                     streams,
                     ..
                 }) = self.peers.get_mut(&fd)
                 {
+                    if let Some(conn) = self.connections.get_mut(&fd) {
+                        conn.received += data.len() as u64;
+                    }
                     inbox.input(&data);
 
                     loop {
@@ -541,6 +691,16 @@ where
                             })) => {
                                 log::debug!(target: "wire", "Received stream open for id={stream} from {nid}");
 
+                                // Observers don't serve repositories: close the stream right away.
+                                if self.service.config().observer {
+                                    log::debug!(target: "wire", "Refusing stream id={stream} from {nid}: node is an observer");
+
+                                    let frame =
+                                        Frame::control(*link, frame::Control::Close { stream });
+                                    self.actions.push_back(Action::Send(fd, frame.to_bytes()));
+
+                                    continue;
+                                }
                                 let Some(channels) = streams.register(stream) else {
                                     log::warn!(target: "wire", "Peer attempted to open already-open stream id={stream}");
                                     continue;
@@ -684,9 +844,10 @@ where
                         if let Peer::Connected { streams, .. } = &mut peer {
                             streams.shutdown();
                         }
+                        self.closed(fd, &reason);
 
                         if let Some(id) = peer.id() {
-                            self.service.disconnected(*id, &reason);
+                            self.disconnected(*id, &reason);
                         } else {
                             log::debug!(target: "wire", "Inbound disconnection before handshake; ignoring..")
                         }
@@ -718,15 +879,18 @@ where
         match self.peers.entry(fd) {
             Entry::Occupied(e) => {
                 match e.get() {
-                    Peer::Disconnecting { id, reason, .. } => {
+                    Peer::Disconnecting { .. } => {
                         // Disconnect TCP stream.
                         drop(transport);
 
-                        // If there is no ID, the service is not aware of the peer.
-                        if let Some(id) = id {
-                            self.service.disconnected(*id, reason);
+                        if let Peer::Disconnecting { id, reason } = e.remove() {
+                            self.closed(fd, &reason);
+
+                            // If there is no ID, the service is not aware of the peer.
+                            if let Some(id) = id {
+                                self.disconnected(id, &reason);
+                            }
                         }
-                        e.remove();
                     }
                     _ => {
                         panic!("Wire::handover_transport: Unexpected peer with fd {fd} handed over from the reactor");
@@ -798,11 +962,21 @@ where
                         NetTransport::<WireSession<G>>::with_session(session, Link::Outbound)
                     }) {
                         Ok(transport) => {
+                            let fd = transport.as_raw_fd();
+
                             self.service.attempted(node_id, &addr);
+                            self.connections.insert(
+                                fd,
+                                Connection::new(
+                                    self.service.local_time(),
+                                    Some(node_id),
+                                    addr,
+                                    audit::Direction::Outbound,
+                                ),
+                            );
                             // TODO: Keep track of peer address for when peer disconnects before
                             // handshake is complete.
-                            self.peers
-                                .insert(transport.as_raw_fd(), Peer::outbound(node_id));
+                            self.peers.insert(fd, Peer::outbound(node_id));
 
                             self.actions
                                 .push_back(reactor::Action::RegisterTransport(transport));
@@ -810,8 +984,15 @@ where
                         Err(err) => {
                             log::error!(target: "wire", "Error establishing connection: {err}");
 
-                            self.service
-                                .disconnected(node_id, &DisconnectReason::Dial(Arc::new(err)));
+                            let reason = DisconnectReason::Dial(Arc::new(err));
+                            let conn = Connection::new(
+                                self.service.local_time(),
+                                Some(node_id),
+                                addr,
+                                audit::Direction::Outbound,
+                            );
+                            self.audit(conn.entry(&reason));
+                            self.disconnected(node_id, &reason);
                             break;
                         }
                     }
@@ -830,6 +1011,7 @@ where
                     rid,
                     remote,
                     namespaces,
+                    limits,
                 } => {
                     log::trace!(target: "wire", "Processing fetch for {rid} from {remote}..");
 
@@ -838,8 +1020,14 @@ where
                             // Nb. It's possible that a peer is disconnected while an `Io::Fetch`
                             // is in the service's i/o buffer. Since the service may not purge the
                             // buffer on disconnect, we should just ignore i/o actions that don't
-                            // have a connected peer.
+                            // have a connected peer. The service is still told that the fetch
+                            // failed, so that its slot is freed.
                             log::error!(target: "wire", "Peer {remote} is not connected: dropping fetch");
+                            self.service.fetched(
+                                rid,
+                                remote,
+                                Err(worker::FetchError::Io(io::ErrorKind::NotConnected.into())),
+                            );
                             continue;
                         };
                     let (stream, channels) = streams.open();
@@ -852,6 +1040,7 @@ where
                             rid,
                             namespaces,
                             remote,
+                            limits,
                         },
                         stream,
                         channels,
@@ -873,7 +1062,14 @@ where
                 }
             }
         }
-        self.actions.pop_front()
+        let action = self.actions.pop_front();
+
+        if let Some(Action::Send(fd, data)) = &action {
+            if let Some(conn) = self.connections.get_mut(fd) {
+                conn.sent += data.len() as u64;
+            }
+        }
+        action
     }
 }
 
@@ -914,6 +1110,10 @@ pub fn accept<G: Signer + Ecdh<Pk = NodeId>>(
 }
 
 /// Create a new [`WireSession`].
+///
+/// The Noise handshake is always run in full, since our Noise implementation supports neither
+/// pre-shared keys nor session tickets. Instead, it's the gossip session established on top of
+/// it that is resumed when a peer reconnects: see [`radicle::node::Capabilities::RESUME`].
 fn session<G: Signer + Ecdh<Pk = NodeId>>(
     remote_addr: NetAddr<HostName>,
     remote_id: Option<NodeId>,
@@ -2,7 +2,7 @@ mod channels;
 mod fetch;
 mod tunnel;
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::io::{prelude::*, BufReader};
 use std::ops::ControlFlow;
 use std::thread::JoinHandle;
@@ -10,9 +10,11 @@ use std::{env, io, net, process, thread, time};
 
 use crossbeam_channel as chan;
 
-use radicle::identity::Id;
+use radicle::identity::{Id, IdentityError};
+use radicle::node::tracking::FetchLimits;
 use radicle::prelude::NodeId;
-use radicle::storage::{Namespaces, ReadRepository, RefUpdate};
+use radicle::storage::events::Event;
+use radicle::storage::{Namespaces, ReadRepository, ReadStorage, RefUpdate, Rewrite};
 use radicle::{git, Storage};
 
 use crate::runtime::Handle;
@@ -21,6 +23,7 @@ use channels::{ChannelReader, ChannelWriter};
 use tunnel::Tunnel;
 
 pub use channels::{ChannelEvent, Channels};
+pub use fetch::policy::{self, ContentPolicy, Refusal};
 
 /// Worker pool configuration.
 pub struct Config {
@@ -36,6 +39,8 @@ pub struct Config {
     pub daemon: net::SocketAddr,
     /// Git storage.
     pub storage: Storage,
+    /// Policy applied to fetched content.
+    pub policy: ContentPolicy,
 }
 
 /// Error returned by fetch.
@@ -51,6 +56,8 @@ pub enum FetchError {
     StagingTransition(#[from] fetch::error::Transition),
     #[error(transparent)]
     StagingTransfer(#[from] fetch::error::Transfer),
+    #[error("fetch refused: {0}")]
+    Refused(policy::Reason),
 }
 
 impl FetchError {
@@ -67,6 +74,8 @@ pub enum UploadError {
     DaemonConnectionFailed(io::Error),
     #[error("error parsing git command packet-line: {0}")]
     InvalidPacketLine(io::Error),
+    #[error("{remote} is not allowed to fetch {rid}")]
+    Unauthorized { rid: Id, remote: NodeId },
     #[error(transparent)]
     Io(#[from] io::Error),
 }
@@ -90,6 +99,8 @@ pub enum FetchRequest {
         namespaces: Namespaces,
         /// Remote peer we are interacting with.
         remote: NodeId,
+        /// Limits on the data accepted.
+        limits: FetchLimits,
     },
     /// Server is responding to a fetch request by uploading the
     /// specified `refspecs` sent by the client.
@@ -107,6 +118,15 @@ impl FetchRequest {
     }
 }
 
+/// Transfer statistics of a fetch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FetchStats {
+    /// Number of bytes received from the remote.
+    pub received: u64,
+    /// Time spent fetching.
+    pub elapsed: time::Duration,
+}
+
 /// Fetch result of an upload or fetch.
 #[derive(Debug)]
 pub enum FetchResult {
@@ -115,6 +135,12 @@ pub enum FetchResult {
         rid: Id,
         /// Fetch result, including remotes fetched.
         result: Result<(Vec<RefUpdate>, HashSet<NodeId>), FetchError>,
+        /// Content refused by the content policy.
+        refused: Vec<Refusal>,
+        /// Rewrite of the canonical branch history, if the fetch caused one.
+        rewrite: Option<Rewrite>,
+        /// Transfer statistics.
+        stats: FetchStats,
     },
     Responder {
         /// Upload result.
@@ -138,6 +164,24 @@ pub struct TaskResult {
     pub stream: StreamId,
 }
 
+/// Whether `remote` is allowed to fetch the given repository. Since the identity document
+/// is read on every fetch, changes to the allow-list of a private repository take effect
+/// right away.
+pub(crate) fn is_visible(storage: &Storage, rid: Id, remote: &NodeId) -> bool {
+    let visible = storage
+        .repository(rid)
+        .map_err(IdentityError::from)
+        .and_then(|repo| repo.is_visible_to(remote));
+
+    match visible {
+        Ok(visible) => visible,
+        Err(e) => {
+            log::warn!(target: "worker", "Couldn't get the visibility of {rid}: {e}");
+            false
+        }
+    }
+}
+
 /// A worker that replicates git objects.
 struct Worker {
     nid: NodeId,
@@ -148,6 +192,7 @@ struct Worker {
     handle: Handle,
     atomic: bool,
     name: String,
+    policy: ContentPolicy,
 }
 
 impl Worker {
@@ -195,11 +240,33 @@ impl Worker {
                 rid,
                 namespaces,
                 remote,
+                limits,
             } => {
                 log::debug!(target: "worker", "Worker processing outgoing fetch for {}", rid);
-                let result = self.fetch(rid, remote, stream, &namespaces, channels);
-
-                FetchResult::Initiator { rid, result }
+                let mut refused = Vec::new();
+                let mut rewrite = None;
+                let mut stats = FetchStats::default();
+                let start = time::Instant::now();
+                let result = self.fetch(
+                    rid,
+                    remote,
+                    stream,
+                    &namespaces,
+                    limits,
+                    channels,
+                    &mut refused,
+                    &mut rewrite,
+                    &mut stats,
+                );
+                stats.elapsed = start.elapsed();
+
+                FetchResult::Initiator {
+                    rid,
+                    result,
+                    refused,
+                    rewrite,
+                    stats,
+                }
             }
             FetchRequest::Responder { remote } => {
                 log::debug!(target: "worker", "Worker processing incoming fetch..");
@@ -221,14 +288,29 @@ impl Worker {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn fetch(
         &mut self,
         rid: Id,
         remote: NodeId,
         stream: StreamId,
         namespaces: &Namespaces,
+        limits: FetchLimits,
         mut channels: Channels,
+        refused: &mut Vec<Refusal>,
+        rewrite: &mut Option<Rewrite>,
+        stats: &mut FetchStats,
     ) -> Result<(Vec<RefUpdate>, HashSet<NodeId>), FetchError> {
+        if let Err(reason) = self.policy.check_repo(&rid) {
+            log::warn!(target: "worker", "Refusing to fetch {rid} from {remote}: {reason}");
+
+            refused.push(Refusal {
+                namespace: None,
+                reason: reason.clone(),
+            });
+            return Err(FetchError::Refused(reason));
+        }
+        let policy = self.policy.limited(&limits);
         let staging = fetch::StagingPhaseInitial::new(&self.storage, rid, namespaces.clone())?;
         match self._fetch(
             &staging.repo,
@@ -236,8 +318,21 @@ impl Worker {
             staging.refspecs(),
             stream,
             &mut channels,
+            limits.max_pack_size,
+            stats,
         ) {
-            Ok(()) => log::debug!(target: "worker", "Initial fetch for {rid} exited successfully"),
+            Ok(()) => {
+                log::debug!(target: "worker", "Initial fetch for {rid} exited successfully");
+            }
+            Err(FetchError::Refused(reason)) => {
+                log::warn!(target: "worker", "Refusing to fetch {rid} from {remote}: {reason}");
+
+                refused.push(Refusal {
+                    namespace: None,
+                    reason: reason.clone(),
+                });
+                return Err(FetchError::Refused(reason));
+            }
             Err(e) => match (&staging.repo, e) {
                 // When fetching, if the error comes from `git-fetch` returning an error, we
                 // keep going because it could be due to a rejected ref (eg. on `rad/sigrefs`),
@@ -256,22 +351,71 @@ impl Worker {
             },
         }
 
-        let staging = staging.into_final()?;
+        let mut staging = staging.into_final()?;
         match self._fetch(
             &staging.repo,
             remote,
             staging.refspecs(),
             stream,
             &mut channels,
+            limits.max_pack_size,
+            stats,
         ) {
-            Ok(()) => log::debug!(target: "worker", "Final fetch for {rid} exited successfully"),
+            Ok(()) => {
+                log::debug!(target: "worker", "Final fetch for {rid} exited successfully");
+            }
+            Err(FetchError::Refused(reason)) => {
+                log::warn!(target: "worker", "Refusing to fetch {rid} from {remote}: {reason}");
+
+                refused.push(Refusal {
+                    namespace: None,
+                    reason: reason.clone(),
+                });
+                return Err(FetchError::Refused(reason));
+            }
             Err(e) => {
                 log::error!(target: "worker", "Final fetch for {rid} failed: {e}");
                 return Err(e);
             }
         }
 
-        staging.transfer().map_err(FetchError::from)
+        for refusal in staging.refuse(&policy) {
+            if let Some(namespace) = refusal.namespace {
+                log::warn!(
+                    target: "worker",
+                    "Refusing namespace {namespace} of {rid} from {remote}: {}", refusal.reason
+                );
+            }
+            refused.push(refusal);
+        }
+        let (updated, namespaces, rewritten) = staging
+            .transfer(self.policy.allow_rewrites)
+            .map_err(FetchError::from)?;
+        self.publish(rid, &updated);
+        *rewrite = rewritten;
+
+        Ok((updated, namespaces))
+    }
+
+    /// Publish the remotes whose refs were updated by a fetch to the storage events journal.
+    fn publish(&self, rid: Id, updated: &[RefUpdate]) {
+        let journal = self.storage.journal();
+        let remotes = updated
+            .iter()
+            .filter_map(|update| match update {
+                RefUpdate::Updated { name, .. }
+                | RefUpdate::Created { name, .. }
+                | RefUpdate::Deleted { name, .. } => git::parse_ref_namespaced::<NodeId>(name).ok(),
+                RefUpdate::Skipped { .. } => None,
+            })
+            .map(|(remote, _)| remote)
+            .collect::<BTreeSet<_>>();
+
+        for remote in remotes {
+            if let Err(e) = journal.publish(&Event::RefsUpdated { rid, remote }) {
+                log::warn!(target: "worker", "Failed to publish storage event for {rid}: {e}");
+            }
+        }
     }
 
     fn upload_pack(
@@ -299,6 +443,10 @@ impl Worker {
         };
         log::debug!(target: "worker", "Received Git request pktline for {rid}..");
 
+        if !is_visible(&self.storage, rid, &remote) {
+            return Err(UploadError::Unauthorized { rid, remote });
+        }
+
         match self._upload_pack(rid, remote, request, stream, stream_r, stream_w) {
             Ok(()) => {
                 log::debug!(target: "worker", "Upload of {rid} to {remote} exited successfully");
@@ -359,8 +507,8 @@ impl Worker {
 
             let stream_to_daemon = s.spawn(move || {
                 stream_r
-                    .pipe(&mut daemon_w)
-                    .and_then(|()| daemon_w.shutdown(net::Shutdown::Both))
+                    .pipe(&mut daemon_w, None)
+                    .and_then(|_| daemon_w.shutdown(net::Shutdown::Both))
             });
 
             stream_to_daemon.join().unwrap()?;
@@ -370,6 +518,9 @@ impl Worker {
         })
     }
 
+    /// Run `git fetch` through a tunnel to the remote, adding the bytes received to `stats`.
+    /// The transfer is aborted and refused if the total received exceeds `max_pack_size`.
+    #[allow(clippy::too_many_arguments)]
     fn _fetch<S>(
         &self,
         repo: &fetch::StagedRepository,
@@ -377,6 +528,8 @@ impl Worker {
         specs: S,
         stream: StreamId,
         channels: &mut Channels,
+        max_pack_size: Option<u64>,
+        stats: &mut FetchStats,
     ) -> Result<(), FetchError>
     where
         S: fetch::AsRefspecs,
@@ -428,9 +581,14 @@ impl Worker {
             }
         })?;
 
-        tunnel.run(self.timeout)?;
+        let limit = max_pack_size.map(|max| max.saturating_sub(stats.received));
+        let received = tunnel.run(self.timeout, limit)?;
+        stats.received += received;
 
         let result = child.wait()?;
+        if let Some(max) = max_pack_size.filter(|max| stats.received > *max) {
+            return Err(FetchError::Refused(policy::Reason::PackTooLarge { max }));
+        }
         if result.success() {
             Ok(())
         } else {
@@ -478,6 +636,7 @@ impl Pool {
                 timeout: config.timeout,
                 name: config.name.clone(),
                 atomic: config.atomic,
+                policy: config.policy.clone(),
             };
             let thread = thread::Builder::new()
                 .name(config.name.clone())
@@ -101,11 +101,24 @@ impl<T: AsRef<[u8]>> ChannelReader<T> {
         }
     }
 
-    pub fn pipe<W: io::Write>(&mut self, mut writer: W) -> io::Result<()> {
+    /// Pipe the channel data into the writer, until the end of the stream. Returns the
+    /// number of bytes piped. If a `limit` is given, stops early once more than `limit`
+    /// bytes were received.
+    pub fn pipe<W: io::Write>(&mut self, mut writer: W, limit: Option<u64>) -> io::Result<u64> {
+        let mut piped = 0;
+
         loop {
             match self.receiver.recv_timeout(self.timeout) {
-                Ok(ChannelEvent::Data(data)) => writer.write_all(data.as_ref())?,
-                Ok(ChannelEvent::Eof) => return Ok(()),
+                Ok(ChannelEvent::Data(data)) => {
+                    piped += data.as_ref().len() as u64;
+                    // Stop piping as soon as the limit is exceeded, without writing the data
+                    // that exceeds it. It's up to the caller to abort the transfer.
+                    if limit.map_or(false, |max| piped > max) {
+                        return Ok(piped);
+                    }
+                    writer.write_all(data.as_ref())?;
+                }
+                Ok(ChannelEvent::Eof) => return Ok(piped),
                 Ok(ChannelEvent::Close) => return Err(io::ErrorKind::ConnectionReset.into()),
                 Err(chan::RecvTimeoutError::Timeout) => {
                     return Err(io::Error::new(
@@ -185,3 +198,27 @@ impl<T: AsRef<[u8]>> ChannelWriter<T> {
         self.sender.send(ChannelEvent::Close)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pipe_limit() {
+        let (sender, receiver) = chan::unbounded::<ChannelEvent>();
+        let mut reader = ChannelReader::new(receiver, time::Duration::from_secs(1));
+        let mut piped = Vec::new();
+
+        sender.send(vec![1; 8].into()).unwrap();
+        sender.send(vec![2; 8].into()).unwrap();
+        sender.send(ChannelEvent::Eof).unwrap();
+
+        // The data exceeding the limit isn't written, and piping stops there.
+        assert_eq!(reader.pipe(&mut piped, Some(12)).unwrap(), 16);
+        assert_eq!(piped, vec![1; 8]);
+
+        let mut piped = Vec::new();
+        assert_eq!(reader.pipe(&mut piped, None).unwrap(), 0);
+        assert!(piped.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ mod refspecs;
 pub use refspecs::{AsRefspecs, Refspec, SpecialRefs};
 
 pub mod error;
+pub mod policy;
 
 use std::collections::{BTreeMap, HashSet};
 use std::ops::Deref;
@@ -11,10 +12,12 @@ use radicle::git::url;
 use radicle::prelude::{Doc, Id, NodeId};
 use radicle::storage::git::Repository;
 use radicle::storage::refs::IDENTITY_BRANCH;
-use radicle::storage::{Namespaces, RefUpdate, Remote, RemoteId};
+use radicle::storage::{Namespaces, RefUpdate, Remote, RemoteId, Rewrite};
 use radicle::storage::{ReadRepository, ReadStorage, WriteRepository, WriteStorage};
 use radicle::{git, Storage};
 
+use policy::{ContentPolicy, Refusal};
+
 /// The initial phase of staging a fetch from a remote.
 ///
 /// The [`StagingPhaseInitial::refpsecs`] generated are to fetch the
@@ -244,7 +247,14 @@ impl<'a> StagingPhaseFinal<'a> {
     ///
     /// All references that were updated are returned as a
     /// [`RefUpdate`].
-    pub fn transfer(self) -> Result<(Vec<RefUpdate>, HashSet<NodeId>), error::Transfer> {
+    ///
+    /// Finally, the canonical head is set, unless it rewrites the history of the canonical
+    /// branch, and the rewrite is neither allowed by `allow_rewrites`, nor acknowledged in
+    /// the identity document. The rewrite, if any, is returned along with the updates.
+    pub fn transfer(
+        self,
+        allow_rewrites: bool,
+    ) -> Result<(Vec<RefUpdate>, HashSet<NodeId>, Option<Rewrite>), error::Transfer> {
         let verifications = self.verify();
         let production = match &self.repo {
             StagedRepository::Cloning(repo) => self.production.create(repo.id)?,
@@ -364,13 +374,42 @@ impl<'a> StagingPhaseFinal<'a> {
             }
             fetching
         };
-        let head = production.set_head()?;
+        let (head, rewrite) = production.set_head_protected(allow_rewrites)?;
         log::debug!(target: "worker", "Head for {} set to {head}", production.id);
 
         let head = production.set_identity_head()?;
         log::debug!(target: "worker", "'refs/rad/id' for {} set to {head}", production.id);
 
-        Ok((updates, remotes))
+        Ok((updates, remotes, rewrite))
+    }
+
+    /// Apply the content policy to the fetched namespaces. Refused namespaces are
+    /// not transferred into storage.
+    pub fn refuse(&mut self, policy: &ContentPolicy) -> Vec<Refusal> {
+        let production = self.production.repository(self.repo.id).ok();
+        let mut refused = Vec::new();
+
+        for remote in self.remotes() {
+            // Objects that are already in storage don't need to be checked again.
+            let known = production
+                .as_ref()
+                .and_then(|p| p.remote(&remote.id).ok())
+                .map(|r| r.refs.iter().map(|(_, oid)| *oid).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let tips = remote.refs.iter().map(|(_, oid)| *oid);
+
+            if let Err(reason) = policy.check_namespace(&remote.id, &self.repo.backend, tips, known)
+            {
+                refused.push(Refusal {
+                    namespace: Some(remote.id),
+                    reason,
+                });
+            }
+        }
+        for namespace in refused.iter().filter_map(|r| r.namespace.as_ref()) {
+            self.trusted.remove(namespace);
+        }
+        refused
     }
 
     fn remotes(&self) -> impl Iterator<Item = Remote> + '_ {
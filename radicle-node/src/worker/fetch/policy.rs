@@ -0,0 +1,256 @@
+//! Content policy applied to fetched repositories, before they are stored.
+//!
+//! Seed operators use this to refuse content they don't want to host. Repositories
+//! are matched against allow and deny patterns before anything is fetched, and each
+//! fetched namespace is checked against the blocked nodes and the maximum object size
+//! before it is transferred from staging into storage.
+//!
+//! Tracking policies may further limit the pack size and maximum object size accepted
+//! for a given repository. Fetches exceeding the pack size are aborted mid-transfer.
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use radicle::git;
+use radicle::glob;
+use radicle::node::tracking::FetchLimits;
+use radicle::prelude::{Id, NodeId};
+
+/// Content policy configuration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContentPolicy {
+    /// Maximum size of a single git object, in bytes. Namespaces containing
+    /// a larger object are refused.
+    pub max_object_size: Option<u64>,
+    /// Nodes whose namespaces are refused.
+    pub blocked: HashSet<NodeId>,
+    /// If not empty, only repositories matching one of these patterns are fetched.
+    pub allow: Vec<Pattern>,
+    /// Repositories matching one of these patterns are refused.
+    pub deny: Vec<Pattern>,
+    /// Whether to adopt canonical heads that rewrite the history of the canonical branch,
+    /// even if the rewrite isn't acknowledged by the delegates in the identity document.
+    pub allow_rewrites: bool,
+}
+
+impl ContentPolicy {
+    /// Check whether the given repository may be fetched.
+    pub fn check_repo(&self, rid: &Id) -> Result<(), Reason> {
+        if self.deny.iter().any(|p| p.matches(rid)) {
+            return Err(Reason::Denied);
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| p.matches(rid)) {
+            return Err(Reason::NotAllowed);
+        }
+        Ok(())
+    }
+
+    /// Get the policy to apply to a repository with the given fetch limits. The repository's
+    /// maximum object size applies if it is lower than ours.
+    pub fn limited(&self, limits: &FetchLimits) -> Self {
+        let max_object_size = match (self.max_object_size, limits.max_object_size) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            max_object_size,
+            ..self.clone()
+        }
+    }
+
+    /// Check whether a fetched namespace may be stored.
+    ///
+    /// Only objects reachable from `tips` and not from `known` are checked for their size.
+    pub fn check_namespace(
+        &self,
+        namespace: &NodeId,
+        repo: &git::raw::Repository,
+        tips: impl IntoIterator<Item = git::Oid>,
+        known: impl IntoIterator<Item = git::Oid>,
+    ) -> Result<(), Reason> {
+        if self.blocked.contains(namespace) {
+            return Err(Reason::Blocked);
+        }
+        if let Some(max) = self.max_object_size {
+            if let Some((oid, size)) = find_large_object(repo, tips, known, max)
+                .map_err(|e| Reason::Unreadable(e.message().to_owned()))?
+            {
+                return Err(Reason::ObjectTooLarge { oid, size, max });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A repository pattern. The `*` character matches any sequence of characters.
+///
+/// Patterns are matched against the repository URN, eg. `rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(String);
+
+impl Pattern {
+    /// Check whether the pattern matches the given repository.
+    pub fn matches(&self, rid: &Id) -> bool {
+        glob::matches(&self.0, &rid.urn())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Content refused by the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refusal {
+    /// The refused namespace, or `None` if the whole repository was refused.
+    pub namespace: Option<NodeId>,
+    /// Why the content was refused.
+    pub reason: Reason,
+}
+
+/// Reason for refusing content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The repository matches a deny pattern.
+    Denied,
+    /// The repository doesn't match any allow pattern.
+    NotAllowed,
+    /// The namespace belongs to a blocked node.
+    Blocked,
+    /// The namespace contains an object exceeding the maximum size.
+    ObjectTooLarge { oid: git::Oid, size: u64, max: u64 },
+    /// The fetch transferred more data than the maximum pack size.
+    PackTooLarge { max: u64 },
+    /// The fetched objects could not be inspected.
+    Unreadable(String),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Denied => write!(f, "repository is denied by policy"),
+            Self::NotAllowed => write!(f, "repository is not allowed by policy"),
+            Self::Blocked => write!(f, "node is blocked by policy"),
+            Self::ObjectTooLarge { oid, size, max } => write!(
+                f,
+                "object {oid} is {size} bytes, exceeding the maximum of {max} bytes"
+            ),
+            Self::PackTooLarge { max } => {
+                write!(f, "fetch exceeded the maximum pack size of {max} bytes")
+            }
+            Self::Unreadable(err) => write!(f, "fetched objects could not be inspected: {err}"),
+        }
+    }
+}
+
+/// Find an object larger than `max`, reachable from `tips` but not from `known`.
+fn find_large_object(
+    repo: &git::raw::Repository,
+    tips: impl IntoIterator<Item = git::Oid>,
+    known: impl IntoIterator<Item = git::Oid>,
+    max: u64,
+) -> Result<Option<(git::Oid, u64)>, git::raw::Error> {
+    let odb = repo.odb()?;
+    let mut walk = repo.revwalk()?;
+
+    for oid in tips {
+        // Nb. Refs that don't point to commits, eg. some tags, are only checked themselves.
+        match repo.find_object(*oid, None)?.peel_to_commit() {
+            Ok(commit) => walk.push(commit.id())?,
+            Err(_) => {
+                let (size, _) = odb.read_header(*oid)?;
+                if size as u64 > max {
+                    return Ok(Some((oid, size as u64)));
+                }
+            }
+        }
+    }
+    for oid in known {
+        // Known tips may have been deleted or force-pushed over; ignore them if missing.
+        walk.hide(*oid).ok();
+    }
+
+    let mut seen = HashSet::new();
+    let mut found = None;
+
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+
+        if !seen.insert(tree.id()) {
+            continue;
+        }
+        tree.walk(git::raw::TreeWalkMode::PreOrder, |_, entry| {
+            // Skip trees we've already inspected through other commits.
+            if entry.kind() == Some(git::raw::ObjectType::Tree) && !seen.insert(entry.id()) {
+                return git::raw::TreeWalkResult::Skip;
+            }
+            if entry.kind() == Some(git::raw::ObjectType::Blob) {
+                match odb.read_header(entry.id()) {
+                    Ok((size, _)) if size as u64 > max => {
+                        found = Some((entry.id().into(), size as u64));
+                        return git::raw::TreeWalkResult::Abort;
+                    }
+                    _ => {}
+                }
+            }
+            git::raw::TreeWalkResult::Ok
+        })
+        .ok();
+
+        if found.is_some() {
+            break;
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_repo() {
+        let rid = Id::from_urn("rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5").unwrap();
+        let mut policy = ContentPolicy::default();
+
+        assert_eq!(policy.check_repo(&rid), Ok(()));
+
+        policy.allow.push("rad:z4*".parse().unwrap());
+        assert_eq!(policy.check_repo(&rid), Err(Reason::NotAllowed));
+
+        policy.allow.push("rad:z3*".parse().unwrap());
+        assert_eq!(policy.check_repo(&rid), Ok(()));
+
+        policy.deny.push(rid.urn().parse().unwrap());
+        assert_eq!(policy.check_repo(&rid), Err(Reason::Denied));
+    }
+
+    #[test]
+    fn test_limited() {
+        let mut policy = ContentPolicy::default();
+        let limits = FetchLimits {
+            max_pack_size: None,
+            max_object_size: Some(1024),
+        };
+        assert_eq!(policy.limited(&limits).max_object_size, Some(1024));
+
+        policy.max_object_size = Some(512);
+        assert_eq!(policy.limited(&limits).max_object_size, Some(512));
+        assert_eq!(
+            policy.limited(&FetchLimits::default()).max_object_size,
+            Some(512)
+        );
+    }
+}
@@ -43,8 +43,12 @@ impl<'a> Tunnel<'a> {
         self.local_addr
     }
 
-    /// Run the tunnel until the connection is closed.
-    pub fn run(&mut self, timeout: time::Duration) -> io::Result<()> {
+    /// Run the tunnel until the connection is closed. Returns the number of bytes received
+    /// from the remote.
+    ///
+    /// If more than `limit` bytes are received, the local connection is shut down, which
+    /// aborts the fetch, and the number of bytes received so far is returned.
+    pub fn run(&mut self, timeout: time::Duration, limit: Option<u64>) -> io::Result<u64> {
         let (remote_w, remote_r) = self.channels.split();
         let (local, _) = self.listener.accept()?;
         let (mut local_r, local_w) = (local.try_clone()?, local);
@@ -58,7 +62,13 @@ impl<'a> Tunnel<'a> {
         thread::scope(|s| {
             let remote_to_local = thread::Builder::new()
                 .name(self.local.to_string())
-                .spawn_scoped(s, || remote_r.pipe(local_w))?;
+                .spawn_scoped(s, || {
+                    let received = remote_r.pipe(&local_w, limit)?;
+                    if limit.map_or(false, |max| received > max) {
+                        local_w.shutdown(net::Shutdown::Both)?;
+                    }
+                    Ok::<u64, io::Error>(received)
+                })?;
 
             let local_to_remote = thread::Builder::new()
                 .name(self.local.to_string())
@@ -85,10 +95,10 @@ impl<'a> Tunnel<'a> {
                     Worker::eof(nid, stream_id, remote_w, &mut self.handle)
                 })?;
 
-            remote_to_local.join().unwrap()?;
+            let received = remote_to_local.join().unwrap()?;
             local_to_remote.join().unwrap()?;
 
-            Ok::<(), io::Error>(())
+            Ok::<u64, io::Error>(received)
         })
     }
 }
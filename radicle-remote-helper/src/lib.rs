@@ -6,8 +6,12 @@ use std::{env, io, process};
 use thiserror::Error;
 
 use radicle::crypto::PublicKey;
+use radicle::git;
+use radicle::identity::policy::{Policy, ProtectionError, SignoffError};
+use radicle::identity::Did;
 use radicle::node::Handle;
 use radicle::storage::git::transport::local::{Url, UrlError};
+use radicle::storage::git::Repository;
 use radicle::storage::{ReadRepository, WriteRepository, WriteStorage};
 
 /// The service invoked by git on the remote repository, during a push.
@@ -38,6 +42,80 @@ pub enum Error {
     /// Error with the remote url.
     #[error("invalid remote url: {0}")]
     RemoteUrl(#[from] UrlError),
+    /// Pushed commits don't satisfy the repository policy.
+    #[error("push to default branch rejected: {0}")]
+    Policy(#[from] SignoffError),
+    /// The default branch is protected, and the pushed commit isn't an approved patch merge.
+    #[error("push to default branch rejected: {0}")]
+    Protection(#[from] ProtectionError),
+}
+
+/// The default branch of the repository, in the namespace being pushed to.
+struct DefaultBranch {
+    /// Fully qualified, namespaced branch name.
+    name: git::Namespaced<'static>,
+    /// Head of the branch before the push.
+    head: Option<git::Oid>,
+    /// Repository policy.
+    policy: Policy,
+    /// Repository delegates.
+    delegates: Vec<Did>,
+}
+
+impl DefaultBranch {
+    fn load(
+        proj: &Repository,
+        namespace: &PublicKey,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static>> {
+        let (_, doc) = proj.identity_doc()?;
+        let doc = doc.verified()?;
+        let project = doc.project()?;
+        let name = git::refs::storage::branch(namespace, project.default_branch());
+        let head = proj.backend.refname_to_id(&name).ok().map(git::Oid::from);
+
+        Ok(Self {
+            name,
+            head,
+            policy: doc.policy()?,
+            delegates: doc.delegates.into(),
+        })
+    }
+
+    /// Check the commits pushed to the branch against the repository policy. If they
+    /// don't satisfy it, the branch is reset to its previous head.
+    fn check(&self, proj: &Repository) -> Result<(), Box<dyn std::error::Error + 'static>> {
+        let Ok(new) = proj.backend.refname_to_id(&self.name) else {
+            return Ok(());
+        };
+        if Some(git::Oid::from(new)) == self.head {
+            return Ok(());
+        }
+        if let Err(err) = self.verify(proj, new.into()) {
+            match self.head {
+                Some(old) => {
+                    proj.backend.reference(
+                        &self.name,
+                        *old,
+                        true,
+                        "Reset by repository policy",
+                    )?;
+                }
+                None => {
+                    proj.backend.find_reference(&self.name)?.delete()?;
+                }
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Verify that the branch can be updated to the given head.
+    fn verify(&self, proj: &Repository, head: git::Oid) -> Result<(), Error> {
+        self.policy.check(&proj.backend, self.head, head)?;
+        self.policy.check_protection(proj, head, &self.delegates)?;
+
+        Ok(())
+    }
 }
 
 /// Run the radicle remote helper using the given profile.
@@ -102,6 +180,15 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
                     None
                 };
 
+                // Git doesn't give us the commits before they are received, so we remember the
+                // default branch head, and check the pushed commits once the push is done.
+                let default_branch = match url.namespace {
+                    Some(namespace) if *service == GIT_RECEIVE_PACK => {
+                        Some(DefaultBranch::load(&proj, &namespace)?)
+                    }
+                    _ => None,
+                };
+
                 if *service == GIT_UPLOAD_PACK {
                     // TODO: Fetch from network.
                 }
@@ -120,6 +207,9 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
                     .spawn()?;
 
                 if child.wait()?.success() && *service == GIT_RECEIVE_PACK {
+                    if let Some(branch) = &default_branch {
+                        branch.check(&proj)?;
+                    }
                     if let Some(signer) = signer {
                         proj.sign_refs(&signer)?;
                         proj.set_head()?;
@@ -1,4 +1,4 @@
-use crate::Paint;
+use crate::{theme, Color, Paint};
 
 pub fn default<D: std::fmt::Display>(msg: D) -> Paint<D> {
     Paint::new(msg)
@@ -9,44 +9,52 @@ pub fn wrap<D: std::fmt::Display>(msg: D) -> Paint<D> {
 }
 
 pub fn negative<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::red(msg).bold()
+    Paint::new(msg).fg(theme::current().negative).bold()
 }
 
 pub fn positive<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::green(msg).bold()
+    Paint::new(msg).fg(theme::current().positive).bold()
 }
 
 pub fn primary<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::magenta(msg)
+    Paint::new(msg).fg(theme::current().primary)
 }
 
 pub fn secondary<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::blue(msg).bold()
+    Paint::new(msg).fg(theme::current().secondary).bold()
 }
 
 pub fn tertiary<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::cyan(msg)
+    Paint::new(msg).fg(theme::current().tertiary)
 }
 
 pub fn tertiary_bold<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::cyan(msg).bold()
+    Paint::new(msg).fg(theme::current().tertiary).bold()
 }
 
 pub fn yellow<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::yellow(msg)
+    Paint::new(msg).fg(theme::current().warning)
 }
 
 pub fn faint<D: std::fmt::Display>(msg: D) -> Paint<D> {
-    Paint::fixed(236, msg)
+    let faint = theme::current().faint;
+
+    if faint == Color::Unset {
+        Paint::new(msg).dim()
+    } else {
+        Paint::new(msg).fg(faint)
+    }
 }
 
 pub fn highlight<D: std::fmt::Debug + std::fmt::Display>(input: D) -> Paint<D> {
-    Paint::green(input).bold()
+    Paint::new(input).fg(theme::current().positive).bold()
 }
 
 pub fn badge_primary<D: std::fmt::Display>(input: D) -> Paint<String> {
     if Paint::is_enabled() {
-        Paint::magenta(format!(" {input} ")).invert()
+        Paint::new(format!(" {input} "))
+            .fg(theme::current().primary)
+            .invert()
     } else {
         Paint::new(format!("❲{input}❳"))
     }
@@ -54,7 +62,9 @@ pub fn badge_primary<D: std::fmt::Display>(input: D) -> Paint<String> {
 
 pub fn badge_positive<D: std::fmt::Display>(input: D) -> Paint<String> {
     if Paint::is_enabled() {
-        Paint::green(format!(" {input} ")).invert()
+        Paint::new(format!(" {input} "))
+            .fg(theme::current().positive)
+            .invert()
     } else {
         Paint::new(format!("❲{input}❳"))
     }
@@ -62,7 +72,9 @@ pub fn badge_positive<D: std::fmt::Display>(input: D) -> Paint<String> {
 
 pub fn badge_negative<D: std::fmt::Display>(input: D) -> Paint<String> {
     if Paint::is_enabled() {
-        Paint::red(format!(" {input} ")).invert()
+        Paint::new(format!(" {input} "))
+            .fg(theme::current().negative)
+            .invert()
     } else {
         Paint::new(format!("❲{input}❳"))
     }
@@ -70,14 +82,16 @@ pub fn badge_negative<D: std::fmt::Display>(input: D) -> Paint<String> {
 
 pub fn badge_secondary<D: std::fmt::Display>(input: D) -> Paint<String> {
     if Paint::is_enabled() {
-        Paint::blue(format!(" {input} ")).invert()
+        Paint::new(format!(" {input} "))
+            .fg(theme::current().secondary)
+            .invert()
     } else {
         Paint::new(format!("❲{input}❳"))
     }
 }
 
 pub fn bold<D: std::fmt::Display>(input: D) -> Paint<D> {
-    Paint::white(input).bold()
+    Paint::new(input).fg(theme::current().strong).bold()
 }
 
 pub fn dim<D: std::fmt::Display>(input: D) -> Paint<D> {
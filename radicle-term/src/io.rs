@@ -9,7 +9,7 @@ use zeroize::Zeroizing;
 
 use crate::command;
 use crate::format;
-use crate::{style, Paint};
+use crate::{style, theme, Paint, Theme};
 
 // TODO: Try not to export this.
 pub use inquire::Select;
@@ -23,16 +23,30 @@ pub const TAB: &str = "    ";
 pub type Passphrase = Zeroizing<String>;
 
 /// Render configuration.
-pub static CONFIG: Lazy<RenderConfig> = Lazy::new(|| RenderConfig {
-    prompt: StyleSheet::new().with_fg(Color::LightCyan),
-    prompt_prefix: Styled::new("?").with_fg(Color::LightBlue),
-    answered_prompt_prefix: Styled::new("✓").with_fg(Color::LightGreen),
-    answer: StyleSheet::new(),
-    highlighted_option_prefix: Styled::new("*").with_fg(Color::LightYellow),
-    help_message: StyleSheet::new().with_fg(Color::DarkGrey),
-    error_message: ErrorMessageRenderConfig::default_colored()
-        .with_prefix(Styled::new("✗").with_fg(Color::LightRed)),
-    ..RenderConfig::default_colored()
+///
+/// Prompts are rendered without colors if colors are disabled, or if the
+/// monochrome theme is active.
+pub static CONFIG: Lazy<RenderConfig> = Lazy::new(|| {
+    if !Paint::is_enabled() || theme::current() == Theme::MONOCHROME {
+        return RenderConfig {
+            prompt_prefix: Styled::new("?"),
+            answered_prompt_prefix: Styled::new("✓"),
+            highlighted_option_prefix: Styled::new("*"),
+            error_message: ErrorMessageRenderConfig::empty().with_prefix(Styled::new("✗")),
+            ..RenderConfig::empty()
+        };
+    }
+    RenderConfig {
+        prompt: StyleSheet::new().with_fg(Color::LightCyan),
+        prompt_prefix: Styled::new("?").with_fg(Color::LightBlue),
+        answered_prompt_prefix: Styled::new("✓").with_fg(Color::LightGreen),
+        answer: StyleSheet::new(),
+        highlighted_option_prefix: Styled::new("*").with_fg(Color::LightYellow),
+        help_message: StyleSheet::new().with_fg(Color::DarkGrey),
+        error_message: ErrorMessageRenderConfig::default_colored()
+            .with_prefix(Styled::new("✗").with_fg(Color::LightRed)),
+        ..RenderConfig::default_colored()
+    }
 });
 
 #[macro_export]
@@ -80,10 +94,19 @@ pub fn notice_args(args: fmt::Arguments) {
     println!("{} {args}", Paint::new("!").dim());
 }
 
+/// Check whether both standard input and standard output are attached to a terminal.
+pub fn is_terminal() -> bool {
+    termion::is_tty(&std::io::stdin()) && termion::is_tty(&std::io::stdout())
+}
+
 pub fn columns() -> Option<usize> {
     termion::terminal_size().map(|(cols, _)| cols as usize).ok()
 }
 
+pub fn rows() -> Option<usize> {
+    termion::terminal_size().map(|(_, rows)| rows as usize).ok()
+}
+
 pub fn headline(headline: impl fmt::Display) {
     println!();
     println!("{}", style(headline).bold());
@@ -241,8 +264,46 @@ where
     }
 }
 
+/// Check whether all characters of `input` appear in `text`, in order, ignoring case.
+pub fn fuzzy_match(input: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+
+    input
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|c| text.any(|t| t == c))
+}
+
+/// Select one of the given options, filtering them with [`fuzzy_match`] as the user types.
+/// Returns `None` if the prompt was skipped.
+pub fn select_fuzzy<T>(prompt: &str, options: Vec<T>) -> Result<Option<T>, InquireError>
+where
+    T: fmt::Display,
+{
+    Select::new(prompt, options)
+        .with_render_config(*CONFIG)
+        .with_filter(&|input, _, value, _| fuzzy_match(input, value))
+        .with_help_message("↑↓ to move, type to search, enter to select")
+        .prompt_skippable()
+}
+
 pub fn markdown(content: &str) {
     if !content.is_empty() && command::bat(["-p", "-l", "md"], content).is_err() {
         blob(content);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("", "Fix the bug"));
+        assert!(fuzzy_match("fix", "Fix the bug"));
+        assert!(fuzzy_match("ftb", "Fix the bug"));
+        assert!(fuzzy_match("BUG", "Fix the bug"));
+        assert!(!fuzzy_match("bugs", "Fix the bug"));
+        assert!(!fuzzy_match("bf", "Fix the bug"));
+    }
+}
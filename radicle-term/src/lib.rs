@@ -8,9 +8,11 @@ pub mod format;
 pub mod hstack;
 pub mod io;
 pub mod label;
+pub mod pager;
 pub mod spinner;
 pub mod table;
 pub mod textarea;
+pub mod theme;
 pub mod vstack;
 
 pub use ansi::Color;
@@ -24,6 +26,7 @@ pub use label::{label, Label};
 pub use spinner::{spinner, Spinner};
 pub use table::Table;
 pub use textarea::{textarea, TextArea};
+pub use theme::Theme;
 pub use vstack::{VStack, VStackOptions};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -0,0 +1,74 @@
+//! Display long output through the user's pager, like git does.
+use std::io::Write;
+use std::{env, io, process};
+
+use crate::element::Element;
+
+/// Environment variable used to set the pager. Takes precedence over `PAGER`.
+pub const RAD_PAGER: &str = "RAD_PAGER";
+/// Environment variable used to set the pager, shared with other programs.
+pub const PAGER: &str = "PAGER";
+/// Pager used when none is set.
+pub const DEFAULT_PAGER: &str = "less";
+
+/// Get the pager command from the environment. Returns `None` if paging was
+/// disabled, by setting the pager to an empty string or `cat`.
+pub fn command() -> Option<String> {
+    let pager = env::var(RAD_PAGER)
+        .or_else(|_| env::var(PAGER))
+        .unwrap_or_else(|_| DEFAULT_PAGER.to_owned());
+    let pager = pager.trim();
+
+    if pager.is_empty() || pager == "cat" {
+        None
+    } else {
+        Some(pager.to_owned())
+    }
+}
+
+/// Display the given text through the user's pager.
+///
+/// The pager is only used if the output is a terminal, and the text doesn't fit
+/// on the screen. Otherwise, the text is printed as-is.
+pub fn page(text: &str) -> io::Result<()> {
+    let fits = crate::io::rows().map_or(true, |rows| text.lines().count() < rows);
+
+    match command() {
+        Some(pager) if !fits && termion::is_tty(&io::stdout()) => spawn(&pager, text),
+        _ => {
+            let mut stdout = io::stdout().lock();
+
+            stdout.write_all(text.as_bytes())?;
+            stdout.flush()
+        }
+    }
+}
+
+/// Display the given element through the user's pager. See [`page`].
+pub fn page_element(element: &impl Element) -> io::Result<()> {
+    page(&element.display())
+}
+
+/// Run the pager and write the text to its standard input.
+fn spawn(pager: &str, text: &str) -> io::Result<()> {
+    // Like git, keep colors and quit if the text fits on one screen, unless the
+    // user configured `less` otherwise.
+    let less = env::var("LESS").unwrap_or_else(|_| String::from("FRX"));
+    let mut child = process::Command::new("sh")
+        .arg("-c")
+        .arg(pager)
+        .env("LESS", less)
+        .stdin(process::Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(text.as_bytes()) {
+            // The user may quit the pager before all the text was written.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    child.wait()?;
+
+    Ok(())
+}
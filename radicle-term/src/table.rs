@@ -50,7 +50,7 @@ impl Default for TableOptions {
 impl TableOptions {
     pub fn bordered() -> Self {
         Self {
-            border: Some(term::colors::FAINT),
+            border: Some(term::theme::current().faint),
             spacing: 3,
             ..Self::default()
         }
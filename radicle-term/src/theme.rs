@@ -0,0 +1,212 @@
+//! Color themes and color output settings.
+//!
+//! The functions in [`crate::format`] don't use fixed colors, but look up the colors to use
+//! in the active [`Theme`]. Whether colors are output at all is controlled separately, via
+//! [`set_color`].
+use std::str::FromStr;
+use std::sync::{PoisonError, RwLock};
+use std::{env, fmt};
+
+use crate::ansi::Color;
+
+/// Environment variable used to select a color theme.
+pub const RAD_THEME: &str = "RAD_THEME";
+/// Environment variable used to disable colored output. See <https://no-color.org>.
+pub const NO_COLOR: &str = "NO_COLOR";
+
+/// The active theme.
+static THEME: RwLock<Theme> = RwLock::new(Theme::DEFAULT);
+
+/// A color scheme for terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Name of the theme, used to select it.
+    pub name: &'static str,
+    /// Color used for success.
+    pub positive: Color,
+    /// Color used for errors.
+    pub negative: Color,
+    /// Color used for warnings.
+    pub warning: Color,
+    /// Main accent color.
+    pub primary: Color,
+    /// Secondary accent color.
+    pub secondary: Color,
+    /// Tertiary accent color.
+    pub tertiary: Color,
+    /// Color of emphasized text.
+    pub strong: Color,
+    /// Color of de-emphasized text and borders. When unset, text is dimmed instead.
+    pub faint: Color,
+}
+
+impl Theme {
+    /// The default theme.
+    pub const DEFAULT: Self = Self {
+        name: "default",
+        positive: Color::Green,
+        negative: Color::Red,
+        warning: Color::Yellow,
+        primary: Color::Magenta,
+        secondary: Color::Blue,
+        tertiary: Color::Cyan,
+        strong: Color::White,
+        faint: Color::Fixed(236),
+    };
+
+    /// A theme using bright colors, for better legibility.
+    pub const HIGH_CONTRAST: Self = Self {
+        name: "high-contrast",
+        positive: Color::Fixed(10),
+        negative: Color::Fixed(9),
+        warning: Color::Fixed(11),
+        primary: Color::Fixed(13),
+        secondary: Color::Fixed(12),
+        tertiary: Color::Fixed(14),
+        strong: Color::Fixed(15),
+        faint: Color::Fixed(248),
+    };
+
+    /// A theme without colors. Text attributes such as bold are still output.
+    pub const MONOCHROME: Self = Self {
+        name: "monochrome",
+        positive: Color::Unset,
+        negative: Color::Unset,
+        warning: Color::Unset,
+        primary: Color::Unset,
+        secondary: Color::Unset,
+        tertiary: Color::Unset,
+        strong: Color::Unset,
+        faint: Color::Unset,
+    };
+
+    /// All available themes.
+    pub const ALL: [Self; 3] = [Self::DEFAULT, Self::HIGH_CONTRAST, Self::MONOCHROME];
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = UnknownTheme;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name == s)
+            .ok_or_else(|| UnknownTheme(s.to_owned()))
+    }
+}
+
+/// Error returned when a theme name is not recognized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTheme(pub String);
+
+impl fmt::Display for UnknownTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let themes = Theme::ALL.map(|t| t.name).join(", ");
+
+        write!(f, "unknown theme '{}', expected one of: {themes}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTheme {}
+
+/// When to output colors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Output colors if the output is a terminal and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    /// Always output colors.
+    Always,
+    /// Never output colors.
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "invalid color mode '{s}', expected 'always', 'auto' or 'never'"
+            )),
+        }
+    }
+}
+
+/// Get the active theme.
+pub fn current() -> Theme {
+    *THEME.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Set the active theme.
+pub fn set(theme: Theme) {
+    *THEME.write().unwrap_or_else(PoisonError::into_inner) = theme;
+}
+
+/// Set when to output colors.
+pub fn set_color(mode: ColorMode) {
+    let choice = match mode {
+        ColorMode::Always => concolor::ColorChoice::Always,
+        ColorMode::Never => concolor::ColorChoice::Never,
+        ColorMode::Auto if no_color() => concolor::ColorChoice::Never,
+        ColorMode::Auto => concolor::ColorChoice::Auto,
+    };
+    concolor::set(choice);
+}
+
+/// Configure colors from the environment and the given color mode.
+///
+/// The theme is read from `RAD_THEME`.
+pub fn init(mode: ColorMode) -> Result<(), UnknownTheme> {
+    set_color(mode);
+
+    match env::var(RAD_THEME) {
+        Ok(name) if !name.is_empty() => set(name.parse()?),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether `NO_COLOR` is set to a non-empty value.
+fn no_color() -> bool {
+    env::var_os(NO_COLOR).map_or(false, |v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_str() {
+        for theme in Theme::ALL {
+            assert_eq!(theme.name.parse::<Theme>(), Ok(theme));
+        }
+        assert_eq!(
+            "solarized".parse::<Theme>(),
+            Err(UnknownTheme(String::from("solarized")))
+        );
+    }
+
+    #[test]
+    fn test_color_mode_from_str() {
+        assert_eq!("always".parse::<ColorMode>(), Ok(ColorMode::Always));
+        assert_eq!("auto".parse::<ColorMode>(), Ok(ColorMode::Auto));
+        assert_eq!("never".parse::<ColorMode>(), Ok(ColorMode::Never));
+        assert!("sometimes".parse::<ColorMode>().is_err());
+    }
+}
@@ -0,0 +1,58 @@
+//! Benchmarks for storage and COB hot paths.
+//!
+//! Run with `cargo bench -p radicle --features test`.
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use radicle::cob::issue::Issues;
+use radicle::cob::patch::Patches;
+use radicle::crypto::test::signer::MockSigner;
+use radicle::crypto::Signer;
+use radicle::storage::git::Storage;
+use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
+use radicle::test::fixtures;
+
+fn storage(c: &mut Criterion) {
+    let tmp = tempfile::tempdir().unwrap();
+    let signer = MockSigner::default();
+    let storage = Storage::open(tmp.path().join("storage")).unwrap();
+    let shape = fixtures::Shape {
+        commits: 32,
+        branches: 16,
+        issues: 32,
+        patches: 16,
+        ..fixtures::Shape::default()
+    };
+    let (rid, _) = fixtures::generate(tmp.path().join("acme"), &shape, &storage, &signer);
+    let repo = storage.repository(rid).unwrap();
+    let remote = repo.remote(signer.public_key()).unwrap();
+
+    c.bench_function("validate_remote", |b| {
+        b.iter(|| repo.validate_remote(&remote).unwrap())
+    });
+    c.bench_function("sign_refs", |b| b.iter(|| repo.sign_refs(&signer).unwrap()));
+    c.bench_function("inventory", |b| b.iter(|| storage.inventory().unwrap()));
+}
+
+fn cob(c: &mut Criterion) {
+    let tmp = tempfile::tempdir().unwrap();
+    let signer = MockSigner::default();
+    let storage = Storage::open(tmp.path().join("storage")).unwrap();
+    let shape = fixtures::Shape {
+        issues: 64,
+        patches: 16,
+        branches: 16,
+        ..fixtures::Shape::default()
+    };
+    let (rid, _) = fixtures::generate(tmp.path().join("acme"), &shape, &storage, &signer);
+    let repo = storage.repository(rid).unwrap();
+
+    c.bench_function("issues replay", |b| {
+        b.iter(|| Issues::open(&repo).unwrap().all().unwrap().count())
+    });
+    c.bench_function("patches replay", |b| {
+        b.iter(|| Patches::open(&repo).unwrap().all().unwrap().count())
+    });
+}
+
+criterion_group!(benches, storage, cob);
+criterion_main!(benches);
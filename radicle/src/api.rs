@@ -0,0 +1,9 @@
+//! High-level operations on patches and issues.
+//!
+//! These build on the collaborative object stores in [`crate::cob`], and take care of
+//! the details that every client would otherwise have to repeat, such as finding the
+//! merge base of a patch, checking the repository policy or picking the latest patch
+//! revision. They are meant to be shared by the CLI, the HTTP daemon, and any other
+//! tool that operates on a repository in storage.
+pub mod issue;
+pub mod patch;
@@ -0,0 +1,136 @@
+//! Issue operations.
+use thiserror::Error;
+
+use crate::cob::issue::{self, CloseReason, IssueId, Issues, State};
+use crate::cob::thread::CommentId;
+use crate::cob::{store, ActorId, Tag};
+use crate::crypto::Signer;
+use crate::prelude::*;
+use crate::storage::git::Repository;
+
+/// Error returned by issue operations.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("comment {0} not found")]
+    CommentNotFound(CommentId),
+    #[error(transparent)]
+    Issue(#[from] issue::Error),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+}
+
+/// Open an issue, assigned to the given DIDs.
+pub fn open<G: Signer>(
+    repo: &Repository,
+    title: impl ToString,
+    description: impl ToString,
+    tags: &[Tag],
+    assignees: &[Did],
+    signer: &G,
+) -> Result<IssueId, Error> {
+    let mut issues = Issues::open(repo)?;
+    let assignees = assignees
+        .iter()
+        .map(|did| ActorId::from(*did))
+        .collect::<Vec<_>>();
+    let issue = issues.create(title, description, tags, &assignees, signer)?;
+
+    Ok(*issue.id())
+}
+
+/// Comment on an issue. If no comment to reply to is given, the comment is a reply to
+/// the issue description.
+pub fn comment<G: Signer>(
+    repo: &Repository,
+    id: &IssueId,
+    body: impl ToString,
+    reply_to: Option<CommentId>,
+    signer: &G,
+) -> Result<CommentId, Error> {
+    let mut issues = Issues::open(repo)?;
+    let mut issue = issues.get_mut(id)?;
+    let reply_to = match reply_to {
+        Some(reply_to) if issue.thread().comment(&reply_to).is_none() => {
+            return Err(Error::CommentNotFound(reply_to));
+        }
+        Some(reply_to) => reply_to,
+        None => {
+            let (root, _) = issue
+                .comments()
+                .next()
+                .expect("Issue::comments: root comment always exists");
+            *root
+        }
+    };
+    issue.comment(body, reply_to, signer).map_err(Error::from)
+}
+
+/// Close an issue, for the given reason.
+pub fn close<G: Signer>(
+    repo: &Repository,
+    id: &IssueId,
+    reason: CloseReason,
+    signer: &G,
+) -> Result<(), Error> {
+    lifecycle(repo, id, State::Closed { reason }, signer)
+}
+
+/// Re-open a closed issue.
+pub fn reopen<G: Signer>(repo: &Repository, id: &IssueId, signer: &G) -> Result<(), Error> {
+    lifecycle(repo, id, State::Open, signer)
+}
+
+fn lifecycle<G: Signer>(
+    repo: &Repository,
+    id: &IssueId,
+    state: State,
+    signer: &G,
+) -> Result<(), Error> {
+    let mut issues = Issues::open(repo)?;
+    let mut issue = issues.get_mut(id)?;
+
+    if *issue.state() != state {
+        issue.lifecycle(state, signer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn test_open_comment_close() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let did = Did::from(*signer.public_key());
+
+        let id = open(&repo, "Title", "Description", &[], &[did], &signer).unwrap();
+        let reply = comment(&repo, &id, "First", None, &signer).unwrap();
+        comment(&repo, &id, "Second", Some(reply), &signer).unwrap();
+
+        let unknown = CommentId::from(git2::Oid::zero());
+        assert!(matches!(
+            comment(&repo, &id, "Third", Some(unknown), &signer),
+            Err(Error::CommentNotFound(_))
+        ));
+        close(&repo, &id, CloseReason::Solved, &signer).unwrap();
+
+        let issue = Issues::open(&repo).unwrap().get(&id).unwrap().unwrap();
+        assert_eq!(issue.title(), "Title");
+        assert_eq!(issue.assigned().collect::<Vec<_>>(), vec![did]);
+        assert_eq!(issue.comments().count(), 3);
+        assert_eq!(
+            *issue.state(),
+            State::Closed {
+                reason: CloseReason::Solved
+            }
+        );
+
+        reopen(&repo, &id, &signer).unwrap();
+
+        let issue = Issues::open(&repo).unwrap().get(&id).unwrap().unwrap();
+        assert_eq!(*issue.state(), State::Open);
+    }
+}
@@ -0,0 +1,720 @@
+//! Patch operations.
+use std::ops::Range;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::cob::patch::reviewers::{self, Reviewers};
+use crate::cob::patch::{self, MergeTarget, Patch, PatchId, Patches, RevisionId, State, Verdict};
+use crate::cob::thread::CommentId;
+use crate::cob::{store, EntryId, Tag};
+use crate::crypto::Signer;
+use crate::git;
+use crate::identity::doc::DocError;
+use crate::identity::policy::SignoffError;
+use crate::identity::{IdentityError, PayloadError};
+use crate::prelude::*;
+use crate::storage::git::Repository;
+
+/// Error returned by patch operations.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("patch {0} is malformed: no revisions found")]
+    Malformed(PatchId),
+    #[error("patch {0} was not found")]
+    NotFound(PatchId),
+    #[error("commit {commit} does not contain the patch head {head}")]
+    NotMerged { commit: git::Oid, head: git::Oid },
+    #[error("patch {patch} is stacked on {base}, which must be merged first")]
+    Stacked { patch: PatchId, base: PatchId },
+    #[error("invalid split: {0}")]
+    InvalidSplit(&'static str),
+    #[error("failed to determine the merge target: {0}")]
+    Target(#[from] IdentityError),
+    #[error("identity document: {0}")]
+    Doc(#[from] DocError),
+    #[error("policy: {0}")]
+    Payload(#[from] PayloadError),
+    #[error(transparent)]
+    Policy(#[from] SignoffError),
+    #[error(transparent)]
+    Reviewers(#[from] reviewers::Error),
+    #[error("git: {0}")]
+    Git(#[from] git::raw::Error),
+    #[error(transparent)]
+    Patch(#[from] patch::Error),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+}
+
+/// Get the commit that patches with the given merge target are merged into.
+pub fn target(repo: &Repository, target: MergeTarget) -> Result<git::Oid, Error> {
+    match target {
+        MergeTarget::Delegates => {
+            let (_, head) = repo.head()?;
+
+            Ok(head)
+        }
+    }
+}
+
+/// Get the commit a patch is expected to be based on: the head of the patch it is stacked
+/// on, or if there is no such patch or it was merged, the commit of the patch's merge target.
+pub fn upstream(repo: &Repository, patch: &Patch) -> Result<git::Oid, Error> {
+    if let Some(id) = patch.stacked_on() {
+        let base = Patches::open(repo)?.get(id)?.ok_or(Error::NotFound(*id))?;
+
+        if base.state() != State::Merged {
+            return Ok(*base.head());
+        }
+    }
+    target(repo, patch.target())
+}
+
+/// Get the number of commits the latest revision of a patch is ahead and behind of its
+/// [`upstream`]. A patch that is behind should be rebased.
+pub fn ahead_behind(repo: &Repository, patch: &Patch) -> Result<(usize, usize), Error> {
+    let upstream = upstream(repo, patch)?;
+    let head = *patch.head();
+    let ahead_behind = repo.raw().graph_ahead_behind(*head, *upstream)?;
+
+    Ok(ahead_behind)
+}
+
+/// A file that can't be merged cleanly, found by [`check_merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Path of the conflicting file.
+    pub path: PathBuf,
+    /// Line ranges of the common ancestor of the file that were changed on both sides,
+    /// starting at line `1`. Empty when the conflict is not on the file contents, eg. when
+    /// the file was deleted on one side.
+    pub hunks: Vec<Range<usize>>,
+}
+
+/// Test-merge the latest revision of a patch into the current commit of its merge target,
+/// and return the conflicting files, if any. The merge is done in memory, so neither
+/// storage nor any working copy is modified.
+pub fn check_merge(repo: &Repository, patch: &Patch) -> Result<Vec<Conflict>, Error> {
+    let raw = repo.raw();
+    let target = target(repo, patch.target())?;
+    let ours = raw.find_commit(*target)?;
+    let theirs = raw.find_commit(**patch.head())?;
+    let index = raw.merge_commits(&ours, &theirs, None)?;
+
+    if !index.has_conflicts() {
+        return Ok(vec![]);
+    }
+    let mut conflicts = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .into_iter()
+            .flatten()
+            .next()
+            .map(|e| PathBuf::from(String::from_utf8_lossy(&e.path).as_ref()))
+            .unwrap_or_default();
+        let hunks = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+            (Some(ancestor), Some(ours), Some(theirs)) => {
+                overlapping(raw, ancestor.id, ours.id, theirs.id)?
+            }
+            _ => vec![],
+        };
+        conflicts.push(Conflict { path, hunks });
+    }
+    Ok(conflicts)
+}
+
+/// Get the line ranges of the `ancestor` blob that were changed in both `ours` and `theirs`.
+fn overlapping(
+    raw: &git::raw::Repository,
+    ancestor: git::raw::Oid,
+    ours: git::raw::Oid,
+    theirs: git::raw::Oid,
+) -> Result<Vec<Range<usize>>, git::raw::Error> {
+    let ancestor = raw.find_blob(ancestor)?;
+    let changed = |oid| -> Result<Vec<Range<usize>>, git::raw::Error> {
+        let blob = raw.find_blob(oid)?;
+        let mut opts = git::raw::DiffOptions::new();
+        opts.context_lines(0);
+
+        let patch = git::raw::Patch::from_blobs(&ancestor, None, &blob, None, Some(&mut opts))?;
+
+        (0..patch.num_hunks())
+            .map(|ix| {
+                let (hunk, _) = patch.hunk(ix)?;
+                let start = hunk.old_start() as usize;
+
+                Ok(start..start + hunk.old_lines() as usize)
+            })
+            .collect()
+    };
+    let ours = changed(ours)?;
+    let theirs = changed(theirs)?;
+    let mut hunks = Vec::new();
+
+    for o in &ours {
+        for t in &theirs {
+            // Nb. Adjacent changes also conflict, and insertions have empty ranges.
+            if o.start <= t.end && t.start <= o.end {
+                hunks.push(o.start.min(t.start)..o.end.max(t.end));
+            }
+        }
+    }
+    hunks.dedup();
+
+    Ok(hunks)
+}
+
+/// Get the reviewers suggested by the repository's reviewers file, for the changes
+/// between `base` and `head`, and the paths of the declared `scope` of the patch. The
+/// reviewers file is read from `target`.
+pub fn reviewers(
+    repo: &Repository,
+    target: git::Oid,
+    base: git::Oid,
+    head: git::Oid,
+    scope: &[PathBuf],
+) -> Result<Vec<Did>, Error> {
+    let Some(reviewers) = Reviewers::load(target, repo)? else {
+        return Ok(vec![]);
+    };
+    let raw = repo.raw();
+    let base = raw.find_commit(*base)?.tree()?;
+    let head = raw.find_commit(*head)?.tree()?;
+    let diff = raw.diff_tree_to_tree(Some(&base), Some(&head), None)?;
+    let paths = diff
+        .deltas()
+        .flat_map(|d| [d.old_file().path(), d.new_file().path()])
+        .flatten()
+        .chain(scope.iter().map(PathBuf::as_path));
+
+    Ok(reviewers.suggest(paths))
+}
+
+/// Open a patch proposing to merge `head` into the canonical head of the repository.
+/// The commit must already be in storage.
+///
+/// If `stacked_on` is given, the patch is stacked on that patch: its changes are relative
+/// to the head of the other patch, which must be merged first. If a `scope` is given, it
+/// is declared as the paths affected by the patch.
+///
+/// The proposed commits must satisfy the repository policy. Review is requested from
+/// the reviewers suggested for the changed paths and the scope, except from the signer,
+/// and these reviewers are returned along with the patch id.
+#[allow(clippy::too_many_arguments)]
+pub fn open<G: Signer>(
+    repo: &Repository,
+    title: impl ToString,
+    description: impl ToString,
+    head: git::Oid,
+    tags: &[Tag],
+    scope: &[PathBuf],
+    draft: bool,
+    stacked_on: Option<PatchId>,
+    signer: &G,
+) -> Result<(PatchId, Vec<Did>), Error> {
+    let target_oid = target(repo, MergeTarget::default())?;
+    let mut patches = Patches::open(repo)?;
+    let base_oid = match &stacked_on {
+        Some(id) => *patches.get(id)?.ok_or(Error::NotFound(*id))?.head(),
+        None => target_oid,
+    };
+    let base = git::Oid::from(repo.raw().merge_base(*base_oid, *head)?);
+    let (_, doc) = repo.identity_doc()?;
+
+    doc.verified()?
+        .policy()?
+        .check(repo.raw(), Some(base), head)?;
+
+    let reviewers = reviewers(repo, target_oid, base, head, scope)?
+        .into_iter()
+        .filter(|did| did.as_key() != signer.public_key())
+        .collect::<Vec<_>>();
+    let mut patch = if let Some(stacked_on) = stacked_on {
+        let state = if draft { State::Draft } else { State::Open };
+
+        patches.create_stacked(
+            title,
+            description,
+            MergeTarget::default(),
+            base,
+            head,
+            tags,
+            state,
+            stacked_on,
+            signer,
+        )
+    } else if draft {
+        patches.draft(
+            title,
+            description,
+            MergeTarget::default(),
+            base,
+            head,
+            tags,
+            signer,
+        )
+    } else {
+        patches.create(
+            title,
+            description,
+            MergeTarget::default(),
+            base,
+            head,
+            tags,
+            signer,
+        )
+    }?;
+
+    if !scope.is_empty() {
+        patch.set_scope(scope.iter().cloned(), signer)?;
+    }
+    if !reviewers.is_empty() {
+        patch.request_review(
+            reviewers.iter().map(|did| PublicKey::from(*did)),
+            [],
+            signer,
+        )?;
+    }
+    Ok((patch.id, reviewers))
+}
+
+/// Declare the paths affected by a patch, replacing its previous scope. Review is requested
+/// from the reviewers of the new scope paths whose review wasn't requested yet, except from
+/// the signer, and these reviewers are returned.
+pub fn scope<G: Signer>(
+    repo: &Repository,
+    id: &PatchId,
+    scope: &[PathBuf],
+    signer: &G,
+) -> Result<Vec<Did>, Error> {
+    let mut patches = Patches::open(repo)?;
+    let mut patch = patches.get_mut(id)?;
+    let target = target(repo, patch.target())?;
+    let requested = patch.reviewers().collect::<Vec<_>>();
+    let reviewers = match Reviewers::load(target, repo)? {
+        Some(reviewers) => reviewers
+            .suggest(scope.iter().map(PathBuf::as_path))
+            .into_iter()
+            .filter(|did| did.as_key() != signer.public_key() && !requested.contains(did))
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    patch.set_scope(scope.iter().cloned(), signer)?;
+
+    if !reviewers.is_empty() {
+        patch.request_review(
+            reviewers.iter().map(|did| PublicKey::from(*did)),
+            [],
+            signer,
+        )?;
+    }
+    Ok(reviewers)
+}
+
+/// Comment on the latest revision of a patch, optionally replying to another comment.
+pub fn comment<G: Signer>(
+    repo: &Repository,
+    id: &PatchId,
+    body: impl ToString,
+    reply_to: Option<CommentId>,
+    signer: &G,
+) -> Result<EntryId, Error> {
+    let mut patches = Patches::open(repo)?;
+    let mut patch = patches.get_mut(id)?;
+    let (revision, _) = patch.latest().ok_or(Error::Malformed(*id))?;
+    let revision = *revision;
+
+    patch
+        .comment(revision, body, reply_to, signer)
+        .map_err(Error::from)
+}
+
+/// Review the latest revision of a patch, with an optional verdict and comment.
+pub fn review<G: Signer>(
+    repo: &Repository,
+    id: &PatchId,
+    verdict: Option<Verdict>,
+    comment: Option<String>,
+    signer: &G,
+) -> Result<EntryId, Error> {
+    let mut patches = Patches::open(repo)?;
+    let mut patch = patches.get_mut(id)?;
+    let (revision, _) = patch.latest().ok_or(Error::Malformed(*id))?;
+    let revision = *revision;
+
+    patch
+        .review(revision, verdict, comment, vec![], signer)
+        .map_err(Error::from)
+}
+
+/// Record that the latest revision of a patch was merged as part of `commit`, which
+/// must be in storage, and either be or descend from the revision head.
+///
+/// If the patch is stacked on other patches, these must be merged first.
+pub fn merge<G: Signer>(
+    repo: &Repository,
+    id: &PatchId,
+    commit: git::Oid,
+    signer: &G,
+) -> Result<RevisionId, Error> {
+    let mut patches = Patches::open(repo)?;
+
+    if let Some(base) = unmerged_base(&patches, id)? {
+        return Err(Error::Stacked { patch: *id, base });
+    }
+    let mut patch = patches.get_mut(id)?;
+    let (revision, r) = patch.latest().ok_or(Error::Malformed(*id))?;
+    let (revision, head) = (*revision, r.head());
+
+    if commit != head && !repo.raw().graph_descendant_of(*commit, *head)? {
+        return Err(Error::NotMerged { commit, head });
+    }
+    patch.merge(revision, commit, signer)?;
+
+    Ok(revision)
+}
+
+/// A part of a patch that is being split, see [`split`].
+#[derive(Debug, Clone)]
+pub struct Part {
+    /// Title of the new patch.
+    pub title: String,
+    /// Description of the new patch.
+    pub description: String,
+    /// Last commit of the part.
+    pub head: git::Oid,
+}
+
+/// Split a patch into a series of stacked patches, one per part, and archive it.
+///
+/// The part heads must be commits of the patch, in order, the last one being the patch
+/// head. The first new patch is stacked on whatever the original patch was stacked on,
+/// and every other patch on the one before it. The original patch is archived with a
+/// comment pointing to the new patches, which in turn refer to it in their description.
+pub fn split<G: Signer>(
+    repo: &Repository,
+    id: &PatchId,
+    parts: &[Part],
+    signer: &G,
+) -> Result<Vec<PatchId>, Error> {
+    let patch = Patches::open(repo)?.get(id)?.ok_or(Error::NotFound(*id))?;
+    let (revision, r) = patch.latest().ok_or(Error::Malformed(*id))?;
+    let (revision, base, head) = (*revision, *r.base(), r.head());
+
+    match parts.last() {
+        Some(last) if last.head == head => {}
+        _ => {
+            return Err(Error::InvalidSplit(
+                "the last part must end at the patch head",
+            ))
+        }
+    }
+    let mut previous = base;
+    for part in parts {
+        if !repo.raw().graph_descendant_of(*part.head, *previous)? {
+            return Err(Error::InvalidSplit("parts must be non-empty and in order"));
+        }
+        previous = part.head;
+    }
+
+    let tags = patch.tags().cloned().collect::<Vec<_>>();
+    let mut stacked_on = patch.stacked_on().copied();
+    let mut ids = Vec::with_capacity(parts.len());
+
+    for (ix, part) in parts.iter().enumerate() {
+        let reference = format!("Split from patch {id} ({}/{}).", ix + 1, parts.len());
+        let description = match part.description.trim() {
+            "" => reference,
+            description => format!("{description}\n\n{reference}"),
+        };
+        let (new, _) = open(
+            repo,
+            &part.title,
+            description,
+            part.head,
+            &tags,
+            patch.scope(),
+            patch.is_draft(),
+            stacked_on,
+            signer,
+        )?;
+        stacked_on = Some(new);
+        ids.push(new);
+    }
+
+    let mut patches = Patches::open(repo)?;
+    let mut patch = patches.get_mut(id)?;
+    let list = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+    patch.comment(
+        revision,
+        format!("Split into {}.", list.join(", ")),
+        None,
+        signer,
+    )?;
+    patch.archive(signer)?;
+
+    Ok(ids)
+}
+
+/// Get the first patch of the given patch's stack that isn't merged yet, if any.
+pub fn unmerged_base(patches: &Patches, id: &PatchId) -> Result<Option<PatchId>, Error> {
+    let base = patches
+        .stack(id)?
+        .into_iter()
+        .find(|(_, p)| p.state() != State::Merged)
+        .map(|(id, _)| id);
+
+    Ok(base)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::test;
+
+    /// Commit a file on top of the canonical head, on a branch named after the file.
+    fn commit(repo: &Repository, signer: &impl Signer, path: &str) -> git::Oid {
+        let raw = repo.raw();
+        let (_, head) = repo.head().unwrap();
+        let parent = raw.find_commit(*head).unwrap();
+        let sig = git::raw::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = git::write_tree(Path::new(path), b"Hello World!\n", raw).unwrap();
+        let branch = git::RefString::try_from(path).unwrap();
+        let refname = git::refs::storage::branch(signer.public_key(), &branch);
+        let commit = git::commit(raw, &parent, &refname, "Add file", &sig, &tree).unwrap();
+
+        commit.id().into()
+    }
+
+    #[test]
+    fn test_scope() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let head = commit(&repo, &signer, "README");
+        let src = [PathBuf::from("src/")];
+
+        let (id, _) = open(&repo, "Title", "", head, &[], &src, false, None, &signer).unwrap();
+        let patch = Patches::open(&repo).unwrap().get(&id).unwrap().unwrap();
+        assert_eq!(patch.scope(), &src);
+
+        // Without a reviewers file, there is no one to request review from.
+        let reviewers = scope(&repo, &id, &[PathBuf::from("docs")], &signer).unwrap();
+        assert!(reviewers.is_empty());
+
+        let patch = Patches::open(&repo).unwrap().get(&id).unwrap().unwrap();
+        assert_eq!(patch.scope(), &[PathBuf::from("docs")]);
+        assert!(patch.is_in_scope(Path::new("docs/guide.md")));
+        assert!(!patch.is_in_scope(Path::new("src")));
+    }
+
+    #[test]
+    fn test_open_comment_review_merge() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let head = commit(&repo, &signer, "README");
+
+        let (id, reviewers) = open(
+            &repo,
+            "Title",
+            "Description",
+            head,
+            &[],
+            &[],
+            false,
+            None,
+            &signer,
+        )
+        .unwrap();
+        assert!(reviewers.is_empty());
+
+        comment(&repo, &id, "Looks good", None, &signer).unwrap();
+        review(&repo, &id, Some(Verdict::Accept), None, &signer).unwrap();
+
+        let other = commit(&repo, &signer, "LICENSE");
+        assert!(matches!(
+            merge(&repo, &id, other, &signer),
+            Err(Error::NotMerged { .. })
+        ));
+        merge(&repo, &id, head, &signer).unwrap();
+
+        let patch = Patches::open(&repo).unwrap().get(&id).unwrap().unwrap();
+        let (_, revision) = patch.latest().unwrap();
+
+        assert_eq!(patch.title(), "Title");
+        assert_eq!(patch.state(), State::Merged);
+        assert_eq!(revision.discussion().len(), 1);
+        assert_eq!(revision.reviews().count(), 1);
+        assert_eq!(revision.merges().count(), 1);
+    }
+
+    #[test]
+    fn test_stacked_merge_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let bottom = commit(&repo, &signer, "README");
+        let (bottom_id, _) =
+            open(&repo, "Bottom", "", bottom, &[], &[], false, None, &signer).unwrap();
+
+        // Commit on top of the bottom patch.
+        let raw = repo.raw();
+        let sig = git::raw::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = git::write_tree(Path::new("LICENSE"), b"MIT\n", raw).unwrap();
+        let refname = git::refs::storage::branch(
+            signer.public_key(),
+            &git::RefString::try_from("top").unwrap(),
+        );
+        let parent = raw.find_commit(*bottom).unwrap();
+        let top = git::Oid::from(
+            git::commit(raw, &parent, &refname, "Add license", &sig, &tree)
+                .unwrap()
+                .id(),
+        );
+        let (top_id, _) = open(
+            &repo,
+            "Top",
+            "",
+            top,
+            &[],
+            &[],
+            false,
+            Some(bottom_id),
+            &signer,
+        )
+        .unwrap();
+
+        let patches = Patches::open(&repo).unwrap();
+        let patch = patches.get(&top_id).unwrap().unwrap();
+        let (_, revision) = patch.latest().unwrap();
+        assert_eq!(patch.stacked_on(), Some(&bottom_id));
+        assert_eq!(revision.base(), &bottom);
+
+        assert!(matches!(
+            merge(&repo, &top_id, top, &signer),
+            Err(Error::Stacked { base, .. }) if base == bottom_id
+        ));
+        merge(&repo, &bottom_id, bottom, &signer).unwrap();
+        merge(&repo, &top_id, top, &signer).unwrap();
+    }
+
+    #[test]
+    fn test_split() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let first = commit(&repo, &signer, "README");
+        let raw = repo.raw();
+        let sig = git::raw::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = git::write_tree(Path::new("LICENSE"), b"MIT\n", raw).unwrap();
+        let refname = git::refs::storage::branch(
+            signer.public_key(),
+            &git::RefString::try_from("README").unwrap(),
+        );
+        let parent = raw.find_commit(*first).unwrap();
+        let second = git::Oid::from(
+            git::commit(raw, &parent, &refname, "Add license", &sig, &tree)
+                .unwrap()
+                .id(),
+        );
+        let (id, _) = open(&repo, "Large", "", second, &[], &[], false, None, &signer).unwrap();
+        let part = |title: &str, head| Part {
+            title: title.to_owned(),
+            description: String::new(),
+            head,
+        };
+
+        assert!(matches!(
+            split(&repo, &id, &[part("First", first)], &signer),
+            Err(Error::InvalidSplit(_))
+        ));
+        assert!(matches!(
+            split(
+                &repo,
+                &id,
+                &[part("Second", second), part("First", first)],
+                &signer
+            ),
+            Err(Error::InvalidSplit(_))
+        ));
+
+        let ids = split(
+            &repo,
+            &id,
+            &[part("First", first), part("Second", second)],
+            &signer,
+        )
+        .unwrap();
+        let patches = Patches::open(&repo).unwrap();
+        let original = patches.get(&id).unwrap().unwrap();
+        let bottom = patches.get(&ids[0]).unwrap().unwrap();
+        let top = patches.get(&ids[1]).unwrap().unwrap();
+
+        assert_eq!(original.state(), State::Archived);
+        assert_eq!(bottom.stacked_on(), None);
+        assert_eq!(top.stacked_on(), Some(&ids[0]));
+        assert_eq!(top.latest().unwrap().1.base(), &first);
+        assert!(bottom.description().contains(&id.to_string()));
+    }
+
+    #[test]
+    fn test_ahead_behind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let head = commit(&repo, &signer, "README");
+        let (id, _) = open(&repo, "Title", "", head, &[], &[], false, None, &signer).unwrap();
+        let patch = Patches::open(&repo).unwrap().get(&id).unwrap().unwrap();
+
+        assert_eq!(ahead_behind(&repo, &patch).unwrap(), (1, 0));
+
+        // Advance the canonical branch past the patch base.
+        let canonical = commit(&repo, &signer, "LICENSE");
+        let (branch, _) = repo.head().unwrap();
+        repo.raw()
+            .reference(branch.as_str(), *canonical, true, "test")
+            .unwrap();
+
+        assert_eq!(upstream(&repo, &patch).unwrap(), canonical);
+        assert_eq!(ahead_behind(&repo, &patch).unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn test_check_merge() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, repo) = test::setup::context(&tmp);
+        let raw = repo.raw();
+        let sig = git::raw::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let (branch, head) = repo.head().unwrap();
+        let parent = raw.find_commit(*head).unwrap();
+        let commit_file = |name: &str, content: &[u8], update: Option<&str>| {
+            let tree = git::write_tree(Path::new("README"), content, raw).unwrap();
+            let refname = git::refs::storage::branch(
+                signer.public_key(),
+                &git::RefString::try_from(name).unwrap(),
+            );
+            let oid = git::commit(raw, &parent, &refname, name, &sig, &tree)
+                .unwrap()
+                .id();
+            if let Some(update) = update {
+                raw.reference(update, oid, true, "test").unwrap();
+            }
+            git::Oid::from(oid)
+        };
+        let patch = commit_file("patch", b"Hello Radicle!\n", None);
+        let (id, _) = open(&repo, "Title", "", patch, &[], &[], false, None, &signer).unwrap();
+        let patch = Patches::open(&repo).unwrap().get(&id).unwrap().unwrap();
+
+        assert!(check_merge(&repo, &patch).unwrap().is_empty());
+
+        // Change the same file on the canonical branch.
+        commit_file("canonical", b"Hello World?\n", Some(branch.as_str()));
+
+        let conflicts = check_merge(&repo, &patch).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, PathBuf::from("README"));
+        assert!(!conflicts[0].hunks.is_empty());
+    }
+}
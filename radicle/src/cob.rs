@@ -1,6 +1,9 @@
+pub mod cache;
 pub mod common;
 pub mod identity;
 pub mod issue;
+pub mod json;
+pub mod label;
 pub mod op;
 pub mod patch;
 pub mod store;
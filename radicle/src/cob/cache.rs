@@ -0,0 +1,477 @@
+//! Cache of collaborative objects, used to list them quickly.
+//!
+//! Getting the state of an issue or patch means replaying its whole history from Git, which
+//! gets slow on repositories with many objects. The cache keeps a summary of the state of every
+//! object in an SQLite database, along with the targets of the object's refs at the time the
+//! summary was made. When objects are listed, only those whose refs have changed since, eg.
+//! because they were updated locally or fetched from a peer, are loaded from Git again.
+//!
+//! The summaries are also used to find open issues and patches with a title similar to
+//! the one of a new object, to warn about possible duplicates.
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fmt, time};
+
+use radicle_cob::object::{Objects, ParseObjectId, Storage as _};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::cob::common::{Tag, Timestamp};
+use crate::cob::issue::{self, Issue, IssueId};
+use crate::cob::patch::{self, MergeTarget, Patch, PatchId};
+use crate::cob::store::{self, FromHistory};
+use crate::cob::ObjectId;
+use crate::git;
+use crate::prelude::{Did, ReadRepository};
+use crate::sql::transaction;
+use crate::storage::git::cob::TypesError;
+use crate::storage::git::Repository;
+
+/// Name of the cache database file, in the profile home.
+pub const COBS_DB_FILE: &str = "cobs.db";
+
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// Minimum [`similarity`] of two titles for them to be considered similar.
+pub const SIMILARITY_THRESHOLD: f32 = 0.5;
+
+/// An error occuring when using the cache.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    #[error("invalid object id: {0}")]
+    ObjectId(#[from] ParseObjectId),
+    #[error("failed to list objects: {0}")]
+    Types(#[from] TypesError),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+}
+
+/// A collaborative object that can be cached, as a summary of its state.
+pub trait Cacheable: FromHistory {
+    /// Summary of the object state.
+    type Summary: Serialize + DeserializeOwned;
+
+    /// Summarize the object state.
+    fn summary(&self) -> Self::Summary;
+}
+
+/// Summary of an issue, with what's needed to list it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSummary {
+    /// Issue title.
+    pub title: String,
+    /// Issue state.
+    pub state: issue::State,
+    /// Issue author.
+    pub author: Did,
+    /// Issue tags.
+    pub tags: Vec<Tag>,
+    /// Assignees of the issue.
+    pub assignees: Vec<Did>,
+    /// Date by which the issue should be resolved, if any.
+    pub due: Option<Timestamp>,
+    /// When the issue was opened.
+    pub timestamp: Timestamp,
+}
+
+impl IssueSummary {
+    /// Check whether the issue is still open past its due date.
+    pub fn is_overdue(&self, now: Timestamp) -> bool {
+        self.is_due_within(now, 0)
+    }
+
+    /// Check whether the issue is still open and due within `secs` seconds of `now`.
+    /// See [`Issue::is_due_within`].
+    pub fn is_due_within(&self, now: Timestamp, secs: u64) -> bool {
+        match (self.state, self.due) {
+            (issue::State::Open, Some(due)) => due < now + secs,
+            _ => false,
+        }
+    }
+}
+
+impl Cacheable for Issue {
+    type Summary = IssueSummary;
+
+    fn summary(&self) -> IssueSummary {
+        let mut tags = self.tags().cloned().collect::<Vec<_>>();
+        tags.sort();
+
+        IssueSummary {
+            title: self.title().to_owned(),
+            state: *self.state(),
+            author: self.author().id,
+            tags,
+            assignees: self.assigned().collect(),
+            due: self.due(),
+            timestamp: self.timestamp(),
+        }
+    }
+}
+
+/// Summary of a patch, with what's needed to list it. Revision details are those of the
+/// latest revision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchSummary {
+    /// Patch title.
+    pub title: String,
+    /// Patch state.
+    pub state: patch::State,
+    /// Patch author.
+    pub author: Did,
+    /// Patch tags.
+    pub tags: Vec<Tag>,
+    /// Declared paths affected by the patch.
+    pub scope: Vec<PathBuf>,
+    /// Where the patch is intended to be merged.
+    pub target: MergeTarget,
+    /// Patch the latest revision is stacked on, if any.
+    pub stacked_on: Option<PatchId>,
+    /// Base of the latest revision.
+    pub base: git::Oid,
+    /// Head of the latest revision.
+    pub head: git::Oid,
+    /// When the patch was opened.
+    pub timestamp: Timestamp,
+}
+
+impl Cacheable for Patch {
+    type Summary = PatchSummary;
+
+    fn summary(&self) -> PatchSummary {
+        let (_, revision) = self
+            .latest()
+            .expect("Patch::summary: at least one revision is present");
+        let mut tags = self.tags().cloned().collect::<Vec<_>>();
+        tags.sort();
+
+        PatchSummary {
+            title: self.title().to_owned(),
+            state: self.state(),
+            author: self.author().id,
+            tags,
+            scope: self.scope().to_vec(),
+            target: self.target(),
+            stacked_on: self.stacked_on().copied(),
+            base: *revision.base(),
+            head: revision.head(),
+            timestamp: self.timestamp(),
+        }
+    }
+}
+
+/// Persistent cache of collaborative objects.
+pub struct Store {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Store(..)")
+    }
+}
+
+impl Store {
+    const SCHEMA: &str = include_str!("cache/schema.sql");
+
+    /// Open a cache at the given path. Creates a new empty cache if an existing one isn't
+    /// found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory cache.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// List the issues of a repository.
+    pub fn issues(&mut self, repo: &Repository) -> Result<Vec<(IssueId, IssueSummary)>, Error> {
+        self.list::<Issue>(repo)
+    }
+
+    /// List the patches of a repository.
+    pub fn patches(&mut self, repo: &Repository) -> Result<Vec<(PatchId, PatchSummary)>, Error> {
+        self.list::<Patch>(repo)
+    }
+
+    /// Get the open issues of a repository with a title similar to the given one, the most
+    /// similar first.
+    pub fn similar_issues(
+        &mut self,
+        repo: &Repository,
+        title: &str,
+    ) -> Result<Vec<(IssueId, IssueSummary)>, Error> {
+        let open = self
+            .issues(repo)?
+            .into_iter()
+            .filter(|(_, i)| i.state == issue::State::Open);
+
+        Ok(similar(open, title, |i| &i.title))
+    }
+
+    /// Get the open and draft patches of a repository with a title similar to the given one,
+    /// the most similar first.
+    pub fn similar_patches(
+        &mut self,
+        repo: &Repository,
+        title: &str,
+    ) -> Result<Vec<(PatchId, PatchSummary)>, Error> {
+        let open = self
+            .patches(repo)?
+            .into_iter()
+            .filter(|(_, p)| matches!(p.state, patch::State::Open | patch::State::Draft));
+
+        Ok(similar(open, title, |p| &p.title))
+    }
+
+    /// List the objects of type `T` in a repository, ordered by id. Objects that changed
+    /// since they were cached are loaded from the repository, and the cache is updated.
+    pub fn list<T: Cacheable>(
+        &mut self,
+        repo: &Repository,
+    ) -> Result<Vec<(ObjectId, T::Summary)>, Error> {
+        let rid = repo.id();
+        let typename = T::type_name();
+        let mut cached = HashMap::new();
+        {
+            let mut stmt = self
+                .db
+                .prepare("SELECT id, refs, summary FROM cobs WHERE repo = ? AND type = ?")?;
+            stmt.bind((1, &rid))?;
+            stmt.bind((2, typename.as_str()))?;
+
+            for row in stmt.into_iter() {
+                let row = row?;
+                let id = ObjectId::from_str(row.read::<&str, _>("id"))?;
+                let refs = row.read::<String, _>("refs");
+                let summary = row.read::<String, _>("summary");
+
+                cached.insert(id, (refs, summary));
+            }
+        }
+
+        let objects = repo.types(typename)?;
+        let store = store::Store::<T>::open(repo)?;
+        let mut summaries = Vec::with_capacity(objects.len());
+        let mut stale = Vec::new();
+
+        for (id, objects) in objects {
+            let refs = fingerprint(&objects);
+
+            if let Some((cached_refs, summary)) = cached.remove(&id) {
+                // Nb. summaries that can't be decoded, eg. because they were made by an older
+                // version, are made again.
+                if cached_refs == refs {
+                    if let Ok(summary) = serde_json::from_str(&summary) {
+                        summaries.push((id, summary));
+                        continue;
+                    }
+                }
+            }
+            let Some((object, _)) = store.get(&id)? else {
+                continue;
+            };
+            let summary = object.summary();
+
+            stale.push((id, refs, serde_json::to_string(&summary)?));
+            summaries.push((id, summary));
+        }
+
+        transaction(&self.db, |db| {
+            for (id, refs, summary) in &stale {
+                let mut stmt = db.prepare(
+                    "INSERT INTO cobs (repo, type, id, refs, summary)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT DO UPDATE
+                     SET refs = ?4, summary = ?5",
+                )?;
+                stmt.bind((1, &rid))?;
+                stmt.bind((2, typename.as_str()))?;
+                stmt.bind((3, id.to_string().as_str()))?;
+                stmt.bind((4, refs.as_str()))?;
+                stmt.bind((5, summary.as_str()))?;
+                stmt.next()?;
+            }
+            // Objects that are cached but no longer in the repository.
+            for id in cached.keys() {
+                let mut stmt =
+                    db.prepare("DELETE FROM cobs WHERE repo = ? AND type = ? AND id = ?")?;
+                stmt.bind((1, &rid))?;
+                stmt.bind((2, typename.as_str()))?;
+                stmt.bind((3, id.to_string().as_str()))?;
+                stmt.next()?;
+            }
+            Ok(())
+        })?;
+        summaries.sort_by_key(|(id, _)| *id);
+
+        Ok(summaries)
+    }
+}
+
+/// Similarity of two titles, from `0.0` when they have no words in common, to `1.0` when they
+/// have the same words: the number of words found in both titles, divided by the number of
+/// distinct words of the two titles. Case is ignored, as are words of less than three
+/// characters, which are mostly stop words.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let words = |s: &str| {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() >= 3)
+            .map(str::to_lowercase)
+            .collect::<BTreeSet<_>>()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+
+    if union == 0 {
+        return 0.;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+/// Keep the summaries with a title similar to the given one, the most similar first.
+fn similar<S>(
+    summaries: impl IntoIterator<Item = (ObjectId, S)>,
+    title: &str,
+    title_of: impl Fn(&S) -> &str,
+) -> Vec<(ObjectId, S)> {
+    let mut similar = summaries
+        .into_iter()
+        .map(|(id, s)| (similarity(title, title_of(&s)), id, s))
+        .filter(|(similarity, _, _)| *similarity >= SIMILARITY_THRESHOLD)
+        .collect::<Vec<_>>();
+    similar.sort_by(|(a, _, _), (b, _, _)| b.total_cmp(a));
+
+    similar.into_iter().map(|(_, id, s)| (id, s)).collect()
+}
+
+/// Identify the state of an object's refs. An object's state only depends on the commits its
+/// refs point to.
+fn fingerprint(objects: &Objects) -> String {
+    objects
+        .iter()
+        .map(|r| r.target.id.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cob::issue::Issues;
+    use crate::crypto::Signer as _;
+    use crate::test;
+
+    #[test]
+    fn test_issues_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(&project).unwrap();
+        let mut cache = Store::memory().unwrap();
+
+        assert!(cache.issues(&project).unwrap().is_empty());
+
+        let issue = issues
+            .create("First", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        let id = issue.id;
+        let summaries = cache.issues(&project).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].0, id);
+        assert_eq!(summaries[0].1.title, "First");
+        assert_eq!(summaries[0].1.author, Did::from(*signer.public_key()));
+
+        // Listing again is served from the cache.
+        assert_eq!(cache.issues(&project).unwrap(), summaries);
+
+        // Updating the issue changes its refs, which invalidates the cached summary.
+        issues
+            .get_mut(&id)
+            .unwrap()
+            .edit("Second", &signer)
+            .unwrap();
+        let summaries = cache.issues(&project).unwrap();
+        assert_eq!(summaries[0].1.title, "Second");
+
+        // Removed issues are removed from the cache.
+        issues.remove(&id, &signer).unwrap();
+        assert!(cache.issues(&project).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("Fix the node crash", "fix THE node crash!"), 1.);
+        assert_eq!(similarity("Node crashes on startup", "Add a logo"), 0.);
+        assert_eq!(similarity("", "Add a logo"), 0.);
+        // Short words are ignored.
+        assert_eq!(similarity("Add a logo", "Add logo"), 1.);
+        assert_eq!(
+            similarity("Node crash on startup", "Node crash on exit"),
+            0.5
+        );
+    }
+
+    #[test]
+    fn test_similar_issues() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(&project).unwrap();
+        let mut cache = Store::memory().unwrap();
+
+        let crash = issues
+            .create("Node crashes on startup", "", &[], &[], &signer)
+            .unwrap()
+            .id;
+        let exit = issues
+            .create("Node crashes on exit", "", &[], &[], &signer)
+            .unwrap()
+            .id;
+        issues.create("Add a logo", "", &[], &[], &signer).unwrap();
+
+        let similar = cache
+            .similar_issues(&project, "Node crashes at startup")
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(similar, vec![crash, exit]);
+
+        // Closed issues are left out.
+        issues
+            .get_mut(&crash)
+            .unwrap()
+            .lifecycle(
+                issue::State::Closed {
+                    reason: issue::CloseReason::Solved,
+                },
+                &signer,
+            )
+            .unwrap();
+        let similar = cache
+            .similar_issues(&project, "Node crashes at startup")
+            .unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].0, exit);
+    }
+}
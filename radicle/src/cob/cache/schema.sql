@@ -0,0 +1,17 @@
+--
+-- Collaborative object cache SQL schema.
+--
+create table if not exists "cobs" (
+  -- Repository the object belongs to.
+  "repo"         text      not null,
+  -- Type name of the object.
+  "type"         text      not null,
+  -- Object ID.
+  "id"           text      not null,
+  -- Targets of the object's refs when the summary was made.
+  "refs"         text      not null,
+  -- Summary of the object's state, as JSON.
+  "summary"      text      not null,
+
+  primary key ("repo", "type", "id")
+);
@@ -1,3 +1,5 @@
+pub mod board;
+
 use std::ops::Deref;
 use std::str::FromStr;
 
@@ -19,6 +21,8 @@ use crate::crypto::Signer;
 use crate::prelude::{Did, ReadRepository};
 use crate::storage::git as storage;
 
+pub use board::{Board, Column};
+
 /// Issue operation.
 pub type Op = cob::Op<Action>;
 
@@ -98,6 +102,10 @@ pub struct Issue {
     state: LWWReg<Max<State>>,
     /// Associated tags.
     tags: LWWSet<Tag>,
+    /// Board column the issue was last moved to.
+    column: LWWReg<Max<Option<Column>>>,
+    /// Date by which the issue should be resolved.
+    due: LWWReg<Max<Option<Timestamp>>>,
     /// Discussion around this issue.
     thread: Thread,
 }
@@ -108,6 +116,8 @@ impl Semilattice for Issue {
         self.title.merge(other.title);
         self.state.merge(other.state);
         self.tags.merge(other.tags);
+        self.column.merge(other.column);
+        self.due.merge(other.due);
         self.thread.merge(other.thread);
     }
 }
@@ -119,6 +129,8 @@ impl Default for Issue {
             title: LWWReg::initial(Max::from(String::default())),
             state: LWWReg::initial(Max::from(State::default())),
             tags: LWWSet::default(),
+            column: LWWReg::initial(Max::from(None)),
+            due: LWWReg::initial(Max::from(None)),
             thread: Thread::default(),
         }
     }
@@ -161,6 +173,12 @@ impl store::FromHistory for Issue {
                         self.tags.remove(tag, op.clock);
                     }
                 }
+                Action::Move { column } => {
+                    self.column.set(Some(column), op.clock);
+                }
+                Action::Due { due } => {
+                    self.due.set(due, op.clock);
+                }
                 Action::Thread { action } => {
                     self.thread.apply(
                         [cob::Op::new(
@@ -197,6 +215,31 @@ impl Issue {
         self.tags.iter()
     }
 
+    /// Board column the issue was last moved to, if any. See [`Board::column`] for the column
+    /// an issue is shown in.
+    pub fn column(&self) -> Option<&Column> {
+        self.column.get().get().as_ref()
+    }
+
+    /// Date by which the issue should be resolved, if any.
+    pub fn due(&self) -> Option<Timestamp> {
+        *self.due.get().get()
+    }
+
+    /// Check whether the issue is still open past its due date.
+    pub fn is_overdue(&self, now: Timestamp) -> bool {
+        self.is_due_within(now, 0)
+    }
+
+    /// Check whether the issue is still open and due within `secs` seconds of `now`.
+    /// Overdue issues are also due within any period.
+    pub fn is_due_within(&self, now: Timestamp, secs: u64) -> bool {
+        match (self.state(), self.due()) {
+            (State::Open, Some(due)) => due < now + secs,
+            _ => false,
+        }
+    }
+
     pub fn timestamp(&self) -> Timestamp {
         self.thread
             .comments()
@@ -279,6 +322,18 @@ impl store::Transaction<Issue> {
         }))
     }
 
+    /// Edit an issue comment. The issue description is its first comment.
+    pub fn edit_comment<S: ToString>(
+        &mut self,
+        id: CommentId,
+        body: S,
+    ) -> Result<(), store::Error> {
+        self.push(Action::from(thread::Action::Edit {
+            id,
+            body: body.to_string(),
+        }))
+    }
+
     /// Tag an issue.
     pub fn tag(
         &mut self,
@@ -291,6 +346,16 @@ impl store::Transaction<Issue> {
         self.push(Action::Tag { add, remove })
     }
 
+    /// Move an issue to a board column.
+    pub fn move_to(&mut self, column: Column) -> Result<(), store::Error> {
+        self.push(Action::Move { column })
+    }
+
+    /// Set or clear the issue due date.
+    pub fn set_due(&mut self, due: Option<Timestamp>) -> Result<(), store::Error> {
+        self.push(Action::Due { due })
+    }
+
     /// React to an issue comment.
     pub fn react(&mut self, to: CommentId, reaction: Reaction) -> Result<(), store::Error> {
         self.push(Action::Thread {
@@ -363,6 +428,16 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         self.transaction("Comment", signer, |tx| tx.comment(body, reply_to))
     }
 
+    /// Edit an issue comment.
+    pub fn edit_comment<G: Signer, S: ToString>(
+        &mut self,
+        id: CommentId,
+        body: S,
+        signer: &G,
+    ) -> Result<EntryId, Error> {
+        self.transaction("Edit comment", signer, |tx| tx.edit_comment(id, body))
+    }
+
     /// Tag an issue.
     pub fn tag<G: Signer>(
         &mut self,
@@ -373,6 +448,20 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         self.transaction("Tag", signer, |tx| tx.tag(add, remove))
     }
 
+    /// Move an issue to a board column.
+    pub fn move_to<G: Signer>(&mut self, column: Column, signer: &G) -> Result<EntryId, Error> {
+        self.transaction("Move", signer, |tx| tx.move_to(column))
+    }
+
+    /// Set or clear the issue due date.
+    pub fn set_due<G: Signer>(
+        &mut self,
+        due: Option<Timestamp>,
+        signer: &G,
+    ) -> Result<EntryId, Error> {
+        self.transaction("Set due date", signer, |tx| tx.set_due(due))
+    }
+
     /// React to an issue comment.
     pub fn react<G: Signer>(
         &mut self,
@@ -535,6 +624,12 @@ pub enum Action {
     Lifecycle {
         state: State,
     },
+    Move {
+        column: Column,
+    },
+    Due {
+        due: Option<Timestamp>,
+    },
     Tag {
         add: Vec<Tag>,
         remove: Vec<Tag>,
@@ -741,6 +836,28 @@ mod test {
         assert_eq!(r, "Sorry typo");
     }
 
+    #[test]
+    fn test_issue_edit_description() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+
+        let (root, _) = issue.root();
+        let root = *root;
+        issue
+            .edit_comment(root, "Bleh bleh bleh.", &signer)
+            .unwrap();
+
+        let id = issue.id;
+        let issue = issues.get(&id).unwrap().unwrap();
+
+        assert_eq!(issue.description(), Some("Bleh bleh bleh."));
+        assert_eq!(issue.comments().count(), 1);
+    }
+
     #[test]
     fn test_issue_react() {
         let tmp = tempfile::tempdir().unwrap();
@@ -832,6 +949,98 @@ mod test {
         assert!(tags.contains(&wontfix_tag));
     }
 
+    #[test]
+    fn test_issue_board() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(&project).unwrap();
+        let board = Board::default();
+        let done = Column::new("done").unwrap();
+        let in_progress = Column::new("in-progress").unwrap();
+
+        let mut first = issues
+            .create("First", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        first.move_to(in_progress.clone(), &signer).unwrap();
+        let first = first.id;
+
+        let mut second = issues
+            .create("Second", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        second
+            .lifecycle(
+                State::Closed {
+                    reason: CloseReason::Solved,
+                },
+                &signer,
+            )
+            .unwrap();
+        let second = second.id;
+        let third = issues
+            .create("Third", "Blah blah blah.", &[], &[], &signer)
+            .unwrap()
+            .id;
+
+        let issue = issues.get(&first).unwrap().unwrap();
+        assert_eq!(issue.column(), Some(&in_progress));
+
+        let groups = board.group(
+            [first, second, third]
+                .into_iter()
+                .map(|id| (id, issues.get(&id).unwrap().unwrap())),
+        );
+        let groups = groups
+            .into_iter()
+            .map(|(c, issues)| (c, issues.into_iter().map(|(id, _)| id).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            groups,
+            vec![
+                (Column::new("triage").unwrap(), vec![third]),
+                (in_progress, vec![first]),
+                (done, vec![second]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_issue_due() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(&project).unwrap();
+        let now = Timestamp::from(1_700_000_000);
+        let day = 60 * 60 * 24;
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+
+        assert_eq!(issue.due(), None);
+        assert!(!issue.is_overdue(now));
+
+        issue.set_due(Some(now + day), &signer).unwrap();
+        assert_eq!(issue.due(), Some(now + day));
+        assert!(!issue.is_overdue(now));
+        assert!(!issue.is_due_within(now, day / 2));
+        assert!(issue.is_due_within(now, day * 2));
+        assert!(issue.is_overdue(now + day * 2));
+
+        issue
+            .lifecycle(
+                State::Closed {
+                    reason: CloseReason::Solved,
+                },
+                &signer,
+            )
+            .unwrap();
+        assert!(!issue.is_overdue(now + day * 2));
+
+        issue.set_due(None, &signer).unwrap();
+        let id = issue.id;
+        let issue = issues.get(&id).unwrap().unwrap();
+        assert_eq!(issue.due(), None);
+    }
+
     #[test]
     fn test_issue_comment() {
         let tmp = tempfile::tempdir().unwrap();
@@ -0,0 +1,152 @@
+//! Issue boards, which group issues into ordered columns.
+//!
+//! The columns of a repository's board are defined in the `xyz.radicle.board` payload of
+//! its identity document, eg. `"xyz.radicle.board": { "columns": ["triage", "review", "done"] }`.
+//! Repositories without a board payload use the [`Board::default`] columns.
+//!
+//! Issues that were never moved to a column, or that were moved to a column that is no longer
+//! on the board, are shown in the first column when open, and in the last column when closed.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::Verified;
+use crate::identity::doc::{Doc, PayloadError, PayloadId};
+
+use super::{Issue, IssueId, State};
+
+#[derive(Error, Debug)]
+pub enum ColumnError {
+    #[error("invalid column name: `{0}`")]
+    InvalidName(String),
+}
+
+/// A board column.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Column(String);
+
+impl Column {
+    pub fn new(name: impl ToString) -> Result<Self, ColumnError> {
+        let name = name.to_string();
+
+        if name.chars().any(|c| c.is_whitespace()) || name.is_empty() {
+            return Err(ColumnError::InvalidName(name));
+        }
+        Ok(Self(name))
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for Column {
+    type Err = ColumnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for Column {
+    type Error = ColumnError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Column> for String {
+    fn from(Column(name): Column) -> Self {
+        name
+    }
+}
+
+/// A "board" payload in an identity document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    /// Board columns, in order.
+    pub columns: Vec<Column>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self {
+            columns: ["triage", "in-progress", "done"]
+                .into_iter()
+                .map(|c| Column(c.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+impl Board {
+    /// Get the board of a repository out of its identity document. Returns the default board
+    /// if the document has no board payload, or if it has no columns.
+    pub fn from_doc(doc: &Doc<Verified>) -> Result<Self, PayloadError> {
+        let board: Self = match doc.payload.get(&PayloadId::board()) {
+            Some(value) => serde_json::from_value((**value).clone())?,
+            None => return Ok(Self::default()),
+        };
+        if board.columns.is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(board)
+    }
+
+    /// Check whether the given column is on the board.
+    pub fn contains(&self, column: &Column) -> bool {
+        self.columns.contains(column)
+    }
+
+    /// Get the column an issue is shown in.
+    pub fn column(&self, issue: &Issue) -> &Column {
+        if let Some(column) = issue.column().and_then(|c| self.find(c)) {
+            return column;
+        }
+        let column = match issue.state() {
+            State::Open => self.columns.first(),
+            State::Closed { .. } => self.columns.last(),
+        };
+        column.expect("Board::column: boards have at least one column")
+    }
+
+    /// Group issues by column, in board order. Within a column, issues keep the order in which
+    /// they are given.
+    pub fn group(
+        &self,
+        issues: impl IntoIterator<Item = (IssueId, Issue)>,
+    ) -> Vec<(Column, Vec<(IssueId, Issue)>)> {
+        let mut groups = self
+            .columns
+            .iter()
+            .map(|c| (c.clone(), Vec::new()))
+            .collect::<Vec<_>>();
+
+        for (id, issue) in issues {
+            let column = self.column(&issue);
+            let ix = self
+                .columns
+                .iter()
+                .position(|c| c == column)
+                .expect("Board::group: issue column is on the board");
+
+            groups[ix].1.push((id, issue));
+        }
+        groups
+    }
+
+    fn find(&self, column: &Column) -> Option<&Column> {
+        self.columns.iter().find(|c| *c == column)
+    }
+}
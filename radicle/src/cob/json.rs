@@ -0,0 +1,86 @@
+//! JSON representations of collaborative objects.
+//!
+//! These are the representations served by the HTTP API and returned by the C bindings, so that
+//! clients can share their models between the two.
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::cob::issue::{Issue, IssueId};
+use crate::cob::patch::{Patch, PatchId, Revision, RevisionId};
+use crate::cob::thread::{self, CommentId, Thread};
+use crate::cob::{ActorId, Author, Reaction, Timestamp};
+
+/// Returns JSON for an `issue`.
+pub fn issue(id: &IssueId, issue: &Issue) -> Value {
+    json!({
+        "id": id.to_string(),
+        "author": issue.author(),
+        "title": issue.title(),
+        "state": issue.state(),
+        "assignees": issue.assigned().collect::<Vec<_>>(),
+        "discussion": issue
+          .comments()
+          .map(|(id, comment)| Comment::new(id, comment, issue.thread()))
+          .collect::<Vec<_>>(),
+        "tags": issue.tags().collect::<Vec<_>>(),
+    })
+}
+
+/// Returns JSON for a `patch`.
+pub fn patch(id: &PatchId, patch: &Patch) -> Value {
+    json!({
+        "id": id.to_string(),
+        "author": patch.author(),
+        "title": patch.title(),
+        "description": patch.description(),
+        "state": patch.state(),
+        "target": patch.target(),
+        "tags": patch.tags().collect::<Vec<_>>(),
+        "revisions": patch
+          .revisions()
+          .map(|(id, rev)| revision(id, rev))
+          .collect::<Vec<_>>(),
+    })
+}
+
+/// Returns JSON for a patch `revision`.
+pub fn revision(id: &RevisionId, rev: &Revision) -> Value {
+    json!({
+        "id": id,
+        "description": rev.description(),
+        "base": rev.base(),
+        "oid": rev.head(),
+        "merges": rev.merges().collect::<Vec<_>>(),
+        "discussions": rev.discussion().comments()
+          .map(|(id, comment)| Comment::new(id, comment, rev.discussion()))
+          .collect::<Vec<_>>(),
+        "timestamp": rev.timestamp(),
+        "reviews": rev.reviews().collect::<Vec<_>>(),
+    })
+}
+
+/// A comment of a discussion, along with its reactions.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment<'a> {
+    id: CommentId,
+    author: Author,
+    body: &'a str,
+    reactions: Vec<(&'a ActorId, &'a Reaction)>,
+    timestamp: Timestamp,
+    reply_to: Option<CommentId>,
+}
+
+impl<'a> Comment<'a> {
+    /// Create a new comment representation, given the thread it belongs to.
+    pub fn new(id: &'a CommentId, comment: &'a thread::Comment, thread: &'a Thread) -> Self {
+        Self {
+            id: *id,
+            author: Author::new(comment.author()),
+            body: comment.body(),
+            reactions: thread.reactions(id).collect::<Vec<_>>(),
+            timestamp: comment.timestamp(),
+            reply_to: comment.reply_to(),
+        }
+    }
+}
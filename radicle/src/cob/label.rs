@@ -0,0 +1,134 @@
+//! Repository labels, which describe the tags used on issues and patches.
+//!
+//! Labels are defined in the `xyz.radicle.labels` payload of a repository's identity document,
+//! eg. `"xyz.radicle.labels": { "labels": { "bug": { "color": "#d73a4a" } } }`. Tags don't have
+//! to be defined to be used; defining them only gives them a color and a description.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cob::common::{Color, Tag};
+use crate::crypto::Verified;
+use crate::identity::doc::{Doc, Payload, PayloadError, PayloadId};
+
+#[derive(Error, Debug)]
+pub enum LabelError {
+    #[error("label `{0}` is not defined")]
+    NotFound(Tag),
+    #[error("label `{0}` is already defined")]
+    AlreadyDefined(Tag),
+}
+
+/// Metadata of a label.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Label {
+    /// Label color.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    /// Label description.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// A "labels" payload in an identity document.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Labels {
+    /// Defined labels, by name.
+    pub labels: BTreeMap<Tag, Label>,
+}
+
+impl Labels {
+    /// Get the labels of a repository out of its identity document. Returns no labels if the
+    /// document has no labels payload.
+    pub fn from_doc(doc: &Doc<Verified>) -> Result<Self, PayloadError> {
+        match doc.payload.get(&PayloadId::labels()) {
+            Some(value) => Ok(serde_json::from_value((**value).clone())?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Set these labels in the given identity document. Removes the labels payload if there
+    /// are no labels.
+    pub fn to_doc(&self, doc: &mut Doc<Verified>) {
+        if self.labels.is_empty() {
+            doc.payload.remove(&PayloadId::labels());
+        } else {
+            let value = serde_json::json!({ "labels": self.labels });
+            doc.payload
+                .insert(PayloadId::labels(), Payload::from(value));
+        }
+    }
+
+    /// Get a label.
+    pub fn get(&self, name: &Tag) -> Option<&Label> {
+        self.labels.get(name)
+    }
+
+    /// Iterate over the labels, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&Tag, &Label)> {
+        self.labels.iter()
+    }
+
+    /// Define a label, or update an existing one. Returns `true` if the label changed.
+    pub fn define(&mut self, name: Tag, label: Label) -> bool {
+        self.labels.insert(name, label.clone()) != Some(label)
+    }
+
+    /// Rename a label. Fails if the label isn't defined, or if the new name is already taken.
+    pub fn rename(&mut self, from: &Tag, to: Tag) -> Result<(), LabelError> {
+        if self.labels.contains_key(&to) {
+            return Err(LabelError::AlreadyDefined(to));
+        }
+        let label = self
+            .labels
+            .remove(from)
+            .ok_or_else(|| LabelError::NotFound(from.clone()))?;
+        self.labels.insert(to, label);
+
+        Ok(())
+    }
+
+    /// Remove a label. Fails if the label isn't defined.
+    pub fn remove(&mut self, name: &Tag) -> Result<Label, LabelError> {
+        self.labels
+            .remove(name)
+            .ok_or_else(|| LabelError::NotFound(name.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_labels_doc() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let mut labels = Labels::from_doc(&doc).unwrap();
+        let bug = Tag::new("bug").unwrap();
+        let defect = Tag::new("defect").unwrap();
+        let label = Label {
+            color: Some(Color::from_str("#d73a4a").unwrap()),
+            description: String::from("Something isn't working"),
+        };
+        assert!(labels.iter().next().is_none());
+
+        assert!(labels.define(bug.clone(), label.clone()));
+        assert!(!labels.define(bug.clone(), label.clone()));
+        labels.to_doc(&mut doc);
+        assert_eq!(Labels::from_doc(&doc).unwrap(), labels);
+
+        labels.rename(&bug, defect.clone()).unwrap();
+        assert!(labels.rename(&bug, defect.clone()).is_err());
+        assert_eq!(labels.get(&defect), Some(&label));
+
+        assert_eq!(labels.remove(&defect).unwrap(), label);
+        labels.to_doc(&mut doc);
+        assert!(!doc.payload.contains_key(&PayloadId::labels()));
+    }
+}
@@ -2,7 +2,7 @@
 use std::fmt;
 use std::ops::Deref;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use once_cell::sync::Lazy;
@@ -27,6 +27,8 @@ use crate::identity::doc::DocError;
 use crate::prelude::*;
 use crate::storage::git as storage;
 
+pub mod reviewers;
+
 /// The logical clock we use to order operations to patches.
 pub use clock::Lamport as Clock;
 
@@ -92,10 +94,21 @@ pub enum Action {
         add: Vec<Tag>,
         remove: Vec<Tag>,
     },
+    RequestReview {
+        add: Vec<ActorId>,
+        remove: Vec<ActorId>,
+    },
+    /// Declare the paths of the repository affected by the patch.
+    Scope {
+        paths: Vec<PathBuf>,
+    },
     Revision {
         description: String,
         base: git::Oid,
         oid: git::Oid,
+        /// Patch this revision is stacked on, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stacked_on: Option<PatchId>,
     },
     Lifecycle {
         state: State,
@@ -157,6 +170,10 @@ pub struct Patch {
     target: LWWReg<Max<MergeTarget>>,
     /// Associated tags.
     tags: LWWSet<Tag>,
+    /// Actors whose review was requested.
+    reviewers: LWWSet<ActorId>,
+    /// Declared paths affected by the patch.
+    scope: LWWReg<Max<Vec<PathBuf>>>,
     /// List of patch revisions. The initial changeset is part of the
     /// first revision.
     revisions: GMap<RevisionId, Redactable<Revision>>,
@@ -171,6 +188,8 @@ impl Semilattice for Patch {
         self.state.merge(other.state);
         self.target.merge(other.target);
         self.tags.merge(other.tags);
+        self.reviewers.merge(other.reviewers);
+        self.scope.merge(other.scope);
         self.revisions.merge(other.revisions);
     }
 }
@@ -183,6 +202,8 @@ impl Default for Patch {
             state: LWWReg::initial(Max::from(State::default())),
             target: LWWReg::initial(Max::from(MergeTarget::default())),
             tags: LWWSet::default(),
+            reviewers: LWWSet::default(),
+            scope: LWWReg::initial(Max::from(Vec::default())),
             revisions: GMap::default(),
             timeline: GSet::default(),
         }
@@ -219,6 +240,23 @@ impl Patch {
         self.tags.iter()
     }
 
+    /// Actors whose review was requested.
+    pub fn reviewers(&self) -> impl Iterator<Item = Did> + '_ {
+        self.reviewers.iter().map(Did::from)
+    }
+
+    /// Declared paths affected by the patch. Empty if the patch didn't declare a scope.
+    pub fn scope(&self) -> &[PathBuf] {
+        self.scope.get().get()
+    }
+
+    /// Check whether the declared scope of the patch overlaps with the given path, ie.
+    /// whether one of the scope paths is under the given path, or the other way around.
+    /// Patches without a declared scope are never in scope.
+    pub fn is_in_scope(&self, path: &Path) -> bool {
+        overlaps(self.scope(), path)
+    }
+
     /// Patch description.
     pub fn description(&self) -> &str {
         self.description.get().get()
@@ -274,6 +312,11 @@ impl Patch {
         self.revisions().next_back()
     }
 
+    /// Patch that the latest revision is stacked on, if any.
+    pub fn stacked_on(&self) -> Option<&PatchId> {
+        self.latest().and_then(|(_, r)| r.stacked_on())
+    }
+
     /// Check if the patch is open.
     pub fn is_open(&self) -> bool {
         matches!(self.state(), State::Open)
@@ -331,6 +374,17 @@ impl store::FromHistory for Patch {
                         self.tags.remove(tag, op.clock);
                     }
                 }
+                Action::RequestReview { add, remove } => {
+                    for reviewer in add {
+                        self.reviewers.insert(reviewer, op.clock);
+                    }
+                    for reviewer in remove {
+                        self.reviewers.remove(reviewer, op.clock);
+                    }
+                }
+                Action::Scope { paths } => {
+                    self.scope.set(paths, op.clock);
+                }
                 Action::EditRevision {
                     revision,
                     description,
@@ -345,6 +399,7 @@ impl store::FromHistory for Patch {
                     description,
                     base,
                     oid,
+                    stacked_on,
                 } => {
                     // Since revisions are keyed by content hash, we shouldn't re-insert a revision
                     // if it already exists, otherwise this will be resolved via the `merge`
@@ -359,6 +414,7 @@ impl store::FromHistory for Patch {
                             description,
                             base,
                             oid,
+                            stacked_on,
                             timestamp,
                             op.clock,
                         )),
@@ -442,6 +498,22 @@ impl store::FromHistory for Patch {
     }
 }
 
+/// Check whether any of the `scope` paths overlaps with `path`, ie. is under it, or contains
+/// it. Paths are relative to the repository root: leading `/` and `./` are ignored.
+pub fn overlaps(scope: &[PathBuf], path: &Path) -> bool {
+    let normal = |p: &Path| {
+        p.components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .collect::<PathBuf>()
+    };
+    let path = normal(path);
+
+    scope
+        .iter()
+        .map(|p| normal(p.as_path()))
+        .any(|p| p.starts_with(&path) || path.starts_with(&p))
+}
+
 /// A patch revision.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Revision {
@@ -453,6 +525,9 @@ pub struct Revision {
     base: git::Oid,
     /// Reference to the Git object containing the code (revision head).
     oid: git::Oid,
+    /// Patch this revision is stacked on. The revision's changes are relative to the
+    /// head of that patch, which must be merged first.
+    stacked_on: Option<PatchId>,
     /// Discussion around this revision.
     discussion: Thread,
     /// Merges of this revision into other repositories.
@@ -469,6 +544,7 @@ impl Revision {
         description: String,
         base: git::Oid,
         oid: git::Oid,
+        stacked_on: Option<PatchId>,
         timestamp: Timestamp,
         clock: Clock,
     ) -> Self {
@@ -477,6 +553,7 @@ impl Revision {
             description: LWWReg::new(Max::from(description), clock),
             base,
             oid,
+            stacked_on,
             discussion: Thread::default(),
             merges: LWWSet::default(),
             reviews: GMap::default(),
@@ -503,6 +580,11 @@ impl Revision {
         self.oid
     }
 
+    /// Patch this revision is stacked on, if any.
+    pub fn stacked_on(&self) -> Option<&PatchId> {
+        self.stacked_on.as_ref()
+    }
+
     /// When this revision was created.
     pub fn timestamp(&self) -> Timestamp {
         self.timestamp
@@ -637,6 +719,14 @@ pub struct CodeComment {
 }
 
 impl CodeComment {
+    pub fn new(location: CodeLocation, comment: impl ToString, timestamp: Timestamp) -> Self {
+        Self {
+            location,
+            comment: comment.to_string(),
+            timestamp,
+        }
+    }
+
     /// Code location of the comment.
     pub fn location(&self) -> &CodeLocation {
         &self.location
@@ -814,17 +904,19 @@ impl store::Transaction<Patch> {
         self.push(Action::Merge { revision, commit })
     }
 
-    /// Update a patch with a new revision.
+    /// Update a patch with a new revision, optionally stacked on another patch.
     pub fn revision(
         &mut self,
         description: impl ToString,
         base: impl Into<git::Oid>,
         oid: impl Into<git::Oid>,
+        stacked_on: Option<PatchId>,
     ) -> Result<(), store::Error> {
         self.push(Action::Revision {
             description: description.to_string(),
             base: base.into(),
             oid: oid.into(),
+            stacked_on,
         })
     }
 
@@ -844,6 +936,28 @@ impl store::Transaction<Patch> {
 
         self.push(Action::Tag { add, remove })
     }
+
+    /// Request or withdraw a review request.
+    pub fn request_review(
+        &mut self,
+        add: impl IntoIterator<Item = ActorId>,
+        remove: impl IntoIterator<Item = ActorId>,
+    ) -> Result<(), store::Error> {
+        let add = add.into_iter().collect::<Vec<_>>();
+        let remove = remove.into_iter().collect::<Vec<_>>();
+
+        self.push(Action::RequestReview { add, remove })
+    }
+
+    /// Declare the paths affected by a patch.
+    pub fn set_scope(
+        &mut self,
+        paths: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<(), store::Error> {
+        let paths = paths.into_iter().collect::<Vec<_>>();
+
+        self.push(Action::Scope { paths })
+    }
 }
 
 pub struct PatchMut<'a, 'g> {
@@ -962,16 +1076,31 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         self.transaction("Merge revision", signer, |tx| tx.merge(revision, commit))
     }
 
-    /// Update a patch with a new revision.
+    /// Update a patch with a new revision. The new revision is stacked on the same patch
+    /// as the latest revision, if any.
     pub fn update<G: Signer>(
         &mut self,
         description: impl ToString,
         base: impl Into<git::Oid>,
         oid: impl Into<git::Oid>,
         signer: &G,
+    ) -> Result<EntryId, Error> {
+        let stacked_on = self.stacked_on().copied();
+
+        self.update_stacked(description, base, oid, stacked_on, signer)
+    }
+
+    /// Update a patch with a new revision, stacked on the given patch, or on nothing.
+    pub fn update_stacked<G: Signer>(
+        &mut self,
+        description: impl ToString,
+        base: impl Into<git::Oid>,
+        oid: impl Into<git::Oid>,
+        stacked_on: Option<PatchId>,
+        signer: &G,
     ) -> Result<EntryId, Error> {
         self.transaction("Add revision", signer, |tx| {
-            tx.revision(description, base, oid)
+            tx.revision(description, base, oid, stacked_on)
         })
     }
 
@@ -1014,6 +1143,27 @@ impl<'a, 'g> PatchMut<'a, 'g> {
     ) -> Result<EntryId, Error> {
         self.transaction("Tag", signer, |tx| tx.tag(add, remove))
     }
+
+    /// Request reviews from, or withdraw review requests to, one or more actors.
+    pub fn request_review<G: Signer>(
+        &mut self,
+        add: impl IntoIterator<Item = ActorId>,
+        remove: impl IntoIterator<Item = ActorId>,
+        signer: &G,
+    ) -> Result<EntryId, Error> {
+        self.transaction("Request review", signer, |tx| {
+            tx.request_review(add, remove)
+        })
+    }
+
+    /// Declare the paths affected by the patch, replacing any previously declared scope.
+    pub fn set_scope<G: Signer>(
+        &mut self,
+        paths: impl IntoIterator<Item = PathBuf>,
+        signer: &G,
+    ) -> Result<EntryId, Error> {
+        self.transaction("Scope", signer, |tx| tx.set_scope(paths))
+    }
 }
 
 impl<'a, 'g> Deref for PatchMut<'a, 'g> {
@@ -1073,6 +1223,7 @@ impl<'a> Patches<'a> {
             oid,
             tags,
             State::default(),
+            None,
             signer,
         )
     }
@@ -1096,10 +1247,61 @@ impl<'a> Patches<'a> {
             oid,
             tags,
             State::Draft,
+            None,
             signer,
         )
     }
 
+    /// Open a new patch stacked on another patch. The given `base` should be a commit
+    /// of the patch it is stacked on, usually its head.
+    pub fn create_stacked<'g, G: Signer>(
+        &'g mut self,
+        title: impl ToString,
+        description: impl ToString,
+        target: MergeTarget,
+        base: impl Into<git::Oid>,
+        oid: impl Into<git::Oid>,
+        tags: &[Tag],
+        state: State,
+        stacked_on: PatchId,
+        signer: &G,
+    ) -> Result<PatchMut<'a, 'g>, Error> {
+        self._create(
+            title,
+            description,
+            target,
+            base,
+            oid,
+            tags,
+            state,
+            Some(stacked_on),
+            signer,
+        )
+    }
+
+    /// Get the chain of patches the given patch is stacked on, starting with the patch
+    /// at the bottom of the stack, which can be merged first. The given patch is not
+    /// included.
+    pub fn stack(&self, id: &PatchId) -> Result<Vec<(PatchId, Patch)>, Error> {
+        let mut stack = Vec::new();
+        let mut current = self.get(id)?.and_then(|p| p.stacked_on().copied());
+
+        while let Some(id) = current {
+            // Guard against cycles, which can't be prevented across peers.
+            if stack.iter().any(|(p, _)| *p == id) {
+                break;
+            }
+            let Some(patch) = self.get(&id)? else {
+                break;
+            };
+            current = patch.stacked_on().copied();
+            stack.push((id, patch));
+        }
+        stack.reverse();
+
+        Ok(stack)
+    }
+
     /// Patches count by state.
     pub fn counts(&self) -> Result<PatchCounts, store::Error> {
         let all = self.all()?;
@@ -1137,6 +1339,25 @@ impl<'a> Patches<'a> {
         Ok(result)
     }
 
+    /// Find the patch revision that was merged as the given commit.
+    pub fn find_by_merge(
+        &self,
+        commit: &git::Oid,
+    ) -> Result<Option<(PatchId, Patch, RevisionId)>, Error> {
+        let result = self
+            .all()?
+            .filter_map(|result| result.ok())
+            .find_map(|(p_id, p, _)| {
+                let r_id = p
+                    .revisions()
+                    .find(|(_, r)| r.merges().any(|m| m.commit == *commit))
+                    .map(|(r_id, _)| *r_id)?;
+
+                Some((p_id, p, r_id))
+            });
+        Ok(result)
+    }
+
     /// Get a patch.
     pub fn get(&self, id: &ObjectId) -> Result<Option<Patch>, store::Error> {
         self.raw.get(id).map(|r| r.map(|(p, _)| p))
@@ -1189,11 +1410,12 @@ impl<'a> Patches<'a> {
         oid: impl Into<git::Oid>,
         tags: &[Tag],
         state: State,
+        stacked_on: Option<PatchId>,
         signer: &G,
     ) -> Result<PatchMut<'a, 'g>, Error> {
         let (id, patch, clock) =
             Transaction::initial("Create patch", &mut self.raw, signer, |tx| {
-                tx.revision(String::default(), base, oid)?;
+                tx.revision(String::default(), base, oid, stacked_on)?;
                 tx.edit(title, description, target)?;
                 tx.tag(tags.to_owned(), [])?;
 
@@ -1318,6 +1540,7 @@ mod test {
                         description,
                         base,
                         oid,
+                        stacked_on: None,
                     });
 
                     if rng.bool() {
@@ -1433,6 +1656,82 @@ mod test {
         assert_eq!(id, patch_id);
     }
 
+    #[test]
+    fn test_patch_request_review() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(&project).unwrap();
+        let alice = *MockSigner::from_seed([0xaa; 32]).public_key();
+        let bob = *MockSigner::from_seed([0xbb; 32]).public_key();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                git::Oid::try_from("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap(),
+                git::Oid::try_from("e2a85016a458cd809c0ecee81f8c99613b0b0945").unwrap(),
+                &[],
+                &signer,
+            )
+            .unwrap();
+
+        patch.request_review([alice, bob], [], &signer).unwrap();
+        patch.request_review([], [alice], &signer).unwrap();
+
+        let id = patch.id;
+        let patch = patches.get(&id).unwrap().unwrap();
+        let reviewers = patch.reviewers().collect::<Vec<_>>();
+
+        assert_eq!(reviewers, vec![Did::from(bob)]);
+    }
+
+    #[test]
+    fn test_patch_scope() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(&project).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                git::Oid::try_from("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap(),
+                git::Oid::try_from("e2a85016a458cd809c0ecee81f8c99613b0b0945").unwrap(),
+                &[],
+                &signer,
+            )
+            .unwrap();
+        assert!(patch.scope().is_empty());
+        assert!(!patch.is_in_scope(Path::new("src")));
+
+        patch
+            .set_scope(
+                [PathBuf::from("README"), PathBuf::from("src/node/")],
+                &signer,
+            )
+            .unwrap();
+        patch
+            .set_scope(
+                [PathBuf::from("/src/node/"), PathBuf::from("docs")],
+                &signer,
+            )
+            .unwrap();
+
+        let id = patch.id;
+        let patch = patches.get(&id).unwrap().unwrap();
+
+        assert_eq!(
+            patch.scope(),
+            &[PathBuf::from("/src/node/"), PathBuf::from("docs")]
+        );
+        assert!(patch.is_in_scope(Path::new("src")));
+        assert!(patch.is_in_scope(Path::new("./src/node")));
+        assert!(patch.is_in_scope(Path::new("src/node/lib.rs")));
+        assert!(patch.is_in_scope(Path::new("docs/guide.md")));
+        assert!(!patch.is_in_scope(Path::new("src/cli")));
+        assert!(!patch.is_in_scope(Path::new("README")));
+    }
+
     #[test]
     fn test_patch_discussion() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1497,6 +1796,11 @@ mod test {
         let merge = merges.first().unwrap();
         assert_eq!(merge.node, *signer.public_key());
         assert_eq!(merge.commit, base);
+
+        let (p_id, _, r_id) = patches.find_by_merge(&base).unwrap().unwrap();
+        assert_eq!(p_id, id);
+        assert_eq!(r_id, *patch.revisions().next().unwrap().0);
+        assert!(patches.find_by_merge(&oid).unwrap().is_none());
     }
 
     #[test]
@@ -1551,6 +1855,7 @@ mod test {
             description: String::new(),
             base,
             oid,
+            stacked_on: None,
         });
         let a2 = alice.op(Action::Redact { revision: a1.id() });
         let a3 = alice.op(Action::Review {
@@ -1587,6 +1892,7 @@ mod test {
             description: String::new(),
             base,
             oid,
+            stacked_on: None,
         });
         let a2 = alice.op(Action::Redact { revision: a1.id() });
 
@@ -1610,6 +1916,7 @@ mod test {
             description: String::new(),
             base,
             oid,
+            stacked_on: None,
         });
         let a2 = alice.op(Action::Merge {
             revision: a1.id(),
@@ -1840,4 +2147,75 @@ mod test {
         assert_eq!(revision.oid, rev1_oid);
         assert_eq!(revision.description(), "I've made changes.");
     }
+
+    #[test]
+    fn test_patch_stacked() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let base = git::Oid::from_str("af08e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let a_oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let b_oid = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let c_oid = git::Oid::from_str("f2de534b5e81d7c6e2dcaf58c3dd91573c0a0354").unwrap();
+        let mut patches = Patches::open(&project).unwrap();
+        let a = patches
+            .create(
+                "Bottom",
+                "",
+                MergeTarget::Delegates,
+                base,
+                a_oid,
+                &[],
+                &signer,
+            )
+            .unwrap()
+            .id;
+        let b = patches
+            .create_stacked(
+                "Middle",
+                "",
+                MergeTarget::Delegates,
+                a_oid,
+                b_oid,
+                &[],
+                State::Open,
+                a,
+                &signer,
+            )
+            .unwrap()
+            .id;
+        let mut c = patches
+            .create_stacked(
+                "Top",
+                "",
+                MergeTarget::Delegates,
+                b_oid,
+                c_oid,
+                &[],
+                State::Open,
+                b,
+                &signer,
+            )
+            .unwrap();
+        assert_eq!(c.stacked_on(), Some(&b));
+
+        // Updates keep the patch on its stack.
+        c.update("Changes", b_oid, c_oid, &signer).unwrap();
+        assert_eq!(c.stacked_on(), Some(&b));
+
+        let c = c.id;
+        let stack = patches
+            .stack(&c)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(stack, vec![a, b]);
+        assert!(patches.stack(&a).unwrap().is_empty());
+
+        // Unstacking the patch.
+        let mut c = patches.get_mut(&c).unwrap();
+        c.update_stacked("Rebased", base, c_oid, None, &signer)
+            .unwrap();
+        assert_eq!(c.stacked_on(), None);
+    }
 }
@@ -0,0 +1,235 @@
+//! Reviewer suggestions, based on a `.radicle/reviewers` file in the repository.
+//!
+//! The file format is similar to `CODEOWNERS`: each line contains a path pattern,
+//! followed by one or more DIDs. Empty lines and lines starting with `#` are ignored.
+//! When a patch touches a path, the reviewers of the *last* matching line are suggested.
+//!
+//! ```text
+//! # Default reviewers.
+//! *                    did:key:z6MknSLrJoTcukLrE435hVNQT4JUhbvWLX4kUzqkEStBU8Vi
+//! # Documentation.
+//! *.md                 did:key:z6Mkt67GdsW7715MEfRuP4pSZxJRJh6kj6Y48WRqVv4N1tRk
+//! /radicle-node/       did:key:z6MkuPZUQ5ECkVRi35BQGCKi7SQSQbYBhBwkE2YNRUE2ezWH
+//! ```
+//!
+//! In patterns, `*` matches any sequence of characters within a path component. Patterns
+//! containing a `/`, other than at the end, are matched from the root of the repository;
+//! other patterns match at any depth. A pattern matching a directory matches all paths
+//! under it; patterns ending in `/` only match directories.
+use std::path::Path;
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::git;
+use crate::glob;
+use crate::identity::did::DidError;
+use crate::prelude::{Did, ReadRepository};
+
+/// Path to the reviewers file in the repository.
+pub static PATH: Lazy<&Path> = Lazy::new(|| Path::new(".radicle/reviewers"));
+
+/// Error parsing a reviewers file.
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("line {0}: expected a path pattern followed by one or more DIDs")]
+    InvalidLine(usize),
+    #[error("line {0}: {1}")]
+    InvalidDid(usize, DidError),
+}
+
+/// Error loading a reviewers file.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid reviewers file: {0}")]
+    Parse(#[from] ParseError),
+    #[error("reviewers file is not valid UTF-8")]
+    Utf8,
+    #[error("git: {0}")]
+    Git(#[from] git::Error),
+}
+
+/// A line of the reviewers file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Path pattern.
+    pub pattern: String,
+    /// Reviewers of the paths matching the pattern.
+    pub reviewers: Vec<Did>,
+}
+
+impl Rule {
+    /// Check whether the rule applies to the given path.
+    pub fn matches(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        let components = path
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>();
+        let (pattern, directory) = match self.pattern.strip_suffix('/') {
+            Some(pattern) => (pattern, true),
+            None => (self.pattern.as_str(), false),
+        };
+        // The last component of the path is a file, which directory patterns don't match.
+        let max = if directory {
+            components.len().saturating_sub(1)
+        } else {
+            components.len()
+        };
+
+        if pattern.contains('/') {
+            let pattern = pattern
+                .trim_start_matches('/')
+                .split('/')
+                .collect::<Vec<_>>();
+
+            pattern.len() <= max
+                && pattern
+                    .iter()
+                    .zip(&components)
+                    .all(|(p, c)| glob::matches(p, c))
+        } else {
+            components[..max].iter().any(|c| glob::matches(pattern, c))
+        }
+    }
+}
+
+/// Parsed reviewers file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Reviewers {
+    rules: Vec<Rule>,
+}
+
+impl Reviewers {
+    /// Load the reviewers file at the given commit. Returns `None` if there is no file.
+    pub fn load<R: ReadRepository>(commit: git::Oid, repo: &R) -> Result<Option<Self>, Error> {
+        let blob = match repo.blob_at(commit, *PATH) {
+            Ok(blob) => blob,
+            Err(git::Error::NotFound(_)) => return Ok(None),
+            Err(git::Error::Git(e)) if git::is_not_found_err(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let content = std::str::from_utf8(blob.content()).map_err(|_| Error::Utf8)?;
+
+        Ok(Some(content.parse()?))
+    }
+
+    /// Get the reviewers of the given path.
+    pub fn reviewers(&self, path: &Path) -> &[Did] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| r.matches(path))
+            .map(|r| r.reviewers.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the reviewers of a set of paths, eg. the paths changed by a patch.
+    pub fn suggest<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> Vec<Did> {
+        let mut suggested = Vec::new();
+
+        for path in paths {
+            for did in self.reviewers(path) {
+                if !suggested.contains(did) {
+                    suggested.push(*did);
+                }
+            }
+        }
+        suggested
+    }
+}
+
+impl FromStr for Reviewers {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+            let reviewers = parts
+                .map(|s| Did::decode(s).map_err(|e| ParseError::InvalidDid(i + 1, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if reviewers.is_empty() {
+                return Err(ParseError::InvalidLine(i + 1));
+            }
+            rules.push(Rule {
+                pattern: pattern.to_owned(),
+                reviewers,
+            });
+        }
+        Ok(Self { rules })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALICE: &str = "did:key:z6MknSLrJoTcukLrE435hVNQT4JUhbvWLX4kUzqkEStBU8Vi";
+    const BOB: &str = "did:key:z6Mkt67GdsW7715MEfRuP4pSZxJRJh6kj6Y48WRqVv4N1tRk";
+
+    fn rule(pattern: &str) -> Rule {
+        Rule {
+            pattern: pattern.to_owned(),
+            reviewers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let path = Path::new("radicle-node/src/service.rs");
+
+        assert!(rule("*").matches(path));
+        assert!(rule("*.rs").matches(path));
+        assert!(rule("service.rs").matches(path));
+        assert!(rule("src").matches(path));
+        assert!(rule("src/").matches(path));
+        assert!(rule("/radicle-node/").matches(path));
+        assert!(rule("radicle-*/src").matches(path));
+        assert!(rule("radicle-node/src/service.rs").matches(path));
+
+        assert!(!rule("*.md").matches(path));
+        assert!(!rule("service.rs/").matches(path));
+        assert!(!rule("/src").matches(path));
+        assert!(!rule("radicle-cli/").matches(path));
+    }
+
+    #[test]
+    fn test_suggest() {
+        let reviewers =
+            format!("# Comment\n\n* {ALICE}\n*.md {BOB}\n/radicle-node/ {ALICE} {BOB}\n")
+                .parse::<Reviewers>()
+                .unwrap();
+        let alice = Did::decode(ALICE).unwrap();
+        let bob = Did::decode(BOB).unwrap();
+
+        assert_eq!(reviewers.suggest([Path::new("README.md")]), vec![bob]);
+        assert_eq!(reviewers.suggest([Path::new("src/lib.rs")]), vec![alice]);
+        assert_eq!(
+            reviewers.suggest([Path::new("README.md"), Path::new("radicle-node/README.md")]),
+            vec![bob, alice]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            "*.rs".parse::<Reviewers>(),
+            Err(ParseError::InvalidLine(1))
+        ));
+        assert!(matches!(
+            format!("* {ALICE}\n*.rs alice").parse::<Reviewers>(),
+            Err(ParseError::InvalidDid(2, _))
+        ));
+    }
+}
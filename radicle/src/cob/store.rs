@@ -12,6 +12,7 @@ use crate::cob::op::{Op, Ops};
 use crate::cob::{ActorId, Create, EntryId, History, ObjectId, TypeName, Update, Updated};
 use crate::git;
 use crate::prelude::*;
+use crate::storage::events::Event;
 use crate::storage::git as storage;
 use crate::{cob, identity};
 
@@ -49,26 +50,50 @@ pub trait FromHistory: Sized + Default {
         history: &History,
         repo: &R,
     ) -> Result<(Self, Lamport), Error> {
+        let (obj, clock, _) = Self::from_history_verified(history, repo)?;
+
+        Ok((obj, clock))
+    }
+
+    /// Create an object from a history, and return the changes that were left out of it,
+    /// either because they failed verification, or because their operations could not be
+    /// applied. The descendants of these changes are also left out.
+    fn from_history_verified<R: ReadRepository>(
+        history: &History,
+        repo: &R,
+    ) -> Result<(Self, Lamport, Vec<Rejection>), Error> {
+        let mut rejected = history
+            .rejected()
+            .iter()
+            .map(|r| Rejection {
+                entry: r.id,
+                author: r.actor,
+                reason: RejectionReason::Verification(r.reason.clone()),
+            })
+            .collect::<Vec<_>>();
+
         let obj = history.traverse(Self::default(), |mut acc, entry| {
-            match Ops::try_from(entry) {
-                Ok(Ops(ops)) => {
-                    if let Err(err) = acc.apply(ops, repo) {
-                        log::warn!("Error applying op to `{}` state: {err}", Self::type_name());
-                        return ControlFlow::Break(acc);
-                    }
-                }
-                Err(err) => {
-                    log::warn!(
-                        "Error decoding ops for `{}` state: {err}",
-                        Self::type_name()
-                    );
-                    return ControlFlow::Break(acc);
-                }
-            }
-            ControlFlow::Continue(acc)
+            let reason = match Ops::try_from(entry) {
+                Ok(Ops(ops)) => match acc.apply(ops, repo) {
+                    Ok(()) => return ControlFlow::Continue(acc),
+                    Err(err) => RejectionReason::Apply(err.to_string()),
+                },
+                Err(err) => RejectionReason::Encoding(err.to_string()),
+            };
+            log::warn!(
+                "Rejecting change for `{}` state: {reason}",
+                Self::type_name()
+            );
+
+            rejected.push(Rejection {
+                entry: *entry.id(),
+                author: *entry.actor(),
+                reason,
+            });
+            ControlFlow::Break(acc)
         });
 
-        Ok((obj, history.clock().into()))
+        Ok((obj, history.clock().into(), rejected))
     }
 
     /// Create an object from individual operations.
@@ -84,6 +109,28 @@ pub trait FromHistory: Sized + Default {
     }
 }
 
+/// A change that was left out of an object's state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    /// The rejected change.
+    pub entry: EntryId,
+    /// The author the change claims.
+    pub author: ActorId,
+    /// Why the change was rejected.
+    pub reason: RejectionReason,
+}
+
+/// The reason a change was left out of an object's state.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RejectionReason {
+    #[error("verification failed: {0}")]
+    Verification(radicle_cob::history::RejectionReason),
+    #[error("operations could not be decoded: {0}")]
+    Encoding(String),
+    #[error("operations could not be applied: {0}")]
+    Apply(String),
+}
+
 /// Store error.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -164,6 +211,7 @@ where
         )?;
 
         self.repo.sign_refs(signer).map_err(Error::SignRefs)?;
+        self.changed(object_id);
 
         Ok(updated)
     }
@@ -191,6 +239,7 @@ where
         let (object, clock) = T::from_history(cob.history(), self.repo)?;
 
         self.repo.sign_refs(signer).map_err(Error::SignRefs)?;
+        self.changed(*cob.id());
 
         Ok((*cob.id(), object, clock))
     }
@@ -211,6 +260,17 @@ where
         }
     }
 
+    /// Get the changes that were left out of an object's state, if the object exists.
+    /// See [`FromHistory::from_history_verified`].
+    pub fn rejected(&self, id: &ObjectId) -> Result<Option<Vec<Rejection>>, Error> {
+        let Some(cob) = cob::get(self.repo, T::type_name(), id)? else {
+            return Ok(None);
+        };
+        let (_, _, rejected) = T::from_history_verified(cob.history(), self.repo)?;
+
+        Ok(Some(rejected))
+    }
+
     /// Return all objects.
     pub fn all(
         &self,
@@ -239,9 +299,22 @@ where
     pub fn remove<G: Signer>(&self, id: &ObjectId, signer: &G) -> Result<(), Error> {
         cob::remove(self.repo, signer.public_key(), T::type_name(), id)?;
         self.repo.sign_refs(signer).map_err(Error::SignRefs)?;
+        self.changed(*id);
 
         Ok(())
     }
+
+    /// Publish a storage event for a changed object.
+    fn changed(&self, id: ObjectId) {
+        storage::publish(
+            &self.repo.journal(),
+            Event::CobChanged {
+                rid: self.repo.id,
+                type_name: T::type_name().clone(),
+                id,
+            },
+        );
+    }
 }
 
 /// Allows operations to be batched atomically.
@@ -7,6 +7,7 @@ use git_ref_format as format;
 use once_cell::sync::Lazy;
 
 use crate::collections::HashMap;
+use crate::crypto;
 use crate::crypto::PublicKey;
 use crate::storage;
 use crate::storage::refs::Refs;
@@ -126,6 +127,14 @@ pub enum RefError {
     },
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum SignatureError {
+    #[error("git error: {0}")]
+    Git(#[from] git2::Error),
+    #[error("invalid signature: {0}")]
+    Ssh(#[from] crypto::ssh::ExtendedSignatureError),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ListRefsError {
     #[error("git error: {0}")]
@@ -176,6 +185,14 @@ pub mod refs {
             Qualified::from_components(name::component!("rad"), name::component!("sigrefs"), None)
         });
 
+        /// Where a user's personal notes on repository objects are stored.
+        ///
+        /// `refs/notes/rad`
+        ///
+        pub static NOTES_REF: Lazy<Qualified> = Lazy::new(|| {
+            Qualified::from_components(name::component!("notes"), name::component!("rad"), None)
+        });
+
         /// Create the [`Namespaced`] `branch` under the `remote` namespace, i.e.
         ///
         /// `refs/namespaces/<remote>/refs/heads/<branch>`
@@ -192,6 +209,14 @@ pub mod refs {
             IDENTITY_BRANCH.with_namespace(remote.into())
         }
 
+        /// Get the reference where the personal notes of `remote` are stored.
+        ///
+        /// `refs/namespaces/<remote>/refs/notes/rad`
+        ///
+        pub fn notes(remote: &RemoteId) -> Namespaced {
+            NOTES_REF.with_namespace(remote.into())
+        }
+
         /// The collaborative object reference, identified by `typename` and `object_id`, under the given `remote`.
         ///
         /// `refs/namespaces/<remote>/refs/cobs/<typename>/<object_id>`
@@ -348,6 +373,22 @@ pub fn head(repo: &git2::Repository) -> Result<git2::Commit, git2::Error> {
     Ok(head)
 }
 
+/// Verify the SSH signature of a commit, and return the key that signed it.
+/// Returns `None` if the commit isn't signed.
+pub fn commit_signer(
+    repo: &git2::Repository,
+    oid: Oid,
+) -> Result<Option<PublicKey>, SignatureError> {
+    let (signature, data) = match repo.extract_signature(&oid, None) {
+        Ok(result) => result,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let key = crypto::ssh::verify(&*signature, crypto::ssh::GIT_NAMESPACE, &data)?;
+
+    Ok(Some(key))
+}
+
 /// Write a tree with the given blob at the given path.
 pub fn write_tree<'r>(
     path: &Path,
@@ -0,0 +1,59 @@
+//! Glob patterns, where `*` matches any sequence of characters.
+//!
+//! Matching doesn't backtrack: every literal part between wildcards is matched at its first
+//! occurrence, which takes time linear in the length of the input for a given pattern.
+
+/// Match `input` against a pattern where `*` matches any sequence of characters.
+pub fn matches(pattern: &str, input: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let mut input = input;
+
+    // The first part must match the start of the input.
+    let first = parts.next().unwrap_or("");
+    let Some(rest) = input.strip_prefix(first) else {
+        return false;
+    };
+    input = rest;
+
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        // There was no wildcard.
+        return input.is_empty();
+    };
+    for part in parts {
+        match input.find(part) {
+            Some(i) => input = &input[i + part.len()..],
+            None => return false,
+        }
+    }
+    // The last part must match the end of the input.
+    input.len() >= last.len() && input.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("rad:z3gq", "rad:z3gq"));
+        assert!(!matches("rad:z3gq", "rad:z3gqc"));
+        assert!(matches("rad:z3gq*", "rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5"));
+        assert!(matches("*", "rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5"));
+        assert!(matches("*v5", "rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5"));
+        assert!(matches("rad:*JUo*v5", "rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5"));
+        assert!(!matches("rad:*JUo*v6", "rad:z3gqcJUoA1n9HaHKufZs5FCSGazv5"));
+        assert!(!matches("rad:z3*z3", "rad:z3"));
+        assert!(matches("*.md", "README.md"));
+        assert!(matches("**", ""));
+    }
+
+    #[test]
+    fn test_matches_many_wildcards() {
+        let pattern = "*a".repeat(64) + "b";
+        let input = "a".repeat(4096);
+
+        assert!(!matches(&pattern, &input));
+        assert!(matches(&pattern, &(input + "b")));
+    }
+}
@@ -1,21 +1,31 @@
 pub mod did;
 pub mod doc;
+pub mod policy;
 pub mod project;
 
+#[cfg(feature = "git")]
 use std::collections::HashMap;
 
+#[cfg(feature = "git")]
 use radicle_git_ext::Oid;
+#[cfg(feature = "git")]
 use thiserror::Error;
 
 use crate::crypto;
+#[cfg(feature = "git")]
 use crate::crypto::{Signature, Verified};
+#[cfg(feature = "git")]
 use crate::git;
+#[cfg(feature = "git")]
 use crate::storage;
+#[cfg(feature = "git")]
 use crate::storage::{refs, ReadRepository, RemoteId};
 
 pub use crypto::PublicKey;
 pub use did::Did;
-pub use doc::{Doc, Id, IdError, PayloadError};
+pub use doc::{Doc, PayloadError, Visibility};
+#[cfg(feature = "git")]
+pub use doc::{Id, IdError};
 pub use project::Project;
 
 /// Untrusted, well-formed input.
@@ -25,6 +35,7 @@ pub struct Untrusted;
 #[derive(Clone, Copy, Debug)]
 pub struct Trusted;
 
+#[cfg(feature = "git")]
 #[derive(Error, Debug)]
 pub enum IdentityError {
     #[error("git: {0}")]
@@ -55,6 +66,7 @@ pub enum IdentityError {
     Storage(#[from] storage::Error),
 }
 
+#[cfg(feature = "git")]
 impl IdentityError {
     /// Whether this error is caused by something not being found.
     pub fn is_not_found(&self) -> bool {
@@ -66,6 +78,7 @@ impl IdentityError {
     }
 }
 
+#[cfg(feature = "git")]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Identity<I> {
     /// The head of the identity branch. This points to a commit that
@@ -84,6 +97,7 @@ pub struct Identity<I> {
     pub signatures: HashMap<PublicKey, Signature>,
 }
 
+#[cfg(feature = "git")]
 impl Identity<Oid> {
     pub fn verified(self, id: doc::Id) -> Result<Identity<doc::Id>, IdentityError> {
         // The root hash must be equal to the id.
@@ -102,6 +116,7 @@ impl Identity<Oid> {
     }
 }
 
+#[cfg(feature = "git")]
 impl Identity<Untrusted> {
     pub fn load<R: ReadRepository>(
         remote: &RemoteId,
@@ -137,11 +152,7 @@ impl Identity<Untrusted> {
             let untrusted = Doc::<Verified>::load_at(oid.into(), repo)?;
 
             // Check that enough delegates signed this next version.
-            let quorum = untrusted
-                .sigs
-                .iter()
-                .filter(|(key, _)| trusted.delegates.iter().any(|d| **d == **key))
-                .count();
+            let quorum = trusted.quorum(untrusted.sigs.keys());
             if quorum < trusted.threshold {
                 return Err(IdentityError::ThresholdNotReached(
                     quorum,
@@ -1,7 +1,12 @@
+#[cfg(feature = "git")]
 mod id;
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+#[cfg(feature = "git")]
+use std::collections::HashMap;
 use std::fmt;
+#[cfg(feature = "git")]
 use std::fmt::Write as _;
 use std::marker::PhantomData;
 use std::ops::{Deref, Not};
@@ -9,6 +14,7 @@ use std::path::Path;
 
 use nonempty::NonEmpty;
 use once_cell::sync::Lazy;
+#[cfg(feature = "git")]
 use radicle_git_ext::Oid;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -16,13 +22,18 @@ use thiserror::Error;
 use crate::canonical::formatter::CanonicalFormatter;
 use crate::crypto;
 use crate::crypto::{Signature, Unverified, Verified};
+#[cfg(feature = "git")]
 use crate::git;
-use crate::identity::{project::Project, Did};
+use crate::identity::{policy::Policy, project::Project, Did};
+#[cfg(feature = "git")]
 use crate::storage;
+#[cfg(feature = "git")]
 use crate::storage::git::trailers;
+#[cfg(feature = "git")]
 use crate::storage::{ReadRepository, RemoteId};
 
 pub use crypto::PublicKey;
+#[cfg(feature = "git")]
 pub use id::*;
 
 /// Path to the identity document in the identity branch.
@@ -42,14 +53,17 @@ pub enum DocError {
     Delegates(&'static str),
     #[error("invalid signature for {0}: {1}")]
     Signature(PublicKey, crypto::Error),
+    #[cfg(feature = "git")]
     #[error("invalid commit trailers: {0}")]
     Trailers(#[from] trailers::Error),
     #[error("invalid version `{0}`")]
     Version(u32),
     #[error("invalid threshold `{0}`: {1}")]
     Threshold(usize, &'static str),
+    #[cfg(feature = "git")]
     #[error("git: {0}")]
     GitExt(#[from] git::Error),
+    #[cfg(feature = "git")]
     #[error("git: {0}")]
     Git(#[from] git2::Error),
 }
@@ -58,8 +72,11 @@ impl DocError {
     /// Whether this error is caused by the document not being found.
     pub fn is_not_found(&self) -> bool {
         match self {
+            #[cfg(feature = "git")]
             Self::GitExt(git::Error::NotFound(_)) => true,
+            #[cfg(feature = "git")]
             Self::GitExt(git::Error::Git(e)) if git::is_not_found_err(e) => true,
+            #[cfg(feature = "git")]
             Self::Git(err) if git::is_not_found_err(err) => true,
             _ => false,
         }
@@ -83,6 +100,36 @@ impl PayloadId {
     pub fn project() -> Self {
         Self(String::from("xyz.radicle.project"))
     }
+
+    /// Policy payload type.
+    pub fn policy() -> Self {
+        Self(String::from("xyz.radicle.policy"))
+    }
+
+    /// Issue board payload type.
+    pub fn board() -> Self {
+        Self(String::from("xyz.radicle.board"))
+    }
+
+    /// Default seeds payload type.
+    pub fn seeds() -> Self {
+        Self(String::from("xyz.radicle.seeds"))
+    }
+
+    /// Labels payload type.
+    pub fn labels() -> Self {
+        Self(String::from("xyz.radicle.labels"))
+    }
+
+    /// Visibility payload type.
+    pub fn visibility() -> Self {
+        Self(String::from("xyz.radicle.visibility"))
+    }
+
+    /// Acknowledged history rewrites payload type.
+    pub fn rewrites() -> Self {
+        Self(String::from("xyz.radicle.rewrites"))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -114,7 +161,34 @@ impl Deref for Payload {
     }
 }
 
+/// A "visibility" payload in an identity document.
+///
+/// Eg. `"xyz.radicle.visibility": { "type": "private", "allow": ["did:key:z6Mk..."] }`.
+///
+/// Private repositories are only served to their delegates and to the nodes in the
+/// allow-list. Documents without a visibility payload are public.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Visibility {
+    /// Anyone can fetch the repository.
+    #[default]
+    Public,
+    /// Only the delegates and the given nodes can fetch the repository.
+    Private {
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        allow: BTreeSet<Did>,
+    },
+}
+
+impl Visibility {
+    /// Whether the repository is public.
+    pub fn is_public(&self) -> bool {
+        matches!(self, Self::Public)
+    }
+}
+
 /// A verified identity document at a specific commit.
+#[cfg(feature = "git")]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DocAt {
     /// The commit at which this document exists.
@@ -127,6 +201,7 @@ pub struct DocAt {
     pub sigs: HashMap<PublicKey, Signature>,
 }
 
+#[cfg(feature = "git")]
 impl Deref for DocAt {
     type Target = Doc<Verified>;
 
@@ -150,6 +225,26 @@ pub struct Doc<V> {
     verified: PhantomData<V>,
 }
 
+/// Verify signatures over an identity document, given the object id of the document blob.
+pub fn verify_signatures<'a>(
+    blob: &[u8],
+    sigs: impl IntoIterator<Item = (&'a PublicKey, &'a Signature)>,
+) -> Result<(), DocError> {
+    for (pk, sig) in sigs {
+        if let Err(err) = pk.verify(blob, sig) {
+            return Err(DocError::Signature(*pk, err));
+        }
+    }
+    Ok(())
+}
+
+impl<V> Doc<V> {
+    pub fn is_delegate(&self, key: &crypto::PublicKey) -> bool {
+        self.delegates.contains(&key.into())
+    }
+}
+
+#[cfg(feature = "git")]
 impl<V> Doc<V> {
     pub fn canonical_head(repo: &storage::git::Repository) -> Result<Oid, DocError> {
         repo.backend
@@ -167,22 +262,26 @@ impl<V> Doc<V> {
         repo.blob_at(commit, Path::new(&*PATH))
             .map_err(DocError::from)
     }
-
-    pub fn is_delegate(&self, key: &crypto::PublicKey) -> bool {
-        self.delegates.contains(&key.into())
-    }
 }
 
 impl Doc<Verified> {
-    pub fn encode(&self) -> Result<(git::Oid, Vec<u8>), DocError> {
+    /// Encode the document as canonical JSON, as it is stored in the identity branch.
+    pub fn canonical_json(&self) -> Result<Vec<u8>, DocError> {
         let mut buf = Vec::new();
         let mut serializer =
             serde_json::Serializer::with_formatter(&mut buf, CanonicalFormatter::new());
 
         self.serialize(&mut serializer)?;
-        let oid = git2::Oid::hash_object(git2::ObjectType::Blob, &buf)?;
 
-        Ok((oid.into(), buf))
+        Ok(buf)
+    }
+
+    /// Get the number of delegates of this document among the given signers.
+    pub fn quorum<'a>(&self, signers: impl IntoIterator<Item = &'a PublicKey>) -> usize {
+        signers
+            .into_iter()
+            .filter(|key| self.is_delegate(key))
+            .count()
     }
 
     /// Attempt to add a new delegate to the document. Returns `true` if it wasn't there before.
@@ -224,6 +323,124 @@ impl Doc<Verified> {
         Ok(proj)
     }
 
+    /// Get the visibility payload out of this document. Returns [`Visibility::Public`] if the
+    /// document has no visibility payload.
+    pub fn visibility(&self) -> Result<Visibility, PayloadError> {
+        match self.payload.get(&PayloadId::visibility()) {
+            Some(value) => Ok(serde_json::from_value((**value).clone())?),
+            None => Ok(Visibility::Public),
+        }
+    }
+
+    /// Set the visibility of the repository in this document. Removes the visibility payload
+    /// if the repository is public.
+    pub fn set_visibility(&mut self, visibility: &Visibility) -> Result<(), PayloadError> {
+        if visibility.is_public() {
+            self.payload.remove(&PayloadId::visibility());
+        } else {
+            let value = serde_json::to_value(visibility)?;
+            self.payload
+                .insert(PayloadId::visibility(), Payload::from(value));
+        }
+        Ok(())
+    }
+
+    /// Whether the repository may be served to the given node. Delegates can always fetch
+    /// the repository.
+    pub fn is_visible_to(&self, key: &crypto::PublicKey) -> Result<bool, PayloadError> {
+        if self.is_delegate(key) {
+            return Ok(true);
+        }
+        match self.visibility()? {
+            Visibility::Public => Ok(true),
+            Visibility::Private { allow } => Ok(allow.contains(&Did::from(key))),
+        }
+    }
+
+    /// Get the policy payload out of this document. Returns the default policy if the
+    /// document has no policy payload.
+    pub fn policy(&self) -> Result<Policy, PayloadError> {
+        match self.payload.get(&PayloadId::policy()) {
+            Some(value) => Ok(serde_json::from_value((**value).clone())?),
+            None => Ok(Policy::default()),
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Doc<Verified> {
+    /// Get the default seeds of the repository out of this document, eg.
+    /// `"xyz.radicle.seeds": { "seeds": ["<nid>@<addr>"] }`. Returns no seeds if the
+    /// document has no seeds payload.
+    pub fn seeds(&self) -> Result<Vec<crate::node::ConnectAddress>, PayloadError> {
+        #[derive(Deserialize)]
+        struct Seeds {
+            seeds: Vec<crate::node::ConnectAddress>,
+        }
+
+        match self.payload.get(&PayloadId::seeds()) {
+            Some(value) => {
+                let Seeds { seeds } = serde_json::from_value((**value).clone())?;
+                Ok(seeds)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Set the default seeds of the repository in this document. Removes the seeds payload
+    /// if no seeds are given.
+    pub fn set_seeds(&mut self, seeds: &[crate::node::ConnectAddress]) {
+        if seeds.is_empty() {
+            self.payload.remove(&PayloadId::seeds());
+        } else {
+            let value = serde_json::json!({ "seeds": seeds });
+            self.payload
+                .insert(PayloadId::seeds(), Payload::from(value));
+        }
+    }
+
+    /// Get the heads of the canonical branch history rewrites acknowledged by the delegates
+    /// out of this document, eg. `"xyz.radicle.rewrites": { "heads": ["<oid>"] }`. Returns no
+    /// heads if the document has no rewrites payload.
+    ///
+    /// Nodes only adopt a canonical head that rewrites the history of the canonical branch
+    /// if it is, or descends from, one of these heads.
+    pub fn rewrites(&self) -> Result<BTreeSet<Oid>, PayloadError> {
+        #[derive(Deserialize)]
+        struct Rewrites {
+            heads: BTreeSet<Oid>,
+        }
+
+        match self.payload.get(&PayloadId::rewrites()) {
+            Some(value) => {
+                let Rewrites { heads } = serde_json::from_value((**value).clone())?;
+                Ok(heads)
+            }
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// Acknowledge a rewrite of the canonical branch history to the given head. Returns
+    /// `false` if it was already acknowledged.
+    pub fn acknowledge_rewrite(&mut self, head: Oid) -> Result<bool, PayloadError> {
+        let mut heads = self.rewrites()?;
+        if !heads.insert(head) {
+            return Ok(false);
+        }
+        let value = serde_json::json!({ "heads": heads });
+        self.payload
+            .insert(PayloadId::rewrites(), Payload::from(value));
+
+        Ok(true)
+    }
+
+    pub fn encode(&self) -> Result<(git::Oid, Vec<u8>), DocError> {
+        let buf = self.canonical_json()?;
+        let oid = git2::Oid::hash_object(git2::ObjectType::Blob, &buf)?;
+
+        Ok((oid.into(), buf))
+    }
+
     pub fn sign<G: crypto::Signer>(&self, signer: &G) -> Result<(git::Oid, Signature), DocError> {
         let (oid, _) = self.encode()?;
         let sig = signer.sign(oid.as_bytes());
@@ -245,11 +462,8 @@ impl Doc<Verified> {
             .ok_or(DocError::Commit("commit message is not UTF-8"))?;
         let sigs = trailers::parse_signatures(msg)?;
 
-        for (pk, sig) in &sigs {
-            if let Err(err) = pk.verify(blob.id().as_bytes(), sig) {
-                return Err(DocError::Signature(*pk, err));
-            }
-        }
+        verify_signatures(blob.id().as_bytes(), &sigs)?;
+
         Ok(DocAt {
             commit: oid,
             doc,
@@ -380,7 +594,10 @@ impl Doc<Unverified> {
             verified: PhantomData,
         })
     }
+}
 
+#[cfg(feature = "git")]
+impl Doc<Unverified> {
     pub fn load_at<R: ReadRepository>(commit: Oid, repo: &R) -> Result<(Self, Oid), DocError> {
         let blob = Self::blob_at(commit, repo)?;
         let doc = Doc::from_json(blob.content())?;
@@ -479,6 +696,94 @@ mod test {
         assert_eq!(doc, Doc::canonical(&repo).unwrap().doc);
     }
 
+    #[test]
+    fn test_verify_signatures() {
+        let alice = MockSigner::from_seed([0xaa; 32]);
+        let bob = MockSigner::from_seed([0xbb; 32]);
+        let eve = MockSigner::from_seed([0xee; 32]);
+        let project = Project::new(
+            String::from("heartwood"),
+            String::from("Radicle Heartwood Protocol & Stack"),
+            git::refname!("master"),
+        )
+        .unwrap();
+        let delegates = NonEmpty::from((
+            Did::from(alice.public_key()),
+            vec![Did::from(bob.public_key())],
+        ));
+        let doc = Doc::new(project, delegates, 2).verified().unwrap();
+        let (blob, sig) = doc.sign(&alice).unwrap();
+
+        verify_signatures(blob.as_bytes(), [(alice.public_key(), &sig)]).unwrap();
+        assert!(matches!(
+            verify_signatures(blob.as_bytes(), [(eve.public_key(), &sig)]),
+            Err(DocError::Signature(key, _)) if key == *eve.public_key()
+        ));
+        assert_eq!(doc.quorum([alice.public_key(), eve.public_key()]), 1);
+        assert_eq!(doc.quorum([alice.public_key(), bob.public_key()]), 2);
+    }
+
+    #[test]
+    fn test_seeds() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let seed: crate::node::ConnectAddress =
+            "z6MkedTZGJGqgQ2py2b8kGecfxdt2yRdHWF6JpaZC47fovFn@seed.radicle.xyz:8776"
+                .parse()
+                .unwrap();
+        assert!(doc.seeds().unwrap().is_empty());
+
+        doc.set_seeds(&[seed.clone()]);
+        let (_, bytes) = doc.encode().unwrap();
+        let doc = Doc::from_json(&bytes).unwrap().verified().unwrap();
+        assert_eq!(doc.seeds().unwrap(), vec![seed]);
+
+        let mut doc = doc;
+        doc.set_seeds(&[]);
+        assert!(!doc.payload.contains_key(&PayloadId::seeds()));
+    }
+
+    #[test]
+    fn test_rewrites() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let head = arbitrary::oid();
+        assert!(doc.rewrites().unwrap().is_empty());
+
+        assert!(doc.acknowledge_rewrite(head).unwrap());
+        assert!(!doc.acknowledge_rewrite(head).unwrap());
+
+        let (_, bytes) = doc.encode().unwrap();
+        let doc = Doc::from_json(&bytes).unwrap().verified().unwrap();
+        assert_eq!(doc.rewrites().unwrap(), BTreeSet::from([head]));
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let delegate = **doc.delegates.first();
+        let peer = arbitrary::gen::<PublicKey>(1);
+        assert_eq!(doc.visibility().unwrap(), Visibility::Public);
+        assert!(doc.is_visible_to(&peer).unwrap());
+
+        doc.set_visibility(&Visibility::Private {
+            allow: BTreeSet::new(),
+        })
+        .unwrap();
+        let (_, bytes) = doc.encode().unwrap();
+        let mut doc = Doc::from_json(&bytes).unwrap().verified().unwrap();
+        assert!(!doc.visibility().unwrap().is_public());
+        assert!(!doc.is_visible_to(&peer).unwrap());
+        assert!(doc.is_visible_to(&delegate).unwrap());
+
+        doc.set_visibility(&Visibility::Private {
+            allow: BTreeSet::from([Did::from(peer)]),
+        })
+        .unwrap();
+        assert!(doc.is_visible_to(&peer).unwrap());
+
+        doc.set_visibility(&Visibility::Public).unwrap();
+        assert!(!doc.payload.contains_key(&PayloadId::visibility()));
+    }
+
     #[quickcheck]
     fn prop_encode_decode(doc: Doc<Verified>) {
         let (_, bytes) = doc.encode().unwrap();
@@ -0,0 +1,279 @@
+//! Repository policy payload, and the checks it enables.
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "git")]
+use thiserror::Error;
+
+#[cfg(feature = "git")]
+use crate::cob::patch::{self, PatchId, Patches, Verdict};
+#[cfg(feature = "git")]
+use crate::cob::store;
+#[cfg(feature = "git")]
+use crate::git;
+#[cfg(feature = "git")]
+use crate::identity::Did;
+#[cfg(feature = "git")]
+use crate::storage::git::Repository;
+
+/// Trailer used to certify the origin of a commit. See <https://developercertificate.org>.
+pub const SIGNOFF_TRAILER: &str = "Signed-off-by";
+
+/// A "policy" payload in an identity document.
+///
+/// Eg. `"xyz.radicle.policy": { "requireSignoff": true, "protectDefaultBranch": { "approvals": 1 } }`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Policy {
+    /// Whether commits proposed to the default branch must be signed-off by their author,
+    /// with a `Signed-off-by` trailer.
+    #[serde(default)]
+    pub require_signoff: bool,
+    /// Protection of the default branch. When set, the default branch can only be updated to
+    /// the merge of a patch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protect_default_branch: Option<Protection>,
+}
+
+/// Default branch protection settings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Protection {
+    /// Number of delegates, other than the revision author, who must have accepted
+    /// a patch revision before it can be merged.
+    #[serde(default)]
+    pub approvals: usize,
+}
+
+/// Error returned when a commit is missing its sign-off.
+#[cfg(feature = "git")]
+#[derive(Debug, Error)]
+pub enum SignoffError {
+    #[error("commit {0} is not signed-off by its author; add a `{SIGNOFF_TRAILER}` trailer, eg. with `git commit --signoff`")]
+    Missing(git::Oid),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+}
+
+/// Error returned when an update to a protected branch is not allowed.
+#[cfg(feature = "git")]
+#[derive(Debug, Error)]
+pub enum ProtectionError {
+    #[error("commit {0} is not the merge of a patch; the default branch is protected")]
+    Unmerged(git::Oid),
+    #[error(
+        "patch {patch} has {approvals} approval(s) from delegates, but {required} are required"
+    )]
+    NotApproved {
+        patch: PatchId,
+        approvals: usize,
+        required: usize,
+    },
+    #[error(transparent)]
+    Patch(#[from] patch::Error),
+    #[error(transparent)]
+    Store(#[from] store::Error),
+}
+
+#[cfg(feature = "git")]
+impl Policy {
+    /// Check that the commits between `base` and `head` satisfy the policy.
+    ///
+    /// Merge commits are not checked.
+    pub fn check(
+        &self,
+        repo: &git2::Repository,
+        base: Option<git::Oid>,
+        head: git::Oid,
+    ) -> Result<(), SignoffError> {
+        if !self.require_signoff {
+            return Ok(());
+        }
+        let mut walk = repo.revwalk()?;
+        walk.push(*head)?;
+
+        if let Some(base) = base {
+            walk.hide(*base)?;
+        }
+        for oid in walk {
+            let commit = repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            if !is_signed_off(&commit) {
+                return Err(SignoffError::Missing(commit.id().into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the default branch can be updated to `head`, given the branch protection.
+    ///
+    /// The head must be recorded as the merge of a patch revision, with enough approvals
+    /// from the given delegates.
+    pub fn check_protection(
+        &self,
+        repo: &Repository,
+        head: git::Oid,
+        delegates: &[Did],
+    ) -> Result<(), ProtectionError> {
+        let Some(protection) = &self.protect_default_branch else {
+            return Ok(());
+        };
+        let patches = Patches::open(repo)?;
+        let Some((patch_id, patch, revision_id)) = patches.find_by_merge(&head)? else {
+            return Err(ProtectionError::Unmerged(head));
+        };
+        let Some(revision) = patch.revision(&revision_id) else {
+            return Err(ProtectionError::Unmerged(head));
+        };
+        let author = revision.author().id();
+        let approvals = revision
+            .reviews()
+            .filter(|(key, review)| {
+                let did = Did::from(**key);
+                did != *author
+                    && delegates.contains(&did)
+                    && review.verdict() == Some(Verdict::Accept)
+            })
+            .count();
+
+        if approvals < protection.approvals {
+            return Err(ProtectionError::NotApproved {
+                patch: patch_id,
+                approvals,
+                required: protection.approvals,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Check whether a commit has a `Signed-off-by` trailer matching its author's email.
+#[cfg(feature = "git")]
+pub fn is_signed_off(commit: &git2::Commit) -> bool {
+    let author = commit.author();
+    let Some(email) = author.email() else {
+        return false;
+    };
+    let message = String::from_utf8_lossy(commit.message_bytes());
+    // Trailers are in the last paragraph of the message.
+    let trailers = message.trim_end().rsplit("\n\n").next().unwrap_or_default();
+
+    trailers.lines().any(|line| {
+        line.strip_prefix(SIGNOFF_TRAILER)
+            .and_then(|l| l.strip_prefix(':'))
+            .map_or(false, |value| value.trim().ends_with(&format!("<{email}>")))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::cob::patch::MergeTarget;
+    use crate::crypto::Signer as _;
+    use crate::test::{fixtures, setup};
+
+    fn commit(repo: &git2::Repository, parent: git2::Oid, message: &str) -> git2::Oid {
+        let parent = repo.find_commit(parent).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+
+        repo.commit(
+            None,
+            &sig,
+            &sig,
+            message,
+            &parent.tree().unwrap(),
+            &[&parent],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_signoff() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (repo, base) = fixtures::repository(tmp.path());
+        let policy = Policy {
+            require_signoff: true,
+            ..Policy::default()
+        };
+        let signed = commit(
+            &repo,
+            base,
+            "Add feature\n\nSigned-off-by: anonymous <anonymous@radicle.xyz>\n",
+        );
+        let unsigned = commit(&repo, signed, "Fix feature\n");
+        let other = commit(
+            &repo,
+            signed,
+            "Fix feature\n\nSigned-off-by: someone <someone@radicle.xyz>\n",
+        );
+
+        policy
+            .check(&repo, Some(base.into()), signed.into())
+            .unwrap();
+        assert!(matches!(
+            policy.check(&repo, Some(base.into()), unsigned.into()),
+            Err(SignoffError::Missing(oid)) if oid == unsigned.into()
+        ));
+        assert!(matches!(
+            policy.check(&repo, Some(base.into()), other.into()),
+            Err(SignoffError::Missing(oid)) if oid == other.into()
+        ));
+        Policy::default()
+            .check(&repo, Some(base.into()), unsigned.into())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_check_protection() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = setup::context(&tmp);
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let delegates = [Did::from(*signer.public_key())];
+        let mut policy = Policy {
+            protect_default_branch: Some(Protection { approvals: 0 }),
+            ..Policy::default()
+        };
+        let mut patches = Patches::open(&project).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                base,
+                oid,
+                &[],
+                &signer,
+            )
+            .unwrap();
+        let (rid, _) = patch.latest().unwrap();
+        let rid = *rid;
+
+        assert!(matches!(
+            policy.check_protection(&project, oid, &delegates),
+            Err(ProtectionError::Unmerged(_))
+        ));
+        patch
+            .review(rid, Some(Verdict::Accept), None, vec![], &signer)
+            .unwrap();
+        patch.merge(rid, oid, &signer).unwrap();
+        policy.check_protection(&project, oid, &delegates).unwrap();
+
+        // The revision author's own approval doesn't count.
+        policy.protect_default_branch = Some(Protection { approvals: 1 });
+        assert!(matches!(
+            policy.check_protection(&project, oid, &delegates),
+            Err(ProtectionError::NotApproved {
+                approvals: 0,
+                required: 1,
+                ..
+            })
+        ));
+        Policy::default()
+            .check_protection(&project, base, &delegates)
+            .unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use git_ref_format::RefString as BranchName;
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Serialize,
@@ -9,7 +10,6 @@ use thiserror::Error;
 use crate::crypto;
 use crate::identity::doc;
 use crate::identity::doc::Payload;
-use crate::storage::BranchName;
 
 pub use crypto::PublicKey;
 
@@ -8,31 +8,52 @@ pub extern crate radicle_crypto as crypto;
 #[macro_use]
 extern crate amplify;
 
+#[cfg(feature = "git")]
+pub mod api;
 mod canonical;
+#[cfg(feature = "git")]
 pub mod cob;
+#[cfg(feature = "git")]
 pub mod collections;
+#[cfg(feature = "git")]
 pub mod git;
+pub mod glob;
 pub mod identity;
+#[cfg(feature = "git")]
 pub mod node;
+#[cfg(feature = "git")]
 pub mod profile;
+#[cfg(feature = "git")]
 pub mod rad;
 pub mod serde_ext;
+#[cfg(feature = "git")]
 pub mod sql;
+#[cfg(feature = "git")]
 pub mod storage;
 #[cfg(any(test, feature = "test"))]
 pub mod test;
+pub mod verify;
 
+#[cfg(feature = "git")]
 pub use node::Node;
+#[cfg(feature = "git")]
 pub use profile::Profile;
+#[cfg(feature = "git")]
 pub use storage::git::Storage;
 
 pub mod prelude {
     use super::*;
 
     pub use crypto::{PublicKey, Signer, Verified};
-    pub use identity::{project::Project, Did, Doc, Id};
+    pub use identity::{project::Project, Did, Doc};
+
+    #[cfg(feature = "git")]
+    pub use identity::Id;
+    #[cfg(feature = "git")]
     pub use node::{NodeId, Timestamp};
+    #[cfg(feature = "git")]
     pub use profile::Profile;
+    #[cfg(feature = "git")]
     pub use storage::{BranchName, ReadRepository, ReadStorage, WriteRepository, WriteStorage};
 }
 
@@ -1,7 +1,17 @@
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod attestation;
+pub mod audit;
+mod capabilities;
 mod features;
 
 pub mod events;
+pub mod inventory;
+pub mod metrics;
+pub mod reputation;
 pub mod routing;
+pub mod signal;
+pub mod trace;
 pub mod tracking;
 
 use std::collections::{BTreeSet, HashSet};
@@ -18,11 +28,13 @@ use serde::{Deserialize, Serialize};
 use serde_json as json;
 
 use crate::crypto::PublicKey;
-use crate::identity::Id;
-use crate::storage::RefUpdate;
+use crate::identity::{Did, Id};
+use crate::storage::{Oid, RefUpdate};
 
+pub use capabilities::Capabilities;
 pub use events::{Event, Events};
 pub use features::Features;
+pub use signal::Signal;
 
 /// Default name for control socket file.
 pub const DEFAULT_SOCKET_NAME: &str = "control.sock";
@@ -36,6 +48,16 @@ pub const ROUTING_DB_FILE: &str = "routing.db";
 pub const ADDRESS_DB_FILE: &str = "addresses.db";
 /// Filename of tracking table database under the node directory.
 pub const TRACKING_DB_FILE: &str = "tracking.db";
+/// Filename of inventory cache database under the node directory.
+pub const INVENTORY_DB_FILE: &str = "inventory.db";
+/// Filename of connection audit log database under the node directory.
+pub const AUDIT_DB_FILE: &str = "audit.db";
+/// Filename of the file holding the node's process id, under the node directory.
+pub const PID_FILE: &str = "node.pid";
+/// Filename of the node log, under the node directory, when running in the background.
+pub const LOG_FILE: &str = "node.log";
+/// Maximum number of repositories a peer returns in answer to a search.
+pub const SEARCH_RESULTS_LIMIT: usize = 32;
 
 /// Milliseconds since epoch.
 pub type Timestamp = u64;
@@ -93,9 +115,10 @@ impl From<CommandResult> for Result<bool, Error> {
 }
 
 /// Peer public protocol address.
-#[derive(Wrapper, WrapperMut, Clone, Eq, PartialEq, Debug, From)]
+#[derive(Wrapper, WrapperMut, Clone, Eq, PartialEq, Debug, From, Serialize, Deserialize)]
 #[wrapper(Deref, Display, FromStr)]
 #[wrapper_mut(DerefMut)]
+#[serde(try_from = "String", into = "String")]
 pub struct Address(NetAddr<HostName>);
 
 impl cyphernet::addr::Host for Address {
@@ -110,6 +133,20 @@ impl cyphernet::addr::Addr for Address {
     }
 }
 
+impl TryFrom<String> for Address {
+    type Error = <NetAddr<HostName> as std::str::FromStr>::Err;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Address> for String {
+    fn from(value: Address) -> Self {
+        value.to_string()
+    }
+}
+
 impl From<net::SocketAddr> for Address {
     fn from(addr: net::SocketAddr) -> Self {
         Address(NetAddr {
@@ -119,6 +156,82 @@ impl From<net::SocketAddr> for Address {
     }
 }
 
+/// Address of a peer to connect to, in the form `<nid>@<addr>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ConnectAddress {
+    /// Peer node identifier.
+    pub id: NodeId,
+    /// Peer address.
+    pub addr: Address,
+}
+
+impl fmt::Display for ConnectAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.id, self.addr)
+    }
+}
+
+impl std::str::FromStr for ConnectAddress {
+    type Err = ConnectAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, addr) = s
+            .split_once('@')
+            .ok_or_else(|| ConnectAddressError::Format(s.to_owned()))?;
+        let id = id.parse()?;
+        let addr = addr
+            .parse()
+            .map_err(|_| ConnectAddressError::Address(addr.to_owned()))?;
+
+        Ok(Self { id, addr })
+    }
+}
+
+impl TryFrom<String> for ConnectAddress {
+    type Error = ConnectAddressError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ConnectAddress> for String {
+    fn from(value: ConnectAddress) -> Self {
+        value.to_string()
+    }
+}
+
+/// Options passed to [`Handle::connect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// Whether the connection should be maintained by the node, ie. re-established
+    /// when it drops, until the node is stopped.
+    pub persistent: bool,
+    /// How long to wait for the node to respond.
+    pub timeout: time::Duration,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            persistent: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// Error parsing a [`ConnectAddress`].
+#[derive(thiserror::Error, Debug)]
+pub enum ConnectAddressError {
+    #[error("expected `<nid>@<addr>`, got '{0}'")]
+    Format(String),
+    #[error("invalid node id: {0}")]
+    Id(#[from] crate::crypto::PublicKeyError),
+    #[error("invalid address '{0}'")]
+    Address(String),
+}
+
 /// Command name.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -131,8 +244,12 @@ pub enum CommandName {
     SyncInventory,
     /// Connect to node with the given address.
     Connect,
+    /// Disconnect from the given node.
+    Disconnect,
     /// Lookup seeds for the given repository in the routing table.
     Seeds,
+    /// Get the known addresses of the given node.
+    Addresses,
     /// Fetch the given repository from the network.
     Fetch,
     /// Track the given repository.
@@ -145,10 +262,26 @@ pub enum CommandName {
     UntrackNode,
     /// Get the node's status.
     Status,
+    /// Get the peers the node is connected to.
+    Sessions,
     /// Shutdown the node.
     Shutdown,
     /// Subscribe to events.
     Subscribe,
+    /// Search the repositories of connected peers.
+    Search,
+    /// Send a signal to a connected peer.
+    Signal,
+    /// Enable or disable gossip tracing.
+    TraceGossip,
+    /// Get the recorded gossip traces.
+    GossipTraces,
+    /// Reload the node configuration from the profile.
+    ReloadConfig,
+    /// Ban the given node.
+    Ban,
+    /// Unban the given node.
+    Unban,
 }
 
 impl fmt::Display for CommandName {
@@ -243,6 +376,33 @@ impl Seeds {
     }
 }
 
+/// The metadata of a repository matching a search query, as returned by a peer. This lets
+/// users see what a repository is before cloning it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// Repository identifier.
+    pub rid: Id,
+    /// Project name.
+    pub name: String,
+    /// Project description.
+    pub description: String,
+    /// Canonical head of the repository's default branch, on the peer.
+    pub head: Oid,
+    /// Repository delegates.
+    pub delegates: Vec<Did>,
+}
+
+impl SearchResult {
+    /// Check whether a project name or description matches a search query. Matching is a
+    /// case-insensitive substring search, so that the empty query matches every project.
+    pub fn matches(name: &str, description: &str, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        name.to_lowercase().contains(&query) || description.to_lowercase().contains(&query)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "kebab-case")]
 pub enum FetchResult {
@@ -369,6 +529,15 @@ impl Error {
     pub fn is_connection_err(&self) -> bool {
         matches!(self, Self::Connect(_))
     }
+
+    /// Check if the error is due to the node not responding in time.
+    pub fn is_timeout(&self) -> bool {
+        matches!(
+            self,
+            Self::Call(CallError::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        )
+    }
 }
 
 /// Error returned by [`Node::call`] iterator.
@@ -394,16 +563,35 @@ pub trait Handle: Clone + Sync + Send {
     /// Check if the node is running. to a peer.
     fn is_running(&self) -> bool;
     /// Connect to a peer.
-    fn connect(&mut self, node: NodeId, addr: Address) -> Result<(), Self::Error>;
+    fn connect(
+        &mut self,
+        node: NodeId,
+        addr: Address,
+        opts: ConnectOptions,
+    ) -> Result<(), Self::Error>;
+    /// Disconnect from a peer. Returns `false` if we weren't connected to it.
+    fn disconnect(&mut self, node: NodeId) -> Result<bool, Self::Error>;
     /// Lookup the seeds of a given repository in the routing table.
     fn seeds(&mut self, id: Id) -> Result<Seeds, Self::Error>;
-    /// Fetch a repository from the network.
-    fn fetch(&mut self, id: Id, from: NodeId) -> Result<FetchResult, Self::Error>;
+    /// Fetch a repository from the network, waiting up to the given timeout for the
+    /// fetch to complete.
+    fn fetch(
+        &mut self,
+        id: Id,
+        from: NodeId,
+        timeout: time::Duration,
+    ) -> Result<FetchResult, Self::Error>;
     /// Start tracking the given project. Doesn't do anything if the project is already
     /// tracked.
     fn track_repo(&mut self, id: Id, scope: tracking::Scope) -> Result<bool, Self::Error>;
-    /// Start tracking the given node.
-    fn track_node(&mut self, id: NodeId, alias: Option<String>) -> Result<bool, Self::Error>;
+    /// Start tracking the given node. If an expiry is given, the node is untracked
+    /// once that time is reached.
+    fn track_node(
+        &mut self,
+        id: NodeId,
+        alias: Option<String>,
+        expiry: Option<Timestamp>,
+    ) -> Result<bool, Self::Error>;
     /// Untrack the given project and delete it from storage.
     fn untrack_repo(&mut self, id: Id) -> Result<bool, Self::Error>;
     /// Untrack the given node.
@@ -414,6 +602,29 @@ pub trait Handle: Clone + Sync + Send {
     fn announce_inventory(&mut self) -> Result<(), Self::Error>;
     /// Notify the service that our inventory was updated.
     fn sync_inventory(&mut self) -> Result<bool, Self::Error>;
+    /// Ask connected peers for the repositories whose name or description contain the given
+    /// query. Returns the number of peers that were asked. Results are returned as
+    /// [`Event::SearchResults`] events.
+    fn search(&mut self, query: String) -> Result<usize, Self::Error>;
+    /// Send a signal to a connected peer. Returns `false` if the peer isn't connected, or
+    /// doesn't accept signals. Signals received from peers are returned as [`Event::Signal`]
+    /// events.
+    fn signal(&mut self, to: NodeId, signal: Signal) -> Result<bool, Self::Error>;
+    /// Enable or disable gossip tracing. Disabling it drops the recorded traces.
+    /// Returns `true` if tracing was toggled, and `false` if it already was in that state.
+    fn trace_gossip(&mut self, enable: bool) -> Result<bool, Self::Error>;
+    /// Get the recorded gossip traces, oldest first, or `None` if tracing is disabled.
+    fn gossip_traces(&self) -> Result<Option<Vec<trace::Trace>>, Self::Error>;
+    /// Reload the node settings of the profile configuration, and apply them to the running
+    /// node. Returns whether any setting changed.
+    fn reload_config(&mut self) -> Result<bool, Self::Error>;
+    /// Get the known addresses of the given node, from the address book.
+    fn addresses(&self, id: NodeId) -> Result<Vec<Address>, Self::Error>;
+    /// Ban the given node: disconnect from it, and neither connect to it nor accept its
+    /// connections. Returns `false` if it was already banned.
+    fn ban(&mut self, id: NodeId) -> Result<bool, Self::Error>;
+    /// Unban the given node, and reset its reputation. Returns `false` if it wasn't banned.
+    fn unban(&mut self, id: NodeId) -> Result<bool, Self::Error>;
     /// Ask the service to shutdown.
     fn shutdown(self) -> Result<(), Self::Error>;
     /// Query the peer session state.
@@ -470,7 +681,8 @@ impl Node {
 // TODO(finto): repo_policies, node_policies, and routing should all
 // attempt to return iterators instead of allocating vecs.
 impl Handle for Node {
-    type Sessions = ();
+    /// The peers the node is connected to.
+    type Sessions = Vec<NodeId>;
     type Error = Error;
 
     fn is_running(&self) -> bool {
@@ -483,11 +695,15 @@ impl Handle for Node {
         matches!(result, CommandResult::Okay { .. })
     }
 
-    fn connect(&mut self, nid: NodeId, addr: Address) -> Result<(), Error> {
+    fn connect(&mut self, nid: NodeId, addr: Address, opts: ConnectOptions) -> Result<(), Error> {
         self.call::<_, CommandResult>(
             CommandName::Connect,
-            [nid.to_human(), addr.to_string()],
-            DEFAULT_TIMEOUT,
+            [
+                nid.to_human(),
+                addr.to_string(),
+                opts.persistent.to_string(),
+            ],
+            opts.timeout,
         )?
         .next()
         .ok_or(Error::EmptyResponse {
@@ -496,6 +712,15 @@ impl Handle for Node {
         Ok(())
     }
 
+    fn disconnect(&mut self, nid: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(CommandName::Disconnect, [nid], DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::Disconnect,
+        })??;
+
+        response.into()
+    }
+
     fn seeds(&mut self, id: Id) -> Result<Seeds, Error> {
         let seeds: Seeds = self
             .call(CommandName::Seeds, [id.urn()], DEFAULT_TIMEOUT)?
@@ -507,12 +732,17 @@ impl Handle for Node {
         Ok(seeds)
     }
 
-    fn fetch(&mut self, id: Id, from: NodeId) -> Result<FetchResult, Error> {
+    fn fetch(
+        &mut self,
+        id: Id,
+        from: NodeId,
+        timeout: time::Duration,
+    ) -> Result<FetchResult, Error> {
         let result = self
             .call(
                 CommandName::Fetch,
-                [id.urn(), from.to_human()],
-                DEFAULT_TIMEOUT,
+                [id.urn(), from.to_human(), timeout.as_secs().to_string()],
+                timeout,
             )?
             .next()
             .ok_or(Error::EmptyResponse {
@@ -522,13 +752,19 @@ impl Handle for Node {
         Ok(result)
     }
 
-    fn track_node(&mut self, id: NodeId, alias: Option<String>) -> Result<bool, Error> {
-        let id = id.to_human();
-        let args = if let Some(alias) = alias.as_deref() {
-            vec![id.as_str(), alias]
+    fn track_node(
+        &mut self,
+        id: NodeId,
+        alias: Option<String>,
+        expiry: Option<Timestamp>,
+    ) -> Result<bool, Error> {
+        let mut args = vec![id.to_human()];
+        if let Some(expiry) = expiry {
+            args.push(alias.unwrap_or_default());
+            args.push(expiry.to_string());
         } else {
-            vec![id.as_str()]
-        };
+            args.extend(alias);
+        }
 
         let mut line = self.call(CommandName::TrackNode, args, DEFAULT_TIMEOUT)?;
         let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
@@ -596,6 +832,91 @@ impl Handle for Node {
         response.into()
     }
 
+    fn search(&mut self, query: String) -> Result<usize, Error> {
+        let peers = self
+            .call(CommandName::Search, [query], DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::Search,
+            })??;
+
+        Ok(peers)
+    }
+
+    fn signal(&mut self, to: NodeId, signal: Signal) -> Result<bool, Error> {
+        // SAFETY: Signals can always be serialized.
+        #[allow(clippy::unwrap_used)]
+        let signal = json::to_string(&signal).unwrap();
+        let mut line = self.call(
+            CommandName::Signal,
+            [to.to_human(), signal],
+            DEFAULT_TIMEOUT,
+        )?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::Signal,
+        })??;
+
+        response.into()
+    }
+
+    fn trace_gossip(&mut self, enable: bool) -> Result<bool, Error> {
+        let mut line = self.call(CommandName::TraceGossip, [enable], DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::TraceGossip,
+        })??;
+
+        response.into()
+    }
+
+    fn gossip_traces(&self) -> Result<Option<Vec<trace::Trace>>, Error> {
+        let traces = self
+            .call::<&str, _>(CommandName::GossipTraces, [], DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::GossipTraces,
+            })??;
+
+        Ok(traces)
+    }
+
+    fn reload_config(&mut self) -> Result<bool, Error> {
+        let mut line = self.call::<&str, _>(CommandName::ReloadConfig, [], DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::ReloadConfig,
+        })??;
+
+        response.into()
+    }
+
+    fn addresses(&self, id: NodeId) -> Result<Vec<Address>, Error> {
+        let addresses = self
+            .call(CommandName::Addresses, [id], DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::Addresses,
+            })??;
+
+        Ok(addresses)
+    }
+
+    fn ban(&mut self, id: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(CommandName::Ban, [id], DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::Ban,
+        })??;
+
+        response.into()
+    }
+
+    fn unban(&mut self, id: NodeId) -> Result<bool, Error> {
+        let mut line = self.call(CommandName::Unban, [id], DEFAULT_TIMEOUT)?;
+        let response: CommandResult = line.next().ok_or(Error::EmptyResponse {
+            cmd: CommandName::Unban,
+        })??;
+
+        response.into()
+    }
+
     fn subscribe(
         &self,
         timeout: time::Duration,
@@ -613,7 +934,14 @@ impl Handle for Node {
     }
 
     fn sessions(&self) -> Result<Self::Sessions, Error> {
-        todo!();
+        let sessions = self
+            .call::<&str, _>(CommandName::Sessions, [], DEFAULT_TIMEOUT)?
+            .next()
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::Sessions,
+            })??;
+
+        Ok(sessions)
     }
 
     fn shutdown(self) -> Result<(), Error> {
@@ -629,4 +957,28 @@ mod test {
     fn test_command_name_display() {
         assert_eq!(CommandName::TrackNode.to_string(), "track-node");
     }
+
+    #[test]
+    fn test_search_result_matches() {
+        assert!(SearchResult::matches("heartwood", "Radicle", "heart"));
+        assert!(SearchResult::matches("heartwood", "Radicle", "RADICLE"));
+        assert!(SearchResult::matches("heartwood", "", ""));
+        assert!(!SearchResult::matches("heartwood", "Radicle", "acme"));
+    }
+
+    #[test]
+    fn test_connect_address() {
+        let nid = crate::test::arbitrary::gen::<NodeId>(1);
+        let addr = format!("{nid}@seed.radicle.xyz:8776");
+        let seed: ConnectAddress = addr.parse().unwrap();
+
+        assert_eq!(seed.id, nid);
+        assert_eq!(seed.to_string(), addr);
+        assert_eq!(
+            json::from_value::<ConnectAddress>(json::Value::String(addr)).unwrap(),
+            seed
+        );
+        assert!("seed.radicle.xyz:8776".parse::<ConnectAddress>().is_err());
+        assert!(format!("{nid}@").parse::<ConnectAddress>().is_err());
+    }
 }
@@ -0,0 +1,348 @@
+//! Asynchronous node controller, for use with the `tokio` runtime.
+//!
+//! This mirrors the [`Handle`](super::Handle) implementation of [`super::Node`], but talks
+//! to the control socket without blocking, and returns node events as a [`Stream`].
+use std::future::{poll_fn, Future as _};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{io, time};
+
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use serde_json as json;
+use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader, Lines};
+use tokio::net::UnixStream;
+
+use super::{
+    tracking, Address, CallError, Command, CommandName, CommandResult, ConnectOptions, Error,
+    Event, FetchResult, NodeId, Seeds, Timestamp, DEFAULT_TIMEOUT,
+};
+use crate::identity::Id;
+
+/// Asynchronous node controller.
+#[derive(Debug, Clone)]
+pub struct Node {
+    socket: PathBuf,
+}
+
+impl Node {
+    /// Connect to the node, via the socket at the given path.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            socket: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Call a command on the node, and get a stream of responses. Each response must
+    /// arrive within the given timeout, or a [`io::ErrorKind::TimedOut`] error is returned.
+    pub async fn call<A: ToString, T: DeserializeOwned>(
+        &self,
+        name: CommandName,
+        args: impl IntoIterator<Item = A>,
+        timeout: time::Duration,
+    ) -> Result<Responses<T>, io::Error> {
+        let mut stream = UnixStream::connect(&self.socket).await?;
+        let mut cmd = Vec::new();
+
+        Command::new(name, args).to_writer(&mut cmd)?;
+        stream.write_all(&cmd).await?;
+
+        Ok(Responses {
+            cmd: name,
+            lines: BufReader::new(stream).lines(),
+            timeout,
+            deadline: Box::pin(tokio::time::sleep(timeout)),
+            response: PhantomData,
+        })
+    }
+
+    /// Check if the node is running.
+    pub async fn is_running(&self) -> bool {
+        let Ok(mut responses) = self
+            .call::<&str, CommandResult>(CommandName::Status, [], DEFAULT_TIMEOUT)
+            .await
+        else {
+            return false;
+        };
+        matches!(responses.next().await, Some(Ok(CommandResult::Okay { .. })))
+    }
+
+    /// Connect to a peer.
+    pub async fn connect(
+        &self,
+        nid: NodeId,
+        addr: Address,
+        opts: ConnectOptions,
+    ) -> Result<(), Error> {
+        self.call_one::<_, CommandResult>(
+            CommandName::Connect,
+            [
+                nid.to_human(),
+                addr.to_string(),
+                opts.persistent.to_string(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lookup the seeds of a given repository in the routing table.
+    pub async fn seeds(&self, id: Id) -> Result<Seeds, Error> {
+        self.call_one(CommandName::Seeds, [id.urn()]).await
+    }
+
+    /// Fetch a repository from the network, waiting up to the given timeout for the
+    /// fetch to complete.
+    pub async fn fetch(
+        &self,
+        id: Id,
+        from: NodeId,
+        timeout: time::Duration,
+    ) -> Result<FetchResult, Error> {
+        let result = self
+            .call(
+                CommandName::Fetch,
+                [id.urn(), from.to_human(), timeout.as_secs().to_string()],
+                timeout,
+            )
+            .await?
+            .next()
+            .await
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::Fetch,
+            })??;
+
+        Ok(result)
+    }
+
+    /// Start tracking the given repository.
+    pub async fn track_repo(&self, id: Id, scope: tracking::Scope) -> Result<bool, Error> {
+        self.call_one::<_, CommandResult>(CommandName::TrackRepo, [id.urn(), scope.to_string()])
+            .await?
+            .into()
+    }
+
+    /// Start tracking the given node, optionally until the given expiry.
+    pub async fn track_node(
+        &self,
+        id: NodeId,
+        alias: Option<String>,
+        expiry: Option<Timestamp>,
+    ) -> Result<bool, Error> {
+        let mut args = vec![id.to_human()];
+        if let Some(expiry) = expiry {
+            args.push(alias.unwrap_or_default());
+            args.push(expiry.to_string());
+        } else {
+            args.extend(alias);
+        }
+
+        self.call_one::<_, CommandResult>(CommandName::TrackNode, args)
+            .await?
+            .into()
+    }
+
+    /// Untrack the given repository and delete it from storage.
+    pub async fn untrack_repo(&self, id: Id) -> Result<bool, Error> {
+        self.call_one::<_, CommandResult>(CommandName::UntrackRepo, [id.urn()])
+            .await?
+            .into()
+    }
+
+    /// Untrack the given node.
+    pub async fn untrack_node(&self, id: NodeId) -> Result<bool, Error> {
+        self.call_one::<_, CommandResult>(CommandName::UntrackNode, [id])
+            .await?
+            .into()
+    }
+
+    /// Notify the service that a repository has been updated, and announce local refs.
+    pub async fn announce_refs(&self, id: Id) -> Result<(), Error> {
+        self.call_all(CommandName::AnnounceRefs, [id.urn()]).await
+    }
+
+    /// Announce local inventory.
+    pub async fn announce_inventory(&self) -> Result<(), Error> {
+        self.call_all::<&str>(CommandName::AnnounceInventory, [])
+            .await
+    }
+
+    /// Notify the service that our inventory was updated.
+    pub async fn sync_inventory(&self) -> Result<bool, Error> {
+        self.call_one::<&str, CommandResult>(CommandName::SyncInventory, [])
+            .await?
+            .into()
+    }
+
+    /// Query the peer sessions.
+    pub async fn sessions(&self) -> Result<Vec<NodeId>, Error> {
+        self.call_one::<&str, _>(CommandName::Sessions, []).await
+    }
+
+    /// Subscribe to node events. The stream returns a [`io::ErrorKind::TimedOut`] error
+    /// if no event is received within the given timeout.
+    pub async fn subscribe(
+        &self,
+        timeout: time::Duration,
+    ) -> Result<impl Stream<Item = Result<Event, io::Error>>, Error> {
+        let events = self
+            .call::<&str, Event>(CommandName::Subscribe, [], timeout)
+            .await?;
+
+        Ok(Events(events))
+    }
+
+    /// Call a command, and return its first response.
+    async fn call_one<A: ToString, T: DeserializeOwned>(
+        &self,
+        cmd: CommandName,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<T, Error> {
+        let response = self
+            .call(cmd, args, DEFAULT_TIMEOUT)
+            .await?
+            .next()
+            .await
+            .ok_or(Error::EmptyResponse { cmd })??;
+
+        Ok(response)
+    }
+
+    /// Call a command, and check all its responses.
+    async fn call_all<A: ToString>(
+        &self,
+        cmd: CommandName,
+        args: impl IntoIterator<Item = A>,
+    ) -> Result<(), Error> {
+        let mut responses = self
+            .call::<_, CommandResult>(cmd, args, DEFAULT_TIMEOUT)
+            .await?;
+
+        while let Some(response) = responses.next().await {
+            response?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream of responses to a command sent to the node.
+pub struct Responses<T> {
+    cmd: CommandName,
+    lines: Lines<BufReader<UnixStream>>,
+    timeout: time::Duration,
+    deadline: Pin<Box<tokio::time::Sleep>>,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Responses<T> {
+    /// Get the next response, if any.
+    pub async fn next(&mut self) -> Option<Result<T, CallError>> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl<T: DeserializeOwned> Stream for Responses<T> {
+    type Item = Result<T, CallError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.lines).poll_next_line(cx) {
+            Poll::Ready(Ok(Some(line))) => {
+                let deadline = tokio::time::Instant::now() + this.timeout;
+                this.deadline.as_mut().reset(deadline);
+
+                Poll::Ready(Some(json::from_str(&line).map_err(|e| {
+                    CallError::InvalidJson {
+                        cmd: this.cmd,
+                        response: line,
+                        error: e,
+                    }
+                })))
+            }
+            Poll::Ready(Ok(None)) => Poll::Ready(None),
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            Poll::Pending => match this.deadline.as_mut().poll(cx) {
+                Poll::Ready(()) => {
+                    let deadline = tokio::time::Instant::now() + this.timeout;
+                    this.deadline.as_mut().reset(deadline);
+
+                    Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::TimedOut).into())))
+                }
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+}
+
+/// Stream of node events.
+struct Events(Responses<Event>);
+
+impl Stream for Events {
+    type Item = Result<Event, io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().0).poll_next(cx).map(|event| {
+            event.map(|e| {
+                e.map_err(|err| match err {
+                    CallError::Io(e) => e,
+                    CallError::InvalidJson { .. } => {
+                        io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+                    }
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_call() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("control.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            let cmd: Command = json::from_str(&line).unwrap();
+            assert!(matches!(cmd.name, CommandName::SyncInventory));
+
+            CommandResult::updated().to_writer(&mut stream).unwrap();
+            stream.flush().unwrap();
+        });
+        let node = Node::new(&socket);
+
+        assert!(node.sync_inventory().await.unwrap());
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_timeout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("control.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let node = Node::new(&socket);
+        let mut responses = node
+            .call::<&str, CommandResult>(CommandName::Status, [], time::Duration::from_millis(10))
+            .await
+            .unwrap();
+        let _stream = listener.accept().unwrap();
+
+        assert!(matches!(
+            responses.next().await,
+            Some(Err(CallError::Io(e))) if e.kind() == io::ErrorKind::TimedOut
+        ));
+    }
+}
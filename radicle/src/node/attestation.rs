@@ -0,0 +1,127 @@
+//! Node attestations, binding a node to the addresses it can be reached at.
+//!
+//! An attestation is a statement of a node's addresses and alias, signed with the node's key.
+//! Seed operators can publish it, eg. in a DNS record or on a web page, and anyone can check
+//! that the addresses were given by the operator of the node, until the attestation expires.
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::canonical::formatter::CanonicalFormatter;
+use crate::crypto;
+use crate::crypto::{Signature, Signer};
+use crate::node::tracking::Alias;
+use crate::node::{Address, NodeId, Timestamp};
+
+/// Error verifying an attestation.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid signature: {0}")]
+    Signature(#[from] crypto::Error),
+    #[error("attestation expired at {0}")]
+    Expired(Timestamp),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// What a node attests to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    /// The node making the statement.
+    pub nid: NodeId,
+    /// Addresses the node can be reached at.
+    pub addresses: Vec<Address>,
+    /// Alias of the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Alias>,
+    /// Time after which the statement is no longer valid, in milliseconds since the epoch.
+    pub expiry: Timestamp,
+}
+
+impl Statement {
+    /// Encode the statement as canonical JSON, which is what gets signed.
+    pub fn canonical(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        let mut serializer =
+            serde_json::Serializer::with_formatter(&mut buf, CanonicalFormatter::new());
+
+        self.serialize(&mut serializer)?;
+
+        Ok(buf)
+    }
+}
+
+/// A statement, signed by the node making it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attestation {
+    /// The statement.
+    #[serde(flatten)]
+    pub statement: Statement,
+    /// Signature of the canonical statement, by the node.
+    pub signature: Signature,
+}
+
+impl Attestation {
+    /// Create an attestation for the node of the given signer.
+    pub fn new<G: Signer>(
+        addresses: Vec<Address>,
+        alias: Option<Alias>,
+        expiry: Timestamp,
+        signer: &G,
+    ) -> Result<Self, Error> {
+        let statement = Statement {
+            nid: *signer.public_key(),
+            addresses,
+            alias,
+            expiry,
+        };
+        let signature = signer.sign(&statement.canonical()?);
+
+        Ok(Self {
+            statement,
+            signature,
+        })
+    }
+
+    /// Check that the attestation was signed by the node it is about, and that it hasn't
+    /// expired at time `now`.
+    pub fn verify(&self, now: Timestamp) -> Result<(), Error> {
+        self.statement
+            .nid
+            .verify(self.statement.canonical()?, &self.signature)?;
+
+        if self.statement.expiry <= now {
+            return Err(Error::Expired(self.statement.expiry));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::test::signer::MockSigner;
+
+    #[test]
+    fn test_attestation_verify() {
+        let signer = MockSigner::default();
+        let addr: Address = "seed.radicle.xyz:8776".parse().unwrap();
+        let attestation =
+            Attestation::new(vec![addr], Some(String::from("seed")), 1000, &signer).unwrap();
+
+        attestation.verify(999).unwrap();
+        assert!(matches!(
+            attestation.verify(1000),
+            Err(Error::Expired(1000))
+        ));
+
+        let json = serde_json::to_string(&attestation).unwrap();
+        let decoded: Attestation = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, attestation);
+
+        let mut forged = attestation;
+        forged.statement.addresses = vec!["0.0.0.0:8776".parse().unwrap()];
+        assert!(matches!(forged.verify(999), Err(Error::Signature(_))));
+    }
+}
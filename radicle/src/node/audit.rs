@@ -0,0 +1,272 @@
+//! Connection audit log.
+//!
+//! Every inbound and outbound connection attempt is logged once it is closed, along with
+//! the peer's address, whether the handshake completed, why the connection was closed and
+//! how many bytes were exchanged. This lets node operators investigate abuse or connectivity
+//! problems after the fact. The log is rolling: entries older than [`RETENTION`] are pruned
+//! as new ones are added, and the log never holds more than [`MAX_ENTRIES`] entries.
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, time};
+
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::node::{Address, NodeId, Timestamp};
+
+/// How long entries are kept in the log, in milliseconds.
+pub const RETENTION: Timestamp = 1000 * 60 * 60 * 24 * 30;
+/// Maximum number of entries kept in the log. The oldest entries are pruned first.
+pub const MAX_ENTRIES: usize = 100_000;
+
+/// How long to wait for the database lock to be released before failing a read.
+const DB_READ_TIMEOUT: time::Duration = time::Duration::from_secs(3);
+/// How long to wait for the database lock to be released before failing a write.
+const DB_WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring while accessing the audit log.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+}
+
+/// Direction of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The peer connected to us.
+    Inbound,
+    /// We connected to the peer.
+    Outbound,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inbound => f.write_str("inbound"),
+            Self::Outbound => f.write_str("outbound"),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inbound" => Ok(Self::Inbound),
+            "outbound" => Ok(Self::Outbound),
+            _ => Err(s.to_owned()),
+        }
+    }
+}
+
+/// A connection, as logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// When the connection was attempted, in milliseconds since the epoch.
+    pub time: Timestamp,
+    /// The peer, if known.
+    pub nid: Option<NodeId>,
+    /// Address of the peer.
+    pub addr: Address,
+    /// Connection direction.
+    pub direction: Direction,
+    /// Whether the connection was established.
+    pub established: bool,
+    /// Why the connection was closed.
+    pub reason: String,
+    /// Bytes received from the peer.
+    pub received: u64,
+    /// Bytes sent to the peer.
+    pub sent: u64,
+}
+
+/// Persistent connection audit log.
+pub struct Log {
+    db: sql::Connection,
+    max_entries: usize,
+}
+
+impl fmt::Debug for Log {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Log(..)")
+    }
+}
+
+impl Log {
+    const SCHEMA: &str = include_str!("audit/schema.sql");
+
+    /// Open an audit log at the given path. Creates a new empty log
+    /// if an existing one isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_WRITE_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self {
+            db,
+            max_entries: MAX_ENTRIES,
+        })
+    }
+
+    /// Same as [`Self::open`], but in read-only mode.
+    pub fn reader<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db =
+            sql::Connection::open_with_flags(path, sqlite::OpenFlags::new().set_read_only())?;
+        db.set_busy_timeout(DB_READ_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self {
+            db,
+            max_entries: MAX_ENTRIES,
+        })
+    }
+
+    /// Create a new in-memory audit log.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self {
+            db,
+            max_entries: MAX_ENTRIES,
+        })
+    }
+
+    /// Set the maximum number of entries kept in the log. Defaults to [`MAX_ENTRIES`].
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Log a closed connection, and prune entries that are older than [`RETENTION`],
+    /// relative to the entry's time, as well as the oldest entries beyond the maximum.
+    pub fn record(&mut self, entry: Entry) -> Result<(), Error> {
+        crate::sql::transaction(&self.db, |db| {
+            let mut stmt = db.prepare(
+                "INSERT INTO connections
+                 (time, node, address, direction, established, reason, received, sent)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            stmt.bind((1, entry.time as i64))?;
+            stmt.bind((2, entry.nid.as_ref()))?;
+            stmt.bind((3, entry.addr.to_string().as_str()))?;
+            stmt.bind((4, entry.direction.to_string().as_str()))?;
+            stmt.bind((5, entry.established as i64))?;
+            stmt.bind((6, entry.reason.as_str()))?;
+            stmt.bind((7, entry.received as i64))?;
+            stmt.bind((8, entry.sent as i64))?;
+            stmt.next()?;
+
+            let mut stmt = db.prepare("DELETE FROM connections WHERE time < ?")?;
+            stmt.bind((1, entry.time.saturating_sub(RETENTION) as i64))?;
+            stmt.next()?;
+
+            // Row ids increase with every entry, so the oldest entries have the lowest ones.
+            let mut stmt =
+                db.prepare("DELETE FROM connections WHERE rowid <= last_insert_rowid() - ?")?;
+            stmt.bind((1, self.max_entries as i64))?;
+            stmt.next()?;
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the connections attempted at or after the given time, oldest first.
+    pub fn since(&self, time: Timestamp) -> Result<Vec<Entry>, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT time, node, address, direction, established, reason, received, sent
+             FROM connections
+             WHERE time >= ?
+             ORDER BY time",
+        )?;
+        stmt.bind((1, time as i64))?;
+
+        let mut entries = Vec::new();
+        for row in stmt.into_iter() {
+            let row = row?;
+            let direction = row.read::<&str, _>("direction");
+            let direction = Direction::from_str(direction).map_err(|e| sql::Error {
+                code: None,
+                message: Some(format!("sql: invalid connection direction '{e}'")),
+            })?;
+
+            entries.push(Entry {
+                time: row.read::<i64, _>("time") as Timestamp,
+                nid: row.read::<Option<NodeId>, _>("node"),
+                addr: row.read::<Address, _>("address"),
+                direction,
+                established: row.read::<i64, _>("established") != 0,
+                reason: row.read::<String, _>("reason"),
+                received: row.read::<i64, _>("received") as u64,
+                sent: row.read::<i64, _>("sent") as u64,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_record_and_prune() {
+        let mut log = Log::memory().unwrap();
+        let nid = arbitrary::gen::<NodeId>(1);
+        let addr: Address = "127.0.0.1:8776".parse().unwrap();
+        let entry = |time: Timestamp, nid: Option<NodeId>| Entry {
+            time,
+            nid,
+            addr: addr.clone(),
+            direction: Direction::Inbound,
+            established: nid.is_some(),
+            reason: String::from("connection reset"),
+            received: 1024,
+            sent: 512,
+        };
+
+        log.record(entry(1000, Some(nid))).unwrap();
+        log.record(entry(2000, None)).unwrap();
+
+        assert_eq!(
+            log.since(0).unwrap(),
+            vec![entry(1000, Some(nid)), entry(2000, None)]
+        );
+        assert_eq!(log.since(1500).unwrap(), vec![entry(2000, None)]);
+
+        // Recording an entry past the retention period prunes older ones.
+        log.record(entry(RETENTION + 1500, None)).unwrap();
+        assert_eq!(
+            log.since(0).unwrap(),
+            vec![entry(2000, None), entry(RETENTION + 1500, None)]
+        );
+    }
+
+    #[test]
+    fn test_record_max_entries() {
+        let mut log = Log::memory().unwrap().with_max_entries(3);
+        let addr: Address = "127.0.0.1:8776".parse().unwrap();
+        let entry = |time: Timestamp| Entry {
+            time,
+            nid: None,
+            addr: addr.clone(),
+            direction: Direction::Outbound,
+            established: false,
+            reason: String::from("connection refused"),
+            received: 0,
+            sent: 0,
+        };
+
+        for time in 1..=5 {
+            log.record(entry(time)).unwrap();
+        }
+        // Only the most recent entries are kept.
+        assert_eq!(log.since(0).unwrap(), vec![entry(3), entry(4), entry(5)]);
+    }
+}
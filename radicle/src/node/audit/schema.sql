@@ -0,0 +1,24 @@
+--
+-- Connection audit log SQL schema.
+--
+create table if not exists "connections" (
+  -- UNIX time in milliseconds at which the connection was attempted.
+  "time"         integer   not null,
+  -- Node ID of the peer, if known. Inbound connections that are closed
+  -- before the handshake completes don't have one.
+  "node"         text      default null,
+  -- Address of the peer.
+  "address"      text      not null,
+  -- Connection direction, either 'inbound' or 'outbound'.
+  "direction"    text      not null,
+  -- Whether the connection was established, ie. the handshake completed.
+  "established"  integer   not null,
+  -- Why the connection was closed.
+  "reason"       text      not null,
+  -- Bytes received from the peer.
+  "received"     integer   not null default 0,
+  -- Bytes sent to the peer.
+  "sent"         integer   not null default 0
+);
+-- Entries are queried and pruned by time.
+create index if not exists "connections_time" on "connections" ("time");
@@ -0,0 +1,110 @@
+//! Protocol capabilities negotiated between connected nodes.
+use std::{fmt, ops};
+
+/// Protocol capabilities of a node. Unlike [`super::Features`], which are announced to the
+/// whole network, capabilities are exchanged when a session is established, and only the
+/// capabilities supported by *both* ends of a session may be used on it.
+///
+/// This allows new message types and encodings to be rolled out gradually: nodes only
+/// use them with peers that advertize the corresponding capability.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// `NONE` means no optional capabilities are supported.
+    pub const NONE: Capabilities = Capabilities(0);
+
+    /// `ZSTD` means large gossip messages may be sent compressed with zstd.
+    pub const ZSTD: Capabilities = Capabilities(0b00000001);
+
+    /// `SEARCH` means the node answers repository search queries.
+    pub const SEARCH: Capabilities = Capabilities(0b00000010);
+
+    /// `SIGNAL` means the node accepts [`super::Signal`]s.
+    pub const SIGNAL: Capabilities = Capabilities(0b00000100);
+
+    /// `RESUME` means the node issues session tickets, and resumes the sessions of peers
+    /// that present them when reconnecting. Requires [`Capabilities::SIGNAL`].
+    pub const RESUME: Capabilities = Capabilities(0b00001000);
+
+    /// Returns [`Capabilities`] with the other capabilities added.
+    #[must_use]
+    pub const fn with(self, other: Capabilities) -> Capabilities {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns the [`Capabilities`] supported by both `self` and `other`.
+    #[must_use]
+    pub fn intersection(self, other: Capabilities) -> Capabilities {
+        Self(self.0 & other.0)
+    }
+
+    /// Check whether [`Capabilities`] are included.
+    pub fn has(self, flags: Capabilities) -> bool {
+        (self.0 & flags.0) == flags.0
+    }
+}
+
+impl ops::Deref for Capabilities {
+    type Target = u64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == Capabilities::NONE {
+            write!(f, "Capabilities(NONE)")
+        } else {
+            write!(f, "Capabilities(0x{:x})", self.0)
+        }
+    }
+}
+
+impl From<u64> for Capabilities {
+    fn from(c: u64) -> Self {
+        Capabilities(c)
+    }
+}
+
+impl From<Capabilities> for u64 {
+    fn from(flags: Capabilities) -> Self {
+        flags.0
+    }
+}
+
+impl ops::BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.with(rhs)
+    }
+}
+
+impl ops::BitAnd for Capabilities {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_operations() {
+        let a = Capabilities::from(0b011);
+        let b = Capabilities::from(0b110);
+
+        assert_eq!(a & b, Capabilities::from(0b010));
+        assert_eq!(a | b, Capabilities::from(0b111));
+        assert!(a.has(Capabilities::from(0b001)));
+        assert!(!a.has(b));
+        assert!(a.has(Capabilities::NONE));
+        assert_eq!(Capabilities::NONE.intersection(a), Capabilities::NONE);
+    }
+}
@@ -3,8 +3,9 @@ use std::time;
 
 use crossbeam_channel as chan;
 
+use crate::node::{SearchResult, Signal};
 use crate::prelude::*;
-use crate::storage::RefUpdate;
+use crate::storage::{Oid, RefUpdate};
 
 /// A service event.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -30,6 +31,35 @@ pub enum Event {
     PeerConnected {
         nid: NodeId,
     },
+    /// Fetched content was refused by the node's content policy.
+    FetchRefused {
+        remote: NodeId,
+        rid: Id,
+        /// The refused namespace, or `None` if the whole repository was refused.
+        namespace: Option<NodeId>,
+        reason: String,
+    },
+    /// Fetching from a peer rewrote the history of a repository's canonical branch, ie. the
+    /// new canonical head doesn't descend from the current one. Unless adopted, the current
+    /// head is kept until the rewrite is acknowledged by the delegates.
+    CanonicalRewrite {
+        remote: NodeId,
+        rid: Id,
+        old: Oid,
+        new: Oid,
+        adopted: bool,
+    },
+    /// A peer answered a search query.
+    SearchResults {
+        remote: NodeId,
+        query: String,
+        results: Vec<SearchResult>,
+    },
+    /// A peer sent us a signal.
+    Signal {
+        remote: NodeId,
+        signal: Signal,
+    },
 }
 
 /// Events feed.
@@ -12,6 +12,14 @@ impl Features {
     /// `SEED` is the base feature set all seed nodes must support.
     pub const SEED: Features = Features(0b00000001);
 
+    /// `OBSERVER` nodes take part in routing and address gossip, but neither fetch nor
+    /// serve repositories. Fetches should never be routed to them.
+    pub const OBSERVER: Features = Features(0b00000010);
+
+    /// `HELLO` nodes understand the `Hello` message, and negotiate a protocol version and
+    /// capabilities with their peers. Nodes without it must never be sent a `Hello`.
+    pub const HELLO: Features = Features(0b00000100);
+
     /// Returns [`Features`] with the other features added.
     #[must_use]
     pub fn with(self, other: Features) -> Features {
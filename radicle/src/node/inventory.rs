@@ -0,0 +1,298 @@
+//! Persistent cache of the local node's inventory.
+//!
+//! Computing the inventory requires opening every repository in storage. On nodes
+//! hosting thousands of repositories, this can take a long time, so the last known
+//! inventory is persisted and re-validated lazily after startup.
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, time};
+
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::git::Oid;
+use crate::prelude::{Id, Timestamp};
+use crate::sql::transaction;
+
+/// How long to wait for the database lock to be released before failing a write.
+const DB_WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring while accessing the inventory cache.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    /// Invalid object id stored in the cache.
+    #[error("invalid head `{0}` for repository {1}")]
+    InvalidHead(String, Id),
+    /// Internal unit overflow.
+    #[error("the unit overflowed")]
+    UnitOverflow,
+}
+
+/// A cached inventory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The repository.
+    pub rid: Id,
+    /// The repository head at the time the entry was validated. Repositories whose head
+    /// can't be computed, eg. because the delegates diverged, are cached without one.
+    pub head: Option<Oid>,
+    /// The time at which the entry was last validated against storage.
+    pub time: Timestamp,
+}
+
+/// Persistent file storage for the local inventory.
+pub struct Cache {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cache(..)")
+    }
+}
+
+impl Cache {
+    const SCHEMA: &str = include_str!("inventory/schema.sql");
+
+    /// Open an inventory cache at the given path. Creates a new empty cache
+    /// if an existing one isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_WRITE_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory inventory cache.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Get the cached entry for a repository.
+    pub fn get(&self, rid: &Id) -> Result<Option<Entry>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT head, time FROM inventory WHERE repo = ?")?;
+        stmt.bind((1, rid))?;
+
+        if let Some(row) = stmt.into_iter().next() {
+            let row = row?;
+            let head = read_head(&row, rid)?;
+            let time = row.read::<i64, _>("time") as Timestamp;
+
+            return Ok(Some(Entry {
+                rid: *rid,
+                head,
+                time,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Get all cached entries.
+    pub fn entries(&self) -> Result<Vec<Entry>, Error> {
+        let stmt = self.db.prepare("SELECT repo, head, time FROM inventory")?;
+        let mut entries = Vec::new();
+
+        for row in stmt.into_iter() {
+            let row = row?;
+            let rid = row.read::<Id, _>("repo");
+            let head = read_head(&row, &rid)?;
+            let time = row.read::<i64, _>("time") as Timestamp;
+
+            entries.push(Entry { rid, head, time });
+        }
+        Ok(entries)
+    }
+
+    /// Get the cached inventory.
+    pub fn inventory(&self) -> Result<Vec<Id>, Error> {
+        Ok(self.entries()?.into_iter().map(|e| e.rid).collect())
+    }
+
+    /// Insert or update an entry. Returns `true` if the repository wasn't cached,
+    /// or if its head changed.
+    pub fn insert(&mut self, rid: Id, head: Option<Oid>, time: Timestamp) -> Result<bool, Error> {
+        let time: i64 = time.try_into().map_err(|_| Error::UnitOverflow)?;
+        let changed = self.get(&rid)?.map_or(true, |e| e.head != head);
+
+        transaction(&self.db, |db| upsert(db, &rid, head, time))?;
+
+        Ok(changed)
+    }
+
+    /// Replace the cached inventory with the given entries, in a single transaction.
+    /// Cached repositories that aren't part of the new inventory are removed.
+    pub fn set(
+        &mut self,
+        inventory: impl IntoIterator<Item = (Id, Option<Oid>)>,
+        time: Timestamp,
+    ) -> Result<(), Error> {
+        let time: i64 = time.try_into().map_err(|_| Error::UnitOverflow)?;
+        let cached = self.inventory()?;
+
+        transaction(&self.db, |db| {
+            let mut included = HashSet::new();
+
+            for (rid, head) in inventory {
+                upsert(db, &rid, head, time)?;
+                included.insert(rid);
+            }
+            for rid in cached.iter().filter(|rid| !included.contains(rid)) {
+                let mut stmt = db.prepare("DELETE FROM inventory WHERE repo = ?")?;
+
+                stmt.bind((1, rid))?;
+                stmt.next()?;
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Remove a repository from the cache.
+    pub fn remove(&mut self, rid: &Id) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare("DELETE FROM inventory WHERE repo = ?")?;
+
+        stmt.bind((1, rid))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Get the number of cached repositories.
+    pub fn len(&self) -> Result<usize, Error> {
+        let stmt = self.db.prepare("SELECT COUNT(1) FROM inventory")?;
+        let count: i64 = stmt
+            .into_iter()
+            .next()
+            .expect("COUNT will always return a single row")?
+            .read(0);
+        let count: usize = count.try_into().map_err(|_| Error::UnitOverflow)?;
+
+        Ok(count)
+    }
+
+    /// Check whether the cache is empty.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+}
+
+/// Insert or update a single entry.
+fn upsert(db: &sql::Connection, rid: &Id, head: Option<Oid>, time: i64) -> Result<(), sql::Error> {
+    let mut stmt = db.prepare(
+        "INSERT INTO inventory (repo, head, time)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT DO UPDATE
+         SET head = ?2, time = ?3",
+    )?;
+
+    stmt.bind((1, rid))?;
+    stmt.bind((2, head.map(|h| h.to_string()).as_deref()))?;
+    stmt.bind((3, time))?;
+    stmt.next()?;
+
+    Ok(())
+}
+
+/// Read the head column of a row.
+fn read_head(row: &sql::Row, rid: &Id) -> Result<Option<Oid>, Error> {
+    row.read::<Option<&str>, _>("head")
+        .map(|head| Oid::from_str(head).map_err(|_| Error::InvalidHead(head.to_owned(), *rid)))
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_insert_and_get() {
+        let rid = arbitrary::gen::<Id>(1);
+        let head = arbitrary::oid();
+        let mut cache = Cache::memory().unwrap();
+
+        assert!(cache.get(&rid).unwrap().is_none());
+        assert!(cache.insert(rid, Some(head), 1).unwrap());
+        assert!(!cache.insert(rid, Some(head), 2).unwrap());
+        assert_eq!(
+            cache.get(&rid).unwrap(),
+            Some(Entry {
+                rid,
+                head: Some(head),
+                time: 2
+            })
+        );
+        assert!(cache.insert(rid, Some(arbitrary::oid()), 3).unwrap());
+
+        // Repositories without a head are cached too.
+        assert!(cache.insert(rid, None, 4).unwrap());
+        assert_eq!(
+            cache.get(&rid).unwrap(),
+            Some(Entry {
+                rid,
+                head: None,
+                time: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_set() {
+        let ids = arbitrary::vec::<Id>(6);
+        let mut cache = Cache::memory().unwrap();
+
+        cache
+            .set(ids[..4].iter().map(|id| (*id, Some(arbitrary::oid()))), 1)
+            .unwrap();
+        assert_eq!(cache.len().unwrap(), 4);
+
+        // Entries that are no longer in the inventory are removed.
+        cache.set(ids[2..].iter().map(|id| (*id, None)), 2).unwrap();
+        assert_eq!(
+            cache
+                .inventory()
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>(),
+            ids[2..].iter().copied().collect()
+        );
+        assert!(cache
+            .entries()
+            .unwrap()
+            .iter()
+            .all(|e| e.head.is_none() && e.time == 2));
+    }
+
+    #[test]
+    fn test_inventory_and_remove() {
+        let ids = arbitrary::set::<Id>(5..10);
+        let mut cache = Cache::memory().unwrap();
+
+        for id in &ids {
+            cache.insert(*id, Some(arbitrary::oid()), 0).unwrap();
+        }
+        let inventory = cache.inventory().unwrap();
+
+        assert_eq!(cache.len().unwrap(), ids.len());
+        assert_eq!(inventory.into_iter().collect::<HashSet<_>>(), ids);
+
+        for id in &ids {
+            assert!(cache.remove(id).unwrap());
+            assert!(!cache.remove(id).unwrap());
+        }
+        assert!(cache.is_empty().unwrap());
+    }
+}
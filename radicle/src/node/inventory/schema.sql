@@ -0,0 +1,11 @@
+--
+-- Inventory cache SQL schema.
+--
+create table if not exists "inventory" (
+  -- Repository in local storage.
+  "repo"         text      primary key not null,
+  -- Head of the repository when the entry was last validated, if it could be computed.
+  "head"         text,
+  -- UNIX time at which this entry was last validated against storage.
+  "time"         integer   not null
+);
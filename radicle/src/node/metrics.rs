@@ -0,0 +1,259 @@
+//! Peer metrics, used to prefer faster seeds when fetching.
+//!
+//! The round-trip time of pings and the throughput of fetches are tracked per peer, as
+//! exponentially weighted moving averages, so that recent measurements count more than
+//! older ones. Metrics are stored alongside the address book, in the same database.
+use std::path::Path;
+
+use localtime::LocalDuration;
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::prelude::NodeId;
+use crate::sql::Database;
+
+/// An error occuring while accessing peer metrics.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+}
+
+/// Metrics of a single peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// Smoothed round-trip time, in milliseconds.
+    pub rtt: Option<u64>,
+    /// Smoothed fetch throughput, in bytes per second.
+    pub throughput: Option<u64>,
+    /// Number of successful fetches.
+    pub fetches: u64,
+    /// Number of failed fetches.
+    pub failures: u64,
+}
+
+impl Metrics {
+    /// Round-trip time assumed for peers we haven't measured yet, in milliseconds.
+    pub const DEFAULT_RTT: u64 = 200;
+    /// Throughput assumed for peers we haven't fetched from yet, in bytes per second.
+    pub const DEFAULT_THROUGHPUT: u64 = 128 * 1024;
+
+    /// Update the metrics with a round-trip time sample.
+    pub fn with_rtt(mut self, rtt: LocalDuration) -> Self {
+        let sample = rtt.as_millis() as u64;
+        // Nb. Same smoothing factor as TCP, ie. 1/8.
+        self.rtt = Some(self.rtt.map_or(sample, |rtt| (rtt * 7 + sample) / 8));
+        self
+    }
+
+    /// Update the metrics with a successful fetch of `received` bytes, which took `elapsed`.
+    pub fn with_fetch(mut self, received: u64, elapsed: LocalDuration) -> Self {
+        let millis = (elapsed.as_millis() as u64).max(1);
+        let sample = received.saturating_mul(1000) / millis;
+
+        self.throughput = Some(
+            self.throughput
+                .map_or(sample, |t| (t.saturating_mul(3).saturating_add(sample)) / 4),
+        );
+        self.fetches += 1;
+        self
+    }
+
+    /// Update the metrics with a failed fetch.
+    pub fn with_failure(mut self) -> Self {
+        self.failures += 1;
+        self
+    }
+
+    /// Score of the peer as a seed. Higher is better.
+    ///
+    /// The score is the throughput in KiB/s, weighted by the ratio of successful fetches,
+    /// and divided by the round-trip time in tenths of a second. Unmeasured values are
+    /// replaced by defaults, so that new peers are still given a chance.
+    pub fn score(&self) -> f64 {
+        let rtt = self.rtt.unwrap_or(Self::DEFAULT_RTT).max(1) as f64 / 100.;
+        let throughput = self.throughput.unwrap_or(Self::DEFAULT_THROUGHPUT) as f64 / 1024.;
+        let success = (self.fetches + 1) as f64 / (self.fetches + self.failures + 1) as f64;
+
+        throughput * success / rtt
+    }
+}
+
+/// Persistent file storage for peer metrics.
+#[derive(Debug)]
+pub struct Table {
+    db: Database,
+}
+
+impl Table {
+    const SCHEMA: &str = include_str!("metrics/schema.sql");
+
+    /// Open a metrics table at the given path. Creates a new empty table
+    /// if an existing one isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = Database::open(path, Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Same as [`Self::open`], but in read-only mode.
+    pub fn reader<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = Database::reader(path, Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory metrics table.
+    pub fn memory() -> Result<Self, Error> {
+        let db = Database::memory(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Get the metrics of a peer. Returns default metrics for unknown peers.
+    pub fn get(&self, node: &NodeId) -> Result<Metrics, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT rtt, throughput, fetches, failures FROM metrics WHERE node = ?")?;
+        stmt.bind((1, node))?;
+
+        if let Some(row) = stmt.into_iter().next() {
+            let row = row?;
+
+            return Ok(Metrics {
+                rtt: row.read::<Option<i64>, _>("rtt").map(|v| v as u64),
+                throughput: row.read::<Option<i64>, _>("throughput").map(|v| v as u64),
+                fetches: row.read::<i64, _>("fetches") as u64,
+                failures: row.read::<i64, _>("failures") as u64,
+            });
+        }
+        Ok(Metrics::default())
+    }
+
+    /// Record a round-trip time sample for a peer.
+    pub fn record_rtt(&mut self, node: &NodeId, rtt: LocalDuration) -> Result<Metrics, Error> {
+        let metrics = self.get(node)?.with_rtt(rtt);
+        self.put(node, &metrics)?;
+
+        Ok(metrics)
+    }
+
+    /// Record a successful fetch from a peer.
+    pub fn record_fetch(
+        &mut self,
+        node: &NodeId,
+        received: u64,
+        elapsed: LocalDuration,
+    ) -> Result<Metrics, Error> {
+        let metrics = self.get(node)?.with_fetch(received, elapsed);
+        self.put(node, &metrics)?;
+
+        Ok(metrics)
+    }
+
+    /// Record a failed fetch from a peer.
+    pub fn record_failure(&mut self, node: &NodeId) -> Result<Metrics, Error> {
+        let metrics = self.get(node)?.with_failure();
+        self.put(node, &metrics)?;
+
+        Ok(metrics)
+    }
+
+    /// Sort the given nodes by score, best first.
+    pub fn rank<'a>(
+        &self,
+        nodes: impl IntoIterator<Item = &'a NodeId>,
+    ) -> Result<Vec<(NodeId, Metrics)>, Error> {
+        let mut ranked = nodes
+            .into_iter()
+            .map(|n| self.get(n).map(|m| (*n, m)))
+            .collect::<Result<Vec<_>, _>>()?;
+        ranked.sort_by(|(_, a), (_, b)| b.score().total_cmp(&a.score()));
+
+        Ok(ranked)
+    }
+
+    fn put(&mut self, node: &NodeId, metrics: &Metrics) -> Result<(), Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO metrics (node, rtt, throughput, fetches, failures)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT DO UPDATE
+             SET rtt = ?2, throughput = ?3, fetches = ?4, failures = ?5",
+        )?;
+
+        stmt.bind((1, node))?;
+        stmt.bind((2, metrics.rtt.map(|v| v as i64)))?;
+        stmt.bind((3, metrics.throughput.map(|v| v as i64)))?;
+        stmt.bind((4, metrics.fetches as i64))?;
+        stmt.bind((5, metrics.failures as i64))?;
+        stmt.next()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_record_and_get() {
+        let node = arbitrary::gen::<NodeId>(1);
+        let mut table = Table::memory().unwrap();
+
+        assert_eq!(table.get(&node).unwrap(), Metrics::default());
+
+        table
+            .record_rtt(&node, LocalDuration::from_millis(80))
+            .unwrap();
+        table
+            .record_rtt(&node, LocalDuration::from_millis(160))
+            .unwrap();
+        table
+            .record_fetch(&node, 1024 * 1024, LocalDuration::from_secs(2))
+            .unwrap();
+        table.record_failure(&node).unwrap();
+
+        assert_eq!(
+            table.get(&node).unwrap(),
+            Metrics {
+                rtt: Some(90),
+                throughput: Some(512 * 1024),
+                fetches: 1,
+                failures: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let fast = arbitrary::gen::<NodeId>(1);
+        let slow = arbitrary::gen::<NodeId>(2);
+        let unknown = arbitrary::gen::<NodeId>(3);
+        let mut table = Table::memory().unwrap();
+
+        table
+            .record_rtt(&fast, LocalDuration::from_millis(20))
+            .unwrap();
+        table
+            .record_fetch(&fast, 1024 * 1024, LocalDuration::from_secs(1))
+            .unwrap();
+        table
+            .record_rtt(&slow, LocalDuration::from_millis(900))
+            .unwrap();
+        table
+            .record_fetch(&slow, 1024, LocalDuration::from_secs(1))
+            .unwrap();
+
+        let ranked = table
+            .rank([&slow, &unknown, &fast])
+            .unwrap()
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranked, vec![fast, unknown, slow]);
+    }
+}
@@ -0,0 +1,15 @@
+--
+-- Peer metrics SQL schema.
+--
+create table if not exists "metrics" (
+  -- Node ID.
+  "node"         text      primary key not null,
+  -- Smoothed round-trip time, in milliseconds.
+  "rtt"          integer   default null,
+  -- Smoothed fetch throughput, in bytes per second.
+  "throughput"   integer   default null,
+  -- Number of successful fetches from this node.
+  "fetches"      integer   not null default 0,
+  -- Number of failed fetches from this node.
+  "failures"     integer   not null default 0
+);
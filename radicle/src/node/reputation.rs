@@ -0,0 +1,458 @@
+//! Peer reputation, used to avoid misbehaving peers.
+//!
+//! Failed connection attempts, protocol violations, and stale and invalid announcements are
+//! recorded per peer, and add up to a penalty. Peers with a higher penalty are dialed last.
+//! Peers whose penalty, including the [`fetch_penalty`] derived from their
+//! [`Metrics`], reaches [`DEPRIORITIZE_THRESHOLD`] are fetched from and relayed to last.
+//! Peers whose penalty from misbehavior reaches [`BAN_THRESHOLD`] are banned for
+//! [`BAN_DURATION`]: we neither connect to them, nor accept their connections. Connection
+//! failures are usually not the peer's fault, so they never get a peer banned. Peers can also
+//! be banned by hand, until they are unbanned.
+//!
+//! Penalties don't last forever: peers that didn't offend for [`PENALTY_EXPIRY`] are forgiven.
+//!
+//! Reputation is stored alongside the address book, in the same database.
+use std::path::Path;
+
+use localtime::LocalDuration;
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::node::metrics::Metrics;
+use crate::node::Timestamp;
+use crate::prelude::NodeId;
+use crate::sql::Database;
+
+/// Penalty at which a peer is banned.
+pub const BAN_THRESHOLD: u64 = 100;
+/// Penalty at which a peer is preferred less than others, when fetching and relaying.
+pub const DEPRIORITIZE_THRESHOLD: u64 = 25;
+/// Penalty incurred when all fetches from a peer fail. Failed fetches never get a peer banned.
+pub const MAX_FETCH_PENALTY: u64 = 50;
+/// Number of fetches from a peer before failed ones incur a penalty.
+pub const MIN_FETCH_ATTEMPTS: u64 = 4;
+/// How long a peer that reached the [`BAN_THRESHOLD`] stays banned.
+pub const BAN_DURATION: LocalDuration = LocalDuration::from_mins(7 * 24 * 60);
+/// How long after its last offense a peer is forgiven.
+pub const PENALTY_EXPIRY: LocalDuration = LocalDuration::from_mins(24 * 60);
+
+/// An error occuring while accessing peer reputation.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+}
+
+/// Something a peer did that hurts its reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offense {
+    /// We failed to connect to the peer.
+    ConnectionFailure,
+    /// The peer violated the protocol, eg. by sending an invalid message.
+    Violation,
+    /// The peer sent us an announcement of its own that is older than one it sent before.
+    StaleAnnouncement,
+    /// The peer sent us an announcement with an invalid signature.
+    InvalidAnnouncement,
+}
+
+impl Offense {
+    /// Penalty incurred by the offense.
+    pub fn penalty(&self) -> u64 {
+        match self {
+            Self::ConnectionFailure => 2,
+            Self::Violation => 25,
+            Self::StaleAnnouncement => 5,
+            // Invalid announcements are counted separately, but are protocol violations.
+            Self::InvalidAnnouncement => 25,
+        }
+    }
+}
+
+/// Reputation of a single peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reputation {
+    /// Number of failed connection attempts since the last successful one.
+    pub failures: u64,
+    /// Number of protocol violations.
+    pub violations: u64,
+    /// Number of stale announcements.
+    pub stale: u64,
+    /// Number of invalid announcements.
+    pub invalid: u64,
+    /// Local time of the last offense.
+    pub updated: Timestamp,
+    /// Local time at which the peer was banned, if it is.
+    pub banned: Option<Timestamp>,
+    /// Local time at which the ban is lifted. Bans by hand are never lifted.
+    pub expires: Option<Timestamp>,
+}
+
+impl Reputation {
+    /// Total penalty of the peer. Lower is better.
+    pub fn penalty(&self) -> u64 {
+        self.failures * Offense::ConnectionFailure.penalty() + self.misbehavior()
+    }
+
+    /// Penalty of the peer from misbehavior, ie. excluding connection failures. This is what
+    /// gets a peer banned.
+    pub fn misbehavior(&self) -> u64 {
+        self.violations * Offense::Violation.penalty()
+            + self.stale * Offense::StaleAnnouncement.penalty()
+            + self.invalid * Offense::InvalidAnnouncement.penalty()
+    }
+
+    /// Check whether the peer should be preferred less than others, given its metrics.
+    pub fn is_deprioritized(&self, metrics: &Metrics) -> bool {
+        self.penalty() + fetch_penalty(metrics) >= DEPRIORITIZE_THRESHOLD
+    }
+
+    /// Check whether the peer is banned.
+    pub fn is_banned(&self) -> bool {
+        self.banned.is_some()
+    }
+
+    /// Update the reputation with an offense. Bans the peer if its penalty reaches the
+    /// [`BAN_THRESHOLD`].
+    pub fn with_offense(mut self, offense: Offense, now: Timestamp) -> Self {
+        match offense {
+            Offense::ConnectionFailure => self.failures += 1,
+            Offense::Violation => self.violations += 1,
+            Offense::StaleAnnouncement => self.stale += 1,
+            Offense::InvalidAnnouncement => self.invalid += 1,
+        }
+        self.updated = now;
+        self.check_ban(now);
+        self
+    }
+
+    fn check_ban(&mut self, now: Timestamp) {
+        if self.banned.is_none() && self.misbehavior() >= BAN_THRESHOLD {
+            self.banned = Some(now);
+            self.expires = Some(now + BAN_DURATION.as_millis() as Timestamp);
+        }
+    }
+}
+
+/// Penalty incurred by failed fetches from a peer, proportional to the share of its fetches
+/// that failed.
+pub fn fetch_penalty(metrics: &Metrics) -> u64 {
+    let attempts = metrics.fetches + metrics.failures;
+    if attempts < MIN_FETCH_ATTEMPTS {
+        return 0;
+    }
+    metrics.failures * MAX_FETCH_PENALTY / attempts
+}
+
+/// Persistent file storage for peer reputation.
+#[derive(Debug)]
+pub struct Table {
+    db: Database,
+}
+
+impl Table {
+    const SCHEMA: &str = include_str!("reputation/schema.sql");
+
+    /// Open a reputation table at the given path. Creates a new empty table
+    /// if an existing one isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = Database::open(path, Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Same as [`Self::open`], but in read-only mode.
+    pub fn reader<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = Database::reader(path, Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory reputation table.
+    pub fn memory() -> Result<Self, Error> {
+        let db = Database::memory(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Get the reputation of a peer. Returns a clean reputation for unknown peers.
+    pub fn get(&self, node: &NodeId) -> Result<Reputation, Error> {
+        let mut stmt = self.db.prepare(
+            "SELECT failures, violations, stale, invalid, updated, banned, expires
+             FROM reputation WHERE node = ?",
+        )?;
+        stmt.bind((1, node))?;
+
+        if let Some(row) = stmt.into_iter().next() {
+            return Ok(Self::read(&row?));
+        }
+        Ok(Reputation::default())
+    }
+
+    /// Check whether a peer is banned.
+    pub fn is_banned(&self, node: &NodeId) -> Result<bool, Error> {
+        self.get(node).map(|r| r.is_banned())
+    }
+
+    /// Get the reputation of all peers we recorded something for, worst first.
+    pub fn all(&self) -> Result<Vec<(NodeId, Reputation)>, Error> {
+        let mut entries = Vec::new();
+
+        for row in self
+            .db
+            .prepare(
+                "SELECT node, failures, violations, stale, invalid, updated, banned, expires
+                 FROM reputation",
+            )?
+            .into_iter()
+        {
+            let row = row?;
+            entries.push((row.read::<NodeId, _>("node"), Self::read(&row)));
+        }
+        entries.sort_by(|(_, a), (_, b)| {
+            b.is_banned()
+                .cmp(&a.is_banned())
+                .then(b.penalty().cmp(&a.penalty()))
+        });
+
+        Ok(entries)
+    }
+
+    /// Record an offense of a peer. The peer is banned if its penalty reaches the
+    /// [`BAN_THRESHOLD`].
+    pub fn record(
+        &mut self,
+        node: &NodeId,
+        offense: Offense,
+        now: Timestamp,
+    ) -> Result<Reputation, Error> {
+        let reputation = self.get(node)?.with_offense(offense, now);
+        self.put(node, &reputation)?;
+
+        Ok(reputation)
+    }
+
+    /// Record a successful connection to a peer, which clears its connection failures.
+    pub fn record_success(&mut self, node: &NodeId) -> Result<(), Error> {
+        let mut stmt = self
+            .db
+            .prepare("UPDATE reputation SET failures = 0 WHERE node = ?")?;
+        stmt.bind((1, node))?;
+        stmt.next()?;
+
+        Ok(())
+    }
+
+    /// Ban a peer until it is unbanned. Returns `false` if it was already banned.
+    pub fn ban(&mut self, node: &NodeId, now: Timestamp) -> Result<bool, Error> {
+        let mut reputation = self.get(node)?;
+        if reputation.is_banned() {
+            return Ok(false);
+        }
+        reputation.banned = Some(now);
+        reputation.expires = None;
+        self.put(node, &reputation)?;
+
+        Ok(true)
+    }
+
+    /// Unban a peer, and give it a clean reputation. Returns `false` if it wasn't banned.
+    pub fn unban(&mut self, node: &NodeId) -> Result<bool, Error> {
+        if !self.is_banned(node)? {
+            return Ok(false);
+        }
+        let mut stmt = self.db.prepare("DELETE FROM reputation WHERE node = ?")?;
+        stmt.bind((1, node))?;
+        stmt.next()?;
+
+        Ok(true)
+    }
+
+    /// Lift the bans that are over, and forgive the peers that didn't offend for
+    /// [`PENALTY_EXPIRY`], giving them a clean reputation. Returns the number of peers
+    /// affected.
+    pub fn expire(&mut self, now: Timestamp) -> Result<usize, Error> {
+        let forgiven = now.saturating_sub(PENALTY_EXPIRY.as_millis() as Timestamp);
+        let mut stmt = self.db.prepare(
+            "DELETE FROM reputation
+             WHERE (banned IS NOT NULL AND expires <= ?1)
+                OR (banned IS NULL AND updated <= ?2)",
+        )?;
+        stmt.bind((1, now as i64))?;
+        stmt.bind((2, forgiven as i64))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count())
+    }
+
+    fn read(row: &sql::Row) -> Reputation {
+        Reputation {
+            failures: row.read::<i64, _>("failures") as u64,
+            violations: row.read::<i64, _>("violations") as u64,
+            stale: row.read::<i64, _>("stale") as u64,
+            invalid: row.read::<i64, _>("invalid") as u64,
+            updated: row.read::<i64, _>("updated") as Timestamp,
+            banned: row.read::<Option<i64>, _>("banned").map(|t| t as Timestamp),
+            expires: row
+                .read::<Option<i64>, _>("expires")
+                .map(|t| t as Timestamp),
+        }
+    }
+
+    fn put(&mut self, node: &NodeId, reputation: &Reputation) -> Result<(), Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO reputation
+             (node, failures, violations, stale, invalid, updated, banned, expires)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT DO UPDATE
+             SET failures = ?2, violations = ?3, stale = ?4, invalid = ?5, updated = ?6,
+                 banned = ?7, expires = ?8",
+        )?;
+
+        stmt.bind((1, node))?;
+        stmt.bind((2, reputation.failures as i64))?;
+        stmt.bind((3, reputation.violations as i64))?;
+        stmt.bind((4, reputation.stale as i64))?;
+        stmt.bind((5, reputation.invalid as i64))?;
+        stmt.bind((6, reputation.updated as i64))?;
+        stmt.bind((7, reputation.banned.map(|t| t as i64)))?;
+        stmt.bind((8, reputation.expires.map(|t| t as i64)))?;
+        stmt.next()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_record_and_ban() {
+        let node = arbitrary::gen::<NodeId>(1);
+        let mut table = Table::memory().unwrap();
+
+        assert_eq!(table.get(&node).unwrap(), Reputation::default());
+
+        table.record(&node, Offense::ConnectionFailure, 1).unwrap();
+        table.record(&node, Offense::StaleAnnouncement, 2).unwrap();
+        table.record_success(&node).unwrap();
+
+        let reputation = table.get(&node).unwrap();
+        assert_eq!(reputation.failures, 0);
+        assert_eq!(reputation.stale, 1);
+        assert!(!reputation.is_banned());
+
+        for t in 3..7 {
+            table.record(&node, Offense::Violation, t).unwrap();
+        }
+        let reputation = table.get(&node).unwrap();
+        assert_eq!(reputation.penalty(), 105);
+        assert_eq!(reputation.banned, Some(6));
+        assert!(table.is_banned(&node).unwrap());
+
+        assert!(table.unban(&node).unwrap());
+        assert!(!table.unban(&node).unwrap());
+        assert_eq!(table.get(&node).unwrap(), Reputation::default());
+    }
+
+    #[test]
+    fn test_connection_failures_not_banned() {
+        let node = arbitrary::gen::<NodeId>(1);
+        let mut table = Table::memory().unwrap();
+
+        for t in 0..BAN_THRESHOLD {
+            table.record(&node, Offense::ConnectionFailure, t).unwrap();
+        }
+        let reputation = table.get(&node).unwrap();
+        assert!(reputation.penalty() >= BAN_THRESHOLD);
+        assert_eq!(reputation.misbehavior(), 0);
+        assert!(!reputation.is_banned());
+    }
+
+    #[test]
+    fn test_expire() {
+        let forgiven = arbitrary::gen::<NodeId>(1);
+        let offender = arbitrary::gen::<NodeId>(2);
+        let banned = arbitrary::gen::<NodeId>(3);
+        let manual = arbitrary::gen::<NodeId>(4);
+        let mut table = Table::memory().unwrap();
+        let expiry = PENALTY_EXPIRY.as_millis() as Timestamp;
+        let duration = BAN_DURATION.as_millis() as Timestamp;
+
+        table.record(&forgiven, Offense::Violation, 1).unwrap();
+        table.record(&offender, Offense::Violation, 1).unwrap();
+        for _ in 0..BAN_THRESHOLD / Offense::Violation.penalty() {
+            table.record(&banned, Offense::Violation, 1).unwrap();
+        }
+        table.ban(&manual, 1).unwrap();
+        assert_eq!(table.get(&banned).unwrap().expires, Some(1 + duration));
+
+        // Peers that keep offending aren't forgiven.
+        table.record(&offender, Offense::Violation, expiry).unwrap();
+        assert_eq!(table.expire(1 + expiry).unwrap(), 1);
+        assert_eq!(table.get(&forgiven).unwrap(), Reputation::default());
+        assert_eq!(table.get(&offender).unwrap().violations, 2);
+        assert!(table.is_banned(&banned).unwrap());
+
+        // Bans are lifted once they're over, unless they were made by hand.
+        assert_eq!(table.expire(1 + duration).unwrap(), 2);
+        assert_eq!(table.get(&banned).unwrap(), Reputation::default());
+        assert!(table.is_banned(&manual).unwrap());
+    }
+
+    #[test]
+    fn test_fetch_penalty() {
+        let node = arbitrary::gen::<NodeId>(1);
+        let mut table = Table::memory().unwrap();
+        let reputation = Reputation::default();
+
+        // Too few fetches to tell.
+        let metrics = Metrics {
+            failures: 2,
+            ..Metrics::default()
+        };
+        assert_eq!(fetch_penalty(&metrics), 0);
+        assert!(!reputation.is_deprioritized(&metrics));
+
+        let metrics = Metrics {
+            fetches: 2,
+            failures: 2,
+            ..Metrics::default()
+        };
+        assert_eq!(fetch_penalty(&metrics), MAX_FETCH_PENALTY / 2);
+        assert!(reputation.is_deprioritized(&metrics));
+
+        // Invalid announcements count as much as other violations.
+        let reputation = table
+            .record(&node, Offense::InvalidAnnouncement, 1)
+            .unwrap();
+        assert_eq!(reputation.invalid, 1);
+        assert_eq!(reputation.penalty(), Offense::Violation.penalty());
+        assert!(reputation.is_deprioritized(&Metrics::default()));
+    }
+
+    #[test]
+    fn test_ban_and_all() {
+        let good = arbitrary::gen::<NodeId>(1);
+        let bad = arbitrary::gen::<NodeId>(2);
+        let banned = arbitrary::gen::<NodeId>(3);
+        let mut table = Table::memory().unwrap();
+
+        table.record(&good, Offense::ConnectionFailure, 1).unwrap();
+        table.record(&bad, Offense::Violation, 1).unwrap();
+        assert!(table.ban(&banned, 1).unwrap());
+        assert!(!table.ban(&banned, 2).unwrap());
+
+        let all = table
+            .all()
+            .unwrap()
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect::<Vec<_>>();
+
+        assert_eq!(all, vec![banned, bad, good]);
+    }
+}
@@ -0,0 +1,21 @@
+--
+-- Peer reputation SQL schema.
+--
+create table if not exists "reputation" (
+  -- Node ID.
+  "node"         text      primary key not null,
+  -- Number of failed connection attempts since the last successful one.
+  "failures"     integer   not null default 0,
+  -- Number of protocol violations.
+  "violations"   integer   not null default 0,
+  -- Number of stale announcements.
+  "stale"        integer   not null default 0,
+  -- Number of invalid announcements.
+  "invalid"      integer   not null default 0,
+  -- Local time of the last offense.
+  "updated"      integer   not null default 0,
+  -- Local time at which this node was banned, if it is.
+  "banned"       integer   default null,
+  -- Local time at which the ban is lifted, or null if it is never lifted.
+  "expires"      integer   default null
+);
@@ -0,0 +1,92 @@
+//! Signals: direct messages between connected nodes.
+//!
+//! Unlike announcements, signals are sent to a single connected peer, and are never relayed.
+//! They travel over the peer's encrypted session, which authenticates the sender, so a signal
+//! always comes from the node it appears to come from.
+//!
+//! Signals are sent as an envelope made of a kind and an opaque payload, so that nodes can
+//! pass on, or ignore, signals of kinds they don't know about.
+use serde::{Deserialize, Serialize};
+
+use crate::git::Oid;
+use crate::identity::Id;
+
+/// A signal sent to, or received from, a connected peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Signal {
+    /// The sender invited us to a private repository.
+    Invite { rid: Id },
+    /// The sender has new refs for a repository, which we may want to fetch.
+    FetchHint { rid: Id },
+    /// The sender asks us to fetch a repository from it now, eg. because it has new refs.
+    /// We reply with [`Signal::Synced`] once we have fetched it, or [`Signal::SyncDeclined`].
+    SyncRequest { rid: Id },
+    /// Reply to a sync request: we fetched the repository from the sender, and now have
+    /// its signed refs at the given commit.
+    Synced { rid: Id, sigrefs: Oid },
+    /// Reply to a sync request: we didn't fetch the repository from the sender, eg. because
+    /// our tracking policy doesn't allow it, or the fetch failed.
+    SyncDeclined { rid: Id },
+    /// The sender issued us a ticket, which we can present with [`Signal::Resume`] when we
+    /// reconnect, to resume the session instead of starting a new one.
+    Ticket { ticket: u64 },
+    /// The sender presents the ticket we issued it in its previous session, and asks us to
+    /// resume that session: to restore its subscription, and only send it the gossip it
+    /// missed while it was disconnected.
+    Resume { ticket: u64 },
+    /// Reply to a resume request: we can't resume the session, eg. because the ticket
+    /// expired, so the sender should start a new one.
+    ResumeDeclined,
+    /// A signal of a kind we don't know about, eg. sent by a newer node.
+    Unknown { kind: u16, payload: Vec<u8> },
+}
+
+impl Signal {
+    /// Kind of an [`Signal::Invite`] signal.
+    pub const INVITE: u16 = 1;
+    /// Kind of a [`Signal::FetchHint`] signal.
+    pub const FETCH_HINT: u16 = 2;
+    /// Kind of a [`Signal::SyncRequest`] signal.
+    pub const SYNC_REQUEST: u16 = 3;
+    /// Kind of a [`Signal::Synced`] signal.
+    pub const SYNCED: u16 = 4;
+    /// Kind of a [`Signal::SyncDeclined`] signal.
+    pub const SYNC_DECLINED: u16 = 5;
+    /// Kind of a [`Signal::Ticket`] signal.
+    pub const TICKET: u16 = 6;
+    /// Kind of a [`Signal::Resume`] signal.
+    pub const RESUME: u16 = 7;
+    /// Kind of a [`Signal::ResumeDeclined`] signal.
+    pub const RESUME_DECLINED: u16 = 8;
+
+    /// The kind of signal, as sent in the envelope.
+    pub fn kind(&self) -> u16 {
+        match self {
+            Self::Invite { .. } => Self::INVITE,
+            Self::FetchHint { .. } => Self::FETCH_HINT,
+            Self::SyncRequest { .. } => Self::SYNC_REQUEST,
+            Self::Synced { .. } => Self::SYNCED,
+            Self::SyncDeclined { .. } => Self::SYNC_DECLINED,
+            Self::Ticket { .. } => Self::TICKET,
+            Self::Resume { .. } => Self::RESUME,
+            Self::ResumeDeclined => Self::RESUME_DECLINED,
+            Self::Unknown { kind, .. } => *kind,
+        }
+    }
+
+    /// The repository the signal is about, if any.
+    pub fn rid(&self) -> Option<Id> {
+        match self {
+            Self::Invite { rid }
+            | Self::FetchHint { rid }
+            | Self::SyncRequest { rid }
+            | Self::Synced { rid, .. }
+            | Self::SyncDeclined { rid } => Some(*rid),
+            Self::Ticket { .. }
+            | Self::Resume { .. }
+            | Self::ResumeDeclined
+            | Self::Unknown { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Gossip tracing, for debugging announcement propagation.
+//!
+//! When tracing is enabled on a node, it keeps a record of the last [`MAX_TRACES`]
+//! announcements it received: who signed them, which peer relayed them to us, and what the
+//! node did with them, ie. which peers it relayed them to, or why it didn't.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::identity::Id;
+use crate::node::{NodeId, Timestamp};
+
+/// Maximum number of traces kept by the node. Older traces are dropped first.
+pub const MAX_TRACES: usize = 256;
+
+/// Type of traced announcement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    /// Node announcement.
+    Node,
+    /// Inventory announcement.
+    Inventory,
+    /// Refs announcement.
+    Refs,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node => f.write_str("node"),
+            Self::Inventory => f.write_str("inventory"),
+            Self::Refs => f.write_str("refs"),
+        }
+    }
+}
+
+/// What the node did with an announcement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Verdict {
+    /// The announcement was accepted, and relayed to the given peers.
+    Relayed { to: Vec<NodeId> },
+    /// The announcement was not relayed, for the given reason.
+    Dropped { reason: String },
+    /// The announcement was rejected, and the relaying peer disconnected.
+    Rejected { reason: String },
+}
+
+/// A received announcement, as traced by the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    /// When the announcement was received, in milliseconds since the epoch.
+    pub time: Timestamp,
+    /// Type of announcement.
+    pub kind: Kind,
+    /// Node that signed the announcement.
+    pub origin: NodeId,
+    /// Peer that relayed the announcement to us. Same as the origin if it was sent to us
+    /// directly.
+    pub relayer: NodeId,
+    /// Repository the announcement is about, for refs announcements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rid: Option<Id>,
+    /// Timestamp of the announcement, as set by its origin.
+    pub timestamp: Timestamp,
+    /// What the node did with the announcement.
+    pub verdict: Verdict,
+}
@@ -1,20 +1,42 @@
 pub mod store;
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::prelude::Id;
+use crate::prelude::{Did, Id};
 
-use super::NodeId;
+use super::{NodeId, Timestamp};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Repo {
     pub id: Id,
     pub scope: Scope,
     pub policy: Policy,
+    /// Limits on the data accepted when fetching the repository.
+    #[serde(default)]
+    pub limits: FetchLimits,
+}
+
+/// Limits on the data accepted when fetching a repository. Fetches exceeding them are
+/// refused, to protect nodes from very large pushes.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchLimits {
+    /// Maximum number of bytes received in a single fetch.
+    pub max_pack_size: Option<u64>,
+    /// Maximum size of a single git object, in bytes.
+    pub max_object_size: Option<u64>,
+}
+
+impl FetchLimits {
+    /// Check whether there are no limits.
+    pub fn is_empty(&self) -> bool {
+        self.max_pack_size.is_none() && self.max_object_size.is_none()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -22,6 +44,8 @@ pub struct Node {
     pub id: NodeId,
     pub alias: Option<Alias>,
     pub policy: Policy,
+    /// Time after which the policy is dropped, if any.
+    pub expiry: Option<Timestamp>,
 }
 
 /// Node alias.
@@ -90,13 +114,15 @@ impl TryFrom<&sqlite::Value> for Policy {
 }
 
 /// Tracking scope of a repository tracking policy.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Scope {
     /// Track remotes of nodes that are already tracked.
     #[default]
     Trusted,
     /// Track all remotes.
     All,
+    /// Track the repository delegates, and the given remotes only.
+    Explicit(BTreeSet<Did>),
 }
 
 impl fmt::Display for Scope {
@@ -104,6 +130,15 @@ impl fmt::Display for Scope {
         match self {
             Scope::Trusted => f.write_str("trusted"),
             Scope::All => f.write_str("all"),
+            Scope::Explicit(remotes) => {
+                f.write_str("explicit")?;
+
+                for (i, did) in remotes.iter().enumerate() {
+                    f.write_str(if i == 0 { ":" } else { "," })?;
+                    f.write_str(&did.encode())?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -116,9 +151,16 @@ impl FromStr for Scope {
     type Err = ParseScopeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "trusted" => Ok(Self::Trusted),
-            "all" => Ok(Self::All),
+        match s.split_once(':') {
+            None if s == "trusted" => Ok(Self::Trusted),
+            None if s == "all" => Ok(Self::All),
+            None if s == "explicit" => Ok(Self::Explicit(BTreeSet::new())),
+            Some(("explicit", remotes)) => remotes
+                .split(',')
+                .map(Did::decode)
+                .collect::<Result<_, _>>()
+                .map(Self::Explicit)
+                .map_err(|_| ParseScopeError(s.to_string())),
             _ => Err(ParseScopeError(s.to_string())),
         }
     }
@@ -130,11 +172,7 @@ impl sqlite::BindableWithIndex for Scope {
         stmt: &mut sqlite::Statement<'_>,
         i: I,
     ) -> sqlite::Result<()> {
-        let s = match self {
-            Self::Trusted => "trusted",
-            Self::All => "all",
-        };
-        s.bind(stmt, i)
+        self.to_string().as_str().bind(stmt, i)
     }
 }
 
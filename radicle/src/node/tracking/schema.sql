@@ -9,7 +9,10 @@ create table if not exists "node-policies" (
   -- Node alias. May override the alias announced by the node.
   "alias"              text      default '',
   -- Tracking policy for this node.
-  "policy"             text      default 'track'
+  "policy"             text      default 'track',
+  -- Time after which this policy is dropped, in milliseconds since epoch.
+  -- If null, the policy never expires.
+  "expiry"             integer   default null
   --
 ) strict;
 
@@ -22,11 +25,15 @@ create table if not exists "repo-policies" (
   -- Valid values are:
   --
   -- "trusted"         track repository delegates and remotes in the `node-policies` table.
-  -- "delegates-only"  only track repository delegates.
+  -- "explicit:<dids>" only track repository delegates and the given comma-separated DIDs.
   -- "all"             track all remotes.
   --
   "scope"              text      default 'trusted',
   -- Tracking policy for this repository.
-  "policy"             text      default 'track'
+  "policy"             text      default 'track',
+  -- Maximum number of bytes received in a single fetch. If null, there is no limit.
+  "max_pack_size"      integer   default null,
+  -- Maximum size of a single fetched git object, in bytes. If null, there is no limit.
+  "max_object_size"    integer   default null
   --
 ) strict;
@@ -5,9 +5,10 @@ use std::{fmt, io, ops::Not as _, time};
 use sqlite as sql;
 use thiserror::Error;
 
+use crate::node::Timestamp;
 use crate::prelude::{Id, NodeId};
 
-use super::{Node, Policy, Repo, Scope};
+use super::{FetchLimits, Node, Policy, Repo, Scope};
 
 /// How long to wait for the database lock to be released before failing a read.
 const DB_READ_TIMEOUT: time::Duration = time::Duration::from_secs(3);
@@ -44,6 +45,7 @@ impl Config {
         let mut db = sql::Connection::open(path)?;
         db.set_busy_timeout(DB_WRITE_TIMEOUT.as_millis() as usize)?;
         db.execute(Self::SCHEMA)?;
+        Self::migrate(&db)?;
 
         Ok(Self { db })
     }
@@ -67,6 +69,35 @@ impl Config {
         Ok(Self { db })
     }
 
+    /// Bring stores created by older versions up to date with the schema.
+    fn migrate(db: &sql::Connection) -> Result<(), Error> {
+        let mut stmt = db.prepare(
+            "SELECT COUNT(*) FROM pragma_table_info('node-policies') WHERE name = 'expiry'",
+        )?;
+        let has_expiry = match stmt.into_iter().next() {
+            Some(row) => row?.read::<i64, _>(0) > 0,
+            None => false,
+        };
+        if !has_expiry {
+            db.execute("ALTER TABLE `node-policies` ADD COLUMN expiry integer DEFAULT NULL")?;
+        }
+
+        let mut stmt = db.prepare(
+            "SELECT COUNT(*) FROM pragma_table_info('repo-policies') WHERE name = 'max_pack_size'",
+        )?;
+        let has_limits = match stmt.into_iter().next() {
+            Some(row) => row?.read::<i64, _>(0) > 0,
+            None => false,
+        };
+        if !has_limits {
+            db.execute(
+                "ALTER TABLE `repo-policies` ADD COLUMN max_pack_size integer DEFAULT NULL;
+                 ALTER TABLE `repo-policies` ADD COLUMN max_object_size integer DEFAULT NULL;",
+            )?;
+        }
+        Ok(())
+    }
+
     /// Track a node.
     pub fn track_node(&mut self, id: &NodeId, alias: Option<&str>) -> Result<bool, Error> {
         let mut stmt = self.db.prepare(
@@ -115,6 +146,26 @@ impl Config {
         Ok(self.db.change_count() > 0)
     }
 
+    /// Set the time after which a node's policy expires, or `None` for it to never expire.
+    /// Does nothing if the node has no policy.
+    pub fn set_node_expiry(
+        &mut self,
+        id: &NodeId,
+        expiry: Option<Timestamp>,
+    ) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "UPDATE `node-policies`
+             SET expiry = ?2
+             WHERE id = ?1 AND expiry IS NOT ?2",
+        )?;
+
+        stmt.bind((1, id))?;
+        stmt.bind((2, expiry.map(|t| t as i64)))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
     /// Set a repository's tracking policy.
     pub fn set_repo_policy(&mut self, id: &Id, policy: Policy) -> Result<bool, Error> {
         let mut stmt = self.db.prepare(
@@ -131,6 +182,24 @@ impl Config {
         Ok(self.db.change_count() > 0)
     }
 
+    /// Set the limits on the data accepted when fetching a repository.
+    pub fn set_repo_limits(&mut self, id: &Id, limits: FetchLimits) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO `repo-policies` (id, max_pack_size, max_object_size)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT DO UPDATE
+             SET max_pack_size = ?2, max_object_size = ?3
+             WHERE max_pack_size IS NOT ?2 OR max_object_size IS NOT ?3",
+        )?;
+
+        stmt.bind((1, id))?;
+        stmt.bind((2, limits.max_pack_size.map(|n| n as i64)))?;
+        stmt.bind((3, limits.max_object_size.map(|n| n as i64)))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
     /// Untrack a node.
     pub fn untrack_node(&mut self, id: &NodeId) -> Result<bool, Error> {
         let mut stmt = self
@@ -181,7 +250,7 @@ impl Config {
     pub fn node_policy(&self, id: &NodeId) -> Result<Option<Node>, Error> {
         let mut stmt = self
             .db
-            .prepare("SELECT alias, policy FROM `node-policies` WHERE id = ?")?;
+            .prepare("SELECT alias, policy, expiry FROM `node-policies` WHERE id = ?")?;
 
         stmt.bind((1, id))?;
 
@@ -189,11 +258,13 @@ impl Config {
             let alias = row.read::<&str, _>("alias");
             let alias = alias.is_empty().not().then_some(alias.to_owned());
             let policy = row.read::<Policy, _>("policy");
+            let expiry = row.read::<Option<i64>, _>("expiry").map(|t| t as Timestamp);
 
             return Ok(Some(Node {
                 id: *id,
                 alias,
                 policy,
+                expiry,
             }));
         }
         Ok(None)
@@ -201,9 +272,10 @@ impl Config {
 
     /// Get a repository's tracking policy.
     pub fn repo_policy(&self, id: &Id) -> Result<Option<Repo>, Error> {
-        let mut stmt = self
-            .db
-            .prepare("SELECT scope, policy FROM `repo-policies` WHERE id = ?")?;
+        let mut stmt = self.db.prepare(
+            "SELECT scope, policy, max_pack_size, max_object_size
+             FROM `repo-policies` WHERE id = ?",
+        )?;
 
         stmt.bind((1, id))?;
 
@@ -212,6 +284,7 @@ impl Config {
                 id: *id,
                 scope: row.read::<Scope, _>("scope"),
                 policy: row.read::<Policy, _>("policy"),
+                limits: Self::read_limits(&row),
             }));
         }
         Ok(None)
@@ -221,7 +294,7 @@ impl Config {
     pub fn node_policies(&self) -> Result<Box<dyn Iterator<Item = Node>>, Error> {
         let mut stmt = self
             .db
-            .prepare("SELECT id, alias, policy FROM `node-policies`")?
+            .prepare("SELECT id, alias, policy, expiry FROM `node-policies`")?
             .into_iter();
         let mut entries = Vec::new();
 
@@ -230,18 +303,41 @@ impl Config {
             let alias = row.read::<&str, _>("alias").to_owned();
             let alias = alias.is_empty().not().then_some(alias.to_owned());
             let policy = row.read::<Policy, _>("policy");
+            let expiry = row.read::<Option<i64>, _>("expiry").map(|t| t as Timestamp);
 
-            entries.push(Node { id, alias, policy });
+            entries.push(Node {
+                id,
+                alias,
+                policy,
+                expiry,
+            });
         }
         Ok(Box::new(entries.into_iter()))
     }
 
+    /// Get the nodes whose policy expired at the given time.
+    pub fn expired_nodes(&self, now: Timestamp) -> Result<Vec<NodeId>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id FROM `node-policies` WHERE expiry <= ?")?;
+        let mut nodes = Vec::new();
+
+        stmt.bind((1, now as i64))?;
+
+        for row in stmt.into_iter() {
+            nodes.push(row?.read::<NodeId, _>("id"));
+        }
+        Ok(nodes)
+    }
+
     // TODO: see if sql can return iterator directly
     /// Get repository tracking policies.
     pub fn repo_policies(&self) -> Result<Box<dyn Iterator<Item = Repo>>, Error> {
         let mut stmt = self
             .db
-            .prepare("SELECT id, scope, policy FROM `repo-policies`")?
+            .prepare(
+                "SELECT id, scope, policy, max_pack_size, max_object_size FROM `repo-policies`",
+            )?
             .into_iter();
         let mut entries = Vec::new();
 
@@ -249,16 +345,36 @@ impl Config {
             let id = row.read("id");
             let scope = row.read("scope");
             let policy = row.read::<Policy, _>("policy");
+            let limits = Self::read_limits(&row);
 
-            entries.push(Repo { id, scope, policy });
+            entries.push(Repo {
+                id,
+                scope,
+                policy,
+                limits,
+            });
         }
         Ok(Box::new(entries.into_iter()))
     }
+
+    fn read_limits(row: &sql::Row) -> FetchLimits {
+        FetchLimits {
+            max_pack_size: row
+                .read::<Option<i64>, _>("max_pack_size")
+                .map(|n| n as u64),
+            max_object_size: row
+                .read::<Option<i64>, _>("max_object_size")
+                .map(|n| n as u64),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeSet;
+
     use crate::assert_matches;
+    use crate::prelude::Did;
 
     use super::*;
     use crate::test::arbitrary;
@@ -335,6 +451,26 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_repo_limits() {
+        let id = arbitrary::gen::<Id>(1);
+        let mut db = Config::open(":memory:").unwrap();
+        let limits = FetchLimits {
+            max_pack_size: Some(1024),
+            max_object_size: None,
+        };
+
+        assert!(db.track_repo(&id, Scope::All).unwrap());
+        assert!(db.repo_policy(&id).unwrap().unwrap().limits.is_empty());
+        assert!(db.set_repo_limits(&id, limits).unwrap());
+        assert!(!db.set_repo_limits(&id, limits).unwrap());
+        assert_eq!(db.repo_policy(&id).unwrap().unwrap().limits, limits);
+        assert_eq!(db.repo_policies().unwrap().next().unwrap().limits, limits);
+
+        assert!(db.set_repo_limits(&id, FetchLimits::default()).unwrap());
+        assert!(db.repo_policy(&id).unwrap().unwrap().limits.is_empty());
+    }
+
     #[test]
     fn test_update_scope() {
         let id = arbitrary::gen::<Id>(1);
@@ -346,6 +482,28 @@ mod test {
         assert_eq!(db.repo_policy(&id).unwrap().unwrap().scope, Scope::Trusted);
     }
 
+    #[test]
+    fn test_explicit_scope() {
+        let id = arbitrary::gen::<Id>(1);
+        let remotes = arbitrary::vec::<NodeId>(2)
+            .into_iter()
+            .map(Did::from)
+            .collect::<BTreeSet<_>>();
+        let scope = Scope::Explicit(remotes);
+        let mut db = Config::open(":memory:").unwrap();
+
+        assert_eq!(scope.to_string().parse::<Scope>().unwrap(), scope);
+        assert_eq!(
+            "explicit".parse::<Scope>().unwrap(),
+            Scope::Explicit(BTreeSet::new())
+        );
+        assert!("explicit:did:key:z6Mk".parse::<Scope>().is_err());
+
+        assert!(db.track_repo(&id, scope.clone()).unwrap());
+        assert!(!db.track_repo(&id, scope.clone()).unwrap());
+        assert_eq!(db.repo_policy(&id).unwrap().unwrap().scope, scope);
+    }
+
     #[test]
     fn test_repo_policy() {
         let id = arbitrary::gen::<Id>(1);
@@ -357,6 +515,27 @@ mod test {
         assert_eq!(db.repo_policy(&id).unwrap().unwrap().policy, Policy::Block);
     }
 
+    #[test]
+    fn test_node_expiry() {
+        let ids = arbitrary::vec::<NodeId>(2);
+        let mut db = Config::open(":memory:").unwrap();
+
+        for id in &ids {
+            assert!(db.track_node(id, None).unwrap());
+        }
+        assert!(!db.set_node_expiry(&ids[0], None).unwrap());
+        assert!(db.set_node_expiry(&ids[0], Some(100)).unwrap());
+        assert!(!db.set_node_expiry(&ids[0], Some(100)).unwrap());
+        assert_eq!(db.node_policy(&ids[0]).unwrap().unwrap().expiry, Some(100));
+        assert_eq!(db.node_policy(&ids[1]).unwrap().unwrap().expiry, None);
+
+        assert!(db.expired_nodes(99).unwrap().is_empty());
+        assert_eq!(db.expired_nodes(100).unwrap(), vec![ids[0]]);
+
+        assert!(db.set_node_expiry(&ids[0], None).unwrap());
+        assert!(db.expired_nodes(u32::MAX as Timestamp).unwrap().is_empty());
+    }
+
     #[test]
     fn test_node_policy() {
         let id = arbitrary::gen::<NodeId>(1);
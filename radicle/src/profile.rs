@@ -9,17 +9,23 @@
 //!       radicle.pub                            # Public key (PKCS 8)
 //!     node/
 //!       control.sock                           # Node control socket
+//!     config.json                              # Profile configuration
 //!
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+use std::{fmt, fs, io};
 
+use serde::{Deserialize, Serialize};
+use serde_json as json;
 use thiserror::Error;
 
+use crate::cob::cache;
 use crate::crypto::ssh::agent::Agent;
 use crate::crypto::ssh::{keystore, Keystore, Passphrase};
 use crate::crypto::{PublicKey, Signer};
-use crate::node::{self, tracking};
+use crate::node::{self, tracking, ConnectAddress};
 use crate::prelude::Did;
+use crate::storage::checkouts;
 use crate::storage::git::transport;
 use crate::storage::git::Storage;
 
@@ -56,6 +62,229 @@ pub enum Error {
     Agent(#[from] crate::crypto::ssh::agent::Error),
     #[error("profile key `{0}` is not registered with ssh-agent")]
     KeyNotRegistered(PublicKey),
+    #[error("invalid configuration file '{0}': {1}")]
+    Config(PathBuf, json::Error),
+}
+
+/// Profile configuration, stored in `$RAD_HOME/config.json`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Seeds to announce to and sync with by default, eg. when running `rad sync`.
+    #[serde(default)]
+    pub preferred_seeds: Vec<ConnectAddress>,
+    /// How timestamps are displayed by the CLI.
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// Authors whose issues, patches and comments are hidden by the CLI.
+    #[serde(default)]
+    pub blocked: Vec<Did>,
+    /// Peers the node always maintains a connection to, eg. added with
+    /// `rad node connect --persistent`.
+    #[serde(default)]
+    pub persistent_peers: Vec<ConnectAddress>,
+    /// Saved queries for list commands, by name, eg. `"mine": "--assigned me --open"`.
+    /// A query is used by passing its name, prefixed with `@`, to a list command.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub queries: BTreeMap<String, String>,
+    /// Node settings. These take precedence over the node's defaults, but not over its
+    /// command-line options, and are applied again when a running node reloads its
+    /// configuration.
+    #[serde(default, skip_serializing_if = "NodeConfig::is_empty")]
+    pub node: NodeConfig,
+}
+
+impl Config {
+    /// Load the configuration from the given path. Returns the default configuration
+    /// if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        match fs::File::open(path) {
+            Ok(file) => json::from_reader(io::BufReader::new(file))
+                .map_err(|e| Error::Config(path.to_path_buf(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the configuration to the given path, replacing any existing file.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let json =
+            json::to_string_pretty(self).map_err(|e| Error::Config(path.to_path_buf(), e))?;
+        fs::write(path, json + "\n")?;
+
+        Ok(())
+    }
+
+    /// Check whether content from the given author should be hidden.
+    pub fn is_blocked(&self, did: &Did) -> bool {
+        self.blocked.contains(did)
+    }
+
+    /// Check whether the given node is a persistent peer.
+    pub fn is_persistent(&self, nid: &node::NodeId) -> bool {
+        self.persistent_peers.iter().any(|p| p.id == *nid)
+    }
+
+    /// Get a saved query by name.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.queries.get(name).map(String::as_str)
+    }
+}
+
+/// Node settings of the profile configuration. Unset settings are left to the node's
+/// command-line options, or defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeConfig {
+    /// Publicly accessible addresses of the node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_addresses: Vec<node::Address>,
+    /// Default tracking policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracking_policy: Option<tracking::Policy>,
+    /// Default scope for tracking policies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracking_scope: Option<tracking::Scope>,
+    /// Service limits.
+    #[serde(default, skip_serializing_if = "NodeLimits::is_empty")]
+    pub limits: NodeLimits,
+    /// URLs that events of tracked repositories are posted to, eg. to trigger CI.
+    /// Only `http://` URLs are supported: HTTPS webhooks must be reached through a proxy
+    /// that terminates TLS, eg. on the same host as the node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<String>,
+    /// Whether to map the node's listen port on the gateway with NAT-PMP or UPnP, and
+    /// advertise the external address obtained.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_mapping: Option<bool>,
+}
+
+impl NodeConfig {
+    /// Check whether no node setting is set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Service limits of the node. See the node's `--limit-*` options.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeLimits {
+    /// Number of routing table entries before the node starts pruning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_max_size: Option<usize>,
+    /// How long to keep a routing table entry before it is pruned, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_max_age: Option<u64>,
+    /// Maximum number of concurrent fetches per connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_concurrency: Option<usize>,
+    /// Maximum number of concurrent fetches, across all connections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fetches: Option<usize>,
+    /// Tolerated clock skew of announcements, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_delta: Option<u64>,
+}
+
+impl NodeLimits {
+    /// Check whether no limit is set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// How timestamps are displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeFormat {
+    /// Relative to the current time, eg. "3 days ago".
+    #[default]
+    Relative,
+    /// Absolute, in ISO-8601 format, in the given timezone.
+    Iso(Timezone),
+}
+
+/// Timezone used to display absolute timestamps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    /// The local timezone.
+    #[default]
+    Local,
+    /// Coordinated Universal Time.
+    Utc,
+    /// Fixed offset from UTC, in minutes.
+    Offset(i32),
+}
+
+/// Error parsing a [`TimeFormat`].
+#[derive(Debug, Error)]
+#[error("invalid time format '{0}', expected 'relative', 'iso' or 'iso:<timezone>'")]
+pub struct TimeFormatError(String);
+
+impl std::str::FromStr for TimeFormat {
+    type Err = TimeFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TimeFormatError(s.to_owned());
+
+        match s.split_once(':') {
+            None if s == "relative" => Ok(Self::Relative),
+            None if s == "iso" => Ok(Self::Iso(Timezone::Local)),
+            Some(("iso", "local")) => Ok(Self::Iso(Timezone::Local)),
+            Some(("iso", "utc")) => Ok(Self::Iso(Timezone::Utc)),
+            Some(("iso", offset)) => {
+                let (sign, offset) = if let Some(offset) = offset.strip_prefix('+') {
+                    (1, offset)
+                } else if let Some(offset) = offset.strip_prefix('-') {
+                    (-1, offset)
+                } else {
+                    return Err(err());
+                };
+                let (hours, minutes) = offset.split_once(':').ok_or_else(err)?;
+                let hours: u32 = hours.parse().map_err(|_| err())?;
+                let minutes: u32 = minutes.parse().map_err(|_| err())?;
+
+                if hours > 23 || minutes > 59 {
+                    return Err(err());
+                }
+                let offset = sign * (hours * 60 + minutes) as i32;
+
+                Ok(Self::Iso(Timezone::Offset(offset)))
+            }
+            _ => Err(err()),
+        }
+    }
+}
+
+impl fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Relative => write!(f, "relative"),
+            Self::Iso(Timezone::Local) => write!(f, "iso"),
+            Self::Iso(Timezone::Utc) => write!(f, "iso:utc"),
+            Self::Iso(Timezone::Offset(minutes)) => {
+                let sign = if *minutes < 0 { '-' } else { '+' };
+                let minutes = minutes.abs();
+
+                write!(f, "iso:{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for TimeFormat {
+    type Error = TimeFormatError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TimeFormat> for String {
+    fn from(value: TimeFormat) -> Self {
+        value.to_string()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -64,6 +293,7 @@ pub struct Profile {
     pub storage: Storage,
     pub keystore: Keystore,
     pub public_key: PublicKey,
+    pub config: Config,
 }
 
 impl Profile {
@@ -71,6 +301,7 @@ impl Profile {
         let storage = Storage::open(home.storage())?;
         let keystore = Keystore::new(&home.keys());
         let public_key = keystore.init("radicle", passphrase)?;
+        let config = Config::load(&home.config())?;
 
         transport::local::register(storage.clone());
 
@@ -79,16 +310,22 @@ impl Profile {
             storage,
             keystore,
             public_key,
+            config,
         })
     }
 
     pub fn load() -> Result<Self, Error> {
-        let home = self::home()?;
+        Self::open(self::home()?)
+    }
+
+    /// Open an existing profile at the given home.
+    pub fn open(home: Home) -> Result<Self, Error> {
         let storage = Storage::open(home.storage())?;
         let keystore = Keystore::new(&home.keys());
         let public_key = keystore
             .public_key()?
             .ok_or_else(|| Error::NotFound(home.path().to_path_buf()))?;
+        let config = Config::load(&home.config())?;
 
         transport::local::register(storage.clone());
 
@@ -97,6 +334,7 @@ impl Profile {
             storage,
             keystore,
             public_key,
+            config,
         })
     }
 
@@ -135,6 +373,22 @@ impl Profile {
         Ok(config)
     }
 
+    /// Return a handle to the collaborative object cache.
+    pub fn cobs(&self) -> Result<cache::Store, cache::Error> {
+        let path = self.home.path().join(cache::COBS_DB_FILE);
+        let cache = cache::Store::open(path)?;
+
+        Ok(cache)
+    }
+
+    /// Return a handle to the registry of working copies.
+    pub fn checkouts(&self) -> Result<checkouts::Store, checkouts::Error> {
+        let path = self.home.path().join(checkouts::CHECKOUTS_DB_FILE);
+        let store = checkouts::Store::open(path)?;
+
+        Ok(store)
+    }
+
     /// Return the path to the keys folder.
     pub fn keys(&self) -> PathBuf {
         self.home.keys()
@@ -225,6 +479,10 @@ impl Home {
         self.path.join("node")
     }
 
+    pub fn config(&self) -> PathBuf {
+        self.path.join("config.json")
+    }
+
     pub fn socket(&self) -> PathBuf {
         env::var_os(env::RAD_SOCKET)
             .map(PathBuf::from)
@@ -237,7 +495,8 @@ mod test {
     use std::fs;
     use std::path::Path;
 
-    use super::Home;
+    use super::{tracking, Config, Did, Error, Home, TimeFormat, Timezone};
+    use crate::test::arbitrary;
 
     // Checks that if we have:
     // '/run/user/1000/.tmpqfK6ih/../.tmpqfK6ih/Radicle/Home'
@@ -266,4 +525,69 @@ mod test {
 
         assert_eq!(home.path, path);
     }
+
+    #[test]
+    fn test_config_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        fs::write(&path, "{}").unwrap();
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        fs::write(&path, r#"{ "preferredSeeds": ["invalid"] }"#).unwrap();
+        assert!(matches!(Config::load(&path), Err(Error::Config(..))));
+
+        fs::write(&path, r#"{ "timeFormat": "iso:utc" }"#).unwrap();
+        assert_eq!(
+            Config::load(&path).unwrap().time_format,
+            TimeFormat::Iso(Timezone::Utc)
+        );
+
+        fs::write(
+            &path,
+            r#"{ "node": { "trackingPolicy": "Track", "limits": { "maxFetches": 4 } } }"#,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.node.tracking_policy, Some(tracking::Policy::Track));
+        assert_eq!(config.node.tracking_scope, None);
+        assert_eq!(config.node.limits.max_fetches, Some(4));
+        assert_eq!(config.node.limits.fetch_concurrency, None);
+    }
+
+    #[test]
+    fn test_config_write() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.json");
+        let did = arbitrary::gen::<Did>(1);
+        let config = Config {
+            blocked: vec![did],
+            ..Config::default()
+        };
+        config.write(&path).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert!(config.is_blocked(&did));
+        assert!(!Config::default().is_blocked(&did));
+    }
+
+    #[test]
+    fn test_time_format() {
+        for s in ["relative", "iso", "iso:utc", "iso:+02:00", "iso:-05:30"] {
+            assert_eq!(s.parse::<TimeFormat>().unwrap().to_string(), s);
+        }
+        assert_eq!(
+            "iso:-05:30".parse::<TimeFormat>().unwrap(),
+            TimeFormat::Iso(Timezone::Offset(-330))
+        );
+        assert_eq!(
+            "iso:local".parse::<TimeFormat>().unwrap(),
+            TimeFormat::Iso(Timezone::Local)
+        );
+        for s in ["", "absolute", "iso:", "iso:02:00", "iso:+24:00", "iso:+2"] {
+            assert!(s.parse::<TimeFormat>().is_err(), "{s}");
+        }
+    }
 }
@@ -1,5 +1,6 @@
 #![allow(clippy::let_unit_value)]
 use std::io;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -51,6 +52,21 @@ pub fn init<G: Signer, S: WriteStorage>(
     default_branch: BranchName,
     signer: &G,
     storage: S,
+) -> Result<(Id, identity::Doc<Verified>, SignedRefs<Verified>), InitError> {
+    init_with(repo, name, description, default_branch, [], signer, storage)
+}
+
+/// Initialize a new radicle project from a git repository, with additional payloads in its
+/// identity document, eg. copied from a template repository. The project payload is always
+/// the one made from the given name, description and default branch.
+pub fn init_with<G: Signer, S: WriteStorage>(
+    repo: &git2::Repository,
+    name: &str,
+    description: &str,
+    default_branch: BranchName,
+    payload: impl IntoIterator<Item = (doc::PayloadId, doc::Payload)>,
+    signer: &G,
+    storage: S,
 ) -> Result<(Id, identity::Doc<Verified>, SignedRefs<Verified>), InitError> {
     // TODO: Better error when project id already exists in storage, but remote doesn't.
     let pk = signer.public_key();
@@ -68,7 +84,13 @@ pub fn init<G: Signer, S: WriteStorage>(
                 .join(", "),
         )
     })?;
-    let doc = identity::Doc::initial(proj, delegate).verified()?;
+    let mut doc = identity::Doc::initial(proj, delegate);
+    for (id, value) in payload {
+        if id != doc::PayloadId::project() {
+            doc.payload.insert(id, value);
+        }
+    }
+    let doc = doc.verified()?;
     let (project, _) = Repository::init(&doc, pk, storage, signer)?;
     let url = git::Url::from(project.id);
 
@@ -186,10 +208,59 @@ pub fn fork<G: Signer, S: storage::WriteStorage>(
     Ok(())
 }
 
+#[derive(Error, Debug)]
+pub enum UnforkError {
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("project identity error: {0}")]
+    InvalidIdentity(#[from] IdentityError),
+    #[error("{0} is a delegate of project `{1}`, and delegates can't remove their fork")]
+    Delegate(RemoteId, Id),
+    #[error("{0} does not have a fork of project `{1}`")]
+    NotForked(RemoteId, Id),
+    #[error("the fork of {0} has collaborative objects in project `{1}`, eg. issues or patches")]
+    HasCobs(RemoteId, Id),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+}
+
+/// Remove a fork of a project from storage, ie. all the refs under the given remote's
+/// namespace. Delegates can't remove their fork, since the canonical refs depend on it.
+///
+/// Since the remote's collaborative objects, eg. its issues, patches and comments, are also
+/// under its namespace, the fork is only removed along with them if `force` is set.
+pub fn unfork<S: storage::WriteStorage>(
+    rid: Id,
+    remote: &RemoteId,
+    force: bool,
+    storage: &S,
+) -> Result<(), UnforkError> {
+    let repository = storage.repository_mut(rid)?;
+
+    if repository.delegates()?.iter().any(|d| **d == *remote) {
+        return Err(UnforkError::Delegate(*remote, rid));
+    }
+    if repository.remote(remote).is_err() {
+        return Err(UnforkError::NotForked(*remote, rid));
+    }
+    if !force {
+        let cobs = format!("refs/namespaces/{remote}/refs/cobs/*");
+
+        if repository.raw().references_glob(&cobs)?.next().is_some() {
+            return Err(UnforkError::HasCobs(*remote, rid));
+        }
+    }
+    repository.remove_remote(remote)?;
+
+    Ok(())
+}
+
 #[derive(Error, Debug)]
 pub enum CheckoutError {
     #[error("failed to fetch to working copy")]
     Fetch(#[source] git2::Error),
+    #[error("failed to fetch to working copy: {0}")]
+    FetchShallow(#[source] io::Error),
     #[error("git: {0}")]
     Git(#[from] git2::Error),
     #[error("storage: {0}")]
@@ -209,6 +280,32 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
     remote: &RemoteId,
     path: P,
     storage: &S,
+) -> Result<git2::Repository, CheckoutError> {
+    checkout_with(proj, remote, path, storage, None)
+}
+
+/// Checkout a project from storage as a shallow working copy, with only the last `depth`
+/// commits of each branch. This effectively does a `git-clone --depth` from storage.
+///
+/// Only the working copy is shallow: storage always has the complete history, which is needed
+/// to verify the repository. The history can be deepened later with `git fetch --deepen`
+/// or `git fetch --unshallow`.
+pub fn shallow_checkout<P: AsRef<Path>, S: storage::ReadStorage>(
+    proj: Id,
+    remote: &RemoteId,
+    path: P,
+    storage: &S,
+    depth: NonZeroUsize,
+) -> Result<git2::Repository, CheckoutError> {
+    checkout_with(proj, remote, path, storage, Some(depth))
+}
+
+fn checkout_with<P: AsRef<Path>, S: storage::ReadStorage>(
+    proj: Id,
+    remote: &RemoteId,
+    path: P,
+    storage: &S,
+    depth: Option<NonZeroUsize>,
 ) -> Result<git2::Repository, CheckoutError> {
     // TODO: Decide on whether we can use `clone_local`
     // TODO: Look into sharing object databases.
@@ -230,7 +327,33 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
         &url,
         &url.clone().with_namespace(*remote),
     )?;
-    git::fetch(&repo, &REMOTE_NAME).map_err(CheckoutError::Fetch)?;
+    if let Some(depth) = depth {
+        // Shallow fetches aren't supported by `git2`, nor by our transport, so we fetch
+        // straight from the storage repository, with `git` itself. Since we're not going
+        // through the transport, we fetch the remote's namespace ourselves.
+        let url = git::url::File::new(storage.path_of(&proj)).to_string();
+        let depth = depth.to_string();
+        let refspec = format!(
+            "+refs/namespaces/{remote}/refs/heads/*:refs/remotes/{}/*",
+            *REMOTE_NAME
+        );
+
+        git::run(
+            path.as_ref(),
+            [
+                "fetch",
+                "--quiet",
+                "--depth",
+                depth.as_str(),
+                url.as_str(),
+                refspec.as_str(),
+            ],
+            None::<(&str, &str)>,
+        )
+        .map_err(CheckoutError::FetchShallow)?;
+    } else {
+        git::fetch(&repo, &REMOTE_NAME).map_err(CheckoutError::Fetch)?;
+    }
 
     {
         // Setup default branch.
@@ -364,6 +487,35 @@ mod tests {
         assert_eq!(doc.delegates.first(), &Did::from(public_key));
     }
 
+    #[test]
+    fn test_init_with() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let labels = doc::Payload::from(serde_json::json!({ "bug": { "color": "#d73a4a" } }));
+        let project = doc::Payload::from(serde_json::json!({ "name": "template" }));
+        let (repo, _) = fixtures::repository(tempdir.path().join("working"));
+        let (_, doc, _) = init_with(
+            &repo,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            [
+                (doc::PayloadId::labels(), labels.clone()),
+                (doc::PayloadId::project(), project),
+            ],
+            &signer,
+            &storage,
+        )
+        .unwrap();
+
+        assert_eq!(doc.payload.get(&doc::PayloadId::labels()), Some(&labels));
+        assert_eq!(doc.project().unwrap().name(), "acme");
+    }
+
     #[test]
     fn test_fork() {
         let mut rng = fastrand::Rng::new();
@@ -402,6 +554,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unfork() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (original, _) = fixtures::repository(tempdir.path().join("original"));
+        let (id, _, _) = init(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+
+        // Delegates can't remove their fork.
+        assert!(matches!(
+            unfork(id, alice.public_key(), true, &storage),
+            Err(UnforkError::Delegate(..))
+        ));
+        assert!(matches!(
+            unfork(id, bob.public_key(), false, &storage),
+            Err(UnforkError::NotForked(..))
+        ));
+
+        fork(id, &bob, &storage).unwrap();
+        unfork(id, bob.public_key(), false, &storage).unwrap();
+
+        let repo = storage.repository(id).unwrap();
+        assert!(repo.remote(bob.public_key()).is_err());
+        assert!(repo.remote(alice.public_key()).is_ok());
+
+        // A fork with collaborative objects is only removed when forced.
+        fork(id, &bob, &storage).unwrap();
+        let (_, head) = repo.head().unwrap();
+        repo.raw()
+            .reference(
+                &format!(
+                    "refs/namespaces/{}/refs/cobs/xyz.radicle.issue/{head}",
+                    bob.public_key()
+                ),
+                head.into(),
+                false,
+                "Create issue",
+            )
+            .unwrap();
+
+        assert!(matches!(
+            unfork(id, bob.public_key(), false, &storage),
+            Err(UnforkError::HasCobs(..))
+        ));
+        assert!(repo.remote(bob.public_key()).is_ok());
+
+        unfork(id, bob.public_key(), true, &storage).unwrap();
+        assert!(repo.remote(bob.public_key()).is_err());
+    }
+
     #[test]
     fn test_checkout() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -454,4 +668,97 @@ mod tests {
                 .collect::<Vec<_>>(),
         );
     }
+
+    #[test]
+    fn test_shallow_checkout() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let remote_id = signer.public_key();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (original, head) = fixtures::repository(tempdir.path().join("original"));
+        let (id, _, _) = init(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &signer,
+            &storage,
+        )
+        .unwrap();
+
+        let copy = shallow_checkout(
+            id,
+            remote_id,
+            tempdir.path().join("copy"),
+            &storage,
+            NonZeroUsize::new(1).unwrap(),
+        )
+        .unwrap();
+        let commit = copy.find_commit(head).unwrap();
+
+        assert!(copy.is_shallow());
+        assert_eq!(copy.head().unwrap().target(), Some(head));
+        assert!(copy.find_commit(commit.parent_id(0).unwrap()).is_err());
+        assert_eq!(
+            copy.branch_upstream_name("refs/heads/master")
+                .unwrap()
+                .to_vec(),
+            b"refs/remotes/rad/master".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_shallow_checkout_remote() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (original, head) = fixtures::repository(tempdir.path().join("original"));
+        let (id, _, _) = init(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        fork(id, &bob, &storage).unwrap();
+
+        // Bob, who isn't a delegate, has a commit that the canonical branch doesn't have.
+        let repo = storage.repository(id).unwrap();
+        let parent = repo.raw().find_commit(head).unwrap();
+        let branch = git::refs::storage::branch(bob.public_key(), &git::refname!("master"));
+        let author = git2::Signature::now("bob", "bob@radicle.xyz").unwrap();
+        let bob_head = repo
+            .raw()
+            .commit(
+                Some(branch.as_str()),
+                &author,
+                &author,
+                "Bob's change",
+                &parent.tree().unwrap(),
+                &[&parent],
+            )
+            .unwrap();
+
+        let copy = shallow_checkout(
+            id,
+            bob.public_key(),
+            tempdir.path().join("copy"),
+            &storage,
+            NonZeroUsize::new(1).unwrap(),
+        )
+        .unwrap();
+
+        assert!(copy.is_shallow());
+        assert_eq!(copy.head().unwrap().target(), Some(bob_head));
+        assert!(copy.find_commit(head).is_err());
+    }
 }
@@ -1,5 +1,7 @@
 use std::ops::Deref;
+use std::path::Path;
 use std::str::FromStr;
+use std::{fmt, time};
 
 use sqlite as sql;
 use sqlite::Value;
@@ -8,6 +10,59 @@ use crate::identity::Id;
 use crate::node;
 use crate::node::Address;
 
+/// How long to wait for the database lock to be released before failing a read.
+pub const DB_READ_TIMEOUT: time::Duration = time::Duration::from_secs(3);
+/// How long to wait for the database lock to be released before failing a write.
+pub const DB_WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// A database connection, whose schema is created when it is opened.
+pub struct Database {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Database {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Database(..)")
+    }
+}
+
+impl Deref for Database {
+    type Target = sql::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+impl Database {
+    /// Open a database at the given path. Creates the schema if it doesn't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P, schema: &str) -> Result<Self, sql::Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_WRITE_TIMEOUT.as_millis() as usize)?;
+        db.execute(schema)?;
+
+        Ok(Self { db })
+    }
+
+    /// Same as [`Self::open`], but in read-only mode.
+    pub fn reader<P: AsRef<Path>>(path: P, schema: &str) -> Result<Self, sql::Error> {
+        let mut db =
+            sql::Connection::open_with_flags(path, sqlite::OpenFlags::new().set_read_only())?;
+        db.set_busy_timeout(DB_READ_TIMEOUT.as_millis() as usize)?;
+        db.execute(schema)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory database.
+    pub fn memory(schema: &str) -> Result<Self, sql::Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(schema)?;
+
+        Ok(Self { db })
+    }
+}
+
 /// Run an SQL query inside a transaction.
 /// Commits the transaction on success, and rolls back on error.
 pub fn transaction<T>(
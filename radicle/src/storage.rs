@@ -1,7 +1,9 @@
+pub mod checkouts;
+pub mod events;
 pub mod git;
 pub mod refs;
 
-use std::collections::{hash_map, HashSet};
+use std::collections::{hash_map, BTreeMap, HashSet};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::{fmt, io};
@@ -20,13 +22,18 @@ use crate::git::{Qualified, RefError, RefString};
 use crate::identity;
 use crate::identity::doc::DocError;
 use crate::identity::Did;
-use crate::identity::{Id, IdentityError};
+use crate::identity::{Id, IdentityError, Visibility};
 use crate::storage::refs::Refs;
 
 use self::refs::SignedRefs;
 
 pub type BranchName = git::RefString;
 pub type Inventory = Vec<Id>;
+/// The outcome of validating each remote of a repository.
+///
+/// For every remote, either the list of unsigned refs found under it, or the error
+/// encountered while validating it.
+pub type Validations = BTreeMap<RemoteId, Result<Vec<RefString>, VerifyError>>;
 
 /// Describes one or more namespaces.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -142,6 +149,25 @@ impl fmt::Display for RefUpdate {
     }
 }
 
+/// A rewrite of the history of a repository's canonical branch, ie. a canonical head that
+/// doesn't descend from the current one, eg. because a delegate force-pushed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rewrite {
+    /// Current head of the canonical branch.
+    pub old: Oid,
+    /// Rewritten head, computed from the delegates' branches.
+    pub new: Oid,
+    /// Whether the rewritten head is adopted as the canonical head.
+    pub adopted: bool,
+}
+
+impl fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.7}...{:.7}", self.old, self.new)
+    }
+}
+
 /// Project remotes. Tracks the git state of a project.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Remotes<V>(HashMap<RemoteId, Remote<V>>);
@@ -295,12 +321,23 @@ pub trait ReadRepository {
 
     /// Validate all remotes with [`ReadRepository::validate_remote`].
     fn validate(&self) -> Result<(), VerifyError> {
-        for (_, remote) in self.remotes()? {
-            self.validate_remote(&remote)?;
+        for (_, result) in self.validate_remotes()? {
+            result?;
         }
         Ok(())
     }
 
+    /// Validate all remotes with [`ReadRepository::validate_remote`], returning the result
+    /// for each remote, instead of stopping at the first invalid one.
+    fn validate_remotes(&self) -> Result<Validations, VerifyError> {
+        let mut validations = Validations::new();
+
+        for (id, remote) in self.remotes()? {
+            validations.insert(id, self.validate_remote(&remote));
+        }
+        Ok(validations)
+    }
+
     /// Validates a remote's signed refs and identity.
     ///
     /// Returns any ref found under that remote that isn't signed.
@@ -384,6 +421,22 @@ pub trait ReadRepository {
 
     /// Get the repository's identity document at a specific commit.
     fn identity_doc_at(&self, head: Oid) -> Result<identity::Doc<Unverified>, DocError>;
+
+    /// Get the repository's visibility.
+    fn visibility(&self) -> Result<Visibility, IdentityError> {
+        let (_, doc) = self.identity_doc()?;
+        let doc = doc.verified()?;
+
+        Ok(doc.visibility()?)
+    }
+
+    /// Whether the repository may be served to the given node.
+    fn is_visible_to(&self, key: &PublicKey) -> Result<bool, IdentityError> {
+        let (_, doc) = self.identity_doc()?;
+        let doc = doc.verified()?;
+
+        Ok(doc.is_visible_to(key)?)
+    }
 }
 
 /// Allows read-write access to a repository.
@@ -395,6 +448,8 @@ pub trait WriteRepository: ReadRepository {
     fn set_identity_head(&self) -> Result<Oid, IdentityError>;
     /// Sign the repository's refs under the `refs/rad/sigrefs` branch.
     fn sign_refs<G: Signer>(&self, signer: &G) -> Result<SignedRefs<Verified>, Error>;
+    /// Remove all of a remote's refs from the repository.
+    fn remove_remote(&self, remote: &RemoteId) -> Result<(), Error>;
     /// Get the underlying git repository.
     fn raw(&self) -> &git2::Repository;
 }
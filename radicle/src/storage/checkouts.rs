@@ -0,0 +1,190 @@
+//! Registry of the working copies checked out from storage.
+//!
+//! A stored repository may back any number of working copies, eg. made with `rad init`,
+//! `rad clone` or `rad checkout`. The registry keeps track of them, so that repositories
+//! which are still in use aren't removed from storage, or stripped of namespaces the working
+//! copies refer to, by housekeeping.
+//!
+//! Working copies are not told when they are deleted or moved: a registered working copy is
+//! only considered in use as long as its `rad` remote still points to the repository. Stale
+//! entries are dropped from the registry whenever it is read.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, time};
+
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::identity::Id;
+use crate::rad;
+use crate::sql::transaction;
+
+/// Name of the registry database file, in the profile home.
+pub const CHECKOUTS_DB_FILE: &str = "checkouts.db";
+
+/// How long to wait for the database lock to be released before failing.
+const DB_TIMEOUT: time::Duration = time::Duration::from_secs(6);
+
+/// An error occuring when using the registry.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+}
+
+/// Working copy registry.
+pub struct Store {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Store(..)")
+    }
+}
+
+impl Store {
+    const SCHEMA: &str = include_str!("checkouts/schema.sql");
+
+    /// Open a registry at the given path. Creates a new empty registry if an existing one
+    /// isn't found.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut db = sql::Connection::open(path)?;
+        db.set_busy_timeout(DB_TIMEOUT.as_millis() as usize)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory registry.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Register a working copy of a repository. Returns `false` if it was already registered.
+    pub fn register(&mut self, rid: &Id, path: &Path) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO checkouts (repo, path)
+             VALUES (?1, ?2)
+             ON CONFLICT DO NOTHING",
+        )?;
+
+        stmt.bind((1, rid))?;
+        stmt.bind((2, canonical(path).to_string_lossy().as_ref()))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Unregister a working copy of a repository. Returns `false` if it wasn't registered.
+    pub fn unregister(&mut self, rid: &Id, path: &Path) -> Result<bool, Error> {
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM checkouts WHERE repo = ?1 AND path = ?2")?;
+
+        stmt.bind((1, rid))?;
+        stmt.bind((2, canonical(path).to_string_lossy().as_ref()))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
+    /// Get the working copies of a repository.
+    pub fn checkouts(&mut self, rid: &Id) -> Result<Vec<PathBuf>, Error> {
+        Ok(self.all()?.remove(rid).unwrap_or_default())
+    }
+
+    /// Check whether a repository backs at least one working copy.
+    pub fn is_in_use(&mut self, rid: &Id) -> Result<bool, Error> {
+        Ok(!self.checkouts(rid)?.is_empty())
+    }
+
+    /// Get all working copies, by repository. Working copies that no longer exist, or no
+    /// longer point to their repository, are unregistered.
+    pub fn all(&mut self) -> Result<BTreeMap<Id, Vec<PathBuf>>, Error> {
+        let mut checkouts = BTreeMap::<Id, Vec<PathBuf>>::new();
+        let mut stale = Vec::new();
+        {
+            let mut stmt = self
+                .db
+                .prepare("SELECT repo, path FROM checkouts ORDER BY repo, path")?;
+
+            for row in stmt.into_iter() {
+                let row = row?;
+                let rid = row.read::<Id, _>("repo");
+                let path = row.read::<String, _>("path");
+
+                if is_checkout_of(&rid, Path::new(&path)) {
+                    checkouts.entry(rid).or_default().push(PathBuf::from(path));
+                } else {
+                    stale.push((rid, path));
+                }
+            }
+        }
+
+        transaction(&self.db, |db| {
+            for (rid, path) in &stale {
+                let mut stmt = db.prepare("DELETE FROM checkouts WHERE repo = ?1 AND path = ?2")?;
+                stmt.bind((1, rid))?;
+                stmt.bind((2, path.as_str()))?;
+                stmt.next()?;
+            }
+            Ok(())
+        })?;
+
+        Ok(checkouts)
+    }
+}
+
+/// Canonicalize a working copy path, if possible, so that a working copy is only registered
+/// once, however it is referred to.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Check whether the given path is a working copy of the repository.
+fn is_checkout_of(rid: &Id, path: &Path) -> bool {
+    matches!(rad::repo(path), Ok((_, id)) if id == *rid)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::crypto::test::signer::MockSigner;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_checkouts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = fixtures::storage(tmp.path().join("storage"), &signer).unwrap();
+        let (rid, _, working, _) =
+            fixtures::project(tmp.path().join("acme"), &storage, &signer).unwrap();
+        let path = working.workdir().unwrap();
+        let mut store = Store::memory().unwrap();
+
+        assert!(!store.is_in_use(&rid).unwrap());
+        assert!(store.register(&rid, path).unwrap());
+        assert!(!store.register(&rid, &tmp.path().join("acme")).unwrap());
+        assert!(store.is_in_use(&rid).unwrap());
+        assert_eq!(store.checkouts(&rid).unwrap(), vec![canonical(path)]);
+
+        // Paths that aren't working copies of the repository are dropped.
+        let other = tmp.path().join("other");
+        fs::create_dir(&other).unwrap();
+        assert!(store.register(&rid, &other).unwrap());
+        assert_eq!(store.checkouts(&rid).unwrap(), vec![canonical(path)]);
+        assert!(!store.unregister(&rid, &other).unwrap());
+
+        // As are working copies that were deleted.
+        fs::remove_dir_all(path).unwrap();
+        assert!(!store.is_in_use(&rid).unwrap());
+        assert!(store.all().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,11 @@
+--
+-- Working copy registry SQL schema.
+--
+create table if not exists "checkouts" (
+  -- Repository the working copy was checked out from.
+  "repo"         text      not null,
+  -- Canonical path of the working copy.
+  "path"         text      not null,
+
+  primary key ("repo", "path")
+);
@@ -0,0 +1,303 @@
+//! Storage events journal.
+//!
+//! Storage is written to by several processes: the node, when fetching, and the CLI, when
+//! creating repositories, pushing or editing collaborative objects. So that other processes,
+//! eg. indexers and frontends, can react to these changes whoever the writer is, writers
+//! append an [`Event`] to a journal file at the root of the storage, and readers follow the
+//! file with a [`Subscriber`].
+//!
+//! Each event is a single line of JSON, appended with a single write, so that the events of
+//! concurrent writers don't interleave. Once the journal grows past [`MAX_SIZE`], the next
+//! writer renames it to [`ROTATED_FILE`], replacing the previous one, and starts a new
+//! journal. Subscribers keep the file they follow open, so they read the events left in the
+//! rotated journal before moving on to the new one.
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::io::{Read as _, Seek as _, Write as _};
+use std::os::unix::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cob::{ObjectId, TypeName};
+use crate::identity::Id;
+use crate::storage::RemoteId;
+
+/// Name of the journal file, at the root of the storage.
+pub const JOURNAL_FILE: &str = ".events";
+/// Name the journal is renamed to once it's rotated, at the root of the storage.
+pub const ROTATED_FILE: &str = ".events.1";
+/// Size past which the journal is rotated, in bytes.
+pub const MAX_SIZE: u64 = 1024 * 1024;
+/// How often a blocking [`Subscriber`] checks the journal for new events.
+pub const POLL_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+/// A storage write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Event {
+    /// A repository was created.
+    RepositoryCreated { rid: Id },
+    /// The refs of a remote were updated.
+    RefsUpdated { rid: Id, remote: RemoteId },
+    /// A collaborative object was created, updated or removed.
+    #[serde(rename_all = "camelCase")]
+    CobChanged {
+        rid: Id,
+        type_name: TypeName,
+        id: ObjectId,
+    },
+}
+
+/// The events journal of a storage.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+    rotated: PathBuf,
+}
+
+impl Journal {
+    /// Get the journal of the storage at the given path.
+    pub fn new<P: AsRef<Path>>(storage: P) -> Self {
+        Self {
+            path: storage.as_ref().join(JOURNAL_FILE),
+            rotated: storage.as_ref().join(ROTATED_FILE),
+        }
+    }
+
+    /// Append an event to the journal, rotating it first if it's too large.
+    pub fn publish(&self, event: &Event) -> Result<(), io::Error> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        loop {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let meta = file.metadata()?;
+
+            if meta.len() < MAX_SIZE {
+                return file.write_all(&line);
+            }
+            // Only rotate the journal if no other writer did since we opened it. Otherwise,
+            // we open the new journal.
+            if inode(&self.path)? == Some(meta.ino()) {
+                match fs::rename(&self.path, &self.rotated) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Subscribe to the events published from now on.
+    pub fn subscribe(&self) -> Result<Subscriber, io::Error> {
+        let (file, offset) = match fs::File::open(&self.path) {
+            Ok(file) => {
+                let offset = file.metadata()?.len();
+                (Some(file), offset)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (None, 0),
+            Err(e) => return Err(e),
+        };
+        Ok(Subscriber {
+            path: self.path.clone(),
+            file,
+            offset,
+            partial: Vec::new(),
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// Get the inode of the file at the given path, if it exists.
+fn inode(path: &Path) -> Result<Option<u64>, io::Error> {
+    match fs::metadata(path) {
+        Ok(meta) => Ok(Some(meta.ino())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Follows a journal, yielding the events appended to it.
+///
+/// Iterating over a subscriber blocks until new events are published. Use
+/// [`Subscriber::poll`] to only get the events that are already there.
+#[derive(Debug)]
+pub struct Subscriber {
+    path: PathBuf,
+    /// The journal file followed. This is no longer the journal once it's rotated.
+    file: Option<fs::File>,
+    /// Position in the followed file up to which events were read.
+    offset: u64,
+    /// Incomplete line read at the end of the journal.
+    partial: Vec<u8>,
+    /// Events read, but not yet yielded.
+    pending: VecDeque<Event>,
+}
+
+impl Subscriber {
+    /// Get the events published since the last call, without blocking.
+    pub fn poll(&mut self) -> Result<Vec<Event>, io::Error> {
+        self.read()?;
+
+        Ok(self.pending.drain(..).collect())
+    }
+
+    /// Read the events appended to the journal since the last read. If the journal was
+    /// rotated, the rest of the rotated journal is read before the new one.
+    fn read(&mut self) -> Result<(), io::Error> {
+        loop {
+            let Some(file) = &mut self.file else {
+                match fs::File::open(&self.path) {
+                    Ok(file) => self.file = Some(file),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                    Err(e) => return Err(e),
+                }
+                continue;
+            };
+            let meta = file.metadata()?;
+
+            if meta.len() < self.offset {
+                // The journal was truncated, start over.
+                self.offset = 0;
+                self.partial.clear();
+            }
+            if meta.len() > self.offset {
+                file.seek(io::SeekFrom::Start(self.offset))?;
+                self.offset += file.read_to_end(&mut self.partial)? as u64;
+                self.parse();
+            }
+            if inode(&self.path)? == Some(meta.ino()) {
+                return Ok(());
+            }
+            // The journal was rotated, and we've read the rest of the one we followed.
+            self.file = None;
+            self.offset = 0;
+            self.partial.clear();
+        }
+    }
+
+    /// Parse the complete lines read.
+    fn parse(&mut self) {
+        while let Some(ix) = self.partial.iter().position(|b| *b == b'\n') {
+            let line = self.partial.drain(..=ix).collect::<Vec<_>>();
+
+            match serde_json::from_slice(&line) {
+                Ok(event) => self.pending.push_back(event),
+                Err(e) => {
+                    log::warn!(target: "storage", "Skipping invalid event in journal: {e}");
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for Subscriber {
+    type Item = Result<Event, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if let Err(e) = self.read() {
+                return Some(Err(e));
+            }
+            if self.pending.is_empty() {
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_publish_subscribe() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path());
+        let rid = arbitrary::gen::<Id>(1);
+        let remote = arbitrary::gen::<RemoteId>(1);
+
+        // Events published before subscribing aren't seen.
+        journal.publish(&Event::RepositoryCreated { rid }).unwrap();
+
+        let mut sub = journal.subscribe().unwrap();
+        assert_eq!(sub.poll().unwrap(), vec![]);
+
+        journal
+            .publish(&Event::RefsUpdated { rid, remote })
+            .unwrap();
+        assert_eq!(
+            sub.poll().unwrap(),
+            vec![Event::RefsUpdated { rid, remote }]
+        );
+        assert_eq!(sub.poll().unwrap(), vec![]);
+
+        // Truncating the journal starts the subscriber over.
+        fs::OpenOptions::new()
+            .write(true)
+            .open(tmp.path().join(JOURNAL_FILE))
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        journal.publish(&Event::RepositoryCreated { rid }).unwrap();
+        assert_eq!(
+            sub.next().unwrap().unwrap(),
+            Event::RepositoryCreated { rid }
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path());
+        let rid = arbitrary::gen::<Id>(1);
+        let remote = arbitrary::gen::<RemoteId>(1);
+        let mut sub = journal.subscribe().unwrap();
+
+        journal.publish(&Event::RepositoryCreated { rid }).unwrap();
+
+        // Fill the journal up to its maximum size.
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(tmp.path().join(JOURNAL_FILE))
+            .unwrap();
+        let len = file.metadata().unwrap().len();
+        file.write_all(&vec![b' '; (MAX_SIZE - len) as usize])
+            .unwrap();
+        file.write_all(b"\n").unwrap();
+
+        // The next event is published in a new journal.
+        journal
+            .publish(&Event::RefsUpdated { rid, remote })
+            .unwrap();
+        assert!(tmp.path().join(ROTATED_FILE).exists());
+        assert_eq!(
+            fs::read(tmp.path().join(JOURNAL_FILE)).unwrap(),
+            [
+                serde_json::to_vec(&Event::RefsUpdated { rid, remote }).unwrap(),
+                b"\n".to_vec()
+            ]
+            .concat()
+        );
+
+        // Subscribers read the rest of the rotated journal, then the new one.
+        assert_eq!(
+            sub.poll().unwrap(),
+            vec![
+                Event::RepositoryCreated { rid },
+                Event::RefsUpdated { rid, remote }
+            ]
+        );
+        assert_eq!(sub.poll().unwrap(), vec![]);
+    }
+}
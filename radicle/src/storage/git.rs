@@ -1,9 +1,13 @@
+pub mod bundle;
 pub mod cob;
+pub mod gc;
+pub mod notes;
 pub mod transport;
 
 use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+use std::sync::Arc;
+use std::{fs, io, panic, thread};
 
 use crypto::{Signer, Unverified, Verified};
 use git_ref_format::refspec;
@@ -14,10 +18,12 @@ use crate::identity;
 use crate::identity::doc::DocError;
 use crate::identity::{Doc, Id};
 use crate::identity::{Identity, IdentityError, Project};
+use crate::storage::events::{Event, Journal};
 use crate::storage::refs;
 use crate::storage::refs::{Refs, SignedRefs};
 use crate::storage::{
-    Inventory, ReadRepository, ReadStorage, Remote, Remotes, WriteRepository, WriteStorage,
+    Inventory, ReadRepository, ReadStorage, Remote, Remotes, Rewrite, Validations, WriteRepository,
+    WriteStorage,
 };
 
 pub use crate::git::*;
@@ -25,6 +31,9 @@ pub use crate::storage::Error;
 
 use super::RemoteId;
 
+/// Maximum number of threads used to validate the remotes of a repository.
+pub const MAX_VALIDATION_WORKERS: usize = 8;
+
 pub static NAMESPACES_GLOB: Lazy<refspec::PatternString> =
     Lazy::new(|| refspec::pattern!("refs/namespaces/*"));
 pub static SIGREFS_GLOB: Lazy<refspec::PatternString> =
@@ -70,6 +79,9 @@ impl<'a> TryFrom<git2::Reference<'a>> for Ref {
 #[derive(Debug, Clone)]
 pub struct Storage {
     path: PathBuf,
+    /// Temporary directory holding the storage, if any. It is removed once the storage and
+    /// all of its clones are dropped.
+    _temp: Option<Arc<tempfile::TempDir>>,
 }
 
 impl ReadStorage for Storage {
@@ -121,7 +133,10 @@ impl WriteStorage for Storage {
     }
 
     fn create(&self, rid: Id) -> Result<Self::RepositoryMut, Error> {
-        Repository::create(paths::repository(self, &rid), rid)
+        let repo = Repository::create(paths::repository(self, &rid), rid)?;
+        publish(&self.journal(), Event::RepositoryCreated { rid });
+
+        Ok(repo)
     }
 }
 
@@ -136,13 +151,32 @@ impl Storage {
             Ok(()) => {}
         }
 
-        Ok(Self { path })
+        Ok(Self { path, _temp: None })
+    }
+
+    /// Create a new, empty storage in a temporary directory, which is removed once the
+    /// storage and all of its clones are dropped. Useful for tests, and for tools that need
+    /// a throw-away storage, eg. to inspect a repository without touching the user's profile.
+    pub fn temporary() -> Result<Self, io::Error> {
+        let temp = tempfile::Builder::new()
+            .prefix("radicle-storage-")
+            .tempdir()?;
+
+        Ok(Self {
+            path: temp.path().to_path_buf(),
+            _temp: Some(Arc::new(temp)),
+        })
     }
 
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
 
+    /// Get the events journal of this storage.
+    pub fn journal(&self) -> Journal {
+        Journal::new(&self.path)
+    }
+
     pub fn repositories(&self) -> Result<Vec<Id>, Error> {
         let mut repos = Vec::new();
 
@@ -211,6 +245,12 @@ pub enum VerifyError {
 }
 
 impl Repository {
+    /// Get the events journal of the storage this repository is in.
+    pub fn journal(&self) -> Journal {
+        let path = self.backend.path();
+        Journal::new(path.parent().unwrap_or(path))
+    }
+
     /// Open an existing repository.
     pub fn open<P: AsRef<Path>>(path: P, id: Id) -> Result<Self, Error> {
         let backend = git2::Repository::open_bare(path.as_ref())?;
@@ -307,6 +347,41 @@ impl Repository {
         Ok(proj)
     }
 
+    /// Check whether the canonical head, as computed from the delegates' branches, rewrites
+    /// the history of the canonical branch, ie. doesn't descend from its current head.
+    ///
+    /// The rewrite is adopted if `allow` is set, or if the identity document acknowledges it,
+    /// see [`Doc::rewrites`].
+    pub fn rewrite(&self, allow: bool) -> Result<Option<Rewrite>, IdentityError> {
+        let (branch_ref, new) = self.canonical_head()?;
+        let old = match self.backend.refname_to_id(branch_ref.as_str()) {
+            Ok(oid) => Oid::from(oid),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if old == new || self.backend.graph_descendant_of(*new, *old)? {
+            return Ok(None);
+        }
+        let (_, doc) = self.identity_doc()?;
+        let adopted = allow
+            || doc.verified()?.rewrites()?.iter().any(|head| {
+                *head == new || matches!(self.backend.graph_descendant_of(*new, **head), Ok(true))
+            });
+
+        Ok(Some(Rewrite { old, new, adopted }))
+    }
+
+    /// Set the canonical head, like [`WriteRepository::set_head`], unless that rewrites the
+    /// history of the canonical branch and the rewrite isn't adopted, see [`Self::rewrite`].
+    ///
+    /// Returns the canonical head, and the rewrite, if any.
+    pub fn set_head_protected(&self, allow: bool) -> Result<(Oid, Option<Rewrite>), IdentityError> {
+        match self.rewrite(allow)? {
+            Some(rewrite) if !rewrite.adopted => Ok((rewrite.old, Some(rewrite))),
+            rewrite => Ok((self.set_head()?, rewrite)),
+        }
+    }
+
     pub fn identity_doc_of(&self, remote: &RemoteId) -> Result<Doc<Verified>, IdentityError> {
         let (doc, _) = identity::Doc::load(remote, self)?;
         let verified = doc.verified()?;
@@ -329,6 +404,61 @@ impl Repository {
         Ok(iter)
     }
 
+    /// Load and validate a single remote, verifying its signed refs.
+    pub fn validate_remote_id(&self, remote: &RemoteId) -> Result<Vec<RefString>, VerifyError> {
+        let remote = self.remote(remote)?;
+
+        self.validate_remote(&remote)
+    }
+
+    /// Validate the given remotes using up to `workers` threads.
+    ///
+    /// Since a [`git2::Repository`] can't be shared between threads, each worker opens its
+    /// own handle to the repository.
+    pub fn validate_remotes_with(
+        &self,
+        remotes: &[RemoteId],
+        workers: usize,
+    ) -> Result<Validations, VerifyError> {
+        if remotes.len() <= 1 || workers <= 1 {
+            return Ok(remotes
+                .iter()
+                .map(|id| (*id, self.validate_remote_id(id)))
+                .collect());
+        }
+        let chunk_size = (remotes.len() + workers - 1) / workers;
+        let path = self.path().to_path_buf();
+        let rid = self.id;
+
+        thread::scope(|scope| {
+            let handles = remotes
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let path = &path;
+
+                    scope.spawn(move || -> Result<Vec<_>, VerifyError> {
+                        let repo = Repository::open(path, rid)?;
+
+                        Ok(chunk
+                            .iter()
+                            .map(|id| (*id, repo.validate_remote_id(id)))
+                            .collect())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut validations = Validations::new();
+            for handle in handles {
+                let results = handle
+                    .join()
+                    .unwrap_or_else(|payload| panic::resume_unwind(payload))?;
+
+                validations.extend(results);
+            }
+            Ok(validations)
+        })
+    }
+
     pub fn remotes(
         &self,
     ) -> Result<
@@ -402,6 +532,16 @@ impl ReadRepository for Repository {
         Ok(unsigned)
     }
 
+    fn validate_remotes(&self) -> Result<Validations, VerifyError> {
+        let remotes = self.remote_ids()?.collect::<Result<Vec<_>, _>>()?;
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_VALIDATION_WORKERS);
+
+        self.validate_remotes_with(&remotes, workers)
+    }
+
     fn reference(
         &self,
         remote: &RemoteId,
@@ -596,15 +736,41 @@ impl WriteRepository for Repository {
         let signed = refs.signed(signer)?;
 
         signed.save(remote, self)?;
+        publish(
+            &self.journal(),
+            Event::RefsUpdated {
+                rid: self.id,
+                remote: *remote,
+            },
+        );
 
         Ok(signed)
     }
 
+    fn remove_remote(&self, remote: &RemoteId) -> Result<(), Error> {
+        let glob = format!("refs/namespaces/{remote}/*");
+
+        for r in self.backend.references_glob(glob.as_str())? {
+            r?.delete()?;
+        }
+        log::debug!(target: "storage", "Removed remote {remote} from {}", self.id);
+
+        Ok(())
+    }
+
     fn raw(&self) -> &git2::Repository {
         &self.backend
     }
 }
 
+/// Publish a storage event. Since the journal is only there to notify other processes,
+/// failing to publish doesn't fail the write that caused the event.
+pub(crate) fn publish(journal: &Journal, event: Event) {
+    if let Err(e) = journal.publish(&event) {
+        log::warn!(target: "storage", "Failed to publish storage event {event:?}: {e}");
+    }
+}
+
 pub mod trailers {
     use std::str::FromStr;
 
@@ -721,6 +887,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_remove_remote() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let proj = storage.repository(id).unwrap();
+
+        assert!(!proj.references_of(signer.public_key()).unwrap().is_empty());
+        proj.remove_remote(signer.public_key()).unwrap();
+        assert!(proj.references_of(signer.public_key()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_temporary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::temporary().unwrap();
+        let path = storage.path().to_path_buf();
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+
+        // Clones share the same directory, which outlives the original.
+        let clone = storage.clone();
+        drop(storage);
+        assert_eq!(clone.inventory().unwrap(), vec![id]);
+
+        // Once the last clone is dropped, the directory is removed.
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_set_head_protected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let (_, old) = repo.head().unwrap();
+        assert_eq!(repo.rewrite(false).unwrap(), None);
+
+        // Force the delegate's branch to an unrelated commit.
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let new = Oid::from(git::initial_commit(&repo.backend, &sig).unwrap().id());
+        repo.backend
+            .reference(
+                &format!("refs/namespaces/{}/refs/heads/master", signer.public_key()),
+                *new,
+                true,
+                "force-push",
+            )
+            .unwrap();
+
+        let (head, rewrite) = repo.set_head_protected(false).unwrap();
+        assert_eq!(head, old);
+        assert_eq!(
+            rewrite,
+            Some(Rewrite {
+                old,
+                new,
+                adopted: false
+            })
+        );
+        assert_eq!(repo.head().unwrap().1, old);
+
+        let (head, rewrite) = repo.set_head_protected(true).unwrap();
+        assert_eq!(head, new);
+        assert!(rewrite.unwrap().adopted);
+        assert_eq!(repo.head().unwrap().1, new);
+        assert_eq!(repo.rewrite(false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_remotes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let remotes = repo
+            .remote_ids()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let remotes = remotes.repeat(4);
+
+        let sequential = repo.validate_remotes_with(&remotes, 1).unwrap();
+        let parallel = repo.validate_remotes_with(&remotes, 4).unwrap();
+
+        assert_eq!(sequential.len(), 1);
+        assert_eq!(parallel.len(), 1);
+        assert!(parallel.values().all(|r| r.is_ok()));
+        assert!(repo.validate().is_ok());
+    }
+
     #[test]
     fn test_sign_refs() {
         let tmp = tempfile::tempdir().unwrap();
@@ -0,0 +1,322 @@
+//! Offline synchronization of repositories through git bundles.
+//!
+//! A bundle contains the namespaced refs of every remote of a repository, including their
+//! signed refs and identity branches, so that it can be carried over to a node that has no
+//! network path to the source. On import, the bundle is first unpacked into a staging
+//! repository where every remote is verified, and only remotes that pass verification are
+//! transferred into storage.
+//!
+//! As with fetches, only the remotes in the repository's tracking scope are imported, and
+//! remotes of blocked nodes never are.
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::{env, io};
+
+use thiserror::Error;
+
+use crate::git;
+use crate::identity::{Did, Id};
+use crate::node::tracking::{self, Policy, Scope};
+use crate::storage::refs::SIGREFS_BRANCH;
+use crate::storage::{ReadRepository, ReadStorage, RemoteId, WriteRepository, WriteStorage};
+
+use super::{Repository, Storage, VerifyError};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+    #[error("git: {0}")]
+    Git(#[from] git::raw::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] super::Error),
+    #[error("identity: {0}")]
+    Identity(#[from] crate::identity::IdentityError),
+    #[error("verification failed: {0}")]
+    Verify(#[from] VerifyError),
+    #[error("tracking: {0}")]
+    Tracking(#[from] tracking::store::Error),
+    #[error("repository {0} is blocked")]
+    Blocked(Id),
+    #[error("repository {0} was not found in storage")]
+    NotFound(Id),
+    #[error("bundle does not contain any valid remote for {0}")]
+    Empty(Id),
+}
+
+/// The result of importing a bundle.
+#[derive(Debug, Default)]
+pub struct Import {
+    /// Remotes that were verified and transferred into storage.
+    pub imported: Vec<RemoteId>,
+    /// Remotes that were already up to date, or more recent in storage.
+    pub skipped: Vec<RemoteId>,
+    /// Remotes that failed verification, with the reason.
+    pub rejected: Vec<(RemoteId, VerifyError)>,
+    /// Remotes that are out of the tracking scope of the repository, or blocked.
+    pub untracked: Vec<RemoteId>,
+}
+
+/// Write a bundle of all the refs of the given repository to `path`.
+pub fn create(storage: &Storage, rid: Id, path: &Path) -> Result<(), Error> {
+    if !storage.contains(&rid)? {
+        return Err(Error::NotFound(rid));
+    }
+    let repo = storage.repository(rid)?;
+    let path = absolute(path)?;
+
+    git::run(
+        repo.path(),
+        [
+            OsStr::new("bundle"),
+            OsStr::new("create"),
+            path.as_os_str(),
+            OsStr::new("--all"),
+        ],
+        git::env::GIT_DEFAULT_CONFIG,
+    )?;
+
+    Ok(())
+}
+
+/// Import the bundle at `path` into the given repository, creating it if necessary.
+///
+/// Remotes are filtered through the tracking policies: repositories without a policy of their
+/// own use the default scope. The signed refs and identity of every remote that is imported are
+/// verified before anything is written to storage. Remotes whose signed refs in storage are not
+/// an ancestor of the ones in the bundle are left untouched.
+pub fn import(
+    storage: &Storage,
+    rid: Id,
+    path: &Path,
+    tracking: &tracking::store::Config,
+) -> Result<Import, Error> {
+    let scope = match tracking.repo_policy(&rid)? {
+        Some(repo) if repo.policy == Policy::Block => return Err(Error::Blocked(rid)),
+        Some(repo) => repo.scope,
+        None => Scope::default(),
+    };
+    let path = absolute(path)?;
+    let tmp = tempfile::Builder::new()
+        .prefix("radicle-bundle-")
+        .tempdir()?;
+    let staging = Repository::create(tmp.path(), rid)?;
+
+    git::run(
+        staging.path(),
+        [
+            OsStr::new("fetch"),
+            OsStr::new("--quiet"),
+            path.as_os_str(),
+            OsStr::new("+refs/namespaces/*:refs/namespaces/*"),
+        ],
+        git::env::GIT_DEFAULT_CONFIG,
+    )?;
+
+    let production = if storage.contains(&rid)? {
+        Some(storage.repository(rid)?)
+    } else {
+        None
+    };
+    // Delegates are always in scope. If we already have the repository, our copy of the
+    // identity is the one we trust.
+    let delegates = match &production {
+        Some(repo) => repo.delegates()?,
+        None => staging.delegates()?,
+    };
+    let mut result = Import::default();
+    let mut verified = Vec::new();
+
+    for (remote, validation) in staging.validate_remotes()? {
+        if !is_tracked(tracking, &scope, delegates.iter(), &remote)? {
+            result.untracked.push(remote);
+            continue;
+        }
+        let unsigned = match validation {
+            Ok(unsigned) => unsigned,
+            Err(e) => {
+                result.rejected.push((remote, e));
+                continue;
+            }
+        };
+        if let Some(production) = &production {
+            if !is_newer(&staging, production, &remote)? {
+                result.skipped.push(remote);
+                continue;
+            }
+        }
+        verified.push((remote, unsigned));
+    }
+
+    if verified.is_empty() {
+        if production.is_none() {
+            return Err(Error::Empty(rid));
+        }
+        return Ok(result);
+    }
+
+    let production = match production {
+        Some(repo) => repo,
+        None => storage.create(rid)?,
+    };
+    let url = git::url::File::new(staging.path().to_path_buf()).to_string();
+    let mut remote = production.backend.remote_anonymous(&url)?;
+    let specs = verified
+        .iter()
+        .map(|(id, _)| format!("+refs/namespaces/{id}/*:refs/namespaces/{id}/*"))
+        .collect::<Vec<_>>();
+    let mut opts = git::raw::FetchOptions::default();
+    // Nb. Refs of remotes that aren't in the bundle must be kept.
+    opts.prune(git::raw::FetchPrune::Off);
+    remote.fetch(&specs, Some(&mut opts), None)?;
+
+    // Delete unsigned refs.
+    for (id, unsigned) in verified {
+        for refstr in unsigned {
+            let q = git::Qualified::from_refstr(&refstr)
+                .expect("bundle::import: unsigned references are qualified");
+
+            if let Ok(mut r) = production.reference(&id, &q) {
+                r.delete()?;
+            }
+        }
+        result.imported.push(id);
+    }
+    production.set_head()?;
+    production.set_identity_head()?;
+
+    Ok(result)
+}
+
+/// Check whether a remote is in the given tracking scope, and isn't blocked.
+fn is_tracked<'a>(
+    tracking: &tracking::store::Config,
+    scope: &Scope,
+    mut delegates: impl Iterator<Item = &'a Did>,
+    remote: &RemoteId,
+) -> Result<bool, Error> {
+    let policy = tracking.node_policy(remote)?.map(|node| node.policy);
+
+    if policy == Some(Policy::Block) {
+        return Ok(false);
+    }
+    if delegates.any(|did| **did == *remote) {
+        return Ok(true);
+    }
+    Ok(match scope {
+        Scope::All => true,
+        Scope::Trusted => policy == Some(Policy::Track),
+        Scope::Explicit(remotes) => remotes.contains(&Did::from(*remote)),
+    })
+}
+
+/// Check whether the signed refs of `remote` in `staging` are strictly ahead of the ones in
+/// `production`.
+fn is_newer(
+    staging: &Repository,
+    production: &Repository,
+    remote: &RemoteId,
+) -> Result<bool, Error> {
+    let Ok(ours) = production.reference_oid(remote, &SIGREFS_BRANCH) else {
+        // Remote is not in storage yet.
+        return Ok(true);
+    };
+    let theirs = staging
+        .backend
+        .refname_to_id(&SIGREFS_BRANCH.with_namespace(remote.into()))?;
+
+    if *ours == theirs {
+        return Ok(false);
+    }
+    // If the bundle's history includes our signed refs, the object will be in staging.
+    if staging.backend.find_commit(*ours).is_err() {
+        return Ok(false);
+    }
+    Ok(staging.backend.graph_descendant_of(theirs, *ours)?)
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, io::Error> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::rad;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let alice = Storage::temporary().unwrap();
+        let bob = Storage::temporary().unwrap();
+        let (rid, _, _, _) = fixtures::project(tmp.path().join("acme"), &alice, &signer).unwrap();
+        let bundle = tmp.path().join("acme.bundle");
+        let tracking = tracking::store::Config::memory().unwrap();
+
+        create(&alice, rid, &bundle).unwrap();
+
+        let result = import(&bob, rid, &bundle, &tracking).unwrap();
+        assert_eq!(result.imported, vec![*signer.public_key()]);
+        assert!(result.rejected.is_empty());
+
+        let repo = bob.repository(rid).unwrap();
+        assert!(repo.validate().is_ok());
+        assert_eq!(
+            repo.head().unwrap(),
+            alice.repository(rid).unwrap().head().unwrap()
+        );
+
+        // Importing the same bundle again is a no-op.
+        let result = import(&bob, rid, &bundle, &tracking).unwrap();
+        assert!(result.imported.is_empty());
+        assert_eq!(result.skipped, vec![*signer.public_key()]);
+    }
+
+    #[test]
+    fn test_bundle_tracking() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::temporary().unwrap();
+        let (rid, _, _, _) = fixtures::project(tmp.path().join("acme"), &storage, &alice).unwrap();
+        let bundle = tmp.path().join("acme.bundle");
+
+        // Bob forks Alice's project, so the bundle contains both of their remotes.
+        rad::fork(rid, &bob, &storage).unwrap();
+        create(&storage, rid, &bundle).unwrap();
+
+        // By default, only the delegates and tracked nodes are imported.
+        let mut tracking = tracking::store::Config::memory().unwrap();
+        let result = import(&Storage::temporary().unwrap(), rid, &bundle, &tracking).unwrap();
+        assert_eq!(result.imported, vec![*alice.public_key()]);
+        assert_eq!(result.untracked, vec![*bob.public_key()]);
+
+        tracking.track_repo(&rid, Scope::All).unwrap();
+        let result = import(&Storage::temporary().unwrap(), rid, &bundle, &tracking).unwrap();
+        assert_eq!(result.imported.len(), 2);
+        assert!(result.untracked.is_empty());
+
+        // Blocked nodes are never imported.
+        tracking
+            .set_node_policy(bob.public_key(), Policy::Block)
+            .unwrap();
+        let result = import(&Storage::temporary().unwrap(), rid, &bundle, &tracking).unwrap();
+        assert_eq!(result.imported, vec![*alice.public_key()]);
+        assert_eq!(result.untracked, vec![*bob.public_key()]);
+
+        // Nor are blocked repositories.
+        tracking.set_repo_policy(&rid, Policy::Block).unwrap();
+        assert!(matches!(
+            import(&Storage::temporary().unwrap(), rid, &bundle, &tracking),
+            Err(Error::Blocked(_))
+        ));
+    }
+}
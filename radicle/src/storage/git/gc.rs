@@ -0,0 +1,203 @@
+//! Storage garbage collection.
+//!
+//! Fetches only ever add namespaces to a repository: the namespaces of peers we stopped
+//! tracking stay around, along with all the objects they reference. Garbage collection
+//! removes these namespaces, and then runs `git gc` so that objects which are no longer
+//! reachable are actually deleted from disk.
+use std::path::Path;
+use std::{fs, io};
+
+use thiserror::Error;
+
+use crate::identity::{IdentityError, PublicKey};
+use crate::storage;
+use crate::storage::refs;
+use crate::storage::{Namespaces, ReadRepository as _, RemoteId, WriteRepository as _};
+
+use super::Repository;
+
+/// Only objects that have been unreachable for longer than this are pruned by `git gc`.
+/// This leaves a grace period for objects written by a fetch that is still in progress,
+/// and not yet referenced.
+pub const PRUNE_EXPIRY: &str = "1.hour.ago";
+
+/// An error occuring while collecting garbage.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The repository delegates couldn't be determined, so it isn't safe to prune any
+    /// namespace.
+    #[error("failed to get repository delegates: {0}")]
+    Delegates(#[source] IdentityError),
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
+    #[error(transparent)]
+    Refs(#[from] refs::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Outcome of collecting garbage in a repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Collected {
+    /// Namespaces that were pruned.
+    pub pruned: Vec<RemoteId>,
+    /// Size of the repository before collection, in bytes.
+    pub before: u64,
+    /// Size of the repository after collection, in bytes.
+    pub after: u64,
+}
+
+impl Collected {
+    /// Number of bytes reclaimed.
+    pub fn reclaimed(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+/// Collect garbage in a repository.
+///
+/// Prunes the namespaces that aren't part of `keep`, and then repacks the repository and
+/// deletes unreachable objects. The namespaces of the repository delegates are always kept,
+/// so nothing is pruned if they can't be determined, eg. because the identity document is
+/// invalid. With `dry_run`, nothing is changed, and the namespaces that would be pruned are returned.
+pub fn collect(repo: &Repository, keep: &Namespaces, dry_run: bool) -> Result<Collected, Error> {
+    let before = size(repo.path())?;
+    let pruned = match keep {
+        Namespaces::All => Vec::new(),
+        Namespaces::Trusted(trusted) => {
+            let delegates = repo
+                .delegates()
+                .map_err(Error::Delegates)?
+                .into_iter()
+                .map(PublicKey::from)
+                .collect::<Vec<_>>();
+
+            repo.remote_ids()?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|remote| !trusted.contains(remote) && !delegates.contains(remote))
+                .collect()
+        }
+    };
+    if dry_run {
+        return Ok(Collected {
+            pruned,
+            before,
+            after: before,
+        });
+    }
+
+    for remote in &pruned {
+        repo.remove_remote(remote)?;
+    }
+    super::run::<_, _, &str, &str>(
+        repo.path(),
+        ["gc", "--quiet", &format!("--prune={PRUNE_EXPIRY}")],
+        [],
+    )?;
+    let after = size(repo.path())?;
+
+    Ok(Collected {
+        pruned,
+        before,
+        after,
+    })
+}
+
+/// Total size of the files under the given path, in bytes.
+pub fn size(path: &Path) -> Result<u64, io::Error> {
+    let mut total = 0;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+
+        if meta.is_dir() {
+            total += size(&entry.path())?;
+        } else {
+            total += meta.len();
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crypto::test::signer::MockSigner;
+    use crypto::Signer as _;
+
+    use super::*;
+    use crate::storage::git::Storage;
+    use crate::storage::ReadStorage as _;
+    use crate::test::{arbitrary, fixtures};
+
+    #[test]
+    fn test_collect_prunes_untracked() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (rid, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(rid).unwrap();
+        let delegate = *signer.public_key();
+        let peer = arbitrary::gen::<RemoteId>(1);
+
+        // Copy the delegate's refs into a second namespace.
+        let copy = || {
+            for r in repo
+                .raw()
+                .references_glob(&format!("refs/namespaces/{delegate}/*"))
+                .unwrap()
+            {
+                let r = r.unwrap();
+                let name = r
+                    .name()
+                    .unwrap()
+                    .replace(&delegate.to_string(), &peer.to_string());
+                repo.raw()
+                    .reference(&name, r.target().unwrap(), false, "test")
+                    .unwrap();
+            }
+        };
+        copy();
+        assert!(!repo.references_of(&peer).unwrap().is_empty());
+
+        // Nothing is pruned when all namespaces are kept.
+        let collected = collect(&repo, &Namespaces::All, false).unwrap();
+        assert!(collected.pruned.is_empty());
+        assert!(!repo.references_of(&peer).unwrap().is_empty());
+
+        // A dry run doesn't change anything.
+        let keep = Namespaces::Trusted(HashSet::new());
+        let collected = collect(&repo, &keep, true).unwrap();
+        assert_eq!(collected.pruned, vec![peer]);
+        assert!(!repo.references_of(&peer).unwrap().is_empty());
+
+        // Untracked peers are pruned, but delegates are kept.
+        let collected = collect(&repo, &keep, false).unwrap();
+        assert_eq!(collected.pruned, vec![peer]);
+        assert!(repo.references_of(&peer).unwrap().is_empty());
+        assert!(!repo.references_of(&delegate).unwrap().is_empty());
+
+        // Nothing is pruned when the delegates can't be determined.
+        copy();
+        for name in [
+            String::from("refs/rad/id"),
+            format!("refs/namespaces/{delegate}/refs/rad/id"),
+            format!("refs/namespaces/{peer}/refs/rad/id"),
+        ] {
+            if let Ok(mut r) = repo.raw().find_reference(&name) {
+                r.delete().unwrap();
+            }
+        }
+        assert!(matches!(
+            collect(&repo, &keep, false),
+            Err(Error::Delegates(_))
+        ));
+        assert!(!repo.references_of(&peer).unwrap().is_empty());
+    }
+}
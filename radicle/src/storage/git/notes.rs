@@ -0,0 +1,162 @@
+//! Personal notes on repository objects.
+//!
+//! Like `git notes`, notes annotate objects, eg. commits, without changing them. Each user's
+//! notes are stored in their own namespace, under [`refs::storage::NOTES_REF`], so they are
+//! signed along with the user's other refs, and replicated with their fork.
+use crypto::Signer;
+
+use crate::git;
+use crate::git::{refs, Oid};
+use crate::storage::{RemoteId, WriteRepository as _};
+
+use super::{Error, Repository};
+
+/// A note on an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// Author of the note.
+    pub author: RemoteId,
+    /// Annotated object.
+    pub object: Oid,
+    /// Note contents.
+    pub message: String,
+}
+
+/// Add a note on an object, in the signer's namespace. If the signer already has a note on
+/// this object, the message is appended to it.
+pub fn add<G: Signer>(
+    repo: &Repository,
+    object: Oid,
+    message: &str,
+    signer: &G,
+) -> Result<Note, Error> {
+    let author = *signer.public_key();
+    let notes = refs::storage::notes(&author);
+    let message = match find(repo, &author, object)? {
+        Some(note) => format!("{}\n\n{}", note.message.trim_end(), message.trim()),
+        None => message.trim().to_owned(),
+    };
+    let sig = signature(&author)?;
+
+    repo.backend.note(
+        &sig,
+        &sig,
+        Some(notes.as_str()),
+        object.into(),
+        &message,
+        true,
+    )?;
+    repo.sign_refs(signer)?;
+
+    Ok(Note {
+        author,
+        object,
+        message,
+    })
+}
+
+/// Remove the signer's note on an object. Returns `false` if there was no note to remove.
+pub fn remove<G: Signer>(repo: &Repository, object: Oid, signer: &G) -> Result<bool, Error> {
+    let author = *signer.public_key();
+    if find(repo, &author, object)?.is_none() {
+        return Ok(false);
+    }
+    let notes = refs::storage::notes(&author);
+    let sig = signature(&author)?;
+
+    repo.backend
+        .note_delete(object.into(), Some(notes.as_str()), &sig, &sig)?;
+    repo.sign_refs(signer)?;
+
+    Ok(true)
+}
+
+/// Get the note of `author` on an object, if any.
+pub fn find(repo: &Repository, author: &RemoteId, object: Oid) -> Result<Option<Note>, Error> {
+    let notes = refs::storage::notes(author);
+
+    match repo.backend.find_note(Some(notes.as_str()), object.into()) {
+        Ok(note) => Ok(Some(Note {
+            author: *author,
+            object,
+            message: String::from_utf8_lossy(note.message_bytes()).into_owned(),
+        })),
+        Err(e) if git::is_not_found_err(&e) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the notes of all remotes on an object.
+pub fn of(repo: &Repository, object: Oid) -> Result<Vec<Note>, Error> {
+    let mut notes = Vec::new();
+
+    for remote in repo.remote_ids()? {
+        if let Some(note) = find(repo, &remote?, object)? {
+            notes.push(note);
+        }
+    }
+    Ok(notes)
+}
+
+/// Get all the notes of `author`.
+pub fn by(repo: &Repository, author: &RemoteId) -> Result<Vec<Note>, Error> {
+    let refname = refs::storage::notes(author);
+    let iter = match repo.backend.notes(Some(refname.as_str())) {
+        Ok(iter) => iter,
+        Err(e) if git::is_not_found_err(&e) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut notes = Vec::new();
+
+    for entry in iter {
+        let (_, object) = entry?;
+
+        if let Some(note) = find(repo, author, object.into())? {
+            notes.push(note);
+        }
+    }
+    Ok(notes)
+}
+
+/// Signature used for the commits of the notes ref.
+fn signature(author: &RemoteId) -> Result<git2::Signature<'static>, git2::Error> {
+    git2::Signature::now("radicle", author.to_string().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::storage::git::Storage;
+    use crate::storage::WriteStorage as _;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_add_remove() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (rid, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let head = Oid::from(head);
+        let repo = storage.repository_mut(rid).unwrap();
+        let author = *signer.public_key();
+
+        assert_eq!(find(&repo, &author, head).unwrap(), None);
+        assert!(!remove(&repo, head, &signer).unwrap());
+
+        add(&repo, head, "Looks good", &signer).unwrap();
+        let note = add(&repo, head, "Needs a test", &signer).unwrap();
+        assert_eq!(note.message, "Looks good\n\nNeeds a test");
+        assert_eq!(of(&repo, head).unwrap(), vec![note.clone()]);
+        assert_eq!(by(&repo, &author).unwrap(), vec![note]);
+
+        // Notes are signed along with the rest of the remote's refs.
+        repo.validate_remote_id(&author).unwrap();
+
+        assert!(remove(&repo, head, &signer).unwrap());
+        assert_eq!(find(&repo, &author, head).unwrap(), None);
+        assert!(by(&repo, &author).unwrap().is_empty());
+    }
+}
@@ -1,7 +1,6 @@
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::io;
-use std::io::{BufRead, BufReader};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
@@ -16,6 +15,7 @@ use crate::git::ext as git_ext;
 use crate::git::Oid;
 use crate::storage;
 use crate::storage::{ReadRepository, RemoteId, WriteRepository};
+use crate::verify;
 
 pub use crate::git::refs::storage::*;
 
@@ -117,39 +117,18 @@ impl Refs {
 
     /// Create refs from a canonical representation.
     pub fn from_canonical(bytes: &[u8]) -> Result<Self, canonical::Error> {
-        let reader = BufReader::new(bytes);
         let mut refs = BTreeMap::new();
 
-        for line in reader.lines() {
-            let line = line?;
-            let (oid, name) = line
-                .split_once(' ')
-                .ok_or(canonical::Error::InvalidFormat)?;
-
-            let name = git::RefString::try_from(name)?;
-            let oid = Oid::from_str(oid)?;
-
-            if oid.is_zero() {
-                continue;
-            }
-            refs.insert(name, oid);
+        for (name, oid) in verify::sigrefs::decode(bytes)? {
+            refs.insert(name, Oid::from_str(&oid)?);
         }
         Ok(Self(refs))
     }
 
+    /// The canonical representation of these refs, which is what gets signed.
+    /// See [`verify::sigrefs::encode`].
     pub fn canonical(&self) -> Vec<u8> {
-        let mut buf = String::new();
-        let refs = self
-            .iter()
-            .filter(|(name, oid)| name.as_refstr() != SIGREFS_BRANCH.as_ref() && !oid.is_zero());
-
-        for (name, oid) in refs {
-            buf.push_str(&oid.to_string());
-            buf.push(' ');
-            buf.push_str(name);
-            buf.push('\n');
-        }
-        buf.into_bytes()
+        verify::sigrefs::encode(self.iter().map(|(name, oid)| (name.as_refstr(), oid)))
     }
 }
 
@@ -369,6 +348,8 @@ pub mod canonical {
         #[error("invalid canonical format")]
         InvalidFormat,
         #[error(transparent)]
+        Decode(#[from] verify::sigrefs::Error),
+        #[error(transparent)]
         Io(#[from] io::Error),
         #[error(transparent)]
         Git(#[from] git2::Error),
@@ -15,7 +15,7 @@ use crate::identity::{
     project::Project,
     Did,
 };
-use crate::node::Address;
+use crate::node::{Address, SearchResult, Signal};
 use crate::storage;
 use crate::storage::refs::{Refs, SignedRefs};
 use crate::test::storage::{MockRepository, MockStorage};
@@ -108,6 +108,53 @@ impl Arbitrary for Project {
     }
 }
 
+impl Arbitrary for SearchResult {
+    fn arbitrary(g: &mut qcheck::Gen) -> Self {
+        let rng = fastrand::Rng::with_seed(u64::arbitrary(g));
+        let project = Project::arbitrary(g);
+        let delegates = iter::repeat_with(|| Did::arbitrary(g))
+            .take(rng.usize(1..6))
+            .collect();
+
+        Self {
+            rid: Id::arbitrary(g),
+            name: project.name().to_owned(),
+            description: project.description().to_owned(),
+            head: oid(),
+            delegates,
+        }
+    }
+}
+
+impl Arbitrary for Signal {
+    fn arbitrary(g: &mut qcheck::Gen) -> Self {
+        let rid = Id::arbitrary(g);
+
+        match g.choose(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap() {
+            1 => Self::Invite { rid },
+            2 => Self::FetchHint { rid },
+            3 => Self::SyncRequest { rid },
+            4 => Self::Synced {
+                rid,
+                sigrefs: oid(),
+            },
+            5 => Self::SyncDeclined { rid },
+            6 => Self::Ticket {
+                ticket: u64::arbitrary(g),
+            },
+            7 => Self::Resume {
+                ticket: u64::arbitrary(g),
+            },
+            8 => Self::ResumeDeclined,
+            _ => Self::Unknown {
+                // Nb. Kinds of known signals are never decoded as unknown signals.
+                kind: u16::arbitrary(g).max(Signal::RESUME_DECLINED + 1),
+                payload: Arbitrary::arbitrary(g),
+            },
+        }
+    }
+}
+
 impl Arbitrary for Doc<Unverified> {
     fn arbitrary(g: &mut qcheck::Gen) -> Self {
         let proj = Project::arbitrary(g);
@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use crate::cob::issue::Issues;
+use crate::cob::patch::{MergeTarget, Patches};
 use crate::crypto::{Signer, Verified};
 use crate::git;
 use crate::identity::Id;
@@ -7,6 +9,7 @@ use crate::rad;
 use crate::storage::git::transport;
 use crate::storage::git::Storage;
 use crate::storage::refs::SignedRefs;
+use crate::storage::{WriteRepository, WriteStorage};
 
 /// The birth of the radicle project, January 1st, 2018.
 const RADICLE_EPOCH: i64 = 1514817556;
@@ -132,6 +135,176 @@ pub fn populate(repo: &git2::Repository, scale: usize) -> Vec<git::Qualified> {
     refs
 }
 
+/// Shape of a repository created with [`generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape {
+    /// Number of commits added to the default branch, each adding a new file.
+    pub commits: usize,
+    /// Size of each file, in bytes.
+    pub file_size: usize,
+    /// Number of feature branches, each one commit ahead of the default branch.
+    pub branches: usize,
+    /// Number of issues.
+    pub issues: usize,
+    /// Number of patches. Each patch proposes one of the feature branches, so there can't
+    /// be more patches than branches.
+    pub patches: usize,
+    /// Seed used to generate file contents.
+    pub seed: u64,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self {
+            commits: 8,
+            file_size: 1024,
+            branches: 2,
+            issues: 4,
+            patches: 2,
+            seed: 42,
+        }
+    }
+}
+
+/// Create a repository of the given shape at the given path, and initialize it into a
+/// project in storage, along with its branches, issues and patches.
+///
+/// Git objects are deterministic for a given shape: file contents are derived from the
+/// shape's seed, and commit times from a fixed epoch.
+pub fn generate<P: AsRef<Path>, G: Signer>(
+    path: P,
+    shape: &Shape,
+    storage: &Storage,
+    signer: &G,
+) -> (Id, git2::Repository) {
+    assert!(
+        shape.patches <= shape.branches,
+        "There can't be more patches than branches"
+    );
+    transport::local::register(storage.clone());
+
+    let (repo, _) = repository(path);
+    let rng = fastrand::Rng::with_seed(shape.seed);
+    let mut buffer = vec![0; shape.file_size];
+    let mut head = repo.head().unwrap().target().unwrap();
+    let mut time = RADICLE_EPOCH;
+
+    for i in 0..shape.commits {
+        time += 60;
+        rng.fill(&mut buffer);
+        head = commit_file(
+            &repo,
+            head,
+            "refs/heads/master",
+            &format!("file-{i}.txt"),
+            &buffer,
+            time,
+        );
+    }
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+        .unwrap();
+
+    let mut branches = Vec::with_capacity(shape.branches);
+    for i in 0..shape.branches {
+        let name = git::RefString::try_from(format!("feature/{i}")).unwrap();
+
+        time += 60;
+        rng.fill(&mut buffer);
+
+        let oid = commit_file(
+            &repo,
+            head,
+            git::refs::workdir::branch(&name).as_str(),
+            &format!("feature-{i}.txt"),
+            &buffer,
+            time,
+        );
+        branches.push((name, oid));
+    }
+
+    let (rid, _, _) = rad::init(
+        &repo,
+        "acme",
+        "Acme's repository",
+        git::refname!("master"),
+        signer,
+        storage,
+    )
+    .unwrap();
+
+    let refs = branches
+        .iter()
+        .map(|(name, _)| git::Qualified::from(git::fmt::lit::refs_heads(name)))
+        .collect::<Vec<_>>();
+    git::push(&repo, &rad::REMOTE_NAME, refs.iter().map(|r| (r, r))).unwrap();
+
+    let project = storage.repository_mut(rid).unwrap();
+    let mut issues = Issues::open(&project).unwrap();
+    for i in 0..shape.issues {
+        issues
+            .create(
+                format!("Issue #{i}"),
+                format!("Description of issue #{i}"),
+                &[],
+                &[],
+                signer,
+            )
+            .unwrap();
+    }
+
+    let mut patches = Patches::open(&project).unwrap();
+    for (i, (_, oid)) in branches.iter().take(shape.patches).enumerate() {
+        patches
+            .create(
+                format!("Patch #{i}"),
+                format!("Description of patch #{i}"),
+                MergeTarget::Delegates,
+                head,
+                *oid,
+                &[],
+                signer,
+            )
+            .unwrap();
+    }
+    project.sign_refs(signer).unwrap();
+
+    (rid, repo)
+}
+
+/// Commit a file with the given contents on top of `parent`, and update `target` to point
+/// to the new commit.
+fn commit_file(
+    repo: &git2::Repository,
+    parent: git2::Oid,
+    target: &str,
+    file: &str,
+    contents: &[u8],
+    time: i64,
+) -> git2::Oid {
+    let sig = git2::Signature::new(
+        "anonymous",
+        "anonymous@radicle.xyz",
+        &git2::Time::new(time, 0),
+    )
+    .unwrap();
+    let parent = repo.find_commit(parent).unwrap();
+    let blob = repo.blob(contents).unwrap();
+    let mut builder = repo.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+    builder.insert(file, blob, 0o100_644).unwrap();
+
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+
+    repo.commit(
+        Some(target),
+        &sig,
+        &sig,
+        &format!("Add {file}"),
+        &tree,
+        &[&parent],
+    )
+    .unwrap()
+}
+
 /// Generate random fixtures.
 pub mod gen {
     use super::*;
@@ -173,3 +346,39 @@ pub mod gen {
         (repo, oid)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crypto::test::signer::MockSigner;
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let shape = Shape {
+            commits: 3,
+            branches: 2,
+            issues: 2,
+            patches: 1,
+            ..Shape::default()
+        };
+        let (rid, repo) = generate(tmp.path().join("acme"), &shape, &storage, &signer);
+        let project = storage.repository(rid).unwrap();
+
+        assert_eq!(Issues::open(&project).unwrap().all().unwrap().count(), 2);
+        assert_eq!(Patches::open(&project).unwrap().all().unwrap().count(), 1);
+
+        let mut walk = repo.revwalk().unwrap();
+        walk.push_head().unwrap();
+        assert_eq!(walk.count(), 2 + shape.commits);
+
+        // The same shape always yields the same objects.
+        let other = Storage::open(tmp.path().join("other")).unwrap();
+        let (_, copy) = generate(tmp.path().join("copy"), &shape, &other, &signer);
+
+        assert_eq!(repo.head().unwrap().target(), copy.head().unwrap().target());
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 use git_ref_format as fmt;
 use radicle_git_ext as git_ext;
@@ -18,10 +19,14 @@ pub struct MockStorage {
     pub inventory: HashMap<Id, Doc<Verified>>,
 
     /// All refs keyed by RID.
-    /// Each value is a map of refs keyed by node Id (public key).
-    pub remotes: HashMap<Id, HashMap<NodeId, refs::SignedRefs<Verified>>>,
+    /// Each value is a map of refs keyed by node Id (public key), shared with the
+    /// repositories opened from this storage, so that remotes they remove are gone.
+    pub remotes: HashMap<Id, MockRemotes>,
 }
 
+/// Signed refs of the remotes of a mock repository, keyed by node Id.
+pub type MockRemotes = Arc<RwLock<HashMap<NodeId, refs::SignedRefs<Verified>>>>;
+
 impl MockStorage {
     pub fn new(inventory: Vec<(Id, Doc<Verified>)>) -> Self {
         Self {
@@ -48,7 +53,9 @@ impl MockStorage {
     ) {
         self.remotes
             .entry(rid)
-            .or_insert(HashMap::new())
+            .or_default()
+            .write()
+            .unwrap()
             .insert(node, signed_refs);
     }
 }
@@ -110,7 +117,7 @@ impl WriteStorage for MockStorage {
 pub struct MockRepository {
     id: Id,
     doc: Doc<Verified>,
-    remotes: HashMap<NodeId, refs::SignedRefs<Verified>>,
+    remotes: MockRemotes,
 }
 
 impl MockRepository {
@@ -118,7 +125,7 @@ impl MockRepository {
         Self {
             id,
             doc,
-            remotes: HashMap::default(),
+            remotes: MockRemotes::default(),
         }
     }
 }
@@ -129,15 +136,18 @@ impl ReadRepository for MockRepository {
     }
 
     fn is_empty(&self) -> Result<bool, git2::Error> {
-        Ok(self.remotes.is_empty())
+        Ok(self.remotes.read().unwrap().is_empty())
     }
 
     fn head(&self) -> Result<(fmt::Qualified, Oid), IdentityError> {
-        todo!()
+        self.canonical_head()
     }
 
     fn canonical_head(&self) -> Result<(fmt::Qualified, Oid), IdentityError> {
-        todo!()
+        let project = self.doc.project()?;
+        let branch = fmt::Qualified::from(fmt::lit::refs_heads(project.default_branch()));
+
+        Ok((branch, git2::Oid::zero().into()))
     }
 
     fn validate_remote(
@@ -153,6 +163,8 @@ impl ReadRepository for MockRepository {
 
     fn remote(&self, id: &RemoteId) -> Result<Remote<Verified>, refs::Error> {
         self.remotes
+            .read()
+            .unwrap()
             .get(id)
             .map(|refs| Remote { refs: refs.clone() })
             .ok_or(refs::Error::InvalidRef)
@@ -161,6 +173,8 @@ impl ReadRepository for MockRepository {
     fn remotes(&self) -> Result<Remotes<Verified>, refs::Error> {
         Ok(self
             .remotes
+            .read()
+            .unwrap()
             .iter()
             .map(|(id, refs)| (*id, Remote { refs: refs.clone() }))
             .collect())
@@ -243,4 +257,10 @@ impl WriteRepository for MockRepository {
     fn set_identity_head(&self) -> Result<Oid, IdentityError> {
         todo!()
     }
+
+    fn remove_remote(&self, remote: &RemoteId) -> Result<(), Error> {
+        self.remotes.write().unwrap().remove(remote);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,183 @@
+//! Verification of signed refs and of collaborative object changes.
+//!
+//! Verifying a signature only requires the signed bytes and the signer's key, so unlike storage
+//! and COBs, this module doesn't depend on `git2`, and is available without the `git` feature.
+//! This allows clients that fetch the raw objects by other means, eg. over HTTP, to verify them.
+
+/// Signed refs, as stored in the `refs/rad/sigrefs` branch of every remote.
+pub mod sigrefs {
+    use std::collections::BTreeMap;
+    use std::fmt;
+
+    use crypto::{PublicKey, Signature};
+    use git_ref_format::{RefStr, RefString};
+    use thiserror::Error;
+
+    /// The branch holding the signed refs, which is never part of the signed refs.
+    pub const SIGREFS_BRANCH: &str = "refs/rad/sigrefs";
+
+    /// Length of a hex-encoded object id.
+    const OID_HEX_LEN: usize = 40;
+
+    #[derive(Debug, Error)]
+    pub enum Error {
+        #[error("invalid signature: {0}")]
+        InvalidSignature(#[from] crypto::Error),
+        #[error(transparent)]
+        InvalidRef(#[from] git_ref_format::Error),
+        #[error("invalid object id `{0}`")]
+        InvalidOid(String),
+        #[error("invalid canonical format")]
+        InvalidFormat,
+    }
+
+    /// Encode refs in their canonical form, which is what gets signed. Every ref is on its own
+    /// line, as its object id followed by its name. The sigrefs branch and zero object ids
+    /// are left out.
+    pub fn encode<'a, O: fmt::Display>(refs: impl IntoIterator<Item = (&'a RefStr, O)>) -> Vec<u8> {
+        let mut buf = String::new();
+
+        for (name, oid) in refs {
+            let oid = oid.to_string();
+
+            if name.as_str() == SIGREFS_BRANCH || is_zero(&oid) {
+                continue;
+            }
+            buf.push_str(&oid);
+            buf.push(' ');
+            buf.push_str(name.as_str());
+            buf.push('\n');
+        }
+        buf.into_bytes()
+    }
+
+    /// Decode refs from their canonical form. Object ids are returned hex-encoded.
+    pub fn decode(bytes: &[u8]) -> Result<BTreeMap<RefString, String>, Error> {
+        let text = std::str::from_utf8(bytes).map_err(|_| Error::InvalidFormat)?;
+        let mut refs = BTreeMap::new();
+
+        for line in text.lines() {
+            let (oid, name) = line.split_once(' ').ok_or(Error::InvalidFormat)?;
+            let name = RefString::try_from(name)?;
+
+            if oid.len() != OID_HEX_LEN || !oid.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::InvalidOid(oid.to_owned()));
+            }
+            if is_zero(oid) {
+                continue;
+            }
+            refs.insert(name, oid.to_ascii_lowercase());
+        }
+        Ok(refs)
+    }
+
+    /// Verify the signature of `signer` over the canonical refs, and return the decoded refs.
+    pub fn verify(
+        refs: &[u8],
+        signer: &PublicKey,
+        signature: &Signature,
+    ) -> Result<BTreeMap<RefString, String>, Error> {
+        signer.verify(refs, signature)?;
+        decode(refs)
+    }
+
+    fn is_zero(oid: &str) -> bool {
+        oid.chars().all(|c| c == '0')
+    }
+}
+
+/// Changes of collaborative objects.
+pub mod cob {
+    use crypto::{PublicKey, Signature};
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    pub enum Error {
+        #[error("invalid signature by {0}: {1}")]
+        Signature(PublicKey, crypto::Error),
+    }
+
+    /// Verify the signatures over a change, given the raw bytes of the object id of the change's
+    /// tree, which is what change authors sign.
+    pub fn change<'a>(
+        revision: &[u8],
+        signatures: impl IntoIterator<Item = (&'a PublicKey, &'a Signature)>,
+    ) -> Result<(), Error> {
+        for (key, sig) in signatures {
+            key.verify(revision, sig)
+                .map_err(|err| Error::Signature(*key, err))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crypto::test::signer::MockSigner;
+    use crypto::Signer as _;
+    use git_ref_format::refname;
+
+    use super::*;
+
+    const OID: &str = "f2de534b5e81d7c6e2dcaf58c3dd91573c0a0354";
+
+    #[test]
+    fn test_sigrefs_roundtrip() {
+        let zero = "0".repeat(40);
+        let refs = [
+            (refname!("refs/heads/master"), OID),
+            (refname!("refs/heads/dev"), zero.as_str()),
+            (refname!("refs/rad/sigrefs"), OID),
+        ];
+        let encoded = sigrefs::encode(refs.iter().map(|(name, oid)| (name.as_refstr(), oid)));
+        assert_eq!(encoded, format!("{OID} refs/heads/master\n").into_bytes());
+
+        let decoded = sigrefs::decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[&refname!("refs/heads/master")], OID);
+    }
+
+    #[test]
+    fn test_sigrefs_decode_invalid() {
+        assert!(matches!(
+            sigrefs::decode(b"refs/heads/master\n"),
+            Err(sigrefs::Error::InvalidFormat)
+        ));
+        assert!(matches!(
+            sigrefs::decode(b"f2de534b refs/heads/master\n"),
+            Err(sigrefs::Error::InvalidOid(_))
+        ));
+        assert!(matches!(
+            sigrefs::decode(format!("{OID} refs/heads/..\n").as_bytes()),
+            Err(sigrefs::Error::InvalidRef(_))
+        ));
+    }
+
+    #[test]
+    fn test_sigrefs_verify() {
+        let signer = MockSigner::from_seed([0xaa; 32]);
+        let other = MockSigner::from_seed([0xbb; 32]);
+        let refs = format!("{OID} refs/heads/master\n").into_bytes();
+        let signature = signer.sign(&refs);
+
+        assert!(sigrefs::verify(&refs, signer.public_key(), &signature).is_ok());
+        assert!(matches!(
+            sigrefs::verify(&refs, other.public_key(), &signature),
+            Err(sigrefs::Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_cob_change() {
+        let alice = MockSigner::from_seed([0xaa; 32]);
+        let bob = MockSigner::from_seed([0xbb; 32]);
+        let revision = [0x42; 20];
+        let sig = alice.sign(&revision);
+
+        assert!(cob::change(&revision, [(alice.public_key(), &sig)]).is_ok());
+        assert!(matches!(
+            cob::change(&revision, [(bob.public_key(), &sig)]),
+            Err(cob::Error::Signature(key, _)) if key == *bob.public_key()
+        ));
+    }
+}